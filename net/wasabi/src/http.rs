@@ -4,22 +4,322 @@ use alloc::format;
 use alloc::string::ToString;
 use noli::net::lookup_host;
 use saba_core::error::Error;
+use saba_core::http::Header;
+use saba_core::http::HttpRequest;
 use saba_core::http::HttpResponse;
+use saba_core::http::ResponseReader;
+use saba_core::http::validate_header;
+use saba_core::scheduler::FetchScheduler;
+use saba_core::scheduler::RequestId;
+use saba_core::url::Url;
 use noli::net::SocketAddr;
 use noli::net::TcpStream;
 use alloc::vec::Vec;
+use crate::tls::TlsStream;
+
+/// レスポンスが途中で切れていた場合に、リクエストを送信する最大の回数
+const MAX_ATTEMPTS: usize = 2;
 
 /// HTTP リクエストと HTTP レスポンスを扱う HTTPClient 構造体
-pub struct HttpClient {}
+pub struct HttpClient {
+  // すべてのリクエストに付けるヘッダ (User-Agent, Accept-Language など)
+  headers: Vec<Header>,
+  // リクエストを中継する HTTP プロキシのホスト名とポート番号
+  proxy: Option<(String, u16)>,
+}
 
 impl HttpClient {
   pub fn new() -> Self {
-    Self {}
+    Self {
+      headers: Vec::new(),
+      proxy: None,
+    }
+  }
+
+  /// リクエストを HTTP プロキシ経由で送信するように設定する。
+  /// プロキシに接続し、リクエストラインには絶対形式のリクエストターゲットを使う。
+  pub fn set_proxy(&mut self, host: String, port: u16) {
+    self.proxy = Some((host, port));
+  }
+
+  /// HTTP プロキシの設定を解除し、サーバに直接接続する。
+  pub fn clear_proxy(&mut self) {
+    self.proxy = None;
+  }
+
+  /// すべてのリクエストに付けるヘッダを設定する。同じ名前のヘッダがすでにある場合は置き換える。
+  /// CR や LF を含むなど、ヘッダとして送信できない場合はエラーとする。
+  pub fn set_header(&mut self, name: &str, value: &str) -> Result<(), Error> {
+    validate_header(name, value)?;
+    self.headers.retain(|h| !h.name().eq_ignore_ascii_case(name));
+    self.headers.push(Header::new(name.to_string(), value.to_string()));
+    Ok(())
+  }
+
+  /// メソッド、ホスト、ポート番号、パスから、クライアントのヘッダを付けた HttpRequest を作成する。
+  pub fn request(
+    &self,
+    method: &str,
+    host: String,
+    port: u16,
+    path: String,
+  ) -> Result<HttpRequest, Error> {
+    let mut request = HttpRequest::new(method, host, port, path);
+    for h in &self.headers {
+      request.set_header(&h.name(), &h.value())?;
+    }
+    request.set_absolute_form(self.proxy.is_some());
+    Ok(request)
+  }
+
+  /// 解析済みの URL のスキームに応じて method のリクエストを送信し、レスポンスを返す。
+  /// https の場合は、TLS で暗号化した接続でリクエストを送信する。
+  /// method が POST の場合は body をリクエストのボディとして送信する。
+  pub fn fetch(&self, url: &Url, method: &str, body: String) -> Result<HttpResponse, Error> {
+    let mut request = self.url_request(method, url)?;
+    match method {
+      "GET" => self.send_get(&request),
+      "POST" => {
+        request.set_body(body);
+        self.send_post(&request)
+      }
+      "HEAD" | "OPTIONS" => self.send(&request),
+      method => Err(Error::UnexpectedInput(format!(
+        "unsupported method: {}",
+        method
+      ))),
+    }
+  }
+
+  /// 解析済みの URL に method のリクエストを1度送信し、ボディを受信した分から順に on_chunk に渡す。
+  /// on_chunk にはデコードできたボディと、ボディを除いたレスポンス (ステータスコードとヘッダ) を渡す。
+  /// すべて受信した後に、ボディ全体を含むレスポンスを返す。
+  /// ボディをすでに渡しているため、レスポンスが途中で切れていても再送せず、エラーとする。
+  pub fn fetch_streaming(
+    &self,
+    url: &Url,
+    method: &str,
+    body: String,
+    mut on_chunk: impl FnMut(&HttpResponse, &str),
+  ) -> Result<HttpResponse, Error> {
+    let mut request = self.url_request(method, url)?;
+    if method == "POST" {
+      request.set_body(body);
+    }
+    let stream = self.open(&request)?;
+    let mut reader = ResponseReader::for_method(&request.method());
+    self.receive_into(stream, &mut reader, |reader, chunk| {
+      if let Some(head) = reader.head() {
+        on_chunk(&head, chunk);
+      }
+    })?;
+    reader.finish_complete()
+  }
+
+  /// URL のスキーム、ホスト、ポート番号、パスから、method の HttpRequest を作成する。
+  /// http と https 以外のスキームの URL や、ポート番号が不正な URL はエラーとする。
+  fn url_request(&self, method: &str, url: &Url) -> Result<HttpRequest, Error> {
+    if !url.is_http() && !url.is_https() {
+      return Err(Error::UnexpectedInput(format!(
+        "unsupported scheme: {}",
+        url.scheme()
+      )));
+    }
+    let mut request = self.request(method, url.host(), port_of(url)?, path_of(url))?;
+    request.set_secure(url.is_https());
+    Ok(request)
   }
 
+  /// GET リクエストを送信し、レスポンスを返す。
   pub fn get(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
+    let request = self.request("GET", host, port, path)?;
+    self.send_get(&request)
+  }
+
+  /// GET リクエストを送信し、レスポンスを返す。
+  /// 受信したボディが Content-Length より短い場合、接続が途中で切れたとみなしてリクエストを再送する。
+  /// 再送しても完全なレスポンスを受信できない場合はエラーとする。
+  fn send_get(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+    for _ in 0..MAX_ATTEMPTS {
+      let response = self.send(request)?;
+      if !response.is_truncated() {
+        return Ok(response);
+      }
+    }
+    Err(Error::Network(format!(
+      "response truncated: received less than Content-Length from {}",
+      request.host()
+    )))
+  }
+
+  /// HEAD リクエストを送信し、ボディを除いたレスポンスを返す。
+  /// レスポンスのボディは読み込まないため、Content-Type や Content-Length を事前に確認するために使える。
+  pub fn head(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
+    let request = self.request("HEAD", host, port, path)?;
+    self.send(&request)
+  }
+
+  /// OPTIONS リクエストを送信し、サーバが対応しているメソッドなどを問い合わせる。
+  pub fn options(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
+    let request = self.request("OPTIONS", host, port, path)?;
+    self.send(&request)
+  }
+
+  /// GET リクエストを送信し、レスポンスを返す。
+  /// 受信中に接続が切れた場合、サーバが範囲リクエストに対応していれば (Accept-Ranges: bytes)、
+  /// Range ヘッダで受信済みのバイト数以降を要求して続きを受信し、1つのレスポンスに結合する。
+  /// https://httpwg.org/specs/rfc9110.html#field.range
+  pub fn get_resumable(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
+    let request = self.request("GET", host, port, path)?;
+    let mut reader = ResponseReader::for_method("GET");
+    let mut result = self
+      .open(&request)
+      .and_then(|stream| self.receive_into(stream, &mut reader, |_, _| {}));
+
+    for _ in 0..MAX_ATTEMPTS {
+      let head = match reader.head() {
+        Some(head) => head,
+        // ヘッダ部すら受信できていない場合は、続きから再開できない。
+        None => break,
+      };
+      // Content-Length がない場合、接続が閉じられるまでがボディなので、正常に受信を終えていれば完了とみなす。
+      let truncated =
+        result.is_err() || (head.content_length().is_some() && !reader.is_complete());
+      if !truncated || !head.accepts_ranges() {
+        break;
+      }
+
+      let mut range_request = request.clone();
+      range_request.set_header("Range", &format!("bytes={}-", reader.body_bytes().len()))?;
+      let mut partial = ResponseReader::for_method("GET");
+      result = self
+        .open(&range_request)
+        .and_then(|stream| self.receive_into(stream, &mut partial, |_, _| {}));
+      reader.append_range(partial)?;
+    }
+    result?;
+
+    let response = reader.finish()?;
+    if response.is_truncated() {
+      return Err(Error::Network(format!(
+        "response truncated: received less than Content-Length from {}",
+        request.host()
+      )));
+    }
+    Ok(response)
+  }
+
+  /// POST リクエストでボディを送信し、レスポンスを返す。
+  /// Content-Length はボディの長さから設定し、Content-Type が headers にない場合は
+  /// application/x-www-form-urlencoded とする。
+  /// POST は冪等ではないため、レスポンスが途中で切れていても再送せずにエラーとする。
+  pub fn post(
+    &self,
+    host: String,
+    port: u16,
+    path: String,
+    headers: Vec<Header>,
+    body: String,
+  ) -> Result<HttpResponse, Error> {
+    let mut request = self.request("POST", host, port, path)?;
+    for h in headers {
+      request.set_header(&h.name(), &h.value())?;
+    }
+    request.set_body(body);
+    self.send_post(&request)
+  }
+
+  /// POST リクエストを送信し、レスポンスを返す。
+  fn send_post(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+    let response = self.send(request)?;
+    if response.is_truncated() {
+      return Err(Error::Network(format!(
+        "response truncated: received less than Content-Length from {}",
+        request.host()
+      )));
+    }
+    Ok(response)
+  }
+
+  /// GET リクエストを送信し、ボディを受信した分から順に on_chunk に渡す。
+  /// すべて受信した後に、ボディ全体を含むレスポンスを返す。
+  /// ボディをすでに渡しているため、レスポンスが途中で切れていても再送せず、エラーとする。
+  pub fn get_streaming(
+    &self,
+    host: String,
+    port: u16,
+    path: String,
+    mut on_chunk: impl FnMut(&str),
+  ) -> Result<HttpResponse, Error> {
+    let request = self.request("GET", host, port, path)?;
+    let stream = self.open(&request)?;
+    let mut reader = ResponseReader::for_method("GET");
+    self.receive_into(stream, &mut reader, |_, chunk| on_chunk(chunk))?;
+    reader.finish_complete()
+  }
+
+  /// スケジューラに登録されたリクエストをすべて送信し、結果をスケジューラに登録する。
+  /// wasabiOS のソケットは読み込みでブロックするため、同時に送信できるリクエストを先にすべて送信し、
+  /// その後で順にレスポンスを受信する。サーバはその間に並行してレスポンスを返すことができる。
+  pub fn fetch_all(&self, scheduler: &mut FetchScheduler) {
+    while !scheduler.is_idle() {
+      let mut started: Vec<(RequestId, HttpRequest, Connection)> = Vec::new();
+      while let Some((id, url)) = scheduler.next_request() {
+        // 送信できないリクエストは、他のリクエストを妨げないようにそのリクエストだけエラーとして完了させる。
+        match self.open_subresource(&url) {
+          Ok((request, stream)) => started.push((id, request, stream)),
+          Err(e) => scheduler.complete(id, Err(e)),
+        }
+      }
+
+      for (id, request, stream) in started {
+        let result = self.receive(stream, &request, |_| {});
+        scheduler.complete(id, result);
+      }
+    }
+  }
+
+  /// サブリソースの URL に GET リクエストを送信し、送信したリクエストと接続を返す。
+  /// http と https 以外のスキームの URL や、ポート番号が不正な URL はエラーとする。
+  fn open_subresource(&self, url: &Url) -> Result<(HttpRequest, Connection), Error> {
+    let request = self.url_request("GET", url)?;
+    let stream = self.open(&request)?;
+    Ok((request, stream))
+  }
+
+  /// HTTP リクエストを1度送信し、受信したレスポンスを返す。
+  pub fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+    self.send_streaming(request, |_| {})
+  }
+
+  /// HTTP リクエストを1度送信し、デコードできたボディを受信した分から順に on_chunk に渡す。
+  /// すべて受信した後に、ボディ全体を含むレスポンスを返す。
+  pub fn send_streaming(
+    &self,
+    request: &HttpRequest,
+    on_chunk: impl FnMut(&str),
+  ) -> Result<HttpResponse, Error> {
+    let stream = self.open(request)?;
+    self.receive(stream, request, on_chunk)
+  }
+
+  /// サーバ (またはプロキシ) に接続し、HTTP リクエストを送信した接続を返す。
+  /// https のリクエストの場合は、TLS のハンドシェイクを行ってから暗号化して送信する。
+  fn open(&self, request: &HttpRequest) -> Result<Connection, Error> {
+    // TODO: プロキシ経由の https には、CONNECT メソッドでトンネルを作る必要がある。
+    if request.is_secure() && self.proxy.is_some() {
+      return Err(Error::Network(
+        "https through an HTTP proxy is not supported".to_string(),
+      ));
+    }
+
     // wasabiOS の lookup_host 関数を使ってホスト名を IP アドレスに変換する。
     // 戻り値は IP アドレスのベクタである。
+    // プロキシを設定している場合は、サーバではなくプロキシに接続する。
+    let (host, port) = match &self.proxy {
+      Some((host, port)) => (host.clone(), *port),
+      None => (request.host(), request.port()),
+    };
     let ips = match lookup_host(&host) {
       Ok(ips) => ips,
       Err(e) => {
@@ -36,7 +336,7 @@ impl HttpClient {
     // TCP ストリームを構築する。noli ライブラリが提供する TcpStream 構造体とデータ書き込み API を使用する。
     // connect メソッドを使ってコネクションを確立し、成功の場合は TcpStream 構造体を返す。
     let socket_addr: SocketAddr = (ips[0], port).into();
-    let mut stream = match TcpStream::connect(socket_addr) {
+    let stream = match TcpStream::connect(socket_addr) {
       Ok(stream) => stream,
       Err(_) => {
         return Err(Error::Network(
@@ -45,56 +345,112 @@ impl HttpClient {
       }
     };
 
-    // TCP ストリームに送信する HTTP のリクエストラインを構築する。
-    // メソッド名、パス名、HTTP バージョンをホワイトスペースで結合する。
-    let mut request = String::from("GET /");
-    request.push_str(&path);
-    request.push_str(" HTTP/1.1\n");
+    let mut stream = if request.is_secure() {
+      Connection::Tls(TlsStream::connect(stream, &request.host())?)
+    } else {
+      Connection::Tcp(stream)
+    };
 
-    // ヘッダの追加
-    request.push_str("Host: ");
-    request.push_str(&host);
-    request.push('\n');
-    request.push_str("Accept: text:html\n");
-    request.push_str("Connection: close\n");
-    request.push('\n');
+    // TCP ストリームに送信する HTTP リクエストの文字列を構築する。
+    let request = request.serialize();
 
     // リクエストの送信
-    // TcpStream 構造体の write メソッドで行う。write メソッドの戻り値は送信したバイト数である。
-    let _bytes_written = match stream.write(request.as_bytes()) {
-      Ok(bytes) => bytes,
-      Err(_) => {
-        return Err(Error::Network(
-          "Failed to send a request to TCP stream".to_string(),
-        ))
-      }
-    };
+    // 接続の write メソッドで行う。write メソッドの戻り値は送信したバイト数である。
+    let _bytes_written = stream.write(request.as_bytes())?;
+
+    Ok(stream)
+  }
 
+  /// リクエストを送信した TCP ストリームからレスポンスを受信し、デコードできたボディから順に on_chunk に渡す。
+  fn receive(
+    &self,
+    stream: Connection,
+    request: &HttpRequest,
+    mut on_chunk: impl FnMut(&str),
+  ) -> Result<HttpResponse, Error> {
+    // HEAD リクエストの場合、ヘッダ部を受信し終えた時点で読み込みを終える。
+    let mut reader = ResponseReader::for_method(&request.method());
+    self.receive_into(stream, &mut reader, |_, chunk| on_chunk(chunk))?;
+
+    // ボディは Content-Type ヘッダの charset に従って Unicode の文字列にデコードする。
+    reader.finish()
+  }
+
+  /// TCP ストリームから受信したレスポンスのバイト列を reader に追加する。
+  /// 新しくデコードできたボディは、その時点の reader とともに on_chunk に渡す。
+  /// 受信に失敗した場合も、それまでに受信したバイト列は reader に残る。
+  fn receive_into(
+    &self,
+    mut stream: Connection,
+    reader: &mut ResponseReader,
+    mut on_chunk: impl FnMut(&ResponseReader, &str),
+  ) -> Result<(), Error> {
     // レスポンスの受信
     // レスポンスの受信は TcpStream 構造体の read メソッドで行う。read メソッドの引数に HTTP レスポンスを格納するバッファを渡す。
     // read メソッドは読み込んだバイト数を返却する。読み込むバイト数が 0 になるまで繰り返す。
-    // 分割されたレスポンスは Vec の extend_from_slice で結合する。    
-    let mut received = Vec::new();
+    // ただし、Content-Length で宣言された長さ、またはチャンク形式のボディの終端を受信し終えた場合は、
+    // 接続が閉じられるのを待たずに読み込みを終える。
+    // 分割されたレスポンスは ResponseReader で結合し、デコードできたボディから順に on_chunk に渡す。
     loop {
-        let mut buf = [0u8; 4096];
-        let bytes_read = match stream.read(&mut buf) {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return Err(Error::Network(
-                    "Failed to receive a request from TCP stream".to_string(),
-                ))
-            }
-        };
-        if bytes_read == 0 {
-            break;
-        }
-        received.extend_from_slice(&buf[..bytes_read]);
+      let mut buf = [0u8; 4096];
+      let bytes_read = stream.read(&mut buf)?;
+      if bytes_read == 0 {
+        break;
+      }
+      if let Some(chunk) = reader.push(&buf[..bytes_read]) {
+        on_chunk(reader, &chunk);
+      }
+      if reader.is_complete() {
+        break;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// リクエストを送信した接続。https の場合は TCP ストリームの上で TLS で暗号化する。
+enum Connection {
+  Tcp(TcpStream),
+  Tls(TlsStream),
+}
+
+impl Connection {
+  fn write(&mut self, data: &[u8]) -> Result<usize, Error> {
+    match self {
+      Connection::Tcp(stream) => stream.write(data).map_err(|_| {
+        Error::Network("Failed to send a request to TCP stream".to_string())
+      }),
+      Connection::Tls(stream) => stream.write(data),
     }
+  }
 
-    match core::str::from_utf8(&received) {
-      Ok(response) => HttpResponse::new(response.to_string()),
-      Err(e) => Err(Error::Network(format!("Invalid ceveived response: {}", e))),
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+    match self {
+      Connection::Tcp(stream) => stream.read(buf).map_err(|_| {
+        Error::Network("Failed to receive a request from TCP stream".to_string())
+      }),
+      Connection::Tls(stream) => stream.read(buf),
     }
+  }
+}
+
+/// URL のポート番号を u16 として返す。
+fn port_of(url: &Url) -> Result<u16, Error> {
+  url.port().parse::<u16>().map_err(|_| {
+    Error::UnexpectedInput(format!(
+      "port number should be u16 but got {}",
+      url.port()
+    ))
+  })
+}
 
+/// URL のパスを返す。クエリパラメータがある場合は、パスに続けて送信する。
+fn path_of(url: &Url) -> String {
+  let mut path = url.path();
+  if !url.searchpart().is_empty() {
+    path.push('?');
+    path.push_str(&url.searchpart());
   }
-}
\ No newline at end of file
+  path
+}
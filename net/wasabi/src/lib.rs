@@ -1,3 +1,4 @@
 #![no_std]
 
-pub mod http;
\ No newline at end of file
+pub mod http;
+pub mod tls;
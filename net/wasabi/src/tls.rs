@@ -0,0 +1,131 @@
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::arch::x86_64::__cpuid;
+use core::arch::x86_64::_rdrand64_step;
+use noli::net::TcpStream;
+use saba_core::error::Error;
+use saba_core::tls::client::TlsClient;
+
+/// RDRAND 命令が一時的に乱数を返せなかった場合に、やり直す最大の回数
+const RDRAND_RETRIES: usize = 10;
+
+/// TCP ストリームの上で TLS 1.3 の通信を行うストリーム
+/// ハンドシェイクと暗号化は saba_core の TlsClient が行い、この構造体はソケットとの間でバイト列を受け渡す。
+pub struct TlsStream {
+  stream: TcpStream,
+  client: TlsClient,
+  // 復号したが、まだ read で返していないアプリケーションデータ
+  plaintext: Vec<u8>,
+}
+
+impl TlsStream {
+  /// 接続済みの TCP ストリームの上で、server_name とのハンドシェイクを行う。
+  pub fn connect(mut stream: TcpStream, server_name: &str) -> Result<Self, Error> {
+    let mut client = TlsClient::new(server_name, random_bytes()?, random_bytes()?);
+    write_all(&mut stream, &client.take_outgoing())?;
+
+    let mut buf = [0u8; 4096];
+    while !client.is_connected() {
+      let bytes_read = read(&mut stream, &mut buf)?;
+      if bytes_read == 0 {
+        return Err(Error::Network(
+          "TLS: the connection was closed during the handshake".to_string(),
+        ));
+      }
+      client.push(&buf[..bytes_read])?;
+      write_all(&mut stream, &client.take_outgoing())?;
+    }
+
+    Ok(Self {
+      stream,
+      client,
+      plaintext: Vec::new(),
+    })
+  }
+
+  /// data を暗号化して送信し、送信したバイト数を返す。
+  pub fn write(&mut self, data: &[u8]) -> Result<usize, Error> {
+    self.client.write(data)?;
+    write_all(&mut self.stream, &self.client.take_outgoing())?;
+    Ok(data.len())
+  }
+
+  /// 受信して復号したデータを buf に書き込み、そのバイト数を返す。
+  /// サーバが接続を閉じた場合は 0 を返す。
+  pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+    let mut received = [0u8; 4096];
+    while self.plaintext.is_empty() {
+      if self.client.is_closed() {
+        return Ok(0);
+      }
+      let bytes_read = read(&mut self.stream, &mut received)?;
+      if bytes_read == 0 {
+        return Ok(0);
+      }
+      self.client.push(&received[..bytes_read])?;
+      // サーバから鍵の更新を求められた場合は、KeyUpdate を返信する。
+      write_all(&mut self.stream, &self.client.take_outgoing())?;
+      self.plaintext.extend(self.client.take_application_data());
+    }
+
+    let n = buf.len().min(self.plaintext.len());
+    buf[..n].copy_from_slice(&self.plaintext[..n]);
+    self.plaintext.drain(..n);
+    Ok(n)
+  }
+}
+
+fn read(stream: &mut TcpStream, buf: &mut [u8]) -> Result<usize, Error> {
+  stream.read(buf).map_err(|_| {
+    Error::Network("Failed to receive a request from TCP stream".to_string())
+  })
+}
+
+/// data をすべて送信し終えるまで書き込む。
+fn write_all(stream: &mut TcpStream, mut data: &[u8]) -> Result<(), Error> {
+  while !data.is_empty() {
+    let bytes_written = match stream.write(data) {
+      Ok(0) | Err(_) => {
+        return Err(Error::Network(
+          "Failed to send a request to TCP stream".to_string(),
+        ))
+      }
+      Ok(bytes) => bytes,
+    };
+    data = &data[bytes_written..];
+  }
+  Ok(())
+}
+
+/// 鍵交換の秘密鍵と ClientHello の random に使う 32 バイトの乱数を、CPU の乱数生成器から取得する。
+/// wasabiOS は乱数を提供しないため、RDRAND 命令を使う。RDRAND に対応していない CPU ではエラーとする。
+fn random_bytes() -> Result<[u8; 32], Error> {
+  // CPUID の leaf 1 の ECX の 30 ビット目が RDRAND への対応を表す。
+  let supported = unsafe { __cpuid(1).ecx & (1 << 30) != 0 };
+  if !supported {
+    return Err(Error::Network(
+      "TLS: the CPU does not provide a random number generator (RDRAND)".to_string(),
+    ));
+  }
+
+  let mut bytes = [0u8; 32];
+  for chunk in bytes.chunks_mut(8) {
+    let value = unsafe { rdrand64() }.ok_or_else(|| {
+      Error::Network("TLS: RDRAND failed to return a random number".to_string())
+    })?;
+    chunk.copy_from_slice(&value.to_le_bytes());
+  }
+  Ok(bytes)
+}
+
+#[target_feature(enable = "rdrand")]
+unsafe fn rdrand64() -> Option<u64> {
+  for _ in 0..RDRAND_RETRIES {
+    let mut value = 0;
+    if _rdrand64_step(&mut value) == 1 {
+      return Some(value);
+    }
+  }
+  None
+}
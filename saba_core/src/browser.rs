@@ -7,6 +7,8 @@ use core::cell::RefCell;
 pub struct Browser {
     active_page_index: usize,
     pages: Vec<Rc<RefCell<Page>>>,
+    // JavaScript を実行するかどうかの設定
+    scripting_enabled: bool,
 }
 
 impl Browser {
@@ -16,6 +18,7 @@ impl Browser {
         let browser = Rc::new(RefCell::new(Self {
             active_page_index: 0,
             pages: Vec::new(),
+            scripting_enabled: true,
         }));
 
         page.set_browser(Rc::downgrade(&browser));
@@ -27,4 +30,14 @@ impl Browser {
     pub fn current_page(&self) -> Rc<RefCell<Page>> {
         self.pages[self.active_page_index].clone()
     }
+
+    /// JavaScript を実行する設定になっているかどうかを返す。デフォルトでは有効である。
+    pub fn scripting_enabled(&self) -> bool {
+        self.scripting_enabled
+    }
+
+    /// JavaScript を実行するかどうかを設定する。次に読み込むページから反映される。
+    pub fn set_scripting_enabled(&mut self, enabled: bool) {
+        self.scripting_enabled = enabled;
+    }
 }
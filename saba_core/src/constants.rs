@@ -22,3 +22,11 @@ pub static CONTENT_AREA_HEIGHT: i64 =
 pub static CHAR_WIDTH: i64 = 8;
 pub static CHAR_HEIGHT: i64 = 16;
 pub static CHAR_HEIGHT_WITH_PADDING: i64 = CHAR_HEIGHT + 4;
+
+// width 属性や height 属性が指定されていない <iframe> の大きさ
+// https://html.spec.whatwg.org/multipage/rendering.html#embedded-content-rendering-rules
+pub static IFRAME_DEFAULT_WIDTH: i64 = 300;
+pub static IFRAME_DEFAULT_HEIGHT: i64 = 150;
+
+// リストの項目の先頭に表示するマーカー ("*" や "10.") の横幅
+pub static LIST_MARKER_WIDTH: i64 = CHAR_WIDTH * 4;
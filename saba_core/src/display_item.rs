@@ -4,7 +4,9 @@ use crate::renderer::layout::layout_object::LayoutSize;
 use alloc::string::String;
 
 /// 画面の描画に使用する DisplayItem 列挙体
-/// 四角(Rect) とテキスト(Text)を描画できる。
+/// 四角(Rect)、テキスト(Text)、画像(Image)を描画できる。
+/// テキストは、太字で描画するかどうかを bold フィールドに、下線と取り消し線を描画するかどうかを underline と line_through フィールドに持つ。
+/// 画像は、<img> 要素の src 属性の値と、画像を描画する領域を持つ。
 #[derive(Debug, Clone, PartialEq)]
 pub enum DisplayItem {
     Rect {
@@ -14,7 +16,100 @@ pub enum DisplayItem {
     },
     Text {
         text: String,
+        bold: bool,
+        underline: bool,
+        line_through: bool,
         style: ComputedStyle,
         layout_point: LayoutPoint,
     },
+    Image {
+        src: String,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    },
+}
+
+impl DisplayItem {
+    /// 描画する位置を返す。
+    pub fn layout_point(&self) -> LayoutPoint {
+        match self {
+            DisplayItem::Rect { layout_point, .. } => *layout_point,
+            DisplayItem::Text { layout_point, .. } => *layout_point,
+            DisplayItem::Image { layout_point, .. } => *layout_point,
+        }
+    }
+
+    /// 描画する位置を (dx, dy) だけ移動した DisplayItem を返す。
+    /// <iframe> の中のページの DisplayItem を、親のページの座標に変換するときに使用する。
+    pub fn translate(&self, dx: i64, dy: i64) -> Self {
+        let mut item = self.clone();
+        match &mut item {
+            DisplayItem::Rect { layout_point, .. }
+            | DisplayItem::Text { layout_point, .. }
+            | DisplayItem::Image { layout_point, .. } => {
+                layout_point.set_x(layout_point.x() + dx);
+                layout_point.set_y(layout_point.y() + dy);
+            }
+        }
+        item
+    }
+
+    /// 原点を左上とする size の大きさの領域で切り取った DisplayItem を返す。
+    /// 四角と画像は領域と重なる部分だけを残し、テキストは描画する位置が領域の中にある場合だけ残す。
+    /// 領域の外にある場合は None を返す。
+    pub fn clip(&self, size: LayoutSize) -> Option<Self> {
+        match self {
+            DisplayItem::Rect {
+                style,
+                layout_point,
+                layout_size,
+            } => {
+                let (layout_point, layout_size) = clip_rect(*layout_point, *layout_size, size)?;
+                Some(DisplayItem::Rect {
+                    style: style.clone(),
+                    layout_point,
+                    layout_size,
+                })
+            }
+            DisplayItem::Image {
+                src,
+                layout_point,
+                layout_size,
+            } => {
+                let (layout_point, layout_size) = clip_rect(*layout_point, *layout_size, size)?;
+                Some(DisplayItem::Image {
+                    src: src.clone(),
+                    layout_point,
+                    layout_size,
+                })
+            }
+            DisplayItem::Text { layout_point, .. } => {
+                let p = *layout_point;
+                if p.x() < 0 || p.x() >= size.width() || p.y() < 0 || p.y() >= size.height() {
+                    return None;
+                }
+                Some(self.clone())
+            }
+        }
+    }
+}
+
+/// point と size で表す領域のうち、原点を左上とする clip の大きさの領域と重なる部分を返す。
+/// 重なる部分がない場合は None を返す。
+fn clip_rect(
+    point: LayoutPoint,
+    size: LayoutSize,
+    clip: LayoutSize,
+) -> Option<(LayoutPoint, LayoutSize)> {
+    let left = point.x().max(0);
+    let top = point.y().max(0);
+    let right = (point.x() + size.width()).min(clip.width());
+    let bottom = (point.y() + size.height()).min(clip.height());
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((
+        LayoutPoint::new(left, top),
+        LayoutSize::new(right - left, bottom - top),
+    ))
 }
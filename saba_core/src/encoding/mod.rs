@@ -0,0 +1,269 @@
+//! レスポンスのボディを Unicode の文字列にデコードする。
+//! https://encoding.spec.whatwg.org/
+
+mod jis0208;
+
+use alloc::string::String;
+use jis0208::JIS0208;
+
+/// デコードできないバイト列を置き換える文字
+const REPLACEMENT_CHARACTER: char = '\u{FFFD}';
+
+/// 文字エンコーディングを表す列挙型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    ShiftJis,
+    EucJp,
+    /// ISO-8859-1 (Latin-1)。1バイトをそのまま U+0000 から U+00FF に対応させる。
+    Latin1,
+}
+
+impl Encoding {
+    /// charset のラベルからエンコーディングを取得する。大文字と小文字は区別しない。
+    /// 対応していないラベルの場合は None を返す。
+    /// https://encoding.spec.whatwg.org/#concept-encoding-get
+    pub fn for_label(label: &str) -> Option<Self> {
+        let label = label.trim().trim_matches('"');
+        let labels: [(&str, Encoding); 16] = [
+            ("utf-8", Encoding::Utf8),
+            ("utf8", Encoding::Utf8),
+            ("unicode-1-1-utf-8", Encoding::Utf8),
+            ("shift_jis", Encoding::ShiftJis),
+            ("shift-jis", Encoding::ShiftJis),
+            ("sjis", Encoding::ShiftJis),
+            ("x-sjis", Encoding::ShiftJis),
+            ("ms_kanji", Encoding::ShiftJis),
+            ("windows-31j", Encoding::ShiftJis),
+            ("csshiftjis", Encoding::ShiftJis),
+            ("euc-jp", Encoding::EucJp),
+            ("x-euc-jp", Encoding::EucJp),
+            ("cseucpkdfmtjapanese", Encoding::EucJp),
+            ("iso-8859-1", Encoding::Latin1),
+            ("latin1", Encoding::Latin1),
+            ("us-ascii", Encoding::Latin1),
+        ];
+        labels
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(label))
+            .map(|(_, encoding)| *encoding)
+    }
+
+    /// バイト列を文字列にデコードする。不正なバイト列は U+FFFD に置き換える。
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::ShiftJis => decode_shift_jis(bytes),
+            Encoding::EucJp => decode_euc_jp(bytes),
+            Encoding::Latin1 => bytes.iter().map(|b| *b as char).collect(),
+        }
+    }
+}
+
+impl Encoding {
+    /// バイト列のうち、文字の途中で途切れずにデコードできる先頭部分のバイト数を返す。
+    /// 分割されて届くボディを少しずつデコードするときに、末尾の不完全な文字を次の分割まで残すために使う。
+    pub fn complete_prefix_len(&self, bytes: &[u8]) -> usize {
+        match self {
+            Encoding::Utf8 => match core::str::from_utf8(bytes) {
+                Ok(_) => bytes.len(),
+                // 末尾の文字が途中で途切れている場合、error_len は None となる。
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => bytes.len(),
+            },
+            Encoding::ShiftJis | Encoding::EucJp => {
+                let mut pos = 0;
+                while pos < bytes.len() {
+                    let length = self.char_length(bytes[pos]);
+                    if pos + length > bytes.len() {
+                        return pos;
+                    }
+                    pos += length;
+                }
+                bytes.len()
+            }
+            Encoding::Latin1 => bytes.len(),
+        }
+    }
+
+    /// 先頭のバイトから、1文字を表すバイト列の長さを返す。
+    fn char_length(&self, lead: u8) -> usize {
+        match (self, lead) {
+            (Encoding::ShiftJis, 0x81..=0x9F | 0xE0..=0xFC) => 2,
+            (Encoding::EucJp, 0x8F) => 3,
+            (Encoding::EucJp, 0x8E | 0xA1..=0xFE) => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// JIS X 0208 のインデックスのポインタに対応する文字を返す。
+fn jis0208_code_point(pointer: usize) -> Option<char> {
+    match JIS0208.get(pointer) {
+        Some(0) | None => None,
+        Some(code_point) => char::from_u32(*code_point as u32),
+    }
+}
+
+/// Shift_JIS のバイト列をデコードする。
+/// https://encoding.spec.whatwg.org/#shift_jis-decoder
+fn decode_shift_jis(bytes: &[u8]) -> String {
+    let mut result = String::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let lead = bytes[pos];
+        pos += 1;
+        match lead {
+            0x00..=0x80 => result.push(lead as char),
+            // 半角カタカナ
+            0xA1..=0xDF => result.push(char::from_u32(0xFF61 + (lead - 0xA1) as u32).unwrap()),
+            0x81..=0x9F | 0xE0..=0xFC => {
+                let trail = match bytes.get(pos) {
+                    Some(trail) => *trail,
+                    None => {
+                        result.push(REPLACEMENT_CHARACTER);
+                        break;
+                    }
+                };
+                let lead_offset = if lead < 0xA0 { 0x81 } else { 0xC1 };
+                let trail_offset = if trail < 0x7F { 0x40 } else { 0x41 };
+                let c = match trail {
+                    0x40..=0x7E | 0x80..=0xFC => jis0208_code_point(
+                        (lead - lead_offset) as usize * 188 + (trail - trail_offset) as usize,
+                    ),
+                    _ => None,
+                };
+                match c {
+                    Some(c) => {
+                        result.push(c);
+                        pos += 1;
+                    }
+                    None => {
+                        result.push(REPLACEMENT_CHARACTER);
+                        // 2バイト目が ASCII の場合は、その文字を改めてデコードする。
+                        if !trail.is_ascii() {
+                            pos += 1;
+                        }
+                    }
+                }
+            }
+            _ => result.push(REPLACEMENT_CHARACTER),
+        }
+    }
+    result
+}
+
+/// EUC-JP のバイト列をデコードする。
+/// JIS X 0212 の補助漢字 (0x8F から始まる3バイトの文字) には対応しておらず、U+FFFD に置き換える。
+/// https://encoding.spec.whatwg.org/#euc-jp-decoder
+fn decode_euc_jp(bytes: &[u8]) -> String {
+    let mut result = String::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let lead = bytes[pos];
+        pos += 1;
+        if lead.is_ascii() {
+            result.push(lead as char);
+            continue;
+        }
+
+        let trail = match bytes.get(pos) {
+            Some(trail) if (0xA1..=0xFE).contains(trail) => *trail,
+            _ => {
+                result.push(REPLACEMENT_CHARACTER);
+                continue;
+            }
+        };
+        match lead {
+            // 半角カタカナ
+            0x8E if trail <= 0xDF => {
+                result.push(char::from_u32(0xFF61 + (trail - 0xA1) as u32).unwrap());
+                pos += 1;
+            }
+            0x8F => {
+                result.push(REPLACEMENT_CHARACTER);
+                pos += 1;
+                if bytes.get(pos).is_some_and(|b| (0xA1..=0xFE).contains(b)) {
+                    pos += 1;
+                }
+            }
+            0xA1..=0xFE => {
+                let pointer = (lead - 0xA1) as usize * 94 + (trail - 0xA1) as usize;
+                result.push(jis0208_code_point(pointer).unwrap_or(REPLACEMENT_CHARACTER));
+                pos += 1;
+            }
+            _ => result.push(REPLACEMENT_CHARACTER),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// charset のラベルからエンコーディングを取得するテスト。
+    fn test_for_label() {
+        assert_eq!(Some(Encoding::Utf8), Encoding::for_label("UTF-8"));
+        assert_eq!(Some(Encoding::ShiftJis), Encoding::for_label("Shift_JIS"));
+        assert_eq!(
+            Some(Encoding::ShiftJis),
+            Encoding::for_label("\"windows-31j\"")
+        );
+        assert_eq!(Some(Encoding::EucJp), Encoding::for_label("euc-jp"));
+        assert_eq!(None, Encoding::for_label("koi8-r"));
+    }
+
+    #[test]
+    /// Shift_JIS のデコードのテスト。
+    fn test_decode_shift_jis() {
+        // "<p>こんにちは、世界ｱ</p>"
+        let bytes = [
+            b'<', b'p', b'>', 0x82, 0xB1, 0x82, 0xF1, 0x82, 0xC9, 0x82, 0xBF, 0x82, 0xCD, 0x81,
+            0x41, 0x90, 0xA2, 0x8A, 0x45, 0xB1, b'<', b'/', b'p', b'>',
+        ];
+        assert_eq!(
+            "<p>こんにちは、世界ｱ</p>",
+            Encoding::ShiftJis.decode(&bytes)
+        );
+
+        // 不正な2バイト目と途中で終わる文字
+        assert_eq!(
+            "\u{FFFD} \u{FFFD}",
+            Encoding::ShiftJis.decode(&[0x82, b' ', 0x82])
+        );
+    }
+
+    #[test]
+    /// EUC-JP のデコードのテスト。
+    fn test_decode_euc_jp() {
+        // "日本語ｶﾅ"
+        let bytes = [0xC6, 0xFC, 0xCB, 0xDC, 0xB8, 0xEC, 0x8E, 0xB6, 0x8E, 0xC5];
+        assert_eq!("日本語ｶﾅ", Encoding::EucJp.decode(&bytes));
+        assert_eq!("a\u{FFFD}b", Encoding::EucJp.decode(&[b'a', 0xA4, b'b']));
+    }
+
+    #[test]
+    /// 末尾の不完全な文字を除いた長さのテスト。
+    fn test_complete_prefix_len() {
+        let utf8 = "日本".as_bytes();
+        assert_eq!(6, Encoding::Utf8.complete_prefix_len(utf8));
+        assert_eq!(3, Encoding::Utf8.complete_prefix_len(&utf8[..5]));
+        assert_eq!(
+            2,
+            Encoding::ShiftJis.complete_prefix_len(&[b'a', b'b', 0x93])
+        );
+        assert_eq!(
+            3,
+            Encoding::ShiftJis.complete_prefix_len(&[b'a', 0x93, 0xFA])
+        );
+        assert_eq!(1, Encoding::EucJp.complete_prefix_len(&[b'a', 0x8F, 0xA1]));
+    }
+
+    #[test]
+    /// Latin-1 のデコードのテスト。
+    fn test_decode_latin1() {
+        assert_eq!("café", Encoding::Latin1.decode(&[b'c', b'a', b'f', 0xE9]));
+    }
+}
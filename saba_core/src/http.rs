@@ -1,3 +1,4 @@
+use crate::encoding::Encoding;
 use crate::error::Error;
 use alloc::format;
 use alloc::string::String;
@@ -12,59 +13,143 @@ pub struct HttpResponse {
     reason: String,
     headers: Vec<Header>,
     body: String,
+    // デコードする前のボディのバイト列。<meta charset> で宣言されたエンコーディングでデコードし直すために使う。
+    raw_body: Vec<u8>,
+    // デコードする前のボディのバイト数。Content-Length と比較するために使う。
+    body_length: usize,
+    // HEAD リクエストに対するレスポンスのように、Content-Length があってもボディを持たない場合に true となる。
+    bodiless: bool,
+    // チャンク形式のボディの終端 (長さ 0 のチャンクと空行) を受信できなかった場合に true となる。
+    missing_last_chunk: bool,
 }
 
 /// HTTP レスポンスの文字列から HttpResponse 構造体を構築する。
 /// 文字列が不正な場合、エラーを返す。
 impl HttpResponse {
     pub fn new(raw_response: String) -> Result<Self, Error> {
-        // レスポンス文字列の先頭のスペースをトリムし、キャリッジリターンと改行シーケンス("\r\n")を単一の改行("\n")に書き換える。
-        // これにより、一貫した行末を保証する。
-        let preprocessed_response = raw_response.trim_start().replace("\r\n", "\n");
-
-        // ステータスラインの分割
-        // レスポンスを最初の改行で分割する。分割した前半を status_line、後半を remaining とする。
-        // 分割できない場合はエラーとする。
-        let (status_line, remaining) = match preprocessed_response.split_once('\n') {
-            Some((s, r)) => (s, r),
-            None => {
-                return Err(Error::Network(format!(
-                    "invalid http response: {}",
-                    preprocessed_response
-                )))
-            }
-        };
+        // レスポンス文字列の先頭のスペースや空行をトリムする。
+        let response = raw_response.trim_start();
+
+        // ステータスラインの後に改行がない場合、レスポンスとして不正とする。
+        if !response.contains('\n') {
+            return Err(Error::Network(format!(
+                "invalid http response: {}",
+                response
+            )));
+        }
 
-        // ヘッダとボディの分割
-        // レスポンスの remaining を2つの連続した改行で分割する。分割できた場合、前半を headers、後半を body  とする。
-        // 2つの改行が見つからない場合、ヘッダが存在しないと判断して headers を空のベクトルに設定する。
-        let (headers, body) = match remaining.split_once("\n\n") {
-            Some((h, b)) => {
-                let mut headers = Vec::new();
-                for header in h.split('\n') {
-                    let splitted_header: Vec<&str> = header.splitn(2, ':').collect();
-                    headers.push(Header::new(
-                        String::from(splitted_header[0].trim()),
-                        String::from(splitted_header[1].trim()),
-                    ));
+        // ヘッダ部とボディの分割
+        // ヘッダ部の終端は空行 ("\r\n\r\n" または "\n\n") である。ボディの改行は書き換えずにそのまま保持する。
+        // ヘッダ部では、キャリッジリターンと改行シーケンス("\r\n")を単一の改行("\n")に書き換え、一貫した行末を保証する。
+        let (head, body) = split_head_and_body(response);
+        let head = head.replace("\r\n", "\n");
+
+        let mut lines = head.split('\n');
+        let (version, status_code, reason) = parse_status_line(lines.next().unwrap_or(""))?;
+
+        // ヘッダの解析
+        // 空白から始まる行は、直前のヘッダの値の続き (obs-fold) として扱う。
+        // コロンを含まない不正な行は無視する。
+        let mut headers: Vec<Header> = Vec::new();
+        for line in lines {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with(' ') || line.starts_with('\t') {
+                if let Some(last) = headers.last_mut() {
+                    last.value.push(' ');
+                    last.value.push_str(line.trim());
                 }
-                (headers, b)
+                continue;
             }
-            None => (Vec::new(), remaining),
-        };
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push(Header::new(
+                    String::from(name.trim()),
+                    String::from(value.trim()),
+                ));
+            }
+        }
 
-        // ステータスラインを分割し、HttpResponse 構造体に設定する。
-        // また、headers や body も設定する。
-        let statuses: Vec<&str> = status_line.split(' ').collect();
         Ok(Self {
-            version: statuses[0].to_string(),
-            status_code: statuses[1].parse().unwrap_or(404),
-            reason: statuses[2].to_string(),
+            version,
+            status_code,
+            reason,
             headers,
             body: body.to_string(),
+            raw_body: body.as_bytes().to_vec(),
+            body_length: body.len(),
+            bodiless: false,
+            missing_last_chunk: false,
         })
     }
 
+    /// 受信したバイト列から HttpResponse 構造体を構築する。
+    /// Transfer-Encoding が chunked の場合は、チャンクを結合したものをボディとする。
+    /// ボディは Content-Type ヘッダの charset に従ってデコードする。
+    /// charset がない場合や対応していない場合は UTF-8 とみなす。
+    pub fn from_bytes(received: &[u8]) -> Result<Self, Error> {
+        let start = received
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(received.len());
+        let received = &received[start..];
+        let (head_end, body_start) =
+            find_head_end(received).unwrap_or((received.len(), received.len()));
+
+        // ヘッダ部は ASCII であるため、UTF-8 として解釈する。
+        let head = String::from_utf8_lossy(&received[..head_end]);
+        let mut response = HttpResponse::new(format!("{}\n\n", head))?;
+
+        let mut body = &received[body_start..];
+        let mut chunks = ChunkedBody::new();
+        if response.has_chunked_body() {
+            chunks.feed(body)?;
+            body = chunks.data();
+            response.missing_last_chunk = !chunks.is_done();
+        }
+        response.body = response.body_encoding().decode(body);
+        response.raw_body = body.to_vec();
+        response.body_length = body.len();
+        Ok(response)
+    }
+
+    /// Transfer-Encoding ヘッダの最後の転送コーディングが chunked かどうか。
+    /// https://datatracker.ietf.org/doc/html/rfc9112#section-6.1
+    pub fn is_chunked(&self) -> bool {
+        self.header_values("Transfer-Encoding")
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(|coding| coding.trim())
+            .filter(|coding| !coding.is_empty())
+            .last()
+            .is_some_and(|coding| coding.eq_ignore_ascii_case("chunked"))
+    }
+
+    /// ボディがチャンク形式で送られてくるかどうか。ボディを持たないステータスコードの場合は false とする。
+    fn has_chunked_body(&self) -> bool {
+        self.is_chunked() && !has_no_body(self.status_code)
+    }
+
+    /// Content-Type ヘッダの charset が表すボディのエンコーディングを返す。
+    /// charset がない場合や対応していない場合は UTF-8 とみなす。
+    fn body_encoding(&self) -> Encoding {
+        self.declared_encoding().unwrap_or(Encoding::Utf8)
+    }
+
+    /// Content-Type ヘッダの charset で宣言されたエンコーディングを返す。
+    /// charset がない場合や対応していない場合は None を返す。
+    pub fn declared_encoding(&self) -> Option<Encoding> {
+        self.content_type()
+            .and_then(|content_type| content_type.charset())
+            .and_then(|charset| Encoding::for_label(&charset))
+    }
+
+    /// デコードする前のボディを、指定したエンコーディングでデコードし直した文字列を返す。
+    pub fn decode_body(&self, encoding: Encoding) -> String {
+        encoding.decode(&self.raw_body)
+    }
+
     pub fn version(&self) -> String {
         self.version.clone()
     }
@@ -80,14 +165,745 @@ impl HttpResponse {
     pub fn body(&self) -> String {
         self.body.clone()
     }
+    /// デコードする前のボディのバイト列を返す。画像のように文字列ではないボディを扱うときに使用する。
+    pub fn raw_body(&self) -> Vec<u8> {
+        self.raw_body.clone()
+    }
+    /// name と一致するヘッダの値を返す。ヘッダ名の大文字と小文字は区別しない。
     pub fn header_value(&self, name: &str) -> Result<String, String> {
         for h in &self.headers {
-            if h.name == name {
+            if h.name.eq_ignore_ascii_case(name) {
                 return Ok(h.value.clone());
             }
         }
         Err(format!("failed to find {} in headers", name))
     }
+
+    /// name と一致するすべてのヘッダの値を、レスポンスに現れた順に返す。
+    /// Set-Cookie など、同じ名前のヘッダが複数現れる場合に使う。ヘッダ名の大文字と小文字は区別しない。
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        self.headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.clone())
+            .collect()
+    }
+
+    /// ボディが Content-Length ヘッダで宣言された長さより短いかどうか。
+    /// 接続が途中で切れ、レスポンスの一部しか受信できなかった場合に true となる。
+    /// チャンク形式の場合は、最後のチャンクを受信できなかった場合に true となる。
+    /// Content-Length ヘッダが存在しない場合は、長さを確認できないため false を返す。
+    pub fn is_truncated(&self) -> bool {
+        if self.bodiless || has_no_body(self.status_code) {
+            return false;
+        }
+        if self.missing_last_chunk {
+            return true;
+        }
+        match self.content_length() {
+            Some(length) => self.body_length < length,
+            None => false,
+        }
+    }
+
+    /// Content-Type ヘッダを解析した結果を返す。
+    /// ヘッダが存在しない場合や、MIME タイプが不正な場合は None を返す。
+    /// https://httpwg.org/specs/rfc9110.html#field.content-type
+    pub fn content_type(&self) -> Option<ContentType> {
+        let value = self.header_value("Content-Type").ok()?;
+        ContentType::parse(&value)
+    }
+
+    /// サーバがバイト単位の範囲リクエスト (Range ヘッダ) に対応しているかどうか。
+    /// https://httpwg.org/specs/rfc9110.html#field.accept-ranges
+    pub fn accepts_ranges(&self) -> bool {
+        self.header_values("Accept-Ranges").iter().any(|value| {
+            value
+                .split(',')
+                .any(|unit| unit.trim().eq_ignore_ascii_case("bytes"))
+        })
+    }
+
+    /// Content-Range ヘッダ ("bytes 100-199/1000" の形式) を解析し、
+    /// 範囲の最初と最後のバイトの位置と、全体の長さ (不明な場合は None) を返す。
+    /// https://httpwg.org/specs/rfc9110.html#field.content-range
+    pub fn content_range(&self) -> Option<(usize, usize, Option<usize>)> {
+        let value = self.header_value("Content-Range").ok()?;
+        let (unit, range) = value.trim().split_once(' ')?;
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+        let (range, total) = range.trim().split_once('/')?;
+        let (first, last) = range.split_once('-')?;
+        let first = first.trim().parse::<usize>().ok()?;
+        let last = last.trim().parse::<usize>().ok()?;
+        if last < first {
+            return None;
+        }
+        let total = match total.trim() {
+            "*" => None,
+            total => Some(total.parse::<usize>().ok()?),
+        };
+        Some((first, last, total))
+    }
+
+    /// Content-Length ヘッダの値を返す。
+    /// 同じ値が複数並んでいる場合 (例: "42, 42") は1つの値とみなす。
+    /// ヘッダが存在しない場合や、値が不正な場合は None を返す。
+    /// Transfer-Encoding ヘッダがある場合、ボディの長さは転送コーディングで決まるため None を返す。
+    /// https://httpwg.org/specs/rfc9110.html#field.content-length
+    /// https://datatracker.ietf.org/doc/html/rfc9112#section-6.3
+    pub fn content_length(&self) -> Option<usize> {
+        if !self.header_values("Transfer-Encoding").is_empty() {
+            return None;
+        }
+        let mut length = None;
+        for value in self.header_values("Content-Length") {
+            for v in value.split(',') {
+                let v = v.trim();
+                if v.is_empty() || !v.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let n = v.parse::<usize>().ok()?;
+                match length {
+                    Some(l) if l != n => return None,
+                    _ => length = Some(n),
+                }
+            }
+        }
+        length
+    }
+}
+
+/// Content-Type ヘッダの値を表す構造体
+/// MIME タイプ (例: "text/html") は小文字に正規化して保持する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    mime_type: String,
+    charset: Option<String>,
+}
+
+impl ContentType {
+    /// "text/html; charset=UTF-8" の形式の文字列を解析する。
+    /// https://mimesniff.spec.whatwg.org/#parsing-a-mime-type
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut params = value.split(';');
+        let mime_type = params.next()?.trim().to_ascii_lowercase();
+        match mime_type.split_once('/') {
+            Some((t, sub)) if !t.is_empty() && !sub.is_empty() => {}
+            _ => return None,
+        }
+
+        let mut charset = None;
+        for param in params {
+            if let Some((name, value)) = param.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("charset") && charset.is_none() {
+                    charset = Some(value.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+
+        Some(Self { mime_type, charset })
+    }
+
+    pub fn mime_type(&self) -> String {
+        self.mime_type.clone()
+    }
+
+    pub fn charset(&self) -> Option<String> {
+        self.charset.clone()
+    }
+}
+
+/// レスポンスのバイト列から、ヘッダ部の終端を表す空行を探す。
+/// 見つかった場合、ヘッダ部の長さとボディの開始位置の組を返す。
+fn find_head_end(bytes: &[u8]) -> Option<(usize, usize)> {
+    for (i, b) in bytes.iter().enumerate() {
+        if *b != b'\n' {
+            continue;
+        }
+        if bytes.get(i + 1) == Some(&b'\n') {
+            return Some((i, i + 2));
+        }
+        if bytes.get(i + 1) == Some(&b'\r') && bytes.get(i + 2) == Some(&b'\n') {
+            return Some((i, i + 3));
+        }
+    }
+    None
+}
+
+/// レスポンスを最初の空行でヘッダ部とボディに分割する。
+/// 空行が存在しない場合、レスポンス全体をヘッダ部とし、ボディは空とする。
+fn split_head_and_body(response: &str) -> (&str, &str) {
+    match find_head_end(response.as_bytes()) {
+        Some((head_end, body_start)) => (&response[..head_end], &response[body_start..]),
+        None => (response, ""),
+    }
+}
+
+/// チャンク形式 (Transfer-Encoding: chunked) のボディを、届いた分から順に復元する構造体。
+/// チャンクの長さの行、チャンク拡張、トレーラーはボディに含めない。
+/// https://datatracker.ietf.org/doc/html/rfc9112#section-7.1
+struct ChunkedBody {
+    state: ChunkState,
+    // 復元したボディのバイト列
+    data: Vec<u8>,
+    // 解析済みの受信したボディのバイト数
+    consumed: usize,
+}
+
+/// ChunkedBody が次に読み込むもの
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkState {
+    /// チャンクの長さの行
+    Size,
+    /// 残りのバイト数が指定されたチャンクのデータ
+    Data(usize),
+    /// チャンクのデータの後の改行
+    DataEnd,
+    /// 最後のチャンクの後のトレーラーと空行
+    Trailer,
+    /// ボディの終端
+    Done,
+}
+
+impl ChunkedBody {
+    fn new() -> Self {
+        Self {
+            state: ChunkState::Size,
+            data: Vec::new(),
+            consumed: 0,
+        }
+    }
+
+    /// これまでに受信したボディのバイト列全体を受け取り、前回の続きから解析する。
+    /// チャンクの長さが不正な場合はエラーとする。
+    fn feed(&mut self, received: &[u8]) -> Result<(), Error> {
+        loop {
+            let rest = &received[self.consumed..];
+            match self.state {
+                ChunkState::Size => {
+                    let Some((line, next)) = next_line(rest) else {
+                        return Ok(());
+                    };
+                    // チャンク拡張 (";name=value") は無視する。
+                    let size = line.split(|b| *b == b';').next().unwrap_or(&[]);
+                    let size = core::str::from_utf8(size).unwrap_or("").trim();
+                    let valid = !size.is_empty() && size.bytes().all(|b| b.is_ascii_hexdigit());
+                    let size = match usize::from_str_radix(size, 16) {
+                        Ok(size) if valid => size,
+                        _ => {
+                            return Err(Error::Network(format!(
+                                "invalid chunk size: {:?}",
+                                String::from_utf8_lossy(line)
+                            )))
+                        }
+                    };
+                    self.consumed += next;
+                    self.state = if size == 0 {
+                        ChunkState::Trailer
+                    } else {
+                        ChunkState::Data(size)
+                    };
+                }
+                ChunkState::Data(remaining) => {
+                    if rest.is_empty() {
+                        return Ok(());
+                    }
+                    let length = remaining.min(rest.len());
+                    self.data.extend_from_slice(&rest[..length]);
+                    self.consumed += length;
+                    self.state = if length == remaining {
+                        ChunkState::DataEnd
+                    } else {
+                        ChunkState::Data(remaining - length)
+                    };
+                }
+                ChunkState::DataEnd => {
+                    let Some((line, next)) = next_line(rest) else {
+                        return Ok(());
+                    };
+                    if !line.is_empty() {
+                        return Err(Error::Network(
+                            "invalid chunk: missing line break after chunk data".to_string(),
+                        ));
+                    }
+                    self.consumed += next;
+                    self.state = ChunkState::Size;
+                }
+                ChunkState::Trailer => {
+                    let Some((line, next)) = next_line(rest) else {
+                        return Ok(());
+                    };
+                    // トレーラーのフィールドは使わずに読み飛ばし、空行でボディを終える。
+                    self.consumed += next;
+                    if line.is_empty() {
+                        self.state = ChunkState::Done;
+                    }
+                }
+                ChunkState::Done => return Ok(()),
+            }
+        }
+    }
+
+    /// 復元したボディのバイト列を返す。
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// 最後のチャンクとトレーラーの後の空行まで受信したかどうか。
+    fn is_done(&self) -> bool {
+        self.state == ChunkState::Done
+    }
+
+    /// ボディの終端までに使った、受信したボディのバイト数を返す。
+    fn consumed(&self) -> usize {
+        self.consumed
+    }
+}
+
+/// バイト列の最初の行 (行末の "\r\n" または "\n" を除く) と、次の行の開始位置を返す。
+/// 改行をまだ受信していない場合は None を返す。
+fn next_line(bytes: &[u8]) -> Option<(&[u8], usize)> {
+    let end = bytes.iter().position(|b| *b == b'\n')?;
+    let line = &bytes[..end];
+    Some((line.strip_suffix(b"\r").unwrap_or(line), end + 1))
+}
+
+/// ステータスコードがボディを持たないレスポンスを表すかどうか。
+/// https://httpwg.org/specs/rfc9110.html#field.content-length
+fn has_no_body(status_code: u32) -> bool {
+    (100..200).contains(&status_code) || status_code == 204 || status_code == 304
+}
+
+/// 分割して届くレスポンスのバイト列を受け取り、ボディを届いた分から順にデコードする構造体。
+/// ネットワークから読み込んだバイト列を push で渡すと、新しくデコードできたボディの文字列を返す。
+pub struct ResponseReader {
+    received: Vec<u8>,
+    // ヘッダ部を受信し終えた後の、ボディを除いたレスポンスとボディの開始位置
+    head: Option<(HttpResponse, usize)>,
+    encoding: Encoding,
+    // デコード済みのボディのバイト数
+    decoded: usize,
+    // HEAD リクエストに対するレスポンスのように、ボディを読み込まない場合に true となる。
+    bodiless: bool,
+    // ボディがチャンク形式の場合に、受信したチャンクを結合したボディ
+    chunks: Option<ChunkedBody>,
+}
+
+impl ResponseReader {
+    pub fn new() -> Self {
+        Self {
+            received: Vec::new(),
+            head: None,
+            encoding: Encoding::Utf8,
+            decoded: 0,
+            bodiless: false,
+            chunks: None,
+        }
+    }
+
+    /// method のリクエストに対するレスポンスを読み込む ResponseReader を作成する。
+    /// HEAD リクエストのレスポンスはボディを持たないため、ヘッダ部を受信し終えた時点で完了とする。
+    /// https://httpwg.org/specs/rfc9110.html#HEAD
+    pub fn for_method(method: &str) -> Self {
+        let mut reader = Self::new();
+        reader.bodiless = method.eq_ignore_ascii_case("HEAD");
+        reader
+    }
+
+    /// 受信したバイト列を追加し、新しくデコードできたボディの文字列を返す。
+    /// ヘッダ部を受信し終えていない場合や、新しい文字がない場合は None を返す。
+    pub fn push(&mut self, bytes: &[u8]) -> Option<String> {
+        self.received.extend_from_slice(bytes);
+
+        if self.head.is_none() {
+            let start = self
+                .received
+                .iter()
+                .position(|b| !b.is_ascii_whitespace())?;
+            let (head_end, body_start) = find_head_end(&self.received[start..])?;
+            let head = String::from_utf8_lossy(&self.received[start..start + head_end]);
+            let head = HttpResponse::new(format!("{}\n\n", head)).ok()?;
+            self.encoding = head.body_encoding();
+            if head.has_chunked_body() {
+                self.chunks = Some(ChunkedBody::new());
+            }
+            self.head = Some((head, start + body_start));
+        }
+
+        if self.bodiless {
+            return None;
+        }
+        let body_start = self.head.as_ref()?.1;
+        if let Some(chunks) = &mut self.chunks {
+            // 不正なチャンクの後は読み進めず、finish でエラーとする。
+            chunks.feed(&self.received[body_start..]).ok()?;
+        }
+        let pending = &self.body_bytes()[self.decoded..];
+        let length = self.encoding.complete_prefix_len(pending);
+        if length == 0 {
+            return None;
+        }
+        let text = self.encoding.decode(&pending[..length]);
+        self.decoded += length;
+        Some(text)
+    }
+
+    /// これまでに受信したボディのバイト列を返す。ヘッダ部を受信し終えていない場合は空とする。
+    /// チャンク形式の場合は、受信したチャンクを結合したバイト列を返す。
+    pub fn body_bytes(&self) -> &[u8] {
+        match (&self.head, &self.chunks) {
+            (Some(_), Some(chunks)) => chunks.data(),
+            (Some((_, body_start)), None) => &self.received[*body_start..],
+            (None, _) => &[],
+        }
+    }
+
+    /// 途中で切れたレスポンスの続きを、Range ヘッダを付けたリクエストのレスポンスで補う。
+    /// partial が 206 で、Content-Range の開始位置が受信済みのボディの長さと一致する場合、そのボディを続けて結合する。
+    /// サーバが範囲リクエストを無視して 200 で全体を返した場合は、partial のレスポンスで置き換える。
+    /// https://httpwg.org/specs/rfc9110.html#status.206
+    pub fn append_range(&mut self, partial: ResponseReader) -> Result<(), Error> {
+        // チャンク形式のボディは受信したバイト列とボディの位置が一致しないため、続きを結合できない。
+        if self.chunks.is_some() {
+            return Err(Error::Network(
+                "failed to resume: chunked response".to_string(),
+            ));
+        }
+        let head = match partial.head() {
+            Some(head) => head,
+            None => {
+                return Err(Error::Network(
+                    "failed to resume: invalid partial response".to_string(),
+                ))
+            }
+        };
+        match head.status_code() {
+            200 => {
+                *self = partial;
+                Ok(())
+            }
+            206 => match head.content_range() {
+                Some((first, _, _)) if first == self.body_bytes().len() => {
+                    self.received.extend_from_slice(partial.body_bytes());
+                    Ok(())
+                }
+                _ => Err(Error::Network(format!(
+                    "failed to resume: unexpected Content-Range {:?}",
+                    head.header_value("Content-Range")
+                ))),
+            },
+            status_code => Err(Error::Network(format!(
+                "failed to resume: unexpected status code {}",
+                status_code
+            ))),
+        }
+    }
+
+    /// ヘッダ部を受信し終えている場合、ボディを除いたレスポンスを返す。
+    pub fn head(&self) -> Option<HttpResponse> {
+        self.head.as_ref().map(|(head, _)| head.clone())
+    }
+
+    /// Content-Length で宣言された長さ、またはチャンク形式のボディの終端まで受信したかどうか。
+    pub fn is_complete(&self) -> bool {
+        if self.bodiless {
+            return self.head.is_some();
+        }
+        if let Some(chunks) = &self.chunks {
+            return chunks.is_done();
+        }
+        match expected_response_length(&self.received) {
+            Some(length) => self.received.len() >= length,
+            None => false,
+        }
+    }
+
+    /// 受信したすべてのバイト列から HttpResponse 構造体を構築する。
+    pub fn finish(self) -> Result<HttpResponse, Error> {
+        if self.bodiless {
+            let mut response = match self.head {
+                Some((head, _)) => head,
+                None => HttpResponse::from_bytes(&self.received)?,
+            };
+            response.body = String::new();
+            response.body_length = 0;
+            response.bodiless = true;
+            return Ok(response);
+        }
+        HttpResponse::from_bytes(&self.received)
+    }
+
+    /// finish と同じく HttpResponse 構造体を構築し、ボディが途中で切れている場合はエラーとする。
+    /// ボディを受信しながら渡していて再送できない場合に、ページの一部だけを表示しないために使う。
+    pub fn finish_complete(self) -> Result<HttpResponse, Error> {
+        let response = self.finish()?;
+        if response.is_truncated() {
+            return Err(Error::Network(match response.content_length() {
+                Some(content_length) => format!(
+                    "response truncated: received {} of {} bytes",
+                    response.body_length, content_length
+                ),
+                None => "response truncated: missing the last chunk".to_string(),
+            }));
+        }
+        Ok(response)
+    }
+}
+
+impl Default for ResponseReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 受信途中のレスポンスのバイト列から、レスポンス全体の長さ (ヘッダ部とボディの合計のバイト数) を返す。
+/// ボディの長さは Content-Length ヘッダの値とする。
+/// チャンク形式の場合は、最後のチャンクとトレーラーの後の空行までをボディとする。
+/// ヘッダ部をまだ受信し終えていない場合や、Content-Length ヘッダが存在しない場合、
+/// チャンク形式のボディの終端を受信していない場合は None を返す。
+pub fn expected_response_length(received: &[u8]) -> Option<usize> {
+    let (head_end, body_start) = find_head_end(received)?;
+    let head = core::str::from_utf8(&received[..head_end]).ok()?;
+    let response = HttpResponse::new(format!("{}\n\n", head)).ok()?;
+    if has_no_body(response.status_code()) {
+        return Some(body_start);
+    }
+    if response.is_chunked() {
+        let mut chunks = ChunkedBody::new();
+        chunks.feed(&received[body_start..]).ok()?;
+        return chunks.is_done().then(|| body_start + chunks.consumed());
+    }
+    Some(body_start + response.content_length()?)
+}
+
+/// ステータスラインを解析し、HTTP バージョン、ステータスコード、理由フレーズを返す。
+/// 理由フレーズは省略でき、スペースを含むこともある (例: "HTTP/1.0 404 Not Found")。
+/// https://www.rfc-editor.org/rfc/rfc9112#name-status-line
+fn parse_status_line(status_line: &str) -> Result<(String, u32, String), Error> {
+    let mut parts = status_line.trim().splitn(3, ' ');
+
+    let version = parts.next().unwrap_or("");
+    if !version.starts_with("HTTP/") {
+        return Err(Error::Network(format!(
+            "invalid http version in status line: {}",
+            status_line
+        )));
+    }
+
+    let code = parts.next().unwrap_or("").trim();
+    let status_code = match code.parse::<u32>() {
+        Ok(c) if code.len() == 3 => c,
+        _ => {
+            return Err(Error::Network(format!(
+                "invalid status code in status line: {}",
+                status_line
+            )))
+        }
+    };
+
+    let reason = parts.next().unwrap_or("").trim();
+    Ok((version.to_string(), status_code, reason.to_string()))
+}
+
+/// リクエストの User-Agent ヘッダのデフォルトの値
+pub static USER_AGENT: &str = concat!("saba/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP リクエストの構造体
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    method: String,
+    host: String,
+    port: u16,
+    path: String,
+    headers: Vec<Header>,
+    body: String,
+    // true の場合、リクエストラインに絶対形式のリクエストターゲット ("http://host/path") を使う。
+    // HTTP プロキシを経由してリクエストを送信するときに使う。
+    absolute_form: bool,
+    // true の場合、TLS で暗号化した接続でリクエストを送信する (https)。
+    secure: bool,
+}
+
+impl HttpRequest {
+    pub fn new(method: &str, host: String, port: u16, path: String) -> Self {
+        Self {
+            method: method.to_string(),
+            host,
+            port,
+            path,
+            headers: Vec::new(),
+            body: String::new(),
+            absolute_form: false,
+            secure: false,
+        }
+    }
+
+    /// リクエストラインに絶対形式のリクエストターゲットを使うかどうかを設定する。
+    /// https://datatracker.ietf.org/doc/html/rfc9112#section-3.2.2
+    pub fn set_absolute_form(&mut self, absolute_form: bool) -> &mut Self {
+        self.absolute_form = absolute_form;
+        self
+    }
+
+    /// リクエストを TLS で暗号化した接続で送信するかどうかを設定する。
+    pub fn set_secure(&mut self, secure: bool) -> &mut Self {
+        self.secure = secure;
+        self
+    }
+
+    /// リクエストを TLS で暗号化した接続で送信する場合に true を返す。
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// リクエストラインのリクエストターゲットを返す。
+    /// 通常はパス ("/index.html") とし、絶対形式の場合はスキームとホストを含む URL とする。
+    /// https://datatracker.ietf.org/doc/html/rfc9112#section-3.2
+    pub fn request_target(&self) -> String {
+        if !self.absolute_form {
+            return format!("/{}", self.path);
+        }
+        let (scheme, default_port) = if self.secure {
+            ("https", 443)
+        } else {
+            ("http", 80)
+        };
+        if self.port == default_port {
+            format!("{}://{}/{}", scheme, self.host, self.path)
+        } else {
+            format!("{}://{}:{}/{}", scheme, self.host, self.port, self.path)
+        }
+    }
+
+    /// ヘッダを追加する。Host などのデフォルトのヘッダと同じ名前の場合は、デフォルトの値を置き換える。
+    /// ヘッダ名またはヘッダの値が不正な場合 (validate_header を参照) はエラーとし、ヘッダを追加しない。
+    pub fn add_header(&mut self, name: &str, value: &str) -> Result<&mut Self, Error> {
+        validate_header(name, value)?;
+        self.headers
+            .push(Header::new(name.to_string(), value.to_string()));
+        Ok(self)
+    }
+
+    /// name のヘッダの値を value にする。同じ名前のヘッダがすでにある場合は置き換える。
+    /// ヘッダ名の大文字と小文字は区別しない。
+    pub fn set_header(&mut self, name: &str, value: &str) -> Result<&mut Self, Error> {
+        validate_header(name, value)?;
+        self.headers.retain(|h| !h.name.eq_ignore_ascii_case(name));
+        self.add_header(name, value)
+    }
+
+    /// name と一致するヘッダの値を返す。ヘッダ名の大文字と小文字は区別しない。
+    pub fn header_value(&self, name: &str) -> Option<String> {
+        self.headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.clone())
+    }
+
+    /// リクエストのボディを設定する。
+    pub fn set_body(&mut self, body: String) -> &mut Self {
+        self.body = body;
+        self
+    }
+
+    pub fn method(&self) -> String {
+        self.method.clone()
+    }
+
+    pub fn host(&self) -> String {
+        self.host.clone()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    pub fn headers(&self) -> Vec<Header> {
+        self.headers.clone()
+    }
+
+    pub fn body(&self) -> String {
+        self.body.clone()
+    }
+
+    /// ソケットに書き込む HTTP リクエストの文字列を構築する。
+    /// ボディを持つ場合は Content-Length を付け、Content-Type が指定されていなければ
+    /// application/x-www-form-urlencoded とする。
+    /// https://datatracker.ietf.org/doc/html/rfc9112#section-3
+    pub fn serialize(&self) -> String {
+        // メソッド名、パス名、HTTP バージョンをホワイトスペースで結合する。
+        let mut request = format!("{} {} HTTP/1.1\r\n", self.method, self.request_target());
+
+        let has_header = |name: &str| {
+            self.headers
+                .iter()
+                .any(|h| h.name.eq_ignore_ascii_case(name))
+        };
+        let mut headers: Vec<(&str, String)> = Vec::new();
+        if !has_header("Host") {
+            headers.push(("Host", self.host.clone()));
+        }
+        if !has_header("User-Agent") {
+            headers.push(("User-Agent", USER_AGENT.to_string()));
+        }
+        if !has_header("Accept") {
+            headers.push(("Accept", "text/html".to_string()));
+        }
+        if !has_header("Connection") {
+            headers.push(("Connection", "close".to_string()));
+        }
+        for h in &self.headers {
+            if !h.name.eq_ignore_ascii_case("Content-Length") {
+                headers.push((&h.name, h.value.clone()));
+            }
+        }
+        if !self.body.is_empty() || self.method == "POST" {
+            if !has_header("Content-Type") {
+                headers.push((
+                    "Content-Type",
+                    "application/x-www-form-urlencoded".to_string(),
+                ));
+            }
+            headers.push(("Content-Length", self.body.len().to_string()));
+        }
+
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        request.push_str(&self.body);
+        request
+    }
+}
+
+/// リクエストに付けるヘッダ名と値が、ヘッダとしてそのまま送信できるかどうかを確認する。
+/// - ヘッダ名は空でなく、トークンに使える文字のみからなる。
+/// - ヘッダの値は CR、LF、NUL を含まない。
+///
+/// 改行を含む値を送信すると、ヘッダやリクエストを外から追加できてしまうため、エラーとする。
+/// https://datatracker.ietf.org/doc/html/rfc9110#section-5.1
+/// https://datatracker.ietf.org/doc/html/rfc9110#section-5.5
+pub fn validate_header(name: &str, value: &str) -> Result<(), Error> {
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if name.is_empty() || !name.chars().all(is_token_char) {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid header name: {:?}",
+            name
+        )));
+    }
+    if value.contains(['\r', '\n', '\0']) {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid header value for {}: {:?}",
+            name, value
+        )));
+    }
+    Ok(())
 }
 
 /// ヘッダの構造体
@@ -101,6 +917,14 @@ impl Header {
     pub fn new(name: String, value: String) -> Self {
         Self { name, value }
     }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
 }
 
 #[cfg(test)]
@@ -160,4 +984,420 @@ mod tests {
         let raw = "HTTP/1.1 200 OK".to_string();
         assert!(HttpResponse::new(raw).is_err());
     }
+
+    #[test]
+    /// HTTP/1.0 のステータスラインと、スペースを含む理由フレーズのテスト
+    fn test_http10_status_line() {
+        let raw = "HTTP/1.0 404 Not Found\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("faild to parse http response");
+        assert_eq!(res.version(), "HTTP/1.0");
+        assert_eq!(res.status_code(), 404);
+        assert_eq!(res.reason(), "Not Found");
+    }
+
+    #[test]
+    /// 理由フレーズが省略された HTTP レスポンスのテスト
+    fn test_missing_reason() {
+        let raw = "HTTP/1.1 204\r\n\r\n".to_string();
+        let res = HttpResponse::new(raw).expect("faild to parse http response");
+        assert_eq!(res.status_code(), 204);
+        assert_eq!(res.reason(), "");
+    }
+
+    #[test]
+    /// CRLF の行末を持つ HTTP レスポンスのテスト
+    /// ヘッダ名の大文字と小文字を区別せず、ボディの改行はそのまま保持する。
+    fn test_crlf_and_case_insensitive_headers() {
+        let raw = "HTTP/1.1 200 OK\r\ncontent-type:   text/html\r\nX-Long: a\r\n  b\r\n\r\nline1\r\nline2"
+            .to_string();
+        let res = HttpResponse::new(raw).expect("faild to parse http response");
+        assert_eq!(
+            res.header_value("Content-Type"),
+            Ok("text/html".to_string())
+        );
+        assert_eq!(res.header_value("x-long"), Ok("a b".to_string()));
+        assert_eq!(res.body(), "line1\r\nline2".to_string());
+    }
+
+    #[test]
+    /// ステータスラインが不正な場合のテスト
+    fn test_invalid_status_line() {
+        assert!(HttpResponse::new("HTTP/1.1 OK\n\n".to_string()).is_err());
+        assert!(HttpResponse::new("<html>\n\n".to_string()).is_err());
+    }
+
+    #[test]
+    /// 同じ名前のヘッダが複数現れる場合のテスト
+    fn test_multiple_headers() {
+        let raw = "HTTP/1.1 200 OK\nSet-Cookie: a=1\nDate: xx\nset-cookie: b=2\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("faild to parse http response");
+        assert_eq!(
+            res.header_values("Set-Cookie"),
+            ["a=1".to_string(), "b=2".to_string()].to_vec()
+        );
+        assert!(res.header_values("Cache-Control").is_empty());
+
+        let names: Vec<String> = res.headers().iter().map(|h| h.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "Set-Cookie".to_string(),
+                "Date".to_string(),
+                "set-cookie".to_string()
+            ]
+            .to_vec()
+        );
+    }
+
+    #[test]
+    /// Content-Type と Content-Length の解析のテスト
+    fn test_content_type_and_length() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: Text/HTML; Charset=\"utf-8\"\nContent-Length: 12, 12\n\n"
+            .to_string();
+        let res = HttpResponse::new(raw).expect("faild to parse http response");
+        let content_type = res.content_type().expect("failed to parse content type");
+        assert_eq!(content_type.mime_type(), "text/html");
+        assert_eq!(content_type.charset(), Some("utf-8".to_string()));
+        assert_eq!(res.content_length(), Some(12));
+
+        let raw = "HTTP/1.1 200 OK\nContent-Type: html\nContent-Length: 1, 2\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("faild to parse http response");
+        assert_eq!(res.content_type(), None);
+        assert_eq!(res.content_length(), None);
+    }
+
+    #[test]
+    /// Content-Length によるレスポンス全体の長さの計算と、途中で切れたレスポンスの検出のテスト
+    fn test_content_length_enforcement() {
+        let head = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n";
+        assert_eq!(
+            None,
+            expected_response_length(b"HTTP/1.1 200 OK\r\nContent-Le")
+        );
+        assert_eq!(
+            Some(head.len() + 5),
+            expected_response_length(format!("{}ab", head).as_bytes())
+        );
+        assert_eq!(
+            None,
+            expected_response_length(b"HTTP/1.1 200 OK\nDate: xx\n\nbody")
+        );
+        let not_modified = "HTTP/1.1 304 Not Modified\nContent-Length: 9\n\n";
+        assert_eq!(
+            Some(not_modified.len()),
+            expected_response_length(not_modified.as_bytes())
+        );
+
+        let truncated = HttpResponse::new(format!("{}abc", head)).unwrap();
+        assert!(truncated.is_truncated());
+        let complete = HttpResponse::new(format!("{}abcde", head)).unwrap();
+        assert!(!complete.is_truncated());
+        let unknown = HttpResponse::new("HTTP/1.1 200 OK\n\nab".to_string()).unwrap();
+        assert!(!unknown.is_truncated());
+    }
+
+    #[test]
+    /// GET リクエストの文字列のテスト。
+    fn test_serialize_get_request() {
+        let request = HttpRequest::new(
+            "GET",
+            "example.com".to_string(),
+            80,
+            "index.html".to_string(),
+        );
+        assert_eq!(
+            "GET /index.html HTTP/1.1\r\nHost: example.com\r\nUser-Agent: saba/0.1.0\r\nAccept: text/html\r\nConnection: close\r\n\r\n",
+            request.serialize()
+        );
+    }
+
+    #[test]
+    /// ボディを持つ POST リクエストの文字列のテスト。
+    fn test_serialize_post_request() {
+        let mut request =
+            HttpRequest::new("POST", "example.com".to_string(), 8000, "form".to_string());
+        request.set_body("name=saba&lang=ja".to_string());
+        assert_eq!(
+            "POST /form HTTP/1.1\r\nHost: example.com\r\nUser-Agent: saba/0.1.0\r\nAccept: text/html\r\nConnection: close\r\n\
+             Content-Type: application/x-www-form-urlencoded\r\nContent-Length: 17\r\n\r\n\
+             name=saba&lang=ja",
+            request.serialize()
+        );
+
+        let mut request =
+            HttpRequest::new("POST", "example.com".to_string(), 80, "api".to_string());
+        request
+            .add_header("content-type", "application/json")
+            .unwrap()
+            .add_header("Content-Length", "999")
+            .unwrap()
+            .set_body("{}".to_string());
+        assert_eq!(
+            "POST /api HTTP/1.1\r\nHost: example.com\r\nUser-Agent: saba/0.1.0\r\nAccept: text/html\r\nConnection: close\r\n\
+             content-type: application/json\r\nContent-Length: 2\r\n\r\n{}",
+            request.serialize()
+        );
+    }
+
+    #[test]
+    /// 呼び出し元がヘッダを設定できることのテスト。
+    fn test_request_headers() {
+        let mut request = HttpRequest::new("GET", "example.com".to_string(), 80, "".to_string());
+        request
+            .set_header("User-Agent", "test-agent")
+            .unwrap()
+            .set_header("Accept-Language", "ja")
+            .unwrap()
+            .set_header("Referer", "http://example.com/prev")
+            .unwrap()
+            .set_header("accept-language", "en")
+            .unwrap();
+        assert_eq!(
+            Some("en".to_string()),
+            request.header_value("Accept-Language")
+        );
+        assert_eq!(None, request.header_value("Cookie"));
+        assert_eq!(
+            "GET / HTTP/1.1\r\nHost: example.com\r\nAccept: text/html\r\nConnection: close\r\n\
+             User-Agent: test-agent\r\nReferer: http://example.com/prev\r\naccept-language: en\r\n\r\n",
+            request.serialize()
+        );
+    }
+
+    #[test]
+    /// CR や LF を含むヘッダは、リクエストに追加せずにエラーとすることのテスト。
+    fn test_reject_invalid_headers() {
+        let mut request = HttpRequest::new("GET", "example.com".to_string(), 80, "".to_string());
+        assert!(request.set_header("X-Test", "a\r\nInjected: 1").is_err());
+        assert!(request.add_header("X-Test", "a\nb").is_err());
+        assert!(request.add_header("X-Test\r\nInjected", "1").is_err());
+        assert!(request.add_header("Bad Name", "1").is_err());
+        assert!(request.add_header("", "1").is_err());
+        assert!(request.headers().is_empty());
+        assert!(!request.serialize().contains("Injected"));
+
+        // 置き換え前のヘッダも残る。
+        request.set_header("X-Test", "ok").unwrap();
+        assert!(request.set_header("x-test", "bad\r\n").is_err());
+        assert_eq!(Some("ok".to_string()), request.header_value("X-Test"));
+    }
+
+    #[test]
+    /// Content-Type の charset に従ってボディをデコードするテスト。
+    fn test_from_bytes_with_charset() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=Shift_JIS\r\nContent-Length: 4\r\n\r\n"
+            .to_vec();
+        // "日本" の Shift_JIS
+        raw.extend_from_slice(&[0x93, 0xFA, 0x96, 0x7B]);
+        let res = HttpResponse::from_bytes(&raw).expect("failed to parse http response");
+        assert_eq!(res.body(), "日本");
+        assert!(!res.is_truncated());
+
+        let mut raw =
+            b"HTTP/1.1 200 OK\nContent-Type: text/html; charset=euc-jp\nContent-Length: 4\n\n"
+                .to_vec();
+        raw.extend_from_slice(&[0xC6, 0xFC]);
+        let res = HttpResponse::from_bytes(&raw).expect("failed to parse http response");
+        assert_eq!(res.body(), "日");
+        assert!(res.is_truncated());
+
+        // charset がない場合は UTF-8 とみなす。
+        let raw = "HTTP/1.1 200 OK\n\n<p>日本</p>".as_bytes();
+        let res = HttpResponse::from_bytes(raw).expect("failed to parse http response");
+        assert_eq!(res.body(), "<p>日本</p>");
+        assert_eq!(None, res.declared_encoding());
+    }
+
+    #[test]
+    /// デコードする前のボディを別のエンコーディングでデコードし直すテスト。
+    fn test_decode_body() {
+        let mut raw = b"HTTP/1.1 200 OK\nContent-Type: text/html\n\n".to_vec();
+        raw.extend_from_slice(&[0x93, 0xFA, 0x96, 0x7B]);
+        let res = HttpResponse::from_bytes(&raw).expect("failed to parse http response");
+        assert_eq!(None, res.declared_encoding());
+        assert_eq!("日本", res.decode_body(Encoding::ShiftJis));
+
+        let raw = b"HTTP/1.1 200 OK\nContent-Type: text/html; charset=EUC-JP\n\n";
+        let res = HttpResponse::from_bytes(raw).expect("failed to parse http response");
+        assert_eq!(Some(Encoding::EucJp), res.declared_encoding());
+    }
+
+    #[test]
+    /// 分割して届いたレスポンスを少しずつデコードするテスト。
+    fn test_response_reader() {
+        let mut raw =
+            b"HTTP/1.1 200 OK\nContent-Type: text/html; charset=Shift_JIS\nContent-Length: 6\n\n"
+                .to_vec();
+        // "<日本" の Shift_JIS
+        raw.extend_from_slice(&[b'<', 0x93, 0xFA, 0x96, 0x7B, b'>']);
+
+        let mut reader = ResponseReader::new();
+        assert_eq!(None, reader.push(&raw[..10]));
+        assert!(reader.head().is_none());
+
+        let body_start = raw.len() - 6;
+        // ヘッダ部と、ボディの "<" と "日" の1バイト目
+        assert_eq!(Some("<".to_string()), reader.push(&raw[10..body_start + 2]));
+        assert_eq!(200, reader.head().unwrap().status_code());
+        assert!(!reader.is_complete());
+        assert_eq!(
+            Some("日本>".to_string()),
+            reader.push(&raw[body_start + 2..])
+        );
+        assert!(reader.is_complete());
+
+        let res = reader.finish().expect("failed to parse http response");
+        assert_eq!(res.body(), "<日本>");
+    }
+
+    #[test]
+    /// 接続が途中で切れたレスポンスを finish_complete がエラーにするテスト。
+    fn test_response_reader_truncated() {
+        let raw = b"HTTP/1.1 200 OK\nContent-Length: 10\n\n0123456789";
+
+        let mut reader = ResponseReader::new();
+        reader.push(&raw[..raw.len() - 4]);
+        assert!(!reader.is_complete());
+        assert!(matches!(reader.finish_complete(), Err(Error::Network(_))));
+
+        let mut reader = ResponseReader::new();
+        reader.push(raw);
+        let res = reader
+            .finish_complete()
+            .expect("failed to parse http response");
+        assert_eq!("0123456789", res.body());
+    }
+
+    #[test]
+    /// チャンク形式のボディから、チャンクの長さの行やトレーラーを除いてボディを復元するテスト。
+    fn test_chunked_response() {
+        let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n";
+        let raw = format!(
+            "{}6;name=value\r\n<p>日\r\n9\r\n本</p>\r\n\r\n0\r\nExpires: never\r\n\r\n",
+            head
+        );
+
+        let res = HttpResponse::from_bytes(raw.as_bytes()).expect("failed to parse http response");
+        assert!(res.is_chunked());
+        assert_eq!("<p>日本</p>\r\n", res.body());
+        assert_eq!(None, res.content_length());
+        assert!(!res.is_truncated());
+        assert_eq!(Some(raw.len()), expected_response_length(raw.as_bytes()));
+
+        // 1バイトずつ受信しても、チャンクの区切りはボディに現れない。
+        let mut reader = ResponseReader::new();
+        let mut body = String::new();
+        for (i, b) in raw.as_bytes().iter().enumerate() {
+            assert!(!reader.is_complete(), "completed at {}", i);
+            if let Some(chunk) = reader.push(&[*b]) {
+                body.push_str(&chunk);
+            }
+        }
+        assert!(reader.is_complete());
+        assert_eq!("<p>日本</p>\r\n", body);
+        assert_eq!(
+            "<p>日本</p>\r\n",
+            reader
+                .finish_complete()
+                .expect("failed to parse http response")
+                .body()
+        );
+
+        // 最後のチャンクを受信できなかった場合は、途中で切れたレスポンスとする。
+        let truncated = format!("{}5\r\nhello\r\n", head);
+        let res = HttpResponse::from_bytes(truncated.as_bytes()).unwrap();
+        assert!(res.is_truncated());
+        assert_eq!(None, expected_response_length(truncated.as_bytes()));
+        let mut reader = ResponseReader::new();
+        assert_eq!(Some("hello".to_string()), reader.push(truncated.as_bytes()));
+        assert!(!reader.is_complete());
+        assert!(matches!(reader.finish_complete(), Err(Error::Network(_))));
+
+        // チャンクの長さが不正な場合はエラーとする。
+        let invalid = format!("{}+5\r\nhello\r\n0\r\n\r\n", head);
+        assert!(HttpResponse::from_bytes(invalid.as_bytes()).is_err());
+    }
+
+    #[test]
+    /// プロキシに送信する絶対形式のリクエストのテスト。
+    fn test_absolute_form_request() {
+        let mut request =
+            HttpRequest::new("GET", "example.com".to_string(), 80, "a?b=1".to_string());
+        assert_eq!("/a?b=1", request.request_target());
+        request.set_absolute_form(true);
+        assert_eq!("http://example.com/a?b=1", request.request_target());
+        assert!(request
+            .serialize()
+            .starts_with("GET http://example.com/a?b=1 HTTP/1.1\r\nHost: example.com\r\n"));
+
+        let mut request = HttpRequest::new("GET", "example.com".to_string(), 8000, "".to_string());
+        request.set_absolute_form(true);
+        assert_eq!("http://example.com:8000/", request.request_target());
+
+        let mut request = HttpRequest::new("GET", "example.com".to_string(), 443, "".to_string());
+        request.set_absolute_form(true).set_secure(true);
+        assert_eq!("https://example.com/", request.request_target());
+    }
+
+    #[test]
+    /// HEAD リクエストのレスポンスはボディを読み込まないことのテスト。
+    fn test_response_reader_for_head() {
+        let raw = b"HTTP/1.1 200 OK\nContent-Type: text/html\nContent-Length: 1024\n\n";
+        let mut reader = ResponseReader::for_method("HEAD");
+        assert_eq!(None, reader.push(raw));
+        assert!(reader.is_complete());
+
+        let res = reader.finish().expect("failed to parse http response");
+        assert_eq!(res.body(), "");
+        assert_eq!(res.content_length(), Some(1024));
+        assert!(!res.is_truncated());
+
+        // GET の場合はボディを待つ。
+        let mut reader = ResponseReader::for_method("GET");
+        reader.push(raw);
+        assert!(!reader.is_complete());
+    }
+
+    #[test]
+    /// Accept-Ranges と Content-Range の解析のテスト。
+    fn test_ranges() {
+        let res = HttpResponse::new(
+            "HTTP/1.1 206 Partial Content\nAccept-Ranges: bytes\nContent-Range: bytes 100-199/1000\n\n"
+                .to_string(),
+        )
+        .unwrap();
+        assert!(res.accepts_ranges());
+        assert_eq!(Some((100, 199, Some(1000))), res.content_range());
+
+        let res = HttpResponse::new(
+            "HTTP/1.1 206 Partial Content\nAccept-Ranges: none\nContent-Range: bytes 0-9/*\n\n"
+                .to_string(),
+        )
+        .unwrap();
+        assert!(!res.accepts_ranges());
+        assert_eq!(Some((0, 9, None)), res.content_range());
+    }
+
+    #[test]
+    /// 途中で切れたレスポンスを範囲リクエストのレスポンスで補うテスト。
+    fn test_append_range() {
+        let mut reader = ResponseReader::new();
+        reader.push(b"HTTP/1.1 200 OK\nAccept-Ranges: bytes\nContent-Length: 10\n\n01234");
+        assert!(!reader.is_complete());
+
+        // 開始位置が一致しない場合はエラーとする。
+        let mut wrong = ResponseReader::new();
+        wrong.push(b"HTTP/1.1 206 Partial Content\nContent-Range: bytes 3-9/10\nContent-Length: 7\n\n3456789");
+        assert!(reader.append_range(wrong).is_err());
+
+        let mut partial = ResponseReader::new();
+        partial.push(b"HTTP/1.1 206 Partial Content\nContent-Range: bytes 5-9/10\nContent-Length: 5\n\n56789");
+        reader.append_range(partial).unwrap();
+        assert!(reader.is_complete());
+
+        let res = reader.finish().unwrap();
+        assert_eq!(200, res.status_code());
+        assert_eq!("0123456789", res.body());
+        assert!(!res.is_truncated());
+    }
 }
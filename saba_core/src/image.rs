@@ -0,0 +1,134 @@
+//! 画像のバイト列から、画像の内在的な大きさ (intrinsic dimensions) を読み取る。
+//! 画素のデコードは行わず、各形式のヘッダに書かれている横幅と高さだけを取り出す。
+//! https://html.spec.whatwg.org/multipage/images.html#img-available
+
+use crate::renderer::layout::layout_object::LayoutSize;
+
+/// 画像のバイト列から横幅と高さを返す。
+/// PNG、GIF、JPEG、BMP に対応し、対応していない形式やヘッダが壊れている場合は None を返す。
+pub fn intrinsic_size(bytes: &[u8]) -> Option<LayoutSize> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_size(bytes)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        gif_size(bytes)
+    } else if bytes.starts_with(&[0xFF, 0xD8]) {
+        jpeg_size(bytes)
+    } else if bytes.starts_with(b"BM") {
+        bmp_size(bytes)
+    } else {
+        None
+    }
+}
+
+/// PNG の大きさは、シグネチャの直後にある IHDR チャンクに 4 バイトのビッグエンディアンで書かれている。
+/// https://www.w3.org/TR/png/#11IHDR
+fn png_size(bytes: &[u8]) -> Option<LayoutSize> {
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+    Some(LayoutSize::new(width as i64, height as i64))
+}
+
+/// GIF の大きさは、ヘッダの直後にある論理画面記述子に 2 バイトのリトルエンディアンで書かれている。
+/// https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+fn gif_size(bytes: &[u8]) -> Option<LayoutSize> {
+    let width = u16::from_le_bytes(bytes.get(6..8)?.try_into().ok()?);
+    let height = u16::from_le_bytes(bytes.get(8..10)?.try_into().ok()?);
+    Some(LayoutSize::new(width as i64, height as i64))
+}
+
+/// JPEG の大きさは、フレームの始まりを表す SOF セグメントに 2 バイトのビッグエンディアンで書かれている。
+/// SOF セグメントが見つかるまで、各セグメントの長さを読んで読み飛ばす。
+/// https://www.w3.org/Graphics/JPEG/itu-t81.pdf
+fn jpeg_size(bytes: &[u8]) -> Option<LayoutSize> {
+    let mut i = 2;
+    loop {
+        if *bytes.get(i)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(i + 1)?;
+        match marker {
+            // マーカーの前に置かれる詰め物のバイト
+            0xFF => {
+                i += 1;
+                continue;
+            }
+            // 長さを持たないマーカー (RSTn、SOI、TEM)
+            0x01 | 0xD0..=0xD8 => {
+                i += 2;
+                continue;
+            }
+            // 画像データの終わり (EOI)
+            0xD9 => return None,
+            _ => {}
+        }
+
+        let length = u16::from_be_bytes(bytes.get(i + 2..i + 4)?.try_into().ok()?) as usize;
+        // SOF0 から SOF15 のうち、DHT (C4)、JPG (C8)、DAC (CC) を除いたものがフレームの始まりを表す。
+        if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let height = u16::from_be_bytes(bytes.get(i + 5..i + 7)?.try_into().ok()?);
+            let width = u16::from_be_bytes(bytes.get(i + 7..i + 9)?.try_into().ok()?);
+            return Some(LayoutSize::new(width as i64, height as i64));
+        }
+        i += 2 + length;
+    }
+}
+
+/// BMP の大きさは、ファイルヘッダの直後にある情報ヘッダに 4 バイトのリトルエンディアンで書かれている。
+/// 高さが負の場合は上から下に画素が並んでいることを表すため、絶対値を使う。
+fn bmp_size(bytes: &[u8]) -> Option<LayoutSize> {
+    let width = i32::from_le_bytes(bytes.get(18..22)?.try_into().ok()?);
+    let height = i32::from_le_bytes(bytes.get(22..26)?.try_into().ok()?);
+    Some(LayoutSize::new((width as i64).abs(), (height as i64).abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_png() {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&[0, 0, 1, 0x2C, 0, 0, 0, 0x64]);
+        assert_eq!(Some(LayoutSize::new(300, 100)), intrinsic_size(&bytes));
+
+        // IHDR チャンクが途中で切れている場合
+        assert_eq!(None, intrinsic_size(&bytes[..20]));
+    }
+
+    #[test]
+    fn test_gif() {
+        let bytes = b"GIF89a\x20\x00\x10\x00".to_vec();
+        assert_eq!(Some(LayoutSize::new(32, 16)), intrinsic_size(&bytes));
+    }
+
+    #[test]
+    fn test_jpeg() {
+        // SOI、APP0 セグメント (長さ 4)、SOF0 セグメントの順に並べる。
+        let mut bytes: Vec<u8> = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00];
+        bytes.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x30, 0x00, 0x40]);
+        assert_eq!(Some(LayoutSize::new(64, 48)), intrinsic_size(&bytes));
+
+        // SOF セグメントより前に画像データが終わる場合
+        assert_eq!(None, intrinsic_size(&[0xFF, 0xD8, 0xFF, 0xD9]));
+    }
+
+    #[test]
+    fn test_bmp() {
+        let mut bytes = b"BM".to_vec();
+        bytes.resize(18, 0);
+        bytes.extend_from_slice(&10i32.to_le_bytes());
+        bytes.extend_from_slice(&(-20i32).to_le_bytes());
+        assert_eq!(Some(LayoutSize::new(10, 20)), intrinsic_size(&bytes));
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        assert_eq!(None, intrinsic_size(b"<svg></svg>"));
+        assert_eq!(None, intrinsic_size(&[]));
+    }
+}
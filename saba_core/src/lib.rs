@@ -5,8 +5,13 @@ extern crate alloc;
 pub mod browser;
 pub mod constants;
 pub mod display_item;
+pub mod encoding;
 pub mod error;
 pub mod http;
+pub mod image;
+pub mod navigation;
 pub mod renderer;
+pub mod scheduler;
+pub mod tls;
 pub mod url;
 pub mod utils;
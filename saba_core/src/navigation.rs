@@ -0,0 +1,818 @@
+use crate::error::Error;
+use crate::http::HttpResponse;
+use crate::renderer::dom::api::escape_string;
+use crate::renderer::html::parse_error::ParseError;
+use crate::scheduler::FetchScheduler;
+use crate::url::form_urlencode;
+use crate::url::percent_decode;
+use crate::url::Url;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// アドレスバーに URL でない文字列が入力された場合に使う検索エンジンの URL。
+/// 入力された文字列をクエリパラメータとして末尾に付け加える。
+pub static SEARCH_URL: &str = "http://html.duckduckgo.com/html/?q=";
+
+/// たどるリダイレクトの最大の回数。
+/// https://fetch.spec.whatwg.org/#http-redirect-fetch
+pub static MAX_REDIRECTS: usize = 20;
+
+/// 認証情報を入力し直せる最大の回数。
+pub static MAX_AUTH_ATTEMPTS: usize = 3;
+
+/// 直前に表示していたページの HTML のパースエラーを一覧表示するデバッグ用のページの URL。
+/// ページの状態を参照する必要があるため、load_about_url ではなく UI が load_parse_errors_page で表示する。
+pub static PARSE_ERRORS_URL: &str = "about:parse-errors";
+
+/// URL にアクセスし、リダイレクトをたどった最終的な URL と HttpResponse 構造体を返す関数の型。
+/// 2つ目の引数は、サーバから認証を求められたときに認証情報を入力するための UI のフックである。
+/// 3つ目の引数は、ページとして表示するレスポンスのボディを受信した分から順に受け取る UI のフックである。
+/// data URL のようにボディを渡さなかったレスポンスは、戻り値のレスポンスからページを作成する。
+pub type UrlHandler =
+    fn(String, &mut CredentialsPrompt, &mut BodyChunkHandler) -> Result<(Url, HttpResponse), Error>;
+
+/// 受信途中のレスポンスのボディのうち、新しくデコードできた部分を受け取る関数の型。
+pub type BodyChunkHandler<'a> = dyn FnMut(&str) + 'a;
+
+/// スケジューラに登録されたサブリソースのリクエストをすべて送信し、結果をスケジューラに登録する関数の型。
+pub type SubresourceFetcher = fn(&mut FetchScheduler);
+
+/// 認証を求めてきた URL とレルムを受け取り、ユーザが入力した認証情報を返す関数の型。
+/// ユーザが入力を取り消した場合は None を返す。
+pub type CredentialsPrompt<'a> = dyn FnMut(&Url, &str) -> Option<Credentials> + 'a;
+
+/// ユーザ名とパスワードの組
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    pub fn new(username: String, password: String) -> Self {
+        Self { username, password }
+    }
+
+    pub fn username(&self) -> String {
+        self.username.clone()
+    }
+
+    pub fn password(&self) -> String {
+        self.password.clone()
+    }
+
+    /// Basic 認証の Authorization ヘッダの値を返す。
+    /// https://datatracker.ietf.org/doc/html/rfc7617#section-2
+    pub fn basic_authorization(&self) -> String {
+        let user_pass = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", encode_base64(user_pass.as_bytes()))
+    }
+}
+
+/// アドレスバーに入力された文字列を、ナビゲーションできる URL に正規化する。
+/// - スキームを持つ場合はそのまま返す。
+/// - `example.com` や `localhost:8000/index.html` のようなホスト名の場合は `http://` を補う。
+/// - それ以外の場合は、入力を検索クエリとして検索エンジンの URL を返す。
+pub fn normalize_input(input: &str) -> String {
+    let input = input.trim();
+
+    if has_scheme(input) {
+        return input.to_string();
+    }
+
+    if looks_like_host(input) {
+        return format!("http://{}", input);
+    }
+
+    format!("{}{}", SEARCH_URL, form_urlencode(input))
+}
+
+/// 文字列がスキームから始まるかどうか。
+/// https://url.spec.whatwg.org/#scheme-state
+fn has_scheme(input: &str) -> bool {
+    let scheme = match input.find(':') {
+        Some(index) => &input[..index],
+        None => return false,
+    };
+    let rest = &input[scheme.len() + 1..];
+
+    let mut chars = scheme.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => (),
+        _ => return false,
+    }
+    if !chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) {
+        return false;
+    }
+
+    // `localhost:8000` のように、":" の後ろがポート番号の場合はスキームではない。
+    let port: &str = rest.split('/').next().unwrap_or("");
+    port.is_empty() || !port.chars().all(|c| c.is_ascii_digit())
+}
+
+/// 文字列がホスト名 (と、それに続くポート番号やパス) のように見えるかどうか。
+fn looks_like_host(input: &str) -> bool {
+    if input.is_empty() || input.chars().any(|c| c.is_whitespace()) {
+        return false;
+    }
+
+    let authority = input.split(['/', '?', '#']).next().unwrap_or("");
+    let host = authority.split(':').next().unwrap_or("");
+    if host.is_empty() || host.starts_with('.') || host.ends_with('.') {
+        return false;
+    }
+    if !host
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    {
+        return false;
+    }
+
+    host.contains('.') || host.eq_ignore_ascii_case("localhost") || authority.contains(':')
+}
+
+/// url にリクエストを送信し、3xx のリダイレクトをたどった最終的な URL とレスポンスを返す。
+/// fetch は URL、メソッド、ボディを受け取り、1回のリクエストを送信する関数である。
+/// - 301/302/303/307/308 の Location ヘッダを、リクエストした URL を基準に解決してたどる。
+/// - 転送先のスキームが http と https 以外の場合はエラーとする。
+///   サーバのレスポンスによって data URL や about URL のページを開かせないためである。
+/// - 303、および POST に対する 301/302 の場合は、ボディのない GET に変更する。
+/// - MAX_REDIRECTS 回を超えてリダイレクトされた場合はエラーとする。
+///
+/// https://fetch.spec.whatwg.org/#http-redirect-fetch
+pub fn follow_redirects<F>(
+    url: Url,
+    method: &str,
+    body: String,
+    mut fetch: F,
+) -> Result<(Url, HttpResponse), Error>
+where
+    F: FnMut(&Url, &str, &str) -> Result<HttpResponse, Error>,
+{
+    let mut url = url;
+    let mut method = method.to_string();
+    let mut body = body;
+
+    for _ in 0..=MAX_REDIRECTS {
+        let response = fetch(&url, &method, &body)?;
+
+        let location = match redirect_location(&response) {
+            Some(location) => location,
+            None => return Ok((url, response)),
+        };
+        let status_code = response.status_code();
+
+        if (status_code == 303 && method != "GET" && method != "HEAD")
+            || ((status_code == 301 || status_code == 302) && method == "POST")
+        {
+            method = "GET".to_string();
+            body = String::new();
+        }
+        // 相対 URL の場合は、リクエストした URL を基準に解決する。
+        url = url.join(&location);
+        if !url.is_http() && !url.is_https() {
+            return Err(Error::Network(format!(
+                "redirect to a non-HTTP(S) URL is not allowed: {}",
+                location
+            )));
+        }
+    }
+
+    Err(Error::Network(format!(
+        "too many redirects: exceeded {} redirects",
+        MAX_REDIRECTS
+    )))
+}
+
+/// レスポンスがリダイレクトの場合、転送先を表す Location ヘッダの値を返す。
+/// 301/302/303/307/308 でも Location ヘッダがない場合は、リダイレクトせずにそのレスポンスを使う。
+fn redirect_location(response: &HttpResponse) -> Option<String> {
+    if !matches!(response.status_code(), 301 | 302 | 303 | 307 | 308) {
+        return None;
+    }
+    response.header_value("Location").ok()
+}
+
+/// follow_redirects と authenticate がリクエストを送信し直さず、そのままページとして表示するレスポンスかどうか。
+/// ボディを受信しながらページに渡す場合に、リダイレクトや認証を求めるレスポンスのボディを渡さないために使う。
+/// 認証の入力が取り消された場合の 401 のように、ここで false でも最終的に表示するレスポンスもある。
+pub fn is_final_response(response: &HttpResponse) -> bool {
+    redirect_location(response).is_none() && basic_auth_realm(response).is_none()
+}
+
+/// レスポンスが Basic 認証を求めている (401 かつ WWW-Authenticate が Basic) 場合、そのレルムを返す。
+/// レルムが指定されていない場合は空文字列を返す。
+/// https://datatracker.ietf.org/doc/html/rfc7617#section-2
+pub fn basic_auth_realm(response: &HttpResponse) -> Option<String> {
+    if response.status_code() != 401 {
+        return None;
+    }
+    response
+        .header_values("WWW-Authenticate")
+        .iter()
+        .find_map(|challenge| {
+            let challenge = challenge.trim();
+            let scheme = challenge.split_whitespace().next()?;
+            if !scheme.eq_ignore_ascii_case("Basic") {
+                return None;
+            }
+            let params = &challenge[scheme.len()..];
+            let realm = params
+                .split(',')
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("realm"))
+                .map(|(_, value)| value.trim().trim_matches('"').to_string());
+            Some(realm.unwrap_or_default())
+        })
+}
+
+/// レスポンスが Basic 認証を求めている場合、prompt で認証情報を入力してもらい、
+/// Authorization ヘッダの値を retry に渡してリクエストを送信し直す。
+/// 認証に MAX_AUTH_ATTEMPTS 回失敗した場合や、入力が取り消された場合は、最後の 401 のレスポンスを返す。
+pub fn authenticate<F>(
+    url: &Url,
+    response: HttpResponse,
+    prompt: &mut CredentialsPrompt,
+    mut retry: F,
+) -> Result<HttpResponse, Error>
+where
+    F: FnMut(&str) -> Result<HttpResponse, Error>,
+{
+    let mut response = response;
+    for _ in 0..MAX_AUTH_ATTEMPTS {
+        let realm = match basic_auth_realm(&response) {
+            Some(realm) => realm,
+            None => return Ok(response),
+        };
+        let credentials = match prompt(url, &realm) {
+            Some(credentials) => credentials,
+            None => return Ok(response),
+        };
+        response = retry(&credentials.basic_authorization())?;
+    }
+    Ok(response)
+}
+
+/// data URL のペイロードをデコードし、それをボディとする HttpResponse を生成する。
+/// ネットワークに接続せずにページを表示するために使う。
+/// https://fetch.spec.whatwg.org/#data-url-processor
+pub fn load_data_url(url: &Url) -> Result<HttpResponse, Error> {
+    // "data:" 以降は "<メディアタイプ>[;base64],<データ>" の形式である。
+    let path = url.path();
+    let (header, payload) = match path.find(',') {
+        Some(index) => (path[..index].trim(), &path[index + 1..]),
+        None => {
+            return Err(Error::UnexpectedInput(format!(
+                "invalid data URL: {}",
+                url.href()
+            )))
+        }
+    };
+
+    let (media_type, is_base64) = match header.len().checked_sub(";base64".len()) {
+        Some(index) if header[index..].eq_ignore_ascii_case(";base64") => {
+            (header[..index].trim(), true)
+        }
+        _ => (header, false),
+    };
+    // メディアタイプが省略された場合は text/plain;charset=US-ASCII とする。
+    let media_type = if media_type.is_empty() {
+        "text/plain;charset=US-ASCII".to_string()
+    } else if media_type.starts_with(';') {
+        format!("text/plain{}", media_type)
+    } else {
+        media_type.to_string()
+    };
+
+    let mut body = percent_decode(payload);
+    if is_base64 {
+        body = match decode_base64(&body) {
+            Some(decoded) => decoded,
+            None => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid base64 in data URL: {}",
+                    url.href()
+                )))
+            }
+        };
+    }
+
+    // ボディはメディアタイプの charset に従ってデコードする。
+    let mut received = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        media_type,
+        body.len()
+    )
+    .into_bytes();
+    received.extend_from_slice(&body);
+    HttpResponse::from_bytes(&received)
+}
+
+/// about URL に対応するブラウザ組み込みのページを生成する。
+/// - about:blank: 空のページ
+/// - about:version: ブラウザのバージョン情報
+/// - about:crash-test: 読み込みに失敗した場合のエラー処理を確認するため、常にエラーを返す。
+///
+/// https://url.spec.whatwg.org/#about-scheme
+pub fn load_about_url(url: &Url) -> Result<HttpResponse, Error> {
+    let page = url.path();
+    match page.as_str() {
+        "blank" => synthesize_response(
+            200,
+            "OK",
+            "text/html",
+            "<html><head></head><body></body></html>",
+        ),
+        "version" => synthesize_response(
+            200,
+            "OK",
+            "text/html",
+            &format!(
+                "<html><head></head><body><h1>saba</h1><p>version {}</p></body></html>",
+                env!("CARGO_PKG_VERSION")
+            ),
+        ),
+        "crash-test" => Err(Error::Other(
+            "about:crash-test intentionally failed to load".to_string(),
+        )),
+        // 入力されたページ名は HTML のテキストとして埋め込むため、タグとして解釈されないようにエスケープする。
+        _ => synthesize_response(
+            404,
+            "Not Found",
+            "text/html",
+            &format!(
+                "<html><head></head><body><h1>Not Found</h1><p>about:{} does not exist.</p></body></html>",
+                escape_string(&page, false)
+            ),
+        ),
+    }
+}
+
+/// パースエラーの一覧を表示するデバッグ用のページを生成する。
+/// 各エラーは、エラーを検出した HTML の位置 (バイト単位) とエラーの種類を1行ずつ表示する。
+pub fn load_parse_errors_page(errors: &[ParseError]) -> Result<HttpResponse, Error> {
+    let mut body = format!(
+        "<html><head></head><body><h1>Parse errors</h1><p>{} errors</p>",
+        errors.len()
+    );
+    for error in errors {
+        body.push_str(&format!("<p>{}: {}</p>", error.offset(), error.kind()));
+    }
+    body.push_str("</body></html>");
+    synthesize_response(200, "OK", "text/html", &body)
+}
+
+/// ネットワークに接続せずに生成したコンテンツから HttpResponse を生成する。
+fn synthesize_response(
+    status_code: u32,
+    reason: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<HttpResponse, Error> {
+    HttpResponse::new(format!(
+        "HTTP/1.1 {} {}\nContent-Type: {}\nContent-Length: {}\n\n{}",
+        status_code,
+        reason,
+        content_type,
+        body.len(),
+        body
+    ))
+}
+
+/// バイト列を base64 でエンコードする。
+/// https://infra.spec.whatwg.org/#forgiving-base64-encode
+fn encode_base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for (index, shift) in [18, 12, 6, 0].iter().enumerate() {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[((n >> shift) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// base64 でエンコードされたバイト列をデコードする。不正な入力の場合は None を返す。
+/// ASCII の空白は無視し、末尾のパディング "=" は省略できる。
+/// https://infra.spec.whatwg.org/#forgiving-base64-decode
+pub(crate) fn decode_base64(input: &[u8]) -> Option<Vec<u8>> {
+    let mut data: Vec<u8> = input
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    if data.len() % 4 == 0 {
+        for _ in 0..2 {
+            if data.last() == Some(&b'=') {
+                data.pop();
+            }
+        }
+    }
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for b in data {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::parse_error::ParseErrorKind;
+
+    /// テスト用に、ステータスコードと Location ヘッダだけを持つレスポンスを作成する。
+    fn redirect_response(status_code: u32, location: &str) -> Result<HttpResponse, Error> {
+        HttpResponse::new(format!(
+            "HTTP/1.1 {} Redirect\nLocation: {}\n\n",
+            status_code, location
+        ))
+    }
+
+    #[test]
+    /// 相対 URL の Location をたどってリダイレクトすることのテスト。
+    fn test_follow_redirects() {
+        let url = Url::new("http://example.com/a/index.html".to_string())
+            .parse()
+            .unwrap();
+        let mut requests = Vec::new();
+        let (final_url, response) =
+            follow_redirects(url, "GET", String::new(), |url, method, _| {
+                requests.push((url.href(), method.to_string()));
+                match url.path().as_str() {
+                    "a/index.html" => redirect_response(301, "next.html"),
+                    "a/next.html" => redirect_response(307, "/final?x=1"),
+                    _ => HttpResponse::new("HTTP/1.1 200 OK\n\nfinal".to_string()),
+                }
+            })
+            .unwrap();
+
+        assert_eq!(200, response.status_code());
+        assert_eq!("final", response.body());
+        assert_eq!("http://example.com/final?x=1", final_url.href());
+        assert_eq!(
+            [
+                (
+                    "http://example.com/a/index.html".to_string(),
+                    "GET".to_string()
+                ),
+                (
+                    "http://example.com/a/next.html".to_string(),
+                    "GET".to_string()
+                ),
+                (
+                    "http://example.com/final?x=1".to_string(),
+                    "GET".to_string()
+                ),
+            ]
+            .to_vec(),
+            requests
+        );
+    }
+
+    #[test]
+    /// 303 では POST を GET に変更し、307 ではメソッドとボディを保持することのテスト。
+    fn test_redirect_method_change() {
+        let url = Url::new("http://example.com/form".to_string())
+            .parse()
+            .unwrap();
+        let mut requests = Vec::new();
+        follow_redirects(
+            url.clone(),
+            "POST",
+            "a=1".to_string(),
+            |url, method, body| {
+                requests.push((url.path(), method.to_string(), body.to_string()));
+                match url.path().as_str() {
+                    "form" => redirect_response(307, "form2"),
+                    "form2" => redirect_response(303, "done"),
+                    _ => HttpResponse::new("HTTP/1.1 200 OK\n\n".to_string()),
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            [
+                ("form".to_string(), "POST".to_string(), "a=1".to_string()),
+                ("form2".to_string(), "POST".to_string(), "a=1".to_string()),
+                ("done".to_string(), "GET".to_string(), "".to_string()),
+            ]
+            .to_vec(),
+            requests
+        );
+    }
+
+    #[test]
+    /// リダイレクトが多すぎる場合のエラーテスト。
+    fn test_too_many_redirects() {
+        let url = Url::new("http://example.com/loop".to_string())
+            .parse()
+            .unwrap();
+        let mut count = 0;
+        let result = follow_redirects(url, "GET", String::new(), |_, _, _| {
+            count += 1;
+            redirect_response(302, "/loop")
+        });
+        assert!(result.is_err());
+        assert_eq!(MAX_REDIRECTS + 1, count);
+
+        // Location がない場合はそのまま返す。
+        let url = Url::new("http://example.com/".to_string()).parse().unwrap();
+        let (_, response) = follow_redirects(url, "GET", String::new(), |_, _, _| {
+            HttpResponse::new("HTTP/1.1 302 Found\n\n".to_string())
+        })
+        .unwrap();
+        assert_eq!(302, response.status_code());
+    }
+
+    #[test]
+    /// http と https 以外の URL へのリダイレクトはたどらずにエラーとすることのテスト。
+    fn test_redirect_to_non_http_url() {
+        for location in ["data:text/html,<p>hi</p>", "about:version"] {
+            let url = Url::new("http://example.com/".to_string()).parse().unwrap();
+            let mut count = 0;
+            let result = follow_redirects(url, "GET", String::new(), |_, _, _| {
+                count += 1;
+                redirect_response(302, location)
+            });
+            assert!(result.is_err());
+            assert_eq!(1, count);
+        }
+
+        // https へのリダイレクトはたどる。
+        let url = Url::new("http://example.com/".to_string()).parse().unwrap();
+        let (final_url, _) = follow_redirects(url, "GET", String::new(), |url, _, _| {
+            if url.is_https() {
+                HttpResponse::new("HTTP/1.1 200 OK\n\n".to_string())
+            } else {
+                redirect_response(301, "https://example.com/secure")
+            }
+        })
+        .unwrap();
+        assert_eq!("https://example.com/secure", final_url.href());
+    }
+
+    #[test]
+    /// Basic 認証のレルムと Authorization ヘッダの値のテスト。
+    fn test_basic_auth() {
+        let response = HttpResponse::new(
+            "HTTP/1.1 401 Unauthorized\nWWW-Authenticate: Bearer\nWWW-Authenticate: Basic realm=\"Admin Area\", charset=\"UTF-8\"\n\n"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(Some("Admin Area".to_string()), basic_auth_realm(&response));
+
+        let response =
+            HttpResponse::new("HTTP/1.1 200 OK\nWWW-Authenticate: Basic\n\n".to_string()).unwrap();
+        assert_eq!(None, basic_auth_realm(&response));
+
+        // RFC 7617 の例
+        let credentials = Credentials::new("Aladdin".to_string(), "open sesame".to_string());
+        assert_eq!(
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==",
+            credentials.basic_authorization()
+        );
+        assert_eq!("YQ==", encode_base64(b"a"));
+        assert_eq!("YWI=", encode_base64(b"ab"));
+    }
+
+    #[test]
+    /// リダイレクトや認証を求めるレスポンスを、ページとして表示するレスポンスとみなさないことのテスト。
+    fn test_is_final_response() {
+        let response = |s: &str| HttpResponse::new(s.to_string()).unwrap();
+        assert!(is_final_response(&response("HTTP/1.1 200 OK\n\nbody")));
+        assert!(is_final_response(&response("HTTP/1.1 404 Not Found\n\n")));
+        assert!(!is_final_response(
+            &redirect_response(302, "/next").unwrap()
+        ));
+        // Location ヘッダがないリダイレクトは、そのまま表示する。
+        assert!(is_final_response(&response("HTTP/1.1 302 Found\n\n")));
+        assert!(!is_final_response(&response(
+            "HTTP/1.1 401 Unauthorized\nWWW-Authenticate: Basic realm=\"a\"\n\n"
+        )));
+        assert!(is_final_response(&response(
+            "HTTP/1.1 401 Unauthorized\nWWW-Authenticate: Bearer\n\n"
+        )));
+    }
+
+    #[test]
+    /// 認証情報を入力してリクエストを送信し直すテスト。
+    fn test_authenticate() {
+        let url = Url::new("http://example.com/admin".to_string())
+            .parse()
+            .unwrap();
+        let unauthorized = || {
+            HttpResponse::new(
+                "HTTP/1.1 401 Unauthorized\nWWW-Authenticate: Basic realm=\"admin\"\n\n"
+                    .to_string(),
+            )
+        };
+
+        let mut realms = Vec::new();
+        let mut prompt = |_: &Url, realm: &str| {
+            realms.push(realm.to_string());
+            Some(Credentials::new("user".to_string(), "pass".to_string()))
+        };
+        let response = authenticate(
+            &url,
+            unauthorized().unwrap(),
+            &mut prompt,
+            |authorization| {
+                if authorization == "Basic dXNlcjpwYXNz" {
+                    HttpResponse::new("HTTP/1.1 200 OK\n\nsecret".to_string())
+                } else {
+                    unauthorized()
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!("secret", response.body());
+        assert_eq!(["admin".to_string()].to_vec(), realms);
+
+        // 入力が取り消された場合は 401 のレスポンスを返す。
+        let mut cancel = |_: &Url, _: &str| None;
+        let response = authenticate(&url, unauthorized().unwrap(), &mut cancel, |_| {
+            unauthorized()
+        })
+        .unwrap();
+        assert_eq!(401, response.status_code());
+
+        // 認証に失敗し続ける場合は MAX_AUTH_ATTEMPTS 回で諦める。
+        let mut count = 0;
+        let mut wrong = |_: &Url, _: &str| Some(Credentials::new("a".to_string(), "b".to_string()));
+        let response = authenticate(&url, unauthorized().unwrap(), &mut wrong, |_| {
+            count += 1;
+            unauthorized()
+        })
+        .unwrap();
+        assert_eq!(401, response.status_code());
+        assert_eq!(MAX_AUTH_ATTEMPTS, count);
+    }
+
+    #[test]
+    /// data URL からレスポンスを生成することのテスト。
+    fn test_load_data_url() {
+        let url = Url::new("data:text/html,<p>Hello%20World</p>".to_string())
+            .parse()
+            .unwrap();
+        let response = load_data_url(&url).unwrap();
+        assert_eq!(200, response.status_code());
+        assert_eq!(
+            Ok("text/html".to_string()),
+            response.header_value("Content-Type")
+        );
+        assert_eq!("<p>Hello World</p>", response.body());
+
+        // "<h1>Hi</h1>" を base64 でエンコードしたもの。
+        let url = Url::new("data:text/html;base64,PGgxPkhpPC9oMT4=".to_string())
+            .parse()
+            .unwrap();
+        let response = load_data_url(&url).unwrap();
+        assert_eq!("<h1>Hi</h1>", response.body());
+
+        let url = Url::new("data:,plain".to_string()).parse().unwrap();
+        let response = load_data_url(&url).unwrap();
+        assert_eq!(
+            Ok("text/plain;charset=US-ASCII".to_string()),
+            response.header_value("Content-Type")
+        );
+        assert_eq!("plain", response.body());
+
+        // "日本" を Shift_JIS でエンコードしたもの。
+        let url = Url::new("data:text/plain;charset=Shift_JIS,%93%FA%96%7B".to_string())
+            .parse()
+            .unwrap();
+        let response = load_data_url(&url).unwrap();
+        assert_eq!("日本", response.body());
+    }
+
+    #[test]
+    /// about URL の組み込みページのテスト。
+    fn test_load_about_url() {
+        let url = Url::new("about:blank".to_string()).parse().unwrap();
+        let response = load_about_url(&url).unwrap();
+        assert_eq!(200, response.status_code());
+        assert_eq!("<html><head></head><body></body></html>", response.body());
+
+        let url = Url::new("about:version".to_string()).parse().unwrap();
+        let response = load_about_url(&url).unwrap();
+        assert!(response.body().contains(env!("CARGO_PKG_VERSION")));
+
+        let url = Url::new("about:crash-test".to_string()).parse().unwrap();
+        assert!(load_about_url(&url).is_err());
+
+        let url = Url::new("about:unknown".to_string()).parse().unwrap();
+        assert_eq!(404, load_about_url(&url).unwrap().status_code());
+
+        // 存在しないページ名に含まれるタグは、テキストとして表示する。
+        let url = Url::new("about:<script>x</script>&".to_string())
+            .parse()
+            .unwrap();
+        let response = load_about_url(&url).unwrap();
+        assert_eq!(404, response.status_code());
+        assert!(response
+            .body()
+            .contains("<p>about:&lt;script&gt;x&lt;/script&gt;&amp; does not exist.</p>"));
+    }
+
+    #[test]
+    /// パースエラーの一覧ページのテスト。
+    fn test_load_parse_errors_page() {
+        let errors = [
+            ParseError::new(ParseErrorKind::UnexpectedEndTag, 6),
+            ParseError::new(ParseErrorKind::EofInTag, 20),
+        ];
+        let response = load_parse_errors_page(&errors).unwrap();
+        assert_eq!(200, response.status_code());
+        assert_eq!(
+            "<html><head></head><body><h1>Parse errors</h1><p>2 errors</p><p>6: unexpected-end-tag</p><p>20: eof-in-tag</p></body></html>",
+            response.body()
+        );
+    }
+
+    #[test]
+    /// 不正な data URL のエラーテスト。
+    fn test_invalid_data_url() {
+        let url = Url::new("data:text/html".to_string()).parse().unwrap();
+        assert!(load_data_url(&url).is_err());
+
+        let url = Url::new("data:text/html;base64,P".to_string())
+            .parse()
+            .unwrap();
+        assert!(load_data_url(&url).is_err());
+    }
+
+    #[test]
+    /// スキームを持つ URL はそのまま返すことのテスト。
+    fn test_url_with_scheme() {
+        assert_eq!("http://example.com", normalize_input("http://example.com"));
+        assert_eq!(
+            "https://example.com/a",
+            normalize_input("  https://example.com/a ")
+        );
+    }
+
+    #[test]
+    /// ホスト名に http:// を補うことのテスト。
+    fn test_bare_host() {
+        assert_eq!("http://example.com", normalize_input("example.com"));
+        assert_eq!(
+            "http://example.com/index.html?a=1",
+            normalize_input("example.com/index.html?a=1")
+        );
+        assert_eq!(
+            "http://localhost:8000/test.html",
+            normalize_input("localhost:8000/test.html")
+        );
+        assert_eq!("http://host.test:8000", normalize_input("host.test:8000"));
+        assert_eq!("http://localhost", normalize_input("localhost"));
+    }
+
+    #[test]
+    /// URL でない入力を検索クエリにすることのテスト。
+    fn test_search_query() {
+        assert_eq!(
+            "http://html.duckduckgo.com/html/?q=rust+browser",
+            normalize_input("rust browser")
+        );
+        assert_eq!(
+            "http://html.duckduckgo.com/html/?q=saba",
+            normalize_input("saba")
+        );
+        assert_eq!(
+            "http://html.duckduckgo.com/html/?q=a%26b%3F",
+            normalize_input("a&b?")
+        );
+    }
+}
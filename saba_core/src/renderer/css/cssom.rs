@@ -1,23 +1,90 @@
 use crate::alloc::string::ToString;
+use crate::renderer::css::parse_error::CssParseError;
+use crate::renderer::css::parse_error::CssParseErrorKind;
+use crate::renderer::css::shorthand::expand_shorthand;
+use crate::renderer::css::shorthand::is_shorthand;
 use crate::renderer::css::token::CssToken;
 use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::dom::node::ElementState;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
-use core::iter::Peekable;
 
 /// CSS のプロパティの値に対するノードを表す ComponentValue 構造体。
 pub type ComponentValue = CssToken;
 
+/// CssTokenizer をラップし、1つ先のトークンとその位置を参照できるようにする構造体。
+/// 解釈できないトークン (BadToken) を読み込んだ時点で、パースエラーとして記録する。
+#[derive(Debug, Clone)]
+struct TokenStream {
+    t: CssTokenizer,
+    // 先読みしたトークンと、その開始位置 (文字単位)
+    peeked: Option<(Option<CssToken>, usize)>,
+    errors: Vec<CssParseError>,
+}
+
+impl TokenStream {
+    fn new(t: CssTokenizer) -> Self {
+        Self {
+            t,
+            peeked: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// 次のトークンをまだ読み込んでいない場合、トークナイザーから読み込む。
+    fn fill(&mut self) {
+        if self.peeked.is_some() {
+            return;
+        }
+
+        let token = self.t.next();
+        if let Some(CssToken::BadToken(c, offset)) = token {
+            self.errors
+                .push(CssParseError::new(CssParseErrorKind::BadToken(c), offset));
+        }
+        self.peeked = Some((token, self.t.token_start()));
+    }
+
+    fn peek(&mut self) -> Option<&CssToken> {
+        self.fill();
+        self.peeked.as_ref().and_then(|(token, _)| token.as_ref())
+    }
+
+    fn next(&mut self) -> Option<CssToken> {
+        self.fill();
+        self.peeked.take().and_then(|(token, _)| token)
+    }
+
+    /// 次のトークンの開始位置 (バイト単位) でパースエラーを記録する。
+    fn error(&mut self, kind: CssParseErrorKind) {
+        self.fill();
+        let start = self.peeked.as_ref().map_or(0, |(_, start)| *start);
+        let offset = self.t.byte_offset(start);
+        self.errors.push(CssParseError::new(kind, offset));
+    }
+}
+
 /// CSS の構文解析を行い、CSS オブジェクトモデル(CSSOM) を作成するための構造体。
-/// CSSTokenizer 構造体を Peekable でラップして保持する。
+/// CSSTokenizer 構造体を TokenStream でラップして保持する。
+/// 解釈できないルールや宣言は読み飛ばし、パースエラーとして記録して解析を続ける。
 #[derive(Debug, Clone)]
 pub struct CssParser {
-    t: Peekable<CssTokenizer>,
+    t: TokenStream,
 }
 
 impl CssParser {
     pub fn new(t: CssTokenizer) -> Self {
-        Self { t: t.peekable() }
+        Self {
+            t: TokenStream::new(t),
+        }
+    }
+
+    /// 構文解析で検出したパースエラーを検出した順に返す。
+    pub fn errors(&self) -> Vec<CssParseError> {
+        self.t.errors.clone()
     }
 
     /// トークン列から CSSOM を構築する。
@@ -46,11 +113,9 @@ impl CssParser {
                 }
                 _ => {
                     // AtKeyword トークン以外の場合、1つのルールを解釈し、ベクタに追加する。
-                    // 1つのルールを解釈し、ベクタに追加する。
-                    let rule = self.consume_qualified_rule();
-                    match rule {
-                        Some(r) => rules.push(r),
-                        None => return rules,
+                    // 解釈できなかったルールは読み飛ばされているため、次のルールの解釈を続ける。
+                    if let Some(r) = self.consume_qualified_rule() {
+                        rules.push(r);
                     }
                 }
             }
@@ -58,13 +123,17 @@ impl CssParser {
     }
 
     /// 1つのルールを解釈する。
+    /// セレクタを解釈できない場合はルール全体を読み飛ばし、None を返す。
     fn consume_qualified_rule(&mut self) -> Option<QualifiedRule> {
         let mut rule = QualifiedRule::new();
 
         loop {
             let token = match self.t.peek() {
                 Some(t) => t,
-                None => return None,
+                None => {
+                    self.t.error(CssParseErrorKind::UnexpectedEof);
+                    return None;
+                }
             };
 
             match token {
@@ -75,59 +144,132 @@ impl CssParser {
                     return Some(rule);
                 }
                 // 開き波括弧以外の場合、ルールのセレクタとして扱う。
-                _ => {
-                    rule.set_selector(self.consume_selector());
+                _ => match self.consume_selector() {
+                    Some(selector) => rule.set_selector(selector),
+                    None => {
+                        self.skip_rule();
+                        return None;
+                    }
+                },
+            }
+        }
+    }
+
+    /// 宣言ブロックの終わりまでトークンを読み飛ばす。
+    /// 宣言ブロック内の入れ子になった波括弧は、対応する閉じ波括弧まで読み飛ばす。
+    fn skip_rule(&mut self) {
+        let mut depth = 0;
+
+        while let Some(token) = self.t.next() {
+            match token {
+                CssToken::OpenCurly => depth += 1,
+                CssToken::CloseCurly => {
+                    depth -= 1;
+                    if depth <= 0 {
+                        return;
+                    }
                 }
+                _ => {}
             }
         }
     }
 
+    /// 宣言ブロックの開始直前までトークンを読み飛ばす。
+    fn skip_to_block(&mut self) {
+        while self.t.peek().is_some() && self.t.peek() != Some(&CssToken::OpenCurly) {
+            self.t.next();
+        }
+    }
+
     /// セレクタを解釈する。
-    fn consume_selector(&mut self) -> Selector {
-        let token = match self.t.next() {
-            Some(t) => t,
-            None => panic!("should have a token but got None"),
-        };
+    /// セレクタとして解釈できないトークンの場合、パースエラーを記録して None を返す。
+    fn consume_selector(&mut self) -> Option<Selector> {
+        let token = self.t.peek()?.clone();
 
         match token {
             // 次のトークンがハッシュトークンの場合、ID セレクタを作成して返す。
-            CssToken::HashToken(value) => Selector::IdSelector(value[1..].to_string()),
+            CssToken::HashToken(value) => {
+                self.t.next();
+                self.consume_pseudo_classes(vec![Selector::IdSelector(value[1..].to_string())])
+            }
 
             // 次のトークンがピリオドの場合、クラスセレクタを作成して返す。
-            CssToken::Delim(delim) => {
-                if delim == '.' {
-                    return Selector::ClassSelector(self.consume_ident());
+            CssToken::Delim('.') => {
+                self.t.next();
+                match self.consume_ident() {
+                    Some(class_name) => {
+                        self.consume_pseudo_classes(vec![Selector::ClassSelector(class_name)])
+                    }
+                    None => {
+                        self.t.error(CssParseErrorKind::InvalidSelector);
+                        None
+                    }
                 }
-                panic!("Parse error: {:?} is an unexpected token.", token);
             }
 
             // 次のトークンが識別子の場合、タイプセレクタを作成して返す。
-            // ただし、a:hovert のようなセレクタは正しく解釈せず、タイプセレクタとして扱う。
-            // コロンが出てきた場合、宣言ブロックの開始直前までトークンを無視する。
+            // a:hover のように疑似クラスが続く場合、複合セレクタとして返す。
             CssToken::Ident(ident) => {
-                if self.t.peek() == Some(&CssToken::Colon) {
-                    while self.t.peek() != Some(&CssToken::OpenCurly) {
-                        self.t.next();
-                    }
-                }
-                Selector::TypeSelector(ident.to_string())
+                self.t.next();
+                self.consume_pseudo_classes(vec![Selector::TypeSelector(ident)])
             }
 
+            // 次のトークンがアスタリスクの場合、全称セレクタを作成して返す。
+            CssToken::Delim('*') => {
+                self.t.next();
+                self.consume_pseudo_classes(vec![Selector::UniversalSelector])
+            }
+
+            // 次のトークンがコロンの場合、:hover のような疑似クラスのみのセレクタとして扱う。
+            CssToken::Colon => self.consume_pseudo_classes(Vec::new()),
+
             // アットキーワード @ の場合、宣言ブロックの開始直前までトークンを無視する。
             // 他の CSS をインポートする @import やメディアクエリを表す @media はサポートしない。
             CssToken::AtKeyword(_keyword) => {
-                while self.t.peek() != Some(&CssToken::OpenCurly) {
-                    self.t.next();
-                }
-                Selector::UnknownSelector
+                self.skip_to_block();
+                Some(Selector::UnknownSelector)
             }
+
+            // BadToken は読み込んだ時点でパースエラーとして記録している。
+            CssToken::BadToken(_, _) => None,
+
             _ => {
-                self.t.next();
-                Selector::UnknownSelector
+                self.t.error(CssParseErrorKind::InvalidSelector);
+                None
             }
         }
     }
 
+    /// コロンに続く疑似クラスを解釈し、selectors に加えて複合セレクタとして返す。
+    /// セレクタが1つのみの場合はそのセレクタを返す。
+    /// 対応していない疑似クラスや疑似要素 (::before など) の場合、パースエラーを記録して None を返す。
+    /// https://www.w3.org/TR/selectors-4/#typedef-compound-selector
+    fn consume_pseudo_classes(&mut self, mut selectors: Vec<Selector>) -> Option<Selector> {
+        while self.t.peek() == Some(&CssToken::Colon) {
+            self.t.next();
+            let pseudo_class = match self.t.peek() {
+                Some(CssToken::Ident(name)) => PseudoClass::from_name(name),
+                _ => None,
+            };
+            match pseudo_class {
+                Some(pseudo_class) => {
+                    self.t.next();
+                    selectors.push(Selector::PseudoClassSelector(pseudo_class));
+                }
+                None => {
+                    self.t.error(CssParseErrorKind::InvalidSelector);
+                    return None;
+                }
+            }
+        }
+
+        if selectors.len() == 1 {
+            selectors.pop()
+        } else {
+            Some(Selector::CompoundSelector(selectors))
+        }
+    }
+
     /// 複数の宣言を解釈する。
     fn consume_list_of_declarations(&mut self) -> Vec<Declaration> {
         let mut declarations = Vec::new();
@@ -135,7 +277,10 @@ impl CssParser {
         loop {
             let token = match self.t.peek() {
                 Some(t) => t,
-                None => return declarations,
+                None => {
+                    self.t.error(CssParseErrorKind::UnexpectedEof);
+                    return declarations;
+                }
             };
 
             match token {
@@ -152,10 +297,9 @@ impl CssParser {
                 }
 
                 // 識別子トークンの場合、1つの宣言を解釈し、ベクタに追加する。
+                // 一括指定プロパティの場合、展開した個別指定プロパティの宣言をすべて追加する。
                 CssToken::Ident(ref _ident) => {
-                    if let Some(declaration) = self.consume_declaration() {
-                        declarations.push(declaration);
-                    }
+                    declarations.extend(self.consume_declaration());
                 }
                 // 上記以外の場合、無視して次のトークンを処理する。
                 _ => {
@@ -166,51 +310,122 @@ impl CssParser {
     }
 
     /// 1つの宣言を解釈する。
-    fn consume_declaration(&mut self) -> Option<Declaration> {
-        if self.t.peek().is_none() {
-            return None;
-        }
+    /// margin などの一括指定プロパティの場合、個別指定プロパティの宣言に展開して返す。
+    /// 宣言として解釈できない場合、パースエラーを記録して空のベクタを返す。
+    fn consume_declaration(&mut self) -> Vec<Declaration> {
+        self.consume_declaration_internal().unwrap_or_default()
+    }
 
+    fn consume_declaration_internal(&mut self) -> Option<Vec<Declaration>> {
         // Declaration を初期化し、この構造体のプロパティに識別子を設定する。
         let mut declaration = Declaration::new();
-        declaration.set_property(self.consume_ident());
+        declaration.set_property(self.consume_ident()?);
 
         // もし次のトークンがコロンでない場合、パースエラーなので None を返す。
-        match self.t.next() {
-            Some(token) => match token {
-                CssToken::Colon => {}
-                _ => return None,
-            },
-            None => return None,
+        match self.t.peek() {
+            Some(CssToken::Colon) => {
+                self.t.next();
+            }
+            None => {
+                self.t.error(CssParseErrorKind::UnexpectedEof);
+                return None;
+            }
+            Some(_) => {
+                self.t.error(CssParseErrorKind::InvalidDeclaration);
+                return None;
+            }
         }
 
-        // Declaration 構造体の値にコンポーネント値を設定する。
-        declaration.set_value(self.consume_component_value());
+        // 値がないまま宣言や宣言ブロックが終わる場合、パースエラーなので None を返す。
+        // セミコロンと閉じ波括弧は consume_list_of_declarations で消費する。
+        match self.t.peek() {
+            Some(CssToken::SemiColon) | Some(CssToken::CloseCurly) => {
+                self.t.error(CssParseErrorKind::InvalidDeclaration);
+                return None;
+            }
+            None => {
+                self.t.error(CssParseErrorKind::UnexpectedEof);
+                return None;
+            }
+            _ => {}
+        }
 
-        Some(declaration)
-    }
+        // 宣言の終わりまでのコンポーネント値を読み込む。
+        let mut values = Vec::new();
+        while !matches!(
+            self.t.peek(),
+            None | Some(CssToken::SemiColon) | Some(CssToken::CloseCurly)
+        ) {
+            values.push(self.consume_component_value());
+        }
 
-    /// 識別子トークンを消費し、文字列を取得する。
-    fn consume_ident(&mut self) -> String {
-        let token = match self.t.next() {
-            Some(t) => t,
-            None => panic!("should have a token but got None"),
-        };
+        // BadToken は読み込んだ時点でパースエラーとして記録しているため、宣言を無視する。
+        if values
+            .iter()
+            .any(|value| matches!(value, CssToken::BadToken(_, _)))
+        {
+            return None;
+        }
 
-        match token {
-            CssToken::Ident(ref ident) => ident.to_string(),
-            _ => {
-                panic!("Parse error: {:?} is an unexpected token.", token);
+        if is_shorthand(&declaration.property) {
+            let expanded = expand_shorthand(&declaration.property, &values);
+            if expanded.is_none() {
+                self.t.error(CssParseErrorKind::InvalidDeclaration);
             }
+            return expanded;
+        }
+
+        // Declaration 構造体の値にコンポーネント値のリストを設定する。
+        declaration.set_value(values);
+        Some(vec![declaration])
+    }
+
+    /// 識別子トークンを消費し、文字列を取得する。識別子トークンでない場合は消費せずに None を返す。
+    fn consume_ident(&mut self) -> Option<String> {
+        match self.t.peek() {
+            Some(CssToken::Ident(_)) => match self.t.next() {
+                Some(CssToken::Ident(ident)) => Some(ident),
+                _ => None,
+            },
+            _ => None,
         }
     }
 
     /// コンポーネント値を解釈する。
     /// https://www.w3.org/TR/css-syntax-3/#consume-component-value
     fn consume_component_value(&mut self) -> ComponentValue {
-        self.t
+        match self
+            .t
             .next()
             .expect("should have a token in consume_component_value")
+        {
+            CssToken::Function(name, _) => self.consume_function(name),
+            token => token,
+        }
+    }
+
+    /// 関数トークンに続く閉じ丸括弧までのコンポーネント値を、関数の引数として解釈する。
+    /// 引数を区切るカンマは引数に含めない。
+    /// https://www.w3.org/TR/css-syntax-3/#consume-function
+    fn consume_function(&mut self, name: String) -> ComponentValue {
+        let mut args = Vec::new();
+
+        loop {
+            match self.t.peek() {
+                // 閉じられていない関数の場合、入力の最後までを引数とする。
+                None => break,
+                Some(CssToken::CloseParenthesis) => {
+                    self.t.next();
+                    break;
+                }
+                Some(CssToken::Delim(',')) => {
+                    self.t.next();
+                }
+                Some(_) => args.push(self.consume_component_value()),
+            }
+        }
+
+        ComponentValue::Function(name, args)
     }
 }
 
@@ -230,6 +445,12 @@ impl StyleSheet {
     pub fn set_rules(&mut self, rules: Vec<QualifiedRule>) {
         self.rules = rules;
     }
+
+    /// other のルールを、このスタイルシートのルールの後ろに加える。
+    /// 後に加えたルールほど、カスケードで優先される。
+    pub fn merge(&mut self, other: StyleSheet) {
+        self.rules.extend(other.rules);
+    }
 }
 
 /// ルールノード (QualifiedRule) 構造体。
@@ -264,28 +485,129 @@ impl QualifiedRule {
 /// セレクタノード
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Selector {
+    /// すべての要素に一致するセレクタ。
+    /// https://www.w3.org/TR/selectors-4/#the-universal-selector
+    UniversalSelector,
     /// https://www.w3.org/TR/selectors-4/#type-selectors
     TypeSelector(String),
     /// https://www.w3.org/TR/selectors-4/#class-html
     ClassSelector(String),
     /// https://www.w3.org/TR/selectors-4/#id-selectors
     IdSelector(String),
+    /// https://www.w3.org/TR/selectors-4/#pseudo-classes
+    PseudoClassSelector(PseudoClass),
+    /// a:hover のように、すべてのセレクタに一致する要素に一致するセレクタ。
+    /// https://www.w3.org/TR/selectors-4/#compound
+    CompoundSelector(Vec<Selector>),
     /// パース中にエラーが発生した場合に使用するセレクタ
     UnknownSelector,
 }
 
+impl Selector {
+    /// ノードがセレクタに一致するかどうかを返す。要素ではないノードはどのセレクタにも一致しない。
+    /// https://www.w3.org/TR/selectors-4/#match-a-selector-against-an-element
+    pub fn matches(&self, node: &Node) -> bool {
+        let element = match node.kind() {
+            NodeKind::Element(e) => e,
+            _ => return false,
+        };
+
+        match self {
+            Selector::UniversalSelector => true,
+            Selector::TypeSelector(type_name) => element.tag_name() == *type_name,
+            Selector::ClassSelector(class_name) => element
+                .attributes()
+                .iter()
+                .any(|attr| attr.name() == "class" && attr.value() == *class_name),
+            Selector::IdSelector(id_name) => element
+                .attributes()
+                .iter()
+                .any(|attr| attr.name() == "id" && attr.value() == *id_name),
+            Selector::PseudoClassSelector(pseudo_class) => pseudo_class.matches(node),
+            Selector::CompoundSelector(selectors) => selectors.iter().all(|s| s.matches(node)),
+            Selector::UnknownSelector => false,
+        }
+    }
+}
+
+/// 対応している疑似クラス
+/// https://www.w3.org/TR/selectors-4/#pseudo-classes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PseudoClass {
+    /// https://www.w3.org/TR/selectors-4/#the-hover-pseudo
+    Hover,
+    /// https://www.w3.org/TR/selectors-4/#link
+    Link,
+    /// https://www.w3.org/TR/selectors-4/#visited-pseudo
+    Visited,
+    /// https://www.w3.org/TR/selectors-4/#the-first-child-pseudo
+    FirstChild,
+}
+
+impl PseudoClass {
+    /// 疑似クラスの名前 (コロンを除く) から PseudoClass を返す。名前の大文字と小文字は区別しない。
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hover" => Some(PseudoClass::Hover),
+            "link" => Some(PseudoClass::Link),
+            "visited" => Some(PseudoClass::Visited),
+            "first-child" => Some(PseudoClass::FirstChild),
+            _ => None,
+        }
+    }
+
+    /// 要素が疑似クラスに一致するかどうかを返す。
+    /// :hover と :visited は要素の状態 (ElementState) を参照する。
+    fn matches(&self, node: &Node) -> bool {
+        let element = match node.kind() {
+            NodeKind::Element(e) => e,
+            _ => return false,
+        };
+
+        match self {
+            PseudoClass::Hover => element.state().contains(ElementState::HOVER),
+            PseudoClass::Link => {
+                element.is_link() && !element.state().contains(ElementState::VISITED)
+            }
+            PseudoClass::Visited => {
+                element.is_link() && element.state().contains(ElementState::VISITED)
+            }
+            // 前の兄弟ノードに要素がない場合、最初の子要素となる。
+            PseudoClass::FirstChild => {
+                let mut sibling = node.previous_sibling().upgrade();
+                while let Some(s) = sibling {
+                    if s.borrow().get_element().is_some() {
+                        return false;
+                    }
+                    sibling = s.borrow().previous_sibling().upgrade();
+                }
+                true
+            }
+        }
+    }
+}
+
 /// 宣言ノード
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration {
     pub property: String,
-    pub value: ComponentValue,
+    /// 宣言の値。margin: 0 auto のように複数のコンポーネント値を持つことがある。
+    pub value: Vec<ComponentValue>,
 }
 
 impl Declaration {
     pub fn new() -> Self {
         Self {
             property: String::new(),
-            value: ComponentValue::Ident(String::new()),
+            value: Vec::new(),
+        }
+    }
+
+    /// プロパティと値のリストから宣言を作成する。
+    pub fn from_values(property: &str, value: Vec<ComponentValue>) -> Self {
+        Self {
+            property: property.to_string(),
+            value,
         }
     }
 
@@ -293,7 +615,7 @@ impl Declaration {
         self.property = property;
     }
 
-    pub fn set_value(&mut self, value: ComponentValue) {
+    pub fn set_value(&mut self, value: Vec<ComponentValue>) {
         self.value = value;
     }
 }
@@ -301,7 +623,15 @@ impl Declaration {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::renderer::dom::api::get_style_content;
+    use crate::renderer::dom::api::get_style_contents;
+    use crate::renderer::dom::api::query_selector;
+    use crate::renderer::dom::api::query_selector_all;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::rc::Rc;
     use alloc::vec;
+    use core::cell::RefCell;
 
     /// 空文字のテスト。何もない文字列が入力だった場合のケースについてのテスト。
     #[test]
@@ -324,7 +654,7 @@ mod tests {
         rule.set_selector(Selector::TypeSelector("p".to_string()));
         let mut declaration = Declaration::new();
         declaration.set_property("color".to_string());
-        declaration.set_value(ComponentValue::Ident("red".to_string()));
+        declaration.set_value(vec![ComponentValue::Ident("red".to_string())]);
         rule.set_declarations(vec![declaration]);
 
         let expected = [rule];
@@ -349,7 +679,7 @@ mod tests {
         rule.set_selector(Selector::IdSelector("id".to_string()));
         let mut declaration = Declaration::new();
         declaration.set_property("color".to_string());
-        declaration.set_value(ComponentValue::Ident("red".to_string()));
+        declaration.set_value(vec![ComponentValue::Ident("red".to_string())]);
         rule.set_declarations(vec![declaration]);
 
         let expected = [rule];
@@ -373,7 +703,7 @@ mod tests {
         rule.set_selector(Selector::ClassSelector("class".to_string()));
         let mut declaration = Declaration::new();
         declaration.set_property("color".to_string());
-        declaration.set_value(ComponentValue::Ident("red".to_string()));
+        declaration.set_value(vec![ComponentValue::Ident("red".to_string())]);
         rule.set_declarations(vec![declaration]);
 
         let expected = [rule];
@@ -397,17 +727,17 @@ mod tests {
         rule1.set_selector(Selector::TypeSelector("p".to_string()));
         let mut declaration1 = Declaration::new();
         declaration1.set_property("content".to_string());
-        declaration1.set_value(ComponentValue::StringToken("Hey".to_string()));
+        declaration1.set_value(vec![ComponentValue::StringToken("Hey".to_string())]);
         rule1.set_declarations(vec![declaration1]);
 
         let mut rule2 = QualifiedRule::new();
         rule2.set_selector(Selector::TypeSelector("h1".to_string()));
         let mut declaration2 = Declaration::new();
         declaration2.set_property("font-size".to_string());
-        declaration2.set_value(ComponentValue::Number(40.0));
+        declaration2.set_value(vec![ComponentValue::Number(40.0)]);
         let mut declaration3 = Declaration::new();
         declaration3.set_property("color".to_string());
-        declaration3.set_value(ComponentValue::Ident("blue".to_string()));
+        declaration3.set_value(vec![ComponentValue::Ident("blue".to_string())]);
         rule2.set_declarations(vec![declaration2, declaration3]);
 
         let expected = [rule1, rule2];
@@ -419,4 +749,283 @@ mod tests {
             i += 1;
         }
     }
+
+    /// 一括指定プロパティが個別指定プロパティに展開されるテスト。
+    #[test]
+    fn test_shorthand_declarations() {
+        let style = "p { margin: 0 auto; color: red; border: 1px; font: bold; }".to_string();
+        let mut parser = CssParser::new(CssTokenizer::new(style));
+        let cssom = parser.parse_stylesheet();
+        let properties: Vec<String> = cssom.rules[0]
+            .declarations
+            .iter()
+            .map(|d| d.property.clone())
+            .collect();
+        assert_eq!(17, properties.len());
+        assert_eq!("margin-top", properties[0]);
+        assert_eq!("margin-left", properties[3]);
+        assert_eq!("color", properties[4]);
+        assert_eq!("border-top-width", properties[5]);
+        assert_eq!(
+            vec![ComponentValue::Ident("auto".to_string())],
+            cssom.rules[0].declarations[1].value
+        );
+        // font の値にはサイズとファミリーが必要である。
+        assert_eq!(
+            vec![CssParseError::new(
+                CssParseErrorKind::InvalidDeclaration,
+                55
+            )],
+            parser.errors()
+        );
+    }
+
+    /// 複数のコンポーネント値を持つ宣言のテスト。
+    #[test]
+    fn test_multiple_component_values() {
+        let style = "p { margin-left: 0 auto; font-family: \"Noto Sans\", serif }".to_string();
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+
+        let mut declaration1 = Declaration::new();
+        declaration1.set_property("margin-left".to_string());
+        declaration1.set_value(vec![
+            ComponentValue::Number(0.0),
+            ComponentValue::Ident("auto".to_string()),
+        ]);
+        let declaration2 = Declaration::from_values(
+            "font-family",
+            vec![
+                ComponentValue::StringToken("Noto Sans".to_string()),
+                ComponentValue::Delim(','),
+                ComponentValue::Ident("serif".to_string()),
+            ],
+        );
+        assert_eq!(
+            vec![declaration1, declaration2],
+            cssom.rules[0].declarations
+        );
+    }
+
+    /// 関数を値に持つ宣言のテスト。
+    #[test]
+    fn test_function_values() {
+        let style = "p { color: rgb(255, 0, 0); background-image: url(foo.png); }".to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        assert_eq!(1, cssom.rules.len());
+        let declarations = &cssom.rules[0].declarations;
+        assert_eq!(2, declarations.len());
+        assert_eq!(
+            vec![ComponentValue::Function(
+                "rgb".to_string(),
+                vec![
+                    ComponentValue::Number(255.0),
+                    ComponentValue::Number(0.0),
+                    ComponentValue::Number(0.0)
+                ]
+            )],
+            declarations[0].value
+        );
+        assert_eq!(
+            vec![ComponentValue::Function(
+                "url".to_string(),
+                vec![ComponentValue::StringToken("foo.png".to_string())]
+            )],
+            declarations[1].value
+        );
+    }
+
+    /// 解釈できないルールや宣言を読み飛ばし、パースエラーを記録して解析を続けることを確認する。
+    #[test]
+    fn test_error_recovery() {
+        let style =
+            "h1 > p { color: red; } div { color: あ; width: ; height: 1px; } .{ } p { color: blue"
+                .to_string();
+        let t = CssTokenizer::new(style);
+        let mut parser = CssParser::new(t);
+        let cssom = parser.parse_stylesheet();
+
+        let mut rule1 = QualifiedRule::new();
+        rule1.set_selector(Selector::TypeSelector("div".to_string()));
+        let mut declaration1 = Declaration::new();
+        declaration1.set_property("height".to_string());
+        declaration1.set_value(vec![ComponentValue::Dimension(1.0, "px".to_string())]);
+        rule1.set_declarations(vec![declaration1]);
+
+        let mut rule2 = QualifiedRule::new();
+        rule2.set_selector(Selector::TypeSelector("p".to_string()));
+        let mut declaration2 = Declaration::new();
+        declaration2.set_property("color".to_string());
+        declaration2.set_value(vec![ComponentValue::Ident("blue".to_string())]);
+        rule2.set_declarations(vec![declaration2]);
+
+        assert_eq!(vec![rule1, rule2], cssom.rules);
+        assert_eq!(
+            vec![
+                CssParseError::new(CssParseErrorKind::InvalidSelector, 3),
+                CssParseError::new(CssParseErrorKind::BadToken('あ'), 36),
+                CssParseError::new(CssParseErrorKind::InvalidDeclaration, 48),
+                CssParseError::new(CssParseErrorKind::InvalidSelector, 66),
+                CssParseError::new(CssParseErrorKind::UnexpectedEof, 85),
+            ],
+            parser.errors()
+        );
+    }
+
+    /// 全称セレクタの解釈と照合のテスト。
+    #[test]
+    fn test_universal_selector() {
+        let style = "* { margin: 0; } *:hover { color: red; }".to_string();
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        assert_eq!(2, cssom.rules.len());
+        assert_eq!(Selector::UniversalSelector, cssom.rules[0].selector);
+        assert_eq!(
+            Selector::CompoundSelector(vec![
+                Selector::UniversalSelector,
+                Selector::PseudoClassSelector(PseudoClass::Hover),
+            ]),
+            cssom.rules[1].selector
+        );
+
+        let html = "<html><head></head><body><p>x</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let all = query_selector_all(document.clone(), &Selector::UniversalSelector);
+        // html、head、body、p 要素に一致し、テキストノードには一致しない。
+        assert_eq!(4, all.len());
+        assert!(!Selector::UniversalSelector.matches(&document.borrow()));
+    }
+
+    /// 疑似クラスのセレクタの解釈と照合のテスト。
+    #[test]
+    fn test_pseudo_classes() {
+        let style = "a:hover { color: red; } p:First-Child { color: red; } :visited { color: red; } p::before { color: red; } .x:link:hover { color: red; }"
+            .to_string();
+        let mut parser = CssParser::new(CssTokenizer::new(style));
+        let cssom = parser.parse_stylesheet();
+        let selectors: Vec<Selector> = cssom.rules.iter().map(|r| r.selector.clone()).collect();
+        assert_eq!(
+            vec![
+                Selector::CompoundSelector(vec![
+                    Selector::TypeSelector("a".to_string()),
+                    Selector::PseudoClassSelector(PseudoClass::Hover),
+                ]),
+                Selector::CompoundSelector(vec![
+                    Selector::TypeSelector("p".to_string()),
+                    Selector::PseudoClassSelector(PseudoClass::FirstChild),
+                ]),
+                Selector::PseudoClassSelector(PseudoClass::Visited),
+                Selector::CompoundSelector(vec![
+                    Selector::ClassSelector("x".to_string()),
+                    Selector::PseudoClassSelector(PseudoClass::Link),
+                    Selector::PseudoClassSelector(PseudoClass::Hover),
+                ]),
+            ],
+            selectors
+        );
+        // 疑似要素には対応していない。
+        assert_eq!(
+            vec![CssParseError::new(CssParseErrorKind::InvalidSelector, 81)],
+            parser.errors()
+        );
+
+        let html = "<html><head></head><body>text<p>1</p><p>2</p><a href=\"/\">x</a></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let p = query_selector_all(document.clone(), &Selector::TypeSelector("p".to_string()));
+        let first_child = &selectors[1];
+        assert!(first_child.matches(&p[0].borrow()));
+        assert!(!first_child.matches(&p[1].borrow()));
+
+        let a = query_selector(document, &Selector::TypeSelector("a".to_string()))
+            .expect("failed to find a");
+        let link = Selector::PseudoClassSelector(PseudoClass::Link);
+        assert!(link.matches(&a.borrow()));
+        assert!(!selectors[0].matches(&a.borrow()));
+        assert!(!selectors[2].matches(&a.borrow()));
+
+        // 要素の状態を変えると照合の結果が変わり、スタイルの再計算が必要になる。
+        a.borrow_mut().set_element_state(ElementState::HOVER, true);
+        a.borrow_mut()
+            .set_element_state(ElementState::VISITED, true);
+        assert!(a.borrow().is_style_dirty());
+        assert!(selectors[0].matches(&a.borrow()));
+        assert!(selectors[2].matches(&a.borrow()));
+        assert!(!link.matches(&a.borrow()));
+
+        a.borrow_mut().clear_style_dirty();
+        a.borrow_mut()
+            .set_element_state(ElementState::VISITED, true);
+        assert!(!a.borrow().is_style_dirty());
+        a.borrow_mut().set_element_state(ElementState::HOVER, false);
+        assert!(a.borrow().is_style_dirty());
+        assert!(!selectors[0].matches(&a.borrow()));
+    }
+
+    /// 文書内のすべての <style> タグのルールを集めるテスト。
+    #[test]
+    fn test_multiple_style_elements() {
+        let html = "<html><head><style>p { color: red; }</style><style></style></head><body><style>div { color: blue; } h1 {</style></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        let contents = get_style_contents(document.clone());
+        assert_eq!(3, contents.len());
+        assert_eq!("", contents[1]);
+
+        // それぞれのスタイルシートを別々に解釈するため、閉じられていないルール (h1 {) は後に加えるスタイルシートに影響しない。
+        let mut sheet = StyleSheet::new();
+        for content in contents.into_iter().rev() {
+            sheet.merge(CssParser::new(CssTokenizer::new(content)).parse_stylesheet());
+        }
+        let selectors: Vec<Selector> = sheet.rules.iter().map(|r| r.selector.clone()).collect();
+        assert_eq!(
+            vec![
+                Selector::TypeSelector("div".to_string()),
+                Selector::TypeSelector("h1".to_string()),
+                Selector::TypeSelector("p".to_string()),
+            ],
+            selectors
+        );
+
+        let text = Rc::new(RefCell::new(Node::new(NodeKind::Text(
+            " span { }".to_string(),
+        ))));
+        let style = query_selector(
+            document.clone(),
+            &Selector::TypeSelector("style".to_string()),
+        )
+        .expect("failed to find <style>");
+        Node::append_child(&style, text).expect("failed to append");
+        assert_eq!(
+            "p { color: red; } span { }\n\ndiv { color: blue; } h1 {",
+            get_style_content(document)
+        );
+    }
+
+    /// セレクタと DOM ツリーのノードの照合のテスト。
+    #[test]
+    fn test_selector_matches() {
+        let html = "<html><head></head><body><p id=\"a\">x</p><div><p class=\"b\">y</p></div></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        let p = query_selector_all(document.clone(), &Selector::TypeSelector("p".to_string()));
+        assert_eq!(2, p.len());
+        assert!(Selector::IdSelector("a".to_string()).matches(&p[0].borrow()));
+        assert!(!Selector::IdSelector("a".to_string()).matches(&p[1].borrow()));
+
+        let b = query_selector(document.clone(), &Selector::ClassSelector("b".to_string()))
+            .expect("failed to find .b");
+        assert!(Rc::ptr_eq(&p[1], &b));
+        // テキストノードはどのセレクタにも一致しない。
+        let text = b.borrow().first_child().expect("failed to get a text node");
+        assert!(!Selector::TypeSelector("p".to_string()).matches(&text.borrow()));
+
+        assert!(query_selector(document, &Selector::IdSelector("c".to_string())).is_none());
+    }
 }
@@ -1,2 +1,4 @@
 pub mod cssom;
+pub mod parse_error;
+pub mod shorthand;
 pub mod token;
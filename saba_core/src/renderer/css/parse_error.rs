@@ -0,0 +1,49 @@
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+/// CSS の構文解析で検出したパースエラーの種類を表す列挙型。
+/// パースエラーが発生した場合、そのルールや宣言を読み飛ばしてスタイルシートの解析を続ける。
+/// https://www.w3.org/TR/css-syntax-3/#error-handling
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CssParseErrorKind {
+    /// トークナイザーが解釈できない文字を検出した。
+    BadToken(char),
+    /// セレクタとして解釈できないトークンが現れたため、ルールを読み飛ばした。
+    InvalidSelector,
+    /// 宣言として解釈できないトークンが現れたため、宣言を読み飛ばした。
+    InvalidDeclaration,
+    /// ルールや宣言が閉じられる前に入力が終わった。
+    UnexpectedEof,
+}
+
+impl Display for CssParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            CssParseErrorKind::BadToken(c) => write!(f, "bad-token {:?}", c),
+            CssParseErrorKind::InvalidSelector => write!(f, "invalid-selector"),
+            CssParseErrorKind::InvalidDeclaration => write!(f, "invalid-declaration"),
+            CssParseErrorKind::UnexpectedEof => write!(f, "unexpected-eof"),
+        }
+    }
+}
+
+/// パースエラーの種類と、エラーを検出したトークンの入力文字列の位置 (バイト単位) を保持する構造体。
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CssParseError {
+    kind: CssParseErrorKind,
+    offset: usize,
+}
+
+impl CssParseError {
+    pub fn new(kind: CssParseErrorKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+
+    pub fn kind(&self) -> CssParseErrorKind {
+        self.kind
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
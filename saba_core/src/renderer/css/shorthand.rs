@@ -0,0 +1,444 @@
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::Declaration;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 上、右、下、左の順の辺の名前。
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+/// https://www.w3.org/TR/css-backgrounds-3/#typedef-line-style
+const BORDER_STYLES: [&str; 10] = [
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+/// https://www.w3.org/TR/css-backgrounds-3/#typedef-line-width
+const BORDER_WIDTHS: [&str; 3] = ["thin", "medium", "thick"];
+
+/// https://www.w3.org/TR/css-backgrounds-3/#typedef-repeat-style
+const BACKGROUND_REPEATS: [&str; 6] = [
+    "repeat",
+    "repeat-x",
+    "repeat-y",
+    "no-repeat",
+    "space",
+    "round",
+];
+
+/// background-position と background-attachment のキーワード。これらの個別指定プロパティには展開しない。
+const BACKGROUND_IGNORED: [&str; 7] = [
+    "left", "right", "top", "bottom", "center", "scroll", "fixed",
+];
+
+/// https://www.w3.org/TR/css-fonts-4/#absolute-size-value
+/// https://www.w3.org/TR/css-fonts-4/#relative-size-value
+const FONT_SIZES: [&str; 10] = [
+    "xx-small",
+    "x-small",
+    "small",
+    "medium",
+    "large",
+    "x-large",
+    "xx-large",
+    "xxx-large",
+    "larger",
+    "smaller",
+];
+
+/// プロパティが、個別指定プロパティに展開する一括指定プロパティ (ショートハンドプロパティ) かどうかを返す。
+/// https://www.w3.org/TR/css-cascade-5/#shorthand-property
+pub fn is_shorthand(property: &str) -> bool {
+    matches!(
+        property,
+        "margin"
+            | "padding"
+            | "border"
+            | "border-top"
+            | "border-right"
+            | "border-bottom"
+            | "border-left"
+            | "border-width"
+            | "border-style"
+            | "border-color"
+            | "background"
+            | "font"
+    )
+}
+
+/// 一括指定プロパティの値を、個別指定プロパティ (ロングハンドプロパティ) の宣言に展開する。
+/// 値に含まれない個別指定プロパティは、そのプロパティの初期値の宣言とする。
+/// 一括指定プロパティではない場合や、値が一括指定プロパティの構文に一致しない場合は None を返す。
+/// https://www.w3.org/TR/css-cascade-5/#shorthand-property
+pub fn expand_shorthand(property: &str, values: &[ComponentValue]) -> Option<Vec<Declaration>> {
+    match property {
+        "margin" | "padding" => expand_sides(property, "", values),
+        "border-width" | "border-style" | "border-color" => {
+            let suffix = property.trim_start_matches("border");
+            expand_sides("border", suffix, values)
+        }
+        "border" => {
+            let mut declarations = Vec::new();
+            for side in SIDES {
+                declarations.extend(expand_border(&format!("border-{}", side), values)?);
+            }
+            Some(declarations)
+        }
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            expand_border(property, values)
+        }
+        "background" => expand_background(values),
+        "font" => expand_font(values),
+        _ => None,
+    }
+}
+
+fn declaration(property: &str, value: ComponentValue) -> Declaration {
+    Declaration::from_values(property, vec![value])
+}
+
+fn ident(value: &str) -> ComponentValue {
+    ComponentValue::Ident(value.to_string())
+}
+
+/// 値がキーワードの一覧に含まれる識別子かどうかを返す。キーワードの大文字と小文字は区別しない。
+fn is_keyword(value: &ComponentValue, keywords: &[&str]) -> bool {
+    match value {
+        ComponentValue::Ident(name) => keywords
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(name)),
+        _ => false,
+    }
+}
+
+/// margin や border-width のように、1つから4つの値を上、右、下、左の辺に割り当てる。
+/// 1つの場合はすべての辺、2つの場合は上下と左右、3つの場合は上、左右、下の順に割り当てる。
+/// https://www.w3.org/TR/css-box-4/#margin-shorthand
+fn expand_sides(prefix: &str, suffix: &str, values: &[ComponentValue]) -> Option<Vec<Declaration>> {
+    let indexes = match values.len() {
+        1 => [0, 0, 0, 0],
+        2 => [0, 1, 0, 1],
+        3 => [0, 1, 2, 1],
+        4 => [0, 1, 2, 3],
+        _ => return None,
+    };
+
+    Some(
+        SIDES
+            .iter()
+            .zip(indexes)
+            .map(|(side, i)| {
+                declaration(&format!("{}-{}{}", prefix, side, suffix), values[i].clone())
+            })
+            .collect(),
+    )
+}
+
+/// border-top などの値を、順不同の線の幅、線の種類、色に分けて、その辺の個別指定プロパティに展開する。
+/// https://www.w3.org/TR/css-backgrounds-3/#border-shorthands
+fn expand_border(property: &str, values: &[ComponentValue]) -> Option<Vec<Declaration>> {
+    let mut width = None;
+    let mut style = None;
+    let mut color = None;
+
+    for value in values {
+        let slot = if is_keyword(value, &BORDER_STYLES) {
+            &mut style
+        } else if is_keyword(value, &BORDER_WIDTHS)
+            || matches!(
+                value,
+                ComponentValue::Number(_) | ComponentValue::Dimension(_, _)
+            )
+        {
+            &mut width
+        } else if matches!(
+            value,
+            ComponentValue::Ident(_)
+                | ComponentValue::HashToken(_)
+                | ComponentValue::Function(_, _)
+        ) {
+            &mut color
+        } else {
+            return None;
+        };
+
+        // 同じ種類の値を2回以上指定することはできない。
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some(value.clone());
+    }
+
+    Some(Vec::from([
+        declaration(
+            &format!("{}-width", property),
+            width.unwrap_or(ident("medium")),
+        ),
+        declaration(
+            &format!("{}-style", property),
+            style.unwrap_or(ident("none")),
+        ),
+        declaration(
+            &format!("{}-color", property),
+            color.unwrap_or(ident("currentcolor")),
+        ),
+    ]))
+}
+
+/// background の値を、背景色、背景画像、背景画像の繰り返し方法の個別指定プロパティに展開する。
+/// 背景画像の位置と固定方法は対応していないため、値に含まれていても展開しない。
+/// https://www.w3.org/TR/css-backgrounds-3/#background
+fn expand_background(values: &[ComponentValue]) -> Option<Vec<Declaration>> {
+    let mut color = None;
+    let mut image = None;
+    let mut repeat = None;
+
+    for value in values {
+        let slot = match value {
+            ComponentValue::Function(name, _) if name.eq_ignore_ascii_case("url") => &mut image,
+            _ if is_keyword(value, &["none"]) => &mut image,
+            _ if is_keyword(value, &BACKGROUND_REPEATS) => &mut repeat,
+            _ if is_keyword(value, &BACKGROUND_IGNORED) => continue,
+            ComponentValue::Number(_)
+            | ComponentValue::Dimension(_, _)
+            | ComponentValue::Percentage(_) => continue,
+            ComponentValue::Ident(_)
+            | ComponentValue::HashToken(_)
+            | ComponentValue::Function(_, _) => &mut color,
+            _ => return None,
+        };
+
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some(value.clone());
+    }
+
+    Some(Vec::from([
+        declaration("background-color", color.unwrap_or(ident("transparent"))),
+        declaration("background-image", image.unwrap_or(ident("none"))),
+        declaration("background-repeat", repeat.unwrap_or(ident("repeat"))),
+    ]))
+}
+
+/// font の値を、フォントのスタイル、太さ、サイズ、行の高さ、フォントファミリーの個別指定プロパティに展開する。
+/// 値は [スタイルや太さ] サイズ [/ 行の高さ] ファミリー の順に指定する。サイズとファミリーは省略できない。
+/// https://www.w3.org/TR/css-fonts-4/#font-prop
+fn expand_font(values: &[ComponentValue]) -> Option<Vec<Declaration>> {
+    let mut style = None;
+    let mut variant = None;
+    let mut weight = None;
+    let mut i = 0;
+
+    // サイズより前に、スタイル、バリアント、太さを順不同で指定できる。
+    // normal はいずれのプロパティの初期値でもあるため、どのプロパティにも割り当てない。
+    while let Some(value) = values.get(i) {
+        let slot = if is_keyword(value, &["normal"]) {
+            i += 1;
+            continue;
+        } else if is_keyword(value, &["italic", "oblique"]) {
+            &mut style
+        } else if is_keyword(value, &["small-caps"]) {
+            &mut variant
+        } else if is_keyword(value, &["bold", "bolder", "lighter"])
+            || matches!(value, ComponentValue::Number(_))
+        {
+            &mut weight
+        } else {
+            break;
+        };
+
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some(value.clone());
+        i += 1;
+    }
+
+    let size = match values.get(i) {
+        Some(value @ (ComponentValue::Dimension(_, _) | ComponentValue::Percentage(_))) => value,
+        Some(value) if is_keyword(value, &FONT_SIZES) => value,
+        _ => return None,
+    };
+    i += 1;
+
+    let mut line_height = None;
+    if values.get(i) == Some(&ComponentValue::Delim('/')) {
+        line_height = Some(values.get(i + 1)?.clone());
+        i += 2;
+    }
+
+    // 残りの値はカンマ区切りのフォントファミリーのリストとする。
+    let family = &values[i..];
+    if !family.iter().all(|value| {
+        matches!(
+            value,
+            ComponentValue::Ident(_) | ComponentValue::StringToken(_) | ComponentValue::Delim(',')
+        )
+    }) || !matches!(
+        family.first(),
+        Some(ComponentValue::Ident(_) | ComponentValue::StringToken(_))
+    ) {
+        return None;
+    }
+
+    Some(Vec::from([
+        declaration("font-style", style.unwrap_or(ident("normal"))),
+        declaration("font-variant", variant.unwrap_or(ident("normal"))),
+        declaration("font-weight", weight.unwrap_or(ident("normal"))),
+        declaration("font-size", size.clone()),
+        declaration("line-height", line_height.unwrap_or(ident("normal"))),
+        Declaration::from_values("font-family", family.to_vec()),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    /// 値が1つの宣言のプロパティと値の組を返す。
+    fn properties(declarations: &[Declaration]) -> Vec<(String, ComponentValue)> {
+        declarations
+            .iter()
+            .map(|d| {
+                assert_eq!(1, d.value.len());
+                (d.property.clone(), d.value[0].clone())
+            })
+            .collect()
+    }
+
+    fn px(value: f64) -> ComponentValue {
+        ComponentValue::Dimension(value, "px".to_string())
+    }
+
+    /// margin と padding の値の数に応じた辺への割り当てのテスト。
+    #[test]
+    fn test_expand_sides() {
+        let margin = expand_shorthand("margin", &[px(1.0), ident("auto")]).unwrap();
+        assert_eq!(
+            vec![
+                ("margin-top".to_string(), px(1.0)),
+                ("margin-right".to_string(), ident("auto")),
+                ("margin-bottom".to_string(), px(1.0)),
+                ("margin-left".to_string(), ident("auto")),
+            ],
+            properties(&margin)
+        );
+
+        let padding = expand_shorthand("padding", &[px(1.0), px(2.0), px(3.0)]).unwrap();
+        assert_eq!(vec![px(2.0)], padding[3].value);
+        assert_eq!("padding-left", padding[3].property);
+
+        let border_style = expand_shorthand("border-style", &[ident("solid")]).unwrap();
+        assert_eq!("border-top-style", border_style[0].property);
+
+        assert!(expand_shorthand("margin", &[]).is_none());
+        assert!(expand_shorthand("margin", &vec![px(1.0); 5]).is_none());
+        assert!(expand_shorthand("color", &[ident("red")]).is_none());
+    }
+
+    /// border と background の順不同の値の割り当てと、省略された値の初期値のテスト。
+    #[test]
+    fn test_expand_border_and_background() {
+        let border = expand_shorthand("border", &[ident("red"), px(1.0), ident("solid")]).unwrap();
+        assert_eq!(12, border.len());
+        assert_eq!(
+            vec![
+                ("border-top-width".to_string(), px(1.0)),
+                ("border-top-style".to_string(), ident("solid")),
+                ("border-top-color".to_string(), ident("red")),
+            ],
+            properties(&border[..3])
+        );
+        assert_eq!("border-left-color", border[11].property);
+
+        let border_top = expand_shorthand("border-top", &[ident("dashed")]).unwrap();
+        assert_eq!(
+            vec![
+                ("border-top-width".to_string(), ident("medium")),
+                ("border-top-style".to_string(), ident("dashed")),
+                ("border-top-color".to_string(), ident("currentcolor")),
+            ],
+            properties(&border_top)
+        );
+        assert!(expand_shorthand("border", &[px(1.0), px(2.0)]).is_none());
+
+        let url = ComponentValue::Function(
+            "url".to_string(),
+            vec![ComponentValue::StringToken("a.png".to_string())],
+        );
+        let background = expand_shorthand(
+            "background",
+            &[
+                ComponentValue::HashToken("#ff0000".to_string()),
+                url.clone(),
+                ident("no-repeat"),
+                ident("center"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                (
+                    "background-color".to_string(),
+                    ComponentValue::HashToken("#ff0000".to_string())
+                ),
+                ("background-image".to_string(), url),
+                ("background-repeat".to_string(), ident("no-repeat")),
+            ],
+            properties(&background)
+        );
+    }
+
+    /// font の値の割り当てのテスト。
+    #[test]
+    fn test_expand_font() {
+        let font = expand_shorthand(
+            "font",
+            &[
+                ident("italic"),
+                ident("bold"),
+                px(16.0),
+                ComponentValue::Delim('/'),
+                ComponentValue::Number(1.5),
+                ComponentValue::StringToken("Noto Sans".to_string()),
+                ComponentValue::Delim(','),
+                ident("sans-serif"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("font-style".to_string(), ident("italic")),
+                ("font-variant".to_string(), ident("normal")),
+                ("font-weight".to_string(), ident("bold")),
+                ("font-size".to_string(), px(16.0)),
+                ("line-height".to_string(), ComponentValue::Number(1.5)),
+            ],
+            properties(&font[..5])
+        );
+        assert_eq!("font-family", font[5].property);
+        assert_eq!(
+            vec![
+                ComponentValue::StringToken("Noto Sans".to_string()),
+                ComponentValue::Delim(','),
+                ident("sans-serif"),
+            ],
+            font[5].value
+        );
+
+        let font = expand_shorthand("font", &[ident("small"), ident("serif")]).unwrap();
+        assert_eq!(vec![ident("normal")], font[0].value);
+        assert_eq!(vec![ident("small")], font[3].value);
+
+        // サイズまたはファミリーがない場合は展開できない。
+        assert!(expand_shorthand("font", &[ident("bold"), ident("serif")]).is_none());
+        assert!(expand_shorthand("font", &[px(16.0)]).is_none());
+        assert!(expand_shorthand(
+            "font",
+            &[px(16.0), ComponentValue::Delim(','), ident("serif")]
+        )
+        .is_none());
+    }
+}
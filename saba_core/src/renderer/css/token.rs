@@ -10,6 +10,11 @@ pub enum CssToken {
     Delim(char),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-number-token
     Number(f64),
+    /// 単位付きの数値 (例: 16px、1.5em)。数値と単位の文字列を持つ。
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-dimension-token
+    Dimension(f64, String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-percentage-token
+    Percentage(f64),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-colon-token
     Colon,
     /// https://www.w3.org/TR/css-syntax-3/#typedef-semicolon-token
@@ -28,6 +33,15 @@ pub enum CssToken {
     StringToken(String),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-at-keyword-token
     AtKeyword(String),
+    /// 関数 (例: rgb(255, 0, 0)) を表す。関数名と引数を持つ。
+    /// トークナイザーは関数名と開き丸括弧までを1つのトークンとし、引数は空のまま返す。
+    /// 引数は CssParser が閉じ丸括弧までのトークンから設定する。
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-function-token
+    /// https://www.w3.org/TR/css-syntax-3/#consume-function
+    Function(String, Vec<CssToken>),
+    /// 解釈できない文字を表す。その文字と、入力文字列の位置 (バイト単位) を持つ。
+    /// CssParser はこのトークンを含むルールや宣言を読み飛ばし、パースエラーとして記録する。
+    BadToken(char, usize),
 }
 
 /// トークナイザーを行う構造体。
@@ -35,6 +49,10 @@ pub enum CssToken {
 pub struct CssTokenizer {
     pos: usize,
     input: Vec<char>,
+    // url( の直後で、引用符で囲まれていない URL を読み取る必要があるかどうか
+    in_url: bool,
+    // 最後に返したトークンの開始位置 (文字単位)
+    token_start: usize,
 }
 
 impl CssTokenizer {
@@ -42,19 +60,31 @@ impl CssTokenizer {
         Self {
             pos: 0,
             input: css.chars().collect(),
+            in_url: false,
+            token_start: 0,
         }
     }
 
+    /// 最後に返したトークンの開始位置 (文字単位) を返す。入力の最後に達した場合は入力の文字数を返す。
+    pub fn token_start(&self) -> usize {
+        self.token_start
+    }
+
+    /// 文字単位の位置を、入力文字列のバイト単位の位置に変換する。
+    pub fn byte_offset(&self, pos: usize) -> usize {
+        self.input[..pos].iter().map(|c| c.len_utf8()).sum()
+    }
+
     /// 再びダブルクォートかシングルクォートが現れるまで、入力を文字として解釈する。
     fn consume_string_token(&mut self) -> String {
         let mut s = String::new();
 
         loop {
+            self.pos += 1;
             if self.pos >= self.input.len() {
                 return s;
             }
 
-            self.pos += 1;
             let c = self.input[self.pos];
             match c {
                 '"' | '\'' => break,
@@ -99,6 +129,49 @@ impl CssTokenizer {
         num
     }
 
+    /// "/*" から "*/" までをコメントとして読み飛ばす。
+    /// 閉じられていないコメントの場合、入力の最後までを読み飛ばす。
+    /// https://www.w3.org/TR/css-syntax-3/#consume-comments
+    fn consume_comment(&mut self) {
+        // "/*" をスキップする。
+        self.pos += 2;
+
+        while self.pos < self.input.len() {
+            if self.input[self.pos] == '*'
+                && self.pos + 1 < self.input.len()
+                && self.input[self.pos + 1] == '/'
+            {
+                self.pos += 2;
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// 識別子を読み取り、直後に開き丸括弧が続く場合は関数トークン、それ以外の場合は識別子トークンを返す。
+    /// 関数トークンの場合は開き丸括弧までを消費する。
+    /// https://www.w3.org/TR/css-syntax-3/#consume-ident-like-token
+    fn consume_ident_like_token(&mut self) -> CssToken {
+        let name = self.consume_ident_token();
+        if self.input.get(self.pos) == Some(&'(') {
+            self.in_url = name.eq_ignore_ascii_case("url");
+            return CssToken::Function(name, Vec::new());
+        }
+        self.pos -= 1;
+        CssToken::Ident(name)
+    }
+
+    /// 引用符で囲まれていない URL を閉じ丸括弧の直前まで読み取る。前後の空白は含めない。
+    /// https://www.w3.org/TR/css-syntax-3/#consume-url-token
+    fn consume_url(&mut self) -> String {
+        let mut s = String::new();
+        while self.pos < self.input.len() && self.input[self.pos] != ')' {
+            s.push(self.input[self.pos]);
+            self.pos += 1;
+        }
+        String::from(s.trim_end())
+    }
+
     /// 文字、数字、ハイフン、アンダースコアが出続ける間、識別子として扱う。
     /// それ以外の文字が登場した場合、今までの文字を返す。
     /// https://www.w3.org/TR/css-syntax-3/#consume-ident-like-token
@@ -109,6 +182,9 @@ impl CssTokenizer {
 
         loop {
             self.pos += 1;
+            if self.pos >= self.input.len() {
+                break;
+            }
             let c = self.input[self.pos];
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => {
@@ -129,11 +205,25 @@ impl Iterator for CssTokenizer {
     /// https://www.w3c.org/TR/css-syntax-3/#consume-token
     fn next(&mut self) -> Option<Self::Item> {
         loop {
+            self.token_start = self.pos;
             if self.pos >= self.input.len() {
                 return None;
             }
 
             let c = self.input[self.pos];
+
+            // url( の直後の場合、空白を読み飛ばし、引用符で始まらない URL を文字列トークンとして返す。
+            if self.in_url {
+                if c.is_ascii_whitespace() {
+                    self.pos += 1;
+                    continue;
+                }
+                self.in_url = false;
+                if c != '"' && c != '\'' && c != ')' {
+                    return Some(CssToken::StringToken(self.consume_url()));
+                }
+            }
+
             let token = match c {
                 // 次のトークンを決定する。
                 '(' => CssToken::OpenParenthesis,
@@ -144,19 +234,40 @@ impl Iterator for CssTokenizer {
                 ';' => CssToken::SemiColon,
                 '{' => CssToken::OpenCurly,
                 '}' => CssToken::CloseCurly,
-                ' ' | '\n' => {
+                ' ' | '\n' | '\t' | '\r' | '\x0C' => {
                     self.pos += 1;
                     continue;
                 }
+                // "/*" の場合、コメントとして読み飛ばす。それ以外の場合、<delim-token> を返す。
+                '/' => {
+                    if self.input.get(self.pos + 1) == Some(&'*') {
+                        self.consume_comment();
+                        continue;
+                    }
+                    CssToken::Delim('/')
+                }
                 // ダブルクォートとシングルクォートが現れたら文字列トークンを返却する。
                 '"' | '\'' => {
                     let value = self.consume_string_token();
                     CssToken::StringToken(value)
                 }
+                // 数値の直後に % が続く場合は <percentage-token>、識別子が続く場合は単位として <dimension-token> を返す。
+                // https://www.w3.org/TR/css-syntax-3/#consume-numeric-token
                 '0'..='9' => {
-                    let t = CssToken::Number(self.consume_numeric_token());
-                    self.pos -= 1;
-                    t
+                    let num = self.consume_numeric_token();
+                    match self.input.get(self.pos) {
+                        // '%' は最後の self.pos += 1 で消費する。
+                        Some('%') => CssToken::Percentage(num),
+                        Some('a'..='z' | 'A'..='Z') => {
+                            let t = CssToken::Dimension(num, self.consume_ident_token());
+                            self.pos -= 1;
+                            t
+                        }
+                        _ => {
+                            self.pos -= 1;
+                            CssToken::Number(num)
+                        }
+                    }
                 }
                 // ハッシュタグの場合、常に #ID 形式の ID セレクタとして扱う。
                 '#' => {
@@ -165,17 +276,15 @@ impl Iterator for CssTokenizer {
                     CssToken::HashToken(value)
                 }
                 // ハイフンの場合、常に識別子の1つとして扱う(負の値は扱わないこととする)。
-                '-' => {
-                    let t = CssToken::Ident(self.consume_ident_token());
-                    self.pos -= 1;
-                    t
-                }
+                '-' => self.consume_ident_like_token(),
                 // アットマークの場合、次の3文字が識別子として有効な文字の場合、<at-keyword-token> トークンを返す。
                 // それ以外の場合、<delim-token> を返す。
                 '@' => {
-                    if self.input[self.pos + 1].is_ascii_alphabetic()
-                        && self.input[self.pos + 2].is_alphanumeric()
-                        && self.input[self.pos + 3].is_alphanumeric()
+                    // 入力の最後を超える位置は空白として扱う。
+                    let peek = |i: usize| self.input.get(self.pos + i).copied().unwrap_or(' ');
+                    if peek(1).is_ascii_alphabetic()
+                        && peek(2).is_alphanumeric()
+                        && peek(3).is_alphanumeric()
                     {
                         // '@' をスキップする。
                         self.pos += 1;
@@ -187,14 +296,12 @@ impl Iterator for CssTokenizer {
                     }
                 }
                 // 小文字、大文字、アンダースコアの場合、識別子トークン(Ident) を作成して返す。
-                'a'..='z' | 'A'..='Z' | '_' => {
-                    let t = CssToken::Ident(self.consume_ident_token());
-                    self.pos -= 1;
-                    t
-                }
-                _ => {
-                    unimplemented!("char {} is not supported yet", c);
-                }
+                // 直後に開き丸括弧が続く場合は関数トークン(Function) を作成して返す。
+                'a'..='z' | 'A'..='Z' | '_' => self.consume_ident_like_token(),
+                // その他の ASCII の記号は <delim-token> を返す。
+                c if c.is_ascii_punctuation() => CssToken::Delim(c),
+                // 上記以外の文字は解釈できないため、位置とともに BadToken を返す。
+                _ => CssToken::BadToken(c, self.byte_offset(self.pos)),
             };
 
             self.pos += 1;
@@ -290,4 +397,109 @@ mod tests {
         }
         assert!(t.next().is_none());
     }
+
+    #[test]
+    /// コメントが読み飛ばされ、前後のトークンに影響しないことを確認する。
+    fn test_comments() {
+        let style = "/* header */ p { /* c1 */ color: /* c2 */ red; } /* unterminated".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Ident("p".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("color".to_string()),
+            CssToken::Colon,
+            CssToken::Ident("red".to_string()),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+
+    #[test]
+    /// 単位付きの数値とパーセンテージが、数値と単位を持つトークンになることを確認する。
+    fn test_dimensions() {
+        let style = "p { width: 80%; margin: 16px; font-size: 1.5em; z: 3 }".to_string();
+        let t = CssTokenizer::new(style);
+        let values: Vec<CssToken> = t
+            .filter(|t| {
+                matches!(
+                    t,
+                    CssToken::Number(_) | CssToken::Dimension(_, _) | CssToken::Percentage(_)
+                )
+            })
+            .collect();
+        assert_eq!(
+            alloc::vec![
+                CssToken::Percentage(80.0),
+                CssToken::Dimension(16.0, "px".to_string()),
+                CssToken::Dimension(1.5, "em".to_string()),
+                CssToken::Number(3.0),
+            ],
+            values
+        );
+
+        // 入力の最後にある単位も読み取れる。
+        let mut t = CssTokenizer::new("10px".to_string());
+        assert_eq!(Some(CssToken::Dimension(10.0, "px".to_string())), t.next());
+        assert!(t.next().is_none());
+    }
+
+    #[test]
+    /// 関数名と開き丸括弧が関数トークンになり、url( の後の引用符のない URL が文字列トークンになることを確認する。
+    fn test_functions() {
+        let style = "rgb(255, 0, 0) url( foo/bar.png ) url(\"a.png\")".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Function("rgb".to_string(), Vec::new()),
+            CssToken::Number(255.0),
+            CssToken::Delim(','),
+            CssToken::Number(0.0),
+            CssToken::Delim(','),
+            CssToken::Number(0.0),
+            CssToken::CloseParenthesis,
+            CssToken::Function("url".to_string(), Vec::new()),
+            CssToken::StringToken("foo/bar.png".to_string()),
+            CssToken::CloseParenthesis,
+            CssToken::Function("url".to_string(), Vec::new()),
+            CssToken::StringToken("a.png".to_string()),
+            CssToken::CloseParenthesis,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+
+    #[test]
+    /// 解釈できない文字が BadToken になり、以降の字句解析を続けることを確認する。
+    fn test_bad_token() {
+        let style = "/* ああ */ \u{1} p { b: あ; }".to_string();
+        let mut t = CssTokenizer::new(style);
+        assert_eq!(Some(CssToken::BadToken('\u{1}', 13)), t.next());
+        assert_eq!(13, t.byte_offset(t.token_start()));
+        let expected = [
+            CssToken::Ident("p".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("b".to_string()),
+            CssToken::Colon,
+            CssToken::BadToken('あ', 22),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+
+        // 閉じられていない文字列や入力の最後のアットマークでもパニックしない。
+        let mut t = CssTokenizer::new("\"abc @".to_string());
+        assert_eq!(Some(CssToken::StringToken("abc @".to_string())), t.next());
+        let mut t = CssTokenizer::new("* @".to_string());
+        assert_eq!(Some(CssToken::Delim('*')), t.next());
+        assert_eq!(Some(CssToken::Delim('@')), t.next());
+        assert!(t.next().is_none());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::renderer::dom::node::Element;
+use crate::renderer::css::cssom::Selector;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
@@ -8,79 +8,129 @@ use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+/// traverse_tree でノードを訪れた後のたどり方を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraversalControl {
+    /// 子ノードをたどってから、次のノードへ進む。
+    Continue,
+    /// 子ノードをたどらずに、次のノードへ進む。
+    SkipChildren,
+    /// たどるのをやめる。
+    Stop,
+}
+
+/// root とその子孫を文書内に現れる順 (深さ優先の行きがけ順) にたどり、各ノードで visit を呼ぶ。
+/// 再帰呼び出しの代わりにこれからたどるノードを明示的なスタックに積むため、深いツリーや兄弟ノードの多いツリーでもコールスタックを溢れさせない。
+pub fn traverse_tree(
+    root: &Rc<RefCell<Node>>,
+    visit: &mut dyn FnMut(&Rc<RefCell<Node>>) -> TraversalControl,
+) {
+    let mut stack = Vec::new();
+    stack.push(root.clone());
+    while let Some(node) = stack.pop() {
+        match visit(&node) {
+            TraversalControl::Continue => {
+                // スタックから先頭の子ノードが先に取り出されるように、逆順に積む。
+                let children: Vec<_> = node.borrow().children().collect();
+                stack.extend(children.into_iter().rev());
+            }
+            TraversalControl::SkipChildren => {}
+            TraversalControl::Stop => return,
+        }
+    }
+}
+
+/// root とその子孫のうち、predicate を満たす最初のノードを文書内に現れる順に探す。
+pub(crate) fn find_in_tree(
+    root: &Rc<RefCell<Node>>,
+    predicate: &dyn Fn(&Node) -> bool,
+) -> Option<Rc<RefCell<Node>>> {
+    let mut found = None;
+    traverse_tree(root, &mut |node| {
+        if predicate(&node.borrow()) {
+            found = Some(node.clone());
+            return TraversalControl::Stop;
+        }
+        TraversalControl::Continue
+    });
+    found
+}
+
 /// 引数の要素の種類 (element_kind) と一致した最初のノードを返す。返すノードは１つのみである。
-/// 引数のノード (node) から再帰的に要素の種類をチェックする。
+/// 引数のノード (node) とその子孫を文書内に現れる順にチェックする。
 pub fn get_target_element_node(
     node: Option<Rc<RefCell<Node>>>,
     element_kind: ElementKind,
 ) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            // 現在のノードの要素が element_kind と同じであれば、そのノードを返却する。
-            if n.borrow().kind()
-                == NodeKind::Element(Element::new(&element_kind.to_string(), Vec::new()))
-            {
-                return Some(n.clone());
+    find_in_tree(&node?, &|n| n.get_element_kind() == Some(element_kind))
+}
+
+/// 引数の要素の種類 (element_kind) と一致するすべてのノードを、文書内に現れる順に返す。
+/// 引数のノード (node) とその子孫をチェックする。
+pub fn get_element_nodes_by_kind(
+    node: Option<Rc<RefCell<Node>>>,
+    element_kind: ElementKind,
+) -> Vec<Rc<RefCell<Node>>> {
+    let mut result = Vec::new();
+    if let Some(n) = node {
+        traverse_tree(&n, &mut |n| {
+            if n.borrow().get_element_kind() == Some(element_kind) {
+                result.push(n.clone());
             }
+            TraversalControl::Continue
+        });
+    }
+    result
+}
 
-            // 現在のノードの要素が element_kind と異なる場合、子ノードと兄弟ノードに対して再帰的に呼び出す。
-            let result1 = get_target_element_node(n.borrow().first_child(), element_kind);
-            let result2 = get_target_element_node(n.borrow().next_sibling(), element_kind);
+/// root の子孫ノードのうち、セレクタに一致する最初の要素を返す。root 自身は対象に含まない。
+/// https://dom.spec.whatwg.org/#dom-parentnode-queryselector
+pub fn query_selector(root: Rc<RefCell<Node>>, selector: &Selector) -> Option<Rc<RefCell<Node>>> {
+    let mut descendants = root.borrow().descendants();
+    descendants.find(|n| selector.matches(&n.borrow()))
+}
 
-            if result1.is_none() && result2.is_none() {
-                return None;
-            }
+/// root の子孫ノードのうち、セレクタに一致するすべての要素を文書内に現れる順に返す。root 自身は対象に含まない。
+/// https://dom.spec.whatwg.org/#dom-parentnode-queryselectorall
+pub fn query_selector_all(root: Rc<RefCell<Node>>, selector: &Selector) -> Vec<Rc<RefCell<Node>>> {
+    let descendants = root.borrow().descendants();
+    descendants
+        .filter(|n| selector.matches(&n.borrow()))
+        .collect()
+}
 
-            if result1.is_none() {
-                return result2;
-            }
-            result1
-        }
-        None => None,
-    }
+/// すべての <style> タグのコンテンツを文書内に現れる順に取得できる関数
+/// 1つの <style> タグに複数のテキストノードがある場合、それらを連結したものを1つのコンテンツとする。
+/// https://html.spec.whatwg.org/multipage/semantics.html#update-a-style-block
+pub fn get_style_contents(root: Rc<RefCell<Node>>) -> Vec<String> {
+    get_element_nodes_by_kind(Some(root), ElementKind::Style)
+        .iter()
+        .map(|style_node| style_node.borrow().text_content())
+        .collect()
 }
 
 /// <style>　タグのコンテンツを取得できる関数
+/// 複数の <style> タグがある場合、文書内に現れる順に改行で連結して返す。
 pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
-    let style_node = match get_target_element_node(Some(root), ElementKind::Style) {
-        Some(node) => node,
-        None => return "".to_string(),
-    };
-    let text_node = match style_node.borrow().first_child() {
-        Some(node) => node,
-        None => return "".to_string(),
-    };
-    let content = match &text_node.borrow().kind() {
-        NodeKind::Text(ref s) => s.clone(),
-        _ => "".to_string(),
-    };
-    content
+    get_style_contents(root).join("\n")
 }
 
 /// DOM ツリーから特定の ID の要素を取得する。
-/// ノードを再帰的にたどり、ノードの ID 名が id_name で指定されたものを返却する。
+/// node が Window を持つ document の場合、Window の ID のマップから探す。
+/// それ以外の場合、ノードとその子孫を文書内に現れる順にたどり、ノードの ID 名が id_name で指定されたものを返却する。
 pub fn get_element_by_id(
     node: Option<Rc<RefCell<Node>>>,
     id_name: &String,
 ) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            if let NodeKind::Element(e) = n.borrow().kind() {
-                for attr in &e.attributes() {
-                    if attr.name() == "id" && attr.value() == *id_name {
-                        return Some(n.clone());
-                    }
-                }
-            }
-            let result1 = get_element_by_id(n.borrow().first_child(), id_name);
-            let result2 = get_element_by_id(n.borrow().next_sibling(), id_name);
-            if result1.is_none() {
-                return result2;
-            }
-            result1
-        }
-        None => None,
+    let n = node?;
+    let window = n.borrow().window().upgrade();
+    if let Some(window) = window {
+        return window.borrow().get_element_by_id(id_name);
     }
+    find_in_tree(&n, &|n| match n.get_element() {
+        Some(e) => e.get_attribute("id").as_ref() == Some(id_name),
+        None => false,
+    })
 }
 
 /// JavaScript のコードを取得するため、<script> タグの関数を取得する関数。
@@ -99,3 +149,118 @@ pub fn get_js_content(root: Rc<RefCell<Node>>) -> String {
     };
     content
 }
+
+/// 文書のタイトルを取得する。
+/// 最初の <title> タグのテキストから前後の空白文字を取り除き、連続する空白文字を1つの空白にまとめたものを返す。
+/// <title> タグがない場合は空文字を返す。
+/// https://html.spec.whatwg.org/multipage/dom.html#document.title
+pub fn get_title_content(root: Rc<RefCell<Node>>) -> String {
+    let title_node = match get_target_element_node(Some(root), ElementKind::Title) {
+        Some(node) => node,
+        None => return "".to_string(),
+    };
+    let text_node = match title_node.borrow().first_child() {
+        Some(node) => node,
+        None => return "".to_string(),
+    };
+    let content = match &text_node.borrow().kind() {
+        NodeKind::Text(ref s) => s.split_ascii_whitespace().collect::<Vec<_>>().join(" "),
+        _ => "".to_string(),
+    };
+    content
+}
+
+/// 終了タグを持たない空要素のタグ名
+/// https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// 中のテキストを文字参照に置き換えずにそのまま出力する要素のタグ名
+const RAW_TEXT_ELEMENTS: [&str; 7] = [
+    "style", "script", "xmp", "iframe", "noembed", "noframes", "noscript",
+];
+
+/// ノードとその子孫を HTML の文字列に変換する。
+/// https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+pub fn outer_html(node: Rc<RefCell<Node>>) -> String {
+    let mut html = String::new();
+    serialize_node(&node, &mut html);
+    html
+}
+
+/// ノードの子孫を HTML の文字列に変換する。ノード自身のタグは含まない。
+/// https://html.spec.whatwg.org/multipage/dynamic-markup-insertion.html#dom-element-innerhtml
+pub fn inner_html(node: Rc<RefCell<Node>>) -> String {
+    let mut html = String::new();
+    serialize_children(&node, &mut html);
+    html
+}
+
+fn serialize_children(node: &Rc<RefCell<Node>>, html: &mut String) {
+    let children = node.borrow().children();
+    for child in children {
+        serialize_node(&child, html);
+    }
+}
+
+fn serialize_node(node: &Rc<RefCell<Node>>, html: &mut String) {
+    let kind = node.borrow().kind();
+    match kind {
+        NodeKind::Document => serialize_children(node, html),
+        NodeKind::Element(e) => {
+            let tag_name = e.tag_name();
+            html.push('<');
+            html.push_str(&tag_name);
+            for attr in e.attributes() {
+                html.push(' ');
+                html.push_str(&attr.name());
+                html.push_str("=\"");
+                html.push_str(&escape_string(&attr.value(), true));
+                html.push('"');
+            }
+            html.push('>');
+
+            if VOID_ELEMENTS.contains(&tag_name.as_str()) {
+                return;
+            }
+            serialize_children(node, html);
+            html.push_str("</");
+            html.push_str(&tag_name);
+            html.push('>');
+        }
+        NodeKind::Text(text) => {
+            let raw = match node.borrow().parent().upgrade() {
+                Some(parent) => match parent.borrow().get_element() {
+                    Some(e) => RAW_TEXT_ELEMENTS.contains(&e.tag_name().as_str()),
+                    None => false,
+                },
+                None => false,
+            };
+            if raw {
+                html.push_str(&text);
+            } else {
+                html.push_str(&escape_string(&text, false));
+            }
+        }
+    }
+}
+
+/// テキストや属性値に含まれる特殊な文字を文字参照に置き換える。
+/// 属性値の場合は " を、テキストの場合は < と > を置き換える。
+/// https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+pub(crate) fn escape_string(s: &str, attribute_mode: bool) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            '"' if attribute_mode => escaped.push_str("&quot;"),
+            '<' if !attribute_mode => escaped.push_str("&lt;"),
+            '>' if !attribute_mode => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,294 @@
+use crate::renderer::dom::node::Node;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// イベントの伝搬の段階を表す。
+/// https://dom.spec.whatwg.org/#dom-event-eventphase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPhase {
+    None,
+    Capturing,
+    AtTarget,
+    Bubbling,
+}
+
+/// DOM ツリーに送出するイベントを表す。
+/// https://dom.spec.whatwg.org/#interface-event
+#[derive(Debug, Clone)]
+pub struct Event {
+    event_type: String,
+    bubbles: bool,
+    target: Option<Rc<RefCell<Node>>>,
+    current_target: Option<Rc<RefCell<Node>>>,
+    phase: EventPhase,
+    // stopPropagation が呼ばれ、以降のノードへの伝搬を止めるかどうか
+    propagation_stopped: bool,
+    // preventDefault が呼ばれ、既定の動作を取り消すかどうか
+    canceled: bool,
+}
+
+impl Event {
+    pub fn new(event_type: &str, bubbles: bool) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            bubbles,
+            target: None,
+            current_target: None,
+            phase: EventPhase::None,
+            propagation_stopped: false,
+            canceled: false,
+        }
+    }
+
+    /// イベントの種類 (例: "click") を返す。
+    pub fn event_type(&self) -> String {
+        self.event_type.clone()
+    }
+
+    /// イベントが祖先ノードへ伝搬 (バブリング) するかどうかを返す。
+    pub fn bubbles(&self) -> bool {
+        self.bubbles
+    }
+
+    /// イベントを送出したノードを返す。送出前は None となる。
+    pub fn target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.target.clone()
+    }
+
+    /// 現在リスナーを呼んでいるノードを返す。伝搬中でない場合は None となる。
+    pub fn current_target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.current_target.clone()
+    }
+
+    /// 現在の伝搬の段階を返す。
+    pub fn phase(&self) -> EventPhase {
+        self.phase
+    }
+
+    /// 現在のノードのリスナーを呼び終えた後、以降のノードへの伝搬を止める。
+    /// https://dom.spec.whatwg.org/#dom-event-stoppropagation
+    pub fn stop_propagation(&mut self) {
+        self.propagation_stopped = true;
+    }
+
+    /// イベントの既定の動作 (例: リンクのクリックによる遷移) を取り消す。
+    /// https://dom.spec.whatwg.org/#dom-event-preventdefault
+    pub fn prevent_default(&mut self) {
+        self.canceled = true;
+    }
+
+    /// 既定の動作が取り消されたかどうかを返す。
+    pub fn default_prevented(&self) -> bool {
+        self.canceled
+    }
+}
+
+/// イベントが送出されたときに呼ぶ処理を表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventCallback {
+    /// addEventListener で登録された JavaScript の関数の名前
+    Function(String),
+    /// onclick などのイベントハンドラ属性に書かれた JavaScript のソースコード
+    /// https://html.spec.whatwg.org/multipage/webappapis.html#event-handler-content-attributes
+    Inline(String),
+}
+
+/// ノードに登録するイベントリスナーを表す。
+/// https://dom.spec.whatwg.org/#concept-event-listener
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventListener {
+    event_type: String,
+    callback: EventCallback,
+    capture: bool,
+}
+
+impl EventListener {
+    pub fn new(event_type: &str, callback: EventCallback, capture: bool) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            callback,
+            capture,
+        }
+    }
+
+    pub fn event_type(&self) -> String {
+        self.event_type.clone()
+    }
+
+    pub fn callback(&self) -> EventCallback {
+        self.callback.clone()
+    }
+
+    /// キャプチャの段階で呼ぶリスナーかどうかを返す。
+    pub fn capture(&self) -> bool {
+        self.capture
+    }
+}
+
+/// ノードが event_type のイベントに対して、phase の段階で呼ぶリスナーを返す。
+/// イベントハンドラ属性 (on + イベントの種類) がある場合、バブリングの段階で呼ぶリスナーとして最初に含める。
+fn listeners_for(
+    node: &Rc<RefCell<Node>>,
+    event_type: &str,
+    phase: EventPhase,
+) -> Vec<EventListener> {
+    let node = node.borrow();
+    let mut listeners = Vec::new();
+
+    if phase != EventPhase::Capturing {
+        if let Some(e) = node.get_element() {
+            if let Some(source) = e.get_attribute(&format!("on{}", event_type)) {
+                listeners.push(EventListener::new(
+                    event_type,
+                    EventCallback::Inline(source),
+                    false,
+                ));
+            }
+        }
+    }
+
+    listeners.extend(node.event_listeners().into_iter().filter(|listener| {
+        listener.event_type == event_type
+            && match phase {
+                EventPhase::Capturing => listener.capture,
+                EventPhase::Bubbling => !listener.capture,
+                _ => true,
+            }
+    }));
+    listeners
+}
+
+/// target にイベントを送出する。
+/// ルートから target の親までをキャプチャの段階、target を target の段階、target の親からルートまでをバブリングの段階として順にリスナーを呼ぶ。
+/// バブリングしないイベントの場合、バブリングの段階は省略する。
+/// リスナーの呼び出しは invoke に任せる。リスナーが DOM ツリーを変更しても、伝搬の経路は送出の開始時点のものを使う。
+/// 戻り値は既定の動作を行うべきかどうか (preventDefault が呼ばれていないかどうか) を表す。
+/// https://dom.spec.whatwg.org/#concept-event-dispatch
+pub fn dispatch_event(
+    target: &Rc<RefCell<Node>>,
+    event: &mut Event,
+    invoke: &mut dyn FnMut(&EventListener, &mut Event),
+) -> bool {
+    event.target = Some(target.clone());
+
+    // target から祖先をたどり、伝搬の経路を作る。
+    let mut path = Vec::new();
+    let mut node = target.borrow().parent().upgrade();
+    while let Some(n) = node {
+        node = n.borrow().parent().upgrade();
+        path.push(n);
+    }
+
+    let mut steps = Vec::new();
+    for n in path.iter().rev() {
+        steps.push((n.clone(), EventPhase::Capturing));
+    }
+    steps.push((target.clone(), EventPhase::AtTarget));
+    if event.bubbles {
+        for n in path.iter() {
+            steps.push((n.clone(), EventPhase::Bubbling));
+        }
+    }
+
+    for (node, phase) in steps {
+        if event.propagation_stopped {
+            break;
+        }
+        event.phase = phase;
+        event.current_target = Some(node.clone());
+        for listener in listeners_for(&node, &event.event_type, phase) {
+            invoke(&listener, event);
+        }
+    }
+
+    event.phase = EventPhase::None;
+    event.current_target = None;
+    !event.canceled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::node::Element;
+    use crate::renderer::dom::node::NodeKind;
+    use alloc::vec;
+
+    fn element(name: &str) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            name,
+            Vec::new(),
+        )))))
+    }
+
+    /// キャプチャ、target、バブリングの順にリスナーが呼ばれることを確認する。
+    #[test]
+    fn test_dispatch_event() {
+        let div = element("div");
+        let p = element("p");
+        Node::append_child(&div, p.clone()).expect("failed to append");
+
+        let listen = |node: &Rc<RefCell<Node>>, name: &str, capture: bool| {
+            node.borrow_mut().add_event_listener(EventListener::new(
+                "click",
+                EventCallback::Function(name.to_string()),
+                capture,
+            ));
+        };
+        listen(&div, "div_bubble", false);
+        listen(&div, "div_capture", true);
+        listen(&p, "p", false);
+        p.borrow_mut().add_event_listener(EventListener::new(
+            "keydown",
+            EventCallback::Function("other".to_string()),
+            false,
+        ));
+
+        let mut log = Vec::new();
+        let mut event = Event::new("click", true);
+        let not_canceled = dispatch_event(&p, &mut event, &mut |listener, event| {
+            if let EventCallback::Function(name) = listener.callback() {
+                log.push((name, event.phase()));
+            }
+        });
+
+        assert!(not_canceled);
+        assert_eq!(
+            vec![
+                ("div_capture".to_string(), EventPhase::Capturing),
+                ("p".to_string(), EventPhase::AtTarget),
+                ("div_bubble".to_string(), EventPhase::Bubbling),
+            ],
+            log
+        );
+        assert!(Rc::ptr_eq(&p, &event.target().expect("no target")));
+    }
+
+    /// stopPropagation と preventDefault、イベントハンドラ属性の扱いを確認する。
+    #[test]
+    fn test_stop_propagation_and_prevent_default() {
+        let div = element("div");
+        let a = element("a");
+        Node::append_child(&div, a.clone()).expect("failed to append");
+        Node::set_attribute(&a, "onclick", "go()");
+        div.borrow_mut().add_event_listener(EventListener::new(
+            "click",
+            EventCallback::Function("never".to_string()),
+            false,
+        ));
+
+        let mut log = Vec::new();
+        let mut event = Event::new("click", true);
+        let not_canceled = dispatch_event(&a, &mut event, &mut |listener, event| {
+            log.push(listener.callback());
+            event.stop_propagation();
+            event.prevent_default();
+        });
+
+        assert!(!not_canceled);
+        assert_eq!(vec![EventCallback::Inline("go()".to_string())], log);
+    }
+}
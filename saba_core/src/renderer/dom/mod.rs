@@ -1,2 +1,4 @@
 pub mod api;
+pub mod event;
+pub mod mutation;
 pub mod node;
@@ -0,0 +1,71 @@
+use crate::renderer::dom::node::Node;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// DOM ツリーに対する変更の種類を表す。
+/// https://dom.spec.whatwg.org/#dom-mutationrecord-type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MutationKind {
+    /// 要素の属性が追加、変更、削除された。変更された属性の名前を持つ。
+    Attributes(String),
+    /// 子ノードが追加または削除された。
+    ChildList,
+    /// テキストノードのテキストが変更された。
+    CharacterData,
+}
+
+/// DOM ツリーに対する1つの変更を表す。
+/// https://dom.spec.whatwg.org/#interface-mutationrecord
+#[derive(Debug, Clone)]
+pub struct MutationRecord {
+    kind: MutationKind,
+    target: Rc<RefCell<Node>>,
+}
+
+impl MutationRecord {
+    pub fn new(kind: MutationKind, target: Rc<RefCell<Node>>) -> Self {
+        Self { kind, target }
+    }
+
+    pub fn kind(&self) -> MutationKind {
+        self.kind.clone()
+    }
+
+    /// 変更されたノードを返す。子ノードの変更の場合は親ノードとなる。
+    pub fn target(&self) -> Rc<RefCell<Node>> {
+        self.target.clone()
+    }
+}
+
+/// ノードとその子孫に対する変更を記録する。
+/// Node::observe で監視するノードに登録すると、そのノードまたは子孫が変更されるたびに MutationRecord が追加される。
+/// ノードは MutationObserver を弱い参照として保持するため、監視を続ける間は呼び出し元が保持しておく必要がある。
+/// https://dom.spec.whatwg.org/#interface-mutationobserver
+#[derive(Debug, Clone, Default)]
+pub struct MutationObserver {
+    records: Vec<MutationRecord>,
+}
+
+impl MutationObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 変更の記録を追加する。
+    pub fn enqueue(&mut self, record: MutationRecord) {
+        self.records.push(record);
+    }
+
+    /// 前回の呼び出し以降に記録された変更があるかどうかを返す。
+    pub fn has_records(&self) -> bool {
+        !self.records.is_empty()
+    }
+
+    /// 記録された変更を記録された順に返し、記録を空にする。
+    /// https://dom.spec.whatwg.org/#dom-mutationobserver-takerecords
+    pub fn take_records(&mut self) -> Vec<MutationRecord> {
+        core::mem::take(&mut self.records)
+    }
+}
@@ -1,8 +1,15 @@
+use crate::error::Error;
+use crate::renderer::dom::api::find_in_tree;
+use crate::renderer::dom::event::EventListener;
+use crate::renderer::dom::mutation::MutationKind;
+use crate::renderer::dom::mutation::MutationObserver;
+use crate::renderer::dom::mutation::MutationRecord;
 use crate::renderer::html::attribute::Attribute;
-use alloc::format;
+use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 use core::fmt::Display;
@@ -23,13 +30,17 @@ pub enum NodeKind {
 /// 1つのノードを表す構造体。
 #[derive(Debug, Clone)]
 pub struct Node {
-    pub kind: NodeKind,                      // ノードの種類
+    pub kind: NodeKind,                              // ノードの種類
     window: Weak<RefCell<Window>>, // DOM ツリーを持つウィンドウ。1つのページに対して1つのウィンドウインスタンスが存在する。弱い参照として保持する（ウィークポインタ）。
     parent: Weak<RefCell<Node>>,   // ノードの親ノード。弱い参照として保持する（ウィークポインタ）。
     first_child: Option<Rc<RefCell<Node>>>, // ノードの一番初めの子ノード
     last_child: Weak<RefCell<Node>>, // ノードの最後の子ノード。弱い参照として保持する（ウィークポインタ）。
     previous_sibling: Weak<RefCell<Node>>, // ノードの前の兄弟ノード。弱い参照として保持する（ウィークポインタ）。
     next_sibling: Option<Rc<RefCell<Node>>>, // ノードの次の兄弟ノード。
+    form_owner: Weak<RefCell<Node>>, // フォームコントロールが属する <form> 要素。弱い参照として保持する（ウィークポインタ）。
+    style_dirty: bool, // スタイルに影響する属性が変更され、スタイルを計算し直す必要があるかどうか。
+    event_listeners: Vec<EventListener>, // addEventListener で登録されたイベントリスナー
+    observers: Vec<Weak<RefCell<MutationObserver>>>, // ノードとその子孫の変更を監視する MutationObserver。弱い参照として保持する（ウィークポインタ）。
 }
 
 impl Node {
@@ -43,6 +54,10 @@ impl Node {
             last_child: Weak::new(),
             previous_sibling: Weak::new(),
             next_sibling: None,
+            form_owner: Weak::new(),
+            style_dirty: false,
+            event_listeners: Vec::new(),
+            observers: Vec::new(),
         }
     }
 
@@ -51,6 +66,11 @@ impl Node {
         self.window = window;
     }
 
+    /// window オブジェクトのゲッター。
+    pub fn window(&self) -> Weak<RefCell<Window>> {
+        self.window.clone()
+    }
+
     /// 親ノードのセッター。
     pub fn set_parent(&mut self, parent: Weak<RefCell<Node>>) {
         self.parent = parent;
@@ -71,6 +91,23 @@ impl Node {
         self.first_child.as_ref().cloned()
     }
 
+    /// 子ノードを先頭から順にたどるイテレータを返す。
+    /// https://dom.spec.whatwg.org/#dom-node-childnodes
+    pub fn children(&self) -> Children {
+        Children {
+            next: self.first_child(),
+        }
+    }
+
+    /// 子孫ノードを文書内に現れる順 (深さ優先の行きがけ順) にたどるイテレータを返す。ノード自身は含まない。
+    /// https://dom.spec.whatwg.org/#concept-tree-descendant
+    pub fn descendants(&self) -> Descendants {
+        Descendants {
+            next: self.first_child(),
+            depth: 1,
+        }
+    }
+
     /// 最後の子ノードのセッター。
     pub fn set_last_child(&mut self, last_child: Weak<RefCell<Node>>) {
         self.last_child = last_child;
@@ -101,6 +138,212 @@ impl Node {
         self.next_sibling.as_ref().cloned()
     }
 
+    /// フォームオーナーのセッター。
+    /// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-owner
+    pub fn set_form_owner(&mut self, form_owner: Weak<RefCell<Node>>) {
+        self.form_owner = form_owner;
+    }
+
+    /// フォームオーナーのゲッター。フォームに属していない場合は None を返す。
+    pub fn form_owner(&self) -> Option<Rc<RefCell<Node>>> {
+        self.form_owner.upgrade()
+    }
+
+    /// ノードのテキストの内容を返す。
+    /// テキストノードの場合はそのテキストを、要素の場合は子孫のテキストノードを文書内に現れる順に連結したものを返す。
+    /// 仕様では Document ノードの場合は null を返すが、ここでは空文字を返す。
+    /// https://dom.spec.whatwg.org/#dom-node-textcontent
+    pub fn text_content(&self) -> String {
+        match &self.kind {
+            NodeKind::Document => String::new(),
+            NodeKind::Text(text) => text.clone(),
+            NodeKind::Element(_) => {
+                let mut content = String::new();
+                for node in self.descendants() {
+                    if let NodeKind::Text(text) = &node.borrow().kind {
+                        content.push_str(text);
+                    }
+                }
+                content
+            }
+        }
+    }
+
+    /// ノードのテキストの内容を設定する。
+    /// テキストノードの場合はそのテキストを置き換え、要素の場合はすべての子ノードを取り除いて1つのテキストノードに置き換える。
+    /// text が空文字の場合、要素の子ノードは空になる。Document ノードの場合は何もしない。
+    /// https://dom.spec.whatwg.org/#dom-node-textcontent
+    pub fn set_text_content(node: &Rc<RefCell<Node>>, text: &str) {
+        let kind = node.borrow().kind();
+        match kind {
+            NodeKind::Document => {}
+            NodeKind::Text(_) => {
+                node.borrow_mut().kind = NodeKind::Text(text.to_string());
+                Self::queue_mutation(node, MutationKind::CharacterData);
+            }
+            NodeKind::Element(_) => {
+                let children: Vec<_> = node.borrow().children().collect();
+                for c in &children {
+                    Self::detach(c);
+                }
+                if !children.is_empty() {
+                    Self::queue_mutation(node, MutationKind::ChildList);
+                }
+                if !text.is_empty() {
+                    let text_node =
+                        Rc::new(RefCell::new(Node::new(NodeKind::Text(text.to_string()))));
+                    // 新しいテキストノードは親を持たないため、追加に失敗することはない。
+                    let _ = Self::append_child(node, text_node);
+                }
+            }
+        }
+    }
+
+    /// 要素の属性を設定する。同じ名前の属性がある場合は値を置き換え、ない場合は最後に追加する。
+    /// 要素ではないノードの場合は何もしない。
+    /// https://dom.spec.whatwg.org/#dom-element-setattribute
+    pub fn set_attribute(node: &Rc<RefCell<Node>>, name: &str, value: &str) {
+        let old_id = match node.borrow().get_element() {
+            Some(e) => e.get_attribute("id"),
+            None => return,
+        };
+        {
+            let mut n = node.borrow_mut();
+            if let NodeKind::Element(ref mut e) = n.kind {
+                e.set_attribute(name, value);
+            }
+            n.attribute_changed(name);
+        }
+        if name == "id" {
+            Self::id_changed(node, old_id, Some(value));
+        }
+        Self::queue_mutation(node, MutationKind::Attributes(name.to_string()));
+    }
+
+    /// 要素の属性を取り除く。属性がない場合や、要素ではないノードの場合は何もしない。
+    /// https://dom.spec.whatwg.org/#dom-element-removeattribute
+    pub fn remove_attribute(node: &Rc<RefCell<Node>>, name: &str) {
+        let old_id = node
+            .borrow()
+            .get_element()
+            .and_then(|e| e.get_attribute("id"));
+        {
+            let mut n = node.borrow_mut();
+            let removed = match n.kind {
+                NodeKind::Element(ref mut e) => e.remove_attribute(name),
+                _ => false,
+            };
+            if !removed {
+                return;
+            }
+            n.attribute_changed(name);
+        }
+        if name == "id" {
+            Self::id_changed(node, old_id, None);
+        }
+        Self::queue_mutation(node, MutationKind::Attributes(name.to_string()));
+    }
+
+    /// 要素の ID が変更されたとき、ドキュメントの ID のマップを更新する。
+    fn id_changed(node: &Rc<RefCell<Node>>, old_id: Option<String>, new_id: Option<&str>) {
+        let window = match Self::owner_window(node) {
+            Some(window) => window,
+            None => return,
+        };
+        let mut window = window.borrow_mut();
+        if let Some(old_id) = old_id {
+            window.unregister_id(&old_id, node);
+        }
+        if let Some(new_id) = new_id {
+            window.register_id(new_id, node);
+        }
+    }
+
+    /// observer にノードとその子孫の変更を記録させる。
+    /// https://dom.spec.whatwg.org/#dom-mutationobserver-observe
+    pub fn observe(&mut self, observer: &Rc<RefCell<MutationObserver>>) {
+        self.observers.retain(|o| o.strong_count() > 0);
+        if !self
+            .observers
+            .iter()
+            .any(|o| o.upgrade().is_some_and(|o| Rc::ptr_eq(&o, observer)))
+        {
+            self.observers.push(Rc::downgrade(observer));
+        }
+    }
+
+    /// target の変更を、target とその祖先に登録された MutationObserver に記録する。
+    /// 複数の祖先に同じ MutationObserver が登録されている場合も、記録は1つのみとする。
+    /// https://dom.spec.whatwg.org/#queueing-a-mutation-record
+    fn queue_mutation(target: &Rc<RefCell<Node>>, kind: MutationKind) {
+        let mut observers: Vec<Rc<RefCell<MutationObserver>>> = Vec::new();
+        let mut node = Some(target.clone());
+        while let Some(n) = node {
+            for observer in n.borrow().observers.iter().filter_map(|o| o.upgrade()) {
+                if !observers.iter().any(|o| Rc::ptr_eq(o, &observer)) {
+                    observers.push(observer);
+                }
+            }
+            node = n.borrow().parent().upgrade();
+        }
+
+        for observer in observers {
+            observer
+                .borrow_mut()
+                .enqueue(MutationRecord::new(kind.clone(), target.clone()));
+        }
+    }
+
+    /// 属性が変更されたときに呼ばれるフック。
+    /// セレクタや要素のスタイルに影響する属性の場合、スタイルの再計算が必要であることを記録する。
+    /// https://dom.spec.whatwg.org/#concept-element-attributes-change-ext
+    fn attribute_changed(&mut self, name: &str) {
+        if name == "class" || name == "id" || name == "style" {
+            self.style_dirty = true;
+        }
+    }
+
+    /// スタイルに影響する属性が変更され、スタイルを計算し直す必要があるかどうかを返す。
+    pub fn is_style_dirty(&self) -> bool {
+        self.style_dirty
+    }
+
+    /// 要素の状態 (ホバー中、訪問済みなど) を設定する。on が true の場合は state を加え、false の場合は取り除く。
+    /// 状態が変わった場合、疑似クラスに一致するかどうかが変わるため、スタイルの再計算が必要であることを記録する。
+    /// 要素ではないノードの場合は何もしない。
+    pub fn set_element_state(&mut self, state: ElementState, on: bool) {
+        if let NodeKind::Element(ref mut e) = self.kind {
+            if e.state.contains(state) != on {
+                e.state.set(state, on);
+                self.style_dirty = true;
+            }
+        }
+    }
+
+    /// スタイルを計算し直した後に、再計算が必要であることを表す記録を消す。
+    pub fn clear_style_dirty(&mut self) {
+        self.style_dirty = false;
+    }
+
+    /// イベントリスナーを登録する。同じ種類、コールバック、キャプチャの指定のリスナーが登録済みの場合は何もしない。
+    /// https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener
+    pub fn add_event_listener(&mut self, listener: EventListener) {
+        if !self.event_listeners.contains(&listener) {
+            self.event_listeners.push(listener);
+        }
+    }
+
+    /// 登録済みのイベントリスナーを取り除く。
+    /// https://dom.spec.whatwg.org/#dom-eventtarget-removeeventlistener
+    pub fn remove_event_listener(&mut self, listener: &EventListener) {
+        self.event_listeners.retain(|l| l != listener);
+    }
+
+    /// 登録済みのイベントリスナーを、登録された順に返す。
+    pub fn event_listeners(&self) -> Vec<EventListener> {
+        self.event_listeners.clone()
+    }
+
     /// ノードの種類を取得する。
     pub fn kind(&self) -> NodeKind {
         self.kind.clone()
@@ -121,6 +364,222 @@ impl Node {
             NodeKind::Element(ref e) => Some(e.kind()),
         }
     }
+
+    /// parent の子ノードの最後に child を追加し、追加したノードを返す。
+    /// child が既に DOM ツリーに含まれている場合、元の位置から取り除いてから追加する。
+    /// https://dom.spec.whatwg.org/#dom-node-appendchild
+    pub fn append_child(
+        parent: &Rc<RefCell<Node>>,
+        child: Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, Error> {
+        Self::insert_before(parent, child, None)
+    }
+
+    /// parent の子ノードの reference の直前に child を追加し、追加したノードを返す。
+    /// reference が None の場合、子ノードの最後に追加する。
+    /// reference が parent の子ノードではない場合や、child が parent 自身またはその祖先の場合はエラーを返す。
+    /// https://dom.spec.whatwg.org/#dom-node-insertbefore
+    pub fn insert_before(
+        parent: &Rc<RefCell<Node>>,
+        child: Rc<RefCell<Node>>,
+        reference: Option<&Rc<RefCell<Node>>>,
+    ) -> Result<Rc<RefCell<Node>>, Error> {
+        if let Some(r) = reference {
+            if !Self::is_child_of(r, parent) {
+                return Err(Error::UnexpectedInput(
+                    "the reference node is not a child of the parent".to_string(),
+                ));
+            }
+        }
+        // ノードを自分自身や子孫の下に移動すると、ツリーに循環ができてしまう。
+        let mut ancestor = Some(parent.clone());
+        while let Some(a) = ancestor {
+            if Rc::ptr_eq(&a, &child) {
+                return Err(Error::UnexpectedInput(
+                    "cannot insert a node into itself or its descendant".to_string(),
+                ));
+            }
+            ancestor = a.borrow().parent().upgrade();
+        }
+
+        // 挿入するノード自身を基準にした場合、その次の兄弟ノードを基準にする。
+        let reference = match reference {
+            Some(r) if Rc::ptr_eq(r, &child) => child.borrow().next_sibling(),
+            r => r.cloned(),
+        };
+
+        let old_parent = child.borrow().parent().upgrade();
+        Self::detach(&child);
+        if let Some(old_parent) = old_parent {
+            if !Rc::ptr_eq(&old_parent, parent) {
+                Self::queue_mutation(&old_parent, MutationKind::ChildList);
+            }
+        }
+
+        let previous = match &reference {
+            Some(r) => r.borrow().previous_sibling().upgrade(),
+            None => parent.borrow().last_child().upgrade(),
+        };
+        match &previous {
+            Some(p) => p.borrow_mut().set_next_sibling(Some(child.clone())),
+            None => parent.borrow_mut().set_first_child(Some(child.clone())),
+        }
+        match &reference {
+            Some(r) => r.borrow_mut().set_previous_sibling(Rc::downgrade(&child)),
+            None => parent.borrow_mut().set_last_child(Rc::downgrade(&child)),
+        }
+
+        {
+            let mut c = child.borrow_mut();
+            c.set_parent(Rc::downgrade(parent));
+            c.set_previous_sibling(match &previous {
+                Some(p) => Rc::downgrade(p),
+                None => Weak::new(),
+            });
+            c.set_next_sibling(reference);
+        }
+        if let Some(window) = Self::owner_window(parent) {
+            window.borrow_mut().subtree_inserted(&child);
+        }
+        Self::queue_mutation(parent, MutationKind::ChildList);
+        Ok(child)
+    }
+
+    /// parent の子ノードから child を取り除き、取り除いたノードを返す。
+    /// child が parent の子ノードではない場合はエラーを返す。
+    /// https://dom.spec.whatwg.org/#dom-node-removechild
+    pub fn remove_child(
+        parent: &Rc<RefCell<Node>>,
+        child: &Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, Error> {
+        if !Self::is_child_of(child, parent) {
+            return Err(Error::UnexpectedInput(
+                "the node to be removed is not a child of the parent".to_string(),
+            ));
+        }
+        Self::detach(child);
+        Self::queue_mutation(parent, MutationKind::ChildList);
+        Ok(child.clone())
+    }
+
+    /// node の親ノードが parent かどうかを返す。
+    fn is_child_of(node: &Rc<RefCell<Node>>, parent: &Rc<RefCell<Node>>) -> bool {
+        match node.borrow().parent().upgrade() {
+            Some(p) => Rc::ptr_eq(&p, parent),
+            None => false,
+        }
+    }
+
+    /// ノードを含む DOM ツリーのルートの document から、そのツリーを持つ Window を返す。
+    /// ドキュメントに含まれないノードの場合は None を返す。
+    fn owner_window(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Window>>> {
+        let mut root = node.clone();
+        loop {
+            let parent = root.borrow().parent().upgrade();
+            match parent {
+                Some(p) => root = p,
+                None => break,
+            }
+        }
+        let window = root.borrow().window().upgrade();
+        window
+    }
+
+    /// ノードを親ノードと兄弟ノードから切り離し、前後の兄弟ノードをつなぎ直す。
+    fn detach(node: &Rc<RefCell<Node>>) {
+        let parent = node.borrow().parent().upgrade();
+        if let Some(window) = Self::owner_window(node) {
+            window.borrow_mut().subtree_removed(node);
+        }
+        let previous = node.borrow().previous_sibling().upgrade();
+        let next = node.borrow().next_sibling();
+
+        match &previous {
+            Some(p) => p.borrow_mut().set_next_sibling(next.clone()),
+            None => {
+                if let Some(parent) = &parent {
+                    parent.borrow_mut().set_first_child(next.clone());
+                }
+            }
+        }
+        match &next {
+            Some(n) => n.borrow_mut().set_previous_sibling(match &previous {
+                Some(p) => Rc::downgrade(p),
+                None => Weak::new(),
+            }),
+            None => {
+                if let Some(parent) = &parent {
+                    parent.borrow_mut().set_last_child(match &previous {
+                        Some(p) => Rc::downgrade(p),
+                        None => Weak::new(),
+                    });
+                }
+            }
+        }
+
+        let mut n = node.borrow_mut();
+        n.set_parent(Weak::new());
+        n.set_previous_sibling(Weak::new());
+        n.set_next_sibling(None);
+    }
+}
+
+/// Node::children が返すイテレータ。
+/// 次にたどる子ノードのみを保持するため、たどっている途中でも子ノードを変更できる。
+#[derive(Debug, Clone)]
+pub struct Children {
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+impl Iterator for Children {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        self.next = current.borrow().next_sibling();
+        Some(current)
+    }
+}
+
+/// Node::descendants が返すイテレータ。
+/// 起点のノードからの深さを数え、起点のノードまで戻ったところでたどるのをやめる。
+#[derive(Debug, Clone)]
+pub struct Descendants {
+    next: Option<Rc<RefCell<Node>>>,
+    depth: usize,
+}
+
+impl Iterator for Descendants {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+
+        // 子ノードがあれば子ノードへ、なければ次の兄弟ノード、または祖先の次の兄弟ノードへ進む。
+        let first_child = current.borrow().first_child();
+        if let Some(child) = first_child {
+            self.next = Some(child);
+            self.depth += 1;
+            return Some(current);
+        }
+        let mut node = current.clone();
+        loop {
+            if let Some(sibling) = node.borrow().next_sibling() {
+                self.next = Some(sibling);
+                break;
+            }
+            self.depth -= 1;
+            if self.depth == 0 {
+                break;
+            }
+            let parent = match node.borrow().parent().upgrade() {
+                Some(parent) => parent,
+                None => break,
+            };
+            node = parent;
+        }
+        Some(current)
+    }
 }
 
 /// Window 構造体。
@@ -130,6 +589,10 @@ impl Node {
 #[derive(Debug, Clone)]
 pub struct Window {
     document: Rc<RefCell<Node>>,
+    forms: Vec<Rc<RefCell<Node>>>, // ツリー構築中に挿入された <form> 要素
+    links: Vec<Rc<RefCell<Node>>>, // ツリー構築中に挿入された href 属性を持つ <a> と <area> 要素
+    images: Vec<Rc<RefCell<Node>>>, // ツリー構築中に挿入された <img> 要素
+    ids: BTreeMap<String, Option<Rc<RefCell<Node>>>>, // ID と、その ID を持つ要素の対応。複数の要素が同じ ID を持つ場合は None とする。
 }
 
 impl Window {
@@ -137,6 +600,10 @@ impl Window {
     pub fn new() -> Self {
         let window = Self {
             document: Rc::new(RefCell::new(Node::new(NodeKind::Document))),
+            forms: Vec::new(),
+            links: Vec::new(),
+            images: Vec::new(),
+            ids: BTreeMap::new(),
         };
 
         window
@@ -150,6 +617,158 @@ impl Window {
     pub fn document(&self) -> Rc<RefCell<Node>> {
         self.document.clone()
     }
+
+    /// ドキュメントのすべての <form> 要素を文書内に現れる順に返す。
+    /// https://html.spec.whatwg.org/multipage/dom.html#dom-document-forms
+    pub fn forms(&self) -> Vec<Rc<RefCell<Node>>> {
+        self.connected(&self.forms)
+    }
+
+    /// ドキュメントのすべての href 属性を持つ <a> と <area> 要素を文書内に現れる順に返す。
+    /// https://html.spec.whatwg.org/multipage/dom.html#dom-document-links
+    pub fn links(&self) -> Vec<Rc<RefCell<Node>>> {
+        self.connected(&self.links)
+    }
+
+    /// ドキュメントのすべての <img> 要素を文書内に現れる順に返す。
+    /// https://html.spec.whatwg.org/multipage/dom.html#dom-document-images
+    pub fn images(&self) -> Vec<Rc<RefCell<Node>>> {
+        self.connected(&self.images)
+    }
+
+    /// HTML パーサが要素をツリーに挿入したときに呼ばれ、要素の種類に応じてコレクションに追加する。
+    /// ツリー構築中は要素が文書内に現れる順に挿入されるため、コレクションも文書内の順となる。
+    pub(crate) fn element_inserted(&mut self, node: &Rc<RefCell<Node>>) {
+        let element = match node.borrow().get_element() {
+            Some(e) => e,
+            None => return,
+        };
+        match element.kind() {
+            ElementKind::Form => self.forms.push(node.clone()),
+            ElementKind::Img => self.images.push(node.clone()),
+            _ if element.is_link() => self.links.push(node.clone()),
+            _ => {}
+        }
+        if let Some(id) = element.get_attribute("id") {
+            self.register_id(&id, node);
+        }
+    }
+
+    /// ID が id_name の要素を返す。
+    /// ID のマップから探し、複数の要素が同じ ID を持つ場合やマップの要素がドキュメントから取り除かれている場合のみ、DOM ツリーをたどって最初の要素を探す。
+    /// https://dom.spec.whatwg.org/#dom-nonelementparentnode-getelementbyid
+    pub fn get_element_by_id(&self, id_name: &str) -> Option<Rc<RefCell<Node>>> {
+        let has_id = |node: &Node| match node.get_element() {
+            Some(e) => e.get_attribute("id").as_deref() == Some(id_name),
+            None => false,
+        };
+        match self.ids.get(id_name) {
+            None => None,
+            Some(Some(node)) if has_id(&node.borrow()) && self.is_connected(node) => {
+                Some(node.clone())
+            }
+            Some(_) => find_in_tree(&self.document, &has_id),
+        }
+    }
+
+    /// ID のマップに要素を追加する。既に同じ ID を持つ別の要素がある場合、その ID は重複として記録する。
+    fn register_id(&mut self, id: &str, node: &Rc<RefCell<Node>>) {
+        if id.is_empty() {
+            return;
+        }
+        match self.ids.get(id) {
+            Some(Some(n)) if !Rc::ptr_eq(n, node) => {
+                self.ids.insert(id.to_string(), None);
+            }
+            Some(_) => {}
+            None => {
+                self.ids.insert(id.to_string(), Some(node.clone()));
+            }
+        }
+    }
+
+    /// ID のマップから要素を取り除く。重複として記録された ID の場合、DOM ツリーをたどって探すため、そのままにする。
+    fn unregister_id(&mut self, id: &str, node: &Rc<RefCell<Node>>) {
+        if let Some(Some(n)) = self.ids.get(id) {
+            if Rc::ptr_eq(n, node) {
+                self.ids.remove(id);
+            }
+        }
+    }
+
+    /// node とその子孫がドキュメントに挿入されたときに呼ばれ、ID を持つ要素を ID のマップに追加する。
+    fn subtree_inserted(&mut self, node: &Rc<RefCell<Node>>) {
+        let descendants = node.borrow().descendants();
+        for n in core::iter::once(node.clone()).chain(descendants) {
+            let id = n.borrow().get_element().and_then(|e| e.get_attribute("id"));
+            if let Some(id) = id {
+                self.register_id(&id, &n);
+            }
+        }
+    }
+
+    /// node とその子孫がドキュメントから取り除かれたときに呼ばれ、ID を持つ要素を ID のマップから取り除く。
+    fn subtree_removed(&mut self, node: &Rc<RefCell<Node>>) {
+        let descendants = node.borrow().descendants();
+        for n in core::iter::once(node.clone()).chain(descendants) {
+            let id = n.borrow().get_element().and_then(|e| e.get_attribute("id"));
+            if let Some(id) = id {
+                self.unregister_id(&id, &n);
+            }
+        }
+    }
+
+    /// node がドキュメントに含まれるかどうかを返す。
+    fn is_connected(&self, node: &Rc<RefCell<Node>>) -> bool {
+        let mut ancestor = node.borrow().parent().upgrade();
+        while let Some(a) = ancestor {
+            if Rc::ptr_eq(&a, &self.document) {
+                return true;
+            }
+            ancestor = a.borrow().parent().upgrade();
+        }
+        false
+    }
+
+    /// コレクションの要素のうち、スクリプトなどによって取り除かれておらず、まだドキュメントに含まれるものを返す。
+    fn connected(&self, nodes: &[Rc<RefCell<Node>>]) -> Vec<Rc<RefCell<Node>>> {
+        nodes
+            .iter()
+            .filter(|node| self.is_connected(node))
+            .cloned()
+            .collect()
+    }
+
+    /// observer に DOM ツリー全体の変更を記録させる。
+    pub fn observe(&self, observer: &Rc<RefCell<MutationObserver>>) {
+        self.document.borrow_mut().observe(observer);
+    }
+}
+
+/// 要素の状態を表すビットフィールド。:hover や :visited などの疑似クラスの照合に使う。
+/// https://html.spec.whatwg.org/multipage/semantics-other.html#pseudo-classes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ElementState(u8);
+
+impl ElementState {
+    /// ポインタが要素またはその子孫の上にある。
+    pub const HOVER: ElementState = ElementState(1 << 0);
+    /// リンク先を訪問済みである。
+    pub const VISITED: ElementState = ElementState(1 << 1);
+
+    /// other の状態をすべて含むかどうかを返す。
+    pub fn contains(&self, other: ElementState) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// on が true の場合は other の状態を加え、false の場合は取り除く。
+    pub fn set(&mut self, other: ElementState, on: bool) {
+        if on {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
+        }
+    }
 }
 
 /// Element 構造体。
@@ -157,16 +776,20 @@ impl Window {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Element {
     kind: ElementKind,
+    tag_name: String,
     attributes: Vec<Attribute>,
+    state: ElementState,
 }
 
 impl Element {
     /// Element オブジェクトを生成する。
+    /// 対応していない要素名やカスタム要素の場合は ElementKind::Unknown となり、タグ名は tag_name に保持する。
     pub fn new(element_name: &str, attributes: Vec<Attribute>) -> Self {
         Self {
-            kind: ElementKind::from_str(element_name)
-                .expect("failed to convert string to ElementKind"),
+            kind: ElementKind::from_str(element_name).unwrap_or(ElementKind::Unknown),
+            tag_name: element_name.to_string(),
             attributes,
+            state: ElementState::default(),
         }
     }
 
@@ -175,10 +798,54 @@ impl Element {
         self.kind
     }
 
+    /// 要素のタグ名を取得する。
+    /// https://dom.spec.whatwg.org/#dom-element-tagname
+    pub fn tag_name(&self) -> String {
+        self.tag_name.clone()
+    }
+
+    /// 要素の状態を返す。状態は Node::set_element_state で変更する。
+    pub fn state(&self) -> ElementState {
+        self.state
+    }
+
+    /// href 属性を持つ a 要素または area 要素のように、リンクとなる要素かどうかを返す。
+    /// https://html.spec.whatwg.org/multipage/links.html#hyperlink
+    pub fn is_link(&self) -> bool {
+        (self.kind == ElementKind::A || self.tag_name == "area")
+            && self.get_attribute("href").is_some()
+    }
+
     /// 要素がデフォルトでブロック要素かインライン要素か決める。
     pub fn is_block_element(&self) -> bool {
         match self.kind {
-            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P => true,
+            ElementKind::Body
+            | ElementKind::H1
+            | ElementKind::H2
+            | ElementKind::H3
+            | ElementKind::H4
+            | ElementKind::H5
+            | ElementKind::H6
+            | ElementKind::P
+            | ElementKind::Div
+            | ElementKind::Section
+            | ElementKind::Article
+            | ElementKind::Nav
+            | ElementKind::Header
+            | ElementKind::Footer
+            | ElementKind::Main
+            | ElementKind::Aside
+            | ElementKind::Ul
+            | ElementKind::Ol
+            | ElementKind::Li
+            | ElementKind::Table
+            | ElementKind::Thead
+            | ElementKind::Tbody
+            | ElementKind::Tfoot
+            | ElementKind::Tr
+            | ElementKind::Form
+            | ElementKind::Hr
+            | ElementKind::Pre => true,
             _ => false,
         }
     }
@@ -198,6 +865,61 @@ impl Element {
         }
         None
     }
+
+    /// 属性の値を設定する。同じ名前の属性がある場合は値を置き換え、ない場合は最後に追加する。
+    /// スタイルの再計算が必要なことを記録するため、DOM ツリーのノードの属性は Node::set_attribute で変更する。
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|attr| attr.name() == name) {
+            Some(attr) => attr.set_value(value.to_string()),
+            None => {
+                let mut attr = Attribute::new();
+                attr.set_name(name.to_string());
+                attr.set_value(value.to_string());
+                self.attributes.push(attr);
+            }
+        }
+    }
+
+    /// 属性を取り除く。戻り値は属性を取り除いたかどうかを表す。
+    pub fn remove_attribute(&mut self, name: &str) -> bool {
+        let len = self.attributes.len();
+        self.attributes.retain(|attr| attr.name() != name);
+        self.attributes.len() != len
+    }
+
+    /// data- で始まるカスタムデータ属性を、名前をキャメルケースに変換したキーと値の組として属性の順に返す。
+    /// 例えば data-user-id 属性のキーは userId となる。名前に ASCII の大文字を含む属性は対象外とする。
+    /// https://html.spec.whatwg.org/multipage/dom.html#dom-dataset
+    pub fn dataset(&self) -> Vec<(String, String)> {
+        self.attributes
+            .iter()
+            .filter_map(|attr| {
+                let name = attr.name();
+                let key = name.strip_prefix("data-")?;
+                if name.chars().any(|c| c.is_ascii_uppercase()) {
+                    return None;
+                }
+                Some((Self::dataset_key(key), attr.value()))
+            })
+            .collect()
+    }
+
+    /// カスタムデータ属性の名前から data- を除いた部分を、dataset のキーに変換する。
+    /// ハイフンの直後に ASCII の小文字が続く場合、ハイフンを取り除いてその文字を大文字にする。
+    fn dataset_key(name: &str) -> String {
+        let mut key = String::new();
+        let mut chars = name.chars().peekable();
+        while let Some(c) = chars.next() {
+            match chars.peek() {
+                Some(next) if c == '-' && next.is_ascii_lowercase() => {
+                    key.push(next.to_ascii_uppercase());
+                    chars.next();
+                }
+                _ => key.push(c),
+            }
+        }
+        key
+    }
 }
 
 /// 要素の種類を表す列挙型。
@@ -208,6 +930,12 @@ pub enum ElementKind {
     Html,
     /// https://html.spec.whatwg.org/multipage/semantics.html#the-head-element
     Head,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-title-element
+    Title,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-meta-element
+    Meta,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-base-element
+    Base,
     /// https://html.spec.whatwg.org/multipage/semantics.html#the-style-element
     Style,
     /// https://html.spec.whatwg.org/multipage/scripting.html#the-script-element
@@ -219,26 +947,176 @@ pub enum ElementKind {
     /// https://html.spec.whatwg.org/multipage/sections.html#the-h1,-h2,-h3,-h4,-h5,-and-h6-elements
     H1,
     H2,
+    H3,
+    H4,
+    H5,
+    H6,
     /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-a-element
     A,
+    /// https://html.spec.whatwg.org/multipage/form-elements.html#the-textarea-element
+    Textarea,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-div-element
+    Div,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-span-element
+    Span,
+    /// https://html.spec.whatwg.org/multipage/sections.html#the-section-element
+    Section,
+    /// https://html.spec.whatwg.org/multipage/sections.html#the-article-element
+    Article,
+    /// https://html.spec.whatwg.org/multipage/sections.html#the-nav-element
+    Nav,
+    /// https://html.spec.whatwg.org/multipage/sections.html#the-header-element
+    Header,
+    /// https://html.spec.whatwg.org/multipage/sections.html#the-footer-element
+    Footer,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-main-element
+    Main,
+    /// https://html.spec.whatwg.org/multipage/sections.html#the-aside-element
+    Aside,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-ul-element
+    Ul,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-ol-element
+    Ol,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-li-element
+    Li,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-table-element
+    Table,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-thead-element
+    Thead,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-tbody-element
+    Tbody,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-tfoot-element
+    Tfoot,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-tr-element
+    Tr,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-td-element
+    Td,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-th-element
+    Th,
+    /// https://html.spec.whatwg.org/multipage/forms.html#the-form-element
+    Form,
+    /// https://html.spec.whatwg.org/multipage/input.html#the-input-element
+    Input,
+    /// https://html.spec.whatwg.org/multipage/form-elements.html#the-button-element
+    Button,
+    /// https://html.spec.whatwg.org/multipage/form-elements.html#the-select-element
+    Select,
+    /// https://html.spec.whatwg.org/multipage/form-elements.html#the-option-element
+    Option,
+    /// https://html.spec.whatwg.org/multipage/form-elements.html#the-optgroup-element
+    Optgroup,
+    /// https://html.spec.whatwg.org/multipage/embedded-content.html#the-img-element
+    Img,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-br-element
+    Br,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-hr-element
+    Hr,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-strong-element
+    Strong,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-em-element
+    Em,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-b-element
+    B,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-i-element
+    I,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-u-element
+    U,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-small-element
+    Small,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-pre-element
+    Pre,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-code-element
+    Code,
+    /// https://html.spec.whatwg.org/multipage/iframe-embed-object.html#the-iframe-element
+    Iframe,
+    /// https://html.spec.whatwg.org/multipage/scripting.html#the-noscript-element
+    Noscript,
+    /// 対応していない要素やカスタム要素。デフォルトではインライン要素として扱う。
+    /// https://html.spec.whatwg.org/multipage/dom.html#htmlunknownelement
+    Unknown,
 }
 
 impl FromStr for ElementKind {
     type Err = String;
 
     /// 文字列から要素を作成する。
+    /// 対応していない要素名やカスタム要素の場合は ElementKind::Unknown を返す。
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "html" => Ok(ElementKind::Html),
             "head" => Ok(ElementKind::Head),
+            "title" => Ok(ElementKind::Title),
+            "meta" => Ok(ElementKind::Meta),
+            "base" => Ok(ElementKind::Base),
             "style" => Ok(ElementKind::Style),
             "script" => Ok(ElementKind::Script),
             "body" => Ok(ElementKind::Body),
             "p" => Ok(ElementKind::P),
             "h1" => Ok(ElementKind::H1),
             "h2" => Ok(ElementKind::H2),
+            "h3" => Ok(ElementKind::H3),
+            "h4" => Ok(ElementKind::H4),
+            "h5" => Ok(ElementKind::H5),
+            "h6" => Ok(ElementKind::H6),
             "a" => Ok(ElementKind::A),
-            _ => Err(format!("unimplemented element name {:?}", s)),
+            "textarea" => Ok(ElementKind::Textarea),
+            "div" => Ok(ElementKind::Div),
+            "span" => Ok(ElementKind::Span),
+            "section" => Ok(ElementKind::Section),
+            "article" => Ok(ElementKind::Article),
+            "nav" => Ok(ElementKind::Nav),
+            "header" => Ok(ElementKind::Header),
+            "footer" => Ok(ElementKind::Footer),
+            "main" => Ok(ElementKind::Main),
+            "aside" => Ok(ElementKind::Aside),
+            "ul" => Ok(ElementKind::Ul),
+            "ol" => Ok(ElementKind::Ol),
+            "li" => Ok(ElementKind::Li),
+            "table" => Ok(ElementKind::Table),
+            "thead" => Ok(ElementKind::Thead),
+            "tbody" => Ok(ElementKind::Tbody),
+            "tfoot" => Ok(ElementKind::Tfoot),
+            "tr" => Ok(ElementKind::Tr),
+            "td" => Ok(ElementKind::Td),
+            "th" => Ok(ElementKind::Th),
+            "form" => Ok(ElementKind::Form),
+            "input" => Ok(ElementKind::Input),
+            "button" => Ok(ElementKind::Button),
+            "select" => Ok(ElementKind::Select),
+            "option" => Ok(ElementKind::Option),
+            "optgroup" => Ok(ElementKind::Optgroup),
+            "img" => Ok(ElementKind::Img),
+            "br" => Ok(ElementKind::Br),
+            "hr" => Ok(ElementKind::Hr),
+            "strong" => Ok(ElementKind::Strong),
+            "em" => Ok(ElementKind::Em),
+            "b" => Ok(ElementKind::B),
+            "i" => Ok(ElementKind::I),
+            "u" => Ok(ElementKind::U),
+            "small" => Ok(ElementKind::Small),
+            "pre" => Ok(ElementKind::Pre),
+            "code" => Ok(ElementKind::Code),
+            "iframe" => Ok(ElementKind::Iframe),
+            "noscript" => Ok(ElementKind::Noscript),
+            _ => Ok(ElementKind::Unknown),
+        }
+    }
+}
+
+/// Node 構造体の破棄。
+/// 子ノードと次の兄弟ノードは強い参照で連なっているため、そのまま破棄すると兄弟ノードの数だけ破棄の処理が再帰する。
+/// 兄弟ノードの多いツリーでコールスタックを溢れさせないように、他から参照されていないノードのリンクをスタックに移してから破棄する。
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<RefCell<Node>>> = Vec::new();
+        stack.extend(self.first_child.take());
+        stack.extend(self.next_sibling.take());
+        while let Some(node) = stack.pop() {
+            if let Ok(node) = Rc::try_unwrap(node) {
+                let mut node = node.into_inner();
+                stack.extend(node.first_child.take());
+                stack.extend(node.next_sibling.take());
+            }
         }
     }
 }
@@ -270,15 +1148,349 @@ impl Display for ElementKind {
         let s = match self {
             ElementKind::Html => "html",
             ElementKind::Head => "head",
+            ElementKind::Title => "title",
+            ElementKind::Meta => "meta",
+            ElementKind::Base => "base",
             ElementKind::Style => "style",
             ElementKind::Script => "script",
             ElementKind::Body => "body",
             ElementKind::H1 => "h1",
             ElementKind::H2 => "h2",
+            ElementKind::H3 => "h3",
+            ElementKind::H4 => "h4",
+            ElementKind::H5 => "h5",
+            ElementKind::H6 => "h6",
             ElementKind::P => "p",
             ElementKind::A => "a",
+            ElementKind::Textarea => "textarea",
+            ElementKind::Div => "div",
+            ElementKind::Span => "span",
+            ElementKind::Section => "section",
+            ElementKind::Article => "article",
+            ElementKind::Nav => "nav",
+            ElementKind::Header => "header",
+            ElementKind::Footer => "footer",
+            ElementKind::Main => "main",
+            ElementKind::Aside => "aside",
+            ElementKind::Ul => "ul",
+            ElementKind::Ol => "ol",
+            ElementKind::Li => "li",
+            ElementKind::Table => "table",
+            ElementKind::Thead => "thead",
+            ElementKind::Tbody => "tbody",
+            ElementKind::Tfoot => "tfoot",
+            ElementKind::Tr => "tr",
+            ElementKind::Td => "td",
+            ElementKind::Th => "th",
+            ElementKind::Form => "form",
+            ElementKind::Input => "input",
+            ElementKind::Button => "button",
+            ElementKind::Select => "select",
+            ElementKind::Option => "option",
+            ElementKind::Optgroup => "optgroup",
+            ElementKind::Img => "img",
+            ElementKind::Br => "br",
+            ElementKind::Hr => "hr",
+            ElementKind::Strong => "strong",
+            ElementKind::Em => "em",
+            ElementKind::B => "b",
+            ElementKind::I => "i",
+            ElementKind::U => "u",
+            ElementKind::Small => "small",
+            ElementKind::Pre => "pre",
+            ElementKind::Code => "code",
+            ElementKind::Iframe => "iframe",
+            ElementKind::Noscript => "noscript",
+            ElementKind::Unknown => "unknown",
         };
 
         write!(f, "{}", s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn element(name: &str) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            name,
+            Vec::new(),
+        )))))
+    }
+
+    /// 子ノードを最初から順にたどったときの要素の種類を返す。
+    /// 最後の子ノードから逆順にたどった結果と一致することも確認する。
+    fn children(parent: &Rc<RefCell<Node>>) -> Vec<Option<ElementKind>> {
+        let mut result = Vec::new();
+        let mut child = parent.borrow().first_child();
+        while let Some(c) = child {
+            assert!(Rc::ptr_eq(
+                parent,
+                &c.borrow().parent().upgrade().expect("parent should exist")
+            ));
+            result.push(c.borrow().get_element_kind());
+            child = c.borrow().next_sibling();
+        }
+
+        let mut reversed = Vec::new();
+        let mut child = parent.borrow().last_child().upgrade();
+        while let Some(c) = child {
+            reversed.push(c.borrow().get_element_kind());
+            child = c.borrow().previous_sibling().upgrade();
+        }
+        reversed.reverse();
+        assert_eq!(result, reversed);
+        result
+    }
+
+    #[test]
+    fn test_append_child() {
+        let div = element("div");
+        let p = element("p");
+        let span = element("span");
+        assert!(Node::append_child(&div, p.clone()).is_ok());
+        assert!(Node::append_child(&div, span.clone()).is_ok());
+        assert_eq!(
+            vec![Some(ElementKind::P), Some(ElementKind::Span)],
+            children(&div)
+        );
+
+        // 既にツリーに含まれているノードを追加すると、元の位置から移動する。
+        assert!(Node::append_child(&div, p.clone()).is_ok());
+        assert_eq!(
+            vec![Some(ElementKind::Span), Some(ElementKind::P)],
+            children(&div)
+        );
+
+        // 自分自身の子孫には追加できない。
+        assert!(Node::append_child(&p, div.clone()).is_err());
+        assert!(Node::append_child(&div, div.clone()).is_err());
+    }
+
+    #[test]
+    fn test_insert_before() {
+        let div = element("div");
+        let p = element("p");
+        let span = element("span");
+        let a = element("a");
+        assert!(Node::append_child(&div, p.clone()).is_ok());
+        assert!(Node::insert_before(&div, span.clone(), Some(&p)).is_ok());
+        assert!(Node::insert_before(&div, a.clone(), Some(&p)).is_ok());
+        assert_eq!(
+            vec![
+                Some(ElementKind::Span),
+                Some(ElementKind::A),
+                Some(ElementKind::P)
+            ],
+            children(&div)
+        );
+
+        // 子ノードではないノードを基準にはできない。
+        let other = element("div");
+        assert!(Node::insert_before(&div, other.clone(), Some(&other)).is_err());
+    }
+
+    #[test]
+    fn test_remove_child() {
+        let div = element("div");
+        let p = element("p");
+        let span = element("span");
+        let a = element("a");
+        for c in [p.clone(), span.clone(), a.clone()] {
+            assert!(Node::append_child(&div, c).is_ok());
+        }
+
+        assert!(Node::remove_child(&div, &span).is_ok());
+        assert_eq!(
+            vec![Some(ElementKind::P), Some(ElementKind::A)],
+            children(&div)
+        );
+        assert!(span.borrow().parent().upgrade().is_none());
+        assert!(span.borrow().next_sibling().is_none());
+
+        assert!(Node::remove_child(&div, &a).is_ok());
+        assert!(Node::remove_child(&div, &p).is_ok());
+        assert!(children(&div).is_empty());
+
+        assert!(Node::remove_child(&div, &p).is_err());
+    }
+
+    #[test]
+    fn test_set_attribute() {
+        let div = element("div");
+        Node::set_attribute(&div, "title", "a");
+        assert!(!div.borrow().is_style_dirty());
+        Node::set_attribute(&div, "title", "b");
+        Node::set_attribute(&div, "class", "c");
+        assert!(div.borrow().is_style_dirty());
+
+        let e = div
+            .borrow()
+            .get_element()
+            .expect("failed to get an element");
+        assert_eq!(Some("b".to_string()), e.get_attribute("title"));
+        assert_eq!(Some("c".to_string()), e.get_attribute("class"));
+        assert_eq!(2, e.attributes().len());
+    }
+
+    #[test]
+    fn test_remove_attribute() {
+        let div = element("div");
+        Node::set_attribute(&div, "id", "a");
+        div.borrow_mut().clear_style_dirty();
+
+        Node::remove_attribute(&div, "title");
+        assert!(!div.borrow().is_style_dirty());
+        Node::remove_attribute(&div, "id");
+        assert!(div.borrow().is_style_dirty());
+        assert_eq!(
+            None,
+            div.borrow()
+                .get_element()
+                .expect("failed to get an element")
+                .get_attribute("id")
+        );
+    }
+
+    #[test]
+    fn test_text_content() {
+        let div = element("div");
+        let p = element("p");
+        let a = Rc::new(RefCell::new(Node::new(NodeKind::Text("a".to_string()))));
+        let b = Rc::new(RefCell::new(Node::new(NodeKind::Text("b".to_string()))));
+        assert!(Node::append_child(&p, a.clone()).is_ok());
+        assert!(Node::append_child(&div, p.clone()).is_ok());
+        assert!(Node::append_child(&div, b.clone()).is_ok());
+        assert_eq!("ab".to_string(), div.borrow().text_content());
+        assert_eq!("a".to_string(), a.borrow().text_content());
+
+        Node::set_text_content(&a, "x");
+        assert_eq!("xb".to_string(), div.borrow().text_content());
+
+        Node::set_text_content(&div, "y");
+        assert_eq!("y".to_string(), div.borrow().text_content());
+        assert_eq!(vec![None], children(&div));
+        assert!(p.borrow().parent().upgrade().is_none());
+
+        Node::set_text_content(&div, "");
+        assert!(div.borrow().first_child().is_none());
+    }
+
+    /// children が子ノードのみを、descendants が子孫ノードを文書内に現れる順に返すことを確認する。
+    /// descendants は起点のノードの兄弟ノードや祖先の兄弟ノードをたどらない。
+    #[test]
+    fn test_children_and_descendants() {
+        let div = element("div");
+        let p = element("p");
+        let span = element("span");
+        let ul = element("ul");
+        let li = element("li");
+        let text = || Rc::new(RefCell::new(Node::new(NodeKind::Text("a".to_string()))));
+        Node::append_child(&div, p.clone()).expect("failed to append");
+        Node::append_child(&p, span.clone()).expect("failed to append");
+        Node::append_child(&p, text()).expect("failed to append");
+        Node::append_child(&div, ul.clone()).expect("failed to append");
+        Node::append_child(&ul, li.clone()).expect("failed to append");
+        Node::append_child(&div, text()).expect("failed to append");
+
+        let kinds = |nodes: Vec<Rc<RefCell<Node>>>| {
+            nodes
+                .iter()
+                .map(|n| n.borrow().get_element_kind())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![Some(ElementKind::P), Some(ElementKind::Ul), None],
+            kinds(div.borrow().children().collect())
+        );
+        assert_eq!(
+            vec![
+                Some(ElementKind::P),
+                Some(ElementKind::Span),
+                None,
+                Some(ElementKind::Ul),
+                Some(ElementKind::Li),
+                None,
+            ],
+            kinds(div.borrow().descendants().collect())
+        );
+        assert_eq!(
+            vec![Some(ElementKind::Span), None],
+            kinds(p.borrow().descendants().collect())
+        );
+        assert_eq!(
+            vec![Some(ElementKind::Li)],
+            kinds(ul.borrow().descendants().collect())
+        );
+        assert!(li.borrow().descendants().next().is_none());
+        assert!(span.borrow().children().next().is_none());
+    }
+
+    /// dataset が data- 属性の名前をキャメルケースのキーに変換して返すことを確認する。
+    #[test]
+    fn test_dataset() {
+        let mut e = Element::new("div", Vec::new());
+        e.set_attribute("id", "a");
+        e.set_attribute("data-user-id", "42");
+        e.set_attribute("data-x", "1");
+        e.set_attribute("data-a-1", "2");
+        e.set_attribute("data-Upper", "3");
+        e.set_attribute("data-", "4");
+
+        assert_eq!(
+            vec![
+                ("userId".to_string(), "42".to_string()),
+                ("x".to_string(), "1".to_string()),
+                ("a-1".to_string(), "2".to_string()),
+                ("".to_string(), "4".to_string()),
+            ],
+            e.dataset()
+        );
+    }
+
+    /// MutationObserver が、登録したノードとその子孫の変更のみを記録することを確認する。
+    #[test]
+    fn test_mutation_observer() {
+        let root = element("body");
+        let div = element("div");
+        let p = element("p");
+        let outside = element("section");
+        Node::append_child(&root, div.clone()).expect("failed to append");
+        Node::append_child(&root, outside.clone()).expect("failed to append");
+        Node::append_child(&div, p.clone()).expect("failed to append");
+
+        let observer = Rc::new(RefCell::new(MutationObserver::new()));
+        div.borrow_mut().observe(&observer);
+        p.borrow_mut().observe(&observer);
+
+        let text = Rc::new(RefCell::new(Node::new(NodeKind::Text("a".to_string()))));
+        Node::append_child(&p, text.clone()).expect("failed to append");
+        Node::set_attribute(&p, "class", "x");
+        Node::remove_attribute(&p, "title");
+        Node::set_text_content(&text, "b");
+        Node::set_attribute(&outside, "class", "y");
+        Node::append_child(&outside, p.clone()).expect("failed to append");
+
+        let records = observer.borrow_mut().take_records();
+        let kinds: Vec<_> = records.iter().map(|r| r.kind()).collect();
+        assert_eq!(
+            vec![
+                MutationKind::ChildList,
+                MutationKind::Attributes("class".to_string()),
+                MutationKind::CharacterData,
+                MutationKind::ChildList,
+            ],
+            kinds
+        );
+        assert!(Rc::ptr_eq(&p, &records[0].target()));
+        assert!(Rc::ptr_eq(&text, &records[2].target()));
+        assert!(Rc::ptr_eq(&div, &records[3].target()));
+        assert!(!observer.borrow().has_records());
+
+        // MutationObserver を破棄した後に変更しても、記録されない。
+        drop(observer);
+        Node::set_attribute(&div, "id", "z");
+    }
+}
@@ -23,6 +23,16 @@ impl Attribute {
     }
   }
 
+  /// 属性名を設定する。
+  pub fn set_name(&mut self, name: String) {
+    self.name = name;
+  }
+
+  /// 属性値を設定する。
+  pub fn set_value(&mut self, value: String) {
+    self.value = value;
+  }
+
   pub fn name(&self) -> String {
     self.name.clone()
   }
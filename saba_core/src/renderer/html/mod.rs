@@ -1,4 +1,5 @@
 pub mod attribute;
+pub mod parse_error;
 pub mod parser;
 pub mod token;
 
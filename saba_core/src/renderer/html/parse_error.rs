@@ -0,0 +1,131 @@
+use crate::renderer::dom::node::ElementKind;
+use alloc::string::String;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+/// HTML の字句解析や構文解析で検出したパースエラーの種類を表す列挙型。
+/// パースエラーが発生しても解析は続けるが、どのように入力を修正して解釈したかを確認するために記録する。
+/// https://html.spec.whatwg.org/multipage/parsing.html#parse-errors
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-abrupt-closing-of-empty-comment
+    AbruptClosingOfEmptyComment,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-end-tag-with-attributes
+    EndTagWithAttributes,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-end-tag-with-trailing-solidus
+    EndTagWithTrailingSolidus,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-eof-before-tag-name
+    EofBeforeTagName,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-eof-in-comment
+    EofInComment,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-eof-in-tag
+    EofInTag,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-incorrectly-opened-comment
+    IncorrectlyOpenedComment,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-invalid-first-character-of-tag-name
+    InvalidFirstCharacterOfTagName,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-missing-end-tag-name
+    MissingEndTagName,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-missing-whitespace-between-attributes
+    MissingWhitespaceBetweenAttributes,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-unexpected-null-character
+    UnexpectedNullCharacter,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-unexpected-question-mark-instead-of-tag-name
+    UnexpectedQuestionMarkInsteadOfTagName,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parse-error-unexpected-solidus-in-tag
+    UnexpectedSolidusInTag,
+    /// 対応する開始タグが開いていないため、ツリー構築段階で無視した終了タグ。
+    /// 仕様ではエラーコードが定義されていないツリー構築段階のパースエラーである。
+    UnexpectedEndTag,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let s = match self {
+            ParseErrorKind::AbruptClosingOfEmptyComment => "abrupt-closing-of-empty-comment",
+            ParseErrorKind::EndTagWithAttributes => "end-tag-with-attributes",
+            ParseErrorKind::EndTagWithTrailingSolidus => "end-tag-with-trailing-solidus",
+            ParseErrorKind::EofBeforeTagName => "eof-before-tag-name",
+            ParseErrorKind::EofInComment => "eof-in-comment",
+            ParseErrorKind::EofInTag => "eof-in-tag",
+            ParseErrorKind::IncorrectlyOpenedComment => "incorrectly-opened-comment",
+            ParseErrorKind::InvalidFirstCharacterOfTagName => "invalid-first-character-of-tag-name",
+            ParseErrorKind::MissingEndTagName => "missing-end-tag-name",
+            ParseErrorKind::MissingWhitespaceBetweenAttributes => {
+                "missing-whitespace-between-attributes"
+            }
+            ParseErrorKind::UnexpectedNullCharacter => "unexpected-null-character",
+            ParseErrorKind::UnexpectedQuestionMarkInsteadOfTagName => {
+                "unexpected-question-mark-instead-of-tag-name"
+            }
+            ParseErrorKind::UnexpectedSolidusInTag => "unexpected-solidus-in-tag",
+            ParseErrorKind::UnexpectedEndTag => "unexpected-end-tag",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+/// パースエラーの種類と、エラーを検出した入力文字列の位置 (バイト単位) を保持する構造体。
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    offset: usize,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// ツリー構築段階で、入力を修正して DOM ツリーを構築したことを表す診断の種類。
+/// パースエラーとは別に、ツリー構築の回復処理が変わっていないかをテストや実行時に確認するために記録する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseDiagnosticKind {
+    /// 省略された <html>、<head>、<body> 要素を補った。
+    ImpliedElement(ElementKind),
+    /// 対応する開始タグが開いていないため、終了タグを無視した。
+    IgnoredEndTag(String),
+    /// 閉じられていない要素が残った状態で入力が終わった。
+    UnexpectedEof,
+}
+
+impl Display for ParseDiagnosticKind {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            ParseDiagnosticKind::ImpliedElement(kind) => write!(f, "implied <{}>", kind),
+            ParseDiagnosticKind::IgnoredEndTag(tag) => write!(f, "ignored </{}>", tag),
+            ParseDiagnosticKind::UnexpectedEof => write!(f, "unexpected eof"),
+        }
+    }
+}
+
+/// 診断の種類と、その処理を行った入力文字列の位置 (バイト単位) を保持する構造体。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    kind: ParseDiagnosticKind,
+    offset: usize,
+}
+
+impl ParseDiagnostic {
+    pub fn new(kind: ParseDiagnosticKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+
+    pub fn kind(&self) -> ParseDiagnosticKind {
+        self.kind.clone()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
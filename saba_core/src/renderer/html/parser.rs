@@ -1,17 +1,29 @@
+use crate::encoding::Encoding;
+use crate::http::ContentType;
 use crate::renderer::dom::node::Element;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
 use crate::renderer::dom::node::Window;
 use crate::renderer::html::attribute::Attribute;
+use crate::renderer::html::parse_error::ParseDiagnostic;
+use crate::renderer::html::parse_error::ParseDiagnosticKind;
+use crate::renderer::html::parse_error::ParseError;
+use crate::renderer::html::parse_error::ParseErrorKind;
 use crate::renderer::html::token::HtmlToken;
 use crate::renderer::html::token::HtmlTokenizer;
+use crate::renderer::html::token::State;
 use alloc::rc::Rc;
+use alloc::rc::Weak;
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 use core::str::FromStr;
 
+/// 表に関する挿入モードで処理するタグ名。
+const TABLE_TAGS: [&str; 7] = ["table", "thead", "tbody", "tfoot", "tr", "td", "th"];
+
 /// https://html.spec.whatwg.org/multipage/parsing.html#the-insertion-mode
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum InsertionMode {
@@ -19,9 +31,15 @@ pub enum InsertionMode {
     BeforeHtml,
     BeforeHead,
     InHead,
+    InHeadNoscript,
     AfterHead,
     InBody,
     Text,
+    InTable,
+    InTableBody,
+    InRow,
+    InCell,
+    InSelect,
     AfterBody,
     AfterAfterBody,
 }
@@ -47,17 +65,139 @@ pub struct HtmlParser {
 
     /// HtmlTokenizer の構造体を格納している。次のトークンは t.next メソッドで取得できる。
     t: HtmlTokenizer,
+
+    /// 現在開いている <form> 要素。フォームコントロールのフォームオーナーとして設定する。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#form-element-pointer
+    form_element_pointer: Option<Rc<RefCell<Node>>>,
+
+    /// <meta> タグで宣言された文字エンコーディング。最初に見つかった宣言のみを使う。
+    declared_encoding: Option<Encoding>,
+
+    /// href 属性を持つ最初の <base> タグの href の値。文書のベース URL を決めるために使う。
+    /// https://html.spec.whatwg.org/multipage/semantics.html#frozen-base-url
+    base_href: Option<String>,
+
+    /// ツリー構築段階で検出したパースエラー。字句解析のパースエラーは HtmlTokenizer が保持する。
+    errors: Vec<ParseError>,
+
+    /// ツリー構築段階で入力を修正して解釈したことを表す診断。
+    diagnostics: Vec<ParseDiagnostic>,
+
+    /// スクリプトの実行が有効かどうかを表すスクリプトフラグ。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#scripting-flag
+    scripting: bool,
 }
 
 impl HtmlParser {
     /// HTML パーサーを作成する。
     pub fn new(t: HtmlTokenizer) -> Self {
+        let window = Rc::new(RefCell::new(Window::new()));
+        // document から Window をたどれるようにする。ID のマップの更新などに使う。
+        window
+            .borrow()
+            .document()
+            .borrow_mut()
+            .set_window(Rc::downgrade(&window));
         Self {
-            window: Rc::new(RefCell::new(Window::new())),
+            window,
             mode: InsertionMode::Initial,
             original_insertion_mode: InsertionMode::Initial,
             stack_of_open_elements: Vec::new(),
             t,
+            form_element_pointer: None,
+            declared_encoding: None,
+            base_href: None,
+            errors: Vec::new(),
+            diagnostics: Vec::new(),
+            scripting: true,
+        }
+    }
+
+    /// スクリプトの実行が有効かどうか (スクリプトフラグ) を設定する。デフォルトでは有効である。
+    /// 有効な場合は <noscript> の中身を読み飛ばし、無効な場合は通常の要素として解析する。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#scripting-flag
+    pub fn set_scripting(&mut self, enabled: bool) {
+        self.scripting = enabled;
+    }
+
+    /// <meta charset> や <meta http-equiv="Content-Type"> で宣言された文字エンコーディングを返す。
+    /// 宣言がない場合や対応していないエンコーディングの場合は None を返す。
+    pub fn declared_encoding(&self) -> Option<Encoding> {
+        self.declared_encoding
+    }
+
+    /// href 属性を持つ最初の <base> タグの href の値を返す。
+    pub fn base_href(&self) -> Option<String> {
+        self.base_href.clone()
+    }
+
+    /// ツリー構築段階で記録した診断を、記録した順に返す。
+    pub fn diagnostics(&self) -> Vec<ParseDiagnostic> {
+        self.diagnostics.clone()
+    }
+
+    /// 字句解析とツリー構築で検出したパースエラーを、入力文字列の位置の順に返す。
+    pub fn errors(&self) -> Vec<ParseError> {
+        let mut errors = self.t.errors();
+        errors.extend(self.errors.iter().copied());
+        errors.sort_by_key(|e| e.offset());
+        errors
+    }
+
+    /// 現在処理しているトークンの位置でパースエラーを記録する。
+    fn parse_error(&mut self, kind: ParseErrorKind) {
+        let offset = self.t.token_range().start;
+        self.errors.push(ParseError::new(kind, offset));
+    }
+
+    /// ツリー構築の回復処理を診断として記録する。
+    fn diagnose(&mut self, kind: ParseDiagnosticKind) {
+        let offset = self.t.token_range().start;
+        self.diagnostics.push(ParseDiagnostic::new(kind, offset));
+    }
+
+    /// 対応する開始タグが開いていない終了タグを無視し、パースエラーと診断を記録する。
+    fn ignore_end_tag(&mut self, tag: &str) {
+        self.parse_error(ParseErrorKind::UnexpectedEndTag);
+        self.diagnose(ParseDiagnosticKind::IgnoredEndTag(tag.to_string()));
+    }
+
+    /// 入力の終わりで、暗黙に閉じられない要素が開いたままであれば診断を記録する。
+    /// 何度も EOF トークンを処理する場合があるため、記録は1度だけ行う。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+    fn check_eof(&mut self) {
+        if self
+            .diagnostics
+            .iter()
+            .any(|d| d.kind() == ParseDiagnosticKind::UnexpectedEof)
+        {
+            return;
+        }
+        let unclosed = self.stack_of_open_elements.iter().any(|n| {
+            !matches!(
+                n.borrow().get_element_kind(),
+                Some(ElementKind::Html)
+                    | Some(ElementKind::Head)
+                    | Some(ElementKind::Body)
+                    | Some(ElementKind::P)
+                    | Some(ElementKind::Li)
+                    | Some(ElementKind::Option)
+                    | Some(ElementKind::Optgroup)
+                    | Some(ElementKind::Tbody)
+                    | Some(ElementKind::Thead)
+                    | Some(ElementKind::Tfoot)
+                    | Some(ElementKind::Tr)
+                    | Some(ElementKind::Td)
+                    | Some(ElementKind::Th)
+            )
+        });
+        if unclosed {
+            // 入力の最後の位置を記録するため、最後に読み込んだトークンの終了位置を使う。
+            let offset = self.t.token_range().end;
+            self.diagnostics.push(ParseDiagnostic::new(
+                ParseDiagnosticKind::UnexpectedEof,
+                offset,
+            ));
         }
     }
 
@@ -69,46 +209,26 @@ impl HtmlParser {
     /// HTML の構造を解析して要素ノードを正しい位置に挿入する。
     /// 指定されたタグと属性を持つ要素ノードw作成し、挿入先の位置を決定する。
     fn insert_element(&mut self, tag: &str, attributes: Vec<Attribute>) {
-        let window = self.window.borrow();
-
         // 現在の開いている要素スタック (stack_of_open_elements) の最後のノードを取得する。
         // スタックが空の場合はルート要素が現在参照しているノードになる。
         let current = match self.stack_of_open_elements.last() {
             Some(n) => n.clone(),
-            None => window.document(),
+            None => self.window.borrow().document(),
         };
         // 新しい要素ノードを作成する。
         // 変更可能な査証カウンタである Rc<RefCell<Node>> 形式とする。
         let node = Rc::new(RefCell::new(self.create_element(tag, attributes)));
 
         // 現在参照しているノードに子要素が存在する場合、最後の兄弟ノードを探索し、新しいノードをその直後に挿入する。
-        if current.borrow().first_child().is_some() {
-            let mut last_sibling = current.borrow().first_child();
-            loop {
-                last_sibling = match last_sibling {
-                    Some(ref node) => {
-                        if node.borrow().next_sibling().is_some() {
-                            node.borrow().next_sibling()
-                        } else {
-                            break;
-                        }
-                    }
-                    None => unimplemented!("last_sibling should be Some"),
-                };
-            }
-
+        let last_sibling = current.borrow().children().last();
+        if let Some(last_sibling) = last_sibling {
             // 新しいノードを最後の兄弟ノードの直後に挿入する。
             last_sibling
-                .unwrap()
                 .borrow_mut()
                 .set_next_sibling(Some(node.clone()));
             // 最後の兄弟ノードを新しいノードの直前の兄弟ノードとして設定する。
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &current
-                    .borrow()
-                    .first_child()
-                    .expect("failed to get a first child"),
-            ))
+            node.borrow_mut()
+                .set_previous_sibling(Rc::downgrade(&last_sibling))
         } else {
             // 現在参照しているノードに兄弟ノードが存在しな場合、現在参照しているノードの最初の子ノードとして新しいノードを設定する。
             current.borrow_mut().set_first_child(Some(node.clone()));
@@ -118,6 +238,9 @@ impl HtmlParser {
         current.borrow_mut().set_last_child(Rc::downgrade(&node)); // 現在のノードの最後の子ノードを新しいノードに設定する。
         node.borrow_mut().set_parent(Rc::downgrade(&current)); // 新しいノードの親を現在参照しているノードに設定する。
 
+        // <form> や <a href> などの要素の場合、ドキュメントのコレクションに追加する。
+        self.window.borrow_mut().element_inserted(&node);
+
         // 新しいノードを開いている要素スタックに追加する。
         self.stack_of_open_elements.push(node);
     }
@@ -166,6 +289,233 @@ impl HtmlParser {
         false
     }
 
+    /// 現在のノード (スタックの最後の要素) の要素の種類を返す。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#current-node
+    fn current_node_kind(&self) -> Option<ElementKind> {
+        self.stack_of_open_elements
+            .last()
+            .and_then(|n| n.borrow().get_element_kind())
+    }
+
+    /// stack_of_open_elements スタックに指定したタグ名の要素が存在する場合に true を返す。
+    /// ElementKind::Unknown の要素はタグ名で区別する必要があるため、タグ名で比較する。
+    fn contain_tag_in_stack(&self, tag: &str) -> bool {
+        self.stack_of_open_elements
+            .iter()
+            .any(|n| match n.borrow().get_element() {
+                Some(e) => e.tag_name() == tag,
+                None => false,
+            })
+    }
+
+    /// stack_of_open_elements スタックから指定したタグ名の要素が現れるまでノードを取り出し続ける。
+    fn pop_until_tag(&mut self, tag: &str) {
+        while let Some(current) = self.stack_of_open_elements.pop() {
+            if let Some(e) = current.borrow().get_element() {
+                if e.tag_name() == tag {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 終了タグを省略できる要素 (<p> や <li>) が現在のノードである間、スタックから取り出す。
+    /// except で指定した種類の要素は取り出さない。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#generate-implied-end-tags
+    fn generate_implied_end_tags(&mut self, except: Option<ElementKind>) {
+        while let Some(current) = self.stack_of_open_elements.last() {
+            let kind = current.borrow().get_element_kind();
+            match kind {
+                Some(ElementKind::P) | Some(ElementKind::Li) if kind != except => {
+                    self.stack_of_open_elements.pop();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// <p> 要素がボタンスコープにあるかを確認する。
+    /// スタックの上から順に確認し、<p> より先に <button> や <table> などのスコープの境界となる要素が現れた場合は false を返す。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-button-scope
+    fn has_p_in_button_scope(&self) -> bool {
+        for node in self.stack_of_open_elements.iter().rev() {
+            match node.borrow().get_element_kind() {
+                Some(ElementKind::P) => return true,
+                Some(ElementKind::Html)
+                | Some(ElementKind::Table)
+                | Some(ElementKind::Td)
+                | Some(ElementKind::Th)
+                | Some(ElementKind::Button) => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// ボタンスコープにある <p> 要素を閉じる。
+    /// <p> の中にブロック要素が現れた場合や、<p> の終了タグが現れた場合に使う。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#close-a-p-element
+    fn close_p_element(&mut self) {
+        if !self.has_p_in_button_scope() {
+            return;
+        }
+        self.generate_implied_end_tags(Some(ElementKind::P));
+        self.pop_until(ElementKind::P);
+    }
+
+    /// <li> の開始タグが現れた時、閉じられていない <li> 要素があれば暗黙的に閉じる。
+    /// スタックの上から順に確認し、<ul> や <ol> に到達した場合はリストの外側になるため何もしない。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+    fn close_list_item(&mut self) {
+        for i in (0..self.stack_of_open_elements.len()).rev() {
+            let kind = self.stack_of_open_elements[i].borrow().get_element_kind();
+            match kind {
+                Some(ElementKind::Li) => {
+                    self.stack_of_open_elements.truncate(i);
+                    return;
+                }
+                Some(ElementKind::Ul) | Some(ElementKind::Ol) => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// 現在のノードが引数の種類の要素か <html> 要素になるまでスタックからノードを取り出す。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#clear-the-stack-back-to-a-table-context
+    fn clear_stack_back_to(&mut self, element_kinds: &[ElementKind]) {
+        while let Some(current) = self.stack_of_open_elements.last() {
+            match current.borrow().get_element_kind() {
+                Some(ElementKind::Html) => return,
+                Some(kind) if element_kinds.contains(&kind) => return,
+                _ => {}
+            }
+            self.stack_of_open_elements.pop();
+        }
+    }
+
+    /// 開いている <td> または <th> 要素を閉じて InRow 状態に遷移する。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#close-the-cell
+    fn close_cell(&mut self) {
+        while let Some(current) = self.stack_of_open_elements.pop() {
+            let kind = current.borrow().get_element_kind();
+            if kind == Some(ElementKind::Td) || kind == Some(ElementKind::Th) {
+                break;
+            }
+        }
+        self.mode = InsertionMode::InRow;
+    }
+
+    /// スタックに積まれている要素から挿入モードを決め直す。
+    /// <table> の終了タグなどで、どの挿入モードに戻るべきかが分からない場合に使用する。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#reset-the-insertion-mode-appropriately
+    fn reset_insertion_mode(&mut self) {
+        for node in self.stack_of_open_elements.iter().rev() {
+            let mode = match node.borrow().get_element_kind() {
+                Some(ElementKind::Select) => InsertionMode::InSelect,
+                Some(ElementKind::Td) | Some(ElementKind::Th) => InsertionMode::InCell,
+                Some(ElementKind::Tr) => InsertionMode::InRow,
+                Some(ElementKind::Tbody) | Some(ElementKind::Thead) | Some(ElementKind::Tfoot) => {
+                    InsertionMode::InTableBody
+                }
+                Some(ElementKind::Table) => InsertionMode::InTable,
+                Some(ElementKind::Body) => InsertionMode::InBody,
+                _ => continue,
+            };
+            self.mode = mode;
+            return;
+        }
+        self.mode = InsertionMode::InBody;
+    }
+
+    /// 表に関する挿入モードで、トークンを処理する挿入モードを返す。
+    /// 表の構造に関わらないトークンは InBody 状態の規則で処理する。
+    /// 仕様では表の中に現れた要素やテキストは表の前に移動する (foster parenting) が、ここでは現在のノードにそのまま追加する。
+    /// また、<head> の中の <noscript> の中に現れた空白文字や <meta> などの開始タグは InHead 状態の規則で処理する。
+    fn mode_for_token(&self, token: &Option<HtmlToken>) -> InsertionMode {
+        if self.mode == InsertionMode::InHeadNoscript {
+            return match token {
+                Some(HtmlToken::Char(' ')) | Some(HtmlToken::Char('\n')) => InsertionMode::InHead,
+                Some(HtmlToken::StartTag {
+                    tag,
+                    self_closing: _,
+                    attributes: _,
+                }) if tag == "meta" || tag == "style" => InsertionMode::InHead,
+                _ => self.mode,
+            };
+        }
+
+        let handled = match (self.mode, token) {
+            (
+                InsertionMode::InCell,
+                Some(HtmlToken::StartTag {
+                    tag,
+                    self_closing: _,
+                    attributes: _,
+                }),
+            ) => tag != "table" && TABLE_TAGS.contains(&tag.as_str()),
+            (
+                InsertionMode::InTable
+                | InsertionMode::InTableBody
+                | InsertionMode::InRow
+                | InsertionMode::InCell,
+                Some(HtmlToken::StartTag {
+                    tag,
+                    self_closing: _,
+                    attributes: _,
+                })
+                | Some(HtmlToken::EndTag { tag }),
+            ) => TABLE_TAGS.contains(&tag.as_str()),
+            (
+                InsertionMode::InTable
+                | InsertionMode::InTableBody
+                | InsertionMode::InRow
+                | InsertionMode::InCell,
+                _,
+            ) => false,
+            _ => true,
+        };
+
+        if handled {
+            self.mode
+        } else {
+            InsertionMode::InBody
+        }
+    }
+
+    /// 現在のノード (直前に挿入したフォームコントロール) のフォームオーナーを、開いている <form> 要素に設定する。
+    fn associate_with_form(&mut self) {
+        if let (Some(form), Some(current)) = (
+            &self.form_element_pointer,
+            self.stack_of_open_elements.last(),
+        ) {
+            current.borrow_mut().set_form_owner(Rc::downgrade(form));
+        }
+    }
+
+    /// <meta> タグの属性から文字エンコーディングの宣言を取り出す。
+    /// charset 属性がある場合はその値を、http-equiv 属性が Content-Type の場合は content 属性の charset パラメータを使う。
+    /// https://html.spec.whatwg.org/multipage/semantics.html#charset
+    fn encoding_from_meta(attributes: &[Attribute]) -> Option<Encoding> {
+        let value = |name: &str| {
+            attributes
+                .iter()
+                .find(|attr| attr.name().eq_ignore_ascii_case(name))
+                .map(|attr| attr.value())
+        };
+
+        if let Some(charset) = value("charset") {
+            return Encoding::for_label(&charset);
+        }
+        match value("http-equiv") {
+            Some(http_equiv) if http_equiv.eq_ignore_ascii_case("content-type") => {
+                let content = value("content")?;
+                let charset = ContentType::parse(&content)?.charset()?;
+                Encoding::for_label(&charset)
+            }
+            _ => None,
+        }
+    }
+
     /// 文字からテキストノードを作成する。
     fn create_char(&self, c: char) -> Node {
         let mut s = String::new();
@@ -183,37 +533,35 @@ impl HtmlParser {
             None => return,
         };
 
-        // 現在の参照ノードがテキストノードの場合、そのノードに文字を追加する。
-        if let NodeKind::Text(ref mut s) = current.borrow_mut().kind {
-            s.push(c);
-            return;
+        // 現在の参照ノードの最後の子ノードがテキストノードの場合、そのノードに文字を追加する。
+        let last_child = current.borrow().last_child().upgrade();
+        if let Some(ref last) = last_child {
+            if let NodeKind::Text(ref mut s) = last.borrow_mut().kind {
+                s.push(c);
+                return;
+            }
         }
 
         // 改行文字や空白文字の場合、テキストノードを追加しない。
-        if c == '\n' || c == ' ' {
+        // ただし <pre> の中では空白文字も内容の一部であるため、そのまま残す。
+        if (c == '\n' || c == ' ') && !self.contain_in_stack(ElementKind::Pre) {
             return;
         }
 
-        // 現在の参照ノードが文字ノードではない場合、新しいテキストノードを作成する。
+        // 最後の子ノードが文字ノードではない場合、新しいテキストノードを作成する。
         let node = Rc::new(RefCell::new(self.create_char(c)));
 
-        // 現在の参照ノードにすでに子要素が存在する場合、新しいテキストノードをその直後に挿入する。
-        if current.borrow().first_child().is_some() {
-            current
-                .borrow()
-                .first_child()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &current
-                    .borrow()
-                    .first_child()
-                    .expect("failed to get a first child"),
-            ));
-        } else {
-            // 現在の参照ノードに兄弟ノードが存在しない場合、新しいテキストノードを現在の参照ノードの最初の子要素として設定する。
-            current.borrow_mut().set_first_child(Some(node.clone()));
+        // 現在の参照ノードにすでに子要素が存在する場合、新しいテキストノードを最後の子ノードの直後に挿入する。
+        // テキストノードは子ノードを持たないため、開いている要素スタックには追加しない。
+        match last_child {
+            Some(last) => {
+                last.borrow_mut().set_next_sibling(Some(node.clone()));
+                node.borrow_mut().set_previous_sibling(Rc::downgrade(&last));
+            }
+            None => {
+                // 現在の参照ノードに子ノードが存在しない場合、新しいテキストノードを現在の参照ノードの最初の子要素として設定する。
+                current.borrow_mut().set_first_child(Some(node.clone()));
+            }
         }
 
         // 挿入操作の完了後、親子関係と兄弟関係のリンクを適切に設定する。
@@ -221,20 +569,72 @@ impl HtmlParser {
         current.borrow_mut().set_last_child(Rc::downgrade(&node));
         // 新しいノードの親を現在の参照ノードに設定する。
         node.borrow_mut().set_parent(Rc::downgrade(&current));
+    }
 
-        // 新しいノードを開いている要素スタックに追加する。
-        self.stack_of_open_elements.push(node);
+    /// 入力文字列の続きをトークナイザに追加する。
+    /// HtmlTokenizer::new_streaming で作成したトークナイザを使う場合に、分割して届いた HTML を渡す。
+    pub fn feed(&mut self, chunk: &str) {
+        self.t.feed(chunk);
+    }
+
+    /// HTML をすべて追加したことを通知する。
+    pub fn close(&mut self) {
+        self.t.close();
+    }
+
+    /// HTML 文字列を context_element の子要素として解析し、どのツリーにも属さないノードの列を返す。
+    /// JavaScript の innerHTML の設定などで使う。
+    /// 表に関する要素を文脈とする場合も、InBody 状態として解析する。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments
+    pub fn parse_fragment(context_element: &Element, html: String) -> Vec<Rc<RefCell<Node>>> {
+        // 文脈となる要素の内容の種類に合わせて、トークナイザの状態を切り替える。
+        let mut t = HtmlTokenizer::new(html);
+        match context_element.kind() {
+            ElementKind::Title | ElementKind::Textarea => t.switch_to(State::Rcdata),
+            ElementKind::Style => t.switch_to(State::Rawtext),
+            ElementKind::Script => t.switch_to(State::ScriptData),
+            _ => {}
+        }
+
+        // 一時的な <html> 要素をルートとして、その子要素として解析する。
+        let mut parser = HtmlParser::new(t);
+        parser.insert_element("html", Vec::new());
+        parser.mode = InsertionMode::InBody;
+        let window = parser.construct_tree();
+
+        let root = match window.borrow().document().borrow().first_child() {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        let nodes: Vec<_> = root.borrow().children().collect();
+        // 一時的な <html> 要素は破棄されるため、親への参照を外す。
+        for node in &nodes {
+            node.borrow_mut().set_parent(Weak::new());
+        }
+        nodes
     }
 
     /// DOM ツリーを構築する。
+    /// トークナイザが入力の続きを待っている場合は、それまでに構築したツリーを返す。
+    /// feed で続きを追加した後に再度呼び出すと、挿入モードと開いている要素のスタックを引き継いで構築を再開する。
     pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
         let mut token = self.t.next();
 
         while token.is_some() {
-            match self.mode {
+            // コメントノードはサポートしていないため、どの挿入モードでもコメントトークンは無視する。
+            if let Some(HtmlToken::Comment(_)) = token {
+                token = self.t.next();
+                continue;
+            }
+
+            if let Some(HtmlToken::Eof) = token {
+                self.check_eof();
+            }
+
+            match self.mode_for_token(&token) {
                 // Initial 状態
                 InsertionMode::Initial => {
-                    // DOCTYPE トークンをサポートしていないため、<!doctype html> のようなトークンは文字トークンとして表す。
+                    // DOCTYPE トークンをサポートしていないため、<!doctype html> のようなトークンはコメントトークンとして表す。
                     // 文字トークンは無視する。
                     if let Some(HtmlToken::Char(_)) = token {
                         token = self.t.next();
@@ -276,6 +676,7 @@ impl HtmlParser {
                     }
                     // 上記以外の場合、自動的に HTML 要素を DOM ツリーに追加する。HTML タグを省略している場合もパースできる。
                     self.insert_element("html", Vec::new());
+                    self.diagnose(ParseDiagnosticKind::ImpliedElement(ElementKind::Html));
                     self.mode = InsertionMode::BeforeHead;
                     continue;
                 }
@@ -311,6 +712,7 @@ impl HtmlParser {
                     // 上記以外の場合、自動的に HEAD 要素を DOM ツリーに追加する。
                     // Head タグを省略している場合も正しくパースできる。
                     self.insert_element("head", Vec::new());
+                    self.diagnose(ParseDiagnosticKind::ImpliedElement(ElementKind::Head));
                     self.mode = InsertionMode::InHead;
                     continue;
                 }
@@ -327,26 +729,74 @@ impl HtmlParser {
                             }
                         }
                         // HtmlToken::StartTag でタグの名前が style や script の場合、DOM ツリーに新しいノードを追加し、Text 状態に遷移する。
+                        // 内容に含まれる < をタグとして扱わないように、トークナイザを RAWTEXT 状態やスクリプトデータ状態に切り替える。
                         Some(HtmlToken::StartTag {
                             ref tag,
                             self_closing: _,
                             ref attributes,
                         }) => {
-                            if tag == "style" || tag == "script" {
+                            if tag == "style" || tag == "script" || tag == "title" {
                                 self.insert_element(tag, attributes.to_vec());
+                                if tag == "style" {
+                                    self.t.switch_to(State::Rawtext);
+                                } else if tag == "title" {
+                                    self.t.switch_to(State::Rcdata);
+                                } else {
+                                    self.t.switch_to(State::ScriptData);
+                                }
                                 self.original_insertion_mode = self.mode;
                                 self.mode = InsertionMode::Text;
                                 token = self.t.next();
                                 continue;
                             }
 
-                            // head が省略されている HTML 文書を扱う絵で必要な処理。
-                            if tag == "body" {
-                                self.pop_until(ElementKind::Head);
-                                self.mode = InsertionMode::AfterHead;
+                            // <meta> は空要素のため、DOM ツリーにノードを追加した後すぐにスタックから取り出す。
+                            // 文字エンコーディングが宣言されている場合、最初の宣言を記録する。
+                            if tag == "meta" {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                if self.declared_encoding.is_none() {
+                                    self.declared_encoding = Self::encoding_from_meta(attributes);
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            // <base> は空要素のため、DOM ツリーにノードを追加した後すぐにスタックから取り出す。
+                            // href 属性を持つ最初の <base> タグのみが文書のベース URL に使われる。
+                            if tag == "base" {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                if self.base_href.is_none() {
+                                    self.base_href = attributes
+                                        .iter()
+                                        .find(|attr| attr.name() == "href")
+                                        .map(|attr| attr.value());
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            // <noscript> の開始タグの場合、スクリプトが有効であれば中身を読み飛ばすために Text 状態に遷移する。
+                            // スクリプトが無効であれば DOM ツリーにノードを追加し、InHeadNoscript 状態に遷移する。
+                            if tag == "noscript" {
+                                self.insert_element(tag, attributes.to_vec());
+                                if self.scripting {
+                                    self.t.switch_to(State::Rawtext);
+                                    self.original_insertion_mode = self.mode;
+                                    self.mode = InsertionMode::Text;
+                                } else {
+                                    self.mode = InsertionMode::InHeadNoscript;
+                                }
+                                token = self.t.next();
                                 continue;
                             }
-                            if let Ok(_element_kind) = ElementKind::from_str(tag) {
+
+                            // head の中に置けない要素の開始タグの場合、head の終了タグが省略されたとみなす。
+                            // head を閉じて AfterHead 状態に遷移し、同じトークンを処理し直す。
+                            // 対応していない要素やカスタム要素の開始タグも同様に扱う。
+                            // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inhead
+                            if tag != "head" && tag != "html" {
                                 self.pop_until(ElementKind::Head);
                                 self.mode = InsertionMode::AfterHead;
                                 continue;
@@ -365,11 +815,43 @@ impl HtmlParser {
                         Some(HtmlToken::Eof) | None => {
                             return self.window.clone();
                         }
+                        // コメントトークンはループの先頭で読み飛ばしている。
+                        Some(HtmlToken::Comment(_)) => {}
                     }
                     // <meta> や <title> などのサポートしていないタグは無視する。
                     token = self.t.next();
                     continue;
                 }
+                // InHeadNoscript 状態
+                // スクリプトが無効な場合に、<head> の中の <noscript> の中身を扱う。
+                // 空白文字や <meta>、<style> の開始タグは mode_for_token により InHead 状態の規則で処理される。
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inheadnoscript
+                InsertionMode::InHeadNoscript => {
+                    match token {
+                        Some(HtmlToken::EndTag { ref tag }) if tag == "noscript" => {
+                            self.stack_of_open_elements.pop();
+                            self.mode = InsertionMode::InHead;
+                            token = self.t.next();
+                            continue;
+                        }
+                        Some(HtmlToken::EndTag { ref tag }) if tag != "br" => {
+                            self.ignore_end_tag(tag);
+                            token = self.t.next();
+                            continue;
+                        }
+                        Some(HtmlToken::StartTag { ref tag, .. })
+                            if tag == "head" || tag == "noscript" =>
+                        {
+                            token = self.t.next();
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    // それ以外のトークンの場合、<noscript> を閉じて InHead 状態で処理し直す。
+                    self.stack_of_open_elements.pop();
+                    self.mode = InsertionMode::InHead;
+                    continue;
+                }
                 // AfterHead 状態
                 // 主に Body 開始タグを扱う。
                 InsertionMode::AfterHead => {
@@ -403,6 +885,7 @@ impl HtmlParser {
                     // 上記以外の場合、自動的に body 要素を DOM ツリーに追加する。
                     // これにより、body タグを省略している場合でもパースできる。
                     self.insert_element("body", Vec::new());
+                    self.diagnose(ParseDiagnosticKind::ImpliedElement(ElementKind::Body));
                     self.mode = InsertionMode::InBody;
                     continue;
                 }
@@ -417,13 +900,119 @@ impl HtmlParser {
                             ref attributes,
                         }) => match tag.as_str() {
                             // p タグの開始の場合、Element ノードを作成して DOM ツリーに追加し、次のトークンに進める。
+                            // <p> の中に <p> を入れることはできないため、開いている <p> を閉じてから追加する。
                             "p" => {
+                                self.close_p_element();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <h1> から <h6> の開始タグの場合、DOM ツリーにノードを追加する。
+                            // 開いている <p> を閉じ、現在のノードが見出しの場合は見出しを入れ子にできないため閉じる。
+                            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                                self.close_p_element();
+                                let current_kind = self
+                                    .stack_of_open_elements
+                                    .last()
+                                    .and_then(|n| n.borrow().get_element_kind());
+                                if let Some(
+                                    ElementKind::H1
+                                    | ElementKind::H2
+                                    | ElementKind::H3
+                                    | ElementKind::H4
+                                    | ElementKind::H5
+                                    | ElementKind::H6,
+                                ) = current_kind
+                                {
+                                    self.stack_of_open_elements.pop();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <div> や <section> などの汎用的なコンテナ要素の開始タグの場合、開いている <p> を閉じてから DOM ツリーにノードを追加する。
+                            "div" | "section" | "article" | "nav" | "header" | "footer"
+                            | "main" | "aside" => {
+                                self.close_p_element();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <pre> の開始タグの場合、DOM ツリーにノードを追加する。
+                            // 開始タグの直後の改行文字は、見た目を整えるためのものとして無視する。
+                            "pre" => {
+                                self.close_p_element();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                if let Some(HtmlToken::Char('\n')) = token {
+                                    token = self.t.next();
+                                }
+                                continue;
+                            }
+                            // <span> や <strong> などのインライン要素の開始タグの場合、DOM ツリーにノードを追加する。
+                            "span" | "strong" | "em" | "b" | "i" | "u" | "small" | "code" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <table> の開始タグの場合、DOM ツリーにノードを追加し、InTable 状態に遷移する。
+                            "table" => {
+                                self.close_p_element();
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InTable;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <form> の開始タグの場合、DOM ツリーにノードを追加し、フォーム要素ポインタに設定する。
+                            // すでに <form> が開いている場合、フォームは入れ子にできないため無視する。
+                            "form" => {
+                                if self.form_element_pointer.is_none() {
+                                    self.close_p_element();
+                                    self.insert_element(tag, attributes.to_vec());
+                                    self.form_element_pointer =
+                                        self.stack_of_open_elements.last().cloned();
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <input> は空要素のため、DOM ツリーにノードを追加した後すぐにスタックから取り出す。
+                            "input" => {
                                 self.insert_element(tag, attributes.to_vec());
+                                self.associate_with_form();
+                                self.stack_of_open_elements.pop();
                                 token = self.t.next();
                                 continue;
                             }
-                            // <h1> や <h2> の開始タグの場合、DOM ツリーにノードを追加する。
-                            "h1" | "h2" => {
+                            // <img>、<br>、<hr> は空要素のため、DOM ツリーにノードを追加した後すぐにスタックから取り出す。
+                            // <img> の src や alt などの属性は画像の読み込みやレイアウトで使用するため、要素の属性として保持する。
+                            "img" | "br" | "hr" => {
+                                // <hr> はブロック要素のため、開いている <p> を閉じる。
+                                if tag == "hr" {
+                                    self.close_p_element();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <button> の開始タグの場合、DOM ツリーにノードを追加し、フォームオーナーを設定する。
+                            "button" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.associate_with_form();
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <ul> や <ol> の開始タグの場合、DOM ツリーにノードを追加する。
+                            "ul" | "ol" => {
+                                self.close_p_element();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <li> の開始タグの場合、閉じられていない <li> を暗黙的に閉じてから DOM ツリーにノードを追加する。
+                            "li" => {
+                                self.close_list_item();
+                                self.close_p_element();
                                 self.insert_element(tag, attributes.to_vec());
                                 token = self.t.next();
                                 continue;
@@ -434,8 +1023,76 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            // <select> の開始タグの場合、DOM ツリーにノードを追加し、InSelect 状態に遷移する。
+                            "select" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.associate_with_form();
+                                self.mode = InsertionMode::InSelect;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <select> の外に現れた <option> や <optgroup> の開始タグの場合、開いている <option> を閉じてからノードを追加する。
+                            "option" | "optgroup" => {
+                                if self.current_node_kind() == Some(ElementKind::Option) {
+                                    self.stack_of_open_elements.pop();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <textarea> の開始タグの場合、DOM ツリーにノードを追加し、トークナイザを RCDATA 状態に切り替えて Text 状態に遷移する。
+                            "textarea" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.associate_with_form();
+                                self.t.switch_to(State::Rcdata);
+                                self.original_insertion_mode = self.mode;
+                                self.mode = InsertionMode::Text;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <noscript> の開始タグの場合、DOM ツリーにノードを追加する。
+                            // スクリプトが有効であれば、中身を読み飛ばすためにトークナイザを RAWTEXT 状態に切り替えて Text 状態に遷移する。
+                            // スクリプトが無効であれば、中身を通常の要素として解析する。
+                            "noscript" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                if self.scripting {
+                                    self.t.switch_to(State::Rawtext);
+                                    self.original_insertion_mode = self.mode;
+                                    self.mode = InsertionMode::Text;
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <style> の開始タグの場合、<head> の中と同様に DOM ツリーにノードを追加し、
+                            // トークナイザを RAWTEXT 状態に切り替えて Text 状態に遷移する。
+                            // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+                            "style" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.t.switch_to(State::Rawtext);
+                                self.original_insertion_mode = self.mode;
+                                self.mode = InsertionMode::Text;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <iframe> の開始タグの場合、DOM ツリーにノードを追加し、トークナイザを RAWTEXT 状態に切り替えて Text 状態に遷移する。
+                            // <iframe> の中身は表示されないため、終了タグまでをテキストとして扱う。
+                            // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+                            "iframe" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.t.switch_to(State::Rawtext);
+                                self.original_insertion_mode = self.mode;
+                                self.mode = InsertionMode::Text;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // 対応していない要素やカスタム要素の開始タグの場合、ElementKind::Unknown として DOM ツリーに追加する。
+                            // 対応している要素でここまでに処理されなかったもの (<html> など) は無視する。
                             _ => {
+                                if ElementKind::from_str(tag) == Ok(ElementKind::Unknown) {
+                                    self.insert_element(tag, attributes.to_vec());
+                                }
                                 token = self.t.next();
+                                continue;
                             }
                         },
                         // 次に処理するトークンが終了タグの場合の処理
@@ -443,11 +1100,13 @@ impl HtmlParser {
                             match tag.as_str() {
                                 "body" => {
                                     self.mode = InsertionMode::AfterBody;
-                                    token = self.t.next();
                                     // パースに失敗した場合、トークンを無視する。
                                     if !self.contain_in_stack(ElementKind::Body) {
+                                        self.ignore_end_tag(tag);
+                                        token = self.t.next();
                                         continue;
                                     }
+                                    token = self.t.next();
                                     self.pop_until(ElementKind::Body);
                                     continue;
                                 }
@@ -462,33 +1121,89 @@ impl HtmlParser {
                                 }
                                 // 次のトークンが </p> 終了タグの時、pop_until メソッドを使ってスタックから <p> タグまで取り出してトークンを次に進める。
                                 "p" => {
+                                    // 対応する開始タグがない終了タグはパースエラーであり、無視する。
+                                    if self.has_p_in_button_scope() {
+                                        self.close_p_element();
+                                    } else {
+                                        self.ignore_end_tag(tag);
+                                    }
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                // 次のトークンが </h1> から </h6> の終了タグの場合、スタックから対応する見出しタグまでを取り出し、トークンを次に進める。
+                                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
+                                    // 対応する開始タグがない終了タグはパースエラーであり、無視する。
+                                    if self.contain_in_stack(element_kind) {
+                                        self.pop_until(element_kind);
+                                    } else {
+                                        self.ignore_end_tag(tag);
+                                    }
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                // </form> の場合、フォーム要素ポインタを解除し、<form> 要素をスタックから取り除く。
+                                // <form> の後に開いた要素は閉じずに残す。
+                                "form" => {
+                                    match self.form_element_pointer.take() {
+                                        Some(form) => {
+                                            self.stack_of_open_elements
+                                                .retain(|n| !Rc::ptr_eq(n, &form));
+                                        }
+                                        None => self.ignore_end_tag(tag),
+                                    }
                                     token = self.t.next();
-                                    self.pop_until(element_kind);
                                     continue;
                                 }
-                                // 次のトークンが </h1> や </h2> の終了タグの場合、スタックから <h1> や <h2> タグまでを取り出し、トークンを次に進める。
-                                "h1" | "h2" => {
+                                // 次のトークンがコンテナ要素や文字を装飾する要素の終了タグの場合、スタックから対応するタグまで取り出し、トークンを次に進める。
+                                "div" | "span" | "section" | "article" | "nav" | "header"
+                                | "footer" | "main" | "aside" | "ul" | "ol" | "li" | "button"
+                                | "strong" | "em" | "b" | "i" | "u" | "small" | "code" | "pre"
+                                | "noscript" | "option" | "optgroup" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
+                                    // 対応する開始タグがない終了タグはパースエラーであり、無視する。
+                                    if self.contain_in_stack(element_kind) {
+                                        self.pop_until(element_kind);
+                                    } else {
+                                        self.ignore_end_tag(tag);
+                                    }
                                     token = self.t.next();
-                                    self.pop_until(element_kind);
                                     continue;
                                 }
                                 // 次のトークンが </a> 終了タグの場合、スタックから <a> タグまで取り出し、トークンを次に進める。
                                 "a" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("faild to convert string to ElementKind");
-                                    token = self.t.next();
-                                    self.pop_until(element_kind);
+                                    // 対応する開始タグがない終了タグはパースエラーであり、無視する。
+                                    if self.contain_in_stack(element_kind) {
+                                        self.pop_until(element_kind);
+                                    } else {
+                                        self.ignore_end_tag(tag);
+                                    }
+                                    token = self.t.next();
                                     continue;
                                 }
+                                // 対応していない要素やカスタム要素の終了タグの場合、タグ名が一致する要素までスタックから取り出す。
                                 _ => {
+                                    if ElementKind::from_str(tag) == Ok(ElementKind::Unknown) {
+                                        if self.contain_tag_in_stack(tag) {
+                                            self.pop_until_tag(tag);
+                                        } else {
+                                            self.ignore_end_tag(tag);
+                                        }
+                                    }
                                     token = self.t.next();
+                                    continue;
                                 }
                             }
                         }
+                        // NUL 文字は無視する。パースエラーはトークナイザが記録している。
+                        Some(HtmlToken::Char('\0')) => {
+                            token = self.t.next();
+                            continue;
+                        }
                         // InBody 状態で HtmlToken::Char が出てきた場合、テキストノードを DOM ツリーに追加する。
                         Some(HtmlToken::Char(c)) => {
                             self.insert_char(c);
@@ -497,7 +1212,9 @@ impl HtmlParser {
                         }
                         Some(HtmlToken::Eof) | None => {
                             return self.window.clone();
-                        } // _ => {}
+                        }
+                        // コメントトークンはループの先頭で読み飛ばしている。
+                        Some(HtmlToken::Comment(_)) => {}
                     }
                 }
                 // Text 状態は <style> や <script> タグが開始した後の状態である。
@@ -521,9 +1238,36 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            if tag == "title" {
+                                self.pop_until(ElementKind::Title);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
+                            if tag == "textarea" {
+                                self.pop_until(ElementKind::Textarea);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
+                            if tag == "iframe" {
+                                self.pop_until(ElementKind::Iframe);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
+                            if tag == "noscript" {
+                                self.pop_until(ElementKind::Noscript);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
                         }
                         Some(HtmlToken::Char(c)) => {
-                            self.insert_char(c);
+                            // スクリプトが有効な場合の <noscript> の中身は表示しないため、DOM ツリーに追加しない。
+                            if self.current_node_kind() != Some(ElementKind::Noscript) {
+                                self.insert_char(c);
+                            }
                             token = self.t.next();
                             continue;
                         }
@@ -531,6 +1275,281 @@ impl HtmlParser {
                     }
                     self.mode = self.original_insertion_mode;
                 }
+                // InTable 状態は <table> 要素の直下を処理する状態である。
+                // 行やセルが <tbody> の外に現れた場合、<tbody> を補って InTableBody 状態で処理し直す。
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intable
+                InsertionMode::InTable => {
+                    match token {
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => match tag.as_str() {
+                            "thead" | "tbody" | "tfoot" => {
+                                self.clear_stack_back_to(&[ElementKind::Table]);
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InTableBody;
+                                token = self.t.next();
+                                continue;
+                            }
+                            "tr" | "td" | "th" => {
+                                self.clear_stack_back_to(&[ElementKind::Table]);
+                                self.insert_element("tbody", Vec::new());
+                                self.mode = InsertionMode::InTableBody;
+                                continue;
+                            }
+                            // 入れ子にならない <table> の開始タグは、現在の表を閉じてから処理し直す。
+                            "table" => {
+                                self.pop_until(ElementKind::Table);
+                                self.reset_insertion_mode();
+                                continue;
+                            }
+                            _ => {}
+                        },
+                        Some(HtmlToken::EndTag { ref tag }) => {
+                            if tag == "table" {
+                                self.pop_until(ElementKind::Table);
+                                self.reset_insertion_mode();
+                                token = self.t.next();
+                                continue;
+                            }
+                            self.ignore_end_tag(tag);
+                        }
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+                        _ => {}
+                    }
+                    token = self.t.next();
+                }
+                // InTableBody 状態は <thead>、<tbody>、<tfoot> 要素の直下を処理する状態である。
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intbody
+                InsertionMode::InTableBody => {
+                    let table_body_context =
+                        [ElementKind::Thead, ElementKind::Tbody, ElementKind::Tfoot];
+                    match token {
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => match tag.as_str() {
+                            "tr" => {
+                                self.clear_stack_back_to(&table_body_context);
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InRow;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <tr> を省略したセルは、<tr> を補って InRow 状態で処理し直す。
+                            "td" | "th" => {
+                                self.clear_stack_back_to(&table_body_context);
+                                self.insert_element("tr", Vec::new());
+                                self.mode = InsertionMode::InRow;
+                                continue;
+                            }
+                            // 新しいセクションや表が始まる場合、現在のセクションを閉じて InTable 状態で処理し直す。
+                            _ => {
+                                self.clear_stack_back_to(&table_body_context);
+                                self.stack_of_open_elements.pop();
+                                self.mode = InsertionMode::InTable;
+                                continue;
+                            }
+                        },
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "thead" | "tbody" | "tfoot" => {
+                                if self.contain_tag_in_stack(tag) {
+                                    self.clear_stack_back_to(&table_body_context);
+                                    self.stack_of_open_elements.pop();
+                                    self.mode = InsertionMode::InTable;
+                                } else {
+                                    self.ignore_end_tag(tag);
+                                }
+                            }
+                            "table" => {
+                                self.clear_stack_back_to(&table_body_context);
+                                self.stack_of_open_elements.pop();
+                                self.mode = InsertionMode::InTable;
+                                continue;
+                            }
+                            _ => {
+                                self.ignore_end_tag(tag);
+                            }
+                        },
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+                        _ => {}
+                    }
+                    token = self.t.next();
+                }
+                // InRow 状態は <tr> 要素の直下を処理する状態である。
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intr
+                InsertionMode::InRow => {
+                    match token {
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => match tag.as_str() {
+                            "td" | "th" => {
+                                self.clear_stack_back_to(&[ElementKind::Tr]);
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InCell;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // 新しい行やセクションが始まる場合、現在の行を閉じて InTableBody 状態で処理し直す。
+                            _ => {
+                                self.clear_stack_back_to(&[ElementKind::Tr]);
+                                self.stack_of_open_elements.pop();
+                                self.mode = InsertionMode::InTableBody;
+                                continue;
+                            }
+                        },
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "tr" => {
+                                self.clear_stack_back_to(&[ElementKind::Tr]);
+                                self.stack_of_open_elements.pop();
+                                self.mode = InsertionMode::InTableBody;
+                            }
+                            "thead" | "tbody" | "tfoot" | "table" => {
+                                if self.contain_tag_in_stack(tag) {
+                                    self.clear_stack_back_to(&[ElementKind::Tr]);
+                                    self.stack_of_open_elements.pop();
+                                    self.mode = InsertionMode::InTableBody;
+                                    continue;
+                                }
+                                self.ignore_end_tag(tag);
+                            }
+                            _ => {
+                                self.ignore_end_tag(tag);
+                            }
+                        },
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+                        _ => {}
+                    }
+                    token = self.t.next();
+                }
+                // InCell 状態は <td> や <th> 要素の中を処理する状態である。
+                // 表の構造に関わるタグ以外は InBody 状態の規則で処理する。
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intd
+                InsertionMode::InCell => {
+                    match token {
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "td" | "th" => {
+                                if self.contain_tag_in_stack(tag) {
+                                    self.pop_until_tag(tag);
+                                    self.mode = InsertionMode::InRow;
+                                } else {
+                                    self.ignore_end_tag(tag);
+                                }
+                            }
+                            _ => {
+                                if self.contain_tag_in_stack(tag) {
+                                    self.close_cell();
+                                    continue;
+                                }
+                                self.ignore_end_tag(tag);
+                            }
+                        },
+                        // 次のセルや行が始まる場合、現在のセルを閉じて InRow 状態で処理し直す。
+                        Some(HtmlToken::StartTag {
+                            tag: _,
+                            self_closing: _,
+                            attributes: _,
+                        }) => {
+                            self.close_cell();
+                            continue;
+                        }
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+                        _ => {}
+                    }
+                    token = self.t.next();
+                }
+                // InSelect 状態は <select> 要素の中を処理する状態である。
+                // <option> と <optgroup> 以外の要素は無視し、<option> の中の文字はテキストノードとして追加する。
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inselect
+                InsertionMode::InSelect => {
+                    match token {
+                        Some(HtmlToken::Char(c)) => {
+                            self.insert_char(c);
+                        }
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => match tag.as_str() {
+                            "option" => {
+                                if self.current_node_kind() == Some(ElementKind::Option) {
+                                    self.stack_of_open_elements.pop();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                            }
+                            "optgroup" => {
+                                if self.current_node_kind() == Some(ElementKind::Option) {
+                                    self.stack_of_open_elements.pop();
+                                }
+                                if self.current_node_kind() == Some(ElementKind::Optgroup) {
+                                    self.stack_of_open_elements.pop();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                            }
+                            // <select> の中の <select> の開始タグは、</select> として扱う。
+                            "select" => {
+                                self.pop_until(ElementKind::Select);
+                                self.reset_insertion_mode();
+                            }
+                            // <input> や <textarea> は <select> の中に入れられないため、<select> を閉じて処理し直す。
+                            "input" | "textarea" => {
+                                self.pop_until(ElementKind::Select);
+                                self.reset_insertion_mode();
+                                continue;
+                            }
+                            _ => {}
+                        },
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "optgroup" => {
+                                // <optgroup> の中で開いている <option> も合わせて閉じる。
+                                let len = self.stack_of_open_elements.len();
+                                if self.current_node_kind() == Some(ElementKind::Option)
+                                    && len >= 2
+                                    && self.stack_of_open_elements[len - 2]
+                                        .borrow()
+                                        .get_element_kind()
+                                        == Some(ElementKind::Optgroup)
+                                {
+                                    self.stack_of_open_elements.pop();
+                                }
+                                if self.current_node_kind() == Some(ElementKind::Optgroup) {
+                                    self.stack_of_open_elements.pop();
+                                } else {
+                                    self.ignore_end_tag(tag);
+                                }
+                            }
+                            "option" => {
+                                if self.current_node_kind() == Some(ElementKind::Option) {
+                                    self.stack_of_open_elements.pop();
+                                } else {
+                                    self.ignore_end_tag(tag);
+                                }
+                            }
+                            "select" => {
+                                self.pop_until(ElementKind::Select);
+                                self.reset_insertion_mode();
+                            }
+                            _ => self.ignore_end_tag(tag),
+                        },
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+                        _ => {}
+                    }
+                    token = self.t.next();
+                }
                 // AfterBody 状態の場合、主に <html> 終了タグを扱う。
                 // 次のトークンが文字トークンの場合、無視して次のトークンを処理する。
                 // 次のトークンが HtmlToken::EndTag でタグの名前が <html> の場合、AfterAfterBody 状態に遷移する。
@@ -576,6 +1595,10 @@ impl HtmlParser {
             }
         }
 
+        // 入力がすべて揃った状態でトークンが尽きた場合は、入力の最後まで処理したことを表す。
+        if self.t.is_closed() {
+            self.check_eof();
+        }
         self.window.clone()
     }
 }
@@ -583,7 +1606,11 @@ impl HtmlParser {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::alloc::string::ToString;
+    use crate::renderer::dom::api::get_element_by_id;
+    use crate::renderer::dom::api::get_title_content;
+    use crate::renderer::dom::api::inner_html;
+    use crate::renderer::dom::api::outer_html;
+    use crate::utils::convert_dom_to_string;
     use alloc::vec;
 
     #[test]
@@ -786,4 +1813,1242 @@ mod tests {
             text
         );
     }
+
+    #[test]
+    /// <style> の内容にタグが含まれる場合のテスト。
+    /// </p> は終了タグとして扱わず、style ノードの子のテキストノードにそのまま含まれることを確認する。
+    fn test_style_with_tag() {
+        let html = "<html><head><style>a</p>{}</style></head></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let style = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(Some(ElementKind::Style), style.borrow().get_element_kind());
+
+        let text = style
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of style");
+        let kind = text.borrow().kind();
+        match kind {
+            NodeKind::Text(s) => assert_eq!("a</p>{}", s),
+            kind => panic!("unexpected node {:?}", kind),
+        }
+    }
+
+    #[test]
+    /// 不正なマークアップを含む HTML でパニックしないことのテスト。
+    /// 対応する開始タグがない終了タグやボガスコメントは無視され、テキストだけが body に追加されることを確認する。
+    fn test_malformed_markup() {
+        let html = "<?xml version=\"1.0\"?><!DOCTYPE html><!><body></3></p></h1>text</a></body>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+        assert_eq!(Some(ElementKind::Body), body.borrow().get_element_kind());
+
+        let text = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        let kind = text.borrow().kind();
+        match kind {
+            NodeKind::Text(s) => assert_eq!("text", s),
+            kind => panic!("unexpected node {:?}", kind),
+        }
+
+        for html in [
+            "<p",
+            "</",
+            "<body></p",
+            "<style>a</sty",
+            "<textarea>a<",
+            "<!-- a",
+        ] {
+            let t = HtmlTokenizer::new(html.to_string());
+            HtmlParser::new(t).construct_tree();
+        }
+    }
+
+    #[test]
+    /// 字句解析とツリー構築のパースエラーが、入力文字列の位置の順に記録されることを確認する。
+    fn test_parse_errors() {
+        let html = "<body></p>a</3></body>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let mut parser = HtmlParser::new(t);
+        parser.construct_tree();
+
+        let expected = vec![
+            ParseError::new(ParseErrorKind::UnexpectedEndTag, 6),
+            ParseError::new(ParseErrorKind::InvalidFirstCharacterOfTagName, 13),
+        ];
+        assert_eq!(expected, parser.errors());
+    }
+
+    #[test]
+    /// HTML を分割して追加しながら構築した DOM ツリーが、まとめて構築した DOM ツリーと同じになることを確認する。
+    fn test_streaming() {
+        let html = "<html><head><style>p{}</style></head><body><p>text</p><a href=\"x\">link</a></body></html>";
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let expected = convert_dom_to_string(&Some(window.borrow().document()));
+
+        for chunk_size in [1, 3, 7, 16] {
+            let mut parser = HtmlParser::new(HtmlTokenizer::new_streaming());
+            for chunk in html.as_bytes().chunks(chunk_size) {
+                parser.feed(core::str::from_utf8(chunk).unwrap());
+                parser.construct_tree();
+            }
+            parser.close();
+            let window = parser.construct_tree();
+            assert_eq!(
+                expected,
+                convert_dom_to_string(&Some(window.borrow().document())),
+                "chunk size {}",
+                chunk_size
+            );
+        }
+    }
+
+    /// <div> や <span> などの汎用的なコンテナ要素が DOM ツリーに追加されることを確認する。
+    #[test]
+    fn test_container_elements() {
+        let html = "<html><head></head><body><div><section><span>a</span></section></div><footer></footer></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let div = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                "div",
+                Vec::new()
+            ))))),
+            div
+        );
+
+        let section = div
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of div");
+        assert_eq!(
+            Some(ElementKind::Section),
+            section.borrow().get_element_kind()
+        );
+
+        let span = section
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of section");
+        assert_eq!(Some(ElementKind::Span), span.borrow().get_element_kind());
+
+        let footer = div
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of div");
+        assert_eq!(
+            Some(ElementKind::Footer),
+            footer.borrow().get_element_kind()
+        );
+    }
+
+    /// 対応していない要素やカスタム要素が ElementKind::Unknown としてタグ名とともに DOM ツリーに残ることを確認する。
+    #[test]
+    fn test_unknown_elements() {
+        let html =
+            "<html><head></head><body><my-widget id=w><blink>a</blink></my-widget>b</body></html>"
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let widget = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        let element = widget
+            .borrow()
+            .get_element()
+            .expect("my-widget should be an element");
+        assert_eq!(ElementKind::Unknown, element.kind());
+        assert_eq!("my-widget", element.tag_name());
+        assert_eq!(Some("w".to_string()), element.get_attribute("id"));
+
+        let blink = widget
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of my-widget");
+        assert_eq!(
+            "blink",
+            blink
+                .borrow()
+                .get_element()
+                .expect("blink should be an element")
+                .tag_name()
+        );
+
+        // </my-widget> で要素が閉じられ、後続のテキストは body の子になる。
+        let text = widget
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of my-widget");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("b".to_string())))),
+            text
+        );
+    }
+
+    /// リスト要素のテスト。
+    /// 終了タグが省略された <li> は次の <li> の開始タグや </ul> で暗黙的に閉じられ、兄弟ノードになることを確認する。
+    #[test]
+    fn test_list() {
+        let html = "<html><head></head><body><ul><li>a<li>b</ul><ol><li>c</li></ol></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let ul = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(Some(ElementKind::Ul), ul.borrow().get_element_kind());
+
+        let li1 = ul
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of ul");
+        assert_eq!(Some(ElementKind::Li), li1.borrow().get_element_kind());
+        let li2 = li1
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of li");
+        assert_eq!(Some(ElementKind::Li), li2.borrow().get_element_kind());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("b".to_string())))),
+            li2.borrow()
+                .first_child()
+                .expect("failed to get a first child of li")
+        );
+
+        let ol = ul
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of ul");
+        assert_eq!(Some(ElementKind::Ol), ol.borrow().get_element_kind());
+        let li3 = ol
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of ol");
+        assert_eq!(Some(ElementKind::Li), li3.borrow().get_element_kind());
+        assert!(li3.borrow().next_sibling().is_none());
+    }
+
+    /// 表のテスト。
+    /// 省略された <tbody> が補われ、<td> や <th> が行ごとにまとめられることを確認する。
+    /// また、</table> の後のテキストは <table> の兄弟ノードになることを確認する。
+    #[test]
+    fn test_table() {
+        let html = "<html><head></head><body><table><tr><td>a<td><p>b</p></tr><tr><th>c</table>d</body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let table = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(Some(ElementKind::Table), table.borrow().get_element_kind());
+
+        let tbody = table
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of table");
+        assert_eq!(Some(ElementKind::Tbody), tbody.borrow().get_element_kind());
+
+        let tr1 = tbody
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of tbody");
+        assert_eq!(Some(ElementKind::Tr), tr1.borrow().get_element_kind());
+        let td1 = tr1
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of tr");
+        assert_eq!(Some(ElementKind::Td), td1.borrow().get_element_kind());
+        let td2 = td1
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of td");
+        assert_eq!(Some(ElementKind::Td), td2.borrow().get_element_kind());
+        let p = td2
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of td");
+        assert_eq!(Some(ElementKind::P), p.borrow().get_element_kind());
+
+        let tr2 = tr1
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of tr");
+        let th = tr2
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of tr");
+        assert_eq!(Some(ElementKind::Th), th.borrow().get_element_kind());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("c".to_string())))),
+            th.borrow()
+                .first_child()
+                .expect("failed to get a first child of th")
+        );
+
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("d".to_string())))),
+            table
+                .borrow()
+                .next_sibling()
+                .expect("failed to get a next sibling of table")
+        );
+    }
+
+    /// フォームのテスト。
+    /// <input> は空要素として扱われ、<input> と <button> のフォームオーナーが <form> 要素になることを確認する。
+    #[test]
+    fn test_form() {
+        let html = "<html><head></head><body><form action=/post><input name=q><button>go</button></form><input></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let form = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(Some(ElementKind::Form), form.borrow().get_element_kind());
+
+        let input = form
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of form");
+        assert_eq!(Some(ElementKind::Input), input.borrow().get_element_kind());
+        assert!(input.borrow().first_child().is_none());
+        assert!(Rc::ptr_eq(
+            &form,
+            &input
+                .borrow()
+                .form_owner()
+                .expect("input should have a form owner")
+        ));
+
+        let button = input
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of input");
+        assert_eq!(
+            Some(ElementKind::Button),
+            button.borrow().get_element_kind()
+        );
+        assert!(Rc::ptr_eq(
+            &form,
+            &button
+                .borrow()
+                .form_owner()
+                .expect("button should have a form owner")
+        ));
+
+        // </form> の後の <input> はフォームに属さない。
+        let outside = form
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of form");
+        assert_eq!(
+            Some(ElementKind::Input),
+            outside.borrow().get_element_kind()
+        );
+        assert!(outside.borrow().form_owner().is_none());
+    }
+
+    /// 画像のテスト。
+    /// <img> は空要素として扱われ、src と alt 属性を保持し、後続のテキストが兄弟ノードになることを確認する。
+    #[test]
+    fn test_img() {
+        let html =
+            r#"<html><head></head><body><p><img src="/logo.png" alt="logo">text</p></body></html>"#
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let p = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+
+        let img = p
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of p");
+        let element = img
+            .borrow()
+            .get_element()
+            .expect("img should be an element");
+        assert_eq!(ElementKind::Img, element.kind());
+        assert_eq!(Some("/logo.png".to_string()), element.get_attribute("src"));
+        assert_eq!(Some("logo".to_string()), element.get_attribute("alt"));
+        assert!(img.borrow().first_child().is_none());
+
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("text".to_string())))),
+            img.borrow()
+                .next_sibling()
+                .expect("failed to get a next sibling of img")
+        );
+    }
+
+    /// 改行と区切り線のテスト。
+    /// <br> と <hr> は空要素として扱われ、子ノードを持たずに後続のノードが兄弟ノードになることを確認する。
+    #[test]
+    fn test_br_hr() {
+        let html = "<html><head></head><body>a<br>b<hr/>c</body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let a = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        let br = a
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of text");
+        assert_eq!(Some(ElementKind::Br), br.borrow().get_element_kind());
+        assert!(br.borrow().first_child().is_none());
+
+        let b = br
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of br");
+        let hr = b
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of text");
+        assert_eq!(Some(ElementKind::Hr), hr.borrow().get_element_kind());
+        assert!(hr.borrow().first_child().is_none());
+
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("c".to_string())))),
+            hr.borrow()
+                .next_sibling()
+                .expect("failed to get a next sibling of hr")
+        );
+    }
+
+    /// 整形済みテキストのテスト。
+    /// <pre> の中では空白文字と改行文字がそのまま残り、開始タグ直後の改行文字だけが無視されることを確認する。
+    #[test]
+    fn test_pre() {
+        let html = "<html><head></head><body><pre>\n  fn main() {\n    <code>x</code>\n  }\n</pre> <p> a</p></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let pre = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(Some(ElementKind::Pre), pre.borrow().get_element_kind());
+
+        let text = pre
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of pre");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text(
+                "  fn main() {\n    ".to_string()
+            )))),
+            text
+        );
+        let code = text
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of text");
+        assert_eq!(Some(ElementKind::Code), code.borrow().get_element_kind());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text(
+                "\n  }\n".to_string()
+            )))),
+            code.borrow()
+                .next_sibling()
+                .expect("failed to get a next sibling of code")
+        );
+
+        // <pre> の外側では、これまで通り先頭の空白文字は無視される。
+        let p = pre
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of pre");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("a".to_string())))),
+            p.borrow()
+                .first_child()
+                .expect("failed to get a first child of p")
+        );
+    }
+
+    /// タイトルのテスト。
+    /// <title> タグが <head> の子ノードとして追加され、中のタグはテキストとして扱われることを確認する。
+    #[test]
+    fn test_title() {
+        let html =
+            "<html><head><title> A  <b>page</b>\n</title></head><body></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let title = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(Some(ElementKind::Title), title.borrow().get_element_kind());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text(
+                " A  <b>page</b>\n".to_string()
+            )))),
+            title
+                .borrow()
+                .first_child()
+                .expect("failed to get a first child of title")
+        );
+
+        assert_eq!("A <b>page</b>", get_title_content(document));
+    }
+
+    /// <meta> タグで宣言された文字エンコーディングのテスト。
+    /// charset 属性と http-equiv 属性のどちらでも宣言でき、最初の宣言が使われることを確認する。
+    #[test]
+    fn test_meta_charset() {
+        let html = r#"<html><head><meta charset="Shift_JIS"><meta charset="euc-jp"></head><body></body></html>"#.to_string();
+        let mut parser = HtmlParser::new(HtmlTokenizer::new(html));
+        let window = parser.construct_tree();
+        assert_eq!(Some(Encoding::ShiftJis), parser.declared_encoding());
+
+        let meta = window
+            .borrow()
+            .document()
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(Some(ElementKind::Meta), meta.borrow().get_element_kind());
+        assert!(meta.borrow().first_child().is_none());
+
+        let html = r#"<html><head><meta http-equiv="Content-Type" content="text/html; charset=EUC-JP"></head></html>"#.to_string();
+        let mut parser = HtmlParser::new(HtmlTokenizer::new(html));
+        parser.construct_tree();
+        assert_eq!(Some(Encoding::EucJp), parser.declared_encoding());
+
+        let html =
+            r#"<html><head><meta name="viewport" content="width=device-width"></head></html>"#
+                .to_string();
+        let mut parser = HtmlParser::new(HtmlTokenizer::new(html));
+        parser.construct_tree();
+        assert_eq!(None, parser.declared_encoding());
+    }
+
+    /// <base> タグのテスト。
+    /// href 属性を持つ最初の <base> タグの値が記録されることを確認する。
+    #[test]
+    fn test_base_href() {
+        let html = r#"<html><head><base target="_blank"><base href="/docs/"><base href="/other/"></head><body></body></html>"#.to_string();
+        let mut parser = HtmlParser::new(HtmlTokenizer::new(html));
+        let window = parser.construct_tree();
+        assert_eq!(Some("/docs/".to_string()), parser.base_href());
+
+        let base = window
+            .borrow()
+            .document()
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(Some(ElementKind::Base), base.borrow().get_element_kind());
+        assert!(base.borrow().first_child().is_none());
+
+        let html = "<html><head></head><body></body></html>".to_string();
+        let mut parser = HtmlParser::new(HtmlTokenizer::new(html));
+        parser.construct_tree();
+        assert_eq!(None, parser.base_href());
+    }
+
+    /// 暗黙的に閉じられる <p> のテスト。
+    /// <p> の終了タグが省略されている場合、次の <p> やブロック要素の開始タグで閉じられて兄弟ノードになることを確認する。
+    #[test]
+    fn test_implied_p_end_tag() {
+        let html = "<html><head></head><body><p>one<p>two<div>three</div><p><b>four<ul><li>five</ul></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let mut kinds = Vec::new();
+        let mut child = body.borrow().first_child();
+        while let Some(node) = child {
+            kinds.push(node.borrow().get_element_kind());
+            child = node.borrow().next_sibling();
+        }
+        assert_eq!(
+            vec![
+                Some(ElementKind::P),
+                Some(ElementKind::P),
+                Some(ElementKind::Div),
+                Some(ElementKind::P),
+                Some(ElementKind::Ul),
+            ],
+            kinds
+        );
+
+        // <p> の中の <b> は <ul> の前に <p> とともに閉じられる。
+        let second = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of p");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("two".to_string())))),
+            second
+                .borrow()
+                .first_child()
+                .expect("failed to get a first child of p")
+        );
+    }
+
+    /// HTML の断片を解析するテスト。
+    /// 文脈となる要素の子要素として解析したノードの列が返ることを確認する。
+    #[test]
+    fn test_parse_fragment() {
+        let context = Element::new("div", Vec::new());
+        let nodes = HtmlParser::parse_fragment(&context, "<p>a</p>b<span>c</span>".to_string());
+        assert_eq!(3, nodes.len());
+        assert_eq!(Some(ElementKind::P), nodes[0].borrow().get_element_kind());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("b".to_string())))),
+            nodes[1]
+        );
+        assert_eq!(
+            Some(ElementKind::Span),
+            nodes[2].borrow().get_element_kind()
+        );
+        assert!(nodes[0].borrow().parent().upgrade().is_none());
+
+        // <textarea> を文脈とする場合、タグは文字として扱われる。
+        let context = Element::new("textarea", Vec::new());
+        let nodes = HtmlParser::parse_fragment(&context, "<b>x</b>".to_string());
+        assert_eq!(1, nodes.len());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text(
+                "<b>x</b>".to_string()
+            )))),
+            nodes[0]
+        );
+
+        let nodes = HtmlParser::parse_fragment(&Element::new("div", Vec::new()), "".to_string());
+        assert!(nodes.is_empty());
+    }
+
+    #[test]
+    fn test_iframe() {
+        let html = "<html><head></head><body><iframe src=\"/child.html\"><p>fallback</p></iframe><p>after</p></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let iframe = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        let mut attr = Attribute::new();
+        for c in "src".chars() {
+            attr.add_char(c, true);
+        }
+        for c in "/child.html".chars() {
+            attr.add_char(c, false);
+        }
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                "iframe",
+                vec![attr]
+            ))))),
+            iframe
+        );
+
+        // <iframe> の中身はタグとして解釈されず、テキストとして扱われる。
+        let text = iframe
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of iframe");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text(
+                "<p>fallback</p>".to_string()
+            )))),
+            text
+        );
+
+        let p = iframe
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of iframe");
+        assert_eq!(Some(ElementKind::P), p.borrow().get_element_kind());
+    }
+
+    #[test]
+    fn test_noscript_scripting_enabled() {
+        let html = "<html><head><noscript><style>p{}</style></noscript></head><body><noscript><p>no js</p></noscript><p>a</p></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let html = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document");
+        let head = html
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html");
+        // スクリプトが有効な場合、<noscript> の中身は DOM ツリーに追加されない。
+        let noscript = head
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(
+            Some(ElementKind::Noscript),
+            noscript.borrow().get_element_kind()
+        );
+        assert!(noscript.borrow().first_child().is_none());
+
+        let noscript = head
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(
+            Some(ElementKind::Noscript),
+            noscript.borrow().get_element_kind()
+        );
+        assert!(noscript.borrow().first_child().is_none());
+
+        let p = noscript
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of noscript");
+        assert_eq!(Some(ElementKind::P), p.borrow().get_element_kind());
+    }
+
+    #[test]
+    fn test_noscript_scripting_disabled() {
+        let html = "<html><head><noscript><style>p{}</style></noscript></head><body><noscript><p>no js</p></noscript></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let mut parser = HtmlParser::new(t);
+        parser.set_scripting(false);
+        let window = parser.construct_tree();
+        let document = window.borrow().document();
+
+        let head = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html");
+        // スクリプトが無効な場合、<head> の中の <noscript> の <style> は通常どおり解析される。
+        let style = head
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of noscript");
+        assert_eq!(Some(ElementKind::Style), style.borrow().get_element_kind());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("p{}".to_string())))),
+            style
+                .borrow()
+                .first_child()
+                .expect("failed to get a first child of style")
+        );
+
+        let p = head
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of noscript");
+        assert_eq!(Some(ElementKind::P), p.borrow().get_element_kind());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("no js".to_string())))),
+            p.borrow()
+                .first_child()
+                .expect("failed to get a first child of p")
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        let html = "<html><head></head><body><form><select name=\"s\"><option>a<option>b</option><div>x</div><optgroup><option>c</optgroup></select><textarea>t</textarea></form></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let form = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        let select = form
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of form");
+        assert_eq!(
+            Some(ElementKind::Select),
+            select.borrow().get_element_kind()
+        );
+        assert!(Rc::ptr_eq(
+            &form,
+            &select
+                .borrow()
+                .form_owner()
+                .expect("select should have a form owner")
+        ));
+
+        // 閉じていない <option> は次の <option> で閉じられる。
+        let option1 = select
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of select");
+        assert_eq!(
+            Some(ElementKind::Option),
+            option1.borrow().get_element_kind()
+        );
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("a".to_string())))),
+            option1
+                .borrow()
+                .first_child()
+                .expect("failed to get a first child of option")
+        );
+        let option2 = option1
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of option");
+        assert_eq!(
+            Some(ElementKind::Option),
+            option2.borrow().get_element_kind()
+        );
+
+        // <select> の中の <div> は無視されるが、中のテキストは <select> に追加される。
+        let text = option2
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of option");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("x".to_string())))),
+            text
+        );
+
+        let optgroup = text
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of text");
+        assert_eq!(
+            Some(ElementKind::Optgroup),
+            optgroup.borrow().get_element_kind()
+        );
+        let option3 = optgroup
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of optgroup");
+        assert_eq!(
+            Some(ElementKind::Option),
+            option3.borrow().get_element_kind()
+        );
+        assert!(optgroup.borrow().next_sibling().is_none());
+
+        let textarea = select
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of select");
+        assert_eq!(
+            Some(ElementKind::Textarea),
+            textarea.borrow().get_element_kind()
+        );
+        assert!(textarea.borrow().form_owner().is_some());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("t".to_string())))),
+            textarea
+                .borrow()
+                .first_child()
+                .expect("failed to get a first child of textarea")
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let html = "<p>a</span><div>b".to_string();
+        let t = HtmlTokenizer::new(html);
+        let mut parser = HtmlParser::new(t);
+        parser.construct_tree();
+
+        assert_eq!(
+            vec![
+                ParseDiagnostic::new(ParseDiagnosticKind::ImpliedElement(ElementKind::Html), 0),
+                ParseDiagnostic::new(ParseDiagnosticKind::ImpliedElement(ElementKind::Head), 0),
+                ParseDiagnostic::new(ParseDiagnosticKind::ImpliedElement(ElementKind::Body), 0),
+                ParseDiagnostic::new(ParseDiagnosticKind::IgnoredEndTag("span".to_string()), 4),
+                ParseDiagnostic::new(ParseDiagnosticKind::UnexpectedEof, 17),
+            ],
+            parser.diagnostics()
+        );
+    }
+
+    #[test]
+    fn test_no_diagnostics() {
+        let html = "<html><head></head><body><p>a</p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let mut parser = HtmlParser::new(t);
+        parser.construct_tree();
+
+        assert!(parser.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let html = "<html><head><style>a > b {}</style></head><body><p class=\"x\">a b<br></p><img src=\"a.png\"><ul><li>1</li><li>2</li></ul></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html.clone())).construct_tree();
+        let document = window.borrow().document();
+
+        let serialized = outer_html(document);
+        assert_eq!(html, serialized);
+
+        // 直列化した HTML を解析し直しても、同じ DOM ツリーになる。
+        let window = HtmlParser::new(HtmlTokenizer::new(serialized.clone())).construct_tree();
+        assert_eq!(serialized, outer_html(window.borrow().document()));
+    }
+
+    #[test]
+    fn test_inner_html() {
+        let html = "<html><head></head><body><div id=\"a\"><span>x</span>y</div></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let div = get_element_by_id(Some(document), &"a".to_string()).expect("failed to get #a");
+
+        assert_eq!("<span>x</span>y".to_string(), inner_html(div.clone()));
+
+        // テキストや属性値に含まれる特殊な文字は文字参照に置き換えられる。
+        Node::set_text_content(&div, "a & <b>");
+        Node::set_attribute(&div, "title", "\"c\"");
+        assert_eq!(
+            "<div id=\"a\" title=\"&quot;c&quot;\">a &amp; &lt;b&gt;</div>".to_string(),
+            outer_html(div)
+        );
+    }
+
+    /// ツリー構築中に <form>、href 属性を持つ <a> と <area>、<img> 要素がドキュメントのコレクションに追加されることを確認する。
+    /// ドキュメントから取り除かれた要素はコレクションに含まれない。
+    #[test]
+    fn test_document_collections() {
+        let html = "<html><body><form id=\"f\"><img src=\"a.png\"></form><a href=\"/x\">x</a><a name=\"y\">y</a><map><area href=\"/z\"></map><div id=\"d\"><img src=\"b.png\"></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        let ids = |nodes: Vec<Rc<RefCell<Node>>>| {
+            nodes
+                .iter()
+                .map(|n| {
+                    let e = n.borrow().get_element().expect("should be an element");
+                    e.get_attribute("id")
+                        .or(e.get_attribute("href"))
+                        .or(e.get_attribute("src"))
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["f".to_string()], ids(window.borrow().forms()));
+        assert_eq!(
+            vec!["/x".to_string(), "/z".to_string()],
+            ids(window.borrow().links())
+        );
+        assert_eq!(
+            vec!["a.png".to_string(), "b.png".to_string()],
+            ids(window.borrow().images())
+        );
+
+        let div = get_element_by_id(Some(document), &"d".to_string()).expect("failed to get #d");
+        let parent = div
+            .borrow()
+            .parent()
+            .upgrade()
+            .expect("failed to get a parent");
+        Node::remove_child(&parent, &div).expect("failed to remove");
+        assert_eq!(vec!["a.png".to_string()], ids(window.borrow().images()));
+    }
+
+    /// getElementById が、ツリー構築後の要素の挿入や削除、ID の変更を反映した要素を返すことを確認する。
+    /// 同じ ID を持つ要素が複数ある場合は、文書内で最初の要素を返す。
+    #[test]
+    fn test_get_element_by_id() {
+        let html = "<html><body><div id=\"a\">1</div><p id=\"a\">2</p><span id=\"b\"></span></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let find = |id: &str| get_element_by_id(Some(document.clone()), &id.to_string());
+        let kind =
+            |node: Option<Rc<RefCell<Node>>>| node.and_then(|n| n.borrow().get_element_kind());
+
+        assert_eq!(Some(ElementKind::Div), kind(find("a")));
+        assert_eq!(Some(ElementKind::Span), kind(find("b")));
+        assert_eq!(None, kind(find("x")));
+
+        let div = find("a").expect("failed to get #a");
+        let body = div
+            .borrow()
+            .parent()
+            .upgrade()
+            .expect("failed to get a body");
+        Node::remove_child(&body, &div).expect("failed to remove");
+        assert_eq!(Some(ElementKind::P), kind(find("a")));
+
+        let span = find("b").expect("failed to get #b");
+        Node::set_attribute(&span, "id", "c");
+        assert_eq!(None, kind(find("b")));
+        assert_eq!(Some(ElementKind::Span), kind(find("c")));
+
+        // ID を持つ要素をドキュメントに追加すると、その ID で探せるようになる。
+        let p = find("a").expect("failed to get #a");
+        Node::remove_attribute(&p, "id");
+        assert_eq!(None, kind(find("a")));
+        Node::set_attribute(&div, "id", "d");
+        Node::append_child(&body, div).expect("failed to append");
+        assert_eq!(Some(ElementKind::Div), kind(find("d")));
+    }
+
+    /// 文書の先頭にあるカスタム要素が、省略された head を閉じて body の中に挿入されることを確認する。
+    #[test]
+    fn test_leading_custom_element() {
+        let html = "<my-el>hi</my-el>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        let html = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document");
+        assert_eq!(Some(ElementKind::Html), html.borrow().get_element_kind());
+        let head = html
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html");
+        assert_eq!(Some(ElementKind::Head), head.borrow().get_element_kind());
+        assert!(head.borrow().first_child().is_none());
+        let body = head
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+        assert_eq!(Some(ElementKind::Body), body.borrow().get_element_kind());
+
+        let my_el = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                "my-el",
+                Vec::new()
+            ))))),
+            my_el
+        );
+        let element = my_el
+            .borrow()
+            .get_element()
+            .expect("failed to get an element");
+        assert_eq!(ElementKind::Unknown, element.kind());
+        assert_eq!("my-el", element.tag_name());
+
+        let text = my_el
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of my-el");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("hi".to_string())))),
+            text
+        );
+    }
 }
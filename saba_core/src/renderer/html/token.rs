@@ -1,10 +1,13 @@
 use crate::renderer::html::attribute::Attribute;
+use crate::renderer::html::parse_error::ParseError;
+use crate::renderer::html::parse_error::ParseErrorKind;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::ops::Range;
 
 
 /// Tokenization で定義されている状態を表す列挙型。
-/// 正規には 80 の状態があるが、ここでは 17 とする。
+/// 正規には 80 の状態があるが、ここでは 32 とする。
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum State {
     /// https://html.spec.whatwg.org/multipage/parsing.html#data-state
@@ -33,6 +36,36 @@ pub enum State {
     AfterAttributeValueQuoted,
     /// https://html.spec.whatwg.org/multipage/parsing.html#self-closing-start-tag-state
     SelfClosingStartTag,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#markup-declaration-open-state
+    MarkupDeclarationOpen,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#bogus-comment-state
+    BogusComment,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#comment-start-state
+    CommentStart,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#comment-start-dash-state
+    CommentStartDash,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#comment-state
+    Comment,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#comment-end-dash-state
+    CommentEndDash,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#comment-end-state
+    CommentEnd,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rcdata-state
+    Rcdata,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rcdata-less-than-sign-state
+    RcdataLessThanSign,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rcdata-end-tag-open-state
+    RcdataEndTagOpen,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rcdata-end-tag-name-state
+    RcdataEndTagName,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rawtext-state
+    Rawtext,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rawtext-less-than-sign-state
+    RawtextLessThanSign,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rawtext-end-tag-open-state
+    RawtextEndTagOpen,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rawtext-end-tag-name-state
+    RawtextEndTagName,
     /// https://html.spec.whatwg.org/multipage/parsing.html#script-data-state
     ScriptData,
     /// https://html.spec.whatwg.org/multipage/parsing.html#script-data-less-than-sign-state
@@ -45,6 +78,13 @@ pub enum State {
     TemporaryBuffer,
 }
 
+/// 入力文字列の最後を超えて読み込んだときに返す文字。
+/// EOF の判定には is_eof を使い、この文字自体は各状態で特別扱いしない。
+const EOF_CHAR: char = '\0';
+
+/// NUL 文字の代わりに使う置換文字 (U+FFFD)
+const REPLACEMENT_CHARACTER: char = '\u{FFFD}';
+
 /// HTML トークンの列挙型
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HtmlToken {
@@ -60,20 +100,43 @@ pub enum HtmlToken {
   },
   // 文字
   Char(char),
+  // コメント。<!DOCTYPE> や <?xml ?> などのサポートしていないマークアップもコメントとして扱う。
+  Comment(String),
   // ファイルの終了 (End of file)
   Eof,
 }
 
 /// 字句解析に必要な情報を保持する HtmlTokenizer 構造体。
 /// ステートマシンの状態 (State)、HTML の文字列 (input)、現在処理している文字の位置 (pos) などを管理する。
+/// input は受け取った文字列を前処理 (改行の正規化) した後の文字列を保持し、pos などの位置はすべてバイト単位のインデックスで表す。
+/// タグ名や属性値などは1文字ずつ組み立てず、開始位置 (mark) から現在の位置までの範囲を切り出して作成する。
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HtmlTokenizer  {
   state: State,
   pos: usize,
   reconsume: bool,
   latest_token: Option<HtmlToken>,
-  input: Vec<char>,
+  input: String,
+  // 最後に読み込んだ文字。reconsume で再処理するときに使う。
+  current: char,
+  // 読み込み中のタグ名、属性名、属性値、コメントの開始位置
+  mark: usize,
+  // 最後に返したトークンの開始位置
+  token_start: usize,
   buf: String,
+  // 最後に返した開始タグの名前。終了タグが適切な終了タグかどうかの判定に使う。
+  // https://html.spec.whatwg.org/multipage/parsing.html#appropriate-end-tag-token
+  last_start_tag: String,
+  // TemporaryBuffer 状態で一時的なバッファを文字として返し終えた後に戻る状態。
+  return_state: State,
+  // 検出したパースエラー
+  errors: Vec<ParseError>,
+  // 入力文字列がすべて揃っているかどうか。false の場合、入力の最後に到達すると feed で続きが追加されるまで処理を中断する。
+  closed: bool,
+  // 入力の続きを待って処理を中断しているかどうか
+  suspended: bool,
+  // 最後に追加した入力文字列が CR で終わっていたかどうか。次の入力の先頭の LF と合わせて1つの改行とする。
+  last_cr: bool,
 }
 
 impl HtmlTokenizer {
@@ -84,16 +147,122 @@ impl HtmlTokenizer {
       pos: 0,
       reconsume: false, // 状態の変更だけ行い、現在の文字を再利用するかどうか
       latest_token: None,
-      input: html.chars().collect(),
+      input: String::new(),
+      current: EOF_CHAR,
+      mark: 0,
+      token_start: 0,
       buf: String::new(),
+      last_start_tag: String::new(),
+      return_state: State::Data,
+      errors: Vec::new(),
+      closed: true,
+      suspended: false,
+      last_cr: false,
+    }
+    .with_input(html)
+  }
+
+  /// 入力文字列を設定する。改行を含まない場合はコピーせずにそのまま使う。
+  fn with_input(mut self, html: String) -> Self {
+    if html.contains('\r') {
+      self.push_input(&html);
+    } else {
+      self.input = html;
     }
+    self
+  }
+
+  /// 入力文字列の CRLF と単独の CR を LF に正規化して、input に追加する。
+  /// https://html.spec.whatwg.org/multipage/parsing.html#preprocessing-the-input-stream
+  fn push_input(&mut self, chunk: &str) {
+    let mut rest = chunk;
+    if self.last_cr && rest.starts_with('\n') {
+      rest = &rest[1..];
+    }
+    for (i, line) in rest.split('\r').enumerate() {
+      let line = if i > 0 {
+        self.input.push('\n');
+        line.strip_prefix('\n').unwrap_or(line)
+      } else {
+        line
+      };
+      self.input.push_str(line);
+    }
+    if !chunk.is_empty() {
+      self.last_cr = chunk.ends_with('\r');
+    }
+  }
+
+  /// 入力文字列を分割して受け取るトークナイザを作成する。
+  /// 入力文字列は feed で追加し、すべて追加したら close を呼ぶ。
+  pub fn new_streaming() -> Self {
+    let mut tokenizer = Self::new(String::new());
+    tokenizer.closed = false;
+    tokenizer
+  }
+
+  /// 入力文字列の続きを追加する。
+  /// next が None を返した後でも、続きを追加すれば中断した位置からトークンを返す。
+  pub fn feed(&mut self, chunk: &str) {
+    assert!(!self.closed, "cannot feed a closed tokenizer");
+    self.push_input(chunk);
+  }
+
+  /// 入力文字列がすべて揃ったことを通知する。以降、入力の最後は EOF として扱う。
+  pub fn close(&mut self) {
+    self.closed = true;
+  }
+
+  /// 入力文字列がすべて揃っているかどうかを返す。
+  /// true の場合、next が None を返したときは入力の最後まで処理したことを表す。
+  pub fn is_closed(&self) -> bool {
+    self.closed
+  }
+
+  /// トークナイザの状態を切り替える。
+  /// <style> や <textarea> などの開始タグを処理した後、ツリー構築段階から呼び出される。
+  /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments
+  pub fn switch_to(&mut self, state: State) {
+    self.state = state;
+  }
+
+  /// これまでに検出したパースエラーを返す。
+  pub fn errors(&self) -> Vec<ParseError> {
+    self.errors.clone()
+  }
+
+  /// 現在の文字の位置でパースエラーを記録する。
+  fn parse_error(&mut self, kind: ParseErrorKind) {
+    let offset = self.current_offset();
+    self.errors.push(ParseError::new(kind, offset));
+  }
+
+  /// 最後に返したトークンに対応する、入力文字列のバイト単位の範囲を返す。
+  pub fn token_range(&self) -> Range<usize> {
+    let end = if self.reconsume {
+      self.current_offset()
+    } else {
+      self.pos.min(self.input.len())
+    };
+    self.token_start.min(end)..end
   }
 
   /// input 文字列から現在の位置 pos の文字を1文字返却する。
   /// 現在位置 pos をカウントアップする。
+  /// 入力文字列の最後を超えた場合は EOF_CHAR を返し、is_eof が true になる。
   fn consume_next_input(&mut self) -> char {
-    let c = self.input[self.pos];
-    self.pos += 1;
+    let c = self
+      .input
+      .get(self.pos..)
+      .and_then(|rest| rest.chars().next())
+      .unwrap_or(EOF_CHAR);
+    // NUL 文字はどの状態でもパースエラーとする。
+    if c == '\0' && self.pos < self.input.len() {
+      let offset = self.pos;
+      self.errors.push(ParseError::new(ParseErrorKind::UnexpectedNullCharacter, offset));
+    }
+    self.pos += c.len_utf8();
+    self.current = c;
     c
   }
 
@@ -101,7 +270,28 @@ impl HtmlTokenizer {
   /// reconsume を解除する。
   fn reconsume_input(&mut self) -> char {
     self.reconsume = false;
-    self.input[self.pos - 1]
+    self.current
+  }
+
+  /// 最後に読み込んだ文字の開始位置を返す。入力文字列の最後を超えた場合は入力文字列の長さを返す。
+  fn current_offset(&self) -> usize {
+    (self.pos - self.current.len_utf8()).min(self.input.len())
+  }
+
+  /// 開始位置 mark から end までの入力文字列を返す。
+  fn marked(&self, end: usize) -> &str {
+    &self.input[self.mark.min(end)..end]
+  }
+
+  /// 開始位置 mark から end までの入力文字列を、NUL 文字を置換文字に置き換えて返す。
+  /// タグ名、属性、コメントに含まれる NUL 文字は置換文字として扱う。
+  fn marked_string(&self, end: usize) -> String {
+    let marked = self.marked(end);
+    if marked.contains('\0') {
+      marked.replace('\0', "\u{FFFD}")
+    } else {
+      String::from(marked)
+    }
   }
 
   /// 開始タグまたは終了タグを作成する。
@@ -119,9 +309,30 @@ impl HtmlTokenizer {
     }
   }
 
-  /// 最後のトークンの名前に1文字追加する。
-  fn append_tag_name(&mut self, c: char) {
+  /// コメントを作成する。
+  /// コメントの内容は start から始まる。
+  fn create_comment(&mut self, start: usize) {
+    self.latest_token = Some(HtmlToken::Comment(String::new()));
+    self.mark = start;
+  }
+
+  /// コメントの内容を開始位置から、現在の文字より trailing バイト前までの範囲に設定し、コメントを返す。
+  /// 例えば --> で終わる場合、trailing を 2 として末尾の -- を含めない。
+  fn take_comment(&mut self, trailing: usize) -> Option<HtmlToken> {
+    assert!(self.latest_token.is_some());
+    let end = self.current_offset().saturating_sub(trailing);
+    let comment = self.marked_string(end);
+    if let Some(HtmlToken::Comment(ref mut data)) = self.latest_token {
+      *data = comment;
+    }
+    self.take_latest_token()
+  }
+
+  /// 最後のトークンの名前を、開始位置から現在の文字の直前までの範囲に設定する。
+  /// タグ名は小文字に変換する。
+  fn set_tag_name(&mut self) {
     assert!(self.latest_token.is_some());
+    let name = self.marked_string(self.current_offset()).to_ascii_lowercase();
     if let Some(t) = self.latest_token.as_mut() {
       match t {
         HtmlToken::StartTag {
@@ -129,15 +340,17 @@ impl HtmlTokenizer {
           self_closing: _,
           attributes: _,
         }
-        | HtmlToken::EndTag { ref mut tag } => tag.push(c),
+        | HtmlToken::EndTag { ref mut tag } => *tag = name,
         _ => panic!("`latest_token` should be either StartTag or EndTag"),
       }
     }
   }
 
   /// 最後のトークンに属性を追加する。
+  /// 属性名は現在の文字から始まる。
   fn start_new_attribute(&mut self) {
     assert!(self.latest_token.is_some());
+    self.mark = self.current_offset();
 
     // 属性を追加するタグが開始タグの場合、属性を新規追加する。
     if let Some(t) = self.latest_token.as_mut() {
@@ -149,15 +362,24 @@ impl HtmlTokenizer {
         } => {
           attributes.push(Attribute::new());
         }
-        _ => panic!("`latest_token` should be either StartTag"),
+        // 終了タグの属性はパースエラーであり、無視する。
+        HtmlToken::EndTag { .. } => {
+          self.errors.push(ParseError::new(ParseErrorKind::EndTagWithAttributes, self.mark));
+        }
+        _ => panic!("`latest_token` should be either StartTag or EndTag"),
       }
     }
   }
 
-  /// 最後のトークンに属性の文字を追加する。
-  fn append_attribute(&mut self, c: char, is_name: bool) {
+  /// 最後のトークンの最後の属性の名前または値を、開始位置から現在の文字の直前までの範囲に設定する。
+  /// 属性名は小文字に変換する。
+  fn set_attribute(&mut self, is_name: bool) {
     assert!(self.latest_token.is_some());
 
+    let mut s = self.marked_string(self.current_offset());
+    if is_name {
+      s.make_ascii_lowercase();
+    }
     if let Some(t) = self.latest_token.as_mut() {
       match t {
         HtmlToken::StartTag {
@@ -167,9 +389,14 @@ impl HtmlTokenizer {
         } => {
           let len = attributes.len();
           assert!(len > 0);
-          attributes[len - 1].add_char(c, is_name);
+          if is_name {
+            attributes[len - 1].set_name(s);
+          } else {
+            attributes[len - 1].set_value(s);
+          }
         }
-        _ => panic!("`latest_token` should be either StartTag"),
+        HtmlToken::EndTag { .. } => {}
+        _ => panic!("`latest_token` should be either StartTag or EndTag"),
       }
     }
   }
@@ -177,6 +404,7 @@ impl HtmlTokenizer {
   /// 最後のトークンが開始タグの場合、self__closing フラグを true にする。
   fn set_self_closing_flag(&mut self) {
     assert!(self.latest_token.is_some());
+    let offset = self.current_offset();
 
     if let Some(t) = self.latest_token.as_mut() {
       match t {
@@ -185,7 +413,11 @@ impl HtmlTokenizer {
           ref mut self_closing,
           attributes: _,
         } => *self_closing = true,
-        _ => panic!("`latest_token` should be either StartTag"),
+        // 終了タグの自己終了フラグはパースエラーであり、無視する。
+        HtmlToken::EndTag { .. } => {
+          self.errors.push(ParseError::new(ParseErrorKind::EndTagWithTrailingSolidus, offset));
+        }
+        _ => panic!("`latest_token` should be either StartTag or EndTag"),
       }
     }
   }
@@ -196,9 +428,57 @@ impl HtmlTokenizer {
       let t = self.latest_token.as_ref().cloned();
       self.latest_token = None;
       assert!(self.latest_token.is_none());
-  
+
+      if let Some(HtmlToken::StartTag { ref tag, .. }) = t {
+        self.last_start_tag = tag.clone();
+      }
       t
     }
+
+    /// 読み込み中の終了タグが、最後に返した開始タグと同じ名前の終了タグ (適切な終了タグ) かどうか。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#appropriate-end-tag-token
+    fn is_appropriate_end_tag(&self) -> bool {
+      match self.latest_token {
+        Some(HtmlToken::EndTag { .. }) => self
+          .marked(self.current_offset())
+          .eq_ignore_ascii_case(&self.last_start_tag),
+        _ => false,
+      }
+    }
+
+    /// ScriptData, Rcdata, Rawtext の終了タグ名状態で1文字処理する。
+    /// 適切な終了タグでない場合、"</" とそれまでのタグ名を文字として返すため TemporaryBuffer 状態に遷移し、
+    /// その後 text_state に戻る。
+    fn text_end_tag_name(&mut self, c: char, text_state: State) -> Option<HtmlToken> {
+      if self.is_appropriate_end_tag() {
+        if c == ' ' || c == '\t' || c == '\n' || c == '\x0C' {
+          self.set_tag_name();
+          self.state = State::BeforeAttributeName;
+          return None;
+        }
+        if c == '/' {
+          self.set_tag_name();
+          self.state = State::SelfClosingStartTag;
+          return None;
+        }
+        if c == '>' {
+          self.set_tag_name();
+          self.state = State::Data;
+          return self.take_latest_token();
+        }
+      }
+      // アルファベットの場合、タグ名の続きとして読み進める。
+      if c.is_ascii_alphabetic() {
+        return None;
+      }
+      // タグ名の直前の "</" からタグ名までを一時的なバッファ buf に入れる。
+      self.latest_token = None;
+      self.buf = String::from(&self.input[self.mark - 2..self.current_offset()]);
+      self.reconsume = true;
+      self.return_state = text_state;
+      self.state = State::TemporaryBuffer;
+      None
+    }
     /// 入力文字列 input の最後の文字まで処理したかどうか。
     fn is_eof(&self) -> bool {
       self.pos > self.input.len()
@@ -212,10 +492,30 @@ impl Iterator for HtmlTokenizer {
 
   /// 入力文字列 input を1文字ずつ処理する。
   fn next(&mut self) -> Option<Self::Item> {
-    if self.pos >= self.input.len() {
+    // 入力文字列をすべて読み込んでいても、再処理する文字が残っている場合は処理を続ける。
+    // タグの途中などで入力が終わった場合は、その状態で EOF を処理する。
+    if self.closed
+      && !self.reconsume
+      && (self.pos > self.input.len() || (self.pos == self.input.len() && self.state == State::Data))
+    {
       return None;
     }
+    // 入力の続きを待って中断していた場合、トークンの開始位置は中断前のままにする。
+    if !self.suspended {
+      self.token_start = if self.reconsume {
+        self.current_offset()
+      } else {
+        self.pos
+      };
+    }
+    self.suspended = false;
     loop {
+      // 入力の続きがまだ届いていない場合、feed で追加されるまで処理を中断する。
+      if !self.closed && !self.reconsume && self.pos >= self.input.len() {
+        self.suspended = true;
+        return None;
+      }
+
       // 次に処理する文字を取得する。
       // reconsume が true の場合、現在位置の1つ前の文字を読み込む。
       // false の場合、現在位置の文字を読み込む。
@@ -251,63 +551,99 @@ impl Iterator for HtmlTokenizer {
             self.reconsume = true;
             self.state = State::TagName;
             self.create_tag(true);
+            self.mark = self.current_offset();
             continue;
           }
 
-          // 入力文字列が最後に到達した場合、Eof トークンを返却する。
-          if self.is_eof() {
-            return Some(HtmlToken::Eof);
+          // <! の場合、コメントなどのマークアップ宣言の開始状態に遷移する。
+          if c == '!' {
+            self.state = State::MarkupDeclarationOpen;
+            continue;
+          }
+
+          // <? の場合 (例: <?xml ...?>)、ボガスコメントとして扱う。
+          if c == '?' {
+            self.parse_error(ParseErrorKind::UnexpectedQuestionMarkInsteadOfTagName);
+            self.create_comment(self.current_offset());
+            self.reconsume = true;
+            self.state = State::BogusComment;
+            continue;
           }
 
+          // それ以外の場合 (例: "a < b")、< を文字として返し、現在の文字をデータ状態で再処理する。
+          // 入力文字列が最後に到達した場合も、< を返した後にデータ状態で Eof トークンを返す。
+          if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofBeforeTagName);
+          } else {
+            self.parse_error(ParseErrorKind::InvalidFirstCharacterOfTagName);
+          }
           self.reconsume = true;
           self.state = State::Data;
+          return Some(HtmlToken::Char('<'));
         }
         State::EndTagOpen => {
-          // 終了タグ開始状態で入力文字列が最後に到達した場合、Eof トークンを返却する。
-          if self.is_eof() {
-            return Some(HtmlToken::Eof);
-          }
-
           // 終了タグ開始状態でアルファベットの場合、タグ名状態に遷移させ、終了タグを作成する。
           if c.is_ascii_alphabetic() {
             self.reconsume = true;
             self.state = State::TagName;
             self.create_tag(false);
+            self.mark = self.current_offset();
+            continue;
+          }
+
+          // </> の場合、何も返さずにデータ状態に遷移する。
+          if c == '>' {
+            self.parse_error(ParseErrorKind::MissingEndTagName);
+            self.state = State::Data;
+            continue;
+          }
+
+          // 終了タグ開始状態で入力文字列が最後に到達した場合、</ を文字として返してから Eof トークンを返す。
+          if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofBeforeTagName);
+            self.buf = String::from("</");
+            self.reconsume = true;
+            self.return_state = State::Data;
+            self.state = State::TemporaryBuffer;
             continue;
           }
+
+          // それ以外の場合 (例: </3>)、ボガスコメントとして扱う。
+          self.parse_error(ParseErrorKind::InvalidFirstCharacterOfTagName);
+          self.create_comment(self.current_offset());
+          self.reconsume = true;
+          self.state = State::BogusComment;
         }
         State::TagName  => {
           // タグ名状態でスペースの場合、属性名開始前の状態に遷移させる。
           if c == ' ' {
+            self.set_tag_name();
             self.state = State::BeforeAttributeName;
             continue;
           }
 
           // タグ名状態で / の場合、現在の終了タグの開始状態に遷移させる。
           if c == '/' {
+            self.set_tag_name();
             self.state = State::SelfClosingStartTag;
             continue;
           }
 
           // タグ名状態で鵜 > の場合、データ状態に遷移させ、create_tag メソッドで作成した latest_token を返す。
           if c == '>' {
+            self.set_tag_name();
             self.state = State::Data;
             return self.take_latest_token();
           }
 
-          // 次の文字がアルファベット大文字の場合、現在のタグの名前として追加する。
-          if c.is_ascii_uppercase() {
-            self.append_tag_name(c.to_ascii_lowercase());
-            continue;
-          }
-
           // 入力文字列の最後に到達した場合、Eof トークンを返す。
           if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInTag);
             return Some(HtmlToken::Eof);
           }
 
-          self.append_tag_name(c);
-        }        
+          // それ以外の文字はタグ名の一部として読み進める。タグ名はタグ名の終わりで小文字に変換して設定する。
+        }
         State::BeforeAttributeName => {
           // タグ属性名の開始前状態の場合に、/ や > 終了時は属性名終了状態に遷移させる。
           if c == '/' || c == '>' || self.is_eof() {
@@ -323,21 +659,18 @@ impl Iterator for HtmlTokenizer {
         State::AttributeName => {
           // 属性名状態の場合に、スペース, / , > または文字列の最後の場合、ステータスを属性名終了状態に遷移させる。
           if c == ' ' || c == '/' || c == '>' || self.is_eof() {
+            self.set_attribute(/*is_name*/ true);
             self.reconsume = true;
             self.state = State::AfterAttributeName;
             continue;
           }
           // = の場合、属性値前の状態に遷移させる。
           if c == '=' {
+            self.set_attribute(/*is_name*/ true);
             self.state = State::BeforeAttributeValue;
             continue;
           }
-          // アスキー文字の場合、属性名に一文字追加する。
-          if c.is_ascii_uppercase() {
-            self.append_attribute(c.to_ascii_lowercase(), /*is_name*/ true);
-            continue;
-          }
-          self.append_attribute(c, /*is_name*/ true);
+          // それ以外の文字は属性名の一部として読み進める。
         }
         // タグの属性名の処理中の場合
         State::AfterAttributeName => {
@@ -358,6 +691,7 @@ impl Iterator for HtmlTokenizer {
             return self.take_latest_token();
           }
           if self.is_eof() { // 文字の最後の場合
+            self.parse_error(ParseErrorKind::EofInTag);
             return Some(HtmlToken::Eof);
           }
           self.reconsume = true;
@@ -370,55 +704,63 @@ impl Iterator for HtmlTokenizer {
           if c == ' ' {
             continue;
           } // 空白は無視する
+          // 属性値は引用符の次の文字から始まる。
           if c == '"' { 
+            self.mark = self.pos;
             self.state = State::AttributeValueDoubleQuoted;
             continue;
           }
           if c == '\'' {
+            self.mark = self.pos;
             self.state = State::AttributeValueSingleQuoted;
             continue;
           }
+          self.mark = self.current_offset();
           self.reconsume = true;
           self.state = State::AttributeValueUnquoted;
         }
         // ダブルクォートで囲まれた属性値を処理する状態
         State::AttributeValueDoubleQuoted => {
           if c == '"' { // ダブルクォートが登場した場合、属性値の終了状態に遷移する。
+            self.set_attribute(/*is_name*/ false);
             self.state = State::AfterAttributeValueQuoted;
             continue;
           }
           if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInTag);
             return Some(HtmlToken::Eof);
           }
-          self.append_attribute(c, /*is_name*/ false);
         }
         // シングルクォートで囲まれた属性値を処理する状態
         State::AttributeValueSingleQuoted => {
           if c == '\'' { // シングルクォートが登場した場合、属性値の終了状態に遷移する。
+            self.set_attribute(/*is_name*/ false);
             self.state = State::AfterAttributeValueQuoted;
             continue;
           }
           if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInTag);
             return Some(HtmlToken::Eof);
           }
-          self.append_attribute(c, /*is_name*/ false);
         }
         // シングルクォートで囲まれたタグの属性値を処理する。
         State::AttributeValueUnquoted => {
           if c == ' ' {
+            self.set_attribute(/*is_name*/ false);
             self.state = State::BeforeAttributeName;
             continue;
           }
 
           if c == '>' {
+            self.set_attribute(/*is_name*/ false);
             self.state = State::Data;
             return self.take_latest_token();
           }
 
           if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInTag);
             return Some(HtmlToken::Eof);
           }
-          self.append_attribute(c, /*is_name*/ false);
         }
 
 
@@ -437,10 +779,13 @@ impl Iterator for HtmlTokenizer {
             return self.take_latest_token();
           }
           if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInTag);
             return Some(HtmlToken::Eof);
           }
+          // 属性値の直後に空白なしで次の属性が続く場合 (例: a="1"b="2")、属性名開始前の状態で再処理する。
+          self.parse_error(ParseErrorKind::MissingWhitespaceBetweenAttributes);
           self.reconsume = true;
-          self.state = State::BeforeAttributeValue;
+          self.state = State::BeforeAttributeName;
         }
         // 自己終了タグを処理する状態の場合
         State::SelfClosingStartTag => {
@@ -450,9 +795,208 @@ impl Iterator for HtmlTokenizer {
             return self.take_latest_token();
           }
           if self.is_eof() {
-            // invalid parser error.
+            self.parse_error(ParseErrorKind::EofInTag);
             return Some(HtmlToken::Eof);
           }
+          // それ以外の場合 (例: <br/ a>)、属性名開始前の状態で再処理する。
+          self.parse_error(ParseErrorKind::UnexpectedSolidusInTag);
+          self.reconsume = true;
+          self.state = State::BeforeAttributeName;
+        }
+
+        // <! の直後の状態。
+        // -- が続く場合はコメントとして扱う。DOCTYPE や CDATA セクションはサポートしていないため、ボガスコメントとして扱う。
+        State::MarkupDeclarationOpen => {
+          // "--" や "DOCTYPE" かどうか判定できるだけの入力が届いていない場合、続きを待つ。
+          if !self.closed && self.input.len() - self.current_offset() < 7 {
+            self.reconsume = true;
+            self.suspended = true;
+            return None;
+          }
+          if c == '-' && self.input[self.pos..].starts_with('-') {
+            self.pos += 1;
+            self.create_comment(self.pos);
+            self.state = State::CommentStart;
+            continue;
+          }
+          // DOCTYPE はパースエラーではないため、エラーとして記録しない。
+          let offset = self.current_offset();
+          if !self.input[offset..]
+            .get(..7)
+            .is_some_and(|s| s.eq_ignore_ascii_case("doctype"))
+          {
+            self.parse_error(ParseErrorKind::IncorrectlyOpenedComment);
+          }
+          self.create_comment(offset);
+          self.reconsume = true;
+          self.state = State::BogusComment;
+        }
+        // 不正なマークアップを > が現れるまでコメントとして読み飛ばす状態。
+        State::BogusComment => {
+          if c == '>' || self.is_eof() {
+            self.state = State::Data;
+            return self.take_comment(0);
+          }
+        }
+        State::CommentStart => {
+          if c == '-' {
+            self.state = State::CommentStartDash;
+            continue;
+          }
+          // <!--> の場合、空のコメントを返す。
+          if c == '>' {
+            self.parse_error(ParseErrorKind::AbruptClosingOfEmptyComment);
+            self.state = State::Data;
+            return self.take_comment(0);
+          }
+          self.reconsume = true;
+          self.state = State::Comment;
+        }
+        State::CommentStartDash => {
+          if c == '-' {
+            self.state = State::CommentEnd;
+            continue;
+          }
+          // <!---> の場合、空のコメントを返す。
+          if c == '>' {
+            self.parse_error(ParseErrorKind::AbruptClosingOfEmptyComment);
+            self.state = State::Data;
+            return self.take_comment(1);
+          }
+          if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInComment);
+            self.state = State::Data;
+            return self.take_comment(1);
+          }
+          self.reconsume = true;
+          self.state = State::Comment;
+        }
+        State::Comment => {
+          if c == '-' {
+            self.state = State::CommentEndDash;
+            continue;
+          }
+          if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInComment);
+            self.state = State::Data;
+            return self.take_comment(0);
+          }
+        }
+        State::CommentEndDash => {
+          if c == '-' {
+            self.state = State::CommentEnd;
+            continue;
+          }
+          // 末尾の - はコメントに含めない。
+          if self.is_eof() {
+            self.parse_error(ParseErrorKind::EofInComment);
+            self.state = State::Data;
+            return self.take_comment(1);
+          }
+          self.reconsume = true;
+          self.state = State::Comment;
+        }
+        State::CommentEnd => {
+          // 末尾の -- はコメントに含めない。
+          if c == '>' || self.is_eof() {
+            if self.is_eof() {
+              self.parse_error(ParseErrorKind::EofInComment);
+            }
+            self.state = State::Data;
+            return self.take_comment(2);
+          }
+          // --- のように - が続く場合、最初の - はコメントの一部になる。
+          if c == '-' {
+            continue;
+          }
+          self.reconsume = true;
+          self.state = State::Comment;
+        }
+
+        // <textarea> や <title> の内容を処理する状態。
+        // 対応する終了タグ以外のタグは文字として扱う。
+        State::Rcdata => {
+          if c == '<' {
+            self.state = State::RcdataLessThanSign;
+            continue;
+          }
+          if self.is_eof() {
+            return Some(HtmlToken::Eof);
+          }
+          // NUL 文字は置換文字として返す。
+          if c == '\0' {
+            return Some(HtmlToken::Char(REPLACEMENT_CHARACTER));
+          }
+          return Some(HtmlToken::Char(c));
+        }
+        State::RcdataLessThanSign => {
+          if c == '/' {
+            self.state = State::RcdataEndTagOpen;
+            continue;
+          }
+          self.reconsume = true;
+          self.state = State::Rcdata;
+          return Some(HtmlToken::Char('<'));
+        }
+        State::RcdataEndTagOpen => {
+          if c.is_ascii_alphabetic() {
+            self.reconsume = true;
+            self.state = State::RcdataEndTagName;
+            self.create_tag(false);
+            self.mark = self.current_offset();
+            continue;
+          }
+          self.reconsume = true;
+          self.state = State::Rcdata;
+          return Some(HtmlToken::Char('<'));
+        }
+        State::RcdataEndTagName => {
+          if let Some(token) = self.text_end_tag_name(c, State::Rcdata) {
+            return Some(token);
+          }
+        }
+
+        // <style> の内容を処理する状態。
+        // Rcdata と同じく、対応する終了タグ以外のタグは文字として扱う。
+        State::Rawtext => {
+          if c == '<' {
+            self.state = State::RawtextLessThanSign;
+            continue;
+          }
+          if self.is_eof() {
+            return Some(HtmlToken::Eof);
+          }
+          // NUL 文字は置換文字として返す。
+          if c == '\0' {
+            return Some(HtmlToken::Char(REPLACEMENT_CHARACTER));
+          }
+          return Some(HtmlToken::Char(c));
+        }
+        State::RawtextLessThanSign => {
+          if c == '/' {
+            self.state = State::RawtextEndTagOpen;
+            continue;
+          }
+          self.reconsume = true;
+          self.state = State::Rawtext;
+          return Some(HtmlToken::Char('<'));
+        }
+        State::RawtextEndTagOpen => {
+          if c.is_ascii_alphabetic() {
+            self.reconsume = true;
+            self.state = State::RawtextEndTagName;
+            self.create_tag(false);
+            self.mark = self.current_offset();
+            continue;
+          }
+          self.reconsume = true;
+          self.state = State::Rawtext;
+          return Some(HtmlToken::Char('<'));
+        }
+        State::RawtextEndTagName => {
+          if let Some(token) = self.text_end_tag_name(c, State::Rawtext) {
+            return Some(token);
+          }
         }
 
         // <script> タグに記述されている Javascript を処理する状態
@@ -464,13 +1008,16 @@ impl Iterator for HtmlTokenizer {
           if self.is_eof() {
             return Some(HtmlToken::Eof);
           }
+          // NUL 文字は置換文字として返す。
+          if c == '\0' {
+            return Some(HtmlToken::Char(REPLACEMENT_CHARACTER));
+          }
           return Some(HtmlToken::Char(c));
         }
 
         // スクリプトデータ処理中に < 文字が現れた場合の処理。</script> の終了タグなのかどうか判断する。
         State::ScriptDataLessThanSign => {
           if c == '/' {
-            self.buf = String::new(); // 一時的なバッファを用意する。
             self.state = State::ScriptDataEndTagOpen;
             continue;
           }
@@ -484,6 +1031,7 @@ impl Iterator for HtmlTokenizer {
             self.reconsume = true;
             self.state = State::ScriptDataEndTagName;
             self.create_tag(false);
+            self.mark = self.current_offset();
             continue;
           }
 
@@ -493,25 +1041,17 @@ impl Iterator for HtmlTokenizer {
         }
         // Javascript の終了タグのタグ名部分を処理する状態
         State::ScriptDataEndTagName => {
-          if c == '>' { // スクリプトの終了タグが閉じられた場合、データ状態に遷移させ、最後のトークンを返却する。
-            self.state = State::Data;
-            return self.take_latest_token();
+          // スクリプトの終了タグが閉じられた場合、データ状態に遷移させ、最後のトークンを返却する。
+          if let Some(token) = self.text_end_tag_name(c, State::ScriptData) {
+            return Some(token);
           }
-          if c.is_ascii_alphabetic() { // アルファベットの場合、一時的なバッファ buf に文字を追加し、append_tag_name で文字をトークンに追加する。
-            self.buf.push(c);
-            self.append_tag_name(c.to_ascii_lowercase());
-            continue;
-          }
-          self.state = State::TemporaryBuffer;
-          self.buf = String::from("</") + &self.buf;
-          self.buf.push(c);
-          continue;
         }
         // 一次的なバッファの管理
+        // バッファを返し終えたら、終了タグ名状態で読み込んだ文字を return_state で再処理する。
         State::TemporaryBuffer => {
           self.reconsume = true;
           if self.buf.chars().count() == 0 {
-            self.state = State::ScriptData;
+            self.state = self.return_state.clone();
             continue;
           }
           // 最初の一文字を削除する。
@@ -646,4 +1186,226 @@ mod tests {
       assert_eq!(Some(e), tokenizer.next());
     }
   }
-}
\ No newline at end of file
+
+  // <style> の内容のテスト
+  // 内容に含まれるタグは文字として扱い、</style> だけを終了タグとする。
+  #[test]
+  fn test_style_rawtext() {
+    let html = "<style>a</p></style>".to_string();
+    let mut tokenizer = HtmlTokenizer::new(html);
+    assert_eq!(
+      Some(HtmlToken::StartTag {
+        tag: "style".to_string(),
+        self_closing: false,
+        attributes: Vec::new(),
+      }),
+      tokenizer.next()
+    );
+    tokenizer.switch_to(State::Rawtext);
+
+    let expected = [
+      HtmlToken::Char('a'),
+      HtmlToken::Char('<'),
+      HtmlToken::Char('/'),
+      HtmlToken::Char('p'),
+      HtmlToken::Char('>'),
+      HtmlToken::EndTag {
+        tag: "style".to_string(),
+      },
+    ];
+    for e in expected {
+      assert_eq!(Some(e), tokenizer.next());
+    }
+  }
+
+  // <textarea> の内容のテスト
+  #[test]
+  fn test_textarea_rcdata() {
+    let html = "<textarea>a<b</textarea>".to_string();
+    let mut tokenizer = HtmlTokenizer::new(html);
+    assert_eq!(
+      Some(HtmlToken::StartTag {
+        tag: "textarea".to_string(),
+        self_closing: false,
+        attributes: Vec::new(),
+      }),
+      tokenizer.next()
+    );
+    tokenizer.switch_to(State::Rcdata);
+
+    let expected = [
+      HtmlToken::Char('a'),
+      HtmlToken::Char('<'),
+      HtmlToken::Char('b'),
+      HtmlToken::EndTag {
+        tag: "textarea".to_string(),
+      },
+    ];
+    for e in expected {
+      assert_eq!(Some(e), tokenizer.next());
+    }
+  }
+
+  // コメントとボガスコメントのテスト
+  #[test]
+  fn test_comments() {
+    let html = "<!--a-b--><?xml c?><!d></3><!---->".to_string();
+    let mut tokenizer = HtmlTokenizer::new(html);
+    let expected = [
+      HtmlToken::Comment("a-b".to_string()),
+      HtmlToken::Comment("?xml c?".to_string()),
+      HtmlToken::Comment("d".to_string()),
+      HtmlToken::Comment("3".to_string()),
+      HtmlToken::Comment("".to_string()),
+    ];
+    for e in expected {
+      assert_eq!(Some(e), tokenizer.next());
+    }
+  }
+
+  // 不正なマークアップでパニックしないことのテスト
+  #[test]
+  fn test_malformed_markup() {
+    let inputs = [
+      "<", "</", "<p", "<p a", "<p a=", "<p a=\"", "<p/", "</p a=b>", "<!", "<!-", "<!--", "<!-- a -",
+      "<?", "a < b", "<p a=\"1\"b=2>", "<br/ a>",
+    ];
+    for input in inputs {
+      let tokenizer = HtmlTokenizer::new(input.to_string());
+      assert!(tokenizer.count() <= input.len() + 1, "{}", input);
+    }
+
+    let html = "a < b</>".to_string();
+    let mut tokenizer = HtmlTokenizer::new(html);
+    let expected = [
+      HtmlToken::Char('a'),
+      HtmlToken::Char(' '),
+      HtmlToken::Char('<'),
+      HtmlToken::Char(' '),
+      HtmlToken::Char('b'),
+      HtmlToken::Eof,
+    ];
+    for e in expected {
+      assert_eq!(Some(e), tokenizer.next());
+    }
+    assert_eq!(None, tokenizer.next());
+  }
+
+  // マルチバイト文字を含む入力と、トークンに対応する入力文字列の範囲のテスト
+  #[test]
+  fn test_token_range() {
+    let html = "<p title='あ'>い</P>".to_string();
+    let mut tokenizer = HtmlTokenizer::new(html.clone());
+    let mut attr = Attribute::new();
+    attr.set_name("title".to_string());
+    attr.set_value("あ".to_string());
+
+    assert_eq!(
+      Some(HtmlToken::StartTag {
+        tag: "p".to_string(),
+        self_closing: false,
+        attributes: vec![attr],
+      }),
+      tokenizer.next()
+    );
+    assert_eq!("<p title='あ'>", &html[tokenizer.token_range()]);
+
+    assert_eq!(Some(HtmlToken::Char('い')), tokenizer.next());
+    assert_eq!("い", &html[tokenizer.token_range()]);
+
+    assert_eq!(
+      Some(HtmlToken::EndTag {
+        tag: "p".to_string(),
+      }),
+      tokenizer.next()
+    );
+    assert_eq!("</P>", &html[tokenizer.token_range()]);
+  }
+
+  // パースエラーの種類と位置のテスト
+  #[test]
+  fn test_parse_errors() {
+    let html = "<!DOCTYPE html><?xml?></p a><!x><p".to_string();
+    let mut tokenizer = HtmlTokenizer::new(html);
+    while tokenizer.next().is_some() {}
+
+    let expected = [
+      ParseError::new(ParseErrorKind::UnexpectedQuestionMarkInsteadOfTagName, 16),
+      ParseError::new(ParseErrorKind::EndTagWithAttributes, 26),
+      ParseError::new(ParseErrorKind::IncorrectlyOpenedComment, 30),
+      ParseError::new(ParseErrorKind::EofInTag, 34),
+    ];
+    assert_eq!(expected.to_vec(), tokenizer.errors());
+  }
+
+  // 入力文字列を分割して追加した場合も、まとめて渡した場合と同じトークンを返すことのテスト
+  #[test]
+  fn test_streaming() {
+    let html = "<p class='a'>te<!-- c --></p><!doctype html>";
+    let expected: Vec<HtmlToken> = HtmlTokenizer::new(html.to_string()).collect();
+
+    for chunk_size in 1..html.len() {
+      let mut tokenizer = HtmlTokenizer::new_streaming();
+      let mut tokens = Vec::new();
+      for chunk in html.as_bytes().chunks(chunk_size) {
+        tokenizer.feed(core::str::from_utf8(chunk).unwrap());
+        tokens.extend(&mut tokenizer);
+      }
+      tokenizer.close();
+      tokens.extend(&mut tokenizer);
+      assert_eq!(expected, tokens, "chunk size {}", chunk_size);
+    }
+  }
+
+  // 改行の正規化のテスト
+  #[test]
+  fn test_newline_normalization() {
+    let expected = [
+      HtmlToken::Char('a'),
+      HtmlToken::Char('\n'),
+      HtmlToken::Char('b'),
+      HtmlToken::Char('\n'),
+      HtmlToken::Char('\n'),
+      HtmlToken::Char('c'),
+    ];
+
+    let tokenizer = HtmlTokenizer::new("a\r\nb\r\rc".to_string());
+    assert_eq!(expected.to_vec(), tokenizer.collect::<Vec<_>>());
+
+    // CR と LF が別々の入力に分かれている場合も1つの改行とする。
+    let mut tokenizer = HtmlTokenizer::new_streaming();
+    let mut tokens = Vec::new();
+    for chunk in ["a\r", "\nb\r", "\r", "c"] {
+      tokenizer.feed(chunk);
+      tokens.extend(&mut tokenizer);
+    }
+    tokenizer.close();
+    tokens.extend(&mut tokenizer);
+    assert_eq!(expected.to_vec(), tokens);
+  }
+
+  // NUL 文字のテスト
+  #[test]
+  fn test_null_character() {
+    let html = "<p\0 a='\0'>\0<style>\0</style>".to_string();
+    let mut tokenizer = HtmlTokenizer::new(html);
+    let mut attr = Attribute::new();
+    attr.set_name("a".to_string());
+    attr.set_value("\u{FFFD}".to_string());
+    assert_eq!(
+      Some(HtmlToken::StartTag {
+        tag: "p\u{FFFD}".to_string(),
+        self_closing: false,
+        attributes: vec![attr],
+      }),
+      tokenizer.next()
+    );
+    assert_eq!(Some(HtmlToken::Char('\0')), tokenizer.next());
+    tokenizer.next();
+    tokenizer.switch_to(State::Rawtext);
+    assert_eq!(Some(HtmlToken::Char('\u{FFFD}')), tokenizer.next());
+
+    let offsets: Vec<usize> = tokenizer.errors().iter().map(|e| e.offset()).collect();
+    assert_eq!(vec![2, 7, 10, 18], offsets);
+  }
+}
@@ -1,7 +1,10 @@
+use crate::renderer::js::runtime::JsError;
 use crate::renderer::js::token::JsLexer;
 use crate::renderer::js::token::Token;
+use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::iter::Peekable;
 
@@ -14,16 +17,70 @@ pub enum Node {
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
     },
+    /// 等価演算子 (==, !=, ===, !==) による比較を表す。
+    /// https://262.ecma-international.org/#sec-equality-operators
+    EqualityExpression {
+        operator: String,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    },
+    /// 関係演算子 (<, >, <=, >=) による比較を表す。
+    /// https://262.ecma-international.org/#sec-relational-operators
+    RelationalExpression {
+        operator: String,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    },
+    /// 条件演算子 (test ? consequent : alternate) を表す。
+    /// https://262.ecma-international.org/#sec-conditional-operator
+    ConditionalExpression {
+        test: Option<Rc<Node>>,
+        consequent: Option<Rc<Node>>,
+        alternate: Option<Rc<Node>>,
+    },
+    /// 代入を表す。
+    /// 複合代入 (+=, -=, *=, /=) の場合、operator は演算子の記号 ('+', '-', '*', '/') であり、単純な代入の場合は '=' である。
+    /// https://262.ecma-international.org/#sec-assignment-operators
     AssignmentExpression {
         operator: char,
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
     },
+    /// インクリメント (++) またはデクリメント (--) を表す。
+    /// operator は '+' または '-' であり、prefix が true の場合は前置 (++a)、false の場合は後置 (a++) である。
+    /// https://262.ecma-international.org/#sec-update-expressions
+    UpdateExpression {
+        operator: char,
+        prefix: bool,
+        argument: Option<Rc<Node>>,
+    },
+    /// プロパティへのアクセスを表す。
+    /// computed が true の場合は object[property]、false の場合は object.property の形式である。
     MemberExpression {
         object: Option<Rc<Node>>,
         property: Option<Rc<Node>>,
+        computed: bool,
     },
     NumericLiteral(u64),
+    /// true または false を表す。
+    BooleanLiteral(bool),
+    /// null を表す。
+    NullLiteral,
+    /// [] で囲まれる配列リテラルを表す。
+    /// https://262.ecma-international.org/#sec-array-initializer
+    ArrayExpression {
+        elements: Vec<Option<Rc<Node>>>,
+    },
+    /// {} で囲まれるオブジェクトリテラルを表す。
+    /// https://262.ecma-international.org/#sec-object-initializer
+    ObjectExpression {
+        properties: Vec<Option<Rc<Node>>>,
+    },
+    /// オブジェクトリテラルのキーと値の組を表す。
+    Property {
+        key: String,
+        value: Option<Rc<Node>>,
+    },
     /// var から始まる宣言を表す。
     VariableDeclaration {
         declarations: Vec<Option<Rc<Node>>>,
@@ -46,6 +103,57 @@ pub enum Node {
     ReturnStatement {
         argument: Option<Rc<Node>>,
     },
+    /// break の予約語から始まる文を表す。
+    BreakStatement,
+    /// continue の予約語から始まる文を表す。
+    ContinueStatement,
+    /// if の予約語から始まる文を表す。else 節がない場合、alternate は None である。
+    /// https://262.ecma-international.org/#sec-if-statement
+    IfStatement {
+        test: Option<Rc<Node>>,
+        consequent: Option<Rc<Node>>,
+        alternate: Option<Rc<Node>>,
+    },
+    /// while の予約語から始まる文を表す。
+    /// https://262.ecma-international.org/#sec-while-statement
+    WhileStatement {
+        test: Option<Rc<Node>>,
+        body: Option<Rc<Node>>,
+    },
+    /// for の予約語から始まる文を表す。
+    /// https://262.ecma-international.org/#sec-for-statement
+    ForStatement {
+        init: Option<Rc<Node>>,
+        test: Option<Rc<Node>>,
+        update: Option<Rc<Node>>,
+        body: Option<Rc<Node>>,
+    },
+    /// switch の予約語から始まる文を表す。
+    /// https://262.ecma-international.org/#sec-switch-statement
+    SwitchStatement {
+        discriminant: Option<Rc<Node>>,
+        cases: Vec<Option<Rc<Node>>>,
+    },
+    /// switch 文の case 節、または default 節を表す。default 節の場合、test は None である。
+    SwitchCase {
+        test: Option<Rc<Node>>,
+        consequent: Vec<Option<Rc<Node>>>,
+    },
+    /// throw の予約語から始まる文を表す。
+    /// https://262.ecma-international.org/#sec-throw-statement
+    ThrowStatement {
+        argument: Option<Rc<Node>>,
+    },
+    /// try の予約語から始まる文を表す。
+    /// catch 節がない場合、handler は None である。catch 節に変数がない場合、param は None である。
+    /// finally 節がない場合、finalizer は None である。
+    /// https://262.ecma-international.org/#sec-try-statement
+    TryStatement {
+        block: Option<Rc<Node>>,
+        param: Option<Rc<Node>>,
+        handler: Option<Rc<Node>>,
+        finalizer: Option<Rc<Node>>,
+    },
     /// function の予約語から始まる文を表す。
     FunctionDeclaration {
         id: Option<Rc<Node>>,
@@ -57,6 +165,12 @@ pub enum Node {
         callee: Option<Rc<Node>>,
         arguments: Vec<Option<Rc<Node>>>,
     },
+    /// new の予約語から始まる式を表す。
+    /// https://262.ecma-international.org/#sec-new-operator
+    NewExpression {
+        callee: Option<Rc<Node>>,
+        arguments: Vec<Option<Rc<Node>>>,
+    },
 }
 
 impl Node {
@@ -76,6 +190,42 @@ impl Node {
         }))
     }
 
+    pub fn new_equality_expression(
+        operator: String,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::EqualityExpression {
+            operator,
+            left,
+            right,
+        }))
+    }
+
+    pub fn new_relational_expression(
+        operator: String,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::RelationalExpression {
+            operator,
+            left,
+            right,
+        }))
+    }
+
+    pub fn new_conditional_expression(
+        test: Option<Rc<Self>>,
+        consequent: Option<Rc<Self>>,
+        alternate: Option<Rc<Self>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ConditionalExpression {
+            test,
+            consequent,
+            alternate,
+        }))
+    }
+
     pub fn new_assignment_expression(
         operator: char,
         left: Option<Rc<Node>>,
@@ -88,17 +238,54 @@ impl Node {
         }))
     }
 
+    pub fn new_update_expression(
+        operator: char,
+        prefix: bool,
+        argument: Option<Rc<Self>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::UpdateExpression {
+            operator,
+            prefix,
+            argument,
+        }))
+    }
+
     pub fn new_member_expression(
         object: Option<Rc<Self>>,
         property: Option<Rc<Self>>,
+        computed: bool,
     ) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::MemberExpression { object, property }))
+        Some(Rc::new(Node::MemberExpression {
+            object,
+            property,
+            computed,
+        }))
     }
 
     pub fn new_numeric_literal(value: u64) -> Option<Rc<Self>> {
         Some(Rc::new(Node::NumericLiteral(value)))
     }
 
+    pub fn new_boolean_literal(value: bool) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::BooleanLiteral(value)))
+    }
+
+    pub fn new_null_literal() -> Option<Rc<Self>> {
+        Some(Rc::new(Node::NullLiteral))
+    }
+
+    pub fn new_array_expression(elements: Vec<Option<Rc<Self>>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ArrayExpression { elements }))
+    }
+
+    pub fn new_object_expression(properties: Vec<Option<Rc<Self>>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ObjectExpression { properties }))
+    }
+
+    pub fn new_property(key: String, value: Option<Rc<Self>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::Property { key, value }))
+    }
+
     pub fn new_variable_declarator(
         id: Option<Rc<Self>>,
         init: Option<Rc<Self>>,
@@ -126,6 +313,79 @@ impl Node {
         Some(Rc::new(Node::ReturnStatement { argument }))
     }
 
+    pub fn new_break_statement() -> Option<Rc<Self>> {
+        Some(Rc::new(Node::BreakStatement))
+    }
+
+    pub fn new_continue_statement() -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ContinueStatement))
+    }
+
+    pub fn new_if_statement(
+        test: Option<Rc<Self>>,
+        consequent: Option<Rc<Self>>,
+        alternate: Option<Rc<Self>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::IfStatement {
+            test,
+            consequent,
+            alternate,
+        }))
+    }
+
+    pub fn new_while_statement(test: Option<Rc<Self>>, body: Option<Rc<Self>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::WhileStatement { test, body }))
+    }
+
+    pub fn new_for_statement(
+        init: Option<Rc<Self>>,
+        test: Option<Rc<Self>>,
+        update: Option<Rc<Self>>,
+        body: Option<Rc<Self>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ForStatement {
+            init,
+            test,
+            update,
+            body,
+        }))
+    }
+
+    pub fn new_switch_statement(
+        discriminant: Option<Rc<Self>>,
+        cases: Vec<Option<Rc<Self>>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::SwitchStatement {
+            discriminant,
+            cases,
+        }))
+    }
+
+    pub fn new_switch_case(
+        test: Option<Rc<Self>>,
+        consequent: Vec<Option<Rc<Self>>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::SwitchCase { test, consequent }))
+    }
+
+    pub fn new_throw_statement(argument: Option<Rc<Self>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ThrowStatement { argument }))
+    }
+
+    pub fn new_try_statement(
+        block: Option<Rc<Self>>,
+        param: Option<Rc<Self>>,
+        handler: Option<Rc<Self>>,
+        finalizer: Option<Rc<Self>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::TryStatement {
+            block,
+            param,
+            handler,
+            finalizer,
+        }))
+    }
+
     pub fn new_function_declaration(
         id: Option<Rc<Self>>,
         params: Vec<Option<Rc<Self>>>,
@@ -140,22 +400,36 @@ impl Node {
     ) -> Option<Rc<Self>> {
         Some(Rc::new(Node::CallExpression { callee, arguments }))
     }
+
+    pub fn new_new_expression(
+        callee: Option<Rc<Self>>,
+        arguments: Vec<Option<Rc<Self>>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::NewExpression { callee, arguments }))
+    }
 }
 
 /// AST を構築する JsParser 構造体
 pub struct JsParser {
     t: Peekable<JsLexer>,
+    // 解釈中に最初に見つかった構文エラー
+    error: Option<JsError>,
 }
 
 impl JsParser {
     pub fn new(t: JsLexer) -> Self {
-        Self { t: t.peekable() }
+        Self {
+            t: t.peekable(),
+            error: None,
+        }
     }
 
     /// AST を構築する。
     /// BNF の Program を定義する。
     /// Program ::= ( SourceElements )? <EOF>
-    pub fn parse_ast(&mut self) -> Program {
+    /// 文法に合わないコードの場合は、最初に見つかった構文エラーを JsError::SyntaxError として返す。
+    /// https://262.ecma-international.org/#sec-early-errors
+    pub fn parse_ast(&mut self) -> Result<Program, JsError> {
         let mut program = Program::new();
         let mut body = Vec::new();
 
@@ -166,16 +440,24 @@ impl JsParser {
             match node {
                 Some(n) => body.push(n),
                 None => {
+                    if let Some(error) = self.error.take() {
+                        return Err(error);
+                    }
                     // ノードを作成できなくなった場合、これまで作成したノードのベクタを body にセットして、今まで構築した AST を返却する。
                     program.set_body(body);
-                    return program;
+                    return Ok(program);
                 }
             }
         }
     }
 
-    /// BNF の Statement として ExpressionStatement、VariableStatement、ReturnStatement を解釈する。
-    /// Statement ::= ExpressionStatement | VariableStatement | ReturnStatemet
+    /// BNF の Statement として ExpressionStatement、VariableStatement、ReturnStatement などの文を解釈する。
+    /// Statement ::= Block | ExpressionStatement | VariableStatement | ReturnStatemet | BreakStatement
+    ///             | ContinueStatement | IfStatement | IterationStatement | SwitchStatement
+    ///             | ThrowStatement | TryStatement
+    /// BreakStatement ::= "break" ( ";" )?
+    /// ContinueStatement ::= "continue" ( ";" )?
+    /// ThrowStatement ::= "throw" AssignmentExpression ( ";" )?
     /// ExpressionStatement ::= AssignmentExpression ( ";" )?
     fn statement(&mut self) -> Option<Rc<Node>> {
         let t = match self.t.peek() {
@@ -184,6 +466,8 @@ impl JsParser {
         };
 
         let node = match t {
+            // 文の先頭の { はオブジェクトリテラルではなくブロックとして解釈する。
+            Token::Punctuator('{') => self.block_statement(),
             Token::Keyword(keyword) => {
                 if keyword == "var" {
                     // "var" の予約語を消費する。
@@ -193,8 +477,41 @@ impl JsParser {
                     // "return" の予約語を消費する。
                     assert!(self.t.next().is_some());
                     Node::new_return_statement(self.assignment_expression())
+                } else if keyword == "break" {
+                    // "break" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    Node::new_break_statement()
+                } else if keyword == "continue" {
+                    // "continue" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    Node::new_continue_statement()
+                } else if keyword == "if" {
+                    // "if" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    self.if_statement()
+                } else if keyword == "while" {
+                    // "while" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    self.while_statement()
+                } else if keyword == "for" {
+                    // "for" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    self.for_statement()
+                } else if keyword == "switch" {
+                    // "switch" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    self.switch_statement()
+                } else if keyword == "throw" {
+                    // "throw" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    Node::new_throw_statement(self.assignment_expression())
+                } else if keyword == "try" {
+                    // "try" の予約語を消費する。
+                    assert!(self.t.next().is_some());
+                    self.try_statement()
                 } else {
-                    None
+                    // true, false, null などのリテラルから始まる式として解釈する。
+                    Node::new_expression_statement(self.assignment_expression())
                 }
             }
             _ => Node::new_expression_statement(self.assignment_expression()),
@@ -212,10 +529,208 @@ impl JsParser {
         node
     }
 
+    /// Block の解釈
+    /// 開き波括弧 { を消費し、閉じ波括弧 } が現れるまでブロック内の文として解釈する。
+    /// BNF は以下の通り。
+    /// Block ::= "{" ( SourceElement )* "}"
+    fn block_statement(&mut self) -> Option<Rc<Node>> {
+        self.expect_punctuator('{');
+
+        let mut body = Vec::new();
+        loop {
+            match self.t.peek() {
+                Some(Token::Punctuator('}')) => {
+                    // } を消費する。
+                    assert!(self.t.next().is_some());
+                    return Node::new_block_statement(body);
+                }
+                Some(_) => body.push(self.source_element()),
+                None => return Node::new_block_statement(body),
+            }
+        }
+    }
+
+    /// IfStatement の解釈
+    /// if の予約語は消費済みである。
+    /// BNF は以下の通り。
+    /// IfStatement ::= "if" "(" Expression ")" Statement ( "else" Statement )?
+    fn if_statement(&mut self) -> Option<Rc<Node>> {
+        self.expect_punctuator('(');
+        let test = self.assignment_expression();
+        self.expect_punctuator(')');
+        let consequent = self.statement();
+
+        let alternate = match self.t.peek() {
+            Some(Token::Keyword(keyword)) if keyword == "else" => {
+                // "else" の予約語を消費する。
+                assert!(self.t.next().is_some());
+                self.statement()
+            }
+            _ => None,
+        };
+        Node::new_if_statement(test, consequent, alternate)
+    }
+
+    /// WhileStatement の解釈
+    /// while の予約語は消費済みである。
+    /// BNF は以下の通り。
+    /// WhileStatement ::= "while" "(" Expression ")" Statement
+    fn while_statement(&mut self) -> Option<Rc<Node>> {
+        self.expect_punctuator('(');
+        let test = self.assignment_expression();
+        self.expect_punctuator(')');
+        Node::new_while_statement(test, self.statement())
+    }
+
+    /// ForStatement の解釈
+    /// for の予約語は消費済みである。初期化式、条件式、更新式はそれぞれ省略できる。
+    /// BNF は以下の通り。
+    /// ForStatement ::= "for" "(" ( "var" VariableDeclaration | Expression )? ";" ( Expression )? ";" ( Expression )? ")" Statement
+    fn for_statement(&mut self) -> Option<Rc<Node>> {
+        self.expect_punctuator('(');
+
+        let init = match self.t.peek() {
+            Some(Token::Punctuator(';')) => None,
+            Some(Token::Keyword(keyword)) if keyword == "var" => {
+                // "var" の予約語を消費する。
+                assert!(self.t.next().is_some());
+                self.variable_declaration()
+            }
+            _ => self.assignment_expression(),
+        };
+        self.expect_punctuator(';');
+
+        let test = match self.t.peek() {
+            Some(Token::Punctuator(';')) => None,
+            _ => self.assignment_expression(),
+        };
+        self.expect_punctuator(';');
+
+        let update = match self.t.peek() {
+            Some(Token::Punctuator(')')) => None,
+            _ => self.assignment_expression(),
+        };
+        self.expect_punctuator(')');
+
+        Node::new_for_statement(init, test, update, self.statement())
+    }
+
+    /// SwitchStatement の解釈
+    /// switch の予約語は消費済みである。
+    /// BNF は以下の通り。
+    /// SwitchStatement ::= "switch" "(" AssignmentExpression ")" "{" ( CaseClause | DefaultClause )* "}"
+    /// CaseClause ::= "case" AssignmentExpression ":" ( SourceElement )*
+    /// DefaultClause ::= "default" ":" ( SourceElement )*
+    fn switch_statement(&mut self) -> Option<Rc<Node>> {
+        self.expect_punctuator('(');
+        let discriminant = self.assignment_expression();
+        self.expect_punctuator(')');
+        self.expect_punctuator('{');
+
+        let mut cases = Vec::new();
+        loop {
+            let test = match self.t.next() {
+                Some(Token::Keyword(keyword)) if keyword == "case" => {
+                    let test = self.assignment_expression();
+                    self.expect_punctuator(':');
+                    test
+                }
+                Some(Token::Keyword(keyword)) if keyword == "default" => {
+                    self.expect_punctuator(':');
+                    None
+                }
+                Some(Token::Punctuator('}')) | None => {
+                    return Node::new_switch_statement(discriminant, cases)
+                }
+                Some(t) => {
+                    return self.syntax_error(format!(
+                        "switch should have case or default but got {:?}",
+                        t
+                    ))
+                }
+            };
+
+            // 次の case 節、default 節、または } が現れるまでを節の文として解釈する。
+            let mut consequent = Vec::new();
+            loop {
+                match self.t.peek() {
+                    Some(Token::Keyword(keyword)) if keyword == "case" || keyword == "default" => {
+                        break
+                    }
+                    Some(Token::Punctuator('}')) | None => break,
+                    _ => consequent.push(self.source_element()),
+                }
+            }
+            cases.push(Node::new_switch_case(test, consequent));
+        }
+    }
+
+    /// TryStatement の解釈
+    /// try の予約語は消費済みである。catch 節と finally 節の少なくとも一方が必要である。
+    /// BNF は以下の通り。
+    /// TryStatement ::= "try" Block ( Catch ( Finally )? | Finally )
+    /// Catch ::= "catch" ( "(" Identifier ")" )? Block
+    /// Finally ::= "finally" Block
+    fn try_statement(&mut self) -> Option<Rc<Node>> {
+        let block = self.block_statement();
+
+        let mut param = None;
+        let mut handler = None;
+        if let Some(Token::Keyword(keyword)) = self.t.peek() {
+            if keyword == "catch" {
+                // "catch" の予約語を消費する。
+                assert!(self.t.next().is_some());
+                if let Some(Token::Punctuator('(')) = self.t.peek() {
+                    // '(' を消費する。
+                    assert!(self.t.next().is_some());
+                    param = self.identifier();
+                    self.expect_punctuator(')');
+                }
+                handler = self.block_statement();
+            }
+        }
+
+        let mut finalizer = None;
+        if let Some(Token::Keyword(keyword)) = self.t.peek() {
+            if keyword == "finally" {
+                // "finally" の予約語を消費する。
+                assert!(self.t.next().is_some());
+                finalizer = self.block_statement();
+            }
+        }
+
+        if handler.is_none() && finalizer.is_none() {
+            return self.syntax_error("try statement should have catch or finally".to_string());
+        }
+        Node::new_try_statement(block, param, handler, finalizer)
+    }
+
+    /// 次のトークンが記号 c であることを確認し、消費する。
+    /// 異なるトークンの場合は構文エラーとする。
+    fn expect_punctuator(&mut self, c: char) {
+        match self.t.next() {
+            Some(Token::Punctuator(p)) if p == c => {}
+            t => {
+                self.syntax_error(format!("expected {:?} but got {:?}", c, t));
+            }
+        }
+    }
+
+    /// 構文エラーを記録し、残りのトークンを読み捨てて解釈を打ち切る。
+    /// 呼び出し元はノードの代わりに戻り値の None を返す。
+    fn syntax_error(&mut self, message: String) -> Option<Rc<Node>> {
+        if self.error.is_none() {
+            self.error = Some(JsError::SyntaxError(message));
+        }
+        for _ in self.t.by_ref() {}
+        None
+    }
+
     /// BNF の AssignExpression を解釈する。
-    /// AssignmentExpression ::= AdditiveExpression ("=" AdditiveExpression )*
+    /// AssignmentExpression ::= ConditionalExpression ( AssignmentOperator AssignmentExpression )*
+    /// AssignmentOperator ::= "=" | "+=" | "-=" | "*=" | "/="
     fn assignment_expression(&mut self) -> Option<Rc<Node>> {
-        let expr = self.additive_expression();
+        let expr = self.conditional_expression();
 
         let t = match self.t.peek() {
             Some(token) => token,
@@ -228,85 +743,197 @@ impl JsParser {
                 assert!(self.t.next().is_some());
                 Node::new_assignment_expression('=', expr, self.assignment_expression())
             }
+            Token::MultiCharPunctuator(op)
+                if op == "+=" || op == "-=" || op == "*=" || op == "/=" =>
+            {
+                // 複合代入演算子の先頭の記号を演算子とする。
+                let operator = op.chars().next().unwrap_or('=');
+                // 複合代入演算子を消費する。
+                assert!(self.t.next().is_some());
+                Node::new_assignment_expression(operator, expr, self.assignment_expression())
+            }
             _ => expr,
         }
     }
 
+    /// BNF の ConditionalExpression を解釈する。
+    /// ConditionalExpression ::= EqualityExpression ( "?" AssignmentExpression ":" AssignmentExpression )?
+    fn conditional_expression(&mut self) -> Option<Rc<Node>> {
+        let test = self.equality_expression();
+
+        match self.t.peek() {
+            Some(Token::Punctuator('?')) => {
+                // '?' を消費する。
+                assert!(self.t.next().is_some());
+                let consequent = self.assignment_expression();
+                self.expect_punctuator(':');
+                let alternate = self.assignment_expression();
+                Node::new_conditional_expression(test, consequent, alternate)
+            }
+            _ => test,
+        }
+    }
+
+    /// BNF の EqualityExpression を解釈する。
+    /// EqualityExpression ::= RelationalExpression ( EqualityOperator RelationalExpression )*
+    /// EqualityOperator ::= "==" | "!=" | "===" | "!=="
+    fn equality_expression(&mut self) -> Option<Rc<Node>> {
+        let mut left = self.relational_expression();
+
+        loop {
+            let operator = match self.t.peek() {
+                Some(Token::MultiCharPunctuator(op))
+                    if op == "==" || op == "!=" || op == "===" || op == "!==" =>
+                {
+                    op.clone()
+                }
+                _ => return left,
+            };
+            // 等価演算子を消費する。
+            assert!(self.t.next().is_some());
+            left = Node::new_equality_expression(operator, left, self.relational_expression());
+        }
+    }
+
+    /// BNF の RelationalExpression を解釈する。
+    /// RelationalExpression ::= AdditiveExpression ( RelationalOperator AdditiveExpression )*
+    /// RelationalOperator ::= "<" | ">" | "<=" | ">="
+    fn relational_expression(&mut self) -> Option<Rc<Node>> {
+        let mut left = self.additive_expression();
+
+        loop {
+            let operator = match self.t.peek() {
+                Some(Token::Punctuator(c)) if *c == '<' || *c == '>' => c.to_string(),
+                Some(Token::MultiCharPunctuator(op)) if op == "<=" || op == ">=" => op.clone(),
+                _ => return left,
+            };
+            // 関係演算子を消費する。
+            assert!(self.t.next().is_some());
+            left = Node::new_relational_expression(operator, left, self.additive_expression());
+        }
+    }
+
     /// BNF の AdditiveExpression を解釈する。
-    /// AdditiveExpression ::= LeftHandSideExpression ( AdditiveOperator AssignmentExpression )*
+    /// 左結合であり、1 - 2 + 3 は (1 - 2) + 3 として解釈する。
+    /// AdditiveExpression ::= UpdateExpression ( AdditiveOperator UpdateExpression )*
     fn additive_expression(&mut self) -> Option<Rc<Node>> {
         // 足し算や引き算の左辺となるノードを作成する。
-        let left = self.left_hand_side_expression();
+        let mut left = self.update_expression();
 
-        let t = match self.t.peek() {
-            Some(token) => token.clone(),
-            // トークンが存在しない場合、作成したノードをそのまま返す。
-            None => return left,
-        };
+        loop {
+            let c = match self.t.peek() {
+                Some(Token::Punctuator(c)) if *c == '+' || *c == '-' => *c,
+                // トークンが存在しない場合や演算子でない場合、作成したノードをそのまま返す。
+                _ => return left,
+            };
+            // '+' または '-' の記号を消費する。
+            assert!(self.t.next().is_some());
+            left = Node::new_additive_expression(c, left, self.update_expression());
+        }
+    }
 
-        match t {
-            Token::Punctuator(c) => match c {
-                // '+' または '-' の記号を消費する。
-                '+' | '-' => {
-                    assert!(self.t.next().is_some());
-                    Node::new_additive_expression(c, left, self.assignment_expression())
-                }
-                _ => left,
-            },
-            _ => left,
+    /// BNF の UpdateExpression を解釈する。
+    /// UpdateExpression ::= LeftHandSideExpression ( "++" | "--" )? | ( "++" | "--" ) UpdateExpression
+    fn update_expression(&mut self) -> Option<Rc<Node>> {
+        // 前置の ++ または -- を解釈する。
+        if let Some(Token::MultiCharPunctuator(op)) = self.t.peek() {
+            if op == "++" || op == "--" {
+                let operator = if op == "++" { '+' } else { '-' };
+                // "++" または "--" を消費する。
+                assert!(self.t.next().is_some());
+                return Node::new_update_expression(operator, true, self.update_expression());
+            }
+        }
+
+        let expr = self.left_hand_side_expression();
+
+        // 後置の ++ または -- を解釈する。
+        match self.t.peek() {
+            Some(Token::MultiCharPunctuator(op)) if op == "++" || op == "--" => {
+                let operator = if op == "++" { '+' } else { '-' };
+                // "++" または "--" を消費する。
+                assert!(self.t.next().is_some());
+                Node::new_update_expression(operator, false, expr)
+            }
+            _ => expr,
         }
     }
 
     /// BNF の LeftHandSideExpression を解釈する。
+    /// 関数呼び出しの結果に対するプロパティアクセスや関数呼び出し (例: JSON.parse(s).a) も解釈する。
     /// LeftHandSideExpression ::= CallExpression | MemberExpression
+    /// CallExpression ::= MemberExpression Arguments ( Arguments | "." Identifier | "[" Expression "]" )*
     fn left_hand_side_expression(&mut self) -> Option<Rc<Node>> {
-        let expr = self.member_expression();
-
-        let t = match self.t.peek() {
-            Some(token) => token,
-            None => return expr,
-        };
+        let mut expr = self.member_expression();
 
-        match t {
-            Token::Punctuator(c) => {
-                if c == &'(' {
+        loop {
+            match self.t.peek() {
+                Some(Token::Punctuator('(')) => {
                     // '(' を消費する。
                     assert!(self.t.next().is_some());
-                    return Node::new_call_expression(expr, self.arguments());
+                    expr = Node::new_call_expression(expr, self.arguments());
                 }
-                expr
+                Some(Token::Punctuator('.')) | Some(Token::Punctuator('[')) => {
+                    expr = self.member_access(expr);
+                }
+                _ => return expr,
             }
-            _ => expr,
         }
     }
 
     /// BNF の MemberExpression を解釈する。
-    /// MemberExpression ::= PrimaryExpression ( "." Identifier )*
+    /// MemberExpression ::= ( PrimaryExpression | "new" MemberExpression ( Arguments )? ) ( "." Identifier | "[" Expression "]" )*
     fn member_expression(&mut self) -> Option<Rc<Node>> {
-        let expr = self.primary_expression();
-
-        let t = match self.t.peek() {
-            Some(token) => token,
-            None => return expr,
+        let mut expr = match self.t.peek() {
+            Some(Token::Keyword(keyword)) if keyword == "new" => {
+                // "new" の予約語を消費する。
+                assert!(self.t.next().is_some());
+                let callee = self.member_expression();
+                let arguments = match self.t.peek() {
+                    Some(Token::Punctuator('(')) => {
+                        // '(' を消費する。
+                        assert!(self.t.next().is_some());
+                        self.arguments()
+                    }
+                    _ => Vec::new(),
+                };
+                Node::new_new_expression(callee, arguments)
+            }
+            _ => self.primary_expression(),
         };
 
-        match t {
-            Token::Punctuator(c) => {
-                if c == &'.' {
-                    // '.' を消費する。
+        loop {
+            match self.t.peek() {
+                Some(Token::Punctuator('.')) | Some(Token::Punctuator('[')) => {
+                    expr = self.member_access(expr);
+                }
+                _ => return expr,
+            }
+        }
+    }
+
+    /// "." Identifier または "[" Expression "]" の形式のプロパティアクセスを解釈する。
+    fn member_access(&mut self, object: Option<Rc<Node>>) -> Option<Rc<Node>> {
+        match self.t.next() {
+            Some(Token::Punctuator('.')) => {
+                Node::new_member_expression(object, self.identifier(), false)
+            }
+            Some(Token::Punctuator('[')) => {
+                let property = self.assignment_expression();
+                // ']' を消費する。
+                if let Some(Token::Punctuator(']')) = self.t.peek() {
                     assert!(self.t.next().is_some());
-                    return Node::new_member_expression(expr, self.identifier());
                 }
-                expr
+                Node::new_member_expression(object, property, true)
             }
-            _ => expr,
+            _ => object,
         }
     }
 
     /// BNF の PrimaryExpression を解釈する。
     /// PrimaryExpression は配列、変数や関数名、文字や数値リテラルを表す。
-    /// PrimaryExpression ::= Identifier | Literal
-    /// Literal ::= <digit>+
+    /// PrimaryExpression ::= Identifier | Literal | ArrayLiteral | ObjectLiteral | "(" Expression ")"
+    /// Literal ::= <digit>+ | "true" | "false" | "null"
     /// <digit> ::= 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
     fn primary_expression(&mut self) -> Option<Rc<Node>> {
         let t = match self.t.next() {
@@ -318,10 +945,88 @@ impl JsParser {
             Token::Identifier(value) => Node::new_identifier(value),
             Token::StringLiteral(value) => Node::new_string_literal(value),
             Token::Number(value) => Node::new_numeric_literal(value),
+            Token::Keyword(keyword) => match keyword.as_str() {
+                "true" => Node::new_boolean_literal(true),
+                "false" => Node::new_boolean_literal(false),
+                "null" => Node::new_null_literal(),
+                _ => None,
+            },
+            // 丸括弧で囲まれた式
+            Token::Punctuator('(') => {
+                let expr = self.assignment_expression();
+                self.expect_punctuator(')');
+                expr
+            }
+            Token::Punctuator('[') => self.array_literal(),
+            Token::Punctuator('{') => self.object_literal(),
             _ => None,
         }
     }
 
+    /// ArrayLiteral の解釈
+    /// 開き角括弧 [ は消費済みであり、閉じ角括弧 ] が現れるまでカンマ , で区切られた要素を解釈する。
+    /// BNF は以下の通り。
+    /// ArrayLiteral ::= "[" ( AssignmentExpression ( "," AssignmentExpression )* )? "]"
+    fn array_literal(&mut self) -> Option<Rc<Node>> {
+        let mut elements = Vec::new();
+
+        loop {
+            match self.t.peek() {
+                Some(Token::Punctuator(']')) => {
+                    // ']' を消費する。
+                    assert!(self.t.next().is_some());
+                    return Node::new_array_expression(elements);
+                }
+                Some(Token::Punctuator(',')) => {
+                    // ',' を消費する。
+                    assert!(self.t.next().is_some());
+                }
+                Some(_) => elements.push(self.assignment_expression()),
+                None => return Node::new_array_expression(elements),
+            }
+        }
+    }
+
+    /// ObjectLiteral の解釈
+    /// 開き波括弧 { は消費済みであり、閉じ波括弧 } が現れるまでカンマ , で区切られたキーと値の組を解釈する。
+    /// BNF は以下の通り。
+    /// ObjectLiteral ::= "{" ( PropertyAssignment ( "," PropertyAssignment )* )? "}"
+    /// PropertyAssignment ::= PropertyName ":" AssignmentExpression
+    /// PropertyName ::= Identifier | StringLiteral | NumericLiteral
+    fn object_literal(&mut self) -> Option<Rc<Node>> {
+        let mut properties = Vec::new();
+
+        loop {
+            let key = match self.t.next() {
+                Some(Token::Punctuator('}')) | None => {
+                    return Node::new_object_expression(properties)
+                }
+                Some(Token::Punctuator(',')) => continue,
+                Some(Token::Identifier(name)) => name,
+                Some(Token::Keyword(name)) => name,
+                Some(Token::StringLiteral(name)) => name,
+                Some(Token::Number(num)) => num.to_string(),
+                Some(t) => {
+                    return self.syntax_error(format!(
+                        "object literal should have a property name but got {:?}",
+                        t
+                    ))
+                }
+            };
+
+            // ':' を消費する。
+            match self.t.next() {
+                Some(Token::Punctuator(':')) => {}
+                t => {
+                    return self
+                        .syntax_error(format!("object literal should have ':' but got {:?}", t))
+                }
+            }
+
+            properties.push(Node::new_property(key, self.assignment_expression()));
+        }
+    }
+
     /// BNF の SourceElement を解釈する。
     /// SourceElement ::= FunctionDeclaration | Statement
     fn source_element(&mut self) -> Option<Rc<Node>> {
@@ -407,14 +1112,8 @@ impl JsParser {
     fn parameter_list(&mut self) -> Vec<Option<Rc<Node>>> {
         let mut params = Vec::new();
 
-        // '(' を消費する。もし次のトークンが '(' ではない場合、エラーになる。
-        match self.t.next() {
-            Some(t) => match t {
-                Token::Punctuator(c) => assert!(c == '('),
-                _ => unimplemented!("function should have '(' but got {:?}", t),
-            },
-            None => unimplemented!("function should have '(' but got None"),
-        }
+        // '(' を消費する。もし次のトークンが '(' ではない場合、構文エラーになる。
+        self.expect_punctuator('(');
 
         loop {
             // ')' に到達するまで、params に仮引数となる変数を追加する。
@@ -446,13 +1145,8 @@ impl JsParser {
     /// BNFは以下の通り。
     /// FunctionBody ::= "{" ( SourceElements )? "}"
     fn function_body(&mut self) -> Option<Rc<Node>> {
-        match self.t.next() {
-            Some(t) => match t {
-                Token::Punctuator(c) => assert!(c == '{'),
-                _ => unimplemented!("function should have open curly blacket but got {:?}", t),
-            },
-            None => unimplemented!("function should have open curly blacket but got None"),
-        }
+        // '{' を消費する。もし次のトークンが '{' ではない場合、構文エラーになる。
+        self.expect_punctuator('{');
 
         let mut body = Vec::new();
 
@@ -469,7 +1163,10 @@ impl JsParser {
                     }
                     _ => {}
                 },
-                None => {}
+                None => {
+                    return self
+                        .syntax_error("function should have close curly bracket".to_string())
+                }
             }
             body.push(self.source_element());
         }
@@ -488,17 +1185,16 @@ impl JsParser {
             // ) に到達するまで、解釈した値を arguments ベクタに追加する。
             match self.t.peek() {
                 Some(t) => match t {
-                    Token::Punctuator(c) => {
-                        // ) を消費する。
-                        if c == &')' {
-                            assert!(self.t.next().is_some());
-                            return arguments;
-                        }
-                        // , を消費する。
-                        if c == &',' {
-                            assert!(self.t.next().is_some());
-                        }
+                    // ) を消費する。
+                    Token::Punctuator(')') => {
+                        assert!(self.t.next().is_some());
+                        return arguments;
+                    }
+                    // , を消費する。
+                    Token::Punctuator(',') => {
+                        assert!(self.t.next().is_some());
                     }
+                    // 配列リテラルやオブジェクトリテラルなど、記号から始まる引数も解釈する。
                     _ => arguments.push(self.assignment_expression()),
                 },
                 None => return arguments,
@@ -539,7 +1235,7 @@ mod tests {
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
         let expected = Program::new();
-        assert_eq!(expected, parser.parse_ast());
+        assert_eq!(Ok(expected), parser.parse_ast());
     }
 
     /// 1つの数字だけのテスト
@@ -555,7 +1251,7 @@ mod tests {
             Node::NumericLiteral(42),
         )))));
         expected.set_body(body);
-        assert_eq!(expected, parser.parse_ast());
+        assert_eq!(Ok(expected), parser.parse_ast());
     }
 
     /// 足し算のテスト
@@ -576,7 +1272,7 @@ mod tests {
             },
         )))));
         expected.set_body(body);
-        assert_eq!(expected, parser.parse_ast());
+        assert_eq!(Ok(expected), parser.parse_ast());
     }
 
     /// 変数定義のテスト
@@ -598,7 +1294,7 @@ mod tests {
             .to_vec(),
         }));
         expected.set_body(body);
-        assert_eq!(expected, parser.parse_ast());
+        assert_eq!(Ok(expected), parser.parse_ast());
     }
 
     /// 変数呼び出すのテスト
@@ -632,7 +1328,7 @@ mod tests {
             .to_vec(),
         }));
         expected.set_body(body);
-        assert_eq!(expected, parser.parse_ast());
+        assert_eq!(Ok(expected), parser.parse_ast());
     }
 
     /// 関数定義のテスト
@@ -655,7 +1351,7 @@ mod tests {
             })),
         }));
         expected.set_body(body);
-        assert_eq!(expected, parser.parse_ast());
+        assert_eq!(Ok(expected), parser.parse_ast());
     }
 
     /// 引数付き関数定義のテスト
@@ -687,6 +1383,281 @@ mod tests {
             })),
         }));
         expected.set_body(body);
-        assert_eq!(expected, parser.parse_ast());
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// 配列リテラルとオブジェクトリテラルのテスト
+    #[test]
+    fn test_array_and_object_literal() {
+        let input = "[1, true, null]; ({a: 1, \"b c\": [\"x\"]})".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [
+            Rc::new(Node::ExpressionStatement(Some(Rc::new(
+                Node::ArrayExpression {
+                    elements: [
+                        Some(Rc::new(Node::NumericLiteral(1))),
+                        Some(Rc::new(Node::BooleanLiteral(true))),
+                        Some(Rc::new(Node::NullLiteral)),
+                    ]
+                    .to_vec(),
+                },
+            )))),
+            Rc::new(Node::ExpressionStatement(Some(Rc::new(
+                Node::ObjectExpression {
+                    properties: [
+                        Some(Rc::new(Node::Property {
+                            key: "a".to_string(),
+                            value: Some(Rc::new(Node::NumericLiteral(1))),
+                        })),
+                        Some(Rc::new(Node::Property {
+                            key: "b c".to_string(),
+                            value: Some(Rc::new(Node::ArrayExpression {
+                                elements: [Some(Rc::new(Node::StringLiteral("x".to_string())))]
+                                    .to_vec(),
+                            })),
+                        })),
+                    ]
+                    .to_vec(),
+                },
+            )))),
+        ]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// プロパティアクセスのテスト
+    /// a.b[0] は、a.b を object とし、0 を property とする MemberExpression になるはずである。
+    #[test]
+    fn test_member_expression() {
+        let input = "a.b[0]".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::MemberExpression {
+                object: Some(Rc::new(Node::MemberExpression {
+                    object: Some(Rc::new(Node::Identifier("a".to_string()))),
+                    property: Some(Rc::new(Node::Identifier("b".to_string()))),
+                    computed: false,
+                })),
+                property: Some(Rc::new(Node::NumericLiteral(0))),
+                computed: true,
+            },
+        ))))]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// switch 文のテスト
+    /// case 節と default 節が SwitchCase ノードとなり、default 節の test は None になるはずである。
+    #[test]
+    fn test_switch() {
+        let input = "switch (a) { case 1: b; break; default: c; }".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::SwitchStatement {
+            discriminant: Some(Rc::new(Node::Identifier("a".to_string()))),
+            cases: [
+                Some(Rc::new(Node::SwitchCase {
+                    test: Some(Rc::new(Node::NumericLiteral(1))),
+                    consequent: [
+                        Some(Rc::new(Node::ExpressionStatement(Some(Rc::new(
+                            Node::Identifier("b".to_string()),
+                        ))))),
+                        Some(Rc::new(Node::BreakStatement)),
+                    ]
+                    .to_vec(),
+                })),
+                Some(Rc::new(Node::SwitchCase {
+                    test: None,
+                    consequent: [Some(Rc::new(Node::ExpressionStatement(Some(Rc::new(
+                        Node::Identifier("c".to_string()),
+                    )))))]
+                    .to_vec(),
+                })),
+            ]
+            .to_vec(),
+        })]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// 加算と減算は左結合であることを確認する。
+    #[test]
+    fn test_additive_left_associative() {
+        let input = "1 - 2 + 3".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::AdditiveExpression {
+                operator: '+',
+                left: Some(Rc::new(Node::AdditiveExpression {
+                    operator: '-',
+                    left: Some(Rc::new(Node::NumericLiteral(1))),
+                    right: Some(Rc::new(Node::NumericLiteral(2))),
+                })),
+                right: Some(Rc::new(Node::NumericLiteral(3))),
+            },
+        ))))]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// for 文と if 文、continue 文のテスト
+    #[test]
+    fn test_for_statement() {
+        let input = "for (var i = 0; i < 3; i = i + 1) { if (i == 1) continue; }".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let i = || Some(Rc::new(Node::Identifier("i".to_string())));
+        let body = [Rc::new(Node::ForStatement {
+            init: Some(Rc::new(Node::VariableDeclaration {
+                declarations: [Some(Rc::new(Node::VariableDeclarator {
+                    id: i(),
+                    init: Some(Rc::new(Node::NumericLiteral(0))),
+                }))]
+                .to_vec(),
+            })),
+            test: Some(Rc::new(Node::RelationalExpression {
+                operator: "<".to_string(),
+                left: i(),
+                right: Some(Rc::new(Node::NumericLiteral(3))),
+            })),
+            update: Some(Rc::new(Node::AssignmentExpression {
+                operator: '=',
+                left: i(),
+                right: Some(Rc::new(Node::AdditiveExpression {
+                    operator: '+',
+                    left: i(),
+                    right: Some(Rc::new(Node::NumericLiteral(1))),
+                })),
+            })),
+            body: Some(Rc::new(Node::BlockStatement {
+                body: [Some(Rc::new(Node::IfStatement {
+                    test: Some(Rc::new(Node::EqualityExpression {
+                        operator: "==".to_string(),
+                        left: i(),
+                        right: Some(Rc::new(Node::NumericLiteral(1))),
+                    })),
+                    consequent: Some(Rc::new(Node::ContinueStatement)),
+                    alternate: None,
+                }))]
+                .to_vec(),
+            })),
+        })]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// try 文、throw 文、new 演算子の解釈を確認する。
+    #[test]
+    fn test_try_statement() {
+        let input = "try { throw new Error('x'); } catch (e) {} finally {}".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::TryStatement {
+            block: Some(Rc::new(Node::BlockStatement {
+                body: [Some(Rc::new(Node::ThrowStatement {
+                    argument: Some(Rc::new(Node::NewExpression {
+                        callee: Some(Rc::new(Node::Identifier("Error".to_string()))),
+                        arguments: [Some(Rc::new(Node::StringLiteral("x".to_string())))].to_vec(),
+                    })),
+                }))]
+                .to_vec(),
+            })),
+            param: Some(Rc::new(Node::Identifier("e".to_string()))),
+            handler: Some(Rc::new(Node::BlockStatement { body: Vec::new() })),
+            finalizer: Some(Rc::new(Node::BlockStatement { body: Vec::new() })),
+        })]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// 前置と後置のインクリメント、デクリメントの解釈を確認する。
+    #[test]
+    fn test_update_expression() {
+        let input = "i++; --a[0]".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [
+            Rc::new(Node::ExpressionStatement(Some(Rc::new(
+                Node::UpdateExpression {
+                    operator: '+',
+                    prefix: false,
+                    argument: Some(Rc::new(Node::Identifier("i".to_string()))),
+                },
+            )))),
+            Rc::new(Node::ExpressionStatement(Some(Rc::new(
+                Node::UpdateExpression {
+                    operator: '-',
+                    prefix: true,
+                    argument: Some(Rc::new(Node::MemberExpression {
+                        object: Some(Rc::new(Node::Identifier("a".to_string()))),
+                        property: Some(Rc::new(Node::NumericLiteral(0))),
+                        computed: true,
+                    })),
+                },
+            )))),
+        ]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// 条件演算子の解釈を確認する。条件演算子は右結合である。
+    #[test]
+    fn test_conditional_expression() {
+        let input = "a ? 1 : b ? 2 : 3".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::ConditionalExpression {
+                test: Some(Rc::new(Node::Identifier("a".to_string()))),
+                consequent: Some(Rc::new(Node::NumericLiteral(1))),
+                alternate: Some(Rc::new(Node::ConditionalExpression {
+                    test: Some(Rc::new(Node::Identifier("b".to_string()))),
+                    consequent: Some(Rc::new(Node::NumericLiteral(2))),
+                    alternate: Some(Rc::new(Node::NumericLiteral(3))),
+                })),
+            },
+        ))))]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(Ok(expected), parser.parse_ast());
+    }
+
+    /// 文法に合わないコードは、パニックせずに SyntaxError になることを確認する。
+    #[test]
+    fn test_syntax_error() {
+        for input in [
+            "try { a = 1; }",
+            "switch (a) { 1; }",
+            "if (a { b = 1; }",
+            "({1})",
+            "({a 1})",
+            "function f { return 1; }",
+            "function f() return 1;",
+            "function f() { return 1;",
+        ] {
+            let mut parser = JsParser::new(JsLexer::new(input.to_string()));
+            match parser.parse_ast() {
+                Err(JsError::SyntaxError(_)) => {}
+                result => panic!("{:?} should be a SyntaxError but got {:?}", input, result),
+            }
+        }
     }
 }
@@ -0,0 +1,614 @@
+use crate::renderer::js::json;
+use crate::renderer::js::runtime::join_elements;
+use crate::renderer::js::runtime::JsError;
+use crate::renderer::js::runtime::RuntimeValue;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// 組み込み関数の型。
+/// 評価済みの引数を受け取り、呼び出し結果の値を返す。
+/// Err を返した場合、そのエラーは例外として送出される。
+pub type BuiltinFunction = fn(&[RuntimeValue]) -> Result<RuntimeValue, JsError>;
+
+/// 組み込み関数のテーブル。
+/// 関数名と、その関数の実装の組を保持する。
+/// https://262.ecma-international.org/#sec-function-properties-of-the-global-object
+static BUILTIN_FUNCTIONS: [(&str, BuiltinFunction); 10] = [
+    ("parseInt", parse_int),
+    ("parseFloat", parse_float),
+    ("Number", number),
+    ("JSON.parse", json_parse),
+    ("JSON.stringify", json_stringify),
+    ("Error", error),
+    ("TypeError", type_error),
+    ("ReferenceError", reference_error),
+    ("SyntaxError", syntax_error),
+    ("RangeError", range_error),
+];
+
+/// 配列のメソッドの型。
+/// メソッドを呼び出した配列と評価済みの引数を受け取り、呼び出し結果の値を返す。
+pub type BuiltinMethod = fn(&RuntimeValue, &[RuntimeValue]) -> Result<RuntimeValue, JsError>;
+
+/// 配列のメソッドのテーブル。
+/// メソッド名と、そのメソッドの実装の組を保持する。
+/// https://262.ecma-international.org/#sec-properties-of-the-array-prototype-object
+static ARRAY_METHODS: [(&str, BuiltinMethod); 4] = [
+    ("push", array_push),
+    ("pop", array_pop),
+    ("indexOf", array_index_of),
+    ("join", array_join),
+];
+
+/// 関数名に一致する組み込み関数を返す。
+/// 一致する組み込み関数が存在しない場合、None を返す。
+pub fn find_builtin_function(name: &str) -> Option<BuiltinFunction> {
+    for (builtin_name, function) in BUILTIN_FUNCTIONS.iter() {
+        if *builtin_name == name {
+            return Some(*function);
+        }
+    }
+    None
+}
+
+/// メソッド名に一致する配列のメソッドを返す。
+/// 一致するメソッドが存在しない場合、None を返す。
+pub fn find_array_method(name: &str) -> Option<BuiltinMethod> {
+    for (method_name, method) in ARRAY_METHODS.iter() {
+        if *method_name == name {
+            return Some(*method);
+        }
+    }
+    None
+}
+
+/// JavaScript の空白文字かどうか。
+fn is_js_whitespace(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t' | '\n' | '\r' | '\u{0b}' | '\u{0c}' | '\u{a0}' | '\u{feff}'
+    )
+}
+
+/// 引数を文字列に変換する。引数が存在しない場合は "undefined" とする。
+fn argument_to_string(arguments: &[RuntimeValue], index: usize) -> String {
+    match arguments.get(index) {
+        Some(value) => value.to_string(),
+        None => "undefined".to_string(),
+    }
+}
+
+/// parseInt(string, radix)
+/// 文字列の先頭から基数 radix で解釈できる部分を整数に変換する。
+/// 基数が省略された場合、"0x" で始まる文字列は 16 進数、それ以外は 10 進数として扱う。
+/// https://262.ecma-international.org/#sec-parseint-string-radix
+fn parse_int(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    let input = argument_to_string(arguments, 0);
+    let mut s = input.trim_start_matches(is_js_whitespace);
+
+    // 符号を読み込む。
+    let mut sign = 1f64;
+    if let Some(rest) = s.strip_prefix('-') {
+        sign = -1f64;
+        s = rest;
+    } else if let Some(rest) = s.strip_prefix('+') {
+        s = rest;
+    }
+
+    // 基数を決定する。0 または省略された場合は 10 とみなす。
+    let mut radix = match arguments.get(1) {
+        Some(value) => {
+            let r = value.to_number();
+            if r.is_nan() || r.is_infinite() {
+                0
+            } else {
+                r as i64
+            }
+        }
+        None => 0,
+    };
+    let mut strip_prefix = true;
+    if radix != 0 {
+        if !(2..=36).contains(&radix) {
+            return Ok(RuntimeValue::Number(f64::NAN));
+        }
+        if radix != 16 {
+            strip_prefix = false;
+        }
+    } else {
+        radix = 10;
+    }
+
+    // 16 進数のプレフィックス "0x" または "0X" を取り除く。
+    if strip_prefix {
+        if let Some(rest) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            s = rest;
+            radix = 16;
+        }
+    }
+
+    // 基数で解釈できる文字が続く限り、数値として読み込む。
+    let mut num = 0f64;
+    let mut digits = 0;
+    for c in s.chars() {
+        match c.to_digit(radix as u32) {
+            Some(d) => {
+                num = num * radix as f64 + d as f64;
+                digits += 1;
+            }
+            None => break,
+        }
+    }
+
+    // 数値として解釈できる文字が1つもない場合、NaN を返す。
+    if digits == 0 {
+        return Ok(RuntimeValue::Number(f64::NAN));
+    }
+    Ok(RuntimeValue::Number(sign * num))
+}
+
+/// 文字列の先頭から、10 進数の浮動小数点数として解釈できる最長の部分の長さを返す。
+/// StrDecimalLiteral ::= ( "+" | "-" )? ( <digit>+ ( "." <digit>* )? | "." <digit>+ ) ( ( "e" | "E" ) ( "+" | "-" )? <digit>+ )?
+fn decimal_literal_length(s: &str) -> usize {
+    let chars: Vec<char> = s.chars().collect();
+    let mut pos = 0;
+
+    if pos < chars.len() && (chars[pos] == '+' || chars[pos] == '-') {
+        pos += 1;
+    }
+
+    // 整数部と小数部を読み込む。
+    let mut digits = 0;
+    while pos < chars.len() && chars[pos].is_ascii_digit() {
+        pos += 1;
+        digits += 1;
+    }
+    if pos < chars.len() && chars[pos] == '.' {
+        let mut fraction_end = pos + 1;
+        let mut fraction_digits = 0;
+        while fraction_end < chars.len() && chars[fraction_end].is_ascii_digit() {
+            fraction_end += 1;
+            fraction_digits += 1;
+        }
+        if digits > 0 || fraction_digits > 0 {
+            pos = fraction_end;
+            digits += fraction_digits;
+        }
+    }
+    if digits == 0 {
+        return 0;
+    }
+
+    // 指数部を読み込む。指数に数字が続かない場合、指数部は含めない。
+    if pos < chars.len() && (chars[pos] == 'e' || chars[pos] == 'E') {
+        let mut exponent_end = pos + 1;
+        if exponent_end < chars.len() && (chars[exponent_end] == '+' || chars[exponent_end] == '-')
+        {
+            exponent_end += 1;
+        }
+        let exponent_start = exponent_end;
+        while exponent_end < chars.len() && chars[exponent_end].is_ascii_digit() {
+            exponent_end += 1;
+        }
+        if exponent_end > exponent_start {
+            pos = exponent_end;
+        }
+    }
+
+    // 読み込んだ文字はすべて ASCII 文字であるため、文字数はバイト数と一致する。
+    pos
+}
+
+/// "Infinity" の表記を解釈する。
+fn parse_infinity(s: &str) -> Option<f64> {
+    if s.starts_with("Infinity") || s.starts_with("+Infinity") {
+        return Some(f64::INFINITY);
+    }
+    if s.starts_with("-Infinity") {
+        return Some(f64::NEG_INFINITY);
+    }
+    None
+}
+
+/// parseFloat(string)
+/// 文字列の先頭から 10 進数の浮動小数点数として解釈できる部分を数値に変換する。
+/// https://262.ecma-international.org/#sec-parsefloat-string
+fn parse_float(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    let input = argument_to_string(arguments, 0);
+    let s = input.trim_start_matches(is_js_whitespace);
+
+    if let Some(infinity) = parse_infinity(s) {
+        return Ok(RuntimeValue::Number(infinity));
+    }
+
+    let len = decimal_literal_length(s);
+    if len == 0 {
+        return Ok(RuntimeValue::Number(f64::NAN));
+    }
+    match s[..len].parse::<f64>() {
+        Ok(num) => Ok(RuntimeValue::Number(num)),
+        Err(_) => Ok(RuntimeValue::Number(f64::NAN)),
+    }
+}
+
+/// Number(value)
+/// 引数を数値に変換する。引数が省略された場合は 0 を返す。
+/// https://262.ecma-international.org/#sec-number-constructor-number-value
+fn number(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    match arguments.first() {
+        Some(value) => Ok(RuntimeValue::Number(value.to_number())),
+        None => Ok(RuntimeValue::Number(0f64)),
+    }
+}
+
+/// JSON.parse(text)
+/// JSON 文字列を解釈し、値を構築する。
+/// 文字列が JSON として不正な場合は SyntaxError を送出する。
+/// https://262.ecma-international.org/#sec-json.parse
+fn json_parse(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    let text = argument_to_string(arguments, 0);
+    json::parse(&text).map_err(|e| JsError::SyntaxError(format!("{:?}", e)))
+}
+
+/// JSON.stringify(value)
+/// 値を JSON 文字列に変換する。
+/// 値が undefined の場合は undefined を返し、循環参照を含む場合は TypeError を送出する。
+/// https://262.ecma-international.org/#sec-json.stringify
+fn json_stringify(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    let value = arguments.first().unwrap_or(&RuntimeValue::Undefined);
+    match json::stringify(value) {
+        Ok(Some(s)) => Ok(RuntimeValue::StringLiteral(s)),
+        Ok(None) => Ok(RuntimeValue::Undefined),
+        Err(e) => Err(JsError::TypeError(format!("{:?}", e))),
+    }
+}
+
+/// name の種類のエラーオブジェクトを作成する。
+/// 1つ目の引数をメッセージとし、省略された場合は空文字列とする。
+/// https://262.ecma-international.org/#sec-error-message
+fn new_error(name: &str, arguments: &[RuntimeValue]) -> RuntimeValue {
+    let message = match arguments.first() {
+        Some(RuntimeValue::Undefined) | None => "".to_string(),
+        Some(value) => value.to_string(),
+    };
+    RuntimeValue::new_error(name, &message)
+}
+
+/// Error(message)
+/// https://262.ecma-international.org/#sec-error-constructor
+fn error(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    Ok(new_error("Error", arguments))
+}
+
+/// TypeError(message)
+/// https://262.ecma-international.org/#sec-nativeerror-constructors
+fn type_error(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    Ok(new_error("TypeError", arguments))
+}
+
+/// ReferenceError(message)
+/// https://262.ecma-international.org/#sec-nativeerror-constructors
+fn reference_error(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    Ok(new_error("ReferenceError", arguments))
+}
+
+/// SyntaxError(message)
+/// https://262.ecma-international.org/#sec-nativeerror-constructors
+fn syntax_error(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    Ok(new_error("SyntaxError", arguments))
+}
+
+/// RangeError(message)
+/// https://262.ecma-international.org/#sec-nativeerror-constructors
+fn range_error(arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    Ok(new_error("RangeError", arguments))
+}
+
+/// メソッドを呼び出した値が配列でない場合のエラーを返す。
+fn not_an_array(name: &str) -> JsError {
+    JsError::TypeError(format!("Array.prototype.{} called on non-array", name))
+}
+
+/// Array.prototype.push(...items)
+/// 配列の末尾に引数の値を順に追加し、追加後の配列の長さを返す。
+/// https://262.ecma-international.org/#sec-array.prototype.push
+fn array_push(array: &RuntimeValue, arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    match array {
+        RuntimeValue::Array(elements) => {
+            let mut elements = elements.borrow_mut();
+            elements.extend_from_slice(arguments);
+            Ok(RuntimeValue::Number(elements.len() as f64))
+        }
+        _ => Err(not_an_array("push")),
+    }
+}
+
+/// Array.prototype.pop()
+/// 配列の末尾の要素を取り除き、その値を返す。配列が空の場合は undefined を返す。
+/// https://262.ecma-international.org/#sec-array.prototype.pop
+fn array_pop(array: &RuntimeValue, _arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    match array {
+        RuntimeValue::Array(elements) => Ok(elements
+            .borrow_mut()
+            .pop()
+            .unwrap_or(RuntimeValue::Undefined)),
+        _ => Err(not_an_array("pop")),
+    }
+}
+
+/// Array.prototype.indexOf(searchElement, fromIndex)
+/// 厳密等価 (===) な最初の要素のインデックスを返す。見つからない場合は -1 を返す。
+/// fromIndex が負の場合、配列の末尾からの位置とみなす。
+/// https://262.ecma-international.org/#sec-array.prototype.indexof
+fn array_index_of(
+    array: &RuntimeValue,
+    arguments: &[RuntimeValue],
+) -> Result<RuntimeValue, JsError> {
+    let elements = match array {
+        RuntimeValue::Array(elements) => elements.borrow(),
+        _ => return Err(not_an_array("indexOf")),
+    };
+    let search = arguments.first().unwrap_or(&RuntimeValue::Undefined);
+
+    let len = elements.len() as f64;
+    let from = match arguments.get(1) {
+        Some(value) => {
+            let n = value.to_number();
+            if n.is_nan() {
+                0f64
+            } else if n < 0f64 {
+                (len + (n as i64) as f64).max(0f64)
+            } else {
+                (n as i64) as f64
+            }
+        }
+        None => 0f64,
+    };
+    if from >= len {
+        return Ok(RuntimeValue::Number(-1f64));
+    }
+
+    for (i, element) in elements.iter().enumerate().skip(from as usize) {
+        if element.strict_equals(search) {
+            return Ok(RuntimeValue::Number(i as f64));
+        }
+    }
+    Ok(RuntimeValue::Number(-1f64))
+}
+
+/// Array.prototype.join(separator)
+/// 配列の要素を文字列に変換し、separator で連結する。separator が省略された場合は "," とする。
+/// https://262.ecma-international.org/#sec-array.prototype.join
+fn array_join(array: &RuntimeValue, arguments: &[RuntimeValue]) -> Result<RuntimeValue, JsError> {
+    let separator = match arguments.first() {
+        Some(RuntimeValue::Undefined) | None => ",".to_string(),
+        Some(value) => value.to_string(),
+    };
+    match array {
+        RuntimeValue::Array(elements) => Ok(RuntimeValue::StringLiteral(join_elements(
+            elements, &separator,
+        ))),
+        _ => Err(not_an_array("join")),
+    }
+}
+
+/// 文字列を数値に変換する。
+/// 文字列全体が数値として解釈できない場合、NaN を返す。
+/// https://262.ecma-international.org/#sec-stringtonumber
+pub fn string_to_number(input: &str) -> f64 {
+    let s = input.trim_matches(is_js_whitespace);
+
+    // 空文字列は 0 に変換される。
+    if s.is_empty() {
+        return 0f64;
+    }
+
+    // 2 進数、8 進数、16 進数の表記を解釈する。
+    for (prefix, radix) in [
+        ("0x", 16),
+        ("0X", 16),
+        ("0o", 8),
+        ("0O", 8),
+        ("0b", 2),
+        ("0B", 2),
+    ] {
+        if let Some(digits) = s.strip_prefix(prefix) {
+            if digits.is_empty() {
+                return f64::NAN;
+            }
+            let mut num = 0f64;
+            for c in digits.chars() {
+                match c.to_digit(radix) {
+                    Some(d) => num = num * radix as f64 + d as f64,
+                    None => return f64::NAN,
+                }
+            }
+            return num;
+        }
+    }
+
+    if let Some(infinity) = parse_infinity(s) {
+        if s.trim_start_matches(['+', '-']) == "Infinity" {
+            return infinity;
+        }
+        return f64::NAN;
+    }
+
+    // 文字列全体が 10 進数の表記でなければならない。
+    if decimal_literal_length(s) != s.len() {
+        return f64::NAN;
+    }
+    s.parse::<f64>().unwrap_or(f64::NAN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// parseInt の基本的な変換のテスト
+    #[test]
+    fn test_parse_int() {
+        let cases = [
+            ("42", 42f64),
+            ("  -17px", -17f64),
+            ("0x1f", 31f64),
+            ("3.99", 3f64),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                Ok(RuntimeValue::Number(expected)),
+                parse_int(&[RuntimeValue::StringLiteral(input.to_string())])
+            );
+        }
+    }
+
+    /// parseInt の基数指定のテスト
+    #[test]
+    fn test_parse_int_with_radix() {
+        let cases = [
+            ("ff", 16f64, 255f64),
+            ("0xff", 16f64, 255f64),
+            ("101", 2f64, 5f64),
+            ("z", 36f64, 35f64),
+            ("10", 0f64, 10f64),
+        ];
+        for (input, radix, expected) in cases {
+            assert_eq!(
+                Ok(RuntimeValue::Number(expected)),
+                parse_int(&[
+                    RuntimeValue::StringLiteral(input.to_string()),
+                    RuntimeValue::Number(radix),
+                ])
+            );
+        }
+    }
+
+    /// parseInt で数値として解釈できない場合、NaN を返すことを確認する。
+    #[test]
+    fn test_parse_int_nan() {
+        let not_a_number = parse_int(&[RuntimeValue::StringLiteral("abc".to_string())]).unwrap();
+        assert!(not_a_number.to_number().is_nan());
+
+        let invalid_radix = parse_int(&[
+            RuntimeValue::StringLiteral("10".to_string()),
+            RuntimeValue::Number(37f64),
+        ])
+        .unwrap();
+        assert!(invalid_radix.to_number().is_nan());
+    }
+
+    /// parseFloat のテスト
+    #[test]
+    fn test_parse_float() {
+        let cases = [
+            ("2.75", 2.75f64),
+            ("  1.5em", 1.5f64),
+            (".5", 0.5f64),
+            ("-2e3", -2000f64),
+            ("1e", 1f64),
+            ("Infinity", f64::INFINITY),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                Ok(RuntimeValue::Number(expected)),
+                parse_float(&[RuntimeValue::StringLiteral(input.to_string())])
+            );
+        }
+        assert!(
+            parse_float(&[RuntimeValue::StringLiteral("px".to_string())])
+                .unwrap()
+                .to_number()
+                .is_nan()
+        );
+    }
+
+    /// Number による変換のテスト
+    /// 文字列全体が数値でない場合、NaN となることを確認する。
+    #[test]
+    fn test_number() {
+        assert_eq!(Ok(RuntimeValue::Number(0f64)), number(&[]));
+        assert_eq!(
+            Ok(RuntimeValue::Number(12.5f64)),
+            number(&[RuntimeValue::StringLiteral(" 12.5 ".to_string())])
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(0f64)),
+            number(&[RuntimeValue::StringLiteral("".to_string())])
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(255f64)),
+            number(&[RuntimeValue::StringLiteral("0xff".to_string())])
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(7f64)),
+            number(&[RuntimeValue::Number(7f64)])
+        );
+        assert!(number(&[RuntimeValue::StringLiteral("12px".to_string())])
+            .unwrap()
+            .to_number()
+            .is_nan());
+    }
+
+    /// JSON.parse に不正な JSON を渡した場合、SyntaxError となることを確認する。
+    #[test]
+    fn test_json_parse_error() {
+        let result = json_parse(&[RuntimeValue::StringLiteral("{".to_string())]);
+        assert!(matches!(result, Err(JsError::SyntaxError(_))));
+    }
+
+    /// エラーのコンストラクタが name と message のプロパティを持つオブジェクトを返すことを確認する。
+    #[test]
+    fn test_error_constructors() {
+        let e = type_error(&[RuntimeValue::StringLiteral("bad".to_string())]).unwrap();
+        assert_eq!(
+            RuntimeValue::StringLiteral("TypeError".to_string()),
+            e.get_property("name")
+        );
+        assert_eq!(
+            RuntimeValue::StringLiteral("bad".to_string()),
+            e.get_property("message")
+        );
+
+        let e = error(&[]).unwrap();
+        assert_eq!(
+            RuntimeValue::StringLiteral("".to_string()),
+            e.get_property("message")
+        );
+    }
+
+    /// indexOf の検索開始位置の指定と、厳密等価による比較を確認する。
+    #[test]
+    fn test_array_index_of() {
+        let array = RuntimeValue::new_array(
+            [
+                RuntimeValue::Number(1f64),
+                RuntimeValue::StringLiteral("1".to_string()),
+                RuntimeValue::Number(1f64),
+            ]
+            .to_vec(),
+        );
+        let one = RuntimeValue::Number(1f64);
+        assert_eq!(
+            Ok(RuntimeValue::Number(0f64)),
+            array_index_of(&array, &[one.clone()])
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(1f64)),
+            array_index_of(&array, &[RuntimeValue::StringLiteral("1".to_string())])
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(2f64)),
+            array_index_of(&array, &[one.clone(), RuntimeValue::Number(1f64)])
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(2f64)),
+            array_index_of(&array, &[one.clone(), RuntimeValue::Number(-1f64)])
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(-1f64)),
+            array_index_of(&array, &[one, RuntimeValue::Number(5f64)])
+        );
+    }
+}
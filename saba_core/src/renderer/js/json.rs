@@ -0,0 +1,511 @@
+use crate::error::Error;
+use crate::renderer::js::runtime::number_to_string;
+use crate::renderer::js::runtime::PropertyMap;
+use crate::renderer::js::runtime::RuntimeValue;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// 値を JSON 文字列に変換する。
+/// 値が undefined の場合、JSON として表現できないため None を返す。
+/// 配列やオブジェクトが循環参照を含む場合、エラーを返す。
+/// https://262.ecma-international.org/#sec-json.stringify
+pub fn stringify(value: &RuntimeValue) -> Result<Option<String>, Error> {
+    let mut stack = Vec::new();
+    serialize_value(value, &mut stack)
+}
+
+/// 値を JSON 文字列に変換する。
+/// stack には変換中の配列とオブジェクトのポインタを保持し、循環参照の検出に使う。
+/// https://262.ecma-international.org/#sec-serializejsonproperty
+fn serialize_value(
+    value: &RuntimeValue,
+    stack: &mut Vec<*const ()>,
+) -> Result<Option<String>, Error> {
+    let s = match value {
+        RuntimeValue::Null => "null".to_string(),
+        RuntimeValue::Undefined => return Ok(None),
+        RuntimeValue::Boolean(b) => b.to_string(),
+        // NaN や Infinity は JSON で表現できないため null とする。
+        RuntimeValue::Number(num) => {
+            if num.is_finite() {
+                number_to_string(*num)
+            } else {
+                "null".to_string()
+            }
+        }
+        RuntimeValue::StringLiteral(s) => quote(s),
+        RuntimeValue::Array(elements) => {
+            let ptr = elements.as_ptr() as *const ();
+            enter(ptr, stack)?;
+
+            let mut items = Vec::new();
+            for element in elements.borrow().iter() {
+                // undefined の要素は null として出力する。
+                match serialize_value(element, stack)? {
+                    Some(item) => items.push(item),
+                    None => items.push("null".to_string()),
+                }
+            }
+
+            stack.pop();
+            format!("[{}]", items.join(","))
+        }
+        RuntimeValue::Object(properties) => {
+            let ptr = properties.as_ptr() as *const ();
+            enter(ptr, stack)?;
+
+            let mut items = Vec::new();
+            for (key, value) in properties.borrow().iter() {
+                // undefined のプロパティは出力しない。
+                if let Some(item) = serialize_value(value, stack)? {
+                    items.push(format!("{}:{}", quote(key), item));
+                }
+            }
+
+            stack.pop();
+            format!("{{{}}}", items.join(","))
+        }
+        // DOM ノードは列挙可能なプロパティを持たない空のオブジェクトとして扱う。
+        RuntimeValue::HtmlElement { .. } => "{}".to_string(),
+    };
+    Ok(Some(s))
+}
+
+/// 変換中の配列またはオブジェクトを stack に積む。
+/// 既に stack に積まれている場合、循環参照としてエラーを返す。
+fn enter(ptr: *const (), stack: &mut Vec<*const ()>) -> Result<(), Error> {
+    if stack.contains(&ptr) {
+        return Err(Error::UnexpectedInput(
+            "converting circular structure to JSON".to_string(),
+        ));
+    }
+    stack.push(ptr);
+    Ok(())
+}
+
+/// 文字列をダブルクォートで囲み、JSON のエスケープシーケンスに変換する。
+/// https://262.ecma-international.org/#sec-quotejsonstring
+fn quote(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\u{08}' => result.push_str("\\b"),
+            '\u{0c}' => result.push_str("\\f"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// JSON 文字列を解釈し、値を構築する。
+/// https://262.ecma-international.org/#sec-json.parse
+pub fn parse(text: &str) -> Result<RuntimeValue, Error> {
+    let mut parser = JsonParser::new(text);
+    let value = parser.parse_value()?;
+
+    // 値の後ろに空白以外の文字が続く場合はエラーとする。
+    parser.skip_whitespace();
+    if parser.pos < parser.input.len() {
+        return Err(parser.unexpected());
+    }
+    Ok(value)
+}
+
+/// JSON の構文解析を行う JsonParser 構造体
+/// 読み込んだ位置を保持する pos, 入力文字列を保持する input を持つ。
+/// https://www.rfc-editor.org/rfc/rfc8259
+struct JsonParser {
+    pos: usize,
+    input: Vec<char>,
+}
+
+impl JsonParser {
+    fn new(text: &str) -> Self {
+        Self {
+            pos: 0,
+            input: text.chars().collect(),
+        }
+    }
+
+    /// 現在の位置の文字が想定外であることを表すエラーを返す。
+    fn unexpected(&self) -> Error {
+        match self.input.get(self.pos) {
+            Some(c) => Error::UnexpectedInput(format!(
+                "unexpected character {:?} in JSON at position {}",
+                c, self.pos
+            )),
+            None => Error::UnexpectedInput("unexpected end of JSON input".to_string()),
+        }
+    }
+
+    /// JSON の空白文字を読み飛ばす。
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len()
+            && matches!(self.input[self.pos], ' ' | '\t' | '\n' | '\r')
+        {
+            self.pos += 1;
+        }
+    }
+
+    /// 現在の位置の文字が c と一致する場合、その文字を消費する。
+    fn consume_char(&mut self, c: char) -> Result<(), Error> {
+        if self.input.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            return Ok(());
+        }
+        Err(self.unexpected())
+    }
+
+    /// 現在の位置から始まる文字列が word と一致する場合、その文字列を消費する。
+    fn consume_word(&mut self, word: &str) -> Result<(), Error> {
+        for c in word.chars() {
+            self.consume_char(c)?;
+        }
+        Ok(())
+    }
+
+    /// value ::= object | array | string | number | "true" | "false" | "null"
+    fn parse_value(&mut self) -> Result<RuntimeValue, Error> {
+        self.skip_whitespace();
+
+        match self.input.get(self.pos) {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => Ok(RuntimeValue::StringLiteral(self.parse_string()?)),
+            Some('t') => {
+                self.consume_word("true")?;
+                Ok(RuntimeValue::Boolean(true))
+            }
+            Some('f') => {
+                self.consume_word("false")?;
+                Ok(RuntimeValue::Boolean(false))
+            }
+            Some('n') => {
+                self.consume_word("null")?;
+                Ok(RuntimeValue::Null)
+            }
+            Some('-') | Some('0'..='9') => self.parse_number(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// object ::= "{" ( string ":" value ( "," string ":" value )* )? "}"
+    fn parse_object(&mut self) -> Result<RuntimeValue, Error> {
+        self.consume_char('{')?;
+        let mut properties = PropertyMap::new();
+
+        self.skip_whitespace();
+        if self.consume_char('}').is_ok() {
+            return Ok(RuntimeValue::new_object(properties));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.consume_char(':')?;
+            let value = self.parse_value()?;
+
+            // 同じキーが複数回現れた場合、後の値で上書きする。
+            match properties.iter_mut().find(|(k, _)| *k == key) {
+                Some(property) => property.1 = value,
+                None => properties.push((key, value)),
+            }
+
+            self.skip_whitespace();
+            if self.consume_char(',').is_ok() {
+                continue;
+            }
+            self.consume_char('}')?;
+            return Ok(RuntimeValue::new_object(properties));
+        }
+    }
+
+    /// array ::= "[" ( value ( "," value )* )? "]"
+    fn parse_array(&mut self) -> Result<RuntimeValue, Error> {
+        self.consume_char('[')?;
+        let mut elements = Vec::new();
+
+        self.skip_whitespace();
+        if self.consume_char(']').is_ok() {
+            return Ok(RuntimeValue::new_array(elements));
+        }
+
+        loop {
+            elements.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            if self.consume_char(',').is_ok() {
+                continue;
+            }
+            self.consume_char(']')?;
+            return Ok(RuntimeValue::new_array(elements));
+        }
+    }
+
+    /// string ::= '"' ( <unescaped char> | "\" <escape> )* '"'
+    fn parse_string(&mut self) -> Result<String, Error> {
+        self.consume_char('"')?;
+        let mut result = String::new();
+
+        loop {
+            let c = match self.input.get(self.pos) {
+                Some(c) => *c,
+                None => return Err(self.unexpected()),
+            };
+
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(result);
+                }
+                '\\' => {
+                    self.pos += 1;
+                    let escaped = match self.input.get(self.pos) {
+                        Some(c) => *c,
+                        None => return Err(self.unexpected()),
+                    };
+                    match escaped {
+                        '"' | '\\' | '/' => result.push(escaped),
+                        'b' => result.push('\u{08}'),
+                        'f' => result.push('\u{0c}'),
+                        'n' => result.push('\n'),
+                        'r' => result.push('\r'),
+                        't' => result.push('\t'),
+                        'u' => {
+                            self.pos += 1;
+                            result.push(self.parse_unicode_escape()?);
+                            continue;
+                        }
+                        _ => return Err(self.unexpected()),
+                    }
+                    self.pos += 1;
+                }
+                // 制御文字はエスケープせずに含めることはできない。
+                c if (c as u32) < 0x20 => return Err(self.unexpected()),
+                c => {
+                    result.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// 4 桁の 16 進数を読み込む。
+    fn parse_hex4(&mut self) -> Result<u32, Error> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.input.get(self.pos).and_then(|c| c.to_digit(16)) {
+                Some(d) => code = code * 16 + d,
+                None => return Err(self.unexpected()),
+            }
+            self.pos += 1;
+        }
+        Ok(code)
+    }
+
+    /// \u の後に続く 16 進数を文字に変換する。サロゲートペアは 1 つの文字に結合する。
+    /// 対になっていないサロゲートは置換文字 (U+FFFD) とする。
+    fn parse_unicode_escape(&mut self) -> Result<char, Error> {
+        let high = self.parse_hex4()?;
+        if (0xd800..0xdc00).contains(&high)
+            && self.input.get(self.pos) == Some(&'\\')
+            && self.input.get(self.pos + 1) == Some(&'u')
+        {
+            let saved = self.pos;
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+            if (0xdc00..0xe000).contains(&low) {
+                let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                return Ok(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            self.pos = saved;
+        }
+        Ok(char::from_u32(high).unwrap_or('\u{fffd}'))
+    }
+
+    /// number ::= "-"? ( "0" | [1-9] [0-9]* ) ( "." [0-9]+ )? ( ( "e" | "E" ) ( "+" | "-" )? [0-9]+ )?
+    fn parse_number(&mut self) -> Result<RuntimeValue, Error> {
+        let start = self.pos;
+
+        if self.input.get(self.pos) == Some(&'-') {
+            self.pos += 1;
+        }
+
+        // 整数部を読み込む。先頭が 0 の場合、後に数字を続けることはできない。
+        match self.input.get(self.pos) {
+            Some('0') => self.pos += 1,
+            Some('1'..='9') => self.consume_digits(),
+            _ => return Err(self.unexpected()),
+        }
+
+        // 小数部を読み込む。
+        if self.input.get(self.pos) == Some(&'.') {
+            self.pos += 1;
+            if !self.input.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.unexpected());
+            }
+            self.consume_digits();
+        }
+
+        // 指数部を読み込む。
+        if matches!(self.input.get(self.pos), Some('e') | Some('E')) {
+            self.pos += 1;
+            if matches!(self.input.get(self.pos), Some('+') | Some('-')) {
+                self.pos += 1;
+            }
+            if !self.input.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.unexpected());
+            }
+            self.consume_digits();
+        }
+
+        let s: String = self.input[start..self.pos].iter().collect();
+        match s.parse::<f64>() {
+            Ok(num) => Ok(RuntimeValue::Number(num)),
+            Err(_) => Err(Error::UnexpectedInput(format!(
+                "invalid number {} in JSON",
+                s
+            ))),
+        }
+    }
+
+    /// 数字が続く限り、文字を消費する。
+    fn consume_digits(&mut self) {
+        while self.input.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// プリミティブな値の変換のテスト
+    #[test]
+    fn test_stringify_primitive() {
+        let cases = [
+            (RuntimeValue::Null, "null"),
+            (RuntimeValue::Boolean(true), "true"),
+            (RuntimeValue::Number(1.5f64), "1.5"),
+            (RuntimeValue::Number(f64::NAN), "null"),
+            (
+                RuntimeValue::StringLiteral("a\"b\n".to_string()),
+                "\"a\\\"b\\n\"",
+            ),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(Ok(Some(expected.to_string())), stringify(&value));
+        }
+        assert_eq!(Ok(None), stringify(&RuntimeValue::Undefined));
+    }
+
+    /// 配列とオブジェクトの変換のテスト
+    /// 配列の undefined は null となり、オブジェクトの undefined のプロパティは出力されないことを確認する。
+    #[test]
+    fn test_stringify_array_and_object() {
+        let value = RuntimeValue::new_object(vec![
+            ("a".to_string(), RuntimeValue::Number(1f64)),
+            ("b".to_string(), RuntimeValue::Undefined),
+            (
+                "c".to_string(),
+                RuntimeValue::new_array(vec![
+                    RuntimeValue::Boolean(false),
+                    RuntimeValue::Undefined,
+                    RuntimeValue::StringLiteral("x".to_string()),
+                ]),
+            ),
+        ]);
+        assert_eq!(
+            Ok(Some("{\"a\":1,\"c\":[false,null,\"x\"]}".to_string())),
+            stringify(&value)
+        );
+    }
+
+    /// 循環参照を含む値を変換するとエラーになることを確認する。
+    #[test]
+    fn test_stringify_circular() {
+        let array = RuntimeValue::new_array(Vec::new());
+        if let RuntimeValue::Array(elements) = &array {
+            elements.borrow_mut().push(array.clone());
+        }
+        assert!(stringify(&array).is_err());
+
+        // 同じ配列を複数回参照するだけであれば循環参照ではない。
+        let shared = RuntimeValue::new_array(vec![RuntimeValue::Number(1f64)]);
+        let value = RuntimeValue::new_array(vec![shared.clone(), shared]);
+        assert_eq!(Ok(Some("[[1],[1]]".to_string())), stringify(&value));
+    }
+
+    /// JSON 文字列の解釈のテスト
+    #[test]
+    fn test_parse() {
+        let value = parse(" {\"a\": [1, -2.5e1, true, null], \"b\": {\"c\": \"\\u3042\\n\"}} ");
+        let expected = RuntimeValue::new_object(vec![
+            (
+                "a".to_string(),
+                RuntimeValue::new_array(vec![
+                    RuntimeValue::Number(1f64),
+                    RuntimeValue::Number(-25f64),
+                    RuntimeValue::Boolean(true),
+                    RuntimeValue::Null,
+                ]),
+            ),
+            (
+                "b".to_string(),
+                RuntimeValue::new_object(vec![(
+                    "c".to_string(),
+                    RuntimeValue::StringLiteral("あ\n".to_string()),
+                )]),
+            ),
+        ]);
+        assert_eq!(Ok(expected), value);
+    }
+
+    /// サロゲートペアのエスケープシーケンスのテスト
+    #[test]
+    fn test_parse_surrogate_pair() {
+        assert_eq!(
+            Ok(RuntimeValue::StringLiteral("😀".to_string())),
+            parse("\"\\ud83d\\ude00\"")
+        );
+    }
+
+    /// 不正な JSON 文字列はエラーになることを確認する。
+    #[test]
+    fn test_parse_invalid() {
+        for input in [
+            "",
+            "{",
+            "[1,]",
+            "{'a': 1}",
+            "01",
+            "1.",
+            "tru",
+            "\"abc",
+            "1 2",
+            "{\"a\" 1}",
+        ] {
+            assert!(parse(input).is_err(), "{:?} should be invalid", input);
+        }
+    }
+
+    /// stringify と parse の往復で同じ値になることを確認する。
+    #[test]
+    fn test_round_trip() {
+        let text = "{\"name\":\"saba\",\"tags\":[\"a\",\"b\"],\"nested\":{\"n\":0.5,\"ok\":false}}";
+        let value = parse(text).expect("failed to parse JSON");
+        assert_eq!(Ok(Some(text.to_string())), stringify(&value));
+    }
+}
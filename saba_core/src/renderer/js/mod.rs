@@ -1,3 +1,6 @@
 pub mod ast;
+pub mod builtin;
+pub mod json;
 pub mod runtime;
+pub mod time;
 pub mod token;
@@ -1,23 +1,46 @@
 use crate::renderer::dom::api::get_element_by_id;
+use crate::renderer::dom::event::dispatch_event;
+use crate::renderer::dom::event::Event;
+use crate::renderer::dom::event::EventCallback;
+use crate::renderer::dom::event::EventListener;
 use crate::renderer::dom::node::Node as DomNode;
-use crate::renderer::dom::node::NodeKind as DomNodeKind;
+use crate::renderer::js::ast::JsParser;
 use crate::renderer::js::ast::Node;
 use crate::renderer::js::ast::Program;
+use crate::renderer::js::builtin::find_array_method;
+use crate::renderer::js::builtin::find_builtin_function;
+use crate::renderer::js::builtin::string_to_number;
+use crate::renderer::js::builtin::BuiltinFunction;
+use crate::renderer::js::time::MonotonicClock;
+use crate::renderer::js::time::TimeSource;
+use crate::renderer::js::token::JsLexer;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::cell::RefCell;
+use core::cmp::Ordering;
 use core::fmt::Display;
 use core::fmt::Formatter;
 use core::ops::Add;
+use core::ops::Div;
+use core::ops::Mul;
 use core::ops::Sub;
 
 /// 変数の名前と値のタプルを表す。
 type VariableMap = Vec<(String, Option<RuntimeValue>)>;
 
+/// オブジェクトのプロパティの名前と値のタプルを表す。
+/// プロパティは追加された順序を保持する。
+pub type PropertyMap = Vec<(String, RuntimeValue)>;
+
+/// 配列の長さを超えるインデックスに代入する場合に、間を埋める undefined の要素の最大の数。
+/// 配列は要素を連続して保持するため、大きなインデックスへの代入でメモリを使い果たさないように制限する。
+const MAX_ARRAY_GAP: usize = 65536;
+
 /// JavaScript の変数のスコープ管理を行うための構造体
 /// https://262.ecma-international.org/#sec-environment-records
 #[derive(Debug, Clone)]
@@ -54,12 +77,30 @@ impl Environment {
 
     /// 変数の追加
     /// 現在のスコープに新しい変数を追加する。
+    /// 同じ名前の変数が現在のスコープに既に存在する場合 (ループ内の var など)、その値を更新する。
     fn add_variable(&mut self, name: String, value: Option<RuntimeValue>) {
+        for variable in self.variables.iter_mut() {
+            if variable.0 == name {
+                variable.1 = value;
+                return;
+            }
+        }
         self.variables.push((name, value));
     }
 
+    /// 変数の宣言
+    /// 現在のスコープに同じ名前の変数が存在しない場合のみ、値が undefined の変数を追加する。
+    /// 既に存在する変数の値は変更しない。
+    fn declare_variable(&mut self, name: String) {
+        if self.variables.iter().any(|variable| variable.0 == name) {
+            return;
+        }
+        self.variables.push((name, Some(RuntimeValue::Undefined)));
+    }
+
     /// 変数の更新
     /// 現在のスコープに存在する変数の値を更新する。
+    /// 現在のスコープに存在しない場合、外部のスコープの変数を更新する。
     fn update_variable(&mut self, name: String, value: Option<RuntimeValue>) {
         for i in 0..self.variables.len() {
             // 変数を見つけた場合、現在の変数を削除し、更新後の変数を追加する。
@@ -69,6 +110,10 @@ impl Environment {
                 return;
             }
         }
+
+        if let Some(env) = &self.outer {
+            env.borrow_mut().update_variable(name, value);
+        }
     }
 }
 
@@ -85,12 +130,97 @@ impl Function {
     }
 }
 
+/// setTimeout によって登録されたタイマー
+/// https://html.spec.whatwg.org/multipage/timers-and-user-prompts.html#timers
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Timer {
+    id: u64,
+    due: u64,         // コールバックを実行する時刻 (ミリ秒)
+    callback: String, // コールバックとして呼ぶ関数名
+}
+
+/// ランタイムが送出する組み込みのエラー
+/// 送出されたエラーは name と message のプロパティを持つオブジェクトに変換され、try 文の catch 節で捕捉できる。
+/// https://262.ecma-international.org/#sec-native-error-types-used-in-this-standard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsError {
+    /// https://262.ecma-international.org/#sec-native-error-types-used-in-this-standard-typeerror
+    TypeError(String),
+    /// https://262.ecma-international.org/#sec-native-error-types-used-in-this-standard-referenceerror
+    ReferenceError(String),
+    /// https://262.ecma-international.org/#sec-native-error-types-used-in-this-standard-syntaxerror
+    SyntaxError(String),
+    /// https://262.ecma-international.org/#sec-native-error-types-used-in-this-standard-rangeerror
+    RangeError(String),
+}
+
+impl JsError {
+    /// エラーの種類を表す名前を返す。
+    pub fn name(&self) -> &str {
+        match self {
+            JsError::TypeError(_) => "TypeError",
+            JsError::ReferenceError(_) => "ReferenceError",
+            JsError::SyntaxError(_) => "SyntaxError",
+            JsError::RangeError(_) => "RangeError",
+        }
+    }
+
+    /// エラーメッセージを返す。
+    pub fn message(&self) -> &str {
+        match self {
+            JsError::TypeError(message)
+            | JsError::ReferenceError(message)
+            | JsError::SyntaxError(message)
+            | JsError::RangeError(message) => message,
+        }
+    }
+
+    /// throw 文で送出される値に変換する。
+    pub fn to_value(&self) -> RuntimeValue {
+        RuntimeValue::new_error(self.name(), self.message())
+    }
+}
+
+/// 正常終了以外の評価結果 (abrupt completion) を表す。
+/// https://262.ecma-international.org/#sec-completion-record-specification-type
+#[derive(Debug, Clone, PartialEq)]
+enum AbruptCompletion {
+    /// return 文による関数の終了。戻り値を持つ。
+    Return(Option<RuntimeValue>),
+    /// break 文による switch 文やループの終了
+    Break,
+    /// continue 文によるループの現在の繰り返しの終了
+    Continue,
+    /// throw 文やランタイムのエラーによる例外の送出。送出された値を持つ。
+    Throw(RuntimeValue),
+}
+
+impl From<JsError> for AbruptCompletion {
+    fn from(error: JsError) -> Self {
+        AbruptCompletion::Throw(error.to_value())
+    }
+}
+
+/// 文や式の評価結果を表す completion record
+/// 正常終了 (normal completion) は評価した値を Ok で、それ以外は AbruptCompletion を Err で表す。
+/// Err を ? 演算子で伝播させることで、return 文などによって評価を中断できる。
+type Completion = Result<Option<RuntimeValue>, AbruptCompletion>;
+
 /// ランタイム用の構造体
 #[derive(Debug, Clone)]
 pub struct JsRuntime {
     dom_root: Rc<RefCell<DomNode>>,
     env: Rc<RefCell<Environment>>,
     functions: Vec<Function>,
+    clock: MonotonicClock,
+    timers: Vec<Timer>,
+    next_timer_id: u64,
+    scroll_position: (i64, i64),
+    scroll_request: Option<(i64, i64)>,
+    // イベントリスナーの中で preventDefault が呼ばれたかどうか
+    default_prevented: bool,
+    // イベントリスナーの中で stopPropagation が呼ばれたかどうか
+    propagation_stopped: bool,
 }
 
 impl JsRuntime {
@@ -99,43 +229,256 @@ impl JsRuntime {
             dom_root,
             functions: Vec::new(),
             env: Rc::new(RefCell::new(Environment::new(None))),
+            clock: MonotonicClock::default(),
+            timers: Vec::new(),
+            next_timer_id: 1,
+            scroll_position: (0, 0),
+            scroll_request: None,
+            default_prevented: false,
+            propagation_stopped: false,
+        }
+    }
+
+    /// Date.now や setTimeout が参照する時刻の取得元を設定する。
+    pub fn set_time_source(&mut self, source: Rc<dyn TimeSource>) {
+        self.clock = MonotonicClock::new(source);
+    }
+
+    /// window.scrollX と window.scrollY が返すスクロール位置を設定する。
+    /// ユーザの操作などによってページがスクロールされた場合に呼ぶ。
+    pub fn set_scroll_position(&mut self, x: i64, y: i64) {
+        self.scroll_position = (x, y);
+    }
+
+    /// window.scrollTo によって要求されたスクロール位置を返し、要求を取り除く。
+    /// 前回の呼び出し以降にスクロールが要求されていない場合、None を返す。
+    pub fn take_scroll_request(&mut self) -> Option<(i64, i64)> {
+        self.scroll_request.take()
+    }
+
+    /// 実行待ちのタイマーが存在するかどうか。
+    pub fn has_pending_timers(&self) -> bool {
+        !self.timers.is_empty()
+    }
+
+    /// 実行時刻に達したタイマーのコールバックを、実行時刻の早い順に呼ぶ。
+    /// コールバックの中で登録されたタイマーは、次の呼び出しまで実行しない。
+    /// いずれかのコールバックを呼んだ場合、true を返す。
+    pub fn run_expired_timers(&mut self) -> bool {
+        let last_id = self.next_timer_id;
+        let mut ran = false;
+
+        loop {
+            let now = self.clock.now();
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter(|(_, timer)| timer.due <= now && timer.id < last_id)
+                .min_by_key(|(_, timer)| (timer.due, timer.id))
+                .map(|(i, _)| i);
+
+            let timer = match next {
+                Some(i) => self.timers.remove(i),
+                None => return ran,
+            };
+            self.call_function_by_name(&timer.callback, &[]);
+            ran = true;
+        }
+    }
+
+    /// target にイベントを送出し、addEventListener で登録されたリスナーとイベントハンドラ属性を呼ぶ。
+    /// 戻り値は既定の動作を行うべきかどうか (preventDefault が呼ばれていないかどうか) を表す。
+    /// https://dom.spec.whatwg.org/#concept-event-dispatch
+    pub fn dispatch_event(&mut self, target: &Rc<RefCell<DomNode>>, event: &mut Event) -> bool {
+        dispatch_event(target, event, &mut |listener, event| {
+            self.invoke_event_listener(listener, event)
+        })
+    }
+
+    /// イベントリスナーを呼ぶ。リスナーには type、target、currentTarget のプロパティを持つイベントオブジェクトを渡す。
+    /// イベントハンドラ属性のコードが false を返した場合、既定の動作を取り消す。
+    /// https://html.spec.whatwg.org/multipage/webappapis.html#the-event-handler-processing-algorithm
+    fn invoke_event_listener(&mut self, listener: &EventListener, event: &mut Event) {
+        let node_value = |node: Option<Rc<RefCell<DomNode>>>| match node {
+            Some(object) => RuntimeValue::HtmlElement {
+                object,
+                property: None,
+            },
+            None => RuntimeValue::Null,
+        };
+        let event_object = RuntimeValue::new_object(vec![
+            (
+                "type".to_string(),
+                RuntimeValue::StringLiteral(event.event_type()),
+            ),
+            ("target".to_string(), node_value(event.target())),
+            (
+                "currentTarget".to_string(),
+                node_value(event.current_target()),
+            ),
+            (
+                "preventDefault".to_string(),
+                RuntimeValue::StringLiteral("event.preventDefault".to_string()),
+            ),
+            (
+                "stopPropagation".to_string(),
+                RuntimeValue::StringLiteral("event.stopPropagation".to_string()),
+            ),
+        ]);
+
+        self.default_prevented = false;
+        self.propagation_stopped = false;
+        let result = match listener.callback() {
+            EventCallback::Function(name) => self.call_function_by_name(&name, &[event_object]),
+            EventCallback::Inline(source) => self.run_event_handler(&source, event_object),
+        };
+
+        if self.default_prevented || result == Some(RuntimeValue::Boolean(false)) {
+            event.prevent_default();
+        }
+        if self.propagation_stopped {
+            event.stop_propagation();
+        }
+    }
+
+    /// イベントハンドラ属性に書かれたコードを、event 変数からイベントオブジェクトを参照できるスコープで実行する。
+    /// return 文で返された値を返す。構文エラーの場合や、捕捉されない例外が送出された場合は None を返す。
+    fn run_event_handler(
+        &mut self,
+        source: &str,
+        event_object: RuntimeValue,
+    ) -> Option<RuntimeValue> {
+        let mut parser = JsParser::new(JsLexer::new(source.to_string()));
+        let program = match parser.parse_ast() {
+            Ok(program) => program,
+            Err(_) => return None,
+        };
+
+        let env = Rc::new(RefCell::new(Environment::new(Some(self.env.clone()))));
+        env.borrow_mut()
+            .add_variable("event".to_string(), Some(event_object));
+        for node in program.body() {
+            self.hoist_declarations(&Some(node.clone()), env.clone());
+        }
+        for node in program.body() {
+            match self.evaluate(&Some(node.clone()), env.clone()) {
+                Ok(_) => {}
+                Err(AbruptCompletion::Return(value)) => return value,
+                Err(_) => return None,
+            }
+        }
+        None
+    }
+
+    /// ユーザが定義した関数を、評価済みの引数とともに呼ぶ。
+    /// 関数が見つからない場合や、関数の中で捕捉されない例外が送出された場合は何もしない。
+    fn call_function_by_name(
+        &mut self,
+        name: &str,
+        arguments: &[RuntimeValue],
+    ) -> Option<RuntimeValue> {
+        let function = self
+            .functions
+            .iter()
+            .rev()
+            .find(|func| func.id == name)?
+            .clone();
+
+        let new_env = Rc::new(RefCell::new(Environment::new(Some(self.env.clone()))));
+        self.call_user_function(&function, arguments, new_env)
+            .ok()
+            .flatten()
+    }
+
+    /// ユーザが定義した関数を呼ぶ。
+    /// 関数のスコープ env に引数を仮引数として割り当ててから、関数の本体を評価する。
+    /// 引数が仮引数より少ない場合、残りの仮引数は undefined とする。
+    fn call_user_function(
+        &mut self,
+        function: &Function,
+        arguments: &[RuntimeValue],
+        env: Rc<RefCell<Environment>>,
+    ) -> Completion {
+        for (i, param) in function.params.iter().enumerate() {
+            if let Some(node) = param {
+                if let Node::Identifier(param_name) = node.borrow() {
+                    let value = arguments.get(i).cloned().unwrap_or(RuntimeValue::Undefined);
+                    env.borrow_mut()
+                        .add_variable(param_name.to_string(), Some(value));
+                }
+            }
+        }
+        self.hoist_declarations(&function.body, env.clone());
+        Self::function_result(self.evaluate(&function.body, env))
+    }
+
+    /// 関数の本体の評価結果を、関数呼び出しの結果に変換する。
+    /// return 文による終了の場合は、その戻り値を関数の戻り値とする。
+    /// 例外の送出は関数の呼び出し元に伝播する。
+    fn function_result(completion: Completion) -> Completion {
+        match completion {
+            Ok(value) => Ok(value),
+            Err(AbruptCompletion::Return(value)) => Ok(value),
+            Err(AbruptCompletion::Break) | Err(AbruptCompletion::Continue) => Ok(None),
+            Err(AbruptCompletion::Throw(value)) => Err(AbruptCompletion::Throw(value)),
         }
     }
 
-    /// program の body に含まれるノードを eval メソッドによってすべて評価する。
+    /// program の body に含まれるノードを順に評価する。
+    /// 評価の前に、関数宣言と var による変数宣言を巻き上げる。
+    /// 捕捉されない例外が送出された場合、残りのノードは評価しない。
     pub fn execute(&mut self, program: &Program) {
         for node in program.body() {
-            self.eval(&Some(node.clone()), self.env.clone());
+            self.hoist_declarations(&Some(node.clone()), self.env.clone());
+        }
+        for node in program.body() {
+            if let Err(AbruptCompletion::Throw(_)) =
+                self.evaluate(&Some(node.clone()), self.env.clone())
+            {
+                return;
+            }
         }
     }
 
-    /// ノードの種類に応じて処理する。
+    /// ノードを評価し、その値を返す。
+    /// return 文や break 文によって評価が中断された場合も、それまでに得られた値を返す。
+    /// 捕捉されない例外が送出された場合は None を返す。
+    #[cfg(test)]
     fn eval(
         &mut self,
         node: &Option<Rc<Node>>,
         env: Rc<RefCell<Environment>>,
     ) -> Option<RuntimeValue> {
+        Self::function_result(self.evaluate(node, env))
+            .ok()
+            .flatten()
+    }
+
+    /// ノードの種類に応じて処理する。
+    /// return 文や break 文による評価の中断は Err として呼び出し元に伝播する。
+    fn evaluate(&mut self, node: &Option<Rc<Node>>, env: Rc<RefCell<Environment>>) -> Completion {
         let node = match node {
             Some(n) => n,
-            None => return None,
+            None => return Ok(None),
         };
 
-        match node.borrow() {
+        let value = match node.borrow() {
             // ExpressionStatement の場合、再帰呼び出し
-            Node::ExpressionStatement(expr) => return self.eval(&expr, env.clone()),
+            Node::ExpressionStatement(expr) => return self.evaluate(expr, env.clone()),
             // 加算または減算
             Node::AdditiveExpression {
                 operator,
                 left,
                 right,
             } => {
-                let left_value = match self.eval(&left, env.clone()) {
+                let left_value = match self.evaluate(left, env.clone())? {
                     Some(value) => value,
-                    None => return None,
+                    None => return Ok(None),
                 };
-                let right_value = match self.eval(&right, env.clone()) {
+                let right_value = match self.evaluate(right, env.clone())? {
                     Some(value) => value,
-                    None => return None,
+                    None => return Ok(None),
                 };
 
                 if operator == &'+' {
@@ -146,82 +489,209 @@ impl JsRuntime {
                     None
                 }
             }
+            Node::EqualityExpression {
+                operator,
+                left,
+                right,
+            } => {
+                let left_value = self
+                    .evaluate(left, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+                let right_value = self
+                    .evaluate(right, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+
+                let result = match operator.as_str() {
+                    "==" => left_value.loose_equals(&right_value),
+                    "!=" => !left_value.loose_equals(&right_value),
+                    "===" => left_value.strict_equals(&right_value),
+                    "!==" => !left_value.strict_equals(&right_value),
+                    _ => return Ok(None),
+                };
+                Some(RuntimeValue::Boolean(result))
+            }
+            Node::RelationalExpression {
+                operator,
+                left,
+                right,
+            } => {
+                let left_value = self
+                    .evaluate(left, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+                let right_value = self
+                    .evaluate(right, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+
+                // 比較できない場合 (NaN を含む場合) は常に false となる。
+                let ordering = left_value.compare(&right_value);
+                let result = match operator.as_str() {
+                    "<" => ordering == Some(Ordering::Less),
+                    ">" => ordering == Some(Ordering::Greater),
+                    "<=" => matches!(ordering, Some(Ordering::Less) | Some(Ordering::Equal)),
+                    ">=" => matches!(ordering, Some(Ordering::Greater) | Some(Ordering::Equal)),
+                    _ => return Ok(None),
+                };
+                Some(RuntimeValue::Boolean(result))
+            }
+            Node::ConditionalExpression {
+                test,
+                consequent,
+                alternate,
+            } => {
+                let condition = self
+                    .evaluate(test, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+                if condition.to_boolean() {
+                    return self.evaluate(consequent, env.clone());
+                }
+                return self.evaluate(alternate, env.clone());
+            }
             Node::AssignmentExpression {
                 operator,
                 left,
                 right,
             } => {
-                if operator != &'=' {
-                    return None;
+                if !matches!(operator, '=' | '+' | '-' | '*' | '/') {
+                    return Ok(None);
                 }
                 // 変数の再割り当て
                 if let Some(node) = left {
                     if let Node::Identifier(id) = node.borrow() {
-                        let new_value = self.eval(right, env.clone());
+                        let current = if *operator == '=' {
+                            None
+                        } else {
+                            self.evaluate(left, env.clone())?
+                        };
+                        let new_value =
+                            self.assigned_value(*operator, current, right, env.clone())?;
                         env.borrow_mut().update_variable(id.to_string(), new_value);
-                        return None;
+                        return Ok(None);
+                    }
+
+                    // 配列の要素やオブジェクトのプロパティへの代入
+                    if let Node::MemberExpression {
+                        object,
+                        property,
+                        computed,
+                    } = node.borrow()
+                    {
+                        let object_value = self.evaluate(object, env.clone())?;
+                        if let Some(RuntimeValue::Null) | Some(RuntimeValue::Undefined) =
+                            object_value
+                        {
+                            return Err(JsError::TypeError(format!(
+                                "Cannot set properties of {}",
+                                object_value.unwrap_or(RuntimeValue::Undefined)
+                            ))
+                            .into());
+                        }
+                        if let Some(RuntimeValue::Array(_)) | Some(RuntimeValue::Object(_)) =
+                            object_value
+                        {
+                            let key = match self.property_key(property, *computed, env.clone())? {
+                                Some(key) => key,
+                                None => return Ok(None),
+                            };
+                            if let Some(object_value) = object_value {
+                                let current = object_value.get_property(&key);
+                                let new_value = self
+                                    .assigned_value(*operator, Some(current), right, env.clone())?
+                                    .unwrap_or(RuntimeValue::Undefined);
+                                object_value.set_property(&key, new_value)?;
+                            }
+                            return Ok(None);
+                        }
                     }
                 }
 
                 // もし左辺の値が DOM ツリーのノードを表す HtmlElement ならば DOM ツリーを更新する。
                 if let Some(RuntimeValue::HtmlElement { object, property }) =
-                    self.eval(left, env.clone())
+                    self.evaluate(left, env.clone())?
                 {
-                    let right_value = match self.eval(right, env.clone()) {
-                        Some(value) => value,
-                        None => return None,
-                    };
+                    // 複合代入の場合、現在のノードのテキストを左辺の値とする。
+                    let current = RuntimeValue::StringLiteral(object.borrow_mut().text_content());
+                    let right_value =
+                        match self.assigned_value(*operator, Some(current), right, env.clone())? {
+                            Some(value) => value,
+                            None => return Ok(None),
+                        };
 
                     if let Some(p) = property {
                         // target.textContent = "foobar"; のようにノードのテキストを変更する。
                         if p == "textContent" {
-                            object
-                                .borrow_mut()
-                                .set_first_child(Some(Rc::new(RefCell::new(DomNode::new(
-                                    DomNodeKind::Text(right_value.to_string()),
-                                )))));
+                            DomNode::set_text_content(&object, &right_value.to_string());
                         }
                     }
                 }
                 None
             }
-            Node::MemberExpression { object, property } => {
-                let object_value = match self.eval(object, env.clone()) {
+            Node::UpdateExpression {
+                operator,
+                prefix,
+                argument,
+            } => return self.evaluate_update(*operator, *prefix, argument, env.clone()),
+            Node::MemberExpression {
+                object,
+                property,
+                computed,
+            } => {
+                let object_value = match self.evaluate(object, env.clone())? {
                     Some(value) => value,
-                    None => return None,
+                    None => return Ok(None),
                 };
-                let property_value = match self.eval(property, env.clone()) {
+                let property_value = match self.property_key(property, *computed, env.clone())? {
                     Some(value) => value,
-                    None => return Some(object_value),
+                    None => return Ok(Some(object_value)),
                 };
-
-                // もしオブジェクトが DOM ノードの場合、HtmlElement の property を更新する。
-                if let RuntimeValue::HtmlElement { object, property } = object_value {
-                    assert!(property.is_none());
-                    // HtmlElement の property に property_value の文字列をセットする。
-                    return Some(RuntimeValue::HtmlElement {
-                        object,
-                        property: Some(property_value.to_string()),
-                    });
+                if object_value == RuntimeValue::StringLiteral("window".to_string()) {
+                    if let Some(value) = self.window_property(&property_value) {
+                        return Ok(Some(value));
+                    }
                 }
-
-                // document.getElementById は "document.getElementById" という1つの文字列として扱う。
-                // このメソッドの呼び出しは、"document.getElementById" という名前の関数の呼び出しとなる。
-                return Some(
-                    object_value + RuntimeValue::StringLiteral(".".to_string()) + property_value,
-                );
+                return Self::member_value(object_value, property_value);
+            }
+            Node::NumericLiteral(value) => Some(RuntimeValue::Number(*value as f64)),
+            Node::BooleanLiteral(value) => Some(RuntimeValue::Boolean(*value)),
+            Node::NullLiteral => Some(RuntimeValue::Null),
+            Node::ArrayExpression { elements } => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(
+                        self.evaluate(element, env.clone())?
+                            .unwrap_or(RuntimeValue::Undefined),
+                    );
+                }
+                Some(RuntimeValue::new_array(values))
             }
-            Node::NumericLiteral(value) => Some(RuntimeValue::Number(*value)),
+            Node::ObjectExpression { properties } => {
+                let object = RuntimeValue::new_object(PropertyMap::new());
+                for property in properties.iter().flatten() {
+                    if let Node::Property { key, value } = property.borrow() {
+                        let value = self
+                            .evaluate(value, env.clone())?
+                            .unwrap_or(RuntimeValue::Undefined);
+                        object.set_property(key, value)?;
+                    }
+                }
+                Some(object)
+            }
+            // Property ノードは ObjectExpression の評価時に処理する。
+            Node::Property { .. } => None,
             Node::VariableDeclaration { declarations } => {
                 for declaration in declarations {
-                    self.eval(&declaration, env.clone());
+                    self.evaluate(declaration, env.clone())?;
                 }
                 None
             }
             Node::VariableDeclarator { id, init } => {
                 if let Some(node) = id {
                     if let Node::Identifier(id) = node.borrow() {
-                        let init = self.eval(&init, env.clone());
+                        // 初期化式がない場合 (var a;)、既に存在する変数の値は変更しない。
+                        if init.is_none() {
+                            env.borrow_mut().declare_variable(id.to_string());
+                            return Ok(None);
+                        }
+                        let init = self.evaluate(init, env.clone())?;
                         env.borrow_mut().add_variable(id.to_string(), init);
                     }
                 }
@@ -229,173 +699,1179 @@ impl JsRuntime {
             }
             Node::Identifier(name) => match env.borrow_mut().get_variable(name.to_string()) {
                 Some(v) => Some(v),
+                None if name == "undefined" => Some(RuntimeValue::Undefined),
                 None => Some(RuntimeValue::StringLiteral(name.to_string())),
             },
             Node::StringLiteral(value) => Some(RuntimeValue::StringLiteral(value.to_string())),
             Node::BlockStatement { body } => {
                 // 波括弧で囲われた BlockStatement ノードを処理する。
+                // return 文などによって評価が中断された場合、残りの文は評価しない。
                 let mut result: Option<RuntimeValue> = None;
                 for stmt in body {
-                    result = self.eval(&stmt, env.clone());
+                    result = self.evaluate(stmt, env.clone())?;
                 }
                 result
             }
             Node::ReturnStatement { argument } => {
-                // ReturnStatement ノードは戻り値が argument なので eval で解釈する。
-                return self.eval(&argument, env.clone());
+                // ReturnStatement ノードは戻り値が argument なので eval で解釈し、関数の評価を中断する。
+                let value = self.evaluate(argument, env.clone())?;
+                return Err(AbruptCompletion::Return(value));
+            }
+            Node::ThrowStatement { argument } => {
+                let value = self
+                    .evaluate(argument, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+                return Err(AbruptCompletion::Throw(value));
+            }
+            Node::TryStatement {
+                block,
+                param,
+                handler,
+                finalizer,
+            } => return self.evaluate_try(block, param, handler, finalizer, env.clone()),
+            Node::BreakStatement => return Err(AbruptCompletion::Break),
+            Node::ContinueStatement => return Err(AbruptCompletion::Continue),
+            Node::IfStatement {
+                test,
+                consequent,
+                alternate,
+            } => {
+                let condition = self
+                    .evaluate(test, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+                if condition.to_boolean() {
+                    return self.evaluate(consequent, env.clone());
+                }
+                return self.evaluate(alternate, env.clone());
             }
+            Node::WhileStatement { test, body } => {
+                return self.evaluate_loop(&None, test, &None, body, env.clone())
+            }
+            Node::ForStatement {
+                init,
+                test,
+                update,
+                body,
+            } => return self.evaluate_loop(init, test, update, body, env.clone()),
+            Node::SwitchStatement {
+                discriminant,
+                cases,
+            } => return self.evaluate_switch(discriminant, cases, env.clone()),
+            // SwitchCase ノードは SwitchStatement の評価時に処理する。
+            Node::SwitchCase { .. } => None,
             Node::FunctionDeclaration { id, params, body } => {
-                // FunctionDeclaration ノードで関数名が文字列の場合、今迄に定義した関数を保持する functions フィールドに Function 構造体を追加する。
-                if let Some(RuntimeValue::StringLiteral(id)) = self.eval(&id, env.clone()) {
-                    let cloned_body = match body {
-                        Some(b) => Some(b.clone()),
-                        None => None,
-                    };
-                    self.functions
-                        .push(Function::new(id, params.to_vec(), cloned_body));
-                };
+                // FunctionDeclaration ノードの場合、今迄に定義した関数を保持する functions フィールドに Function 構造体を追加する。
+                self.define_function(id, params, body);
                 None
             }
             // CallExpression ノードの場合
             Node::CallExpression { callee, arguments } => {
-                // 新しいスコープを作成する。
-                // コンストラクタに現在のスコープである env を渡すことで、現在のスコープが新しく作成するスコープの外側のスコープになる。
-                let new_env = Rc::new(RefCell::new(Environment::new(Some(env))));
-
-                // callee ノードを解釈し、その関数名と一致する関数を探す。
-                let callee_value = match self.eval(callee, new_env.clone()) {
-                    Some(value) => value,
-                    None => return None,
-                };
-
-                // ブラウザ API を呼び出している場合、ユーザが定義した関数は実行しない。
-                let api_result = self.call_browser_api(&callee_value, arguments, new_env.clone());
-                if api_result.0 {
-                    return api_result.1;
-                }
-
-                // 既に定義されている関数を探す。
-                // FunctionDeclaration ノードの解釈時に追加した関数の中から探す。
-                // もし関数名に一致する関数が見つからない場合、パニックマクロを呼ぶ。
-                let function = {
-                    let mut f: Option<Function> = None;
-
-                    for func in &self.functions {
-                        if callee_value == RuntimeValue::StringLiteral(func.id.to_string()) {
-                            f = Some(func.clone());
-                        }
-                    }
-                    match f {
-                        Some(f) => f,
-                        None => panic!("function {:?} doesn't exist", callee),
-                    }
-                };
-
-                // 関数呼び出し時に渡される引数を新しく作成したスコープのローカル変数として割り当てる。
-                assert!(arguments.len() == function.params.len());
-                for (i, item) in arguments.iter().enumerate() {
-                    if let Some(RuntimeValue::StringLiteral(name)) =
-                        self.eval(&function.params[i], new_env.clone())
-                    {
-                        new_env
-                            .borrow_mut()
-                            .add_variable(name, self.eval(item, new_env.clone()));
-                    }
-                }
-                // 関数の中身を新しいスコープとともに eval で解釈する。
-                self.eval(&function.body.clone(), new_env.clone())
+                return self.evaluate_call(callee, arguments, env.clone())
+            }
+            // new 演算子はコンストラクタを関数として呼び出す。
+            // 組み込みのエラーのコンストラクタ (例: new Error("message")) を呼ぶ場合に使う。
+            Node::NewExpression { callee, arguments } => {
+                return self.evaluate_call(callee, arguments, env.clone())
             } //_ => todo!(),
-        }
+        };
+        Ok(value)
     }
 
-    /// ブラウザがサポートするブラウザ API を呼ぶ。
-    /// 引数:
-    ///   * 関数名 (func)
-    ///   * 引数 (arguments)
-    ///   * スコープ (env)
-    /// 戻り値:
-    ///   * (bool, Option<RuntimeValue>) タプル
-    ///     * bool: ブラウザ API が呼ばれたかどうか
-    ///     * Option<RuntimeValue>: ブラウザ API の呼び出しによって得られた結果
-    fn call_browser_api(
+    /// 代入演算子 operator による代入で、左辺に割り当てる値を返す。
+    /// 単純な代入 (=) の場合は右辺の値をそのまま返す。
+    /// 複合代入 (+=, -=, *=, /=) の場合は、左辺の現在の値 current と右辺の値を演算した結果を返す。
+    /// https://262.ecma-international.org/#sec-assignment-operators-runtime-semantics-evaluation
+    fn assigned_value(
         &mut self,
-        func: &RuntimeValue,
-        arguments: &[Option<Rc<Node>>],
+        operator: char,
+        current: Option<RuntimeValue>,
+        right: &Option<Rc<Node>>,
         env: Rc<RefCell<Environment>>,
-    ) -> (bool, Option<RuntimeValue>) {
-        // 関数名が document.getElementById の場合、まず1つ目の引数を解釈する。この値は ID 名を表す文字列のはずである。
-        if func == &RuntimeValue::StringLiteral("document.getElementById".to_string()) {
-            let arg = match self.eval(&arguments[0], env.clone()) {
-                Some(a) => a,
-                None => return (true, None),
-            };
-            // ID 名を使用して DOM ツリーから特定の要素を取得する。
-            let target = match get_element_by_id(Some(self.dom_root.clone()), &arg.to_string()) {
-                Some(n) => n,
-                None => return (true, None),
-            };
-            // DOM ツリーのノードを表す HtmlElement を返す。
-            return (
-                true,
-                Some(RuntimeValue::HtmlElement {
-                    object: target,
-                    property: None,
-                }),
-            );
+    ) -> Completion {
+        let right_value = self.evaluate(right, env)?;
+        if operator == '=' {
+            return Ok(right_value);
         }
-        (false, None)
+
+        let current = current.unwrap_or(RuntimeValue::Undefined);
+        let right_value = right_value.unwrap_or(RuntimeValue::Undefined);
+        let value = match operator {
+            '+' => current + right_value,
+            '-' => current - right_value,
+            '*' => current * right_value,
+            '/' => current / right_value,
+            _ => right_value,
+        };
+        Ok(Some(value))
     }
-}
 
-/// RuntimeValue 列挙型
-/// JavaScript ランタイムで扱う値を表す。
-#[derive(Debug, Clone, PartialEq)]
-pub enum RuntimeValue {
-    /// https://262.ecma-international.org/#sec-numeric-types
-    Number(u64),
-    StringLiteral(String),
-    HtmlElement {
-        object: Rc<RefCell<DomNode>>,
-        property: Option<String>,
-    },
-}
+    /// インクリメント (++) とデクリメント (--) を評価する。
+    /// 変数、配列の要素、オブジェクトのプロパティの値を数値に変換してから 1 を加算または減算する。
+    /// 前置の場合は更新後の値を、後置の場合は更新前の値を返す。
+    /// https://262.ecma-international.org/#sec-update-expressions-runtime-semantics-evaluation
+    fn evaluate_update(
+        &mut self,
+        operator: char,
+        prefix: bool,
+        argument: &Option<Rc<Node>>,
+        env: Rc<RefCell<Environment>>,
+    ) -> Completion {
+        let delta = if operator == '+' { 1f64 } else { -1f64 };
+        let result = |old: f64| {
+            let new = old + delta;
+            let value = if prefix { new } else { old };
+            (RuntimeValue::Number(new), Some(RuntimeValue::Number(value)))
+        };
 
-/// Add トレイトの実装
-impl Add<RuntimeValue> for RuntimeValue {
-    type Output = RuntimeValue;
+        if let Some(node) = argument {
+            match node.borrow() {
+                Node::Identifier(name) => {
+                    let old = self
+                        .evaluate(argument, env.clone())?
+                        .unwrap_or(RuntimeValue::Undefined)
+                        .to_number();
+                    let (new, value) = result(old);
+                    env.borrow_mut()
+                        .update_variable(name.to_string(), Some(new));
+                    return Ok(value);
+                }
+                Node::MemberExpression {
+                    object,
+                    property,
+                    computed,
+                } => {
+                    let object_value = self
+                        .evaluate(object, env.clone())?
+                        .unwrap_or(RuntimeValue::Undefined);
+                    if let RuntimeValue::Null | RuntimeValue::Undefined = object_value {
+                        return Err(JsError::TypeError(format!(
+                            "Cannot read properties of {}",
+                            object_value
+                        ))
+                        .into());
+                    }
+                    let key = match self.property_key(property, *computed, env.clone())? {
+                        Some(key) => key,
+                        None => return Ok(None),
+                    };
+                    let (new, value) = result(object_value.get_property(&key).to_number());
+                    object_value.set_property(&key, new)?;
+                    return Ok(value);
+                }
+                _ => {}
+            }
+        }
+        Err(JsError::SyntaxError(
+            "Invalid left-hand side expression in update operation".to_string(),
+        )
+        .into())
+    }
 
-    fn add(self, rhs: RuntimeValue) -> RuntimeValue {
-        if let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs) {
-            return RuntimeValue::Number(left_num + right_num);
+    /// 関数を定義する。
+    /// 同じ名前の関数が既に定義されている場合、その関数を置き換える。
+    fn define_function(
+        &mut self,
+        id: &Option<Rc<Node>>,
+        params: &[Option<Rc<Node>>],
+        body: &Option<Rc<Node>>,
+    ) {
+        let name = match id {
+            Some(node) => match node.borrow() {
+                Node::Identifier(name) => name.to_string(),
+                _ => return,
+            },
+            None => return,
+        };
+        let function = Function::new(name, params.to_vec(), body.clone());
+        match self.functions.iter_mut().find(|f| f.id == function.id) {
+            Some(f) => *f = function,
+            None => self.functions.push(function),
         }
-        RuntimeValue::StringLiteral(self.to_string() + &rhs.to_string())
     }
-}
 
-/// Sub トレイトの実装
-impl Sub<RuntimeValue> for RuntimeValue {
-    type Output = RuntimeValue;
+    /// 関数宣言と var による変数宣言を巻き上げる (hoisting)。
+    /// 文を評価する前に、関数宣言を定義し、var で宣言された変数を値が undefined の変数としてスコープ env に追加する。
+    /// これにより、後に宣言される関数の呼び出しや変数の参照ができるようになる。
+    /// ブロックや制御構文の中の宣言も対象とするが、関数の中の宣言は関数の呼び出し時に巻き上げる。
+    /// https://262.ecma-international.org/#sec-globaldeclarationinstantiation
+    /// https://262.ecma-international.org/#sec-functiondeclarationinstantiation
+    fn hoist_declarations(&mut self, node: &Option<Rc<Node>>, env: Rc<RefCell<Environment>>) {
+        let node = match node {
+            Some(n) => n,
+            None => return,
+        };
 
-    fn sub(self, rhs: RuntimeValue) -> RuntimeValue {
-        if let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs) {
-            return RuntimeValue::Number(left_num - right_num);
+        match node.borrow() {
+            Node::FunctionDeclaration { id, params, body } => {
+                self.define_function(id, params, body)
+            }
+            Node::VariableDeclaration { declarations } => {
+                for declaration in declarations.iter().flatten() {
+                    if let Node::VariableDeclarator { id: Some(id), .. } = declaration.borrow() {
+                        if let Node::Identifier(name) = id.borrow() {
+                            env.borrow_mut().declare_variable(name.to_string());
+                        }
+                    }
+                }
+            }
+            Node::BlockStatement { body } => {
+                for stmt in body {
+                    self.hoist_declarations(stmt, env.clone());
+                }
+            }
+            Node::IfStatement {
+                consequent,
+                alternate,
+                ..
+            } => {
+                self.hoist_declarations(consequent, env.clone());
+                self.hoist_declarations(alternate, env);
+            }
+            Node::WhileStatement { body, .. } => self.hoist_declarations(body, env),
+            Node::ForStatement { init, body, .. } => {
+                self.hoist_declarations(init, env.clone());
+                self.hoist_declarations(body, env);
+            }
+            Node::SwitchStatement { cases, .. } => {
+                for case in cases.iter().flatten() {
+                    if let Node::SwitchCase { consequent, .. } = case.borrow() {
+                        for stmt in consequent {
+                            self.hoist_declarations(stmt, env.clone());
+                        }
+                    }
+                }
+            }
+            Node::TryStatement {
+                block,
+                handler,
+                finalizer,
+                ..
+            } => {
+                self.hoist_declarations(block, env.clone());
+                self.hoist_declarations(handler, env.clone());
+                self.hoist_declarations(finalizer, env);
+            }
+            _ => {}
         }
-        // NaN
-        RuntimeValue::Number(u64::MIN)
     }
-}
 
-/// RuntimeValue の値を文字列に変換する。
-impl Display for RuntimeValue {
-    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
-        let s = match self {
-            RuntimeValue::Number(value) => format!("{}", value),
-            RuntimeValue::StringLiteral(value) => value.to_string(),
+    /// オブジェクトのプロパティにアクセスした結果を返す。
+    fn member_value(object_value: RuntimeValue, property_value: String) -> Completion {
+        let value = match object_value {
+            // null や undefined のプロパティにはアクセスできない。
+            RuntimeValue::Null | RuntimeValue::Undefined => {
+                return Err(JsError::TypeError(format!(
+                    "Cannot read properties of {} (reading '{}')",
+                    object_value, property_value
+                ))
+                .into());
+            }
+            // DOM ノードのプロパティのプロパティ (例: target.textContent.length) はサポートしていない。
+            RuntimeValue::HtmlElement {
+                property: Some(_), ..
+            } => Some(RuntimeValue::Undefined),
+            // もしオブジェクトが DOM ノードの場合、HtmlElement の property を更新する。
             RuntimeValue::HtmlElement {
                 object,
-                property: _,
+                property: None,
             } => {
-                format!("HtmlElement: {:?}", object)
+                // HtmlElement の property に property_value の文字列をセットする。
+                Some(RuntimeValue::HtmlElement {
+                    object,
+                    property: Some(property_value),
+                })
+            }
+            // 配列の要素、またはオブジェクトのプロパティの値を返す。
+            RuntimeValue::Array(_) | RuntimeValue::Object(_) => {
+                Some(object_value.get_property(&property_value))
+            }
+            RuntimeValue::StringLiteral(s) if property_value == "length" => {
+                Some(RuntimeValue::Number(s.chars().count() as f64))
+            }
+            // document.getElementById は "document.getElementById" という1つの文字列として扱う。
+            // このメソッドの呼び出しは、"document.getElementById" という名前の関数の呼び出しとなる。
+            _ => Some(
+                object_value
+                    + RuntimeValue::StringLiteral(".".to_string())
+                    + RuntimeValue::StringLiteral(property_value),
+            ),
+        };
+        Ok(value)
+    }
+
+    /// 関数呼び出しを評価する。
+    /// ブラウザ API、ユーザが定義した関数、組み込み関数の順に関数名と一致する関数を探して呼ぶ。
+    /// 一致する関数が存在しない場合、例外を送出する。
+    fn evaluate_call(
+        &mut self,
+        callee: &Option<Rc<Node>>,
+        arguments: &[Option<Rc<Node>>],
+        env: Rc<RefCell<Environment>>,
+    ) -> Completion {
+        // 新しいスコープを作成する。
+        // コンストラクタに現在のスコープである env を渡すことで、現在のスコープが新しく作成するスコープの外側のスコープになる。
+        let new_env = Rc::new(RefCell::new(Environment::new(Some(env))));
+
+        // callee ノードを解釈し、その関数名と一致する関数を探す。
+        let callee_value = match callee.as_deref() {
+            Some(Node::MemberExpression {
+                object,
+                property,
+                computed,
+            }) => {
+                let object_value = match self.evaluate(object, new_env.clone())? {
+                    Some(value) => value,
+                    None => return Ok(None),
+                };
+                match self.property_key(property, *computed, new_env.clone())? {
+                    Some(key) => {
+                        // 配列のメソッド呼び出し (例: list.push(1)) の場合、配列を対象としてメソッドを呼ぶ。
+                        if let RuntimeValue::Array(_) = object_value {
+                            if let Some(method) = find_array_method(&key) {
+                                let values = self.evaluate_arguments(arguments, new_env.clone())?;
+                                return Ok(Some(method(&object_value, &values)?));
+                            }
+                        }
+                        Self::member_value(object_value, key)?
+                    }
+                    None => Some(object_value),
+                }
+            }
+            _ => self.evaluate(callee, new_env.clone())?,
+        };
+        let callee_value = match callee_value {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        // ブラウザ API を呼び出している場合、ユーザが定義した関数は実行しない。
+        let api_result = self.call_browser_api(&callee_value, arguments, new_env.clone())?;
+        if api_result.0 {
+            return Ok(api_result.1);
+        }
+
+        // 既に定義されている関数を探す。
+        // FunctionDeclaration ノードの解釈時に追加した関数の中から探す。
+        // ユーザが定義した関数が見つからない場合、組み込み関数を探す。
+        let mut function: Option<Function> = None;
+        for func in &self.functions {
+            if callee_value == RuntimeValue::StringLiteral(func.id.to_string()) {
+                function = Some(func.clone());
+            }
+        }
+        let function = match function {
+            Some(f) => f,
+            None => {
+                if let RuntimeValue::StringLiteral(name) = &callee_value {
+                    if let Some(builtin) = find_builtin_function(name) {
+                        return Ok(Some(self.call_builtin_function(
+                            builtin,
+                            arguments,
+                            new_env.clone(),
+                        )?));
+                    }
+                }
+                return Err(self
+                    .not_callable_error(callee, &callee_value, new_env)
+                    .into());
+            }
+        };
+
+        // 関数呼び出し時に渡される引数を評価し、新しく作成したスコープのローカル変数として割り当てる。
+        let values = self.evaluate_arguments(arguments, new_env.clone())?;
+        // 関数の中身を新しいスコープとともに評価する。
+        // return 文による中断は、関数呼び出しの結果として扱う。
+        self.call_user_function(&function, &values, new_env)
+    }
+
+    /// 関数呼び出しの引数を順に評価する。値を持たない引数は undefined とする。
+    fn evaluate_arguments(
+        &mut self,
+        arguments: &[Option<Rc<Node>>],
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<Vec<RuntimeValue>, AbruptCompletion> {
+        let mut values = Vec::new();
+        for argument in arguments {
+            values.push(
+                self.evaluate(argument, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined),
+            );
+        }
+        Ok(values)
+    }
+
+    /// 呼び出せない値を関数として呼んだ場合のエラーを返す。
+    /// 宣言されていない変数を呼んだ場合は ReferenceError、それ以外の場合は TypeError とする。
+    fn not_callable_error(
+        &self,
+        callee: &Option<Rc<Node>>,
+        callee_value: &RuntimeValue,
+        env: Rc<RefCell<Environment>>,
+    ) -> JsError {
+        if let Some(node) = callee {
+            if let Node::Identifier(name) = node.borrow() {
+                if env.borrow_mut().get_variable(name.to_string()).is_none() {
+                    return JsError::ReferenceError(format!("{} is not defined", name));
+                }
+            }
+        }
+        JsError::TypeError(format!("{} is not a function", callee_value))
+    }
+
+    /// try 文を評価する。
+    /// try ブロックで例外が送出された場合、送出された値を catch 節の変数に割り当てて catch ブロックを評価する。
+    /// finally ブロックは常に評価し、finally ブロックが return 文や例外などで中断された場合はその結果を優先する。
+    /// https://262.ecma-international.org/#sec-try-statement-runtime-semantics-evaluation
+    fn evaluate_try(
+        &mut self,
+        block: &Option<Rc<Node>>,
+        param: &Option<Rc<Node>>,
+        handler: &Option<Rc<Node>>,
+        finalizer: &Option<Rc<Node>>,
+        env: Rc<RefCell<Environment>>,
+    ) -> Completion {
+        let completion = match self.evaluate(block, env.clone()) {
+            Err(AbruptCompletion::Throw(exception)) if handler.is_some() => {
+                // catch 節の変数は catch ブロックのスコープに割り当てる。
+                let catch_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+                if let Some(node) = param {
+                    if let Node::Identifier(name) = node.borrow() {
+                        catch_env
+                            .borrow_mut()
+                            .add_variable(name.to_string(), Some(exception));
+                    }
+                }
+                self.evaluate(handler, catch_env)
+            }
+            completion => completion,
+        };
+
+        if finalizer.is_some() {
+            self.evaluate(finalizer, env)?;
+        }
+        completion
+    }
+
+    /// while 文と for 文を評価する。
+    /// init を評価した後、test が真である間 body と update を繰り返し評価する。test が省略された場合は常に真とみなす。
+    /// break 文はループを終了させ、continue 文は現在の繰り返しの残りの文を飛ばして update に進む。
+    /// https://262.ecma-international.org/#sec-forbodyevaluation
+    fn evaluate_loop(
+        &mut self,
+        init: &Option<Rc<Node>>,
+        test: &Option<Rc<Node>>,
+        update: &Option<Rc<Node>>,
+        body: &Option<Rc<Node>>,
+        env: Rc<RefCell<Environment>>,
+    ) -> Completion {
+        self.evaluate(init, env.clone())?;
+
+        let mut result = None;
+        loop {
+            if test.is_some() {
+                let condition = self
+                    .evaluate(test, env.clone())?
+                    .unwrap_or(RuntimeValue::Undefined);
+                if !condition.to_boolean() {
+                    return Ok(result);
+                }
+            }
+
+            match self.evaluate(body, env.clone()) {
+                Ok(value) => result = value,
+                Err(AbruptCompletion::Break) => return Ok(result),
+                Err(AbruptCompletion::Continue) => {}
+                Err(e) => return Err(e),
+            }
+
+            self.evaluate(update, env.clone())?;
+        }
+    }
+
+    /// switch 文を評価する。
+    /// discriminant と厳密等価 (===) な最初の case 節から、break 文が現れるまで後続の節の文を順に評価する (フォールスルー)。
+    /// 一致する case 節がない場合、default 節から評価する。
+    /// https://262.ecma-international.org/#sec-runtime-semantics-caseblockevaluation
+    fn evaluate_switch(
+        &mut self,
+        discriminant: &Option<Rc<Node>>,
+        cases: &[Option<Rc<Node>>],
+        env: Rc<RefCell<Environment>>,
+    ) -> Completion {
+        let value = self
+            .evaluate(discriminant, env.clone())?
+            .unwrap_or(RuntimeValue::Undefined);
+
+        // 評価を開始する節を探す。
+        let mut start = None;
+        let mut default_index = None;
+        for (i, case) in cases.iter().enumerate() {
+            if let Some(node) = case {
+                if let Node::SwitchCase { test, .. } = node.borrow() {
+                    if test.is_none() {
+                        default_index = Some(i);
+                        continue;
+                    }
+                    let test_value = self
+                        .evaluate(test, env.clone())?
+                        .unwrap_or(RuntimeValue::Undefined);
+                    if value.strict_equals(&test_value) {
+                        start = Some(i);
+                        break;
+                    }
+                }
+            }
+        }
+
+        let start = match start.or(default_index) {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+
+        let mut result = None;
+        for case in cases[start..].iter().flatten() {
+            if let Node::SwitchCase { consequent, .. } = case.borrow() {
+                for stmt in consequent {
+                    match self.evaluate(stmt, env.clone()) {
+                        Ok(value) => result = value,
+                        // break 文は switch 文の評価を終了させる。
+                        Err(AbruptCompletion::Break) => return Ok(result),
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// プロパティアクセスのプロパティ名を返す。
+    /// object.property の形式の場合は変数名をそのままプロパティ名とし、object[property] の形式の場合は式の評価結果を文字列に変換する。
+    fn property_key(
+        &mut self,
+        property: &Option<Rc<Node>>,
+        computed: bool,
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<Option<String>, AbruptCompletion> {
+        if !computed {
+            if let Some(node) = property {
+                if let Node::Identifier(name) = node.borrow() {
+                    return Ok(Some(name.to_string()));
+                }
+            }
+        }
+        Ok(self.evaluate(property, env)?.map(|value| value.to_string()))
+    }
+
+    /// 組み込み関数を呼ぶ。
+    /// 引数をすべて評価してから、組み込み関数に渡す。
+    fn call_builtin_function(
+        &mut self,
+        builtin: BuiltinFunction,
+        arguments: &[Option<Rc<Node>>],
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<RuntimeValue, AbruptCompletion> {
+        // 値を持たない引数は undefined として、引数の位置を保つ。
+        let mut values = Vec::new();
+        for argument in arguments {
+            let value = self.evaluate(argument, env.clone())?;
+            values.push(value.unwrap_or(RuntimeValue::Undefined));
+        }
+        Ok(builtin(&values)?)
+    }
+
+    /// window オブジェクトのプロパティの値を返す。
+    /// ランタイムが管理する値を持つプロパティでない場合、None を返す。
+    /// https://drafts.csswg.org/cssom-view/#extensions-to-the-window-interface
+    fn window_property(&self, name: &str) -> Option<RuntimeValue> {
+        match name {
+            "scrollX" | "pageXOffset" => Some(RuntimeValue::Number(self.scroll_position.0 as f64)),
+            "scrollY" | "pageYOffset" => Some(RuntimeValue::Number(self.scroll_position.1 as f64)),
+            _ => None,
+        }
+    }
+
+    /// ブラウザがサポートするブラウザ API を呼ぶ。
+    /// 引数:
+    ///   * 関数名 (func)
+    ///   * 引数 (arguments)
+    ///   * スコープ (env)
+    /// 戻り値:
+    ///   * (bool, Option<RuntimeValue>) タプル
+    ///     * bool: ブラウザ API が呼ばれたかどうか
+    ///     * Option<RuntimeValue>: ブラウザ API の呼び出しによって得られた結果
+    fn call_browser_api(
+        &mut self,
+        func: &RuntimeValue,
+        arguments: &[Option<Rc<Node>>],
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<(bool, Option<RuntimeValue>), AbruptCompletion> {
+        // 関数名が document.getElementById の場合、まず1つ目の引数を解釈する。この値は ID 名を表す文字列のはずである。
+        if func == &RuntimeValue::StringLiteral("document.getElementById".to_string()) {
+            let arg = match arguments.first() {
+                Some(argument) => match self.evaluate(argument, env.clone())? {
+                    Some(a) => a,
+                    None => return Ok((true, None)),
+                },
+                None => return Ok((true, None)),
+            };
+            // ID 名を使用して DOM ツリーから特定の要素を取得する。
+            let target = match get_element_by_id(Some(self.dom_root.clone()), &arg.to_string()) {
+                Some(n) => n,
+                None => return Ok((true, None)),
+            };
+            // DOM ツリーのノードを表す HtmlElement を返す。
+            return Ok((
+                true,
+                Some(RuntimeValue::HtmlElement {
+                    object: target,
+                    property: None,
+                }),
+            ));
+        }
+
+        // 関数名が Date.now の場合、現在時刻をミリ秒で返す。
+        // https://262.ecma-international.org/#sec-date.now
+        if func == &RuntimeValue::StringLiteral("Date.now".to_string()) {
+            return Ok((true, Some(RuntimeValue::Number(self.clock.now() as f64))));
+        }
+
+        // 関数名が setTimeout の場合、1つ目の引数の関数を2つ目の引数のミリ秒後に呼ぶタイマーを登録する。
+        // 戻り値はタイマーの ID である。
+        // https://html.spec.whatwg.org/multipage/timers-and-user-prompts.html#dom-settimeout
+        if func == &RuntimeValue::StringLiteral("setTimeout".to_string()) {
+            let callback = match arguments.first() {
+                Some(argument) => match self.evaluate(argument, env.clone())? {
+                    Some(value) => value.to_string(),
+                    None => return Ok((true, None)),
+                },
+                None => return Ok((true, None)),
+            };
+            let delay = match arguments.get(1) {
+                Some(argument) => match self.evaluate(argument, env.clone())? {
+                    Some(value) => value.to_number(),
+                    None => 0f64,
+                },
+                None => 0f64,
+            };
+            // 遅延時間が数値でない場合や負の値の場合、0 とみなす。
+            let delay = if delay.is_nan() || delay < 0f64 {
+                0
+            } else {
+                delay as u64
+            };
+
+            let id = self.next_timer_id;
+            self.next_timer_id += 1;
+            self.timers.push(Timer {
+                id,
+                due: self.clock.now().saturating_add(delay),
+                callback,
+            });
+            return Ok((true, Some(RuntimeValue::Number(id as f64))));
+        }
+
+        // 関数名が window.scrollTo の場合、引数の位置へのスクロールを要求する。
+        // 引数は (x, y) の2つの数値、または left と top のプロパティを持つオブジェクトである。
+        // 省略された座標は現在の位置のままとし、負の値や数値でない値は 0 とみなす。
+        // https://drafts.csswg.org/cssom-view/#dom-window-scrollto
+        if func == &RuntimeValue::StringLiteral("window.scrollTo".to_string())
+            || func == &RuntimeValue::StringLiteral("window.scroll".to_string())
+        {
+            let values = self.evaluate_arguments(arguments, env.clone())?;
+            let (x, y) = match values.first() {
+                Some(RuntimeValue::Object(_)) => (
+                    values[0].get_property("left"),
+                    values[0].get_property("top"),
+                ),
+                _ => (
+                    values.first().cloned().unwrap_or(RuntimeValue::Undefined),
+                    values.get(1).cloned().unwrap_or(RuntimeValue::Undefined),
+                ),
+            };
+            let coordinate = |value: RuntimeValue, current: i64| {
+                if value == RuntimeValue::Undefined {
+                    return current;
+                }
+                let n = value.to_number();
+                if n.is_nan() || n < 0f64 {
+                    0
+                } else {
+                    n as i64
+                }
+            };
+            let position = (
+                coordinate(x, self.scroll_position.0),
+                coordinate(y, self.scroll_position.1),
+            );
+            self.scroll_position = position;
+            self.scroll_request = Some(position);
+            return Ok((true, Some(RuntimeValue::Undefined)));
+        }
+
+        // DOM ノードの addEventListener と removeEventListener の場合、イベントの種類とコールバックの関数名、キャプチャの指定からリスナーを作り、ノードに登録または削除する。
+        // 3つ目の引数は真偽値、または capture のプロパティを持つオブジェクトである。
+        // https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener
+        if let RuntimeValue::HtmlElement {
+            object,
+            property: Some(property),
+        } = func
+        {
+            if property == "addEventListener" || property == "removeEventListener" {
+                let values = self.evaluate_arguments(arguments, env.clone())?;
+                let (event_type, callback) = match (values.first(), values.get(1)) {
+                    (Some(event_type), Some(callback)) => {
+                        (event_type.to_string(), callback.to_string())
+                    }
+                    _ => return Ok((true, Some(RuntimeValue::Undefined))),
+                };
+                let capture = match values.get(2) {
+                    Some(RuntimeValue::Object(_)) => values[2].get_property("capture").to_boolean(),
+                    Some(value) => value.to_boolean(),
+                    None => false,
+                };
+                let listener =
+                    EventListener::new(&event_type, EventCallback::Function(callback), capture);
+                if property == "addEventListener" {
+                    object.borrow_mut().add_event_listener(listener);
+                } else {
+                    object.borrow_mut().remove_event_listener(&listener);
+                }
+                return Ok((true, Some(RuntimeValue::Undefined)));
+            }
+        }
+
+        // イベントオブジェクトの preventDefault と stopPropagation の場合、送出中のイベントに反映する。
+        // https://dom.spec.whatwg.org/#dom-event-preventdefault
+        if func == &RuntimeValue::StringLiteral("event.preventDefault".to_string()) {
+            self.default_prevented = true;
+            return Ok((true, Some(RuntimeValue::Undefined)));
+        }
+        if func == &RuntimeValue::StringLiteral("event.stopPropagation".to_string()) {
+            self.propagation_stopped = true;
+            return Ok((true, Some(RuntimeValue::Undefined)));
+        }
+
+        // 関数名が clearTimeout の場合、引数の ID のタイマーを取り消す。
+        // https://html.spec.whatwg.org/multipage/timers-and-user-prompts.html#dom-cleartimeout
+        if func == &RuntimeValue::StringLiteral("clearTimeout".to_string()) {
+            if let Some(argument) = arguments.first() {
+                if let Some(value) = self.evaluate(argument, env.clone())? {
+                    let id = value.to_number();
+                    self.timers.retain(|timer| timer.id as f64 != id);
+                }
+            }
+            return Ok((true, Some(RuntimeValue::Undefined)));
+        }
+        Ok((false, None))
+    }
+}
+
+/// RuntimeValue 列挙型
+/// JavaScript ランタイムで扱う値を表す。
+#[derive(Debug, Clone)]
+pub enum RuntimeValue {
+    /// https://262.ecma-international.org/#sec-numeric-types
+    Number(f64),
+    StringLiteral(String),
+    /// https://262.ecma-international.org/#sec-ecmascript-language-types-boolean-type
+    Boolean(bool),
+    /// https://262.ecma-international.org/#sec-ecmascript-language-types-null-type
+    Null,
+    /// https://262.ecma-international.org/#sec-ecmascript-language-types-undefined-type
+    Undefined,
+    /// 配列を表す。複数の変数から同じ配列を参照できるように、要素は共有される。
+    /// https://262.ecma-international.org/#sec-array-objects
+    Array(Rc<RefCell<Vec<RuntimeValue>>>),
+    /// オブジェクトを表す。複数の変数から同じオブジェクトを参照できるように、プロパティは共有される。
+    /// https://262.ecma-international.org/#sec-object-type
+    Object(Rc<RefCell<PropertyMap>>),
+    HtmlElement {
+        object: Rc<RefCell<DomNode>>,
+        property: Option<String>,
+    },
+}
+
+/// Add トレイトの実装
+impl Add<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    /// どちらかの値が文字列、配列、オブジェクトの場合は文字列として連結し、それ以外の場合は数値として加算する。
+    fn add(self, rhs: RuntimeValue) -> RuntimeValue {
+        if self.is_primitive_non_string() && rhs.is_primitive_non_string() {
+            return RuntimeValue::Number(self.to_number() + rhs.to_number());
+        }
+        RuntimeValue::StringLiteral(self.to_string() + &rhs.to_string())
+    }
+}
+
+/// Sub トレイトの実装
+impl Sub<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    /// 減算は両辺を数値に変換してから行う。数値に変換できない場合、結果は NaN となる。
+    fn sub(self, rhs: RuntimeValue) -> RuntimeValue {
+        RuntimeValue::Number(self.to_number() - rhs.to_number())
+    }
+}
+
+/// Mul トレイトの実装
+impl Mul<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    /// 乗算は両辺を数値に変換してから行う。
+    fn mul(self, rhs: RuntimeValue) -> RuntimeValue {
+        RuntimeValue::Number(self.to_number() * rhs.to_number())
+    }
+}
+
+/// Div トレイトの実装
+impl Div<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    /// 除算は両辺を数値に変換してから行う。0 で割った場合、結果は Infinity または NaN となる。
+    fn div(self, rhs: RuntimeValue) -> RuntimeValue {
+        RuntimeValue::Number(self.to_number() / rhs.to_number())
+    }
+}
+
+impl RuntimeValue {
+    /// 要素を持つ配列を作成する。
+    pub fn new_array(elements: Vec<RuntimeValue>) -> Self {
+        RuntimeValue::Array(Rc::new(RefCell::new(elements)))
+    }
+
+    /// プロパティを持つオブジェクトを作成する。
+    pub fn new_object(properties: PropertyMap) -> Self {
+        RuntimeValue::Object(Rc::new(RefCell::new(properties)))
+    }
+
+    /// name と message のプロパティを持つエラーオブジェクトを作成する。
+    /// https://262.ecma-international.org/#sec-error-objects
+    pub fn new_error(name: &str, message: &str) -> Self {
+        RuntimeValue::new_object(
+            [
+                (
+                    "name".to_string(),
+                    RuntimeValue::StringLiteral(name.to_string()),
+                ),
+                (
+                    "message".to_string(),
+                    RuntimeValue::StringLiteral(message.to_string()),
+                ),
+            ]
+            .to_vec(),
+        )
+    }
+
+    /// 数値、真偽値、null、undefined のいずれかであるかどうか。
+    fn is_primitive_non_string(&self) -> bool {
+        matches!(
+            self,
+            RuntimeValue::Number(_)
+                | RuntimeValue::Boolean(_)
+                | RuntimeValue::Null
+                | RuntimeValue::Undefined
+        )
+    }
+
+    /// 値を真偽値に変換する。
+    /// https://262.ecma-international.org/#sec-toboolean
+    pub fn to_boolean(&self) -> bool {
+        match self {
+            RuntimeValue::Number(value) => !(value.is_nan() || *value == 0f64),
+            RuntimeValue::StringLiteral(value) => !value.is_empty(),
+            RuntimeValue::Boolean(value) => *value,
+            RuntimeValue::Null | RuntimeValue::Undefined => false,
+            RuntimeValue::Array(_) | RuntimeValue::Object(_) | RuntimeValue::HtmlElement { .. } => {
+                true
+            }
+        }
+    }
+
+    /// 等価演算子 (==) による比較を行う。
+    /// 型が異なる場合、数値や文字列に変換してから比較する。
+    /// https://262.ecma-international.org/#sec-islooselyequal
+    pub fn loose_equals(&self, other: &RuntimeValue) -> bool {
+        match (self, other) {
+            (
+                RuntimeValue::Null | RuntimeValue::Undefined,
+                RuntimeValue::Null | RuntimeValue::Undefined,
+            ) => true,
+            (RuntimeValue::Null | RuntimeValue::Undefined, _)
+            | (_, RuntimeValue::Null | RuntimeValue::Undefined) => false,
+            (RuntimeValue::Number(_), RuntimeValue::Number(_))
+            | (RuntimeValue::StringLiteral(_), RuntimeValue::StringLiteral(_))
+            | (RuntimeValue::Boolean(_), RuntimeValue::Boolean(_)) => self.strict_equals(other),
+            // 配列やオブジェクト同士は同じ実体を参照している場合のみ等しい。
+            (
+                RuntimeValue::Array(_) | RuntimeValue::Object(_) | RuntimeValue::HtmlElement { .. },
+                RuntimeValue::Array(_) | RuntimeValue::Object(_) | RuntimeValue::HtmlElement { .. },
+            ) => self.strict_equals(other),
+            // 配列やオブジェクトと文字列の比較は、文字列に変換してから比較する。
+            (
+                RuntimeValue::Array(_) | RuntimeValue::Object(_) | RuntimeValue::HtmlElement { .. },
+                RuntimeValue::StringLiteral(_),
+            )
+            | (
+                RuntimeValue::StringLiteral(_),
+                RuntimeValue::Array(_) | RuntimeValue::Object(_) | RuntimeValue::HtmlElement { .. },
+            ) => self.to_string() == other.to_string(),
+            // それ以外は数値に変換してから比較する。
+            _ => self.to_number() == other.to_number(),
+        }
+    }
+
+    /// 関係演算子 (<, >, <=, >=) のための比較を行う。
+    /// 両辺が文字列の場合は辞書順で比較し、それ以外は数値に変換してから比較する。
+    /// 数値に NaN が含まれる場合、比較できないため None を返す。
+    /// https://262.ecma-international.org/#sec-islessthan
+    pub fn compare(&self, other: &RuntimeValue) -> Option<Ordering> {
+        if let (RuntimeValue::StringLiteral(a), RuntimeValue::StringLiteral(b)) = (self, other) {
+            return Some(a.cmp(b));
+        }
+        self.to_number().partial_cmp(&other.to_number())
+    }
+
+    /// 厳密等価演算子 (===) による比較を行う。
+    /// 配列、オブジェクト、DOM ノードは同じ実体を参照している場合のみ等しい。
+    /// https://262.ecma-international.org/#sec-isstrictlyequal
+    pub fn strict_equals(&self, other: &RuntimeValue) -> bool {
+        match (self, other) {
+            (RuntimeValue::Number(a), RuntimeValue::Number(b)) => a == b,
+            (RuntimeValue::StringLiteral(a), RuntimeValue::StringLiteral(b)) => a == b,
+            (RuntimeValue::Boolean(a), RuntimeValue::Boolean(b)) => a == b,
+            (RuntimeValue::Null, RuntimeValue::Null) => true,
+            (RuntimeValue::Undefined, RuntimeValue::Undefined) => true,
+            (RuntimeValue::Array(a), RuntimeValue::Array(b)) => Rc::ptr_eq(a, b),
+            (RuntimeValue::Object(a), RuntimeValue::Object(b)) => Rc::ptr_eq(a, b),
+            (
+                RuntimeValue::HtmlElement {
+                    object: a,
+                    property: pa,
+                },
+                RuntimeValue::HtmlElement {
+                    object: b,
+                    property: pb,
+                },
+            ) => Rc::ptr_eq(a, b) && pa == pb,
+            _ => false,
+        }
+    }
+
+    /// 値を数値に変換する。
+    /// https://262.ecma-international.org/#sec-tonumber
+    pub fn to_number(&self) -> f64 {
+        match self {
+            RuntimeValue::Number(value) => *value,
+            RuntimeValue::StringLiteral(value) => string_to_number(value),
+            RuntimeValue::Boolean(value) => {
+                if *value {
+                    1f64
+                } else {
+                    0f64
+                }
+            }
+            RuntimeValue::Null => 0f64,
+            // 配列は文字列に変換してから数値に変換する (例: [5] は 5 となる)。
+            RuntimeValue::Array(_) => string_to_number(&self.to_string()),
+            RuntimeValue::Undefined
+            | RuntimeValue::Object(_)
+            | RuntimeValue::HtmlElement { .. } => f64::NAN,
+        }
+    }
+
+    /// 配列の要素、またはオブジェクトのプロパティの値を返す。
+    /// 配列の場合、key は要素のインデックスまたは "length" である。
+    /// 存在しない要素やプロパティの場合、undefined を返す。
+    pub fn get_property(&self, key: &str) -> RuntimeValue {
+        match self {
+            RuntimeValue::Array(elements) => {
+                let elements = RefCell::borrow(elements);
+                if key == "length" {
+                    return RuntimeValue::Number(elements.len() as f64);
+                }
+                match key.parse::<usize>() {
+                    Ok(index) => elements
+                        .get(index)
+                        .cloned()
+                        .unwrap_or(RuntimeValue::Undefined),
+                    Err(_) => RuntimeValue::Undefined,
+                }
+            }
+            RuntimeValue::Object(properties) => {
+                for (k, v) in RefCell::borrow(properties).iter() {
+                    if k == key {
+                        return v.clone();
+                    }
+                }
+                RuntimeValue::Undefined
+            }
+            _ => RuntimeValue::Undefined,
+        }
+    }
+
+    /// 配列の要素、またはオブジェクトのプロパティに値を設定する。
+    /// 配列の長さを超えるインデックスに設定する場合、間の要素は undefined で埋める。
+    /// 埋める要素の数が MAX_ARRAY_GAP を超える場合は RangeError とする。
+    pub fn set_property(&self, key: &str, value: RuntimeValue) -> Result<(), JsError> {
+        match self {
+            RuntimeValue::Array(elements) => {
+                if let Ok(index) = key.parse::<usize>() {
+                    let mut elements = elements.borrow_mut();
+                    if index >= elements.len() {
+                        if index - elements.len() > MAX_ARRAY_GAP {
+                            return Err(JsError::RangeError(format!(
+                                "Array index {} is too far past the array length {}",
+                                index,
+                                elements.len()
+                            )));
+                        }
+                        elements.resize(index + 1, RuntimeValue::Undefined);
+                    }
+                    elements[index] = value;
+                }
+            }
+            RuntimeValue::Object(properties) => {
+                let mut properties = properties.borrow_mut();
+                match properties.iter_mut().find(|(k, _)| k == key) {
+                    Some(property) => property.1 = value,
+                    None => properties.push((key.to_string(), value)),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// 配列とオブジェクトは、同じものを参照しているか、要素とプロパティがすべて等しい場合に等しいとする。
+impl PartialEq for RuntimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        values_equal(self, other, &mut Vec::new())
+    }
+}
+
+/// 2つの値を比較する。
+/// stack には比較中の配列またはオブジェクトのポインタの組を保持し、
+/// 循環参照によって同じ組を再び比較する場合は、等しいとみなして比較を打ち切る。
+fn values_equal(
+    a: &RuntimeValue,
+    b: &RuntimeValue,
+    stack: &mut Vec<(*const (), *const ())>,
+) -> bool {
+    match (a, b) {
+        (RuntimeValue::Number(a), RuntimeValue::Number(b)) => a == b,
+        (RuntimeValue::StringLiteral(a), RuntimeValue::StringLiteral(b)) => a == b,
+        (RuntimeValue::Boolean(a), RuntimeValue::Boolean(b)) => a == b,
+        (RuntimeValue::Null, RuntimeValue::Null) => true,
+        (RuntimeValue::Undefined, RuntimeValue::Undefined) => true,
+        (RuntimeValue::Array(a), RuntimeValue::Array(b)) => {
+            let pair = (a.as_ptr() as *const (), b.as_ptr() as *const ());
+            if Rc::ptr_eq(a, b) || stack.contains(&pair) {
+                return true;
+            }
+            stack.push(pair);
+            let (a, b) = (RefCell::borrow(a), RefCell::borrow(b));
+            let equal = a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| values_equal(a, b, stack));
+            stack.pop();
+            equal
+        }
+        (RuntimeValue::Object(a), RuntimeValue::Object(b)) => {
+            let pair = (a.as_ptr() as *const (), b.as_ptr() as *const ());
+            if Rc::ptr_eq(a, b) || stack.contains(&pair) {
+                return true;
+            }
+            stack.push(pair);
+            let (a, b) = (RefCell::borrow(a), RefCell::borrow(b));
+            let equal = a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|((ka, va), (kb, vb))| ka == kb && values_equal(va, vb, stack));
+            stack.pop();
+            equal
+        }
+        (
+            RuntimeValue::HtmlElement { object, property },
+            RuntimeValue::HtmlElement {
+                object: other_object,
+                property: other_property,
+            },
+        ) => object == other_object && property == other_property,
+        _ => false,
+    }
+}
+
+/// 配列の要素を文字列に変換し、separator で連結する。null と undefined の要素は空文字列とする。
+/// 連結中の配列を要素に含む (循環参照の) 場合、その要素も空文字列とする。
+/// https://262.ecma-international.org/#sec-array.prototype.join
+pub(crate) fn join_elements(elements: &Rc<RefCell<Vec<RuntimeValue>>>, separator: &str) -> String {
+    join_array(elements, separator, &mut Vec::new())
+}
+
+/// stack には連結中の配列のポインタを保持し、循環参照の検出に使う。
+fn join_array(
+    elements: &Rc<RefCell<Vec<RuntimeValue>>>,
+    separator: &str,
+    stack: &mut Vec<*const ()>,
+) -> String {
+    let ptr = elements.as_ptr() as *const ();
+    if stack.contains(&ptr) {
+        return String::new();
+    }
+    stack.push(ptr);
+    let joined = RefCell::borrow(elements)
+        .iter()
+        .map(|element| match element {
+            RuntimeValue::Null | RuntimeValue::Undefined => String::new(),
+            // 要素の配列は、配列を文字列に変換する場合と同じく "," で連結する。
+            RuntimeValue::Array(inner) => join_array(inner, ",", stack),
+            _ => element.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(separator);
+    stack.pop();
+    joined
+}
+
+/// 数値を JavaScript の表記に従って文字列に変換する。
+/// https://262.ecma-international.org/#sec-numeric-types-number-tostring
+pub(crate) fn number_to_string(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        if value > 0f64 {
+            return "Infinity".to_string();
+        }
+        return "-Infinity".to_string();
+    }
+    // -0 は 0 と表記する。
+    if value == 0f64 {
+        return "0".to_string();
+    }
+    format!("{}", value)
+}
+
+/// RuntimeValue の値を文字列に変換する。
+impl Display for RuntimeValue {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let s = match self {
+            RuntimeValue::Number(value) => number_to_string(*value),
+            RuntimeValue::StringLiteral(value) => value.to_string(),
+            RuntimeValue::Boolean(value) => value.to_string(),
+            RuntimeValue::Null => "null".to_string(),
+            RuntimeValue::Undefined => "undefined".to_string(),
+            // 配列の要素をカンマで連結する。
+            RuntimeValue::Array(elements) => join_elements(elements, ","),
+            RuntimeValue::Object(_) => "[object Object]".to_string(),
+            RuntimeValue::HtmlElement {
+                object,
+                property: _,
+            } => {
+                format!("HtmlElement: {:?}", object)
             }
         };
         write!(f, "{}", s)
@@ -405,8 +1881,10 @@ impl Display for RuntimeValue {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::renderer::js::ast::JsParser;
-    use crate::renderer::js::token::JsLexer;
+    use crate::renderer::dom::node::NodeKind as DomNodeKind;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::js::time::FakeTimeSource;
 
     /// JavaScript に数値のみが含まれる場合のテスト。
     /// 評価結果として数値 (RuntimeValue::Number) が返るはずである。
@@ -416,9 +1894,9 @@ mod tests {
         let input = "42".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [Some(RuntimeValue::Number(42))];
+        let expected = [Some(RuntimeValue::Number(42f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -435,9 +1913,9 @@ mod tests {
         let input = "1 + 2".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [Some(RuntimeValue::Number(3))];
+        let expected = [Some(RuntimeValue::Number(3f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -454,9 +1932,9 @@ mod tests {
         let input = "2 - 1".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [Some(RuntimeValue::Number(1))];
+        let expected = [Some(RuntimeValue::Number(1f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -475,7 +1953,7 @@ mod tests {
         let input = "var foo=42;".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
         let expected = [None];
         let mut i = 0;
@@ -496,9 +1974,9 @@ mod tests {
         let input = "var foo=42; foo+1".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, Some(RuntimeValue::Number(43))];
+        let expected = [None, Some(RuntimeValue::Number(43f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -517,9 +1995,9 @@ mod tests {
         let input = "var foo=42; foo=1; foo".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, None, Some(RuntimeValue::Number(1))];
+        let expected = [None, None, Some(RuntimeValue::Number(1f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -536,9 +2014,9 @@ mod tests {
         let input = "function foo() { return 42; } foo()+1".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, Some(RuntimeValue::Number(43))];
+        let expected = [None, Some(RuntimeValue::Number(43f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -555,9 +2033,9 @@ mod tests {
         let input = "function foo(a, b) { return a + b; } foo(1, 2) + 3;".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, Some(RuntimeValue::Number(6))];
+        let expected = [None, Some(RuntimeValue::Number(6f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -574,9 +2052,9 @@ mod tests {
         let input = "var a=42; function foo() { var a=1; return a;} foo() + a".to_string();
         let lexer = JsLexer::new(input);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        let ast = parser.parse_ast().expect("failed to parse");
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, None, Some(RuntimeValue::Number(43))];
+        let expected = [None, None, Some(RuntimeValue::Number(43f64))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -585,4 +2063,641 @@ mod tests {
             i += 1;
         }
     }
+
+    /// 組み込み関数 parseInt, parseFloat, Number の呼び出しのテスト
+    #[test]
+    fn test_numeric_builtin_functions() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input =
+            "parseInt(\"ff\", 16) + 1; parseFloat(\"1.5em\") + 1; Number(\"42\") - 2".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            Some(RuntimeValue::Number(256f64)),
+            Some(RuntimeValue::Number(2.5f64)),
+            Some(RuntimeValue::Number(40f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// ユーザが定義した関数は同じ名前の組み込み関数より優先されることを確認する。
+    #[test]
+    fn test_user_function_shadows_builtin() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "function parseInt(a) { return 7; } parseInt(\"1\")".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [None, Some(RuntimeValue::Number(7f64))];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 数値を文字列に変換する際、JavaScript の表記に従うことを確認する。
+    #[test]
+    fn test_number_to_string() {
+        assert_eq!("3", RuntimeValue::Number(3f64).to_string());
+        assert_eq!("0.5", RuntimeValue::Number(0.5f64).to_string());
+        assert_eq!("NaN", RuntimeValue::Number(f64::NAN).to_string());
+        assert_eq!(
+            "-Infinity",
+            RuntimeValue::Number(f64::NEG_INFINITY).to_string()
+        );
+    }
+
+    /// 配列とオブジェクトのリテラル、プロパティの読み書きのテスト
+    #[test]
+    fn test_array_and_object() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var a = [1, 2]; a[2] = 3; a.length + a[2]; var o = {x: {y: 5}}; o.x.y = 6; o[\"x\"].y; o.z".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            None,
+            Some(RuntimeValue::Number(6f64)),
+            None,
+            None,
+            Some(RuntimeValue::Number(6f64)),
+            Some(RuntimeValue::Undefined),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// JSON.parse と JSON.stringify の呼び出しのテスト
+    #[test]
+    fn test_json() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var data = JSON.parse('{\"items\": [1, 2], \"ok\": true}'); data.items[1] + 1; data.ok; JSON.stringify({a: [1, null, \"b\"], c: undefined})".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::Number(3f64)),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::StringLiteral(
+                "{\"a\":[1,null,\"b\"]}".to_string(),
+            )),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// Date.now が TimeSource の時刻を返すことを確認する。
+    #[test]
+    fn test_date_now() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var start = Date.now(); Date.now() - start".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let source = Rc::new(FakeTimeSource {
+            now: core::cell::Cell::new(1_000),
+        });
+        runtime.set_time_source(source.clone());
+
+        runtime.eval(&Some(ast.body()[0].clone()), runtime.env.clone());
+        source.now.set(1_250);
+        let result = runtime.eval(&Some(ast.body()[1].clone()), runtime.env.clone());
+        assert_eq!(Some(RuntimeValue::Number(250f64)), result);
+    }
+
+    /// setTimeout で登録したコールバックが、実行時刻に達した後に実行時刻の順に呼ばれることを確認する。
+    #[test]
+    fn test_set_timeout() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var log = \"\"; function a() { log = log + \"a\"; } function b() { log = log + \"b\"; } function c() { log = log + \"c\"; } setTimeout(a, 100); setTimeout(b, 50); var t = setTimeout(c, 10); clearTimeout(t);".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let source = Rc::new(FakeTimeSource {
+            now: core::cell::Cell::new(0),
+        });
+        runtime.set_time_source(source.clone());
+        runtime.execute(&ast);
+
+        let log = |runtime: &JsRuntime| runtime.env.borrow_mut().get_variable("log".to_string());
+        assert!(!runtime.run_expired_timers());
+        assert_eq!(
+            Some(RuntimeValue::StringLiteral("".to_string())),
+            log(&runtime)
+        );
+
+        source.now.set(200);
+        assert!(runtime.run_expired_timers());
+        assert_eq!(
+            Some(RuntimeValue::StringLiteral("ba".to_string())),
+            log(&runtime)
+        );
+        assert!(!runtime.has_pending_timers());
+    }
+
+    /// switch 文のテスト
+    /// 一致した case 節から break 文までフォールスルーし、一致しない場合は default 節を評価することを確認する。
+    #[test]
+    fn test_switch() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "function f(x) { var log = \"\"; switch (x) { case 1: log = log + \"a\"; case 2: log = log + \"b\"; break; default: log = log + \"d\"; case 3: log = log + \"c\"; } return log; } f(1); f(2); f(3); f(4)".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::StringLiteral("ab".to_string())),
+            Some(RuntimeValue::StringLiteral("b".to_string())),
+            Some(RuntimeValue::StringLiteral("c".to_string())),
+            Some(RuntimeValue::StringLiteral("dc".to_string())),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// return 文の後の文は評価されないことを確認する。
+    #[test]
+    fn test_return_stops_function() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input =
+            "function f(x) { switch (x) { case 1: return \"one\"; } return \"other\"; } f(1); f(2)"
+                .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::StringLiteral("one".to_string())),
+            Some(RuntimeValue::StringLiteral("other".to_string())),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// ループと break 文、continue 文のテスト
+    /// continue 文は現在の繰り返しを飛ばし、break 文はループを終了させることを確認する。
+    #[test]
+    fn test_loop_break_continue() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var sum = 0; for (var i = 0; i < 10; i = i + 1) { if (i == 2) continue; if (i >= 5) break; sum = sum + i; } sum; var n = 0; while (true) { n = n + 1; if (n === 3) { break; } } n".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            None,
+            Some(RuntimeValue::Number(8f64)),
+            None,
+            None,
+            Some(RuntimeValue::Number(3f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// ループ内の switch 文の continue 文は、ループの次の繰り返しに進むことを確認する。
+    #[test]
+    fn test_continue_in_switch() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var log = \"\"; for (var i = 0; i < 4; i = i + 1) { switch (i) { case 1: continue; case 2: break; } log = log + i; } log".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+        assert_eq!(
+            Some(RuntimeValue::StringLiteral("023".to_string())),
+            runtime.env.borrow_mut().get_variable("log".to_string())
+        );
+    }
+
+    /// 等価演算子と関係演算子のテスト
+    #[test]
+    fn test_comparison() {
+        let cases = [
+            (
+                RuntimeValue::Number(1f64),
+                RuntimeValue::StringLiteral("1".to_string()),
+                true,
+                false,
+            ),
+            (RuntimeValue::Null, RuntimeValue::Undefined, true, false),
+            (RuntimeValue::Null, RuntimeValue::Number(0f64), false, false),
+            (
+                RuntimeValue::new_array([RuntimeValue::Number(1f64)].to_vec()),
+                RuntimeValue::StringLiteral("2".to_string()),
+                false,
+                false,
+            ),
+            (
+                RuntimeValue::Boolean(true),
+                RuntimeValue::Number(1f64),
+                true,
+                false,
+            ),
+            (
+                RuntimeValue::Number(f64::NAN),
+                RuntimeValue::Number(f64::NAN),
+                false,
+                false,
+            ),
+            (
+                RuntimeValue::StringLiteral("a".to_string()),
+                RuntimeValue::StringLiteral("a".to_string()),
+                true,
+                true,
+            ),
+        ];
+        for (a, b, loose, strict) in cases {
+            assert_eq!(loose, a.loose_equals(&b), "{:?} == {:?}", a, b);
+            assert_eq!(strict, a.strict_equals(&b), "{:?} === {:?}", a, b);
+        }
+
+        let array = RuntimeValue::new_array(Vec::new());
+        assert!(array.strict_equals(&array.clone()));
+        assert!(!array.strict_equals(&RuntimeValue::new_array(Vec::new())));
+
+        assert_eq!(
+            Some(Ordering::Less),
+            RuntimeValue::StringLiteral("10".to_string())
+                .compare(&RuntimeValue::StringLiteral("9".to_string()))
+        );
+        assert_eq!(
+            Some(Ordering::Greater),
+            RuntimeValue::Number(10f64).compare(&RuntimeValue::StringLiteral("9".to_string()))
+        );
+        assert_eq!(
+            None,
+            RuntimeValue::Number(1f64).compare(&RuntimeValue::Undefined)
+        );
+    }
+
+    /// throw 文で送出した値や、ランタイムのエラーを catch 節で捕捉できることを確認する。
+    #[test]
+    fn test_try_catch() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var r = 0; var o = null; try { throw 'boom'; r = 1; } catch (e) { r = e; } finally { r = r + '!'; } r; try { undefinedFunction(); } catch (e) { r = e.name; } r; try { o.x; } catch (e) { r = e.name; } r; try { JSON.parse('{'); } catch (e) { r = e.name; } r; try { throw new RangeError('too big'); } catch (e) { r = e.message; } r".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            None,
+            None,
+            Some(RuntimeValue::StringLiteral("boom!".to_string())),
+            None,
+            Some(RuntimeValue::StringLiteral("ReferenceError".to_string())),
+            None,
+            Some(RuntimeValue::StringLiteral("TypeError".to_string())),
+            None,
+            Some(RuntimeValue::StringLiteral("SyntaxError".to_string())),
+            None,
+            Some(RuntimeValue::StringLiteral("too big".to_string())),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// finally ブロックの return 文が例外より優先されること、
+    /// 引数が省略された仮引数が undefined となることを確認する。
+    #[test]
+    fn test_finally_and_missing_arguments() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "function f() { try { throw 'x'; } finally { return 'f'; } } f(); function g(a, b) { return b; } g(1)".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::StringLiteral("f".to_string())),
+            None,
+            Some(RuntimeValue::Undefined),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 捕捉されない例外が送出された場合、残りの文を実行しないことを確認する。
+    #[test]
+    fn test_uncaught_exception() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var a = 1; throw new Error('stop'); a = 2;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+        assert_eq!(
+            Some(RuntimeValue::Number(1f64)),
+            runtime.env.borrow_mut().get_variable("a".to_string())
+        );
+    }
+
+    /// 関数宣言と var による変数宣言が巻き上げられることを確認する。
+    #[test]
+    fn test_hoisting() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var r1 = later(2); var r2 = typeofX; if (true) { var typeofX = 'set'; } var r3 = inner(); function later(a) { return a + 1; } function inner() { var before = v; var v = 5; return before; } var x = 1; var x;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        let get = |name: &str| runtime.env.borrow_mut().get_variable(name.to_string());
+        assert_eq!(Some(RuntimeValue::Number(3f64)), get("r1"));
+        assert_eq!(Some(RuntimeValue::Undefined), get("r2"));
+        assert_eq!(Some(RuntimeValue::Undefined), get("r3"));
+        assert_eq!(
+            Some(RuntimeValue::StringLiteral("set".to_string())),
+            get("typeofX")
+        );
+        // 初期化式のない再宣言は値を変更しない。
+        assert_eq!(Some(RuntimeValue::Number(1f64)), get("x"));
+    }
+
+    /// 複合代入演算子が変数、配列の要素、オブジェクトのプロパティの値を更新することを確認する。
+    #[test]
+    fn test_compound_assignment() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var a = 10; a += 5; a -= 3; a *= 2; a /= 4; a; var s = 'x'; s += 1; s; var arr = [1, 2]; arr[1] += 40; arr[1]; var o = {n: 3}; o.n *= o.n; o.n".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(RuntimeValue::Number(6f64)),
+            None,
+            None,
+            Some(RuntimeValue::StringLiteral("x1".to_string())),
+            None,
+            None,
+            Some(RuntimeValue::Number(42f64)),
+            None,
+            None,
+            Some(RuntimeValue::Number(9f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 前置と後置のインクリメント、デクリメントの値と、変数やプロパティの更新を確認する。
+    #[test]
+    fn test_update_expression() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var i = 5; i++; i; ++i; --i; i--; i; var o = {n: 1}; o.n++; o.n; var sum = 0; for (var j = 0; j < 4; j++) { sum += j; } sum".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::Number(5f64)),
+            Some(RuntimeValue::Number(6f64)),
+            Some(RuntimeValue::Number(7f64)),
+            Some(RuntimeValue::Number(6f64)),
+            Some(RuntimeValue::Number(6f64)),
+            Some(RuntimeValue::Number(5f64)),
+            None,
+            Some(RuntimeValue::Number(1f64)),
+            Some(RuntimeValue::Number(2f64)),
+            None,
+            None,
+            Some(RuntimeValue::Number(6f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 条件演算子が条件に応じて一方の式のみを評価することを確認する。
+    #[test]
+    fn test_conditional_expression() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var n = 0; var a = 1 < 2 ? 'yes' : n++; a; n; var b = null ? 1 : 0 ? 2 : 3; b"
+            .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            None,
+            Some(RuntimeValue::StringLiteral("yes".to_string())),
+            Some(RuntimeValue::Number(0f64)),
+            None,
+            Some(RuntimeValue::Number(3f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 配列のメソッドによって配列を操作できることを確認する。
+    #[test]
+    fn test_array_methods() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var list = []; list.push('a', 'b'); list.push('c'); list.length; list.indexOf('b'); list.indexOf('z'); list.pop(); list.join(' - '); list.join(); var o = {items: [1]}; o.items.push(2); o.items.join('')".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::Number(2f64)),
+            Some(RuntimeValue::Number(3f64)),
+            Some(RuntimeValue::Number(3f64)),
+            Some(RuntimeValue::Number(1f64)),
+            Some(RuntimeValue::Number(-1f64)),
+            Some(RuntimeValue::StringLiteral("c".to_string())),
+            Some(RuntimeValue::StringLiteral("a - b".to_string())),
+            Some(RuntimeValue::StringLiteral("a,b".to_string())),
+            None,
+            Some(RuntimeValue::Number(2f64)),
+            Some(RuntimeValue::StringLiteral("12".to_string())),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 値を返さない関数の呼び出しを組み込み関数に渡した場合、undefined として引数の位置が保たれることを確認する。
+    #[test]
+    fn test_builtin_undefined_argument() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "function f() { } parseInt(f(), 16) + ''; parseInt('12', f())".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::StringLiteral("NaN".to_string())),
+            Some(RuntimeValue::Number(12f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 配列の長さから大きく離れたインデックスへの代入は、要素を確保せずに RangeError となることを確認する。
+    #[test]
+    fn test_array_index_too_large() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "var a = []; var r = ''; try { a[4000000000] = 1; } catch (e) { r = e.name; } r; a.length; a[3] = 1; a.length".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            None,
+            None,
+            Some(RuntimeValue::StringLiteral("RangeError".to_string())),
+            Some(RuntimeValue::Number(0f64)),
+            None,
+            Some(RuntimeValue::Number(4f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+    }
+
+    /// 自身を要素に含む配列の連結と文字列への変換で、循環する要素が空文字列となることを確認する。
+    #[test]
+    fn test_cyclic_array_join() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input =
+            "var a = [0]; a.push(a); a.push(1); a.join(); a + ''; var b = [a]; b.join('-'); a == a"
+                .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::Number(2f64)),
+            Some(RuntimeValue::Number(3f64)),
+            Some(RuntimeValue::StringLiteral("0,,1".to_string())),
+            Some(RuntimeValue::StringLiteral("0,,1".to_string())),
+            None,
+            Some(RuntimeValue::StringLiteral("0,,1".to_string())),
+            Some(RuntimeValue::Boolean(true)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+        }
+
+        // 別々の循環する配列どうしの比較も終了する。
+        let other = RuntimeValue::Array(Rc::new(RefCell::new(vec![RuntimeValue::Number(0f64)])));
+        if let RuntimeValue::Array(elements) = &other {
+            elements.borrow_mut().push(other.clone());
+            elements.borrow_mut().push(RuntimeValue::Number(1f64));
+        }
+        let a = RefCell::borrow(&runtime.env)
+            .get_variable("a".to_string())
+            .unwrap();
+        assert_eq!(a, other);
+    }
+
+    /// window.scrollTo がスクロールを要求し、window.scrollY がスクロール位置を返すことを確認する。
+    #[test]
+    fn test_window_scroll() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let input = "window.scrollY; window.scrollTo(0, 120); window.scrollY; window.scrollTo({top: 0 - 5}); window.scrollX".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom);
+        runtime.set_scroll_position(10, 40);
+
+        let expected = [
+            Some(RuntimeValue::Number(40f64)),
+            Some(RuntimeValue::Undefined),
+            Some(RuntimeValue::Number(120f64)),
+            Some(RuntimeValue::Undefined),
+            Some(RuntimeValue::Number(0f64)),
+        ];
+        for (i, node) in ast.body().iter().enumerate() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+            if i == 2 {
+                assert_eq!(Some((0, 120)), runtime.take_scroll_request());
+                assert_eq!(None, runtime.take_scroll_request());
+            }
+        }
+        // 省略された left は現在の位置のままとなる。
+        assert_eq!(Some((0, 0)), runtime.take_scroll_request());
+    }
+
+    /// addEventListener で登録した関数とイベントハンドラ属性が、送出したイベントによって呼ばれることを確認する。
+    /// イベントハンドラ属性が false を返した場合、既定の動作が取り消される。
+    /// removeEventListener はキャプチャの指定が一致するリスナーのみを取り除く。
+    #[test]
+    fn test_dispatch_event() {
+        let html = "<html><body><div id=\"outer\"><a id=\"link\" onclick='log = log + \"i\"; return false'>x</a></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow_mut().document();
+        let input = "var log = \"\"; function f(e) { log = log + e.type; } function g(e) { log = log + \"c\"; } var outer = document.getElementById(\"outer\"); outer.addEventListener(\"click\", f); outer.addEventListener(\"click\", g, true); outer.addEventListener(\"click\", g, false); outer.removeEventListener(\"click\", g);".to_string();
+        let mut parser = JsParser::new(JsLexer::new(input));
+        let ast = parser.parse_ast().expect("failed to parse");
+        let mut runtime = JsRuntime::new(dom.clone());
+        runtime.execute(&ast);
+
+        let link = get_element_by_id(Some(dom), &"link".to_string()).expect("failed to get a link");
+        let text = link
+            .borrow_mut()
+            .first_child()
+            .expect("failed to get a text");
+        let mut event = Event::new("click", true);
+        assert!(!runtime.dispatch_event(&text, &mut event));
+        assert_eq!(
+            Some(RuntimeValue::StringLiteral("ciclick".to_string())),
+            runtime.env.borrow_mut().get_variable("log".to_string())
+        );
+    }
 }
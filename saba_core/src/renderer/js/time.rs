@@ -0,0 +1,92 @@
+use alloc::rc::Rc;
+use core::cell::Cell;
+use core::fmt::Debug;
+
+/// 現在時刻を提供するトレイト
+/// OS のクロックなど、実行環境ごとに時刻の取得方法を差し替えられるようにする。
+pub trait TimeSource: Debug {
+    /// UNIX エポックからの経過時間をミリ秒で返す。
+    fn now(&self) -> u64;
+}
+
+/// 常に 0 を返す TimeSource
+/// 時刻を取得する手段が与えられていない場合に使う。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullTimeSource;
+
+impl TimeSource for NullTimeSource {
+    fn now(&self) -> u64 {
+        0
+    }
+}
+
+/// 単調増加する時刻を返す時計
+/// TimeSource の返す時刻が巻き戻った場合でも、以前に返した時刻より小さい値は返さない。
+#[derive(Debug, Clone)]
+pub struct MonotonicClock {
+    source: Rc<dyn TimeSource>,
+    last: Cell<u64>,
+}
+
+impl MonotonicClock {
+    pub fn new(source: Rc<dyn TimeSource>) -> Self {
+        Self {
+            source,
+            last: Cell::new(0),
+        }
+    }
+
+    /// 現在時刻をミリ秒で返す。
+    pub fn now(&self) -> u64 {
+        let now = self.source.now().max(self.last.get());
+        self.last.set(now);
+        now
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new(Rc::new(NullTimeSource))
+    }
+}
+
+/// テスト用に任意の時刻を返す TimeSource
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct FakeTimeSource {
+    pub(crate) now: Cell<u64>,
+}
+
+#[cfg(test)]
+impl TimeSource for FakeTimeSource {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// TimeSource の時刻が巻き戻っても、MonotonicClock の時刻は減少しないことを確認する。
+    #[test]
+    fn test_monotonic_clock() {
+        let source = Rc::new(FakeTimeSource {
+            now: Cell::new(100),
+        });
+        let clock = MonotonicClock::new(source.clone());
+        assert_eq!(100, clock.now());
+
+        source.now.set(250);
+        assert_eq!(250, clock.now());
+
+        source.now.set(200);
+        assert_eq!(250, clock.now());
+    }
+
+    /// TimeSource を指定しない場合、時刻は 0 となる。
+    #[test]
+    fn test_default_clock() {
+        assert_eq!(0, MonotonicClock::default().now());
+    }
+}
@@ -3,7 +3,16 @@ use alloc::string::ToString;
 use alloc::vec::Vec;
 
 /// 予約語
-static RESERVED_WEORDS: [&str; 3] = ["var", "function", "return"];
+static RESERVED_WEORDS: [&str; 20] = [
+    "var", "function", "return", "true", "false", "null", "switch", "case", "default", "break",
+    "continue", "if", "else", "while", "for", "throw", "try", "catch", "finally", "new",
+];
+
+/// 2文字以上の記号
+/// 長い記号から順に一致を確認するため、同じ文字から始まる記号は長いものを先に並べる。
+static MULTI_CHAR_PUNCTUATORS: [&str; 12] = [
+    "===", "!==", "==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "++", "--",
+];
 
 /// トークン列挙型
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -23,6 +32,9 @@ pub enum Token {
     /// 文字列を表す。
     /// https://262.ecma-international.org/#sec-literals-string-literals
     StringLiteral(String),
+    /// 2文字以上の記号を表す。
+    /// https://262.ecma-international.org/#sec-punctuators
+    MultiCharPunctuator(String),
 }
 
 /// JsLexer 構造体
@@ -64,7 +76,12 @@ impl JsLexer {
     }
 
     /// 現在の位置 (pos) から始まる文字が予約語と一致する場合、true を返す。
+    /// 予約語の直後に変数として受け入れ可能な文字が続く場合 (例: variable)、予約語とはみなさない。
     fn contains(&self, keyword: &str) -> bool {
+        if self.pos + keyword.len() > self.input.len() {
+            return false;
+        }
+
         for i in 0..keyword.len() {
             if keyword
                 .chars()
@@ -75,7 +92,11 @@ impl JsLexer {
                 return false;
             }
         }
-        true
+
+        match self.input.get(self.pos + keyword.len()) {
+            Some(c) => !(c.is_ascii_alphanumeric() || *c == '_' || *c == '$'),
+            None => true,
+        }
     }
 
     /// 予約語の場合、Token::Keyword トークンを返す。
@@ -88,6 +109,23 @@ impl JsLexer {
         None
     }
 
+    /// 現在の位置 (pos) から始まる文字列が2文字以上の記号と一致する場合、その記号を返す。
+    fn check_multi_char_punctuator(&self) -> Option<String> {
+        for punctuator in MULTI_CHAR_PUNCTUATORS {
+            let len = punctuator.chars().count();
+            if self.pos + len > self.input.len() {
+                continue;
+            }
+            if punctuator
+                .chars()
+                .eq(self.input[self.pos..self.pos + len].iter().copied())
+            {
+                return Some(punctuator.to_string());
+            }
+        }
+        None
+    }
+
     /// 数字でも記号でもなく、かつ、変数として受け入れ可能な文字列で始まった場合、変数が終了するまで入力の文字列を進める。
     fn consume_identifier(&mut self) -> String {
         let mut result = String::new();
@@ -97,7 +135,10 @@ impl JsLexer {
                 return result;
             }
 
-            if self.input[self.pos].is_ascii_alphanumeric() || self.input[self.pos] == '$' {
+            if self.input[self.pos].is_ascii_alphanumeric()
+                || self.input[self.pos] == '$'
+                || self.input[self.pos] == '_'
+            {
                 result.push(self.input[self.pos]);
                 self.pos += 1;
             } else {
@@ -106,9 +147,12 @@ impl JsLexer {
         }
     }
 
-    /// ダブルクォートの場合、文字列として解釈する。
+    /// ダブルクォートまたはシングルクォートの場合、同じ引用符が現れるまでを文字列として解釈する。
+    /// バックスラッシュから始まるエスケープシーケンスは対応する文字に変換する。
+    /// https://262.ecma-international.org/#sec-literals-string-literals
     fn consume_string(&mut self) -> String {
         let mut result = String::new();
+        let quote = self.input[self.pos];
         self.pos += 1;
 
         loop {
@@ -116,15 +160,52 @@ impl JsLexer {
                 return result;
             }
 
-            if self.input[self.pos] == '"' {
+            let c = self.input[self.pos];
+            if c == quote {
                 self.pos += 1;
                 return result;
             }
 
-            result.push(self.input[self.pos]);
+            if c == '\\' && self.pos + 1 < self.input.len() {
+                self.pos += 1;
+                let escaped = self.input[self.pos];
+                match escaped {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    'r' => result.push('\r'),
+                    'b' => result.push('\u{08}'),
+                    'f' => result.push('\u{0c}'),
+                    'v' => result.push('\u{0b}'),
+                    '0' => result.push('\0'),
+                    'u' => match self.consume_unicode_escape() {
+                        Some(u) => result.push(u),
+                        None => result.push('u'),
+                    },
+                    _ => result.push(escaped),
+                }
+                self.pos += 1;
+                continue;
+            }
+
+            result.push(c);
             self.pos += 1;
         }
     }
+
+    /// \uXXXX 形式のエスケープシーケンスを解釈する。
+    /// 呼び出し時、pos は 'u' を指している。解釈に成功した場合、pos は最後の16進数の文字を指す。
+    fn consume_unicode_escape(&mut self) -> Option<char> {
+        if self.pos + 4 >= self.input.len() {
+            return None;
+        }
+
+        let mut code = 0;
+        for i in 1..=4 {
+            code = code * 16 + self.input[self.pos + i].to_digit(16)?;
+        }
+        self.pos += 4;
+        char::from_u32(code)
+    }
 }
 
 impl Iterator for JsLexer {
@@ -137,7 +218,11 @@ impl Iterator for JsLexer {
         }
 
         // ホワイトスペースまたは改行文字が続く限り、次の位置に進める。
-        while self.input[self.pos] == ' ' || self.input[self.pos] == '\n' {
+        while self.input[self.pos] == ' '
+            || self.input[self.pos] == '\n'
+            || self.input[self.pos] == '\t'
+            || self.input[self.pos] == '\r'
+        {
             self.pos += 1;
 
             if self.pos >= self.input.len() {
@@ -152,11 +237,18 @@ impl Iterator for JsLexer {
             return token;
         }
 
+        // 2文字以上の記号の場合、MultiCharPunctuator トークンを返す。
+        if let Some(punctuator) = self.check_multi_char_punctuator() {
+            self.pos += punctuator.chars().count();
+            return Some(Token::MultiCharPunctuator(punctuator));
+        }
+
         let c = self.input[self.pos];
 
         let token = match c {
             // 記号トークン
-            '+' | '-' | ';' | '=' | '(' | ')' | '{' | '}' | ',' | '.' => {
+            '+' | '-' | ';' | '=' | '(' | ')' | '{' | '}' | ',' | '.' | '[' | ']' | ':' | '<'
+            | '>' | '!' | '?' => {
                 let t = Token::Punctuator(c);
                 self.pos += 1;
                 t
@@ -166,7 +258,7 @@ impl Iterator for JsLexer {
             // 変数として受け入れ可能な文字
             'a'..='z' | 'A'..='Z' | '_' | '$' => Token::Identifier(self.consume_identifier()),
             // 文字列の場合
-            '"' => Token::StringLiteral(self.consume_string()),
+            '"' | '\'' => Token::StringLiteral(self.consume_string()),
 
             _ => unimplemented!("char {:?} is not supported yet", c),
         };
@@ -275,4 +367,128 @@ mod tests {
         }
         assert!(lexer.peek().is_none());
     }
+
+    /// 予約語から始まる変数名は変数として扱い、true, false, null は予約語として扱うことを確認する。
+    #[test]
+    fn test_keyword_prefix() {
+        let input = "variable = true; nullable = null".to_string();
+        let mut lexer = JsLexer::new(input).peekable();
+        let expected = [
+            Token::Identifier("variable".to_string()),
+            Token::Punctuator('='),
+            Token::Keyword("true".to_string()),
+            Token::Punctuator(';'),
+            Token::Identifier("nullable".to_string()),
+            Token::Punctuator('='),
+            Token::Keyword("null".to_string()),
+        ]
+        .to_vec();
+        let mut i = 0;
+        while lexer.peek().is_some() {
+            assert_eq!(Some(expected[i].clone()), lexer.next());
+            i += 1;
+        }
+        assert_eq!(expected.len(), i);
+    }
+
+    /// シングルクォートの文字列とエスケープシーケンスのテスト
+    #[test]
+    fn test_string_escape() {
+        let input = r#"'{"a": 1}' "say \"hi\"\n" "\u3042""#.to_string();
+        let mut lexer = JsLexer::new(input).peekable();
+        let expected = [
+            Token::StringLiteral("{\"a\": 1}".to_string()),
+            Token::StringLiteral("say \"hi\"\n".to_string()),
+            Token::StringLiteral("あ".to_string()),
+        ]
+        .to_vec();
+        let mut i = 0;
+        while lexer.peek().is_some() {
+            assert_eq!(Some(expected[i].clone()), lexer.next());
+            i += 1;
+        }
+        assert_eq!(expected.len(), i);
+    }
+
+    /// 比較演算子のトークン化
+    /// 長い記号が優先して一致することを確認する。
+    #[test]
+    fn test_comparison_operators() {
+        let input = "a === b != c <= 1 < 2 = 3".to_string();
+        let mut lexer = JsLexer::new(input).peekable();
+        let expected = [
+            Token::Identifier("a".to_string()),
+            Token::MultiCharPunctuator("===".to_string()),
+            Token::Identifier("b".to_string()),
+            Token::MultiCharPunctuator("!=".to_string()),
+            Token::Identifier("c".to_string()),
+            Token::MultiCharPunctuator("<=".to_string()),
+            Token::Number(1),
+            Token::Punctuator('<'),
+            Token::Number(2),
+            Token::Punctuator('='),
+            Token::Number(3),
+        ]
+        .to_vec();
+        let mut i = 0;
+        while lexer.peek().is_some() {
+            assert_eq!(Some(expected[i].clone()), lexer.next());
+            i += 1;
+        }
+        assert_eq!(expected.len(), i);
+    }
+
+    /// 複合代入演算子のテスト
+    #[test]
+    fn test_compound_assignment_operators() {
+        let input = "a += 1; b -= c *= d /= 2".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = [
+            Token::Identifier("a".to_string()),
+            Token::MultiCharPunctuator("+=".to_string()),
+            Token::Number(1),
+            Token::Punctuator(';'),
+            Token::Identifier("b".to_string()),
+            Token::MultiCharPunctuator("-=".to_string()),
+            Token::Identifier("c".to_string()),
+            Token::MultiCharPunctuator("*=".to_string()),
+            Token::Identifier("d".to_string()),
+            Token::MultiCharPunctuator("/=".to_string()),
+            Token::Number(2),
+        ]
+        .to_vec();
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+    }
+
+    /// インクリメント演算子とデクリメント演算子のテスト
+    #[test]
+    fn test_update_operators() {
+        let input = "i++ + --j".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = [
+            Token::Identifier("i".to_string()),
+            Token::MultiCharPunctuator("++".to_string()),
+            Token::Punctuator('+'),
+            Token::MultiCharPunctuator("--".to_string()),
+            Token::Identifier("j".to_string()),
+        ]
+        .to_vec();
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+    }
+
+    /// 条件演算子のテスト
+    #[test]
+    fn test_conditional_operator() {
+        let input = "a?1:'b'".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = [
+            Token::Identifier("a".to_string()),
+            Token::Punctuator('?'),
+            Token::Number(1),
+            Token::Punctuator(':'),
+            Token::StringLiteral("b".to_string()),
+        ]
+        .to_vec();
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+    }
 }
@@ -6,6 +6,7 @@ use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 use core::cell::RefCell;
 
 /// ComputedValue 構造体
@@ -16,9 +17,29 @@ pub struct ComputedStyle {
     color: Option<Color>,
     display: Option<DisplayType>,
     font_size: Option<FontSize>,
+    // em やパーセンテージで指定された、親の文字の大きさに対する比率。defaulting で px 単位の大きさに変換する。
+    font_size_ratio: Option<f64>,
+    font_weight: Option<FontWeight>,
+    font_style: Option<FontStyle>,
     text_decoration: Option<TextDecoration>,
+    white_space: Option<WhiteSpace>,
+    visibility: Option<Visibility>,
+    float: Option<Float>,
+    flex_direction: Option<FlexDirection>,
+    justify_content: Option<JustifyContent>,
+    // 値が None の場合、auto (内容や親の大きさから決める) を表す。
     height: Option<f64>,
     width: Option<f64>,
+    // 上、右、下、左の辺ごとのマージンとパディング (px 単位)
+    margin: [Option<f64>; 4],
+    padding: [Option<f64>; 4],
+    // 上、右、下、左の辺ごとの線の幅 (px 単位)、線の種類、線の色
+    border_width: [Option<f64>; 4],
+    border_style: [Option<BorderStyle>; 4],
+    // 値が None の場合、currentcolor (color プロパティの値) を表す。
+    border_color: [Option<Color>; 4],
+    // inherit、initial、unset キーワードが指定されたプロパティと、そのキーワード
+    keywords: Vec<(String, CssWideKeyword)>,
 }
 
 impl ComputedStyle {
@@ -28,12 +49,38 @@ impl ComputedStyle {
             color: None,
             display: None,
             font_size: None,
+            font_size_ratio: None,
+            font_weight: None,
+            font_style: None,
             text_decoration: None,
+            white_space: None,
+            visibility: None,
+            float: None,
+            flex_direction: None,
+            justify_content: None,
             height: None,
             width: None,
+            margin: [None; 4],
+            padding: [None; 4],
+            border_width: [None; 4],
+            border_style: [None; 4],
+            border_color: [None, None, None, None],
+            keywords: Vec::new(),
         }
     }
 
+    /// プロパティに inherit、initial、unset キーワードを指定する。
+    /// キーワードは defaulting で値に置き換え、それまでにカスケードで設定した値より優先する。
+    pub fn set_keyword(&mut self, property: &str, keyword: CssWideKeyword) {
+        self.remove_keyword(property);
+        self.keywords.push((property.to_string(), keyword));
+    }
+
+    /// プロパティに指定したキーワードを取り除く。キーワードより後の宣言で値を設定するときに呼ぶ。
+    pub fn remove_keyword(&mut self, property: &str) {
+        self.keywords.retain(|(p, _)| p != property);
+    }
+
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = Some(color);
     }
@@ -63,35 +110,173 @@ impl ComputedStyle {
             .expect("failed to access CSS property: display")
     }
 
+    pub fn set_font_size(&mut self, font_size: FontSize) {
+        self.font_size = Some(font_size);
+        self.font_size_ratio = None;
+    }
+
+    /// 親の文字の大きさに対する比率で文字の大きさを設定する。1.5em や 150% は 1.5 となる。
+    pub fn set_relative_font_size(&mut self, ratio: f64) {
+        self.font_size = None;
+        self.font_size_ratio = Some(ratio);
+    }
+
     pub fn font_size(&self) -> FontSize {
         self.font_size
             .expect("failed to access CSS property: fond_size")
     }
 
+    pub fn set_font_weight(&mut self, font_weight: FontWeight) {
+        self.font_weight = Some(font_weight);
+    }
+
+    pub fn font_weight(&self) -> FontWeight {
+        self.font_weight
+            .expect("failed to access CSS property: font_weight")
+    }
+
+    pub fn font_style(&self) -> FontStyle {
+        self.font_style
+            .expect("failed to access CSS property: font_style")
+    }
+
+    pub fn set_text_decoration(&mut self, text_decoration: TextDecoration) {
+        self.text_decoration = Some(text_decoration);
+    }
+
     pub fn text_decoration(&self) -> TextDecoration {
         self.text_decoration
             .expect("failed to access CSS property: text_decoration")
     }
 
-    pub fn set_height(&mut self, height: f64) {
-        self.height = Some(height);
+    pub fn white_space(&self) -> WhiteSpace {
+        self.white_space
+            .expect("failed to access CSS property: white_space")
+    }
+
+    pub fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = Some(visibility);
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+            .expect("failed to access CSS property: visibility")
+    }
+
+    pub fn set_float(&mut self, float: Float) {
+        self.float = Some(float);
+    }
+
+    pub fn float(&self) -> Float {
+        self.float.expect("failed to access CSS property: float")
+    }
+
+    pub fn set_flex_direction(&mut self, flex_direction: FlexDirection) {
+        self.flex_direction = Some(flex_direction);
+    }
+
+    pub fn flex_direction(&self) -> FlexDirection {
+        self.flex_direction
+            .expect("failed to access CSS property: flex_direction")
+    }
+
+    pub fn set_justify_content(&mut self, justify_content: JustifyContent) {
+        self.justify_content = Some(justify_content);
+    }
+
+    pub fn justify_content(&self) -> JustifyContent {
+        self.justify_content
+            .expect("failed to access CSS property: justify_content")
+    }
+
+    /// 高さを px 単位で設定する。None の場合は auto とする。
+    pub fn set_height(&mut self, height: Option<f64>) {
+        self.height = height;
     }
 
-    pub fn height(&self) -> f64 {
-        self.height.expect("failed to access CSS property: height")
+    /// 高さを px 単位で返す。auto の場合は None を返す。
+    pub fn height(&self) -> Option<f64> {
+        self.height
     }
 
-    pub fn set_width(&mut self, width: f64) {
-        self.width = Some(width)
+    /// 横幅を px 単位で設定する。None の場合は auto とする。
+    pub fn set_width(&mut self, width: Option<f64>) {
+        self.width = width
     }
 
-    pub fn width(&self) -> f64 {
-        self.width.expect("failed to access CSS property: width")
+    /// 横幅を px 単位で返す。auto の場合は None を返す。
+    pub fn width(&self) -> Option<f64> {
+        self.width
+    }
+
+    pub fn set_margin(&mut self, side: Side, margin: f64) {
+        self.margin[side as usize] = Some(margin);
+    }
+
+    /// 辺のマージンを px 単位で返す。
+    pub fn margin(&self, side: Side) -> f64 {
+        self.margin[side as usize].expect("failed to access CSS property: margin")
+    }
+
+    pub fn set_padding(&mut self, side: Side, padding: f64) {
+        self.padding[side as usize] = Some(padding);
+    }
+
+    /// 辺のパディングを px 単位で返す。
+    pub fn padding(&self, side: Side) -> f64 {
+        self.padding[side as usize].expect("failed to access CSS property: padding")
+    }
+
+    pub fn set_border_width(&mut self, side: Side, width: f64) {
+        self.border_width[side as usize] = Some(width);
+    }
+
+    /// 辺の線の幅を px 単位で返す。線の種類が none または hidden の場合は 0 になる。
+    pub fn border_width(&self, side: Side) -> f64 {
+        self.border_width[side as usize].expect("failed to access CSS property: border_width")
+    }
+
+    pub fn set_border_style(&mut self, side: Side, style: BorderStyle) {
+        self.border_style[side as usize] = Some(style);
+    }
+
+    pub fn border_style(&self, side: Side) -> BorderStyle {
+        self.border_style[side as usize].expect("failed to access CSS property: border_style")
+    }
+
+    /// 辺の線の色を設定する。None の場合は currentcolor とする。
+    pub fn set_border_color(&mut self, side: Side, color: Option<Color>) {
+        self.border_color[side as usize] = color;
+    }
+
+    pub fn border_color(&self, side: Side) -> Color {
+        self.border_color[side as usize]
+            .clone()
+            .expect("failed to access CSS property: border_color")
     }
 
     /// ComputedStyle 構造体の各フィールドの値がユーザーによって設定されていない場合、デフォルトの値を設定する。
     /// もし親要素の CSS の値がユーザによって明示的に指定されていれば、その値を親から継承する。
     pub fn defaulting(&mut self, node: &Rc<RefCell<Node>>, parent_style: Option<ComputedStyle>) {
+        // 親の文字の大きさに対する比率で指定された文字の大きさを、px 単位の大きさに変換する。
+        // 親がない場合は medium を基準とする。
+        if let Some(ratio) = self.font_size_ratio.take() {
+            let parent_px = parent_style
+                .as_ref()
+                .map_or(FontSize::Medium.px(), |p| p.font_size().px());
+            self.font_size = Some(FontSize::Px(parent_px * ratio));
+        }
+
+        // inherit、initial、unset キーワードが指定されたプロパティの値を、親の値または初期値に決める。
+        for (property, keyword) in core::mem::take(&mut self.keywords) {
+            let inherit = match keyword {
+                CssWideKeyword::Inherit => true,
+                CssWideKeyword::Initial => false,
+                CssWideKeyword::Unset => Self::is_inherited(&property),
+            };
+            self.apply_keyword(&property, parent_style.as_ref().filter(|_| inherit));
+        }
+
         // 親ノードが存在し、親の CSS の値が初期値とは異なる場合、値を継承する。
         if let Some(parent_style) = parent_style {
             if self.background_color.is_none() && parent_style.background_color() != Color::white()
@@ -104,11 +289,23 @@ impl ComputedStyle {
             if self.font_size.is_none() && parent_style.font_size() != FontSize::Medium {
                 self.font_size = Some(parent_style.font_size());
             }
+            if self.font_weight.is_none() && parent_style.font_weight() != FontWeight::Normal {
+                self.font_weight = Some(parent_style.font_weight());
+            }
+            if self.font_style.is_none() && parent_style.font_style() != FontStyle::Normal {
+                self.font_style = Some(parent_style.font_style());
+            }
             if self.text_decoration.is_none()
                 && parent_style.text_decoration() != TextDecoration::None
             {
                 self.text_decoration = Some(parent_style.text_decoration());
             }
+            if self.white_space.is_none() && parent_style.white_space() != WhiteSpace::Normal {
+                self.white_space = Some(parent_style.white_space());
+            }
+            if self.visibility.is_none() && parent_style.visibility() != Visibility::Visible {
+                self.visibility = Some(parent_style.visibility());
+            }
         }
 
         // 各プロパティに対して、初期値を設定する。
@@ -124,107 +321,409 @@ impl ComputedStyle {
         if self.font_size.is_none() {
             self.font_size = Some(FontSize::default(node));
         }
+        if self.font_weight.is_none() {
+            self.font_weight = Some(FontWeight::default(node));
+        }
+        if self.font_style.is_none() {
+            self.font_style = Some(FontStyle::default(node));
+        }
         if self.text_decoration.is_none() {
             self.text_decoration = Some(TextDecoration::default(node));
         }
-        if self.height.is_none() {
-            self.height = Some(0.0);
+        if self.white_space.is_none() {
+            self.white_space = Some(WhiteSpace::default(node));
+        }
+        if self.visibility.is_none() {
+            self.visibility = Some(Visibility::Visible);
+        }
+        // float は継承プロパティではなく、初期値は none である。
+        if self.float.is_none() {
+            self.float = Some(Float::None);
         }
-        if self.width.is_none() {
-            self.width = Some(0.0);
+        // flex-direction と justify-content は継承プロパティではなく、初期値は row と normal (flex-start と同じ) である。
+        if self.flex_direction.is_none() {
+            self.flex_direction = Some(FlexDirection::Row);
+        }
+        if self.justify_content.is_none() {
+            self.justify_content = Some(JustifyContent::FlexStart);
+        }
+        // height と width は継承プロパティではなく、初期値は auto (None) のため、何もしない。
+
+        // margin、padding、border の各プロパティは継承プロパティではないため、初期値を設定する。
+        for side in Side::ALL {
+            let i = side as usize;
+            // margin と padding は継承プロパティではなく、初期値は 0 である。
+            self.margin[i].get_or_insert(0.0);
+            self.padding[i].get_or_insert(0.0);
+
+            let style = *self.border_style[i].get_or_insert(BorderStyle::None);
+            // 線の種類が none または hidden の場合、線の幅は 0 になる。
+            if style == BorderStyle::None || style == BorderStyle::Hidden {
+                self.border_width[i] = Some(0.0);
+            } else if self.border_width[i].is_none() {
+                self.border_width[i] = line_width_from_keyword("medium");
+            }
+            // currentcolor の場合、color プロパティの値を使う。
+            if self.border_color[i].is_none() {
+                self.border_color[i] = self.color.clone();
+            }
+        }
+    }
+
+    /// プロパティが継承プロパティかどうかを返す。unset キーワードの値の決め方に使う。
+    /// https://www.w3.org/TR/css-cascade-5/#inherited-property
+    fn is_inherited(property: &str) -> bool {
+        matches!(
+            property,
+            "color" | "font-size" | "font-weight" | "font-style" | "white-space" | "visibility"
+        )
+    }
+
+    /// parent がある場合はプロパティの値を親の値とし、ない場合はプロパティの初期値とする。
+    /// 初期値は要素の種類によらない CSS の仕様上の値である (例えば <h1> の font-size も medium となる)。
+    /// https://www.w3.org/TR/css-cascade-5/#initial-values
+    fn apply_keyword(&mut self, property: &str, parent: Option<&ComputedStyle>) {
+        match property {
+            "background-color" => {
+                self.background_color =
+                    Some(parent.map_or(Color::white(), |p| p.background_color()))
+            }
+            "color" => self.color = Some(parent.map_or(Color::black(), |p| p.color())),
+            "display" => self.display = Some(parent.map_or(DisplayType::Inline, |p| p.display())),
+            "font-size" => {
+                self.font_size = Some(parent.map_or(FontSize::Medium, |p| p.font_size()))
+            }
+            "font-weight" => {
+                self.font_weight = Some(parent.map_or(FontWeight::Normal, |p| p.font_weight()))
+            }
+            "font-style" => {
+                self.font_style = Some(parent.map_or(FontStyle::Normal, |p| p.font_style()))
+            }
+            "text-decoration" => {
+                self.text_decoration =
+                    Some(parent.map_or(TextDecoration::None, |p| p.text_decoration()))
+            }
+            "white-space" => {
+                self.white_space = Some(parent.map_or(WhiteSpace::Normal, |p| p.white_space()))
+            }
+            "visibility" => {
+                self.visibility = Some(parent.map_or(Visibility::Visible, |p| p.visibility()))
+            }
+            "float" => self.float = Some(parent.map_or(Float::None, |p| p.float())),
+            "flex-direction" => {
+                self.flex_direction =
+                    Some(parent.map_or(FlexDirection::Row, |p| p.flex_direction()))
+            }
+            "justify-content" => {
+                self.justify_content =
+                    Some(parent.map_or(JustifyContent::FlexStart, |p| p.justify_content()))
+            }
+            "height" => self.height = parent.and_then(|p| p.height()),
+            "width" => self.width = parent.and_then(|p| p.width()),
+            // 初期値は None とし、defaulting で初期値を設定する。
+            _ => {
+                if let Some(side) = Side::from_property("margin", property) {
+                    self.margin[side as usize] = parent.map(|p| p.margin(side));
+                }
+                if let Some(side) = Side::from_property("padding", property) {
+                    self.padding[side as usize] = parent.map(|p| p.padding(side));
+                }
+                match Side::from_border_property(property) {
+                    Some((side, "width")) => {
+                        self.border_width[side as usize] = parent.map(|p| p.border_width(side))
+                    }
+                    Some((side, "style")) => {
+                        self.border_style[side as usize] = parent.map(|p| p.border_style(side))
+                    }
+                    Some((side, "color")) => {
+                        self.border_color[side as usize] = parent.map(|p| p.border_color(side))
+                    }
+                    _ => {}
+                }
+            }
         }
     }
 }
 
+/// CSS-wide キーワード
+/// すべてのプロパティの値として指定でき、値を親から継承するか初期値とするかを明示的に決める。
+/// https://www.w3.org/TR/css-cascade-5/#defaulting-keywords
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CssWideKeyword {
+    /// https://www.w3.org/TR/css-cascade-5/#inherit
+    Inherit,
+    /// https://www.w3.org/TR/css-cascade-5/#initial
+    Initial,
+    /// 継承プロパティの場合は inherit、それ以外の場合は initial として扱う。
+    /// https://www.w3.org/TR/css-cascade-5/#inherit-initial
+    Unset,
+}
+
+impl CssWideKeyword {
+    /// キーワードの名前から CssWideKeyword を返す。名前の大文字と小文字は区別しない。
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "inherit" => Some(Self::Inherit),
+            "initial" => Some(Self::Initial),
+            "unset" => Some(Self::Unset),
+            _ => None,
+        }
+    }
+}
+
+/// CSS の色の名前とカラーコードの対応表。
+/// https://www.w3.org/TR/css-color-4/#named-colors
+const NAMED_COLORS: [(&str, &str); 148] = [
+    ("aliceblue", "#f0f8ff"),
+    ("antiquewhite", "#faebd7"),
+    ("aqua", "#00ffff"),
+    ("aquamarine", "#7fffd4"),
+    ("azure", "#f0ffff"),
+    ("beige", "#f5f5dc"),
+    ("bisque", "#ffe4c4"),
+    ("black", "#000000"),
+    ("blanchedalmond", "#ffebcd"),
+    ("blue", "#0000ff"),
+    ("blueviolet", "#8a2be2"),
+    ("brown", "#a52a2a"),
+    ("burlywood", "#deb887"),
+    ("cadetblue", "#5f9ea0"),
+    ("chartreuse", "#7fff00"),
+    ("chocolate", "#d2691e"),
+    ("coral", "#ff7f50"),
+    ("cornflowerblue", "#6495ed"),
+    ("cornsilk", "#fff8dc"),
+    ("crimson", "#dc143c"),
+    ("cyan", "#00ffff"),
+    ("darkblue", "#00008b"),
+    ("darkcyan", "#008b8b"),
+    ("darkgoldenrod", "#b8860b"),
+    ("darkgray", "#a9a9a9"),
+    ("darkgreen", "#006400"),
+    ("darkgrey", "#a9a9a9"),
+    ("darkkhaki", "#bdb76b"),
+    ("darkmagenta", "#8b008b"),
+    ("darkolivegreen", "#556b2f"),
+    ("darkorange", "#ff8c00"),
+    ("darkorchid", "#9932cc"),
+    ("darkred", "#8b0000"),
+    ("darksalmon", "#e9967a"),
+    ("darkseagreen", "#8fbc8f"),
+    ("darkslateblue", "#483d8b"),
+    ("darkslategray", "#2f4f4f"),
+    ("darkslategrey", "#2f4f4f"),
+    ("darkturquoise", "#00ced1"),
+    ("darkviolet", "#9400d3"),
+    ("deeppink", "#ff1493"),
+    ("deepskyblue", "#00bfff"),
+    ("dimgray", "#696969"),
+    ("dimgrey", "#696969"),
+    ("dodgerblue", "#1e90ff"),
+    ("firebrick", "#b22222"),
+    ("floralwhite", "#fffaf0"),
+    ("forestgreen", "#228b22"),
+    ("fuchsia", "#ff00ff"),
+    ("gainsboro", "#dcdcdc"),
+    ("ghostwhite", "#f8f8ff"),
+    ("gold", "#ffd700"),
+    ("goldenrod", "#daa520"),
+    ("gray", "#808080"),
+    ("green", "#008000"),
+    ("greenyellow", "#adff2f"),
+    ("grey", "#808080"),
+    ("honeydew", "#f0fff0"),
+    ("hotpink", "#ff69b4"),
+    ("indianred", "#cd5c5c"),
+    ("indigo", "#4b0082"),
+    ("ivory", "#fffff0"),
+    ("khaki", "#f0e68c"),
+    ("lavender", "#e6e6fa"),
+    ("lavenderblush", "#fff0f5"),
+    ("lawngreen", "#7cfc00"),
+    ("lemonchiffon", "#fffacd"),
+    ("lightblue", "#add8e6"),
+    ("lightcoral", "#f08080"),
+    ("lightcyan", "#e0ffff"),
+    ("lightgoldenrodyellow", "#fafad2"),
+    ("lightgray", "#d3d3d3"),
+    ("lightgreen", "#90ee90"),
+    ("lightgrey", "#d3d3d3"),
+    ("lightpink", "#ffb6c1"),
+    ("lightsalmon", "#ffa07a"),
+    ("lightseagreen", "#20b2aa"),
+    ("lightskyblue", "#87cefa"),
+    ("lightslategray", "#778899"),
+    ("lightslategrey", "#778899"),
+    ("lightsteelblue", "#b0c4de"),
+    ("lightyellow", "#ffffe0"),
+    ("lime", "#00ff00"),
+    ("limegreen", "#32cd32"),
+    ("linen", "#faf0e6"),
+    ("magenta", "#ff00ff"),
+    ("maroon", "#800000"),
+    ("mediumaquamarine", "#66cdaa"),
+    ("mediumblue", "#0000cd"),
+    ("mediumorchid", "#ba55d3"),
+    ("mediumpurple", "#9370db"),
+    ("mediumseagreen", "#3cb371"),
+    ("mediumslateblue", "#7b68ee"),
+    ("mediumspringgreen", "#00fa9a"),
+    ("mediumturquoise", "#48d1cc"),
+    ("mediumvioletred", "#c71585"),
+    ("midnightblue", "#191970"),
+    ("mintcream", "#f5fffa"),
+    ("mistyrose", "#ffe4e1"),
+    ("moccasin", "#ffe4b5"),
+    ("navajowhite", "#ffdead"),
+    ("navy", "#000080"),
+    ("oldlace", "#fdf5e6"),
+    ("olive", "#808000"),
+    ("olivedrab", "#6b8e23"),
+    ("orange", "#ffa500"),
+    ("orangered", "#ff4500"),
+    ("orchid", "#da70d6"),
+    ("palegoldenrod", "#eee8aa"),
+    ("palegreen", "#98fb98"),
+    ("paleturquoise", "#afeeee"),
+    ("palevioletred", "#db7093"),
+    ("papayawhip", "#ffefd5"),
+    ("peachpuff", "#ffdab9"),
+    ("peru", "#cd853f"),
+    ("pink", "#ffc0cb"),
+    ("plum", "#dda0dd"),
+    ("powderblue", "#b0e0e6"),
+    ("purple", "#800080"),
+    ("rebeccapurple", "#663399"),
+    ("red", "#ff0000"),
+    ("rosybrown", "#bc8f8f"),
+    ("royalblue", "#4169e1"),
+    ("saddlebrown", "#8b4513"),
+    ("salmon", "#fa8072"),
+    ("sandybrown", "#f4a460"),
+    ("seagreen", "#2e8b57"),
+    ("seashell", "#fff5ee"),
+    ("sienna", "#a0522d"),
+    ("silver", "#c0c0c0"),
+    ("skyblue", "#87ceeb"),
+    ("slateblue", "#6a5acd"),
+    ("slategray", "#708090"),
+    ("slategrey", "#708090"),
+    ("snow", "#fffafa"),
+    ("springgreen", "#00ff7f"),
+    ("steelblue", "#4682b4"),
+    ("tan", "#d2b48c"),
+    ("teal", "#008080"),
+    ("thistle", "#d8bfd8"),
+    ("tomato", "#ff6347"),
+    ("turquoise", "#40e0d0"),
+    ("violet", "#ee82ee"),
+    ("wheat", "#f5deb3"),
+    ("white", "#ffffff"),
+    ("whitesmoke", "#f5f5f5"),
+    ("yellow", "#ffff00"),
+    ("yellowgreen", "#9acd32"),
+];
+
 /// Color 構造体
 /// CSS の色の値を表す構造体である。
 /// 色は red, blue, black などの名前や、#ff0000, #808080 などのカラーコードの値をフィールドに持つ。
+/// 不透明度 (アルファ値) は 0.0 (透明) から 1.0 (不透明) の範囲で保持する。
 #[derive(Debug, Clone, PartialEq)]
 pub struct Color {
     name: Option<String>,
     code: String,
+    alpha: f64,
 }
 
 impl Color {
+    /// 色の名前からカラーオブジェクトを作成する。名前の大文字と小文字は区別しない。
+    /// transparent は透明な黒として扱う。
     pub fn from_name(name: &str) -> Result<Self, Error> {
-        let code = match name {
-            "black" => "#000000".to_string(),
-            "silver" => "#c0c0c0".to_string(),
-            "gray" => "#808080".to_string(),
-            "white" => "#ffffff".to_string(),
-            "maroon" => "#800000".to_string(),
-            "red" => "#ff0000".to_string(),
-            "purple" => "#800080".to_string(),
-            "fuchsia" => "#ff00ff".to_string(),
-            "green" => "#008000".to_string(),
-            "lime" => "#00ff00".to_string(),
-            "olive" => "#808000".to_string(),
-            "yellow" => "#ffff00".to_string(),
-            "navy" => "#000080".to_string(),
-            "blue" => "#0000ff".to_string(),
-            "teal" => "#009090".to_string(),
-            "aqua" => "#00ffff".to_string(),
-            "orange" => "#ffa500".to_string(),
-            "lightgray" => "#d3d3de".to_string(),
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Ok(Self {
+                name: Some(name),
+                code: "#000000".to_string(),
+                alpha: 0.0,
+            });
+        }
+
+        match NAMED_COLORS.iter().find(|(n, _)| *n == name) {
+            Some((_, code)) => Ok(Self {
+                name: Some(name),
+                code: code.to_string(),
+                alpha: 1.0,
+            }),
+            None => Err(Error::UnexpectedInput(format!(
+                "color name {:?} is not supported yet",
+                name
+            ))),
+        }
+    }
+
+    /// #rrggbb 形式、または #rgb 形式のカラーコードからカラーオブジェクトを作成する。
+    /// #rgb 形式は各桁を2つ並べた #rrggbb 形式として扱う。
+    /// https://www.w3.org/TR/css-color-4/#hex-notation
+    pub fn from_code(code: &str) -> Result<Self, Error> {
+        let digits = match code.strip_prefix('#') {
+            Some(digits) if digits.chars().all(|c| c.is_ascii_hexdigit()) => digits,
             _ => {
                 return Err(Error::UnexpectedInput(format!(
-                    "color name {:?} is not supported yet",
-                    name
-                )));
+                    "invalid color code {}",
+                    code
+                )))
             }
         };
 
-        Ok(Self {
-            name: Some(name.to_string()),
-            code,
-        })
-    }
-
-    pub fn from_code(code: &str) -> Result<Self, Error> {
-        if code.chars().nth(0) != Some('#') || code.len() != 7 {
-            return Err(Error::UnexpectedInput(format!(
-                "invalid color code {}",
+        let code = match digits.len() {
+            3 => digits.chars().fold("#".to_string(), |mut code, c| {
+                code.push(c);
+                code.push(c);
                 code
-            )));
-        }
-
-        let name = match code {
-            "#000000" => "black".to_string(),
-            "#c0c0c0" => "silber".to_string(),
-            "#808080" => "gray".to_string(),
-            "#ffffff" => "white".to_string(),
-            "#800000" => "maroon".to_string(),
-            "#ff0000" => "red".to_string(),
-            "#800080" => "purple".to_string(),
-            "#ff00ff" => "fuchsia".to_string(),
-            "#008000" => "green".to_string(),
-            "#00ff00" => "lime".to_string(),
-            "#808000" => "olive".to_string(),
-            "#ffff00" => "yellow".to_string(),
-            "#000080" => "navy".to_string(),
-            "#0000ff" => "blue".to_string(),
-            "#008080" => "teal".to_string(),
-            "#ffa500" => "orange".to_string(),
-            "#d3d3d3" => "lightgray".to_string(),
+            }),
+            6 => format!("#{}", digits),
             _ => {
                 return Err(Error::UnexpectedInput(format!(
-                    "color code {:?} is not supported yet",
+                    "invalid color code {}",
                     code
-                )));
+                )))
             }
-        };
+        }
+        .to_ascii_lowercase();
 
         Ok(Self {
-            name: Some(name),
-            code: code.to_string(),
+            name: Self::name_of(&code),
+            code,
+            alpha: 1.0,
         })
     }
 
+    /// rgb() や rgba() のように、赤、緑、青の値 (0 から 255) と不透明度からカラーオブジェクトを作成する。
+    /// 不透明度は 0.0 から 1.0 の範囲に収める。
+    /// https://www.w3.org/TR/css-color-4/#rgb-functions
+    pub fn from_rgba(red: u8, green: u8, blue: u8, alpha: f64) -> Self {
+        let code = format!("#{:02x}{:02x}{:02x}", red, green, blue);
+        Self {
+            name: Self::name_of(&code),
+            code,
+            alpha: alpha.clamp(0.0, 1.0),
+        }
+    }
+
+    /// カラーコードに対応する色の名前を返却する。
+    fn name_of(code: &str) -> Option<String> {
+        NAMED_COLORS
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(name, _)| name.to_string())
+    }
+
     /// 白色のカラーオブジェクトを返却する。
     pub fn white() -> Self {
         Self {
             name: Some("white".to_string()),
             code: "#ffffff".to_string(),
+            alpha: 1.0,
         }
     }
 
@@ -233,6 +732,7 @@ impl Color {
         Self {
             name: Some("black".to_string()),
             code: "#000000".to_string(),
+            alpha: 1.0,
         }
     }
 
@@ -240,6 +740,11 @@ impl Color {
     pub fn code_u32(&self) -> u32 {
         u32::from_str_radix(self.code.trim_start_matches('#'), 16).unwrap()
     }
+
+    /// 不透明度を返却する。
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
 }
 
 /// ノードの種類に応じたフォントサイズを返却する。
@@ -249,24 +754,66 @@ impl FontSize {
             NodeKind::Element(element) => match element.kind() {
                 ElementKind::H1 => FontSize::XXLarge,
                 ElementKind::H2 => FontSize::XLarge,
+                ElementKind::H3 => FontSize::Large,
+                ElementKind::H4 => FontSize::Medium,
+                ElementKind::H5 => FontSize::Small,
+                ElementKind::H6 => FontSize::XSmall,
+                ElementKind::Small => FontSize::Small,
                 _ => FontSize::Medium,
             },
             _ => FontSize::Medium,
         }
     }
+
+    /// https://www.w3.org/TR/css-fonts-4/#absolute-size-value
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "xx-small" | "x-small" => Ok(Self::XSmall),
+            "small" => Ok(Self::Small),
+            "medium" => Ok(Self::Medium),
+            "large" => Ok(Self::Large),
+            "x-large" => Ok(Self::XLarge),
+            "xx-large" | "xxx-large" => Ok(Self::XXLarge),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-size {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+
+    /// 文字の大きさを px 単位で返す。
+    /// 文字は Medium (16px) の整数倍の大きさでしか描画できないため、キーワードの大きさは描画する大きさに合わせる。
+    /// Large 以下は Medium と同じ 16px、XLarge は 2倍の 32px、XXLarge は 3倍の 48px とする。
+    pub fn px(&self) -> f64 {
+        match self {
+            FontSize::XSmall | FontSize::Small | FontSize::Medium | FontSize::Large => 16.0,
+            FontSize::XLarge => 32.0,
+            FontSize::XXLarge => 48.0,
+            FontSize::Px(px) => *px,
+        }
+    }
 }
 
 /// FontSize 列挙型
 /// 文字の大きさを表す列挙型である。
 /// 以下の大きさをサポートする。
-/// 通常の文字を表す Medium
+/// 通常の文字と <h4> タグのデフォルトの文字の大きさを表す Medium
 /// <h1> タグのデフォルトの文字の大きさである XXLarge
 /// <h2> タグのデフォルトの文字の大きさである XLarge
+/// <h3> タグのデフォルトの文字の大きさである Large
+/// <h5> タグのデフォルトの文字の大きさである Small
+/// <h6> タグのデフォルトの文字の大きさである XSmall
+/// px や em で指定された大きさを px 単位で表す Px
+/// https://html.spec.whatwg.org/multipage/rendering.html#sections-and-headings
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum FontSize {
+    XSmall,
+    Small,
     Medium,
+    Large,
     XLarge,
     XXLarge,
+    Px(f64),
 }
 
 /// DisplayType 列挙型
@@ -274,6 +821,9 @@ pub enum FontSize {
 /// ここでは以下をサポートする。
 /// ブロック要素を表す Block
 /// インライン要素を表す Inline
+/// インライン要素のように同じ行に並び、中身の大きさに合わせたブロックを作る InlineBlock
+/// ブロック要素の先頭にリストのマーカーを表示する ListItem
+/// 子要素を横または縦に1列に並べるフレックスコンテナを作る Flex
 /// 非表示にする DisplayNone
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DisplayType {
@@ -281,6 +831,12 @@ pub enum DisplayType {
     Block,
     /// https://www.w3.org/TR/ccs-display-3/#valdef-display-inline
     Inline,
+    /// https://www.w3.org/TR/css-display-3/#valdef-display-inline-block
+    InlineBlock,
+    /// https://www.w3.org/TR/css-display-3/#valdef-display-list-item
+    ListItem,
+    /// https://www.w3.org/TR/css-display-3/#valdef-display-flex
+    Flex,
     /// https://www.w3.org/TR/ccs-display-3/#valdef-display-none
     DisplayNone,
 }
@@ -290,7 +846,9 @@ impl DisplayType {
         match &node.borrow().kind() {
             NodeKind::Document => DisplayType::Block,
             NodeKind::Element(e) => {
-                if e.is_block_element() {
+                if e.kind() == ElementKind::Li {
+                    DisplayType::ListItem
+                } else if e.is_block_element() {
                     DisplayType::Block
                 } else {
                     DisplayType::Inline
@@ -304,6 +862,9 @@ impl DisplayType {
         match s {
             "block" => Ok(Self::Block),
             "inline" => Ok(Self::Inline),
+            "inline-block" => Ok(Self::InlineBlock),
+            "list-item" => Ok(Self::ListItem),
+            "flex" => Ok(Self::Flex),
             "none" => Ok(Self::DisplayNone),
             _ => Err(Error::UnexpectedInput(format!(
                 "display {:?} is not supported yet",
@@ -313,25 +874,341 @@ impl DisplayType {
     }
 }
 
+/// FontWeight 列挙型
+/// CSS の font-weight プロパティに対応する値を表す列挙型である。
+/// ここでは以下をサポートする。
+/// 通常の太さを表す Normal (400 など 600 未満の数値)
+/// <strong> や <b> タグのデフォルトの太さである Bold (700 など 600 以上の数値)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontWeight {
+    Normal,
+    Bold,
+}
+
+impl FontWeight {
+    fn default(node: &Rc<RefCell<Node>>) -> Self {
+        match &node.borrow().kind() {
+            NodeKind::Element(element) => match element.kind() {
+                ElementKind::Strong | ElementKind::B => FontWeight::Bold,
+                _ => FontWeight::Normal,
+            },
+            _ => FontWeight::Normal,
+        }
+    }
+
+    /// キーワードから太さを決める。
+    /// bolder と lighter は親の太さからの相対的な値だが、2種類の太さしか扱わないため、それぞれ Bold と Normal として扱う。
+    /// https://www.w3.org/TR/css-fonts-4/#font-weight-prop
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" | "lighter" => Ok(Self::Normal),
+            "bold" | "bolder" => Ok(Self::Bold),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-weight {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+
+    /// 1 から 1000 までの数値から太さを決める。
+    /// 600 以上を Bold、それ未満を Normal として扱う。
+    pub fn from_number(n: f64) -> Result<Self, Error> {
+        if !(1.0..=1000.0).contains(&n) {
+            return Err(Error::UnexpectedInput(format!(
+                "font-weight {} is out of range",
+                n
+            )));
+        }
+
+        if n >= 600.0 {
+            Ok(Self::Bold)
+        } else {
+            Ok(Self::Normal)
+        }
+    }
+}
+
+/// FontStyle 列挙型
+/// CSS の font-style プロパティに対応する値を表す列挙型である。
+/// ここでは以下をサポートする。
+/// 通常の書体を表す Normal
+/// <em> や <i> タグのデフォルトの書体である Italic
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+}
+
+impl FontStyle {
+    fn default(node: &Rc<RefCell<Node>>) -> Self {
+        match &node.borrow().kind() {
+            NodeKind::Element(element) => match element.kind() {
+                ElementKind::Em | ElementKind::I => FontStyle::Italic,
+                _ => FontStyle::Normal,
+            },
+            _ => FontStyle::Normal,
+        }
+    }
+}
+
+/// Side 列挙型
+/// ボックスの上、右、下、左の辺を表す列挙型である。margin、padding、border などの辺ごとのプロパティに使う。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Side {
+    /// 上、右、下、左の順のすべての辺。
+    pub const ALL: [Side; 4] = [Side::Top, Side::Right, Side::Bottom, Side::Left];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "top" => Some(Self::Top),
+            "right" => Some(Self::Right),
+            "bottom" => Some(Self::Bottom),
+            "left" => Some(Self::Left),
+            _ => None,
+        }
+    }
+
+    /// margin-top のような辺ごとの個別指定プロパティの名前から、辺を返す。
+    pub fn from_property(prefix: &str, property: &str) -> Option<Self> {
+        Self::from_name(property.strip_prefix(prefix)?.strip_prefix('-')?)
+    }
+
+    /// border-top-width のような border の個別指定プロパティの名前を、辺と値の種類 (width、style、color) に分ける。
+    pub fn from_border_property(property: &str) -> Option<(Self, &str)> {
+        let (side, kind) = property.strip_prefix("border-")?.split_once('-')?;
+        Some((Self::from_name(side)?, kind))
+    }
+}
+
+/// 線の幅のキーワードを px 単位の幅に変換する。
+/// https://www.w3.org/TR/css-backgrounds-3/#typedef-line-width
+pub fn line_width_from_keyword(keyword: &str) -> Option<f64> {
+    match keyword {
+        "thin" => Some(1.0),
+        "medium" => Some(3.0),
+        "thick" => Some(5.0),
+        _ => None,
+    }
+}
+
+/// BorderStyle 列挙型
+/// CSS の border-style プロパティに対応する値を表す列挙型である。
+/// https://www.w3.org/TR/css-backgrounds-3/#typedef-line-style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    None,
+    Hidden,
+    Dotted,
+    Dashed,
+    Solid,
+    Double,
+    Groove,
+    Ridge,
+    Inset,
+    Outset,
+}
+
+impl BorderStyle {
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "hidden" => Ok(Self::Hidden),
+            "dotted" => Ok(Self::Dotted),
+            "dashed" => Ok(Self::Dashed),
+            "solid" => Ok(Self::Solid),
+            "double" => Ok(Self::Double),
+            "groove" => Ok(Self::Groove),
+            "ridge" => Ok(Self::Ridge),
+            "inset" => Ok(Self::Inset),
+            "outset" => Ok(Self::Outset),
+            _ => Err(Error::UnexpectedInput(format!(
+                "border-style {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
 /// TextDecoration 列挙型
 /// CSS の text-decoration プロパティに対応する値を表す列挙型である。
 /// ここでは以下をサポートする。
 /// テキストの下線を表す Underline
-/// 装飾のない None
+/// テキストの中央を通る取り消し線を表す LineThrough
+/// 装飾のない None (<a> タグのデフォルトの下線を取り消すためにも使う)
+/// https://www.w3.org/TR/css-text-decor-3/#text-decoration-line-property
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextDecoration {
     None,
     Underline,
+    LineThrough,
 }
 
 impl TextDecoration {
     fn default(node: &Rc<RefCell<Node>>) -> Self {
         match &node.borrow().kind() {
             NodeKind::Element(element) => match element.kind() {
-                ElementKind::A => TextDecoration::Underline,
+                ElementKind::A | ElementKind::U => TextDecoration::Underline,
                 _ => TextDecoration::None,
             },
             _ => TextDecoration::None,
         }
     }
+
+    /// 線の種類のキーワードから装飾を決める。overline は対応していないため、エラーを返す。
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "underline" => Ok(Self::Underline),
+            "line-through" => Ok(Self::LineThrough),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-decoration {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// WhiteSpace 列挙型
+/// CSS の white-space プロパティに対応する値を表す列挙型である。
+/// ここでは以下をサポートする。
+/// 連続する空白文字や改行文字を1つの空白にまとめ、必要に応じて折り返す Normal
+/// 空白文字と改行文字をそのまま残し、折り返しを行わない <pre> タグのデフォルトの Pre
+/// https://drafts.csswg.org/css-text/#white-space-property
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+}
+
+impl WhiteSpace {
+    fn default(node: &Rc<RefCell<Node>>) -> Self {
+        match &node.borrow().kind() {
+            NodeKind::Element(element) => match element.kind() {
+                ElementKind::Pre => WhiteSpace::Pre,
+                _ => WhiteSpace::Normal,
+            },
+            _ => WhiteSpace::Normal,
+        }
+    }
+}
+
+/// Visibility 列挙型
+/// CSS の visibility プロパティに対応する値を表す列挙型である。
+/// ここでは以下をサポートする。
+/// ボックスを描画する Visible
+/// ボックスの大きさと位置を保ったまま描画しない Hidden
+/// 表の行や列に使う collapse は、表をサポートしないため Hidden として扱う。
+/// https://www.w3.org/TR/css-display-3/#visibility
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+}
+
+impl Visibility {
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "visible" => Ok(Self::Visible),
+            "hidden" | "collapse" => Ok(Self::Hidden),
+            _ => Err(Error::UnexpectedInput(format!(
+                "visibility {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// Float 列挙型
+/// CSS の float プロパティに対応する値を表す列挙型である。
+/// ここでは以下をサポートする。
+/// 通常のフローに配置する None
+/// 包含ブロックの左端に寄せ、後に続く行をその右側に回り込ませる Left
+/// 包含ブロックの右端に寄せ、後に続く行をその左側に回り込ませる Right
+/// 文字を書く方向は左から右のみのため、inline-start は Left、inline-end は Right として扱う。
+/// https://www.w3.org/TR/CSS2/visuren.html#float-position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Float {
+    None,
+    Left,
+    Right,
+}
+
+impl Float {
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "left" | "inline-start" => Ok(Self::Left),
+            "right" | "inline-end" => Ok(Self::Right),
+            _ => Err(Error::UnexpectedInput(format!(
+                "float {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// FlexDirection 列挙型
+/// CSS の flex-direction プロパティに対応する値を表す列挙型である。
+/// フレックスアイテムを並べる方向 (主軸) を表し、ここでは以下をサポートする。
+/// 左から右に横に並べる Row
+/// 上から下に縦に並べる Column
+/// https://www.w3.org/TR/css-flexbox-1/#flex-direction-property
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlexDirection {
+    Row,
+    Column,
+}
+
+impl FlexDirection {
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "row" => Ok(Self::Row),
+            "column" => Ok(Self::Column),
+            _ => Err(Error::UnexpectedInput(format!(
+                "flex-direction {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// JustifyContent 列挙型
+/// CSS の justify-content プロパティに対応する値を表す列挙型である。
+/// フレックスアイテムを並べた後に残る主軸方向の余白の配分を表し、ここでは以下をサポートする。
+/// 先頭に詰める FlexStart (normal、start も同じ)
+/// 末尾に詰める FlexEnd (end も同じ)
+/// 中央に寄せる Center
+/// 先頭と末尾のアイテムを両端に置き、アイテムの間に均等に配分する SpaceBetween
+/// 各アイテムの両側に均等に配分する SpaceAround
+/// https://www.w3.org/TR/css-align-3/#propdef-justify-content
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JustifyContent {
+    FlexStart,
+    FlexEnd,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+}
+
+impl JustifyContent {
+    pub fn from_keyword(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" | "start" | "flex-start" => Ok(Self::FlexStart),
+            "end" | "flex-end" => Ok(Self::FlexEnd),
+            "center" => Ok(Self::Center),
+            "space-between" => Ok(Self::SpaceBetween),
+            "space-around" => Ok(Self::SpaceAround),
+            _ => Err(Error::UnexpectedInput(format!(
+                "justify-content {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
 }
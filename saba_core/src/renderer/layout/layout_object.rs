@@ -1,20 +1,37 @@
 use crate::alloc::string::ToString;
 use crate::constants::CHAR_HEIGHT_WITH_PADDING;
 use crate::constants::CHAR_WIDTH;
-use crate::constants::CONTENT_AREA_WIDTH;
-use crate::constants::WINDOW_PADDING;
-use crate::constants::WINDOW_WIDTH;
+use crate::constants::IFRAME_DEFAULT_HEIGHT;
+use crate::constants::IFRAME_DEFAULT_WIDTH;
+use crate::constants::LIST_MARKER_WIDTH;
 use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::ComponentValue;
 use crate::renderer::css::cssom::Declaration;
 use crate::renderer::css::cssom::Selector;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
+use crate::renderer::layout::computed_style::line_width_from_keyword;
+use crate::renderer::layout::computed_style::BorderStyle;
 use crate::renderer::layout::computed_style::Color;
 use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::CssWideKeyword;
 use crate::renderer::layout::computed_style::DisplayType;
+use crate::renderer::layout::computed_style::FlexDirection;
+use crate::renderer::layout::computed_style::Float;
 use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::FontWeight;
+use crate::renderer::layout::computed_style::JustifyContent;
+use crate::renderer::layout::computed_style::Side;
+use crate::renderer::layout::computed_style::TextDecoration;
+use crate::renderer::layout::computed_style::Visibility;
+use crate::renderer::layout::computed_style::WhiteSpace;
+use crate::renderer::layout::line_box::bounding_box;
+use crate::renderer::layout::line_box::FloatBox;
+use crate::renderer::layout::line_box::LineBoxBuilder;
+use crate::renderer::layout::line_box::TextFragment;
+use alloc::format;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
@@ -24,10 +41,14 @@ use core::cell::RefCell;
 
 /// HTML 要素は表示コンテンツの性質に基づいてブロック要素とインライン要素に分類される。
 /// ブロック要素は Block, インライン要素は Inline で表す。
+/// display: inline-block の要素は、インライン要素のように同じ行に並ぶブロックとして InlineBlock で表す。
+/// <img> 要素は、画像の大きさを持ち、テキストのように行に並ぶ置換要素として Image で表す。
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LayoutObjectKind {
     Block,
     Inline,
+    InlineBlock,
+    Image,
     Text,
 }
 
@@ -39,32 +60,125 @@ pub struct LayoutPoint {
     y: i64,
 }
 
-/// 要素の幅を超えるテキストの場合、単語の境界であるホワイトスペースで改行する。
-/// 改行位置を見つける関数である。
-fn find_index_for_line_break(line: String, max_index: usize) -> usize {
-    for i in (0..max_index).rev() {
-        if line.chars().collect::<Vec<char>>()[i] == ' ' {
-            return i;
+/// white-space: pre のテキストを、改行文字の位置で行ごとに分割する。
+/// 末尾の改行文字は空行として扱わない。
+fn split_preformatted_text(text: &str) -> Vec<String> {
+    text.strip_suffix('\n')
+        .unwrap_or(text)
+        .split('\n')
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// 文字の大きさから、1文字の横幅と1行の高さを返す。
+/// Medium (16px) の文字の CHAR_WIDTH と CHAR_HEIGHT_WITH_PADDING を基準に、文字の大きさの比率を掛けて求める。
+fn char_size(font_size: FontSize) -> (i64, i64) {
+    let ratio = font_size.px() / FontSize::Medium.px();
+    // 小数点以下は四捨五入し、1px 未満にはしない。
+    let scale = |length: i64| ((length as f64 * ratio + 0.5) as i64).max(1);
+    (scale(CHAR_WIDTH), scale(CHAR_HEIGHT_WITH_PADDING))
+}
+
+/// width や height プロパティのコンポーネント値から、px 単位の長さを取得する。
+/// auto の場合は Some(None) を返す。単位のない値は 0 のみ受け付ける。
+/// 負の値や px 以外の単位など、長さとして解釈できない場合は None を返す。
+/// https://www.w3.org/TR/css-sizing-3/#preferred-size-properties
+fn length_from_value(value: &ComponentValue) -> Option<Option<f64>> {
+    match value {
+        ComponentValue::Ident(ident) if ident.eq_ignore_ascii_case("auto") => Some(None),
+        ComponentValue::Dimension(n, unit) if *n >= 0.0 && unit.eq_ignore_ascii_case("px") => {
+            Some(Some(*n))
+        }
+        ComponentValue::Number(n) if *n == 0.0 => Some(Some(0.0)),
+        _ => None,
+    }
+}
+
+/// 色の名前、カラーコード、rgb() または rgba() 関数のコンポーネント値から色を作成する。
+/// 色として解釈できない場合は None を返す。
+fn color_from_value(value: &ComponentValue) -> Option<Color> {
+    match value {
+        ComponentValue::Ident(name) => Color::from_name(name).ok(),
+        ComponentValue::HashToken(code) => Color::from_code(code).ok(),
+        ComponentValue::Function(name, args)
+            if name.eq_ignore_ascii_case("rgb") || name.eq_ignore_ascii_case("rgba") =>
+        {
+            color_from_rgb_function(args)
         }
+        _ => None,
     }
-    max_index
 }
 
-/// ウィンドウの大きさによってテキストを指定された幅内に収まるように単語の途中で折り返すことなく、スペースで区切られた部分ごとに分割する処理を行う。
-/// この動作は、CSS の workd-break プロパティが normal の時と同じ動作である。word-break プロパティのデフォルトの挙動は、単語を途中で折り返さない。
-fn split_text(line: String, char_width: i64) -> Vec<String> {
-    let mut result: Vec<String> = vec![];
-    if line.len() as i64 * char_width > (WINDOW_WIDTH + WINDOW_PADDING) {
-        let s = line.split_at(find_index_for_line_break(
-            line.clone(),
-            ((WINDOW_WIDTH + WINDOW_PADDING) / char_width) as usize,
-        ));
-        result.push(s.0.to_string());
-        result.extend(split_text(s.1.trim().to_string(), char_width))
-    } else {
-        result.push(line);
-    }
-    result
+/// rgb() と rgba() 関数の引数から色を作成する。
+/// 赤、緑、青の値は数値 (0 から 255) またはパーセンテージで指定する。
+/// 不透明度は省略でき、数値 (0 から 1) またはパーセンテージで指定する。
+/// カンマ区切りの形式と、rgb(255 0 0 / 50%) のようなスペース区切りの形式の両方を扱う。
+/// https://www.w3.org/TR/css-color-4/#rgb-functions
+fn color_from_rgb_function(args: &[ComponentValue]) -> Option<Color> {
+    let args: Vec<&ComponentValue> = args
+        .iter()
+        .filter(|arg| **arg != ComponentValue::Delim('/'))
+        .collect();
+
+    let channel = |arg: &ComponentValue| -> Option<u8> {
+        let value = match arg {
+            ComponentValue::Number(n) => *n,
+            ComponentValue::Percentage(p) => p * 255.0 / 100.0,
+            _ => return None,
+        };
+        // 小数点以下は四捨五入する。
+        Some((value.clamp(0.0, 255.0) + 0.5) as u8)
+    };
+
+    let alpha = match args.get(3) {
+        None => 1.0,
+        Some(ComponentValue::Number(n)) => *n,
+        Some(ComponentValue::Percentage(p)) => p / 100.0,
+        Some(_) => return None,
+    };
+
+    match args.as_slice() {
+        [red, green, blue] | [red, green, blue, _] => Some(Color::from_rgba(
+            channel(red)?,
+            channel(green)?,
+            channel(blue)?,
+            alpha,
+        )),
+        _ => None,
+    }
+}
+
+/// フレックスアイテムの主軸方向の大きさと、コンテナの主軸方向の大きさから、各アイテムの主軸方向の位置を返す。
+/// 余白が負の場合、space-between は flex-start と、space-around は center と同じになる。
+/// https://www.w3.org/TR/css-align-3/#distribution-values
+fn main_axis_offsets(sizes: &[i64], container: i64, justify_content: JustifyContent) -> Vec<i64> {
+    let count = sizes.len() as i64;
+    let free = container - sizes.iter().sum::<i64>();
+    let (start, gap) = match justify_content {
+        JustifyContent::FlexStart => (0, 0),
+        JustifyContent::FlexEnd => (free, 0),
+        JustifyContent::Center => (free / 2, 0),
+        JustifyContent::SpaceBetween if free > 0 && count > 1 => (0, free / (count - 1)),
+        JustifyContent::SpaceBetween => (0, 0),
+        JustifyContent::SpaceAround if free > 0 && count > 0 => (free / count / 2, free / count),
+        JustifyContent::SpaceAround => (free / 2, 0),
+    };
+
+    let mut offsets = Vec::new();
+    let mut position = start;
+    for size in sizes {
+        offsets.push(position);
+        position += size + gap;
+    }
+    offsets
+}
+
+/// position が、point を左上とする size の大きさの領域に含まれる場合に true を返す。
+fn rect_contains(point: LayoutPoint, size: LayoutSize, position: (i64, i64)) -> bool {
+    point.x() <= position.0
+        && position.0 < point.x() + size.width()
+        && point.y() <= position.1
+        && position.1 < point.y() + size.height()
 }
 
 impl LayoutPoint {
@@ -125,12 +239,17 @@ impl LayoutSize {
 pub struct LayoutObject {
     kind: LayoutObjectKind,
     node: Rc<RefCell<Node>>,
-    first_child: Option<Rc<RefCell<LayoutObject>>>,
-    next_sibling: Option<Rc<RefCell<LayoutObject>>>,
+    children: Vec<Rc<RefCell<LayoutObject>>>,
     parent: Weak<RefCell<LayoutObject>>,
     style: ComputedStyle,
     point: LayoutPoint,
     size: LayoutSize,
+    // 包含ブロックのコンテンツボックスからの相対位置
+    offset: LayoutPoint,
+    // テキストの場合、行ごとに分割したテキストの断片
+    fragments: Vec<TextFragment>,
+    // 画像の場合、受信した画像から読み取った内在的な大きさ。画像を受信していない場合は None となる。
+    intrinsic_size: Option<LayoutSize>,
 }
 
 impl LayoutObject {
@@ -143,12 +262,14 @@ impl LayoutObject {
         Self {
             kind: LayoutObjectKind::Block,
             node: node.clone(),
-            first_child: None,
-            next_sibling: None,
+            children: Vec::new(),
             parent,
             style: ComputedStyle::new(),
             point: LayoutPoint::new(0, 0),
             size: LayoutSize::new(0, 0),
+            offset: LayoutPoint::new(0, 0),
+            fragments: Vec::new(),
+            intrinsic_size: None,
         }
     }
 
@@ -160,20 +281,40 @@ impl LayoutObject {
         self.node.borrow().kind().clone()
     }
 
-    pub fn set_first_child(&mut self, first_child: Option<Rc<RefCell<LayoutObject>>>) {
-        self.first_child = first_child;
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
     }
 
-    pub fn first_child(&self) -> Option<Rc<RefCell<LayoutObject>>> {
-        self.first_child.as_ref().cloned()
+    /// <iframe> や <img> のように、子ノードではなく外部のコンテンツを表示する置換要素の場合に true を返す。
+    /// https://html.spec.whatwg.org/multipage/rendering.html#replaced-elements
+    pub fn is_replaced(&self) -> bool {
+        matches!(
+            self.node.borrow().get_element_kind(),
+            Some(ElementKind::Iframe) | Some(ElementKind::Img)
+        )
     }
 
-    pub fn set_next_sibling(&mut self, next_sibling: Option<Rc<RefCell<LayoutObject>>>) {
-        self.next_sibling = next_sibling;
+    /// 画像の内在的な大きさを設定する。
+    pub fn set_intrinsic_size(&mut self, size: LayoutSize) {
+        self.intrinsic_size = Some(size);
     }
 
-    pub fn next_sibling(&self) -> Option<Rc<RefCell<LayoutObject>>> {
-        self.next_sibling.as_ref().cloned()
+    pub fn intrinsic_size(&self) -> Option<LayoutSize> {
+        self.intrinsic_size
+    }
+
+    /// 子ノードを末尾に追加する。
+    pub fn append_child(&mut self, child: Rc<RefCell<LayoutObject>>) {
+        self.children.push(child);
+    }
+
+    /// 子ノードを先頭から順に返す。
+    pub fn children(&self) -> Vec<Rc<RefCell<LayoutObject>>> {
+        self.children.clone()
+    }
+
+    pub fn first_child(&self) -> Option<Rc<RefCell<LayoutObject>>> {
+        self.children.first().cloned()
     }
 
     pub fn parent(&self) -> Weak<RefCell<Self>> {
@@ -192,92 +333,284 @@ impl LayoutObject {
         self.size
     }
 
+    /// テキストを行ごとに分割した断片を返す。断片の位置は、描画する位置である。
+    pub fn text_fragments(&self) -> Vec<TextFragment> {
+        self.fragments
+            .iter()
+            .map(|fragment| fragment.translate(self.point.x(), self.point.y()))
+            .collect()
+    }
+
+    /// position がノードの描画される領域に含まれる場合に true を返す。
+    /// テキストは行ごとの断片で判定するため、折り返した行の後ろの空いた部分は含まない。
+    /// インライン要素は、子孫のテキストの断片やインラインブロック要素などの領域で判定する。
+    /// それ以外のノードは、ボーダーボックスで判定する。
+    pub fn hit_test(&self, position: (i64, i64)) -> bool {
+        match self.kind {
+            LayoutObjectKind::Text => self
+                .text_fragments()
+                .iter()
+                .any(|fragment| rect_contains(fragment.point(), fragment.size(), position)),
+            LayoutObjectKind::Inline if !self.is_replaced() => self
+                .children
+                .iter()
+                .any(|child| child.borrow().hit_test(position)),
+            _ => rect_contains(self.point, self.size, position),
+        }
+    }
+
+    /// 子ノードを配置する領域 (コンテンツボックス) の位置を返す。
+    /// ノードの位置は線の外側 (ボーダーボックス) の位置のため、線の幅とパディングだけずらす。
+    /// display: list-item の場合、さらに先頭のマーカーの横幅だけ右にずらす。
+    /// https://www.w3.org/TR/css-box-3/#box-model
+    pub fn content_point(&self) -> LayoutPoint {
+        let mut x = self.point.x() + self.inset(Side::Left);
+        if self.style.display() == DisplayType::ListItem {
+            x += LIST_MARKER_WIDTH;
+        }
+        LayoutPoint::new(x, self.point.y() + self.inset(Side::Top))
+    }
+
+    /// 子ノードを配置する領域 (コンテンツボックス) の大きさを返す。
+    /// display: list-item の場合、さらに先頭のマーカーの横幅だけ狭くする。
+    pub fn content_size(&self) -> LayoutSize {
+        let mut width = self.size.width() - self.horizontal_inset();
+        if self.style.display() == DisplayType::ListItem {
+            width -= LIST_MARKER_WIDTH;
+        }
+        LayoutSize::new(
+            width.max(0),
+            (self.size.height() - self.vertical_inset()).max(0),
+        )
+    }
+
+    /// マージンを含めた領域 (マージンボックス) の位置を返す。兄弟ノードの位置の計算に使う。
+    pub fn margin_box_point(&self) -> LayoutPoint {
+        LayoutPoint::new(
+            self.point.x() - self.margin(Side::Left),
+            self.point.y() - self.margin(Side::Top),
+        )
+    }
+
+    /// マージンを含めた領域 (マージンボックス) の大きさを返す。兄弟ノードの位置や親ノードの大きさの計算に使う。
+    pub fn margin_box_size(&self) -> LayoutSize {
+        LayoutSize::new(
+            self.size.width() + self.margin(Side::Left) + self.margin(Side::Right),
+            self.size.height() + self.margin(Side::Top) + self.margin(Side::Bottom),
+        )
+    }
+
+    /// インライン要素とテキストの場合、上下のマージン、パディング、線の幅は行の配置に影響しないため true を返す。
+    fn ignores_vertical_spacing(&self, side: Side) -> bool {
+        matches!(self.kind, LayoutObjectKind::Inline | LayoutObjectKind::Text)
+            && matches!(side, Side::Top | Side::Bottom)
+    }
+
+    /// 辺のマージンを px 単位で返す。
+    fn margin(&self, side: Side) -> i64 {
+        if self.ignores_vertical_spacing(side) {
+            return 0;
+        }
+        self.style.margin(side) as i64
+    }
+
+    /// 辺のパディングと線の幅の合計を px 単位で返す。
+    fn inset(&self, side: Side) -> i64 {
+        if self.ignores_vertical_spacing(side) {
+            return 0;
+        }
+        (self.style.padding(side) + self.style.border_width(side)) as i64
+    }
+
+    /// 左右のパディングと線の幅の合計を返す。
+    fn horizontal_inset(&self) -> i64 {
+        self.inset(Side::Left) + self.inset(Side::Right)
+    }
+
+    /// 上下のパディングと線の幅の合計を返す。
+    fn vertical_inset(&self) -> i64 {
+        self.inset(Side::Top) + self.inset(Side::Bottom)
+    }
+
     /// ノードが選択されているかを判断する。
     /// 引数にセレクタを取り、そのノードがセレクタに選択されている場合 true を返す。
     pub fn is_node_selected(&self, selector: &Selector) -> bool {
-        match &self.node_kind() {
-            NodeKind::Element(e) => match selector {
-                Selector::TypeSelector(type_name) => {
-                    if e.kind().to_string() == *type_name {
-                        return true;
-                    }
-                    false
-                }
-                Selector::ClassSelector(class_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "class" && attr.value() == *class_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::IdSelector(id_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "id" && attr.value() == *id_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::UnknownSelector => false,
-            },
-            _ => false,
-        }
+        selector.matches(&self.node.borrow())
     }
 
     /// CSS の宣言リスト (declarations) を引数に取り、各宣言のプロパティをノードに適用する。
     /// 複数のスタイルシートや同じ要素に複数のスタイルを定義できるが、優先して適用するスタイルを決定する仕組みをカスケードと呼ぶ。
     /// 本ブラウザでは <style> タグに直接書く内部スタイルシートのみサポートする。
-    /// background-color, color, display プロパティのみ変更できる。
+    /// background-color, color, font-size, font-weight, text-decoration, display, visibility, float,
+    /// flex-direction, justify-content, width, height, margin, padding, border の個別指定プロパティのみ変更できる。
+    /// 値が inherit、initial、unset キーワードの場合、値は defaulting_style で決める。
     pub fn cascading_style(&mut self, declarations: Vec<Declaration>) {
         for declaration in declarations {
+            // 対応しているプロパティはいずれも1つのコンポーネント値を取るため、それ以外の値は無視する。
+            let value = match declaration.value.as_slice() {
+                [value] => value,
+                _ => continue,
+            };
+            if let Some(keyword) = match value {
+                ComponentValue::Ident(name) => CssWideKeyword::from_name(name),
+                _ => None,
+            } {
+                self.style.set_keyword(&declaration.property, keyword);
+                continue;
+            }
+            // 後の宣言が優先されるため、前の宣言で指定したキーワードを取り除く。
+            self.style.remove_keyword(&declaration.property);
             match declaration.property.as_str() {
                 "background-color" => {
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(&value) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
+                    self.style
+                        .set_background_color(color_from_value(value).unwrap_or(Color::white()));
+                }
+                "color" => {
+                    self.style
+                        .set_color(color_from_value(value).unwrap_or(Color::black()));
+                }
+                "font-weight" => {
+                    let font_weight = match value {
+                        ComponentValue::Ident(value) => FontWeight::from_keyword(value),
+                        ComponentValue::Number(n) => FontWeight::from_number(*n),
+                        _ => continue,
+                    };
+                    // 解釈できない値の場合、宣言を無視する。
+                    if let Ok(font_weight) = font_weight {
+                        self.style.set_font_weight(font_weight);
                     }
-
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(&color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
+                }
+                "font-size" => match value {
+                    ComponentValue::Ident(value) => {
+                        if let Ok(font_size) = FontSize::from_keyword(value) {
+                            self.style.set_font_size(font_size);
+                        }
+                    }
+                    ComponentValue::Dimension(n, unit) if *n >= 0.0 => {
+                        if unit.eq_ignore_ascii_case("px") {
+                            self.style.set_font_size(FontSize::Px(*n));
+                        } else if unit.eq_ignore_ascii_case("em") {
+                            self.style.set_relative_font_size(*n);
+                        }
+                    }
+                    ComponentValue::Percentage(p) if *p >= 0.0 => {
+                        self.style.set_relative_font_size(p / 100.0)
+                    }
+                    // 解釈できない値の場合、宣言を無視する。
+                    _ => {}
+                },
+                // text-decoration は一括指定プロパティだが、線の種類のみを指定した場合は text-decoration-line と同じとする。
+                "text-decoration" | "text-decoration-line" => {
+                    if let ComponentValue::Ident(value) = value {
+                        if let Ok(text_decoration) = TextDecoration::from_keyword(value) {
+                            self.style.set_text_decoration(text_decoration);
+                        }
                     }
                 }
-                "color" => {
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(&value) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_color(color);
+                "visibility" => {
+                    if let ComponentValue::Ident(value) = value {
+                        if let Ok(visibility) = Visibility::from_keyword(value) {
+                            self.style.set_visibility(visibility);
+                        }
                     }
-
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(&color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_color(color);
+                }
+                "float" => {
+                    if let ComponentValue::Ident(value) = value {
+                        if let Ok(float) = Float::from_keyword(value) {
+                            self.style.set_float(float);
+                        }
+                    }
+                }
+                "flex-direction" => {
+                    if let ComponentValue::Ident(value) = value {
+                        if let Ok(flex_direction) = FlexDirection::from_keyword(value) {
+                            self.style.set_flex_direction(flex_direction);
+                        }
+                    }
+                }
+                "justify-content" => {
+                    if let ComponentValue::Ident(value) = value {
+                        if let Ok(justify_content) = JustifyContent::from_keyword(value) {
+                            self.style.set_justify_content(justify_content);
+                        }
+                    }
+                }
+                "width" | "height" => {
+                    // 解釈できない値の場合、宣言を無視する。
+                    let length = match length_from_value(value) {
+                        Some(length) => length,
+                        None => continue,
+                    };
+                    if declaration.property == "width" {
+                        self.style.set_width(length);
+                    } else {
+                        self.style.set_height(length);
                     }
                 }
                 "display" => {
-                    if let ComponentValue::Ident(value) = declaration.value {
-                        let display_type = match DisplayType::from_str(&value) {
+                    if let ComponentValue::Ident(value) = value {
+                        let display_type = match DisplayType::from_str(value) {
                             Ok(display_type) => display_type,
                             Err(_) => DisplayType::DisplayNone,
                         };
                         self.style.set_display(display_type)
                     }
                 }
-                _ => {}
+                property => {
+                    // 解釈できない値の場合、宣言を無視する。
+                    if let Some(side) = Side::from_property("margin", property) {
+                        // auto は左右の中央寄せなどに使うが、対応していないため 0 として扱う。
+                        let margin = match value {
+                            ComponentValue::Ident(keyword)
+                                if keyword.eq_ignore_ascii_case("auto") =>
+                            {
+                                Some(0.0)
+                            }
+                            _ => length_from_value(value).flatten(),
+                        };
+                        if let Some(margin) = margin {
+                            self.style.set_margin(side, margin);
+                        }
+                        continue;
+                    }
+                    if let Some(side) = Side::from_property("padding", property) {
+                        if let Some(padding) = length_from_value(value).flatten() {
+                            self.style.set_padding(side, padding);
+                        }
+                        continue;
+                    }
+                    match Side::from_border_property(property) {
+                        Some((side, "width")) => {
+                            let width = match value {
+                                ComponentValue::Ident(keyword) => line_width_from_keyword(keyword),
+                                _ => length_from_value(value).flatten(),
+                            };
+                            if let Some(width) = width {
+                                self.style.set_border_width(side, width);
+                            }
+                        }
+                        Some((side, "style")) => {
+                            if let ComponentValue::Ident(keyword) = value {
+                                if let Ok(style) = BorderStyle::from_keyword(keyword) {
+                                    self.style.set_border_style(side, style);
+                                }
+                            }
+                        }
+                        Some((side, "color")) => match value {
+                            ComponentValue::Ident(keyword)
+                                if keyword.eq_ignore_ascii_case("currentcolor") =>
+                            {
+                                self.style.set_border_color(side, None)
+                            }
+                            _ => {
+                                if let Some(color) = color_from_value(value) {
+                                    self.style.set_border_color(side, Some(color));
+                                }
+                            }
+                        },
+                        _ => {}
+                    }
+                }
             }
         }
     }
@@ -303,202 +636,473 @@ impl LayoutObject {
             NodeKind::Element(_) => {
                 let display = self.style.display();
                 match display {
-                    DisplayType::Block => self.kind = LayoutObjectKind::Block,
+                    DisplayType::Block | DisplayType::ListItem | DisplayType::Flex => {
+                        self.kind = LayoutObjectKind::Block
+                    }
                     DisplayType::Inline => self.kind = LayoutObjectKind::Inline,
+                    DisplayType::InlineBlock => self.kind = LayoutObjectKind::InlineBlock,
                     DisplayType::DisplayNone => {
                         panic!("should not create a layout object for display:none")
                     }
                 }
+                // フロートは横幅を中身に合わせる (shrink-to-fit) ため、インラインブロック要素と同じように大きさを計算する。
+                // https://www.w3.org/TR/CSS2/visudet.html#float-width
+                if self.style.float() != Float::None {
+                    self.kind = LayoutObjectKind::InlineBlock;
+                }
+                // フレックスコンテナの子要素 (フレックスアイテム) はブロック化し、float は無視する。
+                // 横に並べる場合は横幅を中身に合わせるため、インラインブロック要素と同じように大きさを計算する。
+                // https://www.w3.org/TR/css-flexbox-1/#flex-items
+                if let Some(parent) = self.parent.upgrade() {
+                    let parent_style = parent.borrow().style();
+                    if parent_style.display() == DisplayType::Flex {
+                        self.kind = match parent_style.flex_direction() {
+                            FlexDirection::Row => LayoutObjectKind::InlineBlock,
+                            FlexDirection::Column => LayoutObjectKind::Block,
+                        };
+                    }
+                }
+                // <img> 要素は display の値によらず、画像の大きさを持つ置換要素として配置する。
+                if self.node.borrow().get_element_kind() == Some(ElementKind::Img) {
+                    self.kind = LayoutObjectKind::Image;
+                }
             }
             NodeKind::Text(_) => self.kind = LayoutObjectKind::Text,
         }
     }
 
-    /// 1つのノードのサイズを計算する。
-    /// ノードがブロック要素の場合、親ノードの横幅がそのまま自身の横幅になる。
-    /// ノードがインライン要素の場合、高さも横幅も子要素のサイズを足し合わせたものとなる。
-    /// ノードがテキストの場合、まずはフォントのサイズによって文字の大きさの比率を決定する。
-    pub fn compute_size(&mut self, parent_size: LayoutSize) {
-        let mut size = LayoutSize::new(0, 0);
+    /// 子ノードのサイズを計算する前に、ブロック要素やインラインブロック要素の子ノードが使える横幅を決める。
+    /// 高さは子ノードのサイズを計算した後に compute_size で決める。
+    pub fn compute_width(&mut self, parent_size: LayoutSize) {
+        self.size.set_width(self.available_width(parent_size));
+    }
 
-        match self.kind() {
-            LayoutObjectKind::Block => {
-                size.set_width(parent_size.width());
-
-                let mut height = 0;
-                let mut child = self.first_child();
-                let mut previous_child_kind = LayoutObjectKind::Block;
-                while child.is_some() {
-                    let c = match child {
-                        Some(c) => c,
-                        None => panic!("first child should exist"),
-                    };
+    /// 親の横幅から左右のマージンを除いた、ノードが使える横幅を返す。
+    fn horizontal_space(&self, parent_size: LayoutSize) -> i64 {
+        (parent_size.width() - self.margin(Side::Left) - self.margin(Side::Right)).max(0)
+    }
 
-                    if previous_child_kind == LayoutObjectKind::Block
-                        || c.borrow().kind() == LayoutObjectKind::Block
-                    {
-                        height += c.borrow().size.height();
-                    }
+    /// width プロパティが指定されている場合はその値にパディングと線の幅を加えたものを、指定されていない場合は親の横幅からマージンを除いたものを返す。
+    /// ただし、横幅は親の横幅からマージンを除いたものを超えない。
+    fn available_width(&self, parent_size: LayoutSize) -> i64 {
+        let space = self.horizontal_space(parent_size);
+        match self.style.width() {
+            Some(width) => (width as i64 + self.horizontal_inset()).min(space),
+            None => space,
+        }
+    }
 
-                    previous_child_kind = c.borrow().kind();
-                    child = c.borrow().next_sibling();
+    /// objects のノードを先頭から順に行ボックスに配置する。
+    /// 包含ブロックのコンテンツボックスからの相対位置を決め、インライン要素とテキストの大きさも決める。
+    /// floats には、親のブロックコンテナに配置され、行が避ける必要のあるフロートを渡す。
+    /// 行の高さの合計と、最も長い行の横幅を返す。
+    /// https://www.w3.org/TR/css-inline-3/#line-boxes
+    pub fn layout_lines(
+        objects: &[Rc<RefCell<LayoutObject>>],
+        width: i64,
+        floats: Vec<FloatBox>,
+    ) -> (i64, i64) {
+        let mut builder = LineBoxBuilder::with_floats(width, floats);
+        Self::place_all_in_lines(objects, &mut builder);
+        builder.finish()
+    }
+
+    fn place_all_in_lines(objects: &[Rc<RefCell<LayoutObject>>], builder: &mut LineBoxBuilder) {
+        for object in objects {
+            object.borrow_mut().place_in_lines(builder);
+        }
+    }
+
+    /// 1つのノードを行ボックスに配置する。
+    /// フロートは通常のフローから外し、包含ブロックの左端または右端に寄せて配置する。
+    /// ブロック要素は新しい行に、インラインブロック要素と置換要素は途中で折り返さずに現在の行に配置する。
+    /// インライン要素は子ノードを続けて配置し、子ノードを囲む領域を自身の位置と大きさとする。
+    /// テキストは単語の境界で折り返し、行ごとの断片に分ける。
+    fn place_in_lines(&mut self, builder: &mut LineBoxBuilder) {
+        if self.style.float() != Float::None {
+            let size = self.margin_box_size();
+            let point = builder.place_float(self.style.float(), size.width(), size.height());
+            self.offset = LayoutPoint::new(
+                point.x() + self.margin(Side::Left),
+                point.y() + self.margin(Side::Top),
+            );
+            return;
+        }
+
+        match self.kind {
+            LayoutObjectKind::Block => {
+                // 前に配置したフロートが残っている場合、子ノードの行がフロートを避けるように配置し直す。
+                builder.break_line();
+                let content_point = self.content_point();
+                let origin = LayoutPoint::new(
+                    content_point.x() - self.point.x() + self.margin(Side::Left),
+                    builder.cursor().y() + content_point.y() - self.point.y()
+                        + self.margin(Side::Top),
+                );
+                let floats = builder.floats_from(origin);
+                if !floats.is_empty() && self.style.display() != DisplayType::Flex {
+                    let (lines_height, _) =
+                        Self::layout_lines(&self.children, self.content_size().width(), floats);
+                    if self.style.height().is_none() {
+                        self.size.set_height(lines_height + self.vertical_inset());
+                    }
                 }
-                size.set_height(height);
+
+                let point = builder.place_block(self.margin_box_size().height());
+                self.offset = LayoutPoint::new(
+                    point.x() + self.margin(Side::Left),
+                    point.y() + self.margin(Side::Top),
+                );
             }
-            // ノードがインライン要素の場合、高さも横幅も子要素のサイズを足し合わせたものとする。
-            // 本実装では、インライン要素の子ノードは常にテキストノードである。
-            LayoutObjectKind::Inline => {
-                // 全ての子ノードの高さと横幅を足し合わせた結果が現在のノードの高さと横幅になる。
-                let mut width = 0;
-                let mut height = 0;
-                let mut child = self.first_child();
-                while child.is_some() {
-                    let c = match child {
-                        Some(c) => c,
-                        None => panic!("first child should exist"),
-                    };
+            LayoutObjectKind::Inline if !self.is_replaced() => {
+                builder.advance(self.margin(Side::Left));
+                let start = builder.cursor();
+                builder.advance(self.inset(Side::Left));
+                Self::place_all_in_lines(&self.children, builder);
+                builder.advance(self.inset(Side::Right));
+                let end = builder.cursor();
 
-                    width += c.borrow().size.width();
-                    height += c.borrow().size.height();
-                    child = c.borrow().next_sibling();
+                // 子ノードと、パディングと線の幅を含めた始まりと終わりの位置を囲む領域を求める。
+                let mut rects = vec![(start, LayoutSize::new(0, 0)), (end, LayoutSize::new(0, 0))];
+                rects.extend(
+                    self.children
+                        .iter()
+                        .map(|c| (c.borrow().offset, c.borrow().size())),
+                );
+                if let Some((point, size)) = bounding_box(rects.into_iter()) {
+                    self.offset = point;
+                    self.size = size;
                 }
-                size.set_width(width);
-                size.set_height(height);
+                builder.advance(self.margin(Side::Right));
+            }
+            LayoutObjectKind::Inline | LayoutObjectKind::InlineBlock | LayoutObjectKind::Image => {
+                let size = self.margin_box_size();
+                let point = builder.place_box(size.width(), size.height());
+                self.offset = LayoutPoint::new(
+                    point.x() + self.margin(Side::Left),
+                    point.y() + self.margin(Side::Top),
+                );
             }
-            // ノードがテキストの倍、フォントのサイズによって文字の大きさの比率を決定する。
             LayoutObjectKind::Text => {
                 if let NodeKind::Text(t) = self.node_kind() {
-                    // フォントサイズによって文字の大きさの比率を決定する。
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
+                    // フォントサイズによって1文字の横幅と1行の高さを決定する。
+                    let (char_width, line_height) = char_size(self.style.font_size());
+                    // white-space: pre の場合、改行文字の位置で改行し、折り返しは行わない。
+                    let fragments = if self.style.white_space() == WhiteSpace::Pre {
+                        builder.place_preformatted_text(
+                            split_preformatted_text(&t),
+                            char_width,
+                            line_height,
+                        )
+                    } else {
+                        builder.place_text(&t, char_width, line_height)
                     };
-                    // 文字の幅、比率、文字列の長さからテキスト要素の幅を計算する。
-                    let width = CHAR_WIDTH * ratio * t.len() as i64;
-                    // もし文字列の長さが描画可能なエリアの横幅より長い場合、テキストを複数行に折り返す。
-                    if width > CONTENT_AREA_WIDTH {
-                        // テキストが複数行の場合
-                        size.set_width(CONTENT_AREA_WIDTH);
-                        // 文字列の長さを描画可能なエリアの横幅で割った結果の数値が行数になる。
-                        let line_num = if width.wrapping_rem(CONTENT_AREA_WIDTH) == 0 {
-                            width.wrapping_div(CONTENT_AREA_WIDTH)
-                        } else {
-                            // 割り切れない場合、最後の行が中途半端な位置で終わることになるため、1行追加する。
-                            width.wrapping_div(CONTENT_AREA_WIDTH) + 1
-                        };
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * line_num);
+
+                    // テキストの位置と大きさは、全ての断片を囲む領域とし、断片の位置はテキストの位置からの相対位置にする。
+                    let (point, size) =
+                        bounding_box(fragments.iter().map(|f| (f.point(), f.size())))
+                            .unwrap_or((builder.cursor(), LayoutSize::new(0, 0)));
+                    self.offset = point;
+                    self.size = size;
+                    self.fragments = fragments
+                        .iter()
+                        .map(|f| f.translate(-point.x(), -point.y()))
+                        .collect();
+                }
+            }
+        }
+    }
+
+    /// フレックスコンテナの子ノード (フレックスアイテム) を、flex-direction の方向 (主軸) に折り返さずに1列に並べる。
+    /// 主軸方向の余白は justify-content に従って配分し、アイテムの伸縮は行わない。
+    /// 交差軸方向は align-items の初期値 stretch と同じく、大きさが指定されていないアイテムを列の大きさまで伸ばす。
+    /// 子ノードのコンテンツボックスからの相対位置を決め、中身の高さと横幅を返す。
+    /// https://www.w3.org/TR/css-flexbox-1/#layout-algorithm
+    fn layout_flex_items(&self, width: i64) -> (i64, i64) {
+        let items = &self.children;
+        for item in items {
+            // テキストは無名のフレックスアイテムとして、コンテナの横幅で行ボックスに配置する。
+            if item.borrow().kind() == LayoutObjectKind::Text {
+                item.borrow_mut()
+                    .place_in_lines(&mut LineBoxBuilder::new(width));
+            }
+        }
+
+        let direction = self.style.flex_direction();
+        let sizes: Vec<LayoutSize> = items
+            .iter()
+            .map(|item| item.borrow().margin_box_size())
+            .collect();
+        let main_sizes: Vec<i64> = sizes
+            .iter()
+            .map(|size| match direction {
+                FlexDirection::Row => size.width(),
+                FlexDirection::Column => size.height(),
+            })
+            .collect();
+        let main_total: i64 = main_sizes.iter().sum();
+        let cross_max = sizes
+            .iter()
+            .map(|size| match direction {
+                FlexDirection::Row => size.height(),
+                FlexDirection::Column => size.width(),
+            })
+            .max()
+            .unwrap_or(0);
+
+        // 主軸方向と交差軸方向のコンテナの大きさ。高さが指定されていない場合は、中身の大きさに合わせる。
+        let (container_main, container_cross) = match direction {
+            FlexDirection::Row => (width, self.style.height().map_or(cross_max, |h| h as i64)),
+            FlexDirection::Column => (self.style.height().map_or(main_total, |h| h as i64), width),
+        };
+        let offsets = main_axis_offsets(&main_sizes, container_main, self.style.justify_content());
+
+        for (item, main_offset) in items.iter().zip(offsets) {
+            let mut item = item.borrow_mut();
+            if item.kind() != LayoutObjectKind::Text {
+                let cross_margin = match direction {
+                    FlexDirection::Row => item.margin(Side::Top) + item.margin(Side::Bottom),
+                    FlexDirection::Column => item.margin(Side::Left) + item.margin(Side::Right),
+                };
+                let stretched = (container_cross - cross_margin).max(0);
+                match direction {
+                    FlexDirection::Row if item.style.height().is_none() => {
+                        item.size.set_height(stretched)
                     }
-                    // テキストが 1行に収まる場合
-                    else {
-                        size.set_width(width);
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio);
+                    FlexDirection::Column if item.style.width().is_none() => {
+                        item.size.set_width(stretched)
                     }
+                    _ => {}
                 }
             }
+
+            item.offset = match direction {
+                FlexDirection::Row => LayoutPoint::new(
+                    main_offset + item.margin(Side::Left),
+                    item.margin(Side::Top),
+                ),
+                FlexDirection::Column => LayoutPoint::new(
+                    item.margin(Side::Left),
+                    main_offset + item.margin(Side::Top),
+                ),
+            };
+        }
+
+        match direction {
+            FlexDirection::Row => (cross_max, main_total),
+            FlexDirection::Column => (main_total, cross_max),
         }
-        self.size = size;
     }
 
-    /// 1つのノードの位置を計算する。
-    /// ノードの位置は、現在のノードと親ノードの一、隣り合わせの兄弟ノードによって決定する。
-    pub fn compute_position(
-        &mut self,
-        parent_point: LayoutPoint,
-        previous_sibling_kind: LayoutObjectKind,
-        previous_sibling_point: Option<LayoutPoint>,
-        previous_sibling_size: Option<LayoutSize>,
-    ) {
-        let mut point = LayoutPoint::new(0, 0);
-
-        match (self.kind(), previous_sibling_kind) {
-            // 自分自身がブロック要素、または、兄弟ノードがブロック要素の場合、このノードは新しい行から描画されることになるため、ウィンドウの下方向 (Y 軸方向) に向かって位置を調整する。
-            (LayoutObjectKind::Block, _) | (_, LayoutObjectKind::Block) => {
-                if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
-                    // 兄弟ノードが存在する場合、兄弟ノードの Y 位置と高さを足し合わせたものが次の位置になる。
-                    point.set_y(pos.y() + size.height());
-                } else {
-                    // 兄弟ノードが存在しない場合、親ノードの Y 座標をセットする。
-                    point.set_y(parent_point.y());
-                }
-                // 新しい行から始まるため、X 座標は常に親要素の X 座標と同じである。
-                point.set_x(parent_point.x());
+    /// 1つのノードのサイズを計算する。
+    /// ノードがブロック要素の場合、親ノードの横幅からマージンを除いたものが自身の横幅になる。
+    /// ただし、width や height プロパティが指定されている場合はその値を優先する。
+    /// ノードの大きさは、パディングと線の幅を含めた大きさ (ボーダーボックス) とする。
+    /// ノードがブロック要素やインラインブロック要素の場合、子ノードを行ボックスに配置し、高さは行の高さの合計になる。
+    /// ノードがインラインブロック要素の場合、横幅は子要素の最も長い行の横幅 (shrink-to-fit) になる。
+    /// インライン要素とテキストの大きさは、包含ブロックの子ノードを行ボックスに配置する時に決まる。
+    pub fn compute_size(&mut self, parent_size: LayoutSize) {
+        let mut size = LayoutSize::new(0, 0);
+
+        // 画像以外の置換要素 (<iframe>) の大きさは子ノードに依存せず、width 属性と height 属性で決まる。
+        if self.is_replaced() && self.kind != LayoutObjectKind::Image {
+            if let NodeKind::Element(e) = self.node_kind() {
+                let width = e
+                    .get_attribute("width")
+                    .and_then(|w| w.parse::<i64>().ok())
+                    .unwrap_or(IFRAME_DEFAULT_WIDTH);
+                let height = e
+                    .get_attribute("height")
+                    .and_then(|h| h.parse::<i64>().ok())
+                    .unwrap_or(IFRAME_DEFAULT_HEIGHT);
+                size.set_width(width);
+                size.set_height(height);
             }
-            // もし自分自身と兄弟ノードがインライン要素の場合、同じ行に続いて配置されるため、ウィンドウの右方向に向かって位置を調整する。
-            (LayoutObjectKind::Inline, LayoutObjectKind::Inline) => {
-                // 兄弟ノードが存在する場合
-                if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
-                    point.set_x(pos.x() + size.width()); // 兄弟ノードの X 位置と横幅を足し合わせたものが次の位置になる。
-                    point.set_y(pos.y()); // インライン要素は兄弟ノードと同じ行に並ぶため、兄弟ノードの Y 位置が自分の Y 位置になる。
+            self.size = size;
+            return;
+        }
+
+        match self.kind() {
+            // ノードがブロック要素の場合、横幅は親の横幅、高さは子要素の行の高さを足し合わせたものとする。
+            // ノードがインラインブロック要素の場合、width プロパティが指定されていなければ、子要素の最も長い行の横幅に合わせる。
+            // width や height プロパティが指定されている場合はその値を使う。ただし、横幅は親の横幅を超えない。
+            LayoutObjectKind::Block | LayoutObjectKind::InlineBlock => {
+                // フレックスコンテナの場合、子ノードを行ボックスではなく1列に並べる。
+                let (lines_height, widest_line) = if self.style.display() == DisplayType::Flex {
+                    self.layout_flex_items(self.content_size().width())
                 } else {
-                    // 兄弟ノードが存在しない場合、親ノードの X と Y 位置をセットする。
-                    point.set_x(parent_point.x());
-                    point.set_y(parent_point.y());
-                }
+                    Self::layout_lines(&self.children, self.content_size().width(), Vec::new())
+                };
+                let width = match (self.kind, self.style.width()) {
+                    (LayoutObjectKind::InlineBlock, None) => (widest_line
+                        + self.horizontal_inset())
+                    .min(self.horizontal_space(parent_size)),
+                    _ => self.available_width(parent_size),
+                };
+                let height = match self.style.height() {
+                    Some(height) => height as i64,
+                    None => lines_height,
+                };
+                size.set_width(width);
+                size.set_height(height + self.vertical_inset());
             }
-            _ => {
-                // ブロック要素やインライン要素ではない場合（テキストノードの場合）、親ノードの位置と同じ位置に描画する。
-                point.set_x(parent_point.x());
-                point.set_y(parent_point.y());
+            // 画像の大きさは、画像の内在的な大きさと、width や height の指定で決まる。
+            LayoutObjectKind::Image => {
+                let content = self.image_size();
+                size.set_width(content.width() + self.horizontal_inset());
+                size.set_height(content.height() + self.vertical_inset());
             }
+            // インライン要素とテキストの大きさは、包含ブロックの layout_lines で決まるため、ここでは何もしない。
+            LayoutObjectKind::Inline | LayoutObjectKind::Text => return,
         }
-        self.point = point;
+        self.size = size;
+    }
+
+    /// 画像のコンテンツボックスの大きさを返す。
+    /// width と height プロパティ、次に width 属性と height 属性の指定を使い、指定がない方向は内在的な大きさの縦横比を保つように決める。
+    /// 両方とも指定がない場合は内在的な大きさを使い、画像を受信していない場合は 0 とする。
+    /// https://www.w3.org/TR/css-images-3/#default-sizing
+    fn image_size(&self) -> LayoutSize {
+        let attribute = |name: &str| match self.node_kind() {
+            NodeKind::Element(e) => e.get_attribute(name).and_then(|v| v.parse::<i64>().ok()),
+            _ => None,
+        };
+        let width = self
+            .style
+            .width()
+            .map(|w| w as i64)
+            .or_else(|| attribute("width"));
+        let height = self
+            .style
+            .height()
+            .map(|h| h as i64)
+            .or_else(|| attribute("height"));
+        let intrinsic = self.intrinsic_size.unwrap_or(LayoutSize::new(0, 0));
+
+        match (width, height) {
+            (Some(w), Some(h)) => LayoutSize::new(w, h),
+            (Some(w), None) if intrinsic.width() > 0 => {
+                LayoutSize::new(w, w * intrinsic.height() / intrinsic.width())
+            }
+            (None, Some(h)) if intrinsic.height() > 0 => {
+                LayoutSize::new(h * intrinsic.width() / intrinsic.height(), h)
+            }
+            (Some(w), None) => LayoutSize::new(w, intrinsic.height()),
+            (None, Some(h)) => LayoutSize::new(intrinsic.width(), h),
+            (None, None) => intrinsic,
+        }
+    }
+
+    /// 1つのノードの位置を計算する。
+    /// ノードの位置は、包含ブロックのコンテンツボックスの位置に、行ボックスに配置した時の相対位置を足したものになる。
+    pub fn compute_position(&mut self, containing_block_point: LayoutPoint) {
+        self.point = LayoutPoint::new(
+            containing_block_point.x() + self.offset.x(),
+            containing_block_point.y() + self.offset.y(),
+        );
+    }
+
+    /// リストの項目のマーカーを返す。
+    /// <ol> の子要素の場合は "1." から始まる番号、それ以外の場合は "*" とする。
+    /// https://www.w3.org/TR/css-lists-3/#markers
+    fn list_marker(&self) -> String {
+        let node = self.node.borrow();
+        let parent = match node.parent().upgrade() {
+            Some(parent) => parent,
+            None => return "*".to_string(),
+        };
+        if parent.borrow().get_element_kind() != Some(ElementKind::Ol) {
+            return "*".to_string();
+        }
+
+        // 前にある <li> の兄弟要素の数から、項目の番号を決める。
+        let mut number = 1;
+        let mut sibling = node.previous_sibling().upgrade();
+        while let Some(s) = sibling {
+            if s.borrow().get_element_kind() == Some(ElementKind::Li) {
+                number += 1;
+            }
+            sibling = s.borrow().previous_sibling().upgrade();
+        }
+        format!("{}.", number)
     }
 
     /// そのノードを DisplayItem に変換する。
     pub fn paint(&mut self) -> Vec<DisplayItem> {
-        if self.style.display() == DisplayType::DisplayNone {
+        // visibility: hidden の場合、display: none とは異なり大きさと位置は計算済みだが、描画はしない。
+        // 子ノードの visibility は個別に判断するため、子ノードは visible であれば描画される。
+        if self.style.display() == DisplayType::DisplayNone
+            || self.style.visibility() == Visibility::Hidden
+        {
             return vec![];
         }
 
         match self.kind {
             // ノードがブロック要素の場合、ノードのスタイル、位置、サイズをそのまま使用して DisplayItem::Rect を作成して返す。
-            LayoutObjectKind::Block => {
+            // display: list-item の場合、子ノードの左側にマーカーのテキストも描画する。
+            LayoutObjectKind::Block | LayoutObjectKind::InlineBlock => {
                 if let NodeKind::Element(_e) = self.node_kind() {
-                    return vec![DisplayItem::Rect {
+                    let mut v = vec![DisplayItem::Rect {
                         style: self.style(),
                         layout_point: self.point(),
                         layout_size: self.size(),
                     }];
+                    if self.style.display() == DisplayType::ListItem {
+                        // マーカーは、パディングと線の内側の左上に描画する。
+                        let marker_point = LayoutPoint::new(
+                            self.point.x() + self.inset(Side::Left),
+                            self.point.y() + self.inset(Side::Top),
+                        );
+                        v.push(DisplayItem::Text {
+                            text: self.list_marker(),
+                            bold: self.style.font_weight() == FontWeight::Bold,
+                            underline: false,
+                            line_through: false,
+                            style: self.style(),
+                            layout_point: marker_point,
+                        });
+                    }
+                    return v;
                 }
             }
             // ノードがインライン要素の場合、本ブラウザでは描画するインライン要素がないため何も行わない。
             LayoutObjectKind::Inline => {
                 // 本ブラウザでは、描画するインライン要素はない。
-                // <img> タグなどをサポートした場合はこのアームの中で処理する。
             }
-            // ノードがテキストノードの場合、ノードのサイズを計算したときと同じようにフォントのサイズと1文字の横幅の情報をもとに、改行すべき位置を探す。
+            // ノードが画像の場合、背景を DisplayItem::Rect として描画し、コンテンツボックスに DisplayItem::Image を作成する。
+            LayoutObjectKind::Image => {
+                let src = match self.node_kind() {
+                    NodeKind::Element(e) => e.get_attribute("src").unwrap_or_default(),
+                    _ => String::new(),
+                };
+                return vec![
+                    DisplayItem::Rect {
+                        style: self.style(),
+                        layout_point: self.point(),
+                        layout_size: self.size(),
+                    },
+                    DisplayItem::Image {
+                        src,
+                        layout_point: self.content_point(),
+                        layout_size: self.content_size(),
+                    },
+                ];
+            }
+            // ノードがテキストノードの場合、行ごとに分割したテキストの断片ごとに DisplayItem::Text を作成する。
             // テキストが複数行になる場合、複数の DisplayItem::Text オブジェクトを返す。
             LayoutObjectKind::Text => {
-                if let NodeKind::Text(t) = self.node_kind() {
-                    let mut v = vec![];
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    let plain_text = t
-                        .replace("\n", " ")
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let lines = split_text(plain_text, CHAR_WIDTH * ratio);
-                    let mut i = 0;
-                    for line in lines {
-                        let item = DisplayItem::Text {
-                            text: line,
-                            style: self.style(),
-                            layout_point: LayoutPoint::new(
-                                self.point().x(),
-                                self.point().y() + CHAR_HEIGHT_WITH_PADDING * i,
-                            ),
-                        };
-                        v.push(item);
-                        i += 1;
-                    }
-                    return v;
-                }
+                return self
+                    .text_fragments()
+                    .into_iter()
+                    .map(|fragment| DisplayItem::Text {
+                        text: fragment.text(),
+                        bold: self.style.font_weight() == FontWeight::Bold,
+                        underline: self.style.text_decoration() == TextDecoration::Underline,
+                        line_through: self.style.text_decoration() == TextDecoration::LineThrough,
+                        style: self.style(),
+                        layout_point: fragment.point(),
+                    })
+                    .collect();
             }
         }
         vec![]
@@ -548,6 +1152,20 @@ pub fn create_layout_object(
     None
 }
 
+/// LayoutObject 構造体の破棄。
+/// Node と同様に、深いレイアウトツリーでも破棄の処理が再帰しないように、子ノードをスタックに移してから破棄する。
+impl Drop for LayoutObject {
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<RefCell<LayoutObject>>> = core::mem::take(&mut self.children);
+        while let Some(object) = stack.pop() {
+            if let Ok(object) = Rc::try_unwrap(object) {
+                let mut object = object.into_inner();
+                stack.append(&mut object.children);
+            }
+        }
+    }
+}
+
 /// LayoutObject 構造体の PartialEq トレイトの実装
 impl PartialEq for LayoutObject {
     /// LayoutObject 構造体の比較
@@ -17,17 +17,37 @@ use core::cell::RefCell;
 #[derive(Debug, Clone)]
 pub struct LayoutView {
     root: Option<Rc<RefCell<LayoutObject>>>,
+    // 文書全体の高さ。描画可能なエリアの高さを超える場合、はみ出した部分はスクロールして表示する。
+    document_height: i64,
 }
 
 impl LayoutView {
     pub fn new(root: Rc<RefCell<Node>>, cssom: &StyleSheet) -> Self {
+        Self::with_images(root, cssom, &[])
+    }
+
+    /// 受信済みの画像の内在的な大きさを使って、レイアウトツリーを作成する。
+    /// images には、<img> 要素のノードと、その画像から読み取った内在的な大きさの組を渡す。
+    pub fn with_images(
+        root: Rc<RefCell<Node>>,
+        cssom: &StyleSheet,
+        images: &[(Rc<RefCell<Node>>, LayoutSize)],
+    ) -> Self {
         // レイアウトツリーは描画される要素だけを持つツリーなので、<body> タグを取得し、その子要素以下をレイアウトツリーのノードに変換する。
         let body_root = get_target_element_node(Some(root), ElementKind::Body);
 
         let mut tree = Self {
-            root: build_layout_tree(&body_root, &None, cssom),
+            root: build_layout_tree(&body_root, cssom),
+            document_height: 0,
         };
 
+        // 画像の大きさは行ボックスへの配置に影響するため、レイアウトを計算する前に設定する。
+        for (node, size) in images {
+            if let Some(object) = tree.find_layout_object(node) {
+                object.borrow_mut().set_intrinsic_size(*size);
+            }
+        }
+
         tree.update_layout();
         tree
     }
@@ -36,146 +56,143 @@ impl LayoutView {
         self.root.clone()
     }
 
+    /// 文書全体の高さを返す。
+    /// ルートノードを配置した行ボックスの高さであり、描画可能なエリアの高さに制限されない。
+    pub fn document_height(&self) -> i64 {
+        self.document_height
+    }
+
     /// ノードの位置、サイズ情報の更新
     /// レイアウトツリーのノードをどこに描画するかを決定するため、位置とサイズを計算する必要がある。
     /// 本メソッドは構築し終えたレイアウトツリーに対して、各ノードのサイズと位置を計算する。    
     fn update_layout(&mut self) {
-        Self::calculate_node_size(&self.root, LayoutSize::new(CONTENT_AREA_WIDTH, 0));
+        let root = self.root.as_slice();
+        Self::calculate_node_size(root, LayoutSize::new(CONTENT_AREA_WIDTH, 0));
 
-        Self::calculate_node_position(
-            &self.root,
-            LayoutPoint::new(0, 0),
-            LayoutObjectKind::Block,
-            None,
-            None,
-        )
+        // ルートノードは、描画可能なエリアを包含ブロックとして配置する。
+        let (height, _) = LayoutObject::layout_lines(root, CONTENT_AREA_WIDTH, Vec::new());
+        self.document_height = height;
+
+        Self::calculate_node_position(root, LayoutPoint::new(0, 0))
     }
 
     /// サイズの計算
     /// レイアウトツリーの各ノードのサイズを再帰的に計算する。
-    /// 第1引数: ターゲットのノード
+    /// 兄弟ノードは再帰呼び出しではなくループでたどるため、再帰の深さはツリーの深さまでとなる。
+    /// 第1引数: ターゲットのノード (同じ親を持つ兄弟ノード)
     /// 第2引数: 親ノードのサイズ
-    fn calculate_node_size(node: &Option<Rc<RefCell<LayoutObject>>>, parent_size: LayoutSize) {
-        if let Some(n) = node {
-            // ノードがブロック要素の場合、子ノードのレイアウトを計算する前に横幅を決める。
-            if n.borrow().kind() == LayoutObjectKind::Block {
-                n.borrow_mut().compute_size(parent_size);
+    fn calculate_node_size(nodes: &[Rc<RefCell<LayoutObject>>], parent_size: LayoutSize) {
+        for n in nodes {
+            // ノードがブロック要素やインラインブロック要素の場合、子ノードのレイアウトを計算する前に横幅を決める。
+            if matches!(
+                n.borrow().kind(),
+                LayoutObjectKind::Block | LayoutObjectKind::InlineBlock
+            ) {
+                n.borrow_mut().compute_width(parent_size);
             }
 
-            let first_child = n.borrow().first_child();
-            Self::calculate_node_size(&first_child, n.borrow().size());
-
-            let next_sibling = n.borrow().next_sibling();
-            Self::calculate_node_size(&next_sibling, parent_size);
+            // インライン要素の子ノードは、インライン要素を含むブロックコンテナの行に配置されるため、親ノードの大きさをそのまま渡す。
+            let children = n.borrow().children();
+            let content_size = match n.borrow().kind() {
+                LayoutObjectKind::Inline => parent_size,
+                _ => n.borrow().content_size(),
+            };
+            Self::calculate_node_size(&children, content_size);
 
             // 子ノードのサイズが決まった後にサイズを計算する。
-            // ブロック要素の時、高さは子ノードの高さに依存する。
-            // インライン要素の時、高さも横幅も子ノードに依存する。
+            // ブロック要素の時、子ノードを行ボックスに配置し、高さは行の高さに依存する。
             n.borrow_mut().compute_size(parent_size);
         }
     }
 
     /// 位置の計算
     /// レイアウトツリーのノードの位置を再帰的に計算する。
-    /// 第1引数: 計算ターゲットのノード
-    /// 第2引数: 親ノードの位置
-    /// 第3引数: 自分より前の兄弟ノードの種類
-    /// 第4引数: 自分より前の兄弟ノードの①
-    /// 第5引数: 自分より前の兄弟ノードのサイズ
+    /// 各ノードの包含ブロックからの相対位置は、サイズの計算の際に行ボックスに配置して決まっている。
+    /// 兄弟ノードは再帰呼び出しではなくループでたどるため、再帰の深さはツリーの深さまでとなる。
+    /// 第1引数: 計算ターゲットのノード (同じ親を持つ兄弟ノード)
+    /// 第2引数: 包含ブロックのコンテンツボックスの位置
     fn calculate_node_position(
-        node: &Option<Rc<RefCell<LayoutObject>>>,
-        parent_point: LayoutPoint,
-        previous_sibling_kind: LayoutObjectKind,
-        previous_sibling_point: Option<LayoutPoint>,
-        previous_sibling_size: Option<LayoutSize>,
+        nodes: &[Rc<RefCell<LayoutObject>>],
+        containing_block_point: LayoutPoint,
     ) {
-        if let Some(n) = node {
+        for n in nodes {
             // 現在のノードの位置を計算する。
-            n.borrow_mut().compute_position(
-                parent_point,
-                previous_sibling_kind,
-                previous_sibling_point,
-                previous_sibling_size,
-            );
+            n.borrow_mut().compute_position(containing_block_point);
 
             // ノードの子ノードの位置を計算する。
-            let first_child = n.borrow().first_child();
-            Self::calculate_node_position(
-                &first_child,
-                n.borrow().point(),
-                LayoutObjectKind::Block,
-                None, // 子ノードには自分より前の兄弟ノードが存在しないため、None を渡す。
-                None, // 子ノードには自分より前の兄弟ノードが存在しないため、None を渡す。
-            );
-
-            // ノードの兄弟ノードの位置を計算する。
-            let next_sibling = n.borrow().next_sibling();
-            Self::calculate_node_position(
-                &next_sibling,
-                parent_point,
-                n.borrow().kind(),
-                Some(n.borrow().point()),
-                Some(n.borrow().size()),
-            );
+            // インライン要素の子ノードは、インライン要素と同じ包含ブロックの行に配置されている。
+            let children = n.borrow().children();
+            let content_point = match n.borrow().kind() {
+                LayoutObjectKind::Inline => containing_block_point,
+                _ => n.borrow().content_point(),
+            };
+            Self::calculate_node_position(&children, content_point);
         }
     }
 
-    /// 現在のノードを DisplayItem 列挙型のベクタに変換する。
-    /// また、子ノードや兄弟ノードに対して再帰的に呼び出し、各ノードの結果を DisplayItem 列挙型のベクタに extend で結合することで、描画に必要な情報のベクタを作成する。
-    fn paint_node(node: &Option<Rc<RefCell<LayoutObject>>>, display_items: &mut Vec<DisplayItem>) {
-        match node {
-            Some(n) => {
-                display_items.extend(n.borrow_mut().paint());
-                let first_child = n.borrow().first_child();
-                Self::paint_node(&first_child, display_items);
-                let next_sibling = n.borrow().next_sibling();
-                Self::paint_node(&next_sibling, display_items);
-            }
-            None => (),
+    /// 兄弟ノードを順に DisplayItem 列挙型のベクタに変換する。
+    /// また、子ノードに対して再帰的に呼び出し、各ノードの結果を DisplayItem 列挙型のベクタに extend で結合することで、描画に必要な情報のベクタを作成する。
+    fn paint_node(nodes: &[Rc<RefCell<LayoutObject>>], display_items: &mut Vec<DisplayItem>) {
+        for n in nodes {
+            display_items.extend(n.borrow_mut().paint());
+            let children = n.borrow().children();
+            Self::paint_node(&children, display_items);
         }
     }
 
     /// paint_node を呼び出し、レイアウトツリーを走査する。
     pub fn paint(&self) -> Vec<DisplayItem> {
         let mut display_items = Vec::new();
-        Self::paint_node(&self.root, &mut display_items);
+        Self::paint_node(self.root.as_slice(), &mut display_items);
         display_items
     }
 
-    /// 指定された位置がレイアウトツリーのどのノードを指しているかを見つける。
-    pub fn find_node_by_position(&self, position: (i64, i64)) -> Option<Rc<RefCell<LayoutObject>>> {
-        Self::find_node_by_position_internal(&self.root(), position)
+    /// DOM ツリーのノードに対応するレイアウトオブジェクトを返す。
+    /// display:none などでレイアウトオブジェクトが作成されていない場合は None を返す。
+    pub fn find_layout_object(
+        &self,
+        node: &Rc<RefCell<Node>>,
+    ) -> Option<Rc<RefCell<LayoutObject>>> {
+        Self::find_layout_object_internal(self.root.as_slice(), node)
     }
 
-    /// 再帰的に呼ぶことでクリックした位置にある葉ノード、つまり、木構造の下位の末端にあるノードを返す。
-    fn find_node_by_position_internal(
-        node: &Option<Rc<RefCell<LayoutObject>>>,
-        position: (i64, i64),
+    fn find_layout_object_internal(
+        objects: &[Rc<RefCell<LayoutObject>>],
+        node: &Rc<RefCell<Node>>,
     ) -> Option<Rc<RefCell<LayoutObject>>> {
-        match node {
-            Some(n) => {
-                let first_child = n.borrow().first_child();
-                let result1 = Self::find_node_by_position_internal(&first_child, position);
-                if result1.is_some() {
-                    return result1;
-                }
-
-                let next_sibling = n.borrow().next_sibling();
-                let result2 = Self::find_node_by_position_internal(&next_sibling, position);
-                if result2.is_some() {
-                    return result2;
-                }
-
-                if n.borrow().point().x() <= position.0
-                    && position.0 <= (n.borrow().point().x() + n.borrow().size().width())
-                    && n.borrow().point().y() <= position.1
-                    && position.1 <= (n.borrow().point().y() + n.borrow().size().height())
-                {
-                    return Some(n.clone());
-                }
-                None
+        for o in objects {
+            if Rc::ptr_eq(&o.borrow().node(), node) {
+                return Some(o.clone());
+            }
+            let children = o.borrow().children();
+            if let Some(found) = Self::find_layout_object_internal(&children, node) {
+                return Some(found);
             }
-            None => None,
+        }
+        None
+    }
+
+    /// 指定された位置がレイアウトツリーのどのノードを指しているかを見つける。
+    /// 後に描画されたノードほど手前に表示されるため、描画する順番の逆順に調べ、最初に位置を含むノードを返す。
+    /// 子ノードは親ノードの後に描画されるため、位置を含む最も下位のノードが見つかる。
+    pub fn find_node_by_position(&self, position: (i64, i64)) -> Option<Rc<RefCell<LayoutObject>>> {
+        let mut nodes = Vec::new();
+        Self::collect_in_paint_order(self.root.as_slice(), &mut nodes);
+        nodes
+            .into_iter()
+            .rev()
+            .find(|n| n.borrow().hit_test(position))
+    }
+
+    /// 兄弟ノードとその子孫ノードを、paint_node で描画する順番に nodes に追加する。
+    fn collect_in_paint_order(
+        objects: &[Rc<RefCell<LayoutObject>>],
+        nodes: &mut Vec<Rc<RefCell<LayoutObject>>>,
+    ) {
+        for n in objects {
+            nodes.push(n.clone());
+            let children = n.borrow().children();
+            Self::collect_in_paint_order(&children, nodes);
         }
     }
 }
@@ -183,104 +200,73 @@ impl LayoutView {
 /// レイアウトツリーの作成
 /// レイアウトツリーはレイアウトオブジェクトをノードとして持つ木構造である。
 /// レイアウトツリーを構築するには、DOM ツリーをルートノードから走査しながら DOM ノードからレイアウトオブジェクトを作成する。
-/// 本関数を再帰的に呼び出してレイアウトオブジェクトを構築する。
+/// 深いツリーや兄弟ノードの多いツリーでもコールスタックを溢れさせないように、再帰呼び出しの代わりに、子ノードをたどる必要のある DOM ノードとそのレイアウトオブジェクトの組をスタックに積んで処理する。
 fn build_layout_tree(
-    node: &Option<Rc<RefCell<Node>>>, // 現在の DOM ツリーのノード
-    parent_obj: &Option<Rc<RefCell<LayoutObject>>>, // 親のレイアウトオブジェクト
+    node: &Option<Rc<RefCell<Node>>>, // ルートとなる DOM ツリーのノード
     cssom: &StyleSheet,               // CSS スタイルシート
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     // create_layout_object 関数によって、ノードとなる LayoutObject の作成を試みる。
     // CSS によって "display:none" が指定されていた場合、ノードは作成されない。
-    let mut target_node = node.clone();
-    let mut layout_object = create_layout_object(node, parent_obj, cssom);
-
-    // もしノードが作成されなかった場合、DOM ノードの兄弟ノードを使用して LayoutObject の作成を試みる。
-    // LyaoutObject が作成されるまで、兄弟ノードをたどり続ける。
-    while layout_object.is_none() {
-        if let Some(n) = target_node {
-            target_node = n.borrow().next_sibling().clone();
-            layout_object = create_layout_object(&target_node, parent_obj, cssom);
-        } else {
-            // もし兄弟ノードがない場合、処理すべき DOM ツリーは終了したので、今まで作成したレイアウトツリーを返却する。
-            return layout_object;
-        }
-    }
+    let root = create_layout_object(node, &None, cssom)?;
 
-    if let Some(n) = target_node {
-        // 現在処理している DMO ノードの子ノードと兄弟ノードに対して、再帰的に本関数を呼び出し、子と兄弟のレイアウトツリーを構築する。
-        let original_first_child = n.borrow().first_child();
-        let original_next_sibling = n.borrow().next_sibling();
-        let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom);
-        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom);
-
-        // もし子ノードに "display:none" が指定されていた場合、LayoutObject は作成されないため、
-        // 子ノードの兄弟ノードを使用して LayoutObject の作成を試みる。
-        // LayoutObject が作成されるか、たどるべき兄弟ノードがなくなるまで処理を繰り返す。
-        if first_child.is_none() && original_first_child.is_some() {
-            let mut original_dom_node = original_first_child
-                .expect("first child should exist")
-                .borrow()
-                .next_sibling();
-
-            loop {
-                first_child = build_layout_tree(&original_dom_node, &layout_object, cssom);
-
-                if first_child.is_none() && original_dom_node.is_some() {
-                    original_dom_node = original_dom_node
-                        .expect("next sibling should exist")
-                        .borrow()
-                        .next_sibling();
-                    continue;
-                }
-                break;
-            }
+    let mut stack = Vec::new();
+    if let Some(n) = node {
+        stack.push((n.clone(), root.clone()));
+    }
+    while let Some((dom_node, layout_object)) = stack.pop() {
+        // 置換要素の子ノードは表示しないため、レイアウトツリーに追加しない。
+        if layout_object.borrow().is_replaced() {
+            continue;
         }
 
-        // もし兄弟ノードに "display:node" が指定されていた場合、LayoutObject は作成されないため、兄弟ノードの兄弟ノードを使用して LayoutObject の作成を試みる。
-        // LayoutObject が作成されるか、たどるべき兄弟ノードがなくなるまで処理を繰り返す。
-        if next_sibling.is_none() && n.borrow().next_sibling().is_some() {
-            let mut original_dom_node = original_next_sibling
-                .expect("first child should exist")
-                .borrow()
-                .next_sibling();
-
-            loop {
-                next_sibling = build_layout_tree(&original_dom_node, &None, cssom);
-
-                if next_sibling.is_none() && original_dom_node.is_some() {
-                    original_dom_node = original_dom_node
-                        .expect("next sibling should exitst")
-                        .borrow()
-                        .next_sibling();
-                    continue;
-                }
-                break;
-            }
+        // 子ノードのうち LayoutObject が作成されたものを、順に子ノードとして追加する。
+        // "display:none" が指定された子ノードは LayoutObject が作成されないため、その子孫もたどらない。
+        let children = dom_node.borrow().children();
+        for child in children {
+            let child_object = match create_layout_object(
+                &Some(child.clone()),
+                &Some(layout_object.clone()),
+                cssom,
+            ) {
+                Some(obj) => obj,
+                None => continue,
+            };
+            layout_object
+                .borrow_mut()
+                .append_child(child_object.clone());
+            stack.push((child, child_object));
         }
-
-        let obj = match layout_object {
-            Some(ref obj) => obj,
-            None => panic!("render object should exist here"),
-        };
-        obj.borrow_mut().set_first_child(first_child);
-        obj.borrow_mut().set_next_sibling(next_sibling);
     }
-
-    layout_object
+    Some(root)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+    use crate::constants::CHAR_WIDTH;
+    use crate::constants::CONTENT_AREA_HEIGHT;
+    use crate::constants::LIST_MARKER_WIDTH;
     use crate::renderer::css::cssom::CssParser;
     use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::api::get_element_by_id;
     use crate::renderer::dom::api::get_style_content;
     use crate::renderer::dom::node::Element;
     use crate::renderer::dom::node::NodeKind;
     use crate::renderer::html::parser::HtmlParser;
     use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::layout::computed_style::BorderStyle;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::renderer::layout::computed_style::DisplayType;
+    use crate::renderer::layout::computed_style::Float;
+    use crate::renderer::layout::computed_style::FontSize;
+    use crate::renderer::layout::computed_style::FontStyle;
+    use crate::renderer::layout::computed_style::FontWeight;
+    use crate::renderer::layout::computed_style::Side;
+    use crate::renderer::layout::computed_style::TextDecoration;
     use alloc::string::String;
+    use alloc::vec;
     use alloc::vec::Vec;
 
     /// 引数の HTML 文字列からレイアウトツリーを作成する関数。
@@ -330,7 +316,7 @@ mod tests {
     /// root ノードの子ノードは LayoutObjectKind::Text であり、かつ NodeKind::Text であることを確認する。
     #[test]
     fn test_text() {
-        let html = "<html<head></head><body>text</body></html>".to_string();
+        let html = "<html><head></head><body>text</body></html>".to_string();
         let layout_view = create_layout_view(html);
 
         let root = layout_view.root();
@@ -412,7 +398,9 @@ mod tests {
                 .node_kind()
         );
 
-        let p = root.expect("root should exist").borrow().first_child();
+        let root = root.expect("root should exist");
+        assert_eq!(1, root.borrow().children().len());
+        let p = root.borrow().first_child();
         assert!(p.is_some());
         assert_eq!(
             LayoutObjectKind::Block,
@@ -428,11 +416,1000 @@ mod tests {
             .borrow()
             .first_child()
             .is_none());
-        assert!(p
-            .clone()
+        // 子ノードは親ノードへのウィークポインタを持つ。
+        let parent = p
             .expect("p node should exist")
             .borrow()
-            .next_sibling()
-            .is_none());
+            .parent()
+            .upgrade()
+            .expect("parent should exist");
+        assert!(Rc::ptr_eq(&root, &parent));
+    }
+
+    /// コンテナ要素のテスト
+    /// <div> はブロック要素、<span> はインライン要素としてレイアウトツリーに追加されることを確認する。
+    #[test]
+    fn test_container_elements() {
+        let html =
+            "<html><head></head><body><div><span>text</span></div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let div = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("div node should exist");
+        assert_eq!(LayoutObjectKind::Block, div.borrow().kind());
+        assert_eq!(
+            NodeKind::Element(Element::new("div", Vec::new())),
+            div.borrow().node_kind()
+        );
+
+        let span = div.borrow().first_child().expect("span node should exist");
+        assert_eq!(LayoutObjectKind::Inline, span.borrow().kind());
+        assert_eq!(
+            NodeKind::Element(Element::new("span", Vec::new())),
+            span.borrow().node_kind()
+        );
+    }
+
+    /// 見出しのテスト
+    /// <h1> から <h6> の見出しがブロック要素になり、見出しのレベルに応じたデフォルトの文字の大きさを持つことを確認する。
+    #[test]
+    fn test_headings() {
+        let html = "<html><head></head><body><h1>1</h1><h2>2</h2><h3>3</h3><h4>4</h4><h5>5</h5><h6>6</h6></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let expected = [
+            FontSize::XXLarge,
+            FontSize::XLarge,
+            FontSize::Large,
+            FontSize::Medium,
+            FontSize::Small,
+            FontSize::XSmall,
+        ];
+        let headings = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .children();
+        assert_eq!(expected.len(), headings.len());
+        for (h, font_size) in headings.iter().zip(expected) {
+            assert_eq!(LayoutObjectKind::Block, h.borrow().kind());
+            let text = h.borrow().first_child().expect("text node should exist");
+            assert_eq!(font_size, text.borrow().style().font_size());
+        }
+    }
+
+    /// 文字を装飾するインライン要素のテスト
+    /// <strong> や <em> などがインライン要素になり、その中のテキストに太字や斜体などのスタイルが継承されることを確認する。
+    #[test]
+    fn test_inline_formatting() {
+        let html = "<html><head></head><body><p><strong>s</strong><em><u>e</u></em><small>m</small></p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p node should exist");
+
+        let children = p.borrow().children();
+        assert_eq!(3, children.len());
+        let strong = &children[0];
+        assert_eq!(LayoutObjectKind::Inline, strong.borrow().kind());
+        let text = strong
+            .borrow()
+            .first_child()
+            .expect("text node should exist");
+        assert_eq!(FontWeight::Bold, text.borrow().style().font_weight());
+        assert_eq!(FontStyle::Normal, text.borrow().style().font_style());
+
+        let em = &children[1];
+        assert_eq!(LayoutObjectKind::Inline, em.borrow().kind());
+        let text = em
+            .borrow()
+            .first_child()
+            .expect("u node should exist")
+            .borrow()
+            .first_child()
+            .expect("text node should exist");
+        assert_eq!(FontWeight::Normal, text.borrow().style().font_weight());
+        assert_eq!(FontStyle::Italic, text.borrow().style().font_style());
+        assert_eq!(
+            TextDecoration::Underline,
+            text.borrow().style().text_decoration()
+        );
+
+        let text = children[2]
+            .borrow()
+            .first_child()
+            .expect("text node should exist");
+        assert_eq!(FontSize::Small, text.borrow().style().font_size());
+    }
+
+    /// inherit、initial、unset キーワードのテスト
+    /// キーワードを指定したプロパティの値が、要素の種類によるデフォルトの値ではなく、親の値または初期値になることを確認する。
+    #[test]
+    fn test_css_wide_keywords() {
+        let html = "<html><head><style>div { color: red; } p { color: blue; } #a { color: inherit; } #b { color: initial; } #c { font-size: inherit; } #d { font-style: unset; } #e { display: unset; } #f { color: inherit; color: green; }</style></head><body><div><p id=\"a\">a</p><p id=\"b\">b</p><h1 id=\"c\">c</h1><em id=\"d\">d</em><div id=\"e\">e</div><p id=\"f\">f</p></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let style_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            let object = layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist");
+            let style = object.borrow().style();
+            let kind = object.borrow().kind();
+            (style, kind)
+        };
+
+        // <p> の color: blue より後の inherit が優先され、親の <div> の色を継承する。
+        assert_eq!(Color::from_name("red").unwrap(), style_of("a").0.color());
+        assert_eq!(Color::black(), style_of("b").0.color());
+        // <h1> のデフォルトの文字の大きさではなく、親の大きさを継承する。
+        assert_eq!(FontSize::Medium, style_of("c").0.font_size());
+        // font-style は継承プロパティのため、unset は inherit として扱う。
+        assert_eq!(FontStyle::Normal, style_of("d").0.font_style());
+        // display は継承プロパティではないため、unset は initial (inline) として扱う。
+        assert_eq!(LayoutObjectKind::Inline, style_of("e").1);
+        // inherit より後の宣言が優先される。
+        assert_eq!(Color::from_name("green").unwrap(), style_of("f").0.color());
+    }
+
+    /// 色の名前、カラーコード、rgb() と rgba() 関数による色の指定のテスト
+    #[test]
+    fn test_color_values() {
+        let html = "<html><head><style>#a { color: RebeccaPurple; background-color: transparent; } #b { color: #0f0; background-color: #FFA500; } #c { color: rgb(255, 128, 0); background-color: rgba(0, 0, 255, 0.5); } #d { color: rgb(100% 0% 50% / 25%); background-color: #12; } #e { color: unknown; background-color: rgb(1, 2); }</style></head><body><p id=\"a\">a</p><p id=\"b\">b</p><p id=\"c\">c</p><p id=\"d\">d</p><p id=\"e\">e</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let style_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            let object = layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist");
+            let style = object.borrow().style();
+            style
+        };
+
+        let a = style_of("a");
+        assert_eq!(0x663399, a.color().code_u32());
+        assert_eq!(0.0, a.background_color().alpha());
+
+        let b = style_of("b");
+        assert_eq!(Color::from_name("lime").unwrap(), b.color());
+        assert_eq!(Color::from_name("orange").unwrap(), b.background_color());
+
+        let c = style_of("c");
+        assert_eq!(0xff8000, c.color().code_u32());
+        assert_eq!(1.0, c.color().alpha());
+        assert_eq!(0x0000ff, c.background_color().code_u32());
+        assert_eq!(0.5, c.background_color().alpha());
+
+        let d = style_of("d");
+        assert_eq!(Color::from_rgba(255, 0, 128, 0.25), d.color());
+        // 解釈できないカラーコードの場合、背景色は白になる。
+        assert_eq!(Color::white(), d.background_color());
+
+        // 解釈できない値の場合、文字色は黒、背景色は白になる。
+        let e = style_of("e");
+        assert_eq!(Color::black(), e.color());
+        assert_eq!(Color::white(), e.background_color());
+    }
+
+    /// font-weight プロパティのテスト
+    /// キーワードと数値の太さが継承され、テキストの DisplayItem に太字かどうかが設定されることを確認する。
+    #[test]
+    fn test_font_weight() {
+        let html = "<html><head><style>#a { font-weight: bold; } #b { font-weight: 300; } #c { font-weight: 700; } #d { font-weight: heavy; }</style></head><body><p id=\"a\">a</p><strong id=\"b\">b</strong><p id=\"c\">c</p><b id=\"d\">d</b><p>e</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let bolds: Vec<(String, bool)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, bold, .. } => Some((text, bold)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("a".to_string(), true),
+                ("b".to_string(), false),
+                ("c".to_string(), true),
+                ("d".to_string(), true),
+                ("e".to_string(), false),
+            ],
+            bolds
+        );
+    }
+
+    /// width と height プロパティのテスト
+    /// ブロック要素の大きさに指定した値が使われ、横幅は親の横幅を超えないことを確認する。
+    #[test]
+    fn test_width_and_height() {
+        let html = "<html><head><style>#a { width: 100px; height: 50px; } #c { width: 5000px; } #d { width: 10em; height: -1px; } #e { width: 30px; width: auto; }</style></head><body><div id=\"a\"><p id=\"b\">b</p></div><div id=\"c\">c</div><div id=\"d\">d</div><div id=\"e\">e</div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let size_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            let object = layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist");
+            let size = object.borrow().size();
+            size
+        };
+        let body_width = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .size()
+            .width();
+
+        assert_eq!(LayoutSize::new(100, 50), size_of("a"));
+        // width が auto の子要素は、指定された親の横幅になる。
+        assert_eq!(LayoutSize::new(100, CHAR_HEIGHT_WITH_PADDING), size_of("b"));
+        // 親の横幅を超える場合、親の横幅に収める。
+        assert_eq!(
+            LayoutSize::new(body_width, CHAR_HEIGHT_WITH_PADDING),
+            size_of("c")
+        );
+        // 解釈できない値は無視する。
+        assert_eq!(
+            LayoutSize::new(body_width, CHAR_HEIGHT_WITH_PADDING),
+            size_of("d")
+        );
+        // 後の宣言の auto が優先される。
+        assert_eq!(
+            LayoutSize::new(body_width, CHAR_HEIGHT_WITH_PADDING),
+            size_of("e")
+        );
+    }
+
+    /// border の個別指定プロパティのテスト
+    /// 一括指定プロパティから展開された値がカスケードされ、指定されていない値に初期値が設定されることを確認する。
+    #[test]
+    fn test_border_properties() {
+        let html = "<html><head><style>#a { border: 2px solid red; border-top-style: dashed; } #b { border-width: thin thick; border-style: solid; color: blue; } #c { border-left: 4px none; } #d { border-style: solid; border-color: inherit; }</style></head><body><div id=\"a\"><p id=\"d\">d</p></div><p id=\"b\">b</p><p id=\"c\">c</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let style_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            let object = layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist");
+            let style = object.borrow().style();
+            style
+        };
+        let red = Color::from_name("red").unwrap();
+
+        let a = style_of("a");
+        assert_eq!(2.0, a.border_width(Side::Top));
+        assert_eq!(BorderStyle::Dashed, a.border_style(Side::Top));
+        assert_eq!(BorderStyle::Solid, a.border_style(Side::Left));
+        assert_eq!(red, a.border_color(Side::Bottom));
+
+        // 線の色を指定しない場合、文字色を使う。
+        let b = style_of("b");
+        assert_eq!(1.0, b.border_width(Side::Top));
+        assert_eq!(5.0, b.border_width(Side::Right));
+        assert_eq!(1.0, b.border_width(Side::Bottom));
+        assert_eq!(
+            Color::from_name("blue").unwrap(),
+            b.border_color(Side::Left)
+        );
+
+        // 線の種類が none の場合、線の幅は 0 になる。
+        let c = style_of("c");
+        assert_eq!(0.0, c.border_width(Side::Left));
+        assert_eq!(BorderStyle::None, c.border_style(Side::Top));
+        assert_eq!(0.0, c.border_width(Side::Top));
+
+        // 線の色は継承されないが、inherit で親の値を使う。線の幅は初期値の medium になる。
+        let d = style_of("d");
+        assert_eq!(red, d.border_color(Side::Right));
+        assert_eq!(Color::black(), d.color());
+        assert_eq!(3.0, d.border_width(Side::Right));
+    }
+
+    /// visibility プロパティのテスト
+    /// visibility: hidden の要素は描画されないが、大きさと位置は保たれることを確認する。
+    /// visibility は継承されるが、子要素で visible を指定すると描画される。
+    #[test]
+    fn test_visibility_hidden() {
+        let html = "<html><head><style>#a { visibility: hidden; } #b { visibility: visible; }</style></head><body><div id=\"a\"><p>hidden</p><p id=\"b\">shown</p></div><p id=\"c\">after</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let object_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist")
+        };
+
+        let texts: Vec<String> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["shown".to_string(), "after".to_string()], texts);
+
+        // 描画しない要素も、大きさを持ち後続の要素の位置に影響する。
+        assert_eq!(
+            CHAR_HEIGHT_WITH_PADDING * 2,
+            object_of("a").borrow().size().height()
+        );
+        assert_eq!(
+            object_of("a").borrow().point().y() + CHAR_HEIGHT_WITH_PADDING * 2,
+            object_of("c").borrow().point().y()
+        );
+    }
+
+    /// px、em、パーセンテージで指定した font-size のテスト
+    /// em とパーセンテージは親の文字の大きさを基準とし、テキストの大きさの計算に実際の大きさが使われることを確認する。
+    #[test]
+    fn test_font_size_values() {
+        let html = "<html><head><style>#a { font-size: 24px; } #b { font-size: 2em; } #c { font-size: 50%; } #d { font-size: x-large; } #e { font-size: 10pt; }</style></head><body><div id=\"a\">ab<p id=\"b\">b</p></div><h1 id=\"c\">c</h1><p id=\"d\">d</p><h2 id=\"e\">e</h2></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let object_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist")
+        };
+
+        let a = object_of("a");
+        assert_eq!(FontSize::Px(24.0), a.borrow().style().font_size());
+        // 24px の文字は Medium (16px) の 1.5倍の大きさで計算する。
+        let text = a.borrow().first_child().expect("text node should exist");
+        assert_eq!(
+            LayoutSize::new(CHAR_WIDTH * 3, CHAR_HEIGHT_WITH_PADDING * 3 / 2),
+            text.borrow().size()
+        );
+
+        assert_eq!(
+            FontSize::Px(48.0),
+            object_of("b").borrow().style().font_size()
+        );
+        // <h1> のデフォルトの大きさではなく、親の <body> の大きさ (16px) の半分になる。
+        assert_eq!(
+            FontSize::Px(8.0),
+            object_of("c").borrow().style().font_size()
+        );
+        assert_eq!(
+            FontSize::XLarge,
+            object_of("d").borrow().style().font_size()
+        );
+        // 対応していない単位は無視し、<h2> のデフォルトの大きさになる。
+        assert_eq!(
+            FontSize::XLarge,
+            object_of("e").borrow().style().font_size()
+        );
+    }
+
+    /// display: inline-block と list-item のテスト
+    /// インラインブロック要素は中身の横幅に合わせて同じ行に並び、リストの項目にはマーカーが描画されることを確認する。
+    #[test]
+    fn test_inline_block_and_list_item() {
+        let html = "<html><head><style>#b { display: inline-block; } #c { display: inline-block; width: 100px; height: 30px; }</style></head><body><div><span id=\"a\">ab</span><div id=\"b\">cde</div><span id=\"c\">f</span></div><ul><li id=\"d\">g</li><li>h</li></ul><ol><li>i</li><p>j</p><li>k</li></ol></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let object_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist")
+        };
+
+        let a = object_of("a");
+        let b = object_of("b");
+        assert_eq!(LayoutObjectKind::InlineBlock, b.borrow().kind());
+        assert_eq!(
+            LayoutSize::new(CHAR_WIDTH * 3, CHAR_HEIGHT_WITH_PADDING),
+            b.borrow().size()
+        );
+        assert_eq!(
+            LayoutPoint::new(CHAR_WIDTH * 2, a.borrow().point().y()),
+            b.borrow().point()
+        );
+        let c = object_of("c");
+        assert_eq!(LayoutSize::new(100, 30), c.borrow().size());
+        assert_eq!(
+            LayoutPoint::new(CHAR_WIDTH * 5, a.borrow().point().y()),
+            c.borrow().point()
+        );
+
+        // リストの項目の中身は、マーカーの横幅だけ右にずれる。
+        let d = object_of("d");
+        assert_eq!(DisplayType::ListItem, d.borrow().style().display());
+        assert_eq!(LayoutObjectKind::Block, d.borrow().kind());
+        let text = d.borrow().first_child().expect("text node should exist");
+        assert_eq!(LIST_MARKER_WIDTH, text.borrow().point().x());
+        assert_eq!(
+            CONTENT_AREA_WIDTH - LIST_MARKER_WIDTH,
+            d.borrow().content_size().width()
+        );
+
+        let texts: Vec<String> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec!["ab", "cde", "f", "*", "g", "*", "h", "1.", "i", "j", "2.", "k"],
+            texts
+        );
+    }
+
+    /// text-decoration プロパティのテスト
+    /// none で <a> のデフォルトの下線を取り消せること、line-through が継承されてテキストの DisplayItem に設定されることを確認する。
+    #[test]
+    fn test_text_decoration() {
+        let html = "<html><head><style>a { text-decoration: none; } #b { text-decoration: line-through; } #c { text-decoration: overline; }</style></head><body><p><a href=\"x\">a</a></p><p id=\"b\">b<a href=\"y\">c</a></p><u id=\"c\">d</u></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let decorations: Vec<(String, bool, bool)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text,
+                    underline,
+                    line_through,
+                    ..
+                } => Some((text, underline, line_through)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("a".to_string(), false, false),
+                ("b".to_string(), false, true),
+                // <a> に指定された none が優先される。
+                ("c".to_string(), false, false),
+                // 対応していない値は無視し、<u> のデフォルトの下線になる。
+                ("d".to_string(), true, false),
+            ],
+            decorations
+        );
+    }
+
+    /// margin と padding のテスト
+    /// ブロック要素の大きさにパディングと線の幅が含まれ、兄弟ノードや子ノードの位置がマージンとパディングの分だけずれることを確認する。
+    #[test]
+    fn test_box_model() {
+        let html = "<html><head><style>#a { margin: 10px 20px; padding: 5px; border: 2px solid black; } #c { margin-top: 8px; padding-left: 4px; } #d { margin: 0 6px; padding: 0 3px; }</style></head><body><div id=\"a\"><p id=\"b\">x</p></div><p id=\"c\">y</p><p id=\"e\"><span>a</span><span id=\"d\">b</span></p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let object_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist")
+        };
+
+        // 横幅は親の横幅から左右のマージンを除いたもの、高さは子ノードの高さにパディングと線の幅を加えたものになる。
+        let a = object_of("a");
+        assert_eq!(LayoutPoint::new(20, 10), a.borrow().point());
+        assert_eq!(
+            LayoutSize::new(CONTENT_AREA_WIDTH - 40, CHAR_HEIGHT_WITH_PADDING + 14),
+            a.borrow().size()
+        );
+
+        // 子ノードは、パディングと線の内側に配置される。
+        let b = object_of("b");
+        assert_eq!(LayoutPoint::new(27, 17), b.borrow().point());
+        assert_eq!(CONTENT_AREA_WIDTH - 54, b.borrow().size().width());
+
+        // 兄弟ノードは、前の兄弟ノードの下のマージンと自身の上のマージンの分だけ離れる。
+        let c = object_of("c");
+        let c_y = 10 + CHAR_HEIGHT_WITH_PADDING + 14 + 10 + 8;
+        assert_eq!(LayoutPoint::new(0, c_y), c.borrow().point());
+        let text = c.borrow().first_child().expect("text node should exist");
+        assert_eq!(LayoutPoint::new(4, c_y), text.borrow().point());
+
+        // インライン要素は、左右のマージンとパディングの分だけ横に並ぶ位置と横幅が変わる。
+        let d = object_of("d");
+        assert_eq!(CHAR_WIDTH + 6, d.borrow().point().x());
+        assert_eq!(CHAR_WIDTH + 6, d.borrow().size().width());
+        let text = d.borrow().first_child().expect("text node should exist");
+        assert_eq!(CHAR_WIDTH + 9, text.borrow().point().x());
+
+        let body = layout_view.root().expect("root should exist");
+        assert_eq!(
+            c_y + CHAR_HEIGHT_WITH_PADDING * 2,
+            body.borrow().size().height()
+        );
+    }
+
+    /// 行ボックスのテスト
+    /// 複数の要素にまたがるテキストが包含ブロックの横幅に収まるように単語の境界で折り返され、行ごとの断片がそれぞれの位置を持つことを確認する。
+    #[test]
+    fn test_line_box() {
+        let html = "<html><head><style>#a { width: 80px; } #c { width: 40px; }</style></head><body><p id=\"a\">aaa bbb <a href=\"x\">ccc ddd</a>, eee</p><p>ab <span>cd</span>ef</p><p id=\"c\">abcdefghij</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let fragments: Vec<(String, LayoutPoint)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text, layout_point)),
+                _ => None,
+            })
+            .collect();
+        let line = CHAR_HEIGHT_WITH_PADDING;
+        assert_eq!(
+            vec![
+                // 横幅が 10 文字分のため、<a> の中の単語は次の行に折り返す。
+                ("aaa bbb".to_string(), LayoutPoint::new(0, 0)),
+                ("ccc ddd".to_string(), LayoutPoint::new(0, line)),
+                // 1つのテキストも、行ごとの断片に分かれる。
+                (",".to_string(), LayoutPoint::new(CHAR_WIDTH * 7, line)),
+                ("eee".to_string(), LayoutPoint::new(0, line * 2)),
+                // 要素の間の空白は1文字分の間隔になり、空白のない要素は続けて並ぶ。
+                ("ab".to_string(), LayoutPoint::new(0, line * 3)),
+                ("cd".to_string(), LayoutPoint::new(CHAR_WIDTH * 3, line * 3)),
+                ("ef".to_string(), LayoutPoint::new(CHAR_WIDTH * 5, line * 3)),
+                // 1行に収まらない単語は、行の横幅で分割する。
+                ("abcde".to_string(), LayoutPoint::new(0, line * 4)),
+                ("fghij".to_string(), LayoutPoint::new(0, line * 5)),
+            ],
+            fragments
+        );
+
+        let body = layout_view.root().expect("root should exist");
+        assert_eq!(line * 6, body.borrow().size().height());
+    }
+
+    /// 日本語のテキストの折り返しのテスト
+    /// 空白のないテキストが文字の間で折り返され、句読点や括弧の前後では折り返さないことを確認する。
+    #[test]
+    fn test_cjk_line_break() {
+        let html = "<html><head><style>p { width: 80px; } #b { width: 40px; }</style></head><body><p>これは日本語の文章です。折り返しを確認します。</p><p id=\"b\">(日本)abc</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let fragments: Vec<(String, LayoutPoint)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text, layout_point)),
+                _ => None,
+            })
+            .collect();
+        let line = CHAR_HEIGHT_WITH_PADDING;
+        assert_eq!(
+            vec![
+                // 横幅が 10 文字分のため、10 文字ごとに文字の間で折り返す。
+                ("これは日本語の文章で".to_string(), LayoutPoint::new(0, 0)),
+                // 句点は行の先頭に置かないため、前の文字と一緒に折り返す。
+                (
+                    "す。折り返しを確認し".to_string(),
+                    LayoutPoint::new(0, line)
+                ),
+                ("ます。".to_string(), LayoutPoint::new(0, line * 2)),
+                // 開き括弧の後ろと、閉じ括弧の前では折り返さない。
+                ("(日".to_string(), LayoutPoint::new(0, line * 3)),
+                ("本)abc".to_string(), LayoutPoint::new(0, line * 4)),
+            ],
+            fragments
+        );
+
+        // テキストの横幅は、バイト数ではなく文字数で決まる。
+        let a = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .and_then(|p| p.borrow().first_child())
+            .expect("text node should exist");
+        assert_eq!(
+            LayoutSize::new(CHAR_WIDTH * 10, line * 3),
+            a.borrow().size()
+        );
+    }
+
+    /// find_node_by_position のテスト
+    /// 折り返したテキストは行ごとの断片で判定し、重なったノードは後に描画されたものを返すことを確認する。
+    #[test]
+    fn test_find_node_by_position() {
+        let html = "<html><head><style>#a { width: 80px; } #f { float: left; width: 40px; }</style></head><body><p id=\"a\">aaa bbb <a href=\"x\">ccc ddd eee</a></p><div><div id=\"f\">ff</div><div id=\"y\">yy</div></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        // テキストが見つかった場合は、テキストを持つ要素を返す。
+        let element_at = |position: (i64, i64)| {
+            let node = layout_view
+                .find_node_by_position(position)
+                .expect("node should exist")
+                .borrow()
+                .node();
+            let parent = node.borrow().parent().upgrade();
+            let is_text = matches!(node.borrow().kind(), NodeKind::Text(_));
+            if is_text {
+                parent.expect("parent should exist")
+            } else {
+                node
+            }
+        };
+        let element = |id: &str| {
+            get_element_by_id(Some(dom.clone()), &id.to_string()).expect("element should exist")
+        };
+        let line = CHAR_HEIGHT_WITH_PADDING;
+
+        // リンクのテキストは "ccc ddd" と "eee" の2行に折り返す。2行目をクリックしてもリンクが見つかる。
+        let link = element_at((CHAR_WIDTH, line * 2 + 5));
+        assert_eq!(Some(ElementKind::A), link.borrow().get_element_kind());
+        // 2行目の "eee" の後ろは、テキストを囲む領域の中でもテキストではなく、段落が見つかる。
+        assert!(Rc::ptr_eq(
+            &element("a"),
+            &element_at((CHAR_WIDTH * 5, line * 2 + 5))
+        ));
+
+        // ブロック要素はフロートと重なって配置される。
+        // 前の兄弟ノードであるフロートの子孫 ("ff") より、後に描画されたブロック要素が見つかる。
+        assert!(Rc::ptr_eq(&element("y"), &element_at((1, line * 3 + 5))));
+    }
+
+    /// float プロパティのテスト
+    /// フロートが包含ブロックの左端と右端に寄せて配置され、後に続く行がフロートを避けて短くなることを確認する。
+    /// 子のブロック要素の行も、親のブロックコンテナに配置されたフロートを避ける。
+    #[test]
+    fn test_float() {
+        let html = "<html><head><style>#a { width: 160px; } #l { float: left; width: 40px; height: 50px; } #r { float: right; width: 24px; height: 30px; } #m { float: left; width: 80px; height: 20px; }</style></head><body><div id=\"a\"><div id=\"l\"></div><span id=\"r\">x</span>aaaa bbbb cccc dddd eeee ffff gggg hhhh iiii</div><div><div id=\"m\"></div><p>jjjj kkkk</p></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let object_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist")
+        };
+
+        assert_eq!(Float::Left, object_of("l").borrow().style().float());
+        assert_eq!(LayoutPoint::new(0, 0), object_of("l").borrow().point());
+        // インライン要素もフロートになり、横幅と高さを持つ。
+        let r = object_of("r");
+        assert_eq!(LayoutObjectKind::InlineBlock, r.borrow().kind());
+        assert_eq!(LayoutPoint::new(160 - 24, 0), r.borrow().point());
+        assert_eq!(LayoutSize::new(24, 30), r.borrow().size());
+
+        let fragments: Vec<(String, LayoutPoint)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text, layout_point)),
+                _ => None,
+            })
+            .collect();
+        let line = CHAR_HEIGHT_WITH_PADDING;
+        assert_eq!(
+            vec![
+                ("x".to_string(), LayoutPoint::new(160 - 24, 0)),
+                // 左右のフロートの間の 12 文字分に収まるように折り返す。
+                ("aaaa bbbb".to_string(), LayoutPoint::new(40, 0)),
+                ("cccc dddd".to_string(), LayoutPoint::new(40, line)),
+                // 右のフロートより下の行は、右端まで使える。
+                ("eeee ffff gggg".to_string(), LayoutPoint::new(40, line * 2)),
+                // 左のフロートより下の行は、左端から始まる。
+                ("hhhh iiii".to_string(), LayoutPoint::new(0, line * 3)),
+                ("jjjj kkkk".to_string(), LayoutPoint::new(80, line * 4)),
+            ],
+            fragments
+        );
+        assert_eq!(line * 4, object_of("a").borrow().size().height());
+    }
+
+    /// display: flex のテスト
+    /// フレックスアイテムが主軸方向に1列に並び、justify-content に従って余白が配分され、交差軸方向に伸びることを確認する。
+    #[test]
+    fn test_flex() {
+        let html = "<html><head><style>#r { display: flex; justify-content: space-between; } #a { height: 40px; } #c { display: flex; flex-direction: column; justify-content: center; height: 100px; }</style></head><body><div id=\"r\"><span id=\"a\">ab</span><div id=\"b\">cde</div>text</div><div id=\"c\"><p id=\"d\">x</p><p id=\"e\">y</p></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &cssom);
+        let object_of = |id: &str| {
+            let node = get_element_by_id(Some(dom.clone()), &id.to_string())
+                .expect("element should exist");
+            layout_view
+                .find_layout_object(&node)
+                .expect("layout object should exist")
+        };
+
+        // 横に並べる場合、アイテムの横幅は中身に合わせ、余白はアイテムの間に均等に配分する。
+        let r = object_of("r");
+        assert_eq!(DisplayType::Flex, r.borrow().style().display());
+        assert_eq!(LayoutSize::new(CONTENT_AREA_WIDTH, 40), r.borrow().size());
+        let gap = (CONTENT_AREA_WIDTH - CHAR_WIDTH * 9) / 2;
+        let a = object_of("a");
+        assert_eq!(LayoutObjectKind::InlineBlock, a.borrow().kind());
+        assert_eq!(LayoutPoint::new(0, 0), a.borrow().point());
+        // 高さが指定されていないアイテムは、最も高いアイテムに合わせて伸びる。
+        let b = object_of("b");
+        assert_eq!(
+            LayoutPoint::new(CHAR_WIDTH * 2 + gap, 0),
+            b.borrow().point()
+        );
+        assert_eq!(LayoutSize::new(CHAR_WIDTH * 3, 40), b.borrow().size());
+        let text = r.borrow().children()[2].clone();
+        assert_eq!(
+            LayoutPoint::new(CHAR_WIDTH * 5 + gap * 2, 0),
+            text.borrow().point()
+        );
+
+        // 縦に並べる場合、アイテムはコンテナの横幅に伸び、余白は前後に均等に配分する。
+        let c = object_of("c");
+        assert_eq!(LayoutSize::new(CONTENT_AREA_WIDTH, 100), c.borrow().size());
+        let line = CHAR_HEIGHT_WITH_PADDING;
+        let d = object_of("d");
+        assert_eq!(LayoutObjectKind::Block, d.borrow().kind());
+        assert_eq!(LayoutPoint::new(0, 40 + 30), d.borrow().point());
+        assert_eq!(LayoutSize::new(CONTENT_AREA_WIDTH, line), d.borrow().size());
+        assert_eq!(
+            LayoutPoint::new(0, 40 + 30 + line),
+            object_of("e").borrow().point()
+        );
+    }
+
+    /// 整形済みテキストのテスト
+    /// <pre> の中のテキストは改行文字の位置で行に分かれ、行数分の高さを持つことを確認する。
+    #[test]
+    fn test_document_height() {
+        let html = "<html><head><style>#a { height: 1000px; }</style></head><body><div id=\"a\"></div><div>text</div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+        // 文書の高さは、描画可能なエリアの高さを超えても制限されない。
+        assert_eq!(
+            1000 + CHAR_HEIGHT_WITH_PADDING,
+            layout_view.document_height()
+        );
+
+        // スクロールした後に描画可能なエリアで切り取ると、はみ出した部分は取り除かれる。
+        // <body> と 1 つ目の <div> は下端だけが残る。
+        let viewport = LayoutSize::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT);
+        let items: Vec<DisplayItem> = layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| item.translate(0, -990).clip(viewport))
+            .collect();
+        let rects: Vec<(LayoutPoint, LayoutSize)> = items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect {
+                    layout_point,
+                    layout_size,
+                    ..
+                } => Some((*layout_point, *layout_size)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    LayoutPoint::new(0, 0),
+                    LayoutSize::new(CONTENT_AREA_WIDTH, 10 + CHAR_HEIGHT_WITH_PADDING)
+                ),
+                (
+                    LayoutPoint::new(0, 0),
+                    LayoutSize::new(CONTENT_AREA_WIDTH, 10)
+                ),
+                (
+                    LayoutPoint::new(0, 10),
+                    LayoutSize::new(CONTENT_AREA_WIDTH, CHAR_HEIGHT_WITH_PADDING)
+                ),
+            ],
+            rects
+        );
+        assert!(items.iter().any(|item| matches!(
+            item,
+            DisplayItem::Text { text, layout_point, .. }
+                if text == "text" && *layout_point == LayoutPoint::new(0, 10)
+        )));
+    }
+
+    #[test]
+    fn test_image() {
+        let html = "<html><head></head><body><div>ab<img id=\"a\" src=\"a.png\">cd</div><img id=\"b\" src=\"b.png\" width=\"30\"><img id=\"c\" src=\"c.png\" width=\"10\" height=\"12\"></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let node_of = |id: &str| {
+            get_element_by_id(Some(dom.clone()), &id.to_string()).expect("element should exist")
+        };
+        // c.png はまだ受信していない画像とする。
+        let images = vec![
+            (node_of("a"), LayoutSize::new(40, 30)),
+            (node_of("b"), LayoutSize::new(60, 40)),
+        ];
+        let layout_view = LayoutView::with_images(dom.clone(), &StyleSheet::new(), &images);
+        let object_of = |id: &str| {
+            layout_view
+                .find_layout_object(&node_of(id))
+                .expect("layout object should exist")
+        };
+
+        // 画像はテキストと同じ行に並び、内在的な大きさを持つ。
+        let a = object_of("a");
+        assert_eq!(LayoutObjectKind::Image, a.borrow().kind());
+        assert_eq!(LayoutSize::new(40, 30), a.borrow().size());
+        assert_eq!(CHAR_WIDTH * 2, a.borrow().point().x());
+        let div = a
+            .borrow()
+            .parent()
+            .upgrade()
+            .expect("div node should exist");
+        let cd = div.borrow().children()[2].clone();
+        assert_eq!(CHAR_WIDTH * 2 + 40, cd.borrow().point().x());
+
+        // width 属性だけが指定されている場合、縦横比を保つ。
+        let b = object_of("b");
+        assert_eq!(LayoutSize::new(30, 20), b.borrow().size());
+        // 受信していない画像は、width 属性と height 属性の大きさになる。
+        let c = object_of("c");
+        assert_eq!(LayoutSize::new(10, 12), c.borrow().size());
+        assert_eq!(b.borrow().point().y(), c.borrow().point().y());
+        assert_eq!(b.borrow().point().x() + 30, c.borrow().point().x());
+
+        let items = layout_view.paint();
+        assert!(items.contains(&DisplayItem::Image {
+            src: "a.png".to_string(),
+            layout_point: a.borrow().point(),
+            layout_size: LayoutSize::new(40, 30),
+        }));
+    }
+
+    #[test]
+    fn test_pre() {
+        let html = "<html><head></head><body><pre>a  b\nc\n\nd\n</pre></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let pre = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("pre node should exist");
+        assert_eq!(LayoutObjectKind::Block, pre.borrow().kind());
+
+        let text = pre.borrow().first_child().expect("text node should exist");
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING * 4, text.borrow().size().height());
+
+        let texts: Vec<String> = text
+            .borrow_mut()
+            .paint()
+            .into_iter()
+            .map(|item| match item {
+                DisplayItem::Text { text, .. } => text,
+                _ => panic!("unexpected display item"),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "a  b".to_string(),
+                "c".to_string(),
+                "".to_string(),
+                "d".to_string()
+            ],
+            texts
+        );
+    }
+
+    /// <iframe> のテスト
+    /// <iframe> は width 属性と height 属性の大きさを持ち、中身のテキストはレイアウトツリーに含まれないことを確認する。
+    #[test]
+    fn test_iframe() {
+        let html = "<html><head></head><body><iframe src=\"a.html\" width=\"200\">fallback</iframe><iframe></iframe></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let children = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .children();
+        assert_eq!(2, children.len());
+        let iframe = &children[0];
+        assert_eq!(LayoutObjectKind::Inline, iframe.borrow().kind());
+        assert!(iframe.borrow().first_child().is_none());
+        assert_eq!(LayoutSize::new(200, 150), iframe.borrow().size());
+
+        let next = children[1].clone();
+        assert_eq!(LayoutSize::new(300, 150), next.borrow().size());
+        assert_eq!(LayoutPoint::new(200, 0), next.borrow().point());
+
+        let node = next.borrow().node();
+        let found = layout_view
+            .find_layout_object(&node)
+            .expect("layout object should exist");
+        assert!(Rc::ptr_eq(&next, &found));
+    }
+
+    /// 兄弟ノードの多い文書でも、コールスタックを溢れさせずにレイアウトツリーを作成し、位置を計算できることを確認する。
+    #[test]
+    fn test_many_siblings() {
+        let count = 5000;
+        let mut html = "<html><head></head><body>".to_string();
+        for _ in 0..count {
+            html.push_str("<p>a</p>");
+        }
+        html.push_str("</body></html>");
+        let layout_view = create_layout_view(html);
+
+        let children = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .children();
+        assert_eq!(count, children.len());
+        let last = children.last().expect("last node should exist");
+        assert_eq!(
+            LayoutPoint::new(0, CHAR_HEIGHT_WITH_PADDING * (count - 1) as i64),
+            last.borrow().point()
+        );
+        let point = last.borrow().point();
+        let found = layout_view
+            .find_node_by_position((point.x() + 1, point.y() + 1))
+            .expect("node should exist");
+        assert!(Rc::ptr_eq(
+            &last.borrow().node(),
+            &found
+                .borrow()
+                .node()
+                .borrow()
+                .parent()
+                .upgrade()
+                .expect("parent should exist")
+        ));
     }
 }
@@ -0,0 +1,498 @@
+use crate::renderer::layout::computed_style::Float;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// 行ボックスに配置されたテキストの断片。
+/// テキストが複数の行にまたがる場合、行ごとに1つの断片となる。
+/// 位置は、断片を持つレイアウトオブジェクトからの相対位置である。
+/// https://www.w3.org/TR/css-inline-3/#line-boxes
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextFragment {
+    text: String,
+    point: LayoutPoint,
+    size: LayoutSize,
+}
+
+impl TextFragment {
+    pub fn new(text: String, point: LayoutPoint, size: LayoutSize) -> Self {
+        Self { text, point, size }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn size(&self) -> LayoutSize {
+        self.size
+    }
+
+    /// 断片の位置を dx、dy だけずらしたものを返す。
+    pub fn translate(&self, dx: i64, dy: i64) -> Self {
+        Self {
+            text: self.text.clone(),
+            point: LayoutPoint::new(self.point.x() + dx, self.point.y() + dy),
+            size: self.size,
+        }
+    }
+}
+
+/// 複数の矩形を囲む最小の矩形の位置と大きさを返す。矩形が1つもない場合は None を返す。
+pub fn bounding_box(
+    rects: impl Iterator<Item = (LayoutPoint, LayoutSize)>,
+) -> Option<(LayoutPoint, LayoutSize)> {
+    let mut result: Option<(i64, i64, i64, i64)> = None;
+    for (point, size) in rects {
+        let (left, top) = (point.x(), point.y());
+        let (right, bottom) = (left + size.width(), top + size.height());
+        result = Some(match result {
+            Some((l, t, r, b)) => (l.min(left), t.min(top), r.max(right), b.max(bottom)),
+            None => (left, top, right, bottom),
+        });
+    }
+    result.map(|(left, top, right, bottom)| {
+        (
+            LayoutPoint::new(left, top),
+            LayoutSize::new(right - left, bottom - top),
+        )
+    })
+}
+
+/// 包含ブロックの左端または右端に寄せて配置したフロートの、マージンを含めた領域。
+/// https://www.w3.org/TR/CSS2/visuren.html#floats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatBox {
+    float: Float,
+    point: LayoutPoint,
+    size: LayoutSize,
+}
+
+impl FloatBox {
+    pub fn new(float: Float, point: LayoutPoint, size: LayoutSize) -> Self {
+        Self { float, point, size }
+    }
+
+    pub fn float(&self) -> Float {
+        self.float
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn size(&self) -> LayoutSize {
+        self.size
+    }
+
+    fn top(&self) -> i64 {
+        self.point.y()
+    }
+
+    fn bottom(&self) -> i64 {
+        self.point.y() + self.size.height()
+    }
+
+    /// 上端 top から下端 bottom までの範囲と、縦方向に重なる場合に true を返す。
+    fn overlaps(&self, top: i64, bottom: i64) -> bool {
+        self.top() < bottom && top < self.bottom()
+    }
+}
+
+/// 行ボックスを組み立てる構造体。
+/// ブロックコンテナの中に続けて並ぶインライン要素、インラインブロック要素、テキストを、コンテナの横幅に収まるように行に分けて配置する。
+/// ブロック要素は行を終わらせ、1つで1行を占める。
+/// 行の中の要素は上端に揃えて配置し、行の高さは行の中で最も高い要素の高さとする。
+/// フロートは通常のフローから外して左端または右端に寄せて積み重ね、フロートの横の行はフロートを避けるように短くする。
+/// 位置は全てコンテナのコンテンツボックスの左上からの相対位置である。
+/// https://www.w3.org/TR/css-inline-3/#line-boxes
+#[derive(Debug, Clone)]
+pub struct LineBoxBuilder {
+    width: i64,
+    // 現在の行で、次の要素を配置する X 位置
+    x: i64,
+    // 現在の行の上端の Y 位置
+    line_top: i64,
+    // 現在の行の高さ
+    line_height: i64,
+    // 現在の行の、フロートを避けた左端と右端の X 位置
+    line_left: i64,
+    line_right: i64,
+    // これまでの行の最も長い横幅
+    widest: i64,
+    // 前のテキストの末尾にあり、まだ配置していない空白の横幅
+    pending_space: Option<i64>,
+    // 配置したフロートと、親のブロックコンテナから引き継いだフロート
+    floats: Vec<FloatBox>,
+    // floats のうち、親のブロックコンテナから引き継いだフロートの数
+    inherited_floats: usize,
+}
+
+impl LineBoxBuilder {
+    pub fn new(width: i64) -> Self {
+        Self::with_floats(width, Vec::new())
+    }
+
+    /// 親のブロックコンテナに配置されたフロートを避けて行を配置する LineBoxBuilder を作成する。
+    /// フロートの位置は、このコンテナのコンテンツボックスの左上からの相対位置で渡す。
+    pub fn with_floats(width: i64, floats: Vec<FloatBox>) -> Self {
+        let mut builder = Self {
+            width,
+            x: 0,
+            line_top: 0,
+            line_height: 0,
+            line_left: 0,
+            line_right: width,
+            widest: 0,
+            pending_space: None,
+            inherited_floats: floats.len(),
+            floats,
+        };
+        builder.update_line_edges();
+        builder
+    }
+
+    /// 次の要素を配置する位置を返す。
+    pub fn cursor(&self) -> LayoutPoint {
+        LayoutPoint::new(self.x, self.line_top)
+    }
+
+    /// 現在の行に何も配置されていない場合に true を返す。
+    pub fn at_line_start(&self) -> bool {
+        self.x == self.line_left
+    }
+
+    /// 全ての行を配置し終えた後の、行の高さの合計と最も長い行の横幅を返す。
+    /// 高さと横幅には、このコンテナに配置したフロートも含める。
+    pub fn finish(mut self) -> (i64, i64) {
+        self.break_line();
+        let mut height = self.line_top;
+        let mut widest = self.widest;
+        for float in &self.floats[self.inherited_floats..] {
+            height = height.max(float.bottom());
+            widest = widest.max(float.point().x() + float.size().width());
+        }
+        (height, widest)
+    }
+
+    /// 現在の行を終わらせ、次の行に移る。
+    /// 何も配置されていない行は高さを持たないため、続けて呼び出しても空の行は増えない。
+    pub fn break_line(&mut self) {
+        self.widest = self.widest.max(self.x);
+        self.line_top += self.line_height;
+        self.line_height = 0;
+        self.pending_space = None;
+        self.update_line_edges();
+    }
+
+    /// 上端 top から下端 bottom までの範囲で、フロートを避けた左端と右端の X 位置を返す。
+    fn edges(&self, top: i64, bottom: i64) -> (i64, i64) {
+        let mut left = 0;
+        let mut right = self.width;
+        for float in self.floats.iter().filter(|f| f.overlaps(top, bottom)) {
+            match float.float() {
+                Float::Left => left = left.max(float.point().x() + float.size().width()),
+                Float::Right => right = right.min(float.point().x()),
+                Float::None => {}
+            }
+        }
+        (left, right)
+    }
+
+    /// 何も配置されていない現在の行の左端と右端を、フロートを避けた位置にする。
+    fn update_line_edges(&mut self) {
+        let (left, right) = self.edges(self.line_top, self.line_top + 1);
+        self.line_left = left;
+        self.line_right = right;
+        self.x = left;
+    }
+
+    /// 現在の行と重なるフロートのうち、最も上にある下端まで行を下げる。
+    /// 行と重なるフロートがない場合は false を返す。
+    fn move_below_float(&mut self) -> bool {
+        let bottom = self
+            .floats
+            .iter()
+            .filter(|f| f.overlaps(self.line_top, self.line_top + 1))
+            .map(|f| f.bottom())
+            .min();
+        match bottom {
+            Some(bottom) => {
+                self.line_top = bottom;
+                self.update_line_edges();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 位置 origin より下に残っているフロートを、origin からの相対位置にして返す。
+    /// 子のブロック要素の行がフロートを避けるように、子のブロック要素の LineBoxBuilder に渡すために使う。
+    pub fn floats_from(&self, origin: LayoutPoint) -> Vec<FloatBox> {
+        self.floats
+            .iter()
+            .filter(|f| f.bottom() > origin.y())
+            .map(|f| {
+                FloatBox::new(
+                    f.float(),
+                    LayoutPoint::new(f.point().x() - origin.x(), f.point().y() - origin.y()),
+                    f.size(),
+                )
+            })
+            .collect()
+    }
+
+    /// フロートを配置し、配置した位置を返す。
+    /// フロートは現在の行の上端、現在の行に要素がある場合は次の行の上端から配置し、それより前のフロートより上には配置しない。
+    /// 左右のフロートの間に収まらない場合は、収まるまでフロートの下端に合わせて下げる。
+    /// https://www.w3.org/TR/CSS2/visuren.html#float-rules
+    pub fn place_float(&mut self, float: Float, width: i64, height: i64) -> LayoutPoint {
+        let mut top = if self.at_line_start() {
+            self.line_top
+        } else {
+            self.line_top + self.line_height
+        };
+        if let Some(last) = self.floats[self.inherited_floats..].last() {
+            top = top.max(last.top());
+        }
+
+        loop {
+            let bottom = top + height.max(1);
+            let (left, right) = self.edges(top, bottom);
+            let lower = self
+                .floats
+                .iter()
+                .filter(|f| f.overlaps(top, bottom))
+                .map(|f| f.bottom())
+                .min();
+            match lower {
+                Some(lower) if right - left < width => top = lower,
+                _ => {
+                    let x = match float {
+                        Float::Right => right - width,
+                        _ => left,
+                    };
+                    let point = LayoutPoint::new(x, top);
+                    self.floats
+                        .push(FloatBox::new(float, point, LayoutSize::new(width, height)));
+                    // 現在の行に何も配置されていない場合、行をフロートの横に配置する。
+                    if self.at_line_start() && self.line_height == 0 {
+                        self.update_line_edges();
+                    }
+                    return point;
+                }
+            }
+        }
+    }
+
+    /// インライン要素のマージン、パディング、線の幅の分だけ、次の要素を配置する位置を右にずらす。
+    pub fn advance(&mut self, width: i64) {
+        self.x += width;
+    }
+
+    /// ブロック要素を新しい行に配置し、配置した位置を返す。ブロック要素の後の要素は次の行に配置する。
+    /// ブロック要素の横幅はフロートに影響されず、包含ブロックの左端から配置する。
+    pub fn place_block(&mut self, height: i64) -> LayoutPoint {
+        self.break_line();
+        let point = LayoutPoint::new(0, self.line_top);
+        self.line_top += height;
+        self.update_line_edges();
+        point
+    }
+
+    /// インラインブロック要素や置換要素のように、途中で折り返さない箱を配置し、配置した位置を返す。
+    /// 現在の行の残りの横幅に収まらない場合、次の行に配置する。
+    pub fn place_box(&mut self, width: i64, height: i64) -> LayoutPoint {
+        self.place_pending_space(width);
+        let point = self.cursor();
+        self.x += width;
+        self.line_height = self.line_height.max(height);
+        point
+    }
+
+    /// 前のテキストの末尾の空白を、横幅 width の内容の前に配置する。
+    /// 空白と内容が現在の行に収まらない場合は、空白を捨てて次の行に移る。
+    /// フロートによって短くなった行に行の先頭でも収まらない場合は、フロートの下まで行を下げる。
+    /// 空白を配置した場合は true を返す。
+    fn place_pending_space(&mut self, width: i64) -> bool {
+        let space = match self.pending_space.take() {
+            Some(space) if !self.at_line_start() => space,
+            _ => 0,
+        };
+        if !self.at_line_start() && self.x + space + width > self.line_right {
+            self.break_line();
+        }
+        if self.at_line_start() {
+            while self.line_height == 0
+                && self.x + width > self.line_right
+                && self.move_below_float()
+            {}
+            return false;
+        }
+        self.x += space;
+        space > 0
+    }
+
+    /// white-space: normal のテキストを、単語の境界で折り返しながら配置し、行ごとの断片を返す。
+    /// 連続する空白文字と改行文字は1つの空白として扱い、行の先頭と末尾の空白は配置しない。
+    /// 日本語などの空白で区切らないテキストは、文字の間でも折り返す。
+    /// 1行に収まらない長さの単語は、行の横幅に収まる文字数で分割する。
+    /// https://www.w3.org/TR/css-text-3/#white-space-phase-2
+    pub fn place_text(
+        &mut self,
+        text: &str,
+        char_width: i64,
+        line_height: i64,
+    ) -> Vec<TextFragment> {
+        let mut fragments = Vec::new();
+        let mut current: Option<(String, LayoutPoint)> = None;
+
+        if text.starts_with(char::is_whitespace) && self.pending_space.is_none() {
+            self.pending_space = Some(char_width);
+        }
+        for word in text.split_whitespace() {
+            // 単語の中に折り返せる位置がある場合、その位置で区切った部分ごとに配置する。
+            // 2つ目以降の部分の前には空白を置かないため、前の部分と同じ行に収まらない場合だけ改行する。
+            for segment in break_segments(word) {
+                let mut rest = segment;
+                while !rest.is_empty() {
+                    // 行の横幅に収まる文字数を超える部分は、次の行に送る。
+                    let (part, remaining) = rest.split_at(Self::split_index(
+                        rest,
+                        (self.line_right - self.line_left) / char_width,
+                    ));
+                    let width = part.chars().count() as i64 * char_width;
+
+                    let spaced = self.place_pending_space(width);
+                    // 行の先頭に配置する場合、前の行の断片を終わらせる。
+                    if self.at_line_start() {
+                        Self::push_fragment(
+                            &mut fragments,
+                            current.take(),
+                            char_width,
+                            line_height,
+                        );
+                    }
+                    match current {
+                        Some((ref mut s, _)) => {
+                            if spaced {
+                                s.push(' ');
+                            }
+                            s.push_str(part);
+                        }
+                        None => current = Some((part.to_string(), self.cursor())),
+                    }
+                    self.x += width;
+                    self.line_height = self.line_height.max(line_height);
+                    rest = remaining;
+                }
+            }
+            self.pending_space = Some(char_width);
+        }
+        if !text.ends_with(char::is_whitespace) {
+            self.pending_space = None;
+        }
+        Self::push_fragment(&mut fragments, current, char_width, line_height);
+        fragments
+    }
+
+    /// 単語を分割する位置として、先頭から max_len 文字目の後ろの境界をバイト単位で返す。
+    /// ただし、行の横幅が1文字より狭い場合でも少なくとも1文字は配置する。
+    fn split_index(word: &str, max_len: i64) -> usize {
+        let count = (max_len.max(1) as usize).min(word.chars().count());
+        word.char_indices()
+            .nth(count)
+            .map_or(word.len(), |(index, _)| index)
+    }
+
+    /// white-space: pre のテキストを配置し、行ごとの断片を返す。
+    /// 改行文字の位置で行を終わらせ、空白文字はそのまま残し、折り返しは行わない。
+    pub fn place_preformatted_text(
+        &mut self,
+        lines: Vec<String>,
+        char_width: i64,
+        line_height: i64,
+    ) -> Vec<TextFragment> {
+        let mut fragments = Vec::new();
+        self.pending_space = None;
+        for (i, line) in lines.into_iter().enumerate() {
+            if i > 0 {
+                self.break_line();
+            }
+            let width = line.chars().count() as i64 * char_width;
+            fragments.push(TextFragment::new(
+                line,
+                self.cursor(),
+                LayoutSize::new(width, line_height),
+            ));
+            self.x += width;
+            self.line_height = self.line_height.max(line_height);
+        }
+        fragments
+    }
+
+    /// 1行分のテキストを断片として追加する。
+    fn push_fragment(
+        fragments: &mut Vec<TextFragment>,
+        fragment: Option<(String, LayoutPoint)>,
+        char_width: i64,
+        line_height: i64,
+    ) {
+        if let Some((text, point)) = fragment {
+            let size = LayoutSize::new(text.chars().count() as i64 * char_width, line_height);
+            fragments.push(TextFragment::new(text, point, size));
+        }
+    }
+}
+
+/// 空白を含まない単語を、文字の間の折り返せる位置で区切る。
+/// 漢字や仮名などの CJK の文字は、前後の文字との間で折り返せる。
+/// ただし、句読点や閉じ括弧の前 (行頭禁則) と、開き括弧の後ろ (行末禁則) では折り返さない。
+/// https://www.w3.org/TR/css-text-3/#line-break-details
+fn break_segments(word: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    for (index, c) in word.char_indices() {
+        if let Some(p) = previous {
+            if (is_cjk(p) || is_cjk(c))
+                && !NO_BREAK_BEFORE.contains(c)
+                && !NO_BREAK_AFTER.contains(p)
+            {
+                segments.push(&word[start..index]);
+                start = index;
+            }
+        }
+        previous = Some(c);
+    }
+    segments.push(&word[start..]);
+    segments
+}
+
+/// 文字の間で折り返せる CJK の文字の場合に true を返す。
+/// https://www.unicode.org/reports/tr14/#ID
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}' // ハングル字母
+        | '\u{2E80}'..='\u{2FDF}' // CJK 部首
+        | '\u{3000}'..='\u{303F}' // CJK の記号と句読点
+        | '\u{3040}'..='\u{30FF}' // 平仮名、片仮名
+        | '\u{3400}'..='\u{4DBF}' // CJK 統合漢字拡張 A
+        | '\u{4E00}'..='\u{9FFF}' // CJK 統合漢字
+        | '\u{AC00}'..='\u{D7AF}' // ハングル音節
+        | '\u{F900}'..='\u{FAFF}' // CJK 互換漢字
+        | '\u{FF00}'..='\u{FFEF}' // 半角・全角形
+    )
+}
+
+/// 行の先頭に置かない文字 (句読点、閉じ括弧、小書きの仮名など)
+const NO_BREAK_BEFORE: &str =
+    "、。，．・：；？！ー々ゝゞヽヾ）］｝」』】〕〉》〙〗ぁぃぅぇぉっゃゅょゎァィゥェォッャュョヮヵヶ)]},.:;?!";
+
+/// 行の末尾に置かない文字 (開き括弧)
+const NO_BREAK_AFTER: &str = "（［｛「『【〔〈《〘〖([{";
@@ -1,3 +1,4 @@
 pub mod computed_style;
 pub mod layout_object;
 pub mod layout_view;
+pub mod line_box;
@@ -1,28 +1,48 @@
 // use crate::alloc::string::ToString;
 use crate::browser::Browser;
+use crate::constants::CONTENT_AREA_HEIGHT;
+use crate::constants::CONTENT_AREA_WIDTH;
 use crate::display_item::DisplayItem;
+use crate::encoding::Encoding;
 use crate::http::HttpResponse;
+use crate::image::intrinsic_size;
 use crate::renderer::css::cssom::CssParser;
 use crate::renderer::css::cssom::StyleSheet;
 use crate::renderer::css::token::CssTokenizer;
-use crate::renderer::dom::api::get_style_content;
+use crate::renderer::dom::api::get_element_nodes_by_kind;
+use crate::renderer::dom::api::get_style_contents;
+use crate::renderer::dom::api::get_title_content;
+use crate::renderer::dom::event::Event;
+use crate::renderer::dom::mutation::MutationObserver;
 use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
 use crate::renderer::dom::node::Window;
+use crate::renderer::html::parse_error::ParseDiagnostic;
+use crate::renderer::html::parse_error::ParseError;
 use crate::renderer::html::parser::HtmlParser;
 use crate::renderer::html::token::HtmlTokenizer;
+use crate::renderer::layout::layout_object::LayoutSize;
 use crate::renderer::layout::layout_view::LayoutView;
 // use crate::utils::convert_dom_to_string;
 use crate::renderer::dom::api::get_js_content;
 use crate::renderer::js::ast::JsParser;
 use crate::renderer::js::runtime::JsRuntime;
+use crate::renderer::js::time::NullTimeSource;
+use crate::renderer::js::time::TimeSource;
 use crate::renderer::js::token::JsLexer;
+use crate::scheduler::FetchScheduler;
+use crate::url::Url;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+/// <iframe> 要素と、その中に表示するページの組
+type ChildPage = (Rc<RefCell<Node>>, Rc<RefCell<Page>>);
+
 /// Page 構造体
 /// Browser 構造体へのウィークポインタと DOM ツリーを保持する Window 構造体、および、描画に関する情報を保持する DisplayItem 構造体を持つ。
 #[derive(Debug, Clone)]
@@ -32,6 +52,27 @@ pub struct Page {
     style: Option<StyleSheet>,
     layout_view: Option<LayoutView>,
     display_items: Vec<DisplayItem>,
+    runtime: Option<JsRuntime>,
+    time_source: Rc<dyn TimeSource>,
+    scroll_position: (i64, i64),
+    // 受信途中のページの HTML パーサ。分割して届いたボディを受け取るたびに DOM ツリーの構築を進める。
+    html_parser: Option<HtmlParser>,
+    // 受信したサブリソース (CSS、スクリプト、画像など) の URL とレスポンス
+    subresources: Vec<(Url, HttpResponse)>,
+    // ページの HTML を解析したときに検出したパースエラー
+    parse_errors: Vec<ParseError>,
+    // ページの HTML を解析したときに、ツリー構築の回復処理を行った記録
+    parse_diagnostics: Vec<ParseDiagnostic>,
+    // <title> タグから取得したページのタイトル
+    title: String,
+    // ページの URL
+    url: Option<Url>,
+    // 相対 URL を解決するときの基準となる URL。<base href> があればその URL、なければページの URL となる。
+    base_url: Option<Url>,
+    // <iframe> 要素とその中に表示するページ
+    child_pages: Vec<ChildPage>,
+    // レイアウトツリーを作成した後の DOM ツリーの変更を記録する。記録がある場合、レイアウトツリーは古くなっている。
+    mutation_observer: Rc<RefCell<MutationObserver>>,
 }
 
 impl Page {
@@ -42,25 +83,289 @@ impl Page {
             style: None,
             layout_view: None,
             display_items: Vec::new(),
+            runtime: None,
+            time_source: Rc::new(NullTimeSource),
+            scroll_position: (0, 0),
+            html_parser: None,
+            subresources: Vec::new(),
+            parse_errors: Vec::new(),
+            parse_diagnostics: Vec::new(),
+            title: String::new(),
+            url: None,
+            base_url: None,
+            child_pages: Vec::new(),
+            mutation_observer: Rc::new(RefCell::new(MutationObserver::new())),
         }
     }
 
+    /// JavaScript の Date.now や setTimeout が参照する時刻の取得元を設定する。
+    pub fn set_time_source(&mut self, time_source: Rc<dyn TimeSource>) {
+        self.time_source = time_source;
+    }
+
     pub fn set_browser(&mut self, browser: Weak<RefCell<Browser>>) {
         self.browser = browser;
     }
 
+    /// ブラウザの設定で JavaScript の実行が有効になっているかどうかを返す。
+    /// ブラウザに属していないページでは有効として扱う。
+    fn scripting_enabled(&self) -> bool {
+        match self.browser.upgrade() {
+            Some(browser) => browser.borrow().scripting_enabled(),
+            None => true,
+        }
+    }
+
     /// HttpResponse を受け取り、DOM ツリーを文字列として返す。
+    /// Content-Type ヘッダで文字エンコーディングが宣言されていない場合、<meta> タグで宣言されたエンコーディングでボディをデコードし直す。
     pub fn receive_response(&mut self, response: HttpResponse) {
-        self.create_frame(response.body());
+        let body = match response.declared_encoding() {
+            Some(_) => response.body(),
+            None => {
+                // <meta> タグは ASCII で書かれるため、UTF-8 としてデコードしたボディから宣言を探せる。
+                let mut prescan = HtmlParser::new(HtmlTokenizer::new(response.body()));
+                prescan.construct_tree();
+                match prescan.declared_encoding() {
+                    Some(encoding) => response.decode_body(encoding),
+                    None => response.body(),
+                }
+            }
+        };
+
+        self.begin_response();
+        self.receive_chunk(&body);
+        self.finish_response();
+    }
+
+    /// 新しいページのボディの受信を開始する。
+    pub fn begin_response(&mut self) {
+        let mut html_parser = HtmlParser::new(HtmlTokenizer::new_streaming());
+        html_parser.set_scripting(self.scripting_enabled());
+        self.html_parser = Some(html_parser);
+        self.subresources = Vec::new();
+        self.child_pages = Vec::new();
+    }
+
+    /// ページの表示に必要なサブリソースのうち、まだ受信していないもののリクエストをスケジューラに登録する。
+    /// 現在は <iframe> の src 属性で指定された文書と、<img> の src 属性で指定された画像のみを取得する。
+    pub fn enqueue_subresources(&self, scheduler: &mut FetchScheduler) {
+        for (_, url) in self.iframes().into_iter().chain(self.images()) {
+            if self.subresource(&url).is_none() {
+                scheduler.enqueue(url);
+            }
+        }
+    }
+
+    /// FetchScheduler で取得したサブリソースのレスポンスを受け取る。
+    /// <iframe> の文書を受け取った場合、その中のページを作成して描画し直す。
+    /// <img> の画像を受け取った場合、画像の大きさに合わせてレイアウトと描画をやり直す。
+    /// TODO: 外部の CSS やスクリプトに対応した後、受け取ったサブリソースをページに反映する。
+    pub fn receive_subresource(&mut self, url: Url, response: HttpResponse) {
+        let is_image = self.images().iter().any(|(_, u)| u.href() == url.href());
+        self.subresources.push((url, response));
+        if is_image {
+            self.set_layout_view();
+            self.paint_tree();
+        } else if self.load_child_pages() {
+            self.paint_tree();
+        }
+    }
+
+    /// スケジューラで完了したサブリソースのリクエストの結果をすべて受け取る。
+    /// 取得に失敗したサブリソースは、ページの表示を妨げないように無視する。
+    pub fn receive_completed_subresources(&mut self, scheduler: &mut FetchScheduler) {
+        for (_, url, result) in scheduler.take_completed() {
+            if let Ok(response) = result {
+                self.receive_subresource(url, response);
+            }
+        }
+    }
+
+    /// url のサブリソースを受信している場合、そのレスポンスを返す。
+    pub fn subresource(&self, url: &Url) -> Option<HttpResponse> {
+        self.subresources
+            .iter()
+            .find(|(u, _)| u.href() == url.href())
+            .map(|(_, response)| response.clone())
+    }
+
+    /// DOM ツリー内の <iframe> 要素と、src 属性をベース URL を基準に解決した URL を返す。
+    fn iframes(&self) -> Vec<(Rc<RefCell<Node>>, Url)> {
+        self.elements_with_src(ElementKind::Iframe)
+    }
+
+    /// DOM ツリー内の <img> 要素と、src 属性をベース URL を基準に解決した URL を返す。
+    fn images(&self) -> Vec<(Rc<RefCell<Node>>, Url)> {
+        self.elements_with_src(ElementKind::Img)
+    }
+
+    /// DOM ツリー内の kind の要素と、src 属性をベース URL を基準に解決した URL を返す。
+    /// src 属性がない、または URL として解釈できない要素は含まない。
+    fn elements_with_src(&self, kind: ElementKind) -> Vec<(Rc<RefCell<Node>>, Url)> {
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return Vec::new(),
+        };
+
+        let mut elements = Vec::new();
+        for node in get_element_nodes_by_kind(Some(dom), kind) {
+            let src = match node.borrow().get_element() {
+                Some(e) => e.get_attribute("src"),
+                None => None,
+            };
+            let url = match (src, &self.base_url) {
+                (Some(src), Some(base)) => Some(base.join(&src)),
+                (Some(src), None) => Url::new(src).parse().ok(),
+                (None, _) => None,
+            };
+            if let Some(url) = url {
+                elements.push((node, url));
+            }
+        }
+        elements
+    }
+
+    /// 画像を受信済みの <img> 要素と、画像から読み取った内在的な大きさを返す。
+    /// 対応していない形式の画像は含まない。
+    fn image_sizes(&self) -> Vec<(Rc<RefCell<Node>>, LayoutSize)> {
+        self.images()
+            .into_iter()
+            .filter_map(|(node, url)| {
+                let response = self.subresource(&url)?;
+                let size = intrinsic_size(&response.raw_body())?;
+                Some((node, size))
+            })
+            .collect()
+    }
+
+    /// 文書を受信済みの <iframe> について、まだ作成していなければ中に表示するページを作成する。
+    /// 中のページは、親のページとは別の Window と DOM ツリーを持つ。
+    /// 戻り値は新しくページを作成したかどうかを表す。
+    /// https://html.spec.whatwg.org/multipage/iframe-embed-object.html#process-the-iframe-attributes
+    fn load_child_pages(&mut self) -> bool {
+        let mut loaded = false;
+        for (node, url) in self.iframes() {
+            if self
+                .child_pages
+                .iter()
+                .any(|(iframe, _)| Rc::ptr_eq(iframe, &node))
+            {
+                continue;
+            }
+            let response = match self.subresource(&url) {
+                Some(response) => response,
+                None => continue,
+            };
+
+            let mut child = Page::new();
+            child.set_browser(self.browser.clone());
+            child.set_time_source(self.time_source.clone());
+            child.set_url(url);
+            child.receive_response(response);
+            self.child_pages.push((node, Rc::new(RefCell::new(child))));
+            loaded = true;
+        }
+        loaded
+    }
+
+    /// <iframe> 要素の中に表示しているページを返す。
+    pub fn child_page(&self, iframe: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Page>>> {
+        self.child_pages
+            .iter()
+            .find(|(node, _)| Rc::ptr_eq(node, iframe))
+            .map(|(_, page)| page.clone())
+    }
+
+    /// ページの HTML を解析したときに検出したパースエラーを返す。
+    pub fn parse_errors(&self) -> Vec<ParseError> {
+        self.parse_errors.clone()
+    }
+
+    /// ページの HTML を解析したときに、省略された要素を補ったり終了タグを無視したりした記録を返す。
+    pub fn parse_diagnostics(&self) -> Vec<ParseDiagnostic> {
+        self.parse_diagnostics.clone()
+    }
+
+    /// ページの URL を設定する。receive_response を呼び出す前に設定する。
+    pub fn set_url(&mut self, url: Url) {
+        self.base_url = Some(url.clone());
+        self.url = Some(url);
+    }
+
+    /// 文書のベース URL を返す。
+    /// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    pub fn base_url(&self) -> Option<Url> {
+        self.base_url.clone()
+    }
+
+    /// リンクやフォームの送信先、サブリソースの相対 URL をベース URL を基準に解決する。
+    /// ベース URL が分からない場合は、そのまま返す。
+    pub fn resolve_url(&self, href: &str) -> String {
+        match &self.base_url {
+            Some(base) => base.join(href).href(),
+            None => href.to_string(),
+        }
+    }
+
+    /// ページのタイトルを返す。<title> タグがない場合は空文字を返す。
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    /// 分割して届いたページのボディの一部を受け取り、届いた分まで DOM ツリーを構築する。
+    /// TODO: 構築途中の DOM ツリーも描画できるようにする。現在は finish_response まで描画しない。
+    pub fn receive_chunk(&mut self, chunk: &str) {
+        if let Some(html_parser) = &mut self.html_parser {
+            html_parser.feed(chunk);
+            html_parser.construct_tree();
+        }
+    }
+
+    /// receive_chunk でボディをすべて渡し終えたレスポンスを受け取り、ページを描画する。
+    /// 渡したボディは Content-Type ヘッダの charset (宣言がない場合は UTF-8) でデコードされている。
+    /// <meta> タグで UTF-8 以外のエンコーディングが宣言されていた場合は、レスポンス全体をデコードし直して構築し直す。
+    /// https://html.spec.whatwg.org/multipage/parsing.html#changing-the-encoding-while-parsing
+    pub fn finish_streaming_response(&mut self, response: HttpResponse) {
+        let meta_encoding = match response.declared_encoding() {
+            Some(_) => None,
+            None => self
+                .html_parser
+                .as_ref()
+                .and_then(|html_parser| html_parser.declared_encoding()),
+        };
+        match meta_encoding {
+            Some(encoding) if encoding != Encoding::Utf8 => self.receive_response(response),
+            _ => self.finish_response(),
+        }
+    }
+
+    /// ボディをすべて受信した後に、DOM ツリーを構築してページを描画する。
+    pub fn finish_response(&mut self) {
+        let html_parser = self
+            .html_parser
+            .take()
+            .unwrap_or_else(|| HtmlParser::new(HtmlTokenizer::new(String::new())));
+        self.create_frame(html_parser);
+        // 既に受信しているサブリソースがあれば、<iframe> の中のページを作成する。
+        self.load_child_pages();
+
+        // 新しいページは先頭から表示する。
+        self.scroll_position = (0, 0);
 
         // HTML を受信した時に Javascript を実行する。
         self.execute_js();
 
         self.set_layout_view();
         self.paint_tree();
+        // スクロール位置は文書の高さで制限するため、レイアウトの後に反映する。
+        self.apply_scroll_request();
     }
 
     fn execute_js(&mut self) {
+        if !self.scripting_enabled() {
+            return;
+        }
+
         let dom = match &self.frame {
             Some(frame) => frame.borrow().document(),
             None => return,
@@ -70,10 +375,104 @@ impl Page {
         let lexer = JsLexer::new(js);
 
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
-
         let mut runtime = JsRuntime::new(dom);
-        runtime.execute(&ast);
+        runtime.set_time_source(self.time_source.clone());
+        // 構文エラーのスクリプトは実行しない。ページの読み込みやイベントハンドラの実行は続ける。
+        // https://html.spec.whatwg.org/multipage/webappapis.html#creating-a-classic-script
+        if let Ok(ast) = parser.parse_ast() {
+            runtime.execute(&ast);
+        }
+
+        // setTimeout で登録されたタイマーを後から実行できるように、ランタイムを保持する。
+        self.runtime = Some(runtime);
+    }
+
+    /// 実行時刻に達した JavaScript のタイマーを実行する。
+    /// タイマーのコールバックが DOM ツリーを変更した場合は、レイアウトと描画をやり直す。
+    /// 戻り値は再描画が必要かどうか (ページの表示が変わった、またはスクロール位置が変わったかどうか) を表す。
+    pub fn run_timers(&mut self) -> bool {
+        if let Some(runtime) = &mut self.runtime {
+            runtime.run_expired_timers();
+        }
+        // <iframe> の中のページのタイマーも実行する。
+        // 中のページを描画し直した場合、その DisplayItem を親のページに反映する必要がある。
+        let mut updated = false;
+        for (_, child) in &self.child_pages {
+            if child.borrow_mut().run_timers() {
+                updated = true;
+            }
+        }
+
+        if self.is_layout_stale() {
+            self.set_layout_view();
+            updated = true;
+        }
+        if updated {
+            self.paint_tree();
+        }
+        let scrolled = self.apply_scroll_request();
+        updated || scrolled
+    }
+
+    /// ページのスクロール位置 (x, y) を返す。
+    /// コンテンツ領域の左上に表示するページ上の座標を表す。
+    pub fn scroll_position(&self) -> (i64, i64) {
+        self.scroll_position
+    }
+
+    /// ページのスクロール位置を設定する。
+    /// JavaScript の window.scrollY などが新しい位置を返すように、ランタイムにも反映する。
+    pub fn set_scroll_position(&mut self, x: i64, y: i64) {
+        self.scroll_position = (x.max(0), y.max(0));
+        if let Some(runtime) = &mut self.runtime {
+            runtime.set_scroll_position(self.scroll_position.0, self.scroll_position.1);
+        }
+    }
+
+    /// スクロールできる最大の位置 (x, y) を返す。
+    /// 縦方向は、文書の末尾がコンテンツ領域の下端に来る位置までとする。
+    /// 横方向は、レイアウトがコンテンツ領域の幅に収まるためスクロールしない。
+    fn max_scroll_position(&self) -> (i64, i64) {
+        let max_y = match &self.layout_view {
+            Some(layout_view) => (layout_view.document_height() - CONTENT_AREA_HEIGHT).max(0),
+            None => 0,
+        };
+        (0, max_y)
+    }
+
+    /// ページを縦に dy だけスクロールする。
+    /// スクロール位置は、文書の末尾がコンテンツ領域の下端に来る位置までに制限する。
+    /// 戻り値はスクロール位置が変わったかどうかを表す。
+    pub fn scroll_by(&mut self, dy: i64) -> bool {
+        let (x, y) = self.scroll_position;
+        let (_, max_y) = self.max_scroll_position();
+        let new_y = (y + dy).clamp(0, max_y);
+        if new_y == y {
+            return false;
+        }
+        self.set_scroll_position(x, new_y);
+        true
+    }
+
+    /// JavaScript の window.scrollTo によって要求されたスクロール位置を反映する。
+    /// scroll_by と同じく、スクロール位置はスクロールできる範囲に制限する。
+    /// 制限した位置をランタイムにも反映し、window.scrollY などが実際の位置を返すようにする。
+    /// https://drafts.csswg.org/cssom-view/#perform-a-scroll
+    /// 戻り値はスクロール位置が変わったかどうかを表す。
+    fn apply_scroll_request(&mut self) -> bool {
+        let request = match &mut self.runtime {
+            Some(runtime) => runtime.take_scroll_request(),
+            None => None,
+        };
+        let (x, y) = match request {
+            Some(position) => position,
+            None => return false,
+        };
+
+        let (max_x, max_y) = self.max_scroll_position();
+        let previous = self.scroll_position;
+        self.set_scroll_position(x.clamp(0, max_x), y.clamp(0, max_y));
+        self.scroll_position != previous
     }
 
     //   pub fn receive_response(&mut self, response: HttpResponse) -> String {
@@ -87,18 +486,30 @@ impl Page {
     //     "".to_string()
     // }
 
-    fn create_frame(&mut self, html: String) {
-        // HTML 文字列から DOM を構築する。
-        let html_tokenizer = HtmlTokenizer::new(html);
-        let frame = HtmlParser::new(html_tokenizer).construct_tree();
+    fn create_frame(&mut self, mut html_parser: HtmlParser) {
+        // 残りの HTML 文字列から DOM の構築を終える。
+        html_parser.close();
+        let frame = html_parser.construct_tree();
+        self.parse_errors = html_parser.errors();
+        self.parse_diagnostics = html_parser.diagnostics();
+        // <base href> は相対 URL の場合もあるため、ページの URL を基準に解決する。
+        self.base_url = match (&self.url, html_parser.base_href()) {
+            (Some(url), Some(href)) => Some(url.join(&href)),
+            (url, _) => url.clone(),
+        };
         // self.frame = Some(frame);
         let dom = frame.borrow().document();
+        self.title = get_title_content(dom.clone());
 
         // CSS を解釈する。
-        let style = get_style_content(dom);
-        let css_tokenizer = CssTokenizer::new(style);
-        let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
+        // <style> タグごとに別のスタイルシートとして解釈し、文書内に現れる順にルールを集める。
+        let mut cssom = StyleSheet::new();
+        for style in get_style_contents(dom) {
+            let css_tokenizer = CssTokenizer::new(style);
+            cssom.merge(CssParser::new(css_tokenizer).parse_stylesheet());
+        }
 
+        frame.borrow().observe(&self.mutation_observer);
         self.frame = Some(frame);
         self.style = Some(cssom);
     }
@@ -115,20 +526,53 @@ impl Page {
             None => return,
         };
 
-        let layout_view = LayoutView::new(dom, &style);
+        let layout_view = LayoutView::with_images(dom, &style, &self.image_sizes());
         self.layout_view = Some(layout_view);
+        // 現在の DOM ツリーからレイアウトツリーを作り直したため、それまでの変更の記録は不要となる。
+        self.mutation_observer.borrow_mut().take_records();
+    }
+
+    /// レイアウトツリーを作成した後に DOM ツリーが変更され、レイアウトツリーが古くなっているかどうかを返す。
+    fn is_layout_stale(&self) -> bool {
+        self.mutation_observer.borrow().has_records()
     }
 
     /// 作成したレイアウトツリーの paint メソッドを使って DisplayItem のベクタを取得し、フィールドにセットする。
+    /// フィールドの DisplayItem はページ上の座標を持ち、スクロール位置は display_items メソッドで反映する。
+    /// <iframe> の中のページの DisplayItem は、<iframe> の大きさで切り取り、<iframe> のレイアウトオブジェクトの位置に移動して追加する。
     fn paint_tree(&mut self) {
-        if let Some(layout_view) = &self.layout_view {
-            self.display_items = layout_view.paint();
+        let layout_view = match &self.layout_view {
+            Some(layout_view) => layout_view,
+            None => return,
+        };
+        self.display_items = layout_view.paint();
+
+        for (iframe, child) in &self.child_pages {
+            let object = match layout_view.find_layout_object(iframe) {
+                Some(object) => object,
+                None => continue,
+            };
+            let point = object.borrow().point();
+            let size = object.borrow().size();
+            for item in &child.borrow().display_items {
+                if let Some(item) = item.clip(size) {
+                    self.display_items
+                        .push(item.translate(point.x(), point.y()));
+                }
+            }
         }
     }
 
     /// DisplayItems 構造体のベクタを取得する。
+    /// 各 DisplayItem はスクロール位置の分だけ移動し、コンテンツ領域の左上を原点とする座標を持つ。
+    /// コンテンツ領域の外にはみ出す部分は切り取る。
     pub fn display_items(&self) -> Vec<DisplayItem> {
-        self.display_items.clone()
+        let (scroll_x, scroll_y) = self.scroll_position;
+        let viewport = LayoutSize::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT);
+        self.display_items
+            .iter()
+            .filter_map(|item| item.translate(-scroll_x, -scroll_y).clip(viewport))
+            .collect()
     }
 
     /// DisplayItem 構造体のベクタをクリアする。
@@ -137,13 +581,30 @@ impl Page {
     }
 
     /// マウスの位置から度のノードがクリックされたか取得し、そのノードの親が href 属性を持ってる場合、その値を返す。
-    pub fn clicked(&self, position: (i64, i64)) -> Option<String> {
-        let view = match &self.layout_view {
-            Some(v) => v,
+    /// リンクをたどる前に、クリックされたノードに click イベントを送出する。
+    /// イベントのリスナーが DOM ツリーを変更した場合は、レイアウトと描画をやり直す。
+    /// リスナーが preventDefault を呼んだ場合、リンクはたどらない。
+    pub fn clicked(&mut self, position: (i64, i64)) -> Option<String> {
+        let node = match &self.layout_view {
+            Some(view) => view.find_node_by_position(position),
             None => return None,
         };
 
-        if let Some(n) = view.find_node_by_position(position) {
+        if let (Some(n), Some(runtime)) = (&node, &mut self.runtime) {
+            let target = n.borrow().node();
+            let mut event = Event::new("click", true);
+            let not_canceled = runtime.dispatch_event(&target, &mut event);
+            if self.is_layout_stale() {
+                self.set_layout_view();
+                self.paint_tree();
+            }
+            self.apply_scroll_request();
+            if !not_canceled {
+                return None;
+            }
+        }
+
+        if let Some(n) = node {
             if let Some(parent) = n.borrow().parent().upgrade() {
                 if let NodeKind::Element(e) = parent.borrow().node_kind() {
                     if e.kind() == ElementKind::A {
@@ -155,3 +616,131 @@ impl Page {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 受信した HTML の <img> を、スケジューラで取得した画像の内在的な大きさでレイアウトするテスト。
+    fn test_receive_image_subresource() {
+        let mut page = Page::new();
+        page.set_url(
+            Url::new("http://host.test/index.html".to_string())
+                .parse()
+                .unwrap(),
+        );
+        let html = "<html><body><img src=\"a.gif\"></body></html>";
+        page.receive_response(
+            HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html)).unwrap(),
+        );
+
+        let mut scheduler = FetchScheduler::new();
+        page.enqueue_subresources(&mut scheduler);
+        let (id, url) = scheduler.next_request().expect("image should be requested");
+        assert_eq!("http://host.test/a.gif", url.href());
+        assert_eq!(None, scheduler.next_request());
+
+        // 横幅 32、高さ 16 の GIF 画像のヘッダ
+        let gif = "HTTP/1.1 200 OK\nContent-Type: image/gif\n\nGIF89a\x20\x00\x10\x00";
+        scheduler.complete(id, HttpResponse::new(gif.to_string()));
+        page.receive_completed_subresources(&mut scheduler);
+
+        let image = page
+            .display_items()
+            .into_iter()
+            .find(|item| matches!(item, DisplayItem::Image { .. }));
+        match image {
+            Some(DisplayItem::Image {
+                src, layout_size, ..
+            }) => {
+                assert_eq!("a.gif", src);
+                assert_eq!(LayoutSize::new(32, 16), layout_size);
+            }
+            _ => panic!("image should be painted: {:?}", page.display_items()),
+        }
+
+        // 受信済みの画像は再びリクエストしない。
+        page.enqueue_subresources(&mut scheduler);
+        assert!(scheduler.is_idle());
+    }
+
+    #[test]
+    /// 構文エラーのスクリプトはそのスクリプトだけを中止し、ページの読み込みを続けるテスト。
+    fn test_script_syntax_error() {
+        let mut page = Page::new();
+        let html = "<html><head><script>var o = {a 1}; document.title = 'x';</script></head><body><p>text</p></body></html>";
+        page.receive_response(
+            HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html)).unwrap(),
+        );
+
+        assert!(page
+            .display_items()
+            .into_iter()
+            .any(|item| matches!(item, DisplayItem::Text { ref text, .. } if text == "text")));
+        // イベントハンドラを実行できるように、ランタイムは保持する。
+        assert!(page.runtime.is_some());
+    }
+
+    #[test]
+    /// window.scrollTo で文書の末尾を越える位置を指定した場合、スクロール位置を文書の末尾までに制限するテスト。
+    /// 制限した位置を window.scrollX と window.scrollY から読み取れることも確認する。
+    fn test_scroll_to_past_the_end() {
+        let mut page = Page::new();
+        let html = alloc::format!(
+            "<html><head><script>function f() {{ var y = document.getElementById(\"y\"); y.textContent = window.scrollX + \",\" + window.scrollY; }} window.scrollTo(50, 100000); setTimeout(f, 0);</script></head><body>{}<p id=\"y\"></p></body></html>",
+            "<p>line</p>".repeat(100)
+        );
+        page.receive_response(
+            HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html)).unwrap(),
+        );
+
+        let document_height = page
+            .layout_view
+            .as_ref()
+            .expect("layout view should exist")
+            .document_height();
+        let max_y = document_height - CONTENT_AREA_HEIGHT;
+        assert!(max_y > 0);
+        assert_eq!((0, max_y), page.scroll_position());
+        // 末尾までスクロールしているため、それ以上は下にスクロールしない。
+        assert!(!page.scroll_by(10));
+
+        page.run_timers();
+        let window = page.frame.as_ref().expect("frame should exist").clone();
+        let y = window
+            .borrow()
+            .get_element_by_id("y")
+            .expect("failed to get #y");
+        assert_eq!(alloc::format!("0,{}", max_y), y.borrow().text_content());
+    }
+
+    #[test]
+    /// 分割して受け取ったボディからページを作成するテスト。
+    /// <meta> タグで UTF-8 以外のエンコーディングが宣言されていた場合は、レスポンス全体をデコードし直す。
+    fn test_finish_streaming_response() {
+        let mut page = Page::new();
+        page.begin_response();
+        page.receive_chunk("<html><head><title>a");
+        page.receive_chunk("b</title></head><body>text</body></html>");
+        page.finish_streaming_response(
+            HttpResponse::new(
+                "HTTP/1.1 200 OK\n\n<html><head><title>ab</title></head><body>text</body></html>"
+                    .to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!("ab", page.title());
+
+        // "あ" の Shift_JIS のバイト列を UTF-8 としてデコードしたボディを渡す。
+        let mut raw =
+            b"HTTP/1.1 200 OK\n\n<html><head><meta charset=\"Shift_JIS\"><title>".to_vec();
+        raw.extend_from_slice(&[0x82, 0xa0]);
+        raw.extend_from_slice(b"</title></head></html>");
+        let response = HttpResponse::from_bytes(&raw).unwrap();
+        page.begin_response();
+        page.receive_chunk(&response.body());
+        page.finish_streaming_response(response);
+        assert_eq!("\u{3042}", page.title());
+    }
+}
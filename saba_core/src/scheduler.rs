@@ -0,0 +1,158 @@
+use crate::error::Error;
+use crate::http::HttpResponse;
+use crate::url::Url;
+use alloc::vec::Vec;
+
+/// 同じホストに同時に送信するリクエストのデフォルトの最大数
+pub static DEFAULT_MAX_REQUESTS_PER_HOST: usize = 2;
+/// 全体で同時に送信するリクエストのデフォルトの最大数
+pub static DEFAULT_MAX_REQUESTS: usize = 6;
+
+/// スケジューラに登録したリクエストを識別する ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestId(usize);
+
+/// CSS やスクリプト、画像などのサブリソースのリクエストを管理する構造体。
+/// 同時に送信するリクエストの数をホストごとと全体で制限し、完了したレスポンスを Page に渡すまで保持する。
+/// ネットワーク層は next_request で送信するリクエストを取り出し、受信したレスポンスを complete で返す。
+#[derive(Debug)]
+pub struct FetchScheduler {
+    max_requests_per_host: usize,
+    max_requests: usize,
+    next_id: usize,
+    // 送信を待っているリクエスト
+    queued: Vec<(RequestId, Url)>,
+    // 送信してレスポンスを待っているリクエスト
+    in_flight: Vec<(RequestId, Url)>,
+    // レスポンスを受信し、Page に渡すのを待っているリクエスト
+    completed: Vec<(RequestId, Url, Result<HttpResponse, Error>)>,
+}
+
+impl FetchScheduler {
+    pub fn new() -> Self {
+        Self::with_limits(DEFAULT_MAX_REQUESTS_PER_HOST, DEFAULT_MAX_REQUESTS)
+    }
+
+    /// 同時に送信するリクエストの最大数を指定して FetchScheduler を作成する。
+    pub fn with_limits(max_requests_per_host: usize, max_requests: usize) -> Self {
+        Self {
+            max_requests_per_host,
+            max_requests,
+            next_id: 0,
+            queued: Vec::new(),
+            in_flight: Vec::new(),
+            completed: Vec::new(),
+        }
+    }
+
+    /// url のリクエストを登録し、その ID を返す。
+    pub fn enqueue(&mut self, url: Url) -> RequestId {
+        let id = RequestId(self.next_id);
+        self.next_id += 1;
+        self.queued.push((id, url));
+        id
+    }
+
+    /// 同時に送信するリクエストの制限を超えない範囲で、次に送信するリクエストを取り出す。
+    /// 登録された順に、まだ制限に達していないホストのリクエストを選ぶ。
+    /// 送信できるリクエストがない場合は None を返す。
+    pub fn next_request(&mut self) -> Option<(RequestId, Url)> {
+        if self.in_flight.len() >= self.max_requests {
+            return None;
+        }
+        let index = self.queued.iter().position(|(_, url)| {
+            let host = url.host();
+            self.in_flight
+                .iter()
+                .filter(|(_, in_flight)| in_flight.host() == host)
+                .count()
+                < self.max_requests_per_host
+        })?;
+        let (id, url) = self.queued.remove(index);
+        self.in_flight.push((id, url.clone()));
+        Some((id, url))
+    }
+
+    /// 送信したリクエストの結果を登録する。
+    pub fn complete(&mut self, id: RequestId, result: Result<HttpResponse, Error>) {
+        if let Some(index) = self.in_flight.iter().position(|(i, _)| *i == id) {
+            let (id, url) = self.in_flight.remove(index);
+            self.completed.push((id, url, result));
+        }
+    }
+
+    /// 完了したリクエストの結果を、完了した順にすべて取り出す。
+    pub fn take_completed(&mut self) -> Vec<(RequestId, Url, Result<HttpResponse, Error>)> {
+        core::mem::take(&mut self.completed)
+    }
+
+    /// 送信を待っているリクエストも、レスポンスを待っているリクエストもない場合に true を返す。
+    pub fn is_idle(&self) -> bool {
+        self.queued.is_empty() && self.in_flight.is_empty()
+    }
+}
+
+impl Default for FetchScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn url(s: &str) -> Url {
+        Url::new(s.to_string()).parse().unwrap()
+    }
+
+    #[test]
+    /// ホストごとの同時リクエスト数の制限のテスト。
+    fn test_limit_per_host() {
+        let mut scheduler = FetchScheduler::with_limits(2, 6);
+        let a1 = scheduler.enqueue(url("http://a.test/1.css"));
+        let a2 = scheduler.enqueue(url("http://a.test/2.css"));
+        let a3 = scheduler.enqueue(url("http://a.test/3.css"));
+        let b1 = scheduler.enqueue(url("http://b.test/1.js"));
+
+        assert_eq!(Some(a1), scheduler.next_request().map(|(id, _)| id));
+        assert_eq!(Some(a2), scheduler.next_request().map(|(id, _)| id));
+        // a.test は制限に達しているため、b.test のリクエストを先に送信する。
+        assert_eq!(Some(b1), scheduler.next_request().map(|(id, _)| id));
+        assert_eq!(None, scheduler.next_request());
+
+        scheduler.complete(a1, HttpResponse::new("HTTP/1.1 200 OK\n\n".to_string()));
+        assert_eq!(Some(a3), scheduler.next_request().map(|(id, _)| id));
+        assert!(!scheduler.is_idle());
+
+        let completed = scheduler.take_completed();
+        assert_eq!(1, completed.len());
+        assert_eq!(a1, completed[0].0);
+        assert_eq!("1.css", completed[0].1.path());
+        assert!(scheduler.take_completed().is_empty());
+
+        scheduler.complete(a2, Err(Error::Network("failed".to_string())));
+        scheduler.complete(a3, HttpResponse::new("HTTP/1.1 200 OK\n\n".to_string()));
+        scheduler.complete(b1, HttpResponse::new("HTTP/1.1 200 OK\n\n".to_string()));
+        assert!(scheduler.is_idle());
+        assert_eq!(3, scheduler.take_completed().len());
+    }
+
+    #[test]
+    /// 全体の同時リクエスト数の制限のテスト。
+    fn test_total_limit() {
+        let mut scheduler = FetchScheduler::with_limits(2, 3);
+        for host in ["a", "b", "c", "d"] {
+            scheduler.enqueue(url(&alloc::format!("http://{}.test/", host)));
+        }
+        assert!(scheduler.next_request().is_some());
+        assert!(scheduler.next_request().is_some());
+        let (third, _) = scheduler.next_request().unwrap();
+        assert_eq!(None, scheduler.next_request());
+
+        scheduler.complete(third, HttpResponse::new("HTTP/1.1 200 OK\n\n".to_string()));
+        let (_, fourth) = scheduler.next_request().unwrap();
+        assert_eq!("d.test", fourth.host());
+    }
+}
@@ -0,0 +1,380 @@
+//! 署名の検証に使う多倍長の符号なし整数と、奇数の法でのモンゴメリ乗算
+//! RSA のべき乗剰余と、楕円曲線の有限体の演算に使う。検証に使う公開された値のみを扱うため、定数時間ではない。
+//! https://en.wikipedia.org/wiki/Montgomery_modular_multiplication
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// 多倍長の符号なし整数。64 ビットの limb を下位から順に並べ、上位の 0 の limb は持たない。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u64>,
+}
+
+impl BigUint {
+    /// ビッグエンディアンのバイト列から整数を作成する。
+    pub fn from_be_bytes(bytes: &[u8]) -> Self {
+        let mut limbs = Vec::new();
+        for chunk in bytes.rchunks(8) {
+            let mut limb = [0u8; 8];
+            limb[8 - chunk.len()..].copy_from_slice(chunk);
+            limbs.push(u64::from_be_bytes(limb));
+        }
+        Self::from_limbs(limbs)
+    }
+
+    fn from_limbs(mut limbs: Vec<u64>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        Self { limbs }
+    }
+
+    /// 整数を length バイトのビッグエンディアンのバイト列にする。収まらない場合は None を返す。
+    pub fn to_be_bytes(&self, length: usize) -> Option<Vec<u8>> {
+        if self.bit_len() > length * 8 {
+            return None;
+        }
+        let mut bytes = vec![0u8; length];
+        for (i, limb) in self.limbs.iter().enumerate() {
+            for (j, b) in limb.to_le_bytes().iter().enumerate() {
+                let index = i * 8 + j;
+                if index < length {
+                    bytes[length - 1 - index] = *b;
+                }
+            }
+        }
+        Some(bytes)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// 値を表すのに必要なビット数を返す。
+    pub fn bit_len(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * 64 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// 下位から index 番目のビットが 1 かどうか。
+    pub fn bit(&self, index: usize) -> bool {
+        self.limbs
+            .get(index / 64)
+            .is_some_and(|limb| (limb >> (index % 64)) & 1 == 1)
+    }
+
+    /// 右に shift ビットずらした値を返す。
+    pub fn shr(&self, shift: usize) -> Self {
+        let (words, bits) = (shift / 64, shift % 64);
+        let mut limbs = Vec::new();
+        for i in words..self.limbs.len() {
+            let mut limb = self.limbs[i] >> bits;
+            if bits > 0 {
+                if let Some(next) = self.limbs.get(i + 1) {
+                    limb |= next << (64 - bits);
+                }
+            }
+            limbs.push(limb);
+        }
+        Self::from_limbs(limbs)
+    }
+
+    /// self - other を返す。self が other より小さい場合は None を返す。
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if *self < *other {
+            return None;
+        }
+        let mut limbs = self.limbs.clone();
+        sub_assign(&mut limbs, &other.limbs);
+        Some(Self::from_limbs(limbs))
+    }
+
+    /// length 個の limb に 0 を補った limb の列を返す。
+    fn padded(&self, length: usize) -> Vec<u64> {
+        let mut limbs = self.limbs.clone();
+        limbs.resize(length, 0);
+        limbs
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| compare_limbs(&self.limbs, &other.limbs))
+    }
+}
+
+/// 同じ長さの limb の列を、上位の limb から比較する。
+fn compare_limbs(a: &[u64], b: &[u64]) -> Ordering {
+    for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+        match x.cmp(y) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+    Ordering::Equal
+}
+
+/// a から b を引き、最上位からの借りを返す。b の長さは a 以下とする。
+fn sub_assign(a: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = false;
+    for (i, x) in a.iter_mut().enumerate() {
+        let y = b.get(i).copied().unwrap_or(0);
+        let (d1, b1) = x.overflowing_sub(y);
+        let (d2, b2) = d1.overflowing_sub(borrow as u64);
+        *x = d2;
+        borrow = b1 || b2;
+    }
+    borrow
+}
+
+/// a に b を足し、最上位からの繰り上がりを返す。a と b は同じ長さとする。
+fn add_assign(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = false;
+    for (x, y) in a.iter_mut().zip(b) {
+        let (s1, c1) = x.overflowing_add(*y);
+        let (s2, c2) = s1.overflowing_add(carry as u64);
+        *x = s2;
+        carry = c1 || c2;
+    }
+    carry
+}
+
+/// 奇数の法 m と、モンゴメリ乗算に使う値。
+/// 法での剰余は m と同じ数の limb の列で表し、モンゴメリ表現 (aR mod m、R = 2^(64 * limb の数)) で演算する。
+#[derive(Debug, Clone)]
+pub struct Modulus {
+    m: Vec<u64>,
+    // -m^(-1) mod 2^64
+    m_inv: u64,
+    // R^2 mod m
+    r2: Vec<u64>,
+}
+
+impl Modulus {
+    /// 法 m を作成する。m が 1 以下の場合や偶数の場合は None を返す。
+    pub fn new(m: &BigUint) -> Option<Self> {
+        if m.bit_len() < 2 || m.limbs[0] & 1 == 0 {
+            return None;
+        }
+        let n = m.limbs.len();
+
+        // ニュートン法で m[0] の 2^64 を法とした逆数を求める。1回で正しいビット数が2倍になる。
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m.limbs[0].wrapping_mul(inv)));
+        }
+
+        // 1 を 2 倍して m で割った余りを取ることを繰り返し、R^2 mod m を求める。
+        let mut r2 = vec![0u64; n];
+        r2[0] = 1;
+        for _ in 0..2 * 64 * n {
+            let carry = r2.iter_mut().fold(0, |carry, limb| {
+                let next = *limb >> 63;
+                *limb = (*limb << 1) | carry;
+                next
+            });
+            if carry == 1 || compare_limbs(&r2, &m.limbs) != Ordering::Less {
+                sub_assign(&mut r2, &m.limbs);
+            }
+        }
+
+        Some(Self {
+            m: m.limbs.clone(),
+            m_inv: inv.wrapping_neg(),
+            r2,
+        })
+    }
+
+    /// 法を返す。
+    pub fn value(&self) -> BigUint {
+        BigUint::from_limbs(self.m.clone())
+    }
+
+    /// m 未満の整数をモンゴメリ表現にする。m 以上の場合は None を返す。
+    pub fn to_montgomery(&self, a: &BigUint) -> Option<Vec<u64>> {
+        if a.limbs.len() > self.m.len() {
+            return None;
+        }
+        let a = a.padded(self.m.len());
+        if compare_limbs(&a, &self.m) != Ordering::Less {
+            return None;
+        }
+        Some(self.mul(&a, &self.r2))
+    }
+
+    /// モンゴメリ表現から整数に戻す。
+    pub fn from_montgomery(&self, a: &[u64]) -> BigUint {
+        let mut one = vec![0u64; self.m.len()];
+        one[0] = 1;
+        BigUint::from_limbs(self.mul(a, &one))
+    }
+
+    /// モンゴメリ表現の 1 (R mod m) を返す。
+    pub fn one(&self) -> Vec<u64> {
+        let mut one = vec![0u64; self.m.len()];
+        one[0] = 1;
+        self.mul(&one, &self.r2)
+    }
+
+    /// モンゴメリ表現の 0 を返す。
+    pub fn zero(&self) -> Vec<u64> {
+        vec![0u64; self.m.len()]
+    }
+
+    /// モンゴメリ乗算 a * b * R^(-1) mod m を返す (CIOS 法)。
+    pub fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = self.m.len();
+        let mut t = vec![0u64; n + 2];
+        for &bi in b.iter().take(n) {
+            let mut carry: u128 = 0;
+            for j in 0..n {
+                let sum = t[j] as u128 + a[j] as u128 * bi as u128 + carry;
+                t[j] = sum as u64;
+                carry = sum >> 64;
+            }
+            let sum = t[n] as u128 + carry;
+            t[n] = sum as u64;
+            t[n + 1] = (sum >> 64) as u64;
+
+            let q = t[0].wrapping_mul(self.m_inv);
+            let mut carry = (t[0] as u128 + q as u128 * self.m[0] as u128) >> 64;
+            for j in 1..n {
+                let sum = t[j] as u128 + q as u128 * self.m[j] as u128 + carry;
+                t[j - 1] = sum as u64;
+                carry = sum >> 64;
+            }
+            let sum = t[n] as u128 + carry;
+            t[n - 1] = sum as u64;
+            t[n] = t[n + 1] + (sum >> 64) as u64;
+        }
+
+        let mut result = t[..n].to_vec();
+        if t[n] != 0 || compare_limbs(&result, &self.m) != Ordering::Less {
+            sub_assign(&mut result, &self.m);
+        }
+        result
+    }
+
+    /// (a + b) mod m を返す。
+    pub fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut sum = a.to_vec();
+        let carry = add_assign(&mut sum, b);
+        if carry || compare_limbs(&sum, &self.m) != Ordering::Less {
+            sub_assign(&mut sum, &self.m);
+        }
+        sum
+    }
+
+    /// (a - b) mod m を返す。
+    pub fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut difference = a.to_vec();
+        if sub_assign(&mut difference, b) {
+            add_assign(&mut difference, &self.m);
+        }
+        difference
+    }
+
+    /// モンゴメリ表現の a の exponent 乗を、モンゴメリ表現で返す。
+    pub fn pow(&self, a: &[u64], exponent: &BigUint) -> Vec<u64> {
+        let mut result = self.one();
+        for i in (0..exponent.bit_len()).rev() {
+            result = self.mul(&result, &result);
+            if exponent.bit(i) {
+                result = self.mul(&result, a);
+            }
+        }
+        result
+    }
+
+    /// 法が素数の場合に、モンゴメリ表現の a の逆数を、フェルマーの小定理 (a^(m-2)) で求める。
+    pub fn invert(&self, a: &[u64]) -> Vec<u64> {
+        let two = BigUint::from_limbs(vec![2]);
+        let exponent = self
+            .value()
+            .checked_sub(&two)
+            .expect("the modulus is greater than 2");
+        self.pow(a, &exponent)
+    }
+
+    /// モンゴメリ表現の値が 0 かどうか。
+    pub fn is_zero(&self, a: &[u64]) -> bool {
+        a.iter().all(|limb| *limb == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+
+    #[test]
+    /// バイト列との変換と比較のテスト。
+    fn test_big_uint() {
+        let a = BigUint::from_be_bytes(&hex("000102030405060708090a"));
+        assert_eq!(73, a.bit_len());
+        assert_eq!(Some(hex("0102030405060708090a")), a.to_be_bytes(10));
+        assert_eq!(None, a.to_be_bytes(9));
+        assert!(BigUint::from_be_bytes(&[0, 0]).is_zero());
+        assert!(a > BigUint::from_be_bytes(&hex("ffffffffffffffff")));
+        assert_eq!(BigUint::from_be_bytes(&hex("0102030405060708")), a.shr(16));
+        assert_eq!(
+            Some(BigUint::from_be_bytes(&hex("ffffffffffffffff"))),
+            BigUint::from_be_bytes(&hex("010000000000000000"))
+                .checked_sub(&BigUint::from_be_bytes(&[1]))
+        );
+    }
+
+    #[test]
+    /// モンゴメリ乗算によるべき乗剰余と逆数のテスト。
+    fn test_modulus() {
+        // 2^127 - 1 は素数である。
+        let p = BigUint::from_be_bytes(&hex("7fffffffffffffffffffffffffffffff"));
+        let modulus = Modulus::new(&p).unwrap();
+        let three = modulus
+            .to_montgomery(&BigUint::from_be_bytes(&[3]))
+            .unwrap();
+
+        // 3^5 = 243
+        let power = modulus.pow(&three, &BigUint::from_be_bytes(&[5]));
+        assert_eq!(
+            BigUint::from_be_bytes(&[243]),
+            modulus.from_montgomery(&power)
+        );
+
+        // 3 * 3^(-1) = 1
+        let inverse = modulus.invert(&three);
+        assert_eq!(
+            BigUint::from_be_bytes(&[1]),
+            modulus.from_montgomery(&modulus.mul(&three, &inverse))
+        );
+
+        // (p - 1) + 3 = 2, 3 - (p - 1) = 4
+        let minus_one = modulus
+            .to_montgomery(&p.checked_sub(&BigUint::from_be_bytes(&[1])).unwrap())
+            .unwrap();
+        assert_eq!(
+            BigUint::from_be_bytes(&[2]),
+            modulus.from_montgomery(&modulus.add(&minus_one, &three))
+        );
+        assert_eq!(
+            BigUint::from_be_bytes(&[4]),
+            modulus.from_montgomery(&modulus.sub(&three, &minus_one))
+        );
+
+        assert!(modulus.to_montgomery(&p).is_none());
+        assert!(Modulus::new(&BigUint::from_be_bytes(&[10])).is_none());
+    }
+}
@@ -0,0 +1,279 @@
+//! 認証付き暗号 ChaCha20-Poly1305
+//! TLS 1.3 のレコードの暗号化と改ざんの検出に使う。
+//! https://datatracker.ietf.org/doc/html/rfc8439
+
+use alloc::vec::Vec;
+
+/// 鍵のバイト数
+pub const KEY_LEN: usize = 32;
+/// ナンスのバイト数
+pub const NONCE_LEN: usize = 12;
+/// 認証タグのバイト数
+pub const TAG_LEN: usize = 16;
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+fn le32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// 鍵、ブロックカウンタ、ナンスから 64 バイトの鍵ストリームを作る。
+/// https://datatracker.ietf.org/doc/html/rfc8439#section-2.3
+fn chacha20_block(key: &[u8; KEY_LEN], counter: u32, nonce: &[u8; NONCE_LEN]) -> [u8; 64] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+    for i in 0..8 {
+        state[4 + i] = le32(&key[i * 4..]);
+    }
+    state[12] = counter;
+    for i in 0..3 {
+        state[13 + i] = le32(&nonce[i * 4..]);
+    }
+
+    let mut working = state;
+    for _ in 0..10 {
+        quarter_round(&mut working, 0, 4, 8, 12);
+        quarter_round(&mut working, 1, 5, 9, 13);
+        quarter_round(&mut working, 2, 6, 10, 14);
+        quarter_round(&mut working, 3, 7, 11, 15);
+        quarter_round(&mut working, 0, 5, 10, 15);
+        quarter_round(&mut working, 1, 6, 11, 12);
+        quarter_round(&mut working, 2, 7, 8, 13);
+        quarter_round(&mut working, 3, 4, 9, 14);
+    }
+
+    let mut block = [0u8; 64];
+    for i in 0..16 {
+        let word = working[i].wrapping_add(state[i]);
+        block[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    block
+}
+
+/// counter から始まる鍵ストリームと data の排他的論理和をとり、暗号化 (または復号) する。
+/// https://datatracker.ietf.org/doc/html/rfc8439#section-2.4
+fn chacha20_xor(key: &[u8; KEY_LEN], counter: u32, nonce: &[u8; NONCE_LEN], data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(64).enumerate() {
+        let block = chacha20_block(key, counter.wrapping_add(i as u32), nonce);
+        for (b, k) in chunk.iter_mut().zip(block.iter()) {
+            *b ^= k;
+        }
+    }
+}
+
+/// 32 バイトのワンタイム鍵で message の認証タグを計算する。
+/// 2^130 - 5 を法とする計算は、26 ビットずつ 5 個の limb に分けて行う。
+/// https://datatracker.ietf.org/doc/html/rfc8439#section-2.5
+fn poly1305(key: &[u8; 32], message: &[u8]) -> [u8; TAG_LEN] {
+    const MASK: u32 = 0x3ffffff;
+    let r0 = le32(&key[0..]) & 0x3ffffff;
+    let r1 = (le32(&key[3..]) >> 2) & 0x3ffff03;
+    let r2 = (le32(&key[6..]) >> 4) & 0x3ffc0ff;
+    let r3 = (le32(&key[9..]) >> 6) & 0x3f03fff;
+    let r4 = (le32(&key[12..]) >> 8) & 0x00fffff;
+    let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+    let (mut h0, mut h1, mut h2, mut h3, mut h4) = (0u32, 0u32, 0u32, 0u32, 0u32);
+
+    for chunk in message.chunks(16) {
+        // 最後のブロックが 16 バイトに満たない場合は、1 を付けてから 0 で埋める。
+        let mut block = [0u8; 17];
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()] = 1;
+        let hibit = (block[16] as u32) << 24;
+
+        h0 += le32(&block[0..]) & MASK;
+        h1 += (le32(&block[3..]) >> 2) & MASK;
+        h2 += (le32(&block[6..]) >> 4) & MASK;
+        h3 += (le32(&block[9..]) >> 6) & MASK;
+        h4 += (le32(&block[12..]) >> 8) | hibit;
+
+        let m = |a: u32, b: u32| a as u64 * b as u64;
+        let d0 = m(h0, r0) + m(h1, s4) + m(h2, s3) + m(h3, s2) + m(h4, s1);
+        let mut d1 = m(h0, r1) + m(h1, r0) + m(h2, s4) + m(h3, s3) + m(h4, s2);
+        let mut d2 = m(h0, r2) + m(h1, r1) + m(h2, r0) + m(h3, s4) + m(h4, s3);
+        let mut d3 = m(h0, r3) + m(h1, r2) + m(h2, r1) + m(h3, r0) + m(h4, s4);
+        let mut d4 = m(h0, r4) + m(h1, r3) + m(h2, r2) + m(h3, r1) + m(h4, r0);
+
+        h0 = d0 as u32 & MASK;
+        d1 += d0 >> 26;
+        h1 = d1 as u32 & MASK;
+        d2 += d1 >> 26;
+        h2 = d2 as u32 & MASK;
+        d3 += d2 >> 26;
+        h3 = d3 as u32 & MASK;
+        d4 += d3 >> 26;
+        h4 = d4 as u32 & MASK;
+        h0 += (d4 >> 26) as u32 * 5;
+        h1 += h0 >> 26;
+        h0 &= MASK;
+    }
+
+    // 繰り上げを終えてから、h が 2^130 - 5 以上であれば 2^130 - 5 を引く。
+    h2 += h1 >> 26;
+    h1 &= MASK;
+    h3 += h2 >> 26;
+    h2 &= MASK;
+    h4 += h3 >> 26;
+    h3 &= MASK;
+    h0 += (h4 >> 26) * 5;
+    h4 &= MASK;
+    h1 += h0 >> 26;
+    h0 &= MASK;
+
+    let mut g0 = h0 + 5;
+    let mut g1 = h1 + (g0 >> 26);
+    g0 &= MASK;
+    let mut g2 = h2 + (g1 >> 26);
+    g1 &= MASK;
+    let mut g3 = h3 + (g2 >> 26);
+    g2 &= MASK;
+    let mut g4 = (h4 + (g3 >> 26)).wrapping_sub(1 << 26);
+    g3 &= MASK;
+
+    // g4 が負 (最上位ビットが 1) の場合は h を、そうでない場合は g を選ぶ。
+    let select_g = (g4 >> 31).wrapping_sub(1);
+    g0 &= select_g;
+    g1 &= select_g;
+    g2 &= select_g;
+    g3 &= select_g;
+    g4 &= select_g;
+    let select_h = !select_g;
+    h0 = (h0 & select_h) | g0;
+    h1 = (h1 & select_h) | g1;
+    h2 = (h2 & select_h) | g2;
+    h3 = (h3 & select_h) | g3;
+    h4 = (h4 & select_h) | g4;
+
+    // 26 ビットの limb を 32 ビットの word に並べ直し、s を加える。
+    let words = [
+        h0 | (h1 << 26),
+        (h1 >> 6) | (h2 << 20),
+        (h2 >> 12) | (h3 << 14),
+        (h3 >> 18) | (h4 << 8),
+    ];
+    let mut tag = [0u8; TAG_LEN];
+    let mut f = 0u64;
+    for i in 0..4 {
+        f = words[i] as u64 + le32(&key[16 + i * 4..]) as u64 + (f >> 32);
+        tag[i * 4..i * 4 + 4].copy_from_slice(&(f as u32).to_le_bytes());
+    }
+    tag
+}
+
+/// 追加の認証データ aad と暗号文から、認証タグを計算する。
+/// https://datatracker.ietf.org/doc/html/rfc8439#section-2.8
+fn compute_tag(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; TAG_LEN] {
+    let mut otk = [0u8; 32];
+    otk.copy_from_slice(&chacha20_block(key, 0, nonce)[..32]);
+
+    let pad16 = |data: &mut Vec<u8>| {
+        while data.len() % 16 != 0 {
+            data.push(0);
+        }
+    };
+    let mut mac_data = aad.to_vec();
+    pad16(&mut mac_data);
+    mac_data.extend_from_slice(ciphertext);
+    pad16(&mut mac_data);
+    mac_data.extend_from_slice(&(aad.len() as u64).to_le_bytes());
+    mac_data.extend_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    poly1305(&otk, &mac_data)
+}
+
+/// plaintext を暗号化し、暗号文の後ろに認証タグを付けて返す。
+pub fn seal(key: &[u8; KEY_LEN], nonce: &[u8; NONCE_LEN], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let mut sealed = plaintext.to_vec();
+    chacha20_xor(key, 1, nonce, &mut sealed);
+    let tag = compute_tag(key, nonce, aad, &sealed);
+    sealed.extend_from_slice(&tag);
+    sealed
+}
+
+/// 認証タグを検証してから暗号文を復号する。
+/// 認証タグが一致しない (暗号文や aad が改ざんされている) 場合は None を返す。
+pub fn open(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    sealed: &[u8],
+) -> Option<Vec<u8>> {
+    if sealed.len() < TAG_LEN {
+        return None;
+    }
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+    let expected = compute_tag(key, nonce, aad, ciphertext);
+    // 一致しないバイトの位置によって時間が変わらないように、すべてのバイトを比較する。
+    let diff = expected
+        .iter()
+        .zip(tag.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+    if diff != 0 {
+        return None;
+    }
+
+    let mut plaintext = ciphertext.to_vec();
+    chacha20_xor(key, 1, nonce, &mut plaintext);
+    Some(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+
+    #[test]
+    /// RFC 8439 の 2.5.2 節の Poly1305 のテストベクタのテスト。
+    fn test_poly1305() {
+        let key: [u8; 32] = hex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b")
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            hex("a8061dc1305136c6c22b8baf0c0127a9"),
+            poly1305(&key, b"Cryptographic Forum Research Group")
+        );
+    }
+
+    #[test]
+    /// RFC 8439 の 2.8.2 節の AEAD のテストベクタで、暗号化と復号、改ざんの検出をテストする。
+    fn test_seal_and_open() {
+        let key: [u8; KEY_LEN] =
+            hex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f")
+                .try_into()
+                .unwrap();
+        let nonce: [u8; NONCE_LEN] = hex("070000004041424344454647").try_into().unwrap();
+        let aad = hex("50515253c0c1c2c3c4c5c6c7");
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+        let sealed = seal(&key, &nonce, &aad, plaintext);
+        assert_eq!(
+            hex(concat!(
+                "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6",
+                "3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36",
+                "92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc",
+                "3ff4def08e4b7a9de576d26586cec64b6116",
+                "1ae10b594f09e26a7e902ecbd0600691"
+            )),
+            sealed
+        );
+        assert_eq!(Some(plaintext.to_vec()), open(&key, &nonce, &aad, &sealed));
+
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert_eq!(None, open(&key, &nonce, &aad, &tampered));
+        assert_eq!(None, open(&key, &nonce, b"", &sealed));
+    }
+}
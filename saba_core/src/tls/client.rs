@@ -0,0 +1,1035 @@
+//! TLS 1.3 のクライアント
+//! ソケットは扱わず、受信したバイト列を push で受け取り、送信するバイト列を take_outgoing で返す。
+//! ネットワーク層は TCP ストリームとの間でバイト列を受け渡し、復号したアプリケーションデータを読み出す。
+//! サーバ証明書は組み込みのルート証明書までのチェーンとホスト名を検証し、CertificateVerify の署名を証明書の公開鍵で確認する。
+//! https://datatracker.ietf.org/doc/html/rfc8446
+
+use crate::error::Error;
+use crate::tls::chacha20poly1305;
+use crate::tls::digest::HashAlgorithm;
+use crate::tls::ecdsa::Curve;
+use crate::tls::hkdf::hkdf_expand;
+use crate::tls::hkdf::hkdf_extract;
+use crate::tls::hkdf::hmac_sha256;
+use crate::tls::roots;
+use crate::tls::sha256::sha256;
+use crate::tls::sha256::Sha256;
+use crate::tls::sha256::HASH_LEN;
+use crate::tls::x25519;
+use crate::tls::x509::verify_server_certificate;
+use crate::tls::x509::PublicKey;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// レコードの種類
+// https://datatracker.ietf.org/doc/html/rfc8446#section-5.1
+const CHANGE_CIPHER_SPEC: u8 = 20;
+const ALERT: u8 = 21;
+const HANDSHAKE: u8 = 22;
+const APPLICATION_DATA: u8 = 23;
+
+// ハンドシェイクメッセージの種類
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4
+const CLIENT_HELLO: u8 = 1;
+const SERVER_HELLO: u8 = 2;
+const NEW_SESSION_TICKET: u8 = 4;
+const ENCRYPTED_EXTENSIONS: u8 = 8;
+const CERTIFICATE: u8 = 11;
+const CERTIFICATE_REQUEST: u8 = 13;
+const CERTIFICATE_VERIFY: u8 = 15;
+const FINISHED: u8 = 20;
+const KEY_UPDATE: u8 = 24;
+
+// 拡張の種類
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2
+const SERVER_NAME: u16 = 0;
+const SUPPORTED_GROUPS: u16 = 10;
+const SIGNATURE_ALGORITHMS: u16 = 13;
+const SUPPORTED_VERSIONS: u16 = 43;
+const KEY_SHARE: u16 = 51;
+
+const TLS_1_2: u16 = 0x0303;
+const TLS_1_3: u16 = 0x0304;
+const TLS_CHACHA20_POLY1305_SHA256: u16 = 0x1303;
+const GROUP_X25519: u16 = 0x001d;
+
+// 署名アルゴリズム
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.3
+const ECDSA_SECP256R1_SHA256: u16 = 0x0403;
+const ECDSA_SECP384R1_SHA384: u16 = 0x0503;
+const RSA_PSS_RSAE_SHA256: u16 = 0x0804;
+const RSA_PSS_RSAE_SHA384: u16 = 0x0805;
+const RSA_PSS_RSAE_SHA512: u16 = 0x0806;
+const RSA_PKCS1_SHA256: u16 = 0x0401;
+const RSA_PKCS1_SHA384: u16 = 0x0501;
+const RSA_PKCS1_SHA512: u16 = 0x0601;
+
+/// ClientHello で対応していると伝える署名アルゴリズム。
+/// signature_algorithms_cert 拡張を送らないため、証明書の署名に使われる rsa_pkcs1 もここに含める。
+/// rsa_pkcs1 は CertificateVerify には使えない。
+const SIGNATURE_SCHEMES: [u16; 8] = [
+    ECDSA_SECP256R1_SHA256,
+    RSA_PSS_RSAE_SHA256,
+    RSA_PKCS1_SHA256,
+    ECDSA_SECP384R1_SHA384,
+    RSA_PSS_RSAE_SHA384,
+    RSA_PKCS1_SHA384,
+    RSA_PSS_RSAE_SHA512,
+    RSA_PKCS1_SHA512,
+];
+
+/// 1つのレコードに入れる平文の最大のバイト数
+const MAX_FRAGMENT_LEN: usize = 1 << 14;
+/// 暗号化されたレコードの最大のバイト数
+const MAX_CIPHERTEXT_LEN: usize = MAX_FRAGMENT_LEN + 256;
+
+/// ServerHello が HelloRetryRequest であることを表す random の値 ("HelloRetryRequest" の SHA-256)
+/// https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3
+const HELLO_RETRY_REQUEST_RANDOM: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+/// ハンドシェイクの状態
+/// https://datatracker.ietf.org/doc/html/rfc8446#appendix-A.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    WaitServerHello,
+    WaitEncryptedExtensions,
+    // CertificateRequest を受け取った場合も、この状態で Certificate を待つ。
+    WaitCertificate,
+    WaitCertificateVerify,
+    WaitFinished,
+    Connected,
+    Closed,
+}
+
+/// 一方向のレコードの暗号化に使う鍵、IV、シーケンス番号
+/// https://datatracker.ietf.org/doc/html/rfc8446#section-7.3
+#[derive(Debug, Clone)]
+struct TrafficKeys {
+    key: [u8; chacha20poly1305::KEY_LEN],
+    iv: [u8; chacha20poly1305::NONCE_LEN],
+    sequence: u64,
+}
+
+impl TrafficKeys {
+    fn new(secret: &[u8; HASH_LEN]) -> Self {
+        let mut key = [0; chacha20poly1305::KEY_LEN];
+        key.copy_from_slice(&hkdf_expand_label(
+            secret,
+            "key",
+            &[],
+            chacha20poly1305::KEY_LEN,
+        ));
+        let mut iv = [0; chacha20poly1305::NONCE_LEN];
+        iv.copy_from_slice(&hkdf_expand_label(
+            secret,
+            "iv",
+            &[],
+            chacha20poly1305::NONCE_LEN,
+        ));
+        Self {
+            key,
+            iv,
+            sequence: 0,
+        }
+    }
+
+    /// 次のレコードのナンス (IV とシーケンス番号の排他的論理和) を返し、シーケンス番号を進める。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-5.3
+    fn next_nonce(&mut self) -> [u8; chacha20poly1305::NONCE_LEN] {
+        let mut nonce = self.iv;
+        for (n, s) in nonce[4..].iter_mut().zip(self.sequence.to_be_bytes()) {
+            *n ^= s;
+        }
+        self.sequence += 1;
+        nonce
+    }
+}
+
+/// TLS 1.3 のクライアントの接続
+#[derive(Debug, Clone)]
+pub struct TlsClient {
+    state: State,
+    server_name: String,
+    // 信頼するルート証明書 (DER)
+    trust_anchors: Vec<Vec<u8>>,
+    // 証明書の有効期間の確認に使う現在時刻 (UNIX 時間の秒)。None の場合は確認しない。
+    current_time: Option<i64>,
+    // 検証したサーバ証明書の公開鍵
+    server_public_key: Option<PublicKey>,
+    private_key: [u8; x25519::KEY_LEN],
+    // これまでに送受信したハンドシェイクメッセージのハッシュ
+    transcript: Sha256,
+    // まだレコードとして処理していない受信したバイト列
+    received: Vec<u8>,
+    // まだメッセージとして処理していないハンドシェイクのバイト列
+    handshake: Vec<u8>,
+    handshake_secret: [u8; HASH_LEN],
+    client_secret: [u8; HASH_LEN],
+    server_secret: [u8; HASH_LEN],
+    read_keys: Option<TrafficKeys>,
+    write_keys: Option<TrafficKeys>,
+    // サーバから CertificateRequest を受け取った場合の certificate_request_context
+    certificate_request: Option<Vec<u8>>,
+    // 送信するバイト列
+    outgoing: Vec<u8>,
+    // 復号したアプリケーションデータ
+    application_data: Vec<u8>,
+}
+
+impl TlsClient {
+    /// server_name に接続するクライアントを作成し、最初に送信する ClientHello を用意する。
+    /// random と private_key は、暗号論的に安全な乱数で毎回作り直す必要がある。
+    /// サーバ証明書は組み込みのルート証明書で検証する。
+    pub fn new(server_name: &str, random: [u8; 32], private_key: [u8; x25519::KEY_LEN]) -> Self {
+        let mut client = Self {
+            state: State::WaitServerHello,
+            server_name: server_name.to_string(),
+            trust_anchors: roots::trust_anchors(),
+            current_time: None,
+            server_public_key: None,
+            private_key,
+            transcript: Sha256::new(),
+            received: Vec::new(),
+            handshake: Vec::new(),
+            handshake_secret: [0; HASH_LEN],
+            client_secret: [0; HASH_LEN],
+            server_secret: [0; HASH_LEN],
+            read_keys: None,
+            write_keys: None,
+            certificate_request: None,
+            outgoing: Vec::new(),
+            application_data: Vec::new(),
+        };
+
+        let client_hello = client.client_hello(server_name, random);
+        client.transcript.update(&client_hello);
+        // 最初のレコードの legacy_record_version は互換性のために TLS 1.0 (0x0301) とする。
+        client.outgoing.extend_from_slice(&[HANDSHAKE, 3, 1]);
+        client
+            .outgoing
+            .extend_from_slice(&(client_hello.len() as u16).to_be_bytes());
+        client.outgoing.extend_from_slice(&client_hello);
+        client
+    }
+
+    /// サーバ証明書の検証に使うルート証明書 (DER) を置き換える。ハンドシェイクの前に呼ぶ。
+    pub fn set_trust_anchors(&mut self, trust_anchors: Vec<Vec<u8>>) {
+        self.trust_anchors = trust_anchors;
+    }
+
+    /// 証明書の有効期間の確認に使う現在時刻 (UNIX 時間の秒) を設定する。ハンドシェイクの前に呼ぶ。
+    /// 設定しない場合、実時間の時計を持たない環境のために有効期間は確認しない。
+    pub fn set_current_time(&mut self, now: i64) {
+        self.current_time = Some(now);
+    }
+
+    /// ハンドシェイクを終え、アプリケーションデータを送受信できる場合に true を返す。
+    pub fn is_connected(&self) -> bool {
+        self.state == State::Connected
+    }
+
+    /// サーバが close_notify を送って接続を閉じた場合に true を返す。
+    pub fn is_closed(&self) -> bool {
+        self.state == State::Closed
+    }
+
+    /// サーバに送信するバイト列をすべて取り出す。
+    pub fn take_outgoing(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.outgoing)
+    }
+
+    /// 復号したアプリケーションデータをすべて取り出す。
+    pub fn take_application_data(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.application_data)
+    }
+
+    /// アプリケーションデータを暗号化して、送信するバイト列に追加する。
+    pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        if self.state != State::Connected {
+            return Err(tls_error("the handshake has not finished"));
+        }
+        self.send_encrypted(APPLICATION_DATA, data);
+        Ok(())
+    }
+
+    /// サーバから受信したバイト列を追加し、揃ったレコードを処理する。
+    /// 復号したアプリケーションデータは take_application_data で、返信するバイト列は take_outgoing で取り出す。
+    pub fn push(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.received.extend_from_slice(bytes);
+        while self.received.len() >= 5 {
+            let length = u16::from_be_bytes([self.received[3], self.received[4]]) as usize;
+            if length > MAX_CIPHERTEXT_LEN {
+                return Err(tls_error("record overflow"));
+            }
+            if self.received.len() < 5 + length {
+                break;
+            }
+            let record: Vec<u8> = self.received.drain(..5 + length).collect();
+            self.process_record(&record)?;
+        }
+        Ok(())
+    }
+
+    /// ClientHello メッセージを作成する。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.2
+    fn client_hello(&self, server_name: &str, random: [u8; 32]) -> Vec<u8> {
+        let mut extensions = Vec::new();
+        // IP アドレスは server_name 拡張に入れられない。
+        // https://datatracker.ietf.org/doc/html/rfc6066#section-3
+        let is_ip_address = server_name.contains(':')
+            || server_name.chars().all(|c| c.is_ascii_digit() || c == '.');
+        if !is_ip_address {
+            let mut name = vec![0];
+            name.extend_from_slice(&with_u16_length(server_name.as_bytes()));
+            push_extension(&mut extensions, SERVER_NAME, &with_u16_length(&name));
+        }
+        push_extension(
+            &mut extensions,
+            SUPPORTED_GROUPS,
+            &with_u16_length(&GROUP_X25519.to_be_bytes()),
+        );
+        let schemes: Vec<u8> = SIGNATURE_SCHEMES
+            .iter()
+            .flat_map(|s| s.to_be_bytes())
+            .collect();
+        push_extension(
+            &mut extensions,
+            SIGNATURE_ALGORITHMS,
+            &with_u16_length(&schemes),
+        );
+        push_extension(
+            &mut extensions,
+            SUPPORTED_VERSIONS,
+            &with_u8_length(&TLS_1_3.to_be_bytes()),
+        );
+        let mut key_share = GROUP_X25519.to_be_bytes().to_vec();
+        key_share.extend_from_slice(&with_u16_length(&x25519::public_key(&self.private_key)));
+        push_extension(&mut extensions, KEY_SHARE, &with_u16_length(&key_share));
+
+        let mut body = TLS_1_2.to_be_bytes().to_vec();
+        body.extend_from_slice(&random);
+        // legacy_session_id
+        body.push(0);
+        body.extend_from_slice(&with_u16_length(
+            &TLS_CHACHA20_POLY1305_SHA256.to_be_bytes(),
+        ));
+        // legacy_compression_methods (null のみ)
+        body.extend_from_slice(&[1, 0]);
+        body.extend_from_slice(&with_u16_length(&extensions));
+        handshake_message(CLIENT_HELLO, &body)
+    }
+
+    /// 受信したレコードを、必要であれば復号してから種類ごとに処理する。
+    fn process_record(&mut self, record: &[u8]) -> Result<(), Error> {
+        let content_type = record[0];
+        let fragment = &record[5..];
+
+        match (content_type, &mut self.read_keys) {
+            // 互換性のために送られる change_cipher_spec は、ハンドシェイク中であれば無視する。
+            // https://datatracker.ietf.org/doc/html/rfc8446#section-5
+            (CHANGE_CIPHER_SPEC, _) if self.state != State::Connected => Ok(()),
+            (APPLICATION_DATA, Some(keys)) => {
+                let nonce = keys.next_nonce();
+                let mut plaintext =
+                    chacha20poly1305::open(&keys.key, &nonce, &record[..5], fragment)
+                        .ok_or_else(|| tls_error("bad record mac"))?;
+                // 平文の末尾の 0 の詰め物を取り除くと、最後のバイトが本来のレコードの種類を表す。
+                while plaintext.last() == Some(&0) {
+                    plaintext.pop();
+                }
+                let inner_type = plaintext
+                    .pop()
+                    .ok_or_else(|| tls_error("a record without a content type"))?;
+                self.process_plaintext(inner_type, &plaintext)
+            }
+            (HANDSHAKE | ALERT, None) => self.process_plaintext(content_type, fragment),
+            _ => Err(tls_error(&format!(
+                "unexpected record of type {}",
+                content_type
+            ))),
+        }
+    }
+
+    /// 復号したレコードの内容を処理する。
+    fn process_plaintext(&mut self, content_type: u8, content: &[u8]) -> Result<(), Error> {
+        match content_type {
+            HANDSHAKE => {
+                self.handshake.extend_from_slice(content);
+                // 1つのレコードに複数のメッセージが入っている場合や、メッセージが複数のレコードに分かれている場合がある。
+                while self.handshake.len() >= 4 {
+                    let length = u24(&self.handshake[1..4]);
+                    if self.handshake.len() < 4 + length {
+                        break;
+                    }
+                    let message: Vec<u8> = self.handshake.drain(..4 + length).collect();
+                    self.process_handshake(&message)?;
+                }
+                Ok(())
+            }
+            APPLICATION_DATA if self.state == State::Connected => {
+                self.application_data.extend_from_slice(content);
+                Ok(())
+            }
+            // close_notify (warning, 0) は正常な切断を表し、それ以外のアラートはエラーとする。
+            // https://datatracker.ietf.org/doc/html/rfc8446#section-6
+            ALERT => {
+                if content == [1, 0] {
+                    self.state = State::Closed;
+                    return Ok(());
+                }
+                Err(tls_error(&format!(
+                    "received an alert: {}",
+                    content.get(1).copied().unwrap_or(0)
+                )))
+            }
+            _ => Err(tls_error(&format!(
+                "unexpected content of type {}",
+                content_type
+            ))),
+        }
+    }
+
+    /// ハンドシェイクメッセージを、現在の状態に応じて処理する。
+    fn process_handshake(&mut self, message: &[u8]) -> Result<(), Error> {
+        let body = &message[4..];
+        match (self.state, message[0]) {
+            (State::WaitServerHello, SERVER_HELLO) => {
+                self.transcript.update(message);
+                self.receive_server_hello(body)?;
+                self.state = State::WaitEncryptedExtensions;
+            }
+            (State::WaitEncryptedExtensions, ENCRYPTED_EXTENSIONS) => {
+                self.transcript.update(message);
+                self.state = State::WaitCertificate;
+            }
+            (State::WaitCertificate, CERTIFICATE_REQUEST) => {
+                self.transcript.update(message);
+                self.certificate_request = Some(Reader::new(body).u8_length_bytes()?.to_vec());
+            }
+            (State::WaitCertificate, CERTIFICATE) => {
+                self.receive_certificate(body)?;
+                self.transcript.update(message);
+                self.state = State::WaitCertificateVerify;
+            }
+            (State::WaitCertificateVerify, CERTIFICATE_VERIFY) => {
+                // 署名の対象は CertificateVerify の直前までのハンドシェイクである。
+                self.receive_certificate_verify(body)?;
+                self.transcript.update(message);
+                self.state = State::WaitFinished;
+            }
+            (State::WaitFinished, FINISHED) => {
+                self.receive_finished(message)?;
+                self.state = State::Connected;
+            }
+            // セッションの再開には対応していないため、チケットは使わない。
+            (State::Connected, NEW_SESSION_TICKET) => {}
+            (State::Connected, KEY_UPDATE) => self.receive_key_update(body)?,
+            (_, message_type) => {
+                return Err(tls_error(&format!(
+                    "unexpected handshake message of type {}",
+                    message_type
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// ServerHello からサーバの公開鍵を取り出し、共有の秘密からハンドシェイクの鍵を導出する。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3
+    fn receive_server_hello(&mut self, body: &[u8]) -> Result<(), Error> {
+        let mut reader = Reader::new(body);
+        reader.u16()?;
+        if reader.bytes(32)? == HELLO_RETRY_REQUEST_RANDOM {
+            return Err(tls_error("HelloRetryRequest is not supported"));
+        }
+        reader.u8_length_bytes()?;
+        if reader.u16()? != TLS_CHACHA20_POLY1305_SHA256 {
+            return Err(tls_error("the server chose an unsupported cipher suite"));
+        }
+        if reader.u8()? != 0 {
+            return Err(tls_error("the server chose a compression method"));
+        }
+
+        let mut version = None;
+        let mut server_key = None;
+        let mut extensions = Reader::new(reader.u16_length_bytes()?);
+        while !extensions.is_empty() {
+            let extension_type = extensions.u16()?;
+            let mut data = Reader::new(extensions.u16_length_bytes()?);
+            match extension_type {
+                SUPPORTED_VERSIONS => version = Some(data.u16()?),
+                KEY_SHARE => {
+                    if data.u16()? != GROUP_X25519 {
+                        return Err(tls_error("the server chose an unsupported group"));
+                    }
+                    server_key = Some(data.u16_length_bytes()?);
+                }
+                _ => {}
+            }
+        }
+        if version != Some(TLS_1_3) {
+            return Err(tls_error("the server does not support TLS 1.3"));
+        }
+        let server_key: [u8; x25519::KEY_LEN] = server_key
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| tls_error("the server did not send a valid key share"))?;
+
+        let shared_secret = x25519::x25519(&self.private_key, &server_key);
+        if shared_secret == [0; x25519::KEY_LEN] {
+            return Err(tls_error("the server sent an invalid public key"));
+        }
+
+        // https://datatracker.ietf.org/doc/html/rfc8446#section-7.1
+        let early_secret = hkdf_extract(&[0; HASH_LEN], &[0; HASH_LEN]);
+        let derived = derive_secret(&early_secret, "derived", &sha256(&[]));
+        self.handshake_secret = hkdf_extract(&derived, &shared_secret);
+        let hash = self.transcript_hash();
+        self.client_secret = derive_secret(&self.handshake_secret, "c hs traffic", &hash);
+        self.server_secret = derive_secret(&self.handshake_secret, "s hs traffic", &hash);
+        self.read_keys = Some(TrafficKeys::new(&self.server_secret));
+        Ok(())
+    }
+
+    /// サーバの証明書チェーンとホスト名を検証し、サーバ証明書の公開鍵を保存する。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.2
+    fn receive_certificate(&mut self, body: &[u8]) -> Result<(), Error> {
+        let mut reader = Reader::new(body);
+        // certificate_request_context はサーバからは空である。
+        reader.u8_length_bytes()?;
+        let mut entries = Reader::new(reader.u24_length_bytes()?);
+        let mut chain = Vec::new();
+        while !entries.is_empty() {
+            chain.push(entries.u24_length_bytes()?);
+            // 証明書ごとの拡張 (OCSP の応答など) は使わない。
+            entries.u16_length_bytes()?;
+        }
+        self.server_public_key = Some(verify_server_certificate(
+            &chain,
+            &self.trust_anchors,
+            &self.server_name,
+            self.current_time,
+        )?);
+        Ok(())
+    }
+
+    /// CertificateVerify の署名を、サーバ証明書の公開鍵で検証する。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.3
+    fn receive_certificate_verify(&self, body: &[u8]) -> Result<(), Error> {
+        let mut reader = Reader::new(body);
+        let scheme = reader.u16()?;
+        let signature = reader.u16_length_bytes()?;
+
+        // 64 個の空白、コンテキスト文字列、0、ハンドシェイクのハッシュを連結したものが署名の対象となる。
+        let mut content = vec![0x20; 64];
+        content.extend_from_slice(b"TLS 1.3, server CertificateVerify");
+        content.push(0);
+        content.extend_from_slice(&self.transcript_hash());
+
+        let key = self
+            .server_public_key
+            .as_ref()
+            .ok_or_else(|| tls_error("the server certificate has not been verified"))?;
+        let verified = match (scheme, key) {
+            (ECDSA_SECP256R1_SHA256, PublicKey::Ec(key)) if key.curve() == Curve::P256 => {
+                key.verify(HashAlgorithm::Sha256, &content, signature)
+            }
+            (ECDSA_SECP384R1_SHA384, PublicKey::Ec(key)) if key.curve() == Curve::P384 => {
+                key.verify(HashAlgorithm::Sha384, &content, signature)
+            }
+            (RSA_PSS_RSAE_SHA256, PublicKey::Rsa(key)) => {
+                key.verify_pss(HashAlgorithm::Sha256, &content, signature)
+            }
+            (RSA_PSS_RSAE_SHA384, PublicKey::Rsa(key)) => {
+                key.verify_pss(HashAlgorithm::Sha384, &content, signature)
+            }
+            (RSA_PSS_RSAE_SHA512, PublicKey::Rsa(key)) => {
+                key.verify_pss(HashAlgorithm::Sha512, &content, signature)
+            }
+            _ => {
+                return Err(tls_error(
+                    "the server chose a signature scheme that does not match its certificate",
+                ))
+            }
+        };
+        if !verified {
+            return Err(tls_error("the CertificateVerify signature is invalid"));
+        }
+        Ok(())
+    }
+
+    /// サーバの Finished を検証し、クライアントの Finished を送信してから、アプリケーションデータの鍵に切り替える。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.4
+    fn receive_finished(&mut self, message: &[u8]) -> Result<(), Error> {
+        let expected = finished_verify_data(&self.server_secret, &self.transcript_hash());
+        if !constant_time_eq(&message[4..], &expected) {
+            return Err(tls_error("the server Finished does not match"));
+        }
+        self.transcript.update(message);
+
+        let hash = self.transcript_hash();
+        let derived = derive_secret(&self.handshake_secret, "derived", &sha256(&[]));
+        let master_secret = hkdf_extract(&derived, &[0; HASH_LEN]);
+        let client_application_secret = derive_secret(&master_secret, "c ap traffic", &hash);
+        let server_application_secret = derive_secret(&master_secret, "s ap traffic", &hash);
+
+        self.write_keys = Some(TrafficKeys::new(&self.client_secret));
+        // 証明書を求められた場合は、証明書を持っていないため空の Certificate を送る。
+        if let Some(context) = self.certificate_request.take() {
+            let mut body = with_u8_length(&context);
+            body.extend_from_slice(&[0, 0, 0]);
+            let certificate = handshake_message(CERTIFICATE, &body);
+            self.transcript.update(&certificate);
+            self.send_encrypted(HANDSHAKE, &certificate);
+        }
+        let verify_data = finished_verify_data(&self.client_secret, &self.transcript_hash());
+        self.send_encrypted(HANDSHAKE, &handshake_message(FINISHED, &verify_data));
+
+        self.client_secret = client_application_secret;
+        self.server_secret = server_application_secret;
+        self.read_keys = Some(TrafficKeys::new(&self.server_secret));
+        self.write_keys = Some(TrafficKeys::new(&self.client_secret));
+        Ok(())
+    }
+
+    /// サーバの KeyUpdate を受け取り、受信に使う鍵を更新する。
+    /// サーバがクライアントの鍵の更新も求めている場合は、KeyUpdate を送信してから送信に使う鍵を更新する。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-4.6.3
+    fn receive_key_update(&mut self, body: &[u8]) -> Result<(), Error> {
+        let update_requested = Reader::new(body).u8()? == 1;
+        self.server_secret = next_traffic_secret(&self.server_secret);
+        self.read_keys = Some(TrafficKeys::new(&self.server_secret));
+        if update_requested {
+            self.send_encrypted(HANDSHAKE, &handshake_message(KEY_UPDATE, &[0]));
+            self.client_secret = next_traffic_secret(&self.client_secret);
+            self.write_keys = Some(TrafficKeys::new(&self.client_secret));
+        }
+        Ok(())
+    }
+
+    /// content を暗号化したレコードを、送信するバイト列に追加する。
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-5.2
+    fn send_encrypted(&mut self, content_type: u8, content: &[u8]) {
+        let keys = match &mut self.write_keys {
+            Some(keys) => keys,
+            None => return,
+        };
+        for chunk in content.chunks(MAX_FRAGMENT_LEN) {
+            let mut inner = chunk.to_vec();
+            inner.push(content_type);
+            let length = inner.len() + chacha20poly1305::TAG_LEN;
+            let mut header = vec![APPLICATION_DATA, 3, 3];
+            header.extend_from_slice(&(length as u16).to_be_bytes());
+            let nonce = keys.next_nonce();
+            let sealed = chacha20poly1305::seal(&keys.key, &nonce, &header, &inner);
+            self.outgoing.extend_from_slice(&header);
+            self.outgoing.extend_from_slice(&sealed);
+        }
+    }
+
+    /// これまでのハンドシェイクメッセージのハッシュを返す。
+    fn transcript_hash(&self) -> [u8; HASH_LEN] {
+        self.transcript.clone().finish()
+    }
+}
+
+/// バイト列を順に読み出す構造体。足りない場合はエラーを返す。
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < n {
+            return Err(tls_error("a message is too short"));
+        }
+        let (bytes, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// 1バイトの長さが前に付いたバイト列を読み出す。
+    fn u8_length_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let length = self.u8()? as usize;
+        self.bytes(length)
+    }
+
+    /// 2バイトの長さが前に付いたバイト列を読み出す。
+    fn u16_length_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let length = self.u16()? as usize;
+        self.bytes(length)
+    }
+
+    /// 3バイトの長さが前に付いたバイト列を読み出す。
+    fn u24_length_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let length = u24(self.bytes(3)?);
+        self.bytes(length)
+    }
+}
+
+fn tls_error(message: &str) -> Error {
+    Error::Network(format!("TLS: {}", message))
+}
+
+fn u24(bytes: &[u8]) -> usize {
+    ((bytes[0] as usize) << 16) | ((bytes[1] as usize) << 8) | bytes[2] as usize
+}
+
+fn with_u8_length(data: &[u8]) -> Vec<u8> {
+    let mut v = vec![data.len() as u8];
+    v.extend_from_slice(data);
+    v
+}
+
+fn with_u16_length(data: &[u8]) -> Vec<u8> {
+    let mut v = (data.len() as u16).to_be_bytes().to_vec();
+    v.extend_from_slice(data);
+    v
+}
+
+fn push_extension(extensions: &mut Vec<u8>, extension_type: u16, data: &[u8]) {
+    extensions.extend_from_slice(&extension_type.to_be_bytes());
+    extensions.extend_from_slice(&with_u16_length(data));
+}
+
+/// 種類と 3 バイトの長さを前に付けたハンドシェイクメッセージを返す。
+fn handshake_message(message_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut message = vec![message_type];
+    message.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    message.extend_from_slice(body);
+    message
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc8446#section-7.1
+fn hkdf_expand_label(secret: &[u8], label: &str, context: &[u8], length: usize) -> Vec<u8> {
+    let mut info = (length as u16).to_be_bytes().to_vec();
+    let mut full_label = "tls13 ".to_string();
+    full_label.push_str(label);
+    info.extend_from_slice(&with_u8_length(full_label.as_bytes()));
+    info.extend_from_slice(&with_u8_length(context));
+    hkdf_expand(secret, &info, length)
+}
+
+fn derive_secret(secret: &[u8], label: &str, transcript_hash: &[u8]) -> [u8; HASH_LEN] {
+    let mut derived = [0; HASH_LEN];
+    derived.copy_from_slice(&hkdf_expand_label(secret, label, transcript_hash, HASH_LEN));
+    derived
+}
+
+/// KeyUpdate の後に使うトラフィックシークレットを返す。
+fn next_traffic_secret(secret: &[u8; HASH_LEN]) -> [u8; HASH_LEN] {
+    derive_secret(secret, "traffic upd", &[])
+}
+
+/// トラフィックシークレットから導出した鍵で、ハンドシェイクのハッシュの HMAC を計算する。
+fn finished_verify_data(secret: &[u8], transcript_hash: &[u8]) -> [u8; HASH_LEN] {
+    let finished_key = hkdf_expand_label(secret, "finished", &[], HASH_LEN);
+    hmac_sha256(&finished_key, transcript_hash)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+    use crate::tls::x509::pem_to_der;
+
+    // x509 のテストと同じ、example.com に対する証明書チェーンとそのルート証明書
+    const ROOT: &str = include_str!("testdata/root.pem");
+    const INTERMEDIATE: &str = include_str!("testdata/intermediate.pem");
+    const LEAF: &str = include_str!("testdata/leaf.pem");
+
+    // 2025年1月1日 00:00:00 UTC
+    const NOW: i64 = 1735689600;
+
+    // test_handshake のハンドシェイクに対して、サーバ証明書の秘密鍵で作成した CertificateVerify の署名
+    // (ecdsa_secp256r1_sha256)。ClientHello やテストのサーバのメッセージを変えた場合は作り直す必要がある。
+    const CERTIFICATE_VERIFY_SIGNATURE: &str =
+        "3045022020bc41c20861ee999e3d1afcbcff12d04054bb6c56df399c906f2c397c0e5895\
+         0221009a77d958a399bcd8ae90e317a8ee85329cc0a18595d26844772139cd8ca3a0c2";
+
+    /// テスト用のルート証明書を信頼するクライアントを作成する。
+    fn test_client() -> TlsClient {
+        let mut client = TlsClient::new("example.com", [1; 32], [2; 32]);
+        client.set_trust_anchors(vec![pem_to_der(ROOT).unwrap()]);
+        client.set_current_time(NOW);
+        client
+    }
+
+    /// サーバ証明書と中間 CA 証明書を送る Certificate メッセージを作成する。
+    fn certificate_message() -> Vec<u8> {
+        let mut entries = Vec::new();
+        for pem in [LEAF, INTERMEDIATE] {
+            let der = pem_to_der(pem).unwrap();
+            entries.extend_from_slice(&(der.len() as u32).to_be_bytes()[1..]);
+            entries.extend_from_slice(&der);
+            entries.extend_from_slice(&[0, 0]);
+        }
+        let mut body = vec![0];
+        body.extend_from_slice(&(entries.len() as u32).to_be_bytes()[1..]);
+        body.extend_from_slice(&entries);
+        handshake_message(CERTIFICATE, &body)
+    }
+
+    /// CertificateVerify メッセージを作成する。
+    fn certificate_verify_message(scheme: u16, signature: &[u8]) -> Vec<u8> {
+        let mut body = scheme.to_be_bytes().to_vec();
+        body.extend_from_slice(&with_u16_length(signature));
+        handshake_message(CERTIFICATE_VERIFY, &body)
+    }
+
+    /// ClientHello を受け取ったテスト用のサーバの鍵
+    struct TestServer {
+        transcript: Sha256,
+        handshake_secret: [u8; HASH_LEN],
+        client_secret: [u8; HASH_LEN],
+        server_secret: [u8; HASH_LEN],
+        write_keys: TrafficKeys,
+    }
+
+    impl TestServer {
+        /// ClientHello のレコードに対して ServerHello を返し、ハンドシェイクの鍵を導出する。
+        fn new(client_hello_record: &[u8], client_public: &[u8; 32]) -> (Self, Vec<u8>) {
+            let server_private = [3; 32];
+            let mut extensions = Vec::new();
+            push_extension(&mut extensions, SUPPORTED_VERSIONS, &TLS_1_3.to_be_bytes());
+            let mut key_share = GROUP_X25519.to_be_bytes().to_vec();
+            key_share.extend_from_slice(&with_u16_length(&x25519::public_key(&server_private)));
+            push_extension(&mut extensions, KEY_SHARE, &key_share);
+            let mut body = TLS_1_2.to_be_bytes().to_vec();
+            body.extend_from_slice(&[4; 32]);
+            body.push(0);
+            body.extend_from_slice(&TLS_CHACHA20_POLY1305_SHA256.to_be_bytes());
+            body.push(0);
+            body.extend_from_slice(&with_u16_length(&extensions));
+            let server_hello = handshake_message(SERVER_HELLO, &body);
+
+            let mut transcript = Sha256::new();
+            transcript.update(&client_hello_record[5..]);
+            transcript.update(&server_hello);
+            let hash = transcript.clone().finish();
+            let shared_secret = x25519::x25519(&server_private, client_public);
+            let early_secret = hkdf_extract(&[0; HASH_LEN], &[0; HASH_LEN]);
+            let derived = derive_secret(&early_secret, "derived", &sha256(&[]));
+            let handshake_secret = hkdf_extract(&derived, &shared_secret);
+            let client_secret = derive_secret(&handshake_secret, "c hs traffic", &hash);
+            let server_secret = derive_secret(&handshake_secret, "s hs traffic", &hash);
+
+            let mut record = vec![HANDSHAKE, 3, 3];
+            record.extend_from_slice(&with_u16_length(&server_hello));
+            let server = Self {
+                transcript,
+                handshake_secret,
+                client_secret,
+                server_secret,
+                write_keys: TrafficKeys::new(&server_secret),
+            };
+            (server, record)
+        }
+
+        fn seal(&mut self, content_type: u8, content: &[u8]) -> Vec<u8> {
+            seal_record(&mut self.write_keys, content_type, content)
+        }
+    }
+
+    fn seal_record(keys: &mut TrafficKeys, content_type: u8, content: &[u8]) -> Vec<u8> {
+        let mut inner = content.to_vec();
+        inner.push(content_type);
+        let mut header = vec![APPLICATION_DATA, 3, 3];
+        header.extend_from_slice(&((inner.len() + 16) as u16).to_be_bytes());
+        let nonce = keys.next_nonce();
+        let mut record = header.clone();
+        record.extend_from_slice(&chacha20poly1305::seal(&keys.key, &nonce, &header, &inner));
+        record
+    }
+
+    /// 暗号化されたレコードを1つ復号し、レコードの種類と内容を返す。
+    fn open_record(keys: &mut TrafficKeys, record: &[u8]) -> (u8, Vec<u8>) {
+        let nonce = keys.next_nonce();
+        let mut plaintext =
+            chacha20poly1305::open(&keys.key, &nonce, &record[..5], &record[5..]).unwrap();
+        let content_type = plaintext.pop().unwrap();
+        (content_type, plaintext)
+    }
+
+    #[test]
+    /// ハンドシェイクを終えてから、アプリケーションデータを送受信するテスト。
+    fn test_handshake() {
+        let mut client = test_client();
+        let client_hello = client.take_outgoing();
+        assert_eq!(&[HANDSHAKE, 3, 1], &client_hello[..3]);
+        assert_eq!(CLIENT_HELLO, client_hello[5]);
+        let client_public = x25519::public_key(&[2; 32]);
+        assert!(client_hello.windows(32).any(|w| w == client_public));
+        assert!(client_hello.windows(11).any(|w| w == b"example.com"));
+
+        let (mut server, mut flight) = TestServer::new(&client_hello, &client_public);
+        // 互換性のための change_cipher_spec は無視される。
+        flight.extend_from_slice(&[CHANGE_CIPHER_SPEC, 3, 3, 0, 1, 1]);
+        let mut messages = handshake_message(ENCRYPTED_EXTENSIONS, &[0, 0]);
+        messages.extend_from_slice(&certificate_message());
+        server.transcript.update(&messages);
+        let certificate_verify =
+            certificate_verify_message(ECDSA_SECP256R1_SHA256, &hex(CERTIFICATE_VERIFY_SIGNATURE));
+        server.transcript.update(&certificate_verify);
+        messages.extend_from_slice(&certificate_verify);
+        let verify_data =
+            finished_verify_data(&server.server_secret, &server.transcript.clone().finish());
+        let finished = handshake_message(FINISHED, &verify_data);
+        server.transcript.update(&finished);
+        messages.extend_from_slice(&finished);
+        flight.extend_from_slice(&server.seal(HANDSHAKE, &messages));
+
+        // レコードが分割して届いても処理できる。
+        for byte in &flight {
+            assert!(!client.is_connected());
+            client.push(&[*byte]).unwrap();
+        }
+        assert!(client.is_connected());
+
+        // クライアントの Finished を検証する。
+        let hash = server.transcript.clone().finish();
+        let mut client_keys = TrafficKeys::new(&server.client_secret);
+        let (content_type, client_finished) =
+            open_record(&mut client_keys, &client.take_outgoing());
+        assert_eq!(HANDSHAKE, content_type);
+        assert_eq!(
+            handshake_message(
+                FINISHED,
+                &finished_verify_data(&server.client_secret, &hash)
+            ),
+            client_finished
+        );
+
+        // アプリケーションデータの鍵に切り替えて送受信する。
+        let derived = derive_secret(&server.handshake_secret, "derived", &sha256(&[]));
+        let master_secret = hkdf_extract(&derived, &[0; HASH_LEN]);
+        let mut client_keys =
+            TrafficKeys::new(&derive_secret(&master_secret, "c ap traffic", &hash));
+        server.write_keys = TrafficKeys::new(&derive_secret(&master_secret, "s ap traffic", &hash));
+
+        client.write(b"GET / HTTP/1.1").unwrap();
+        assert_eq!(
+            (APPLICATION_DATA, b"GET / HTTP/1.1".to_vec()),
+            open_record(&mut client_keys, &client.take_outgoing())
+        );
+
+        let mut response = server.seal(HANDSHAKE, &handshake_message(NEW_SESSION_TICKET, &[0; 8]));
+        response.extend_from_slice(&server.seal(APPLICATION_DATA, b"HTTP/1.1 200 OK"));
+        response.extend_from_slice(&server.seal(ALERT, &[1, 0]));
+        client.push(&response).unwrap();
+        assert_eq!(b"HTTP/1.1 200 OK".to_vec(), client.take_application_data());
+        assert!(client.is_closed());
+    }
+
+    /// client に ServerHello と、EncryptedExtensions、Certificate、scheme と signature の CertificateVerify を渡す。
+    fn push_certificate(
+        client: &mut TlsClient,
+        scheme: u16,
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let client_hello = client.take_outgoing();
+        let (mut server, server_hello) =
+            TestServer::new(&client_hello, &x25519::public_key(&[2; 32]));
+        client.push(&server_hello).unwrap();
+        let mut messages = handshake_message(ENCRYPTED_EXTENSIONS, &[0, 0]);
+        messages.extend_from_slice(&certificate_message());
+        messages.extend_from_slice(&certificate_verify_message(scheme, signature));
+        client.push(&server.seal(HANDSHAKE, &messages))
+    }
+
+    #[test]
+    /// サーバ証明書や CertificateVerify を検証できない場合にエラーとなることのテスト。
+    fn test_certificate_errors() {
+        let signature = hex(CERTIFICATE_VERIFY_SIGNATURE);
+        assert!(push_certificate(&mut test_client(), ECDSA_SECP256R1_SHA256, &signature).is_ok());
+
+        // 証明書がホスト名と一致しない。
+        let mut client = TlsClient::new("example.net", [1; 32], [2; 32]);
+        client.set_trust_anchors(vec![pem_to_der(ROOT).unwrap()]);
+        assert_eq!(
+            Err(Error::Network(
+                "TLS: certificate verification failed: the certificate is not valid for the host"
+                    .to_string()
+            )),
+            push_certificate(&mut client, ECDSA_SECP256R1_SHA256, &signature)
+        );
+
+        // 組み込みのルート証明書は、テスト用のルート証明書を信頼しない。
+        let mut client = TlsClient::new("example.com", [1; 32], [2; 32]);
+        assert_eq!(
+            Err(Error::Network(
+                "TLS: certificate verification failed: unknown issuer".to_string()
+            )),
+            push_certificate(&mut client, ECDSA_SECP256R1_SHA256, &signature)
+        );
+
+        // 有効期間外の証明書。
+        let mut client = test_client();
+        client.set_current_time(2019686401);
+        assert_eq!(
+            Err(Error::Network(
+                "TLS: certificate verification failed: the certificate is expired or not yet valid"
+                    .to_string()
+            )),
+            push_certificate(&mut client, ECDSA_SECP256R1_SHA256, &signature)
+        );
+
+        // 証明書の鍵と一致しない署名アルゴリズムや、不正な署名。
+        for (scheme, signature) in [
+            (RSA_PSS_RSAE_SHA256, signature.clone()),
+            (ECDSA_SECP384R1_SHA384, signature.clone()),
+            (ECDSA_SECP256R1_SHA256, hex("3006020101020101")),
+        ] {
+            assert!(push_certificate(&mut test_client(), scheme, &signature).is_err());
+        }
+    }
+
+    #[test]
+    /// 改ざんされたレコードやアラートを受け取った場合にエラーとなることのテスト。
+    fn test_errors() {
+        let mut client = TlsClient::new("example.com", [1; 32], [2; 32]);
+        let client_hello = client.take_outgoing();
+        let (mut server, server_hello) =
+            TestServer::new(&client_hello, &x25519::public_key(&[2; 32]));
+        client.push(&server_hello).unwrap();
+        let mut record = server.seal(HANDSHAKE, &handshake_message(ENCRYPTED_EXTENSIONS, &[0, 0]));
+        record[6] ^= 1;
+        assert!(client.push(&record).is_err());
+
+        // 書き込みはハンドシェイクを終えてからでなければできない。
+        assert!(client.write(b"data").is_err());
+
+        let mut client = TlsClient::new("example.com", [1; 32], [2; 32]);
+        // handshake_failure (fatal, 40)
+        assert_eq!(
+            Err(Error::Network("TLS: received an alert: 40".to_string())),
+            client.push(&[ALERT, 3, 3, 0, 2, 2, 40])
+        );
+    }
+}
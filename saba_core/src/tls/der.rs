@@ -0,0 +1,200 @@
+//! 証明書と署名の読み取りに使う DER (Distinguished Encoding Rules) の読み取り
+//! 1バイトのタグのみを扱い、不定長の形式は受け付けない。
+//! https://www.itu.int/rec/T-REC-X.690
+
+use crate::error::Error;
+use alloc::format;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// 構造化された文脈固有のタグ [number] を返す。
+pub const fn context_tag(number: u8) -> u8 {
+    0xa0 | number
+}
+
+/// 構造化されていない文脈固有のタグ [number] を返す。
+pub const fn context_primitive_tag(number: u8) -> u8 {
+    0x80 | number
+}
+
+/// DER の不正を表すエラーを作成する。
+pub fn der_error(message: &str) -> Error {
+    Error::Network(format!("TLS: invalid certificate: {}", message))
+}
+
+/// 1つの TLV (タグ、長さ、値)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+    // タグと長さを含むエンコードされたバイト列全体。署名の対象や名前の比較に使う。
+    pub raw: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// 値を DER として読み取る Der を返す。SEQUENCE などの構造化された値に使う。
+    pub fn contents(&self) -> Der<'a> {
+        Der::new(self.value)
+    }
+
+    /// 正の INTEGER の値を、先頭の符号用の 0 を除いたビッグエンディアンのバイト列として返す。
+    pub fn positive_integer(&self) -> Result<&'a [u8], Error> {
+        if self.tag != TAG_INTEGER || self.value.is_empty() || self.value[0] & 0x80 != 0 {
+            return Err(der_error("expected a positive integer"));
+        }
+        let value = match self.value {
+            [0, rest @ ..] if !rest.is_empty() => {
+                if rest[0] & 0x80 == 0 {
+                    return Err(der_error("non-minimal integer"));
+                }
+                rest
+            }
+            value => value,
+        };
+        Ok(value)
+    }
+
+    /// 未使用ビットのない BIT STRING の値を返す。
+    pub fn bit_string(&self) -> Result<&'a [u8], Error> {
+        match self.value {
+            [0, rest @ ..] if self.tag == TAG_BIT_STRING => Ok(rest),
+            _ => Err(der_error("expected a bit string")),
+        }
+    }
+}
+
+/// DER のバイト列を先頭から読み取る。
+#[derive(Debug, Clone)]
+pub struct Der<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// 次の TLV のタグを読み進めずに返す。
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// 次の TLV を読み取る。
+    pub fn read(&mut self) -> Result<Tlv<'a>, Error> {
+        let data = self.data;
+        let (&tag, rest) = data.split_first().ok_or(der_error("unexpected end"))?;
+        if tag & 0x1f == 0x1f {
+            return Err(der_error("unsupported tag"));
+        }
+        let (&first, mut rest) = rest.split_first().ok_or(der_error("unexpected end"))?;
+        let length = if first < 0x80 {
+            first as usize
+        } else {
+            // 長い形式。0x80 (不定長) と 4 バイトを超える長さは受け付けない。
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(der_error("invalid length"));
+            }
+            let length = rest[..count]
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            if length < 0x80 || rest[0] == 0 {
+                return Err(der_error("non-minimal length"));
+            }
+            rest = &rest[count..];
+            length
+        };
+        if rest.len() < length {
+            return Err(der_error("unexpected end"));
+        }
+        let header_len = data.len() - rest.len();
+        self.data = &rest[length..];
+        Ok(Tlv {
+            tag,
+            value: &rest[..length],
+            raw: &data[..header_len + length],
+        })
+    }
+
+    /// tag の TLV を読み取る。タグが異なる場合はエラーを返す。
+    pub fn expect(&mut self, tag: u8) -> Result<Tlv<'a>, Error> {
+        let tlv = self.read()?;
+        if tlv.tag != tag {
+            return Err(der_error("unexpected tag"));
+        }
+        Ok(tlv)
+    }
+
+    /// 次の TLV のタグが tag の場合のみ読み取る。OPTIONAL や DEFAULT の要素に使う。
+    pub fn read_optional(&mut self, tag: u8) -> Result<Option<Tlv<'a>>, Error> {
+        if self.peek_tag() == Some(tag) {
+            self.read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 残りのバイト列がないことを確認する。
+    pub fn finish(&self) -> Result<(), Error> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(der_error("trailing data"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+
+    #[test]
+    /// 短い形式と長い形式の長さ、INTEGER と BIT STRING の値、不正な長さのテスト。
+    fn test_der() {
+        let data = hex("3007020100020200ff0303000102");
+        let mut der = Der::new(&data);
+        let sequence = der.expect(TAG_SEQUENCE).unwrap();
+        assert_eq!(&data[..9], sequence.raw);
+        let mut contents = sequence.contents();
+        assert_eq!(Ok(&[0u8][..]), contents.read().unwrap().positive_integer());
+        assert_eq!(
+            Ok(&[0xffu8][..]),
+            contents.read().unwrap().positive_integer()
+        );
+        assert!(contents.finish().is_ok());
+        assert_eq!(None, der.read_optional(TAG_INTEGER).unwrap());
+        assert_eq!(Ok(&[1u8, 2][..]), der.read().unwrap().bit_string());
+        assert!(der.finish().is_ok());
+
+        // 長い形式の長さ。
+        let mut data = hex("048180");
+        data.resize(3 + 0x80, 0);
+        let tlv = Der::new(&data).expect(TAG_OCTET_STRING).unwrap();
+        assert_eq!(0x80, tlv.value.len());
+
+        // 負の数と、冗長な符号の 0。
+        let data = hex("0201ff02020001");
+        let mut der = Der::new(&data);
+        assert!(der.read().unwrap().positive_integer().is_err());
+        assert!(der.read().unwrap().positive_integer().is_err());
+
+        // 不定長、冗長な長い形式、足りない値。
+        assert!(Der::new(&hex("30800000")).read().is_err());
+        assert!(Der::new(&hex("04810100")).read().is_err());
+        assert!(Der::new(&hex("040300")).read().is_err());
+    }
+}
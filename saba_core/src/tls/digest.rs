@@ -0,0 +1,38 @@
+//! 署名アルゴリズムが指定するハッシュ関数の選択
+//! https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.3
+
+use crate::tls::sha256::sha256;
+use crate::tls::sha256::HASH_LEN;
+use crate::tls::sha512::sha384;
+use crate::tls::sha512::sha512;
+use crate::tls::sha512::SHA384_LEN;
+use crate::tls::sha512::SHA512_LEN;
+use alloc::vec::Vec;
+
+/// 署名の検証に使うハッシュ関数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// data のハッシュ値を返す。
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => sha256(data).to_vec(),
+            HashAlgorithm::Sha384 => sha384(data).to_vec(),
+            HashAlgorithm::Sha512 => sha512(data).to_vec(),
+        }
+    }
+
+    /// ハッシュ値のバイト数を返す。
+    pub fn output_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => HASH_LEN,
+            HashAlgorithm::Sha384 => SHA384_LEN,
+            HashAlgorithm::Sha512 => SHA512_LEN,
+        }
+    }
+}
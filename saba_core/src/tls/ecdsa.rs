@@ -0,0 +1,366 @@
+//! ECDSA 署名の検証
+//! 曲線は secp256r1 (P-256) と secp384r1 (P-384) に対応する。点はヤコビアン座標で表し、有限体の演算にはモンゴメリ表現を使う。
+//! https://datatracker.ietf.org/doc/html/rfc6979#section-2.4
+//! https://www.secg.org/sec1-v2.pdf
+//! https://hyperelliptic.org/EFD/g1p/auto-shortw-jacobian-3.html
+
+use crate::tls::bigint::BigUint;
+use crate::tls::bigint::Modulus;
+use crate::tls::der::Der;
+use crate::tls::der::TAG_SEQUENCE;
+use crate::tls::digest::HashAlgorithm;
+use alloc::vec::Vec;
+
+/// 楕円曲線の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    P256,
+    P384,
+}
+
+impl Curve {
+    /// 座標のバイト数
+    pub fn coordinate_len(&self) -> usize {
+        match self {
+            Curve::P256 => 32,
+            Curve::P384 => 48,
+        }
+    }
+
+    /// 曲線のパラメータ (p, b, n, Gx, Gy) を返す。a はどちらの曲線も -3 である。
+    /// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-186.pdf
+    fn parameters(&self) -> [&'static str; 5] {
+        match self {
+            Curve::P256 => [
+                "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+                "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b",
+                "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551",
+                "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+                "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+            ],
+            Curve::P384 => [
+                "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe\
+                 ffffffff0000000000000000ffffffff",
+                "b3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875a\
+                 c656398d8a2ed19d2a85c8edd3ec2aef",
+                "ffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf\
+                 581a0db248b0a77aecec196accc52973",
+                "aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a38\
+                 5502f25dbf55296c3a545e3872760ab7",
+                "3617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c0\
+                 0a60b1ce1d7e819d7a431d7c90ea0e5f",
+            ],
+        }
+    }
+}
+
+/// 16 進数の文字列を整数にする。
+fn parse_hex(s: &str) -> BigUint {
+    let bytes: Vec<u8> = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("valid hex"))
+        .collect();
+    BigUint::from_be_bytes(&bytes)
+}
+
+/// 曲線上の点のヤコビアン座標 (X, Y, Z)。アフィン座標は (X / Z^2, Y / Z^3) で、Z = 0 は無限遠点を表す。
+/// 各座標は有限体のモンゴメリ表現である。
+#[derive(Debug, Clone)]
+struct Point {
+    x: Vec<u64>,
+    y: Vec<u64>,
+    z: Vec<u64>,
+}
+
+/// 曲線の有限体と、点の演算
+struct CurveField {
+    p: Modulus,
+    b: Vec<u64>,
+}
+
+impl CurveField {
+    fn infinity(&self) -> Point {
+        Point {
+            x: self.p.one(),
+            y: self.p.one(),
+            z: self.p.zero(),
+        }
+    }
+
+    fn is_infinity(&self, point: &Point) -> bool {
+        self.p.is_zero(&point.z)
+    }
+
+    /// アフィン座標の点が曲線 y^2 = x^3 - 3x + b 上にあるかどうか。
+    fn is_on_curve(&self, x: &[u64], y: &[u64]) -> bool {
+        let p = &self.p;
+        let x3 = p.mul(&p.mul(x, x), x);
+        let three_x = p.add(&p.add(x, x), x);
+        let rhs = p.add(&p.sub(&x3, &three_x), &self.b);
+        p.mul(y, y) == rhs
+    }
+
+    /// 点の2倍 (dbl-2001-b)
+    fn double(&self, point: &Point) -> Point {
+        if self.is_infinity(point) {
+            return point.clone();
+        }
+        let p = &self.p;
+        let delta = p.mul(&point.z, &point.z);
+        let gamma = p.mul(&point.y, &point.y);
+        let beta = p.mul(&point.x, &gamma);
+        let t = p.mul(&p.sub(&point.x, &delta), &p.add(&point.x, &delta));
+        let alpha = p.add(&p.add(&t, &t), &t);
+        let beta2 = p.add(&beta, &beta);
+        let beta4 = p.add(&beta2, &beta2);
+        let beta8 = p.add(&beta4, &beta4);
+        let x = p.sub(&p.mul(&alpha, &alpha), &beta8);
+        let y_plus_z = p.add(&point.y, &point.z);
+        let z = p.sub(&p.sub(&p.mul(&y_plus_z, &y_plus_z), &gamma), &delta);
+        let gamma_sq = p.mul(&gamma, &gamma);
+        let gamma_sq2 = p.add(&gamma_sq, &gamma_sq);
+        let gamma_sq4 = p.add(&gamma_sq2, &gamma_sq2);
+        let gamma_sq8 = p.add(&gamma_sq4, &gamma_sq4);
+        let y = p.sub(&p.mul(&alpha, &p.sub(&beta4, &x)), &gamma_sq8);
+        Point { x, y, z }
+    }
+
+    /// 2点の和 (add-2007-bl)
+    fn add(&self, a: &Point, b: &Point) -> Point {
+        if self.is_infinity(a) {
+            return b.clone();
+        }
+        if self.is_infinity(b) {
+            return a.clone();
+        }
+        let p = &self.p;
+        let z1z1 = p.mul(&a.z, &a.z);
+        let z2z2 = p.mul(&b.z, &b.z);
+        let u1 = p.mul(&a.x, &z2z2);
+        let u2 = p.mul(&b.x, &z1z1);
+        let s1 = p.mul(&p.mul(&a.y, &b.z), &z2z2);
+        let s2 = p.mul(&p.mul(&b.y, &a.z), &z1z1);
+        let h = p.sub(&u2, &u1);
+        let r = p.sub(&s2, &s1);
+        if p.is_zero(&h) {
+            // X 座標が同じ点は、同じ点か、互いに逆の点である。
+            return if p.is_zero(&r) {
+                self.double(a)
+            } else {
+                self.infinity()
+            };
+        }
+        let h2 = p.add(&h, &h);
+        let i = p.mul(&h2, &h2);
+        let j = p.mul(&h, &i);
+        let r = p.add(&r, &r);
+        let v = p.mul(&u1, &i);
+        let x = p.sub(&p.sub(&p.mul(&r, &r), &j), &p.add(&v, &v));
+        let s1j = p.mul(&s1, &j);
+        let y = p.sub(&p.mul(&r, &p.sub(&v, &x)), &p.add(&s1j, &s1j));
+        let z1_plus_z2 = p.add(&a.z, &b.z);
+        let z = p.mul(
+            &p.sub(&p.sub(&p.mul(&z1_plus_z2, &z1_plus_z2), &z1z1), &z2z2),
+            &h,
+        );
+        Point { x, y, z }
+    }
+
+    /// u1 * a + u2 * b を返す (Shamir の方法)。
+    fn double_scalar_mul(&self, u1: &BigUint, a: &Point, u2: &BigUint, b: &Point) -> Point {
+        let ab = self.add(a, b);
+        let mut result = self.infinity();
+        for i in (0..u1.bit_len().max(u2.bit_len())).rev() {
+            result = self.double(&result);
+            match (u1.bit(i), u2.bit(i)) {
+                (true, true) => result = self.add(&result, &ab),
+                (true, false) => result = self.add(&result, a),
+                (false, true) => result = self.add(&result, b),
+                (false, false) => {}
+            }
+        }
+        result
+    }
+
+    /// アフィン座標の X 座標を整数で返す。無限遠点の場合は None を返す。
+    fn affine_x(&self, point: &Point) -> Option<BigUint> {
+        if self.is_infinity(point) {
+            return None;
+        }
+        let z_inv = self.p.invert(&point.z);
+        let x = self.p.mul(&point.x, &self.p.mul(&z_inv, &z_inv));
+        Some(self.p.from_montgomery(&x))
+    }
+}
+
+/// ECDSA の公開鍵
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcPublicKey {
+    curve: Curve,
+    x: BigUint,
+    y: BigUint,
+}
+
+impl EcPublicKey {
+    /// 非圧縮形式 (0x04 || X || Y) の公開鍵を読み取る。点が曲線上にない場合は None を返す。
+    /// https://www.secg.org/sec1-v2.pdf (2.3.4)
+    pub fn from_sec1(curve: Curve, bytes: &[u8]) -> Option<Self> {
+        let len = curve.coordinate_len();
+        if bytes.len() != 1 + 2 * len || bytes[0] != 0x04 {
+            return None;
+        }
+        let key = Self {
+            curve,
+            x: BigUint::from_be_bytes(&bytes[1..1 + len]),
+            y: BigUint::from_be_bytes(&bytes[1 + len..]),
+        };
+        let (field, _) = key.field();
+        let x = field.p.to_montgomery(&key.x)?;
+        let y = field.p.to_montgomery(&key.y)?;
+        if !field.is_on_curve(&x, &y) {
+            return None;
+        }
+        Some(key)
+    }
+
+    pub fn curve(&self) -> Curve {
+        self.curve
+    }
+
+    /// 曲線の有限体と、位数 n を返す。
+    fn field(&self) -> (CurveField, BigUint) {
+        let [p, b, n, _, _] = self.curve.parameters();
+        let p = Modulus::new(&parse_hex(p)).expect("p is an odd prime");
+        let b = p.to_montgomery(&parse_hex(b)).expect("b is less than p");
+        (CurveField { p, b }, parse_hex(n))
+    }
+
+    /// DER でエンコードされた署名 (SEQUENCE { r INTEGER, s INTEGER }) を検証する。
+    /// https://datatracker.ietf.org/doc/html/rfc3279#section-2.2.3
+    pub fn verify(&self, hash: HashAlgorithm, message: &[u8], signature: &[u8]) -> bool {
+        let (r, s) = match parse_signature(signature) {
+            Some(rs) => rs,
+            None => return false,
+        };
+        let (field, n) = self.field();
+        let [_, _, _, gx, gy] = self.curve.parameters();
+        let order = Modulus::new(&n).expect("n is an odd prime");
+
+        // r と s は 1 以上 n 未満でなければならない。
+        let (r_mont, s_mont) = match (order.to_montgomery(&r), order.to_montgomery(&s)) {
+            (Some(r_mont), Some(s_mont)) if !r.is_zero() && !s.is_zero() => (r_mont, s_mont),
+            _ => return false,
+        };
+
+        // ハッシュ値の先頭の n のビット数分を e とする。e は 2n 未満なので、n 以上なら n を引く。
+        let digest = hash.digest(message);
+        let mut e = BigUint::from_be_bytes(&digest);
+        if digest.len() * 8 > n.bit_len() {
+            e = e.shr(digest.len() * 8 - n.bit_len());
+        }
+        if let Some(reduced) = e.checked_sub(&n) {
+            e = reduced;
+        }
+        let e_mont = order.to_montgomery(&e).expect("e is reduced modulo n");
+
+        // u1 = e / s、u2 = r / s とし、u1 * G + u2 * Q の X 座標を n で割った余りが r と等しいかを確認する。
+        let w = order.invert(&s_mont);
+        let u1 = order.from_montgomery(&order.mul(&e_mont, &w));
+        let u2 = order.from_montgomery(&order.mul(&r_mont, &w));
+
+        let to_point = |x: &BigUint, y: &BigUint| Point {
+            x: field.p.to_montgomery(x).expect("coordinate is less than p"),
+            y: field.p.to_montgomery(y).expect("coordinate is less than p"),
+            z: field.p.one(),
+        };
+        let g = to_point(&parse_hex(gx), &parse_hex(gy));
+        let q = to_point(&self.x, &self.y);
+        let point = field.double_scalar_mul(&u1, &g, &u2, &q);
+        let mut x = match field.affine_x(&point) {
+            Some(x) => x,
+            None => return false,
+        };
+        // p は 2n 未満なので、X 座標は n を高々1回引けば n 未満になる。
+        if let Some(reduced) = x.checked_sub(&n) {
+            x = reduced;
+        }
+        x == r
+    }
+}
+
+/// DER でエンコードされた署名から r と s を読み取る。
+fn parse_signature(signature: &[u8]) -> Option<(BigUint, BigUint)> {
+    let mut der = Der::new(signature);
+    let sequence = der.expect(TAG_SEQUENCE).ok()?;
+    der.finish().ok()?;
+    let mut contents = sequence.contents();
+    let r = contents.read().ok()?.positive_integer().ok()?;
+    let s = contents.read().ok()?.positive_integer().ok()?;
+    contents.finish().ok()?;
+    Some((BigUint::from_be_bytes(r), BigUint::from_be_bytes(s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+
+    #[test]
+    /// openssl dgst -sha256 -sign で作成した P-256 の署名の検証。
+    fn test_verify_p256() {
+        let key = EcPublicKey::from_sec1(
+            Curve::P256,
+            &hex(
+                "04bd502e4d34a6c1067b73fc1b7182431cac6bb89a7b1bfcccc5aa808387bb9bc0\
+                  bfed9af761ce7c424ed36cfae9a4a06b8d7d774b71d7b964719a33c8268fe99f",
+            ),
+        )
+        .unwrap();
+        let signature = hex(
+            "3046022100dba776794e9786aa4450fc07730abb1ff7b8ca95cd4cdb2c2305c3947bfcc4af\
+             022100821eba33686007ba82e40d779bd154d83b23a2569138b3828dc9d6dc5164a3d2",
+        );
+        assert!(key.verify(HashAlgorithm::Sha256, b"saba", &signature));
+        assert!(!key.verify(HashAlgorithm::Sha256, b"sabb", &signature));
+        assert!(!key.verify(HashAlgorithm::Sha384, b"saba", &signature));
+
+        let mut tampered = signature.clone();
+        tampered[10] ^= 1;
+        assert!(!key.verify(HashAlgorithm::Sha256, b"saba", &tampered));
+        assert!(!key.verify(HashAlgorithm::Sha256, b"saba", &signature[..70]));
+    }
+
+    #[test]
+    /// openssl dgst -sha384 -sign で作成した P-384 の署名の検証。
+    fn test_verify_p384() {
+        let key = EcPublicKey::from_sec1(
+            Curve::P384,
+            &hex(
+                "047dbf8268f71ca4f76ba5aee8ae420a81b26ad5873270864c08b0725a7a8b882c\
+                  2a88fd54d5962382ce263d54059bd791eb4c86db44467a4228c4e24d2595dde36d\
+                  a3817c484c4d2adcbb79a22cd2783c5a2db0387f6a8aea812e0fea21f8d120",
+            ),
+        )
+        .unwrap();
+        let signature = hex(
+            "306402302533a5b12ba6a83642258bd8d5776cc42a74e18359df24b22e612d067c2b1f95\
+             5794344cf60505f1583f706d163343d102302043ba26bac8e7d390ba5bc5d0f3cbb094a2\
+             ae4bf757ee54923e5c869f03de5bb0aa4adf36cd7169a11a5f2362847707",
+        );
+        assert!(key.verify(HashAlgorithm::Sha384, b"saba", &signature));
+        assert!(!key.verify(HashAlgorithm::Sha384, b"sabb", &signature));
+    }
+
+    #[test]
+    /// 曲線上にない点と、圧縮形式の公開鍵は受け付けない。
+    fn test_invalid_public_key() {
+        let mut bytes = hex(
+            "04bd502e4d34a6c1067b73fc1b7182431cac6bb89a7b1bfcccc5aa808387bb9bc0\
+             bfed9af761ce7c424ed36cfae9a4a06b8d7d774b71d7b964719a33c8268fe99f",
+        );
+        bytes[64] ^= 1;
+        assert_eq!(None, EcPublicKey::from_sec1(Curve::P256, &bytes));
+        assert_eq!(None, EcPublicKey::from_sec1(Curve::P256, &bytes[..33]));
+        assert_eq!(None, EcPublicKey::from_sec1(Curve::P384, &bytes));
+    }
+}
@@ -0,0 +1,97 @@
+//! HMAC-SHA256 と、それを使った鍵導出関数 HKDF
+//! https://datatracker.ietf.org/doc/html/rfc2104
+//! https://datatracker.ietf.org/doc/html/rfc5869
+
+use crate::tls::sha256::Sha256;
+use crate::tls::sha256::BLOCK_LEN;
+use crate::tls::sha256::HASH_LEN;
+use alloc::vec::Vec;
+
+/// key を鍵とした data の HMAC-SHA256 を返す。
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; HASH_LEN] {
+    // ブロックより長い鍵は、ハッシュ値を鍵として使う。
+    let mut block = [0u8; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        let mut hasher = Sha256::new();
+        hasher.update(key);
+        block[..HASH_LEN].copy_from_slice(&hasher.finish());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(&block.map(|b| b ^ 0x36));
+    inner.update(data);
+
+    let mut outer = Sha256::new();
+    outer.update(&block.map(|b| b ^ 0x5c));
+    outer.update(&inner.finish());
+    outer.finish()
+}
+
+/// 入力の鍵素材 ikm から、salt を使って疑似乱数の鍵 (PRK) を取り出す。
+/// https://datatracker.ietf.org/doc/html/rfc5869#section-2.2
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; HASH_LEN] {
+    hmac_sha256(salt, ikm)
+}
+
+/// 疑似乱数の鍵 prk と info から、length バイトの鍵を導出する。
+/// https://datatracker.ietf.org/doc/html/rfc5869#section-2.3
+pub fn hkdf_expand(prk: &[u8], info: &[u8], length: usize) -> Vec<u8> {
+    let mut okm = Vec::new();
+    let mut previous: Vec<u8> = Vec::new();
+    let mut counter = 1u8;
+    while okm.len() < length {
+        let mut data = previous.clone();
+        data.extend_from_slice(info);
+        data.push(counter);
+        previous = hmac_sha256(prk, &data).to_vec();
+        okm.extend_from_slice(&previous);
+        counter += 1;
+    }
+    okm.truncate(length);
+    okm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+
+    #[test]
+    /// RFC 4231 のテストケース 2 と 6 (ブロックより長い鍵) のテスト。
+    fn test_hmac_sha256() {
+        assert_eq!(
+            hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"),
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?")
+        );
+        assert_eq!(
+            hex("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"),
+            hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )
+        );
+    }
+
+    #[test]
+    /// RFC 5869 のテストケース 1 のテスト。
+    fn test_hkdf() {
+        let ikm = [0x0b; 22];
+        let salt = hex("000102030405060708090a0b0c");
+        let info = hex("f0f1f2f3f4f5f6f7f8f9");
+
+        let prk = hkdf_extract(&salt, &ikm);
+        assert_eq!(
+            hex("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"),
+            prk
+        );
+        assert_eq!(
+            hex(concat!(
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf",
+                "34007208d5b887185865"
+            )),
+            hkdf_expand(&prk, &info, 42)
+        );
+    }
+}
@@ -0,0 +1,31 @@
+//! TLS 1.3 のクライアントと、それに必要な暗号の実装
+//! 暗号スイートは TLS_CHACHA20_POLY1305_SHA256、鍵交換は X25519 のみに対応する。
+//! サーバ証明書の署名は RSA (PKCS#1 v1.5、PSS) と ECDSA (P-256、P-384) で検証する。
+//! https://datatracker.ietf.org/doc/html/rfc8446
+
+pub mod bigint;
+pub mod chacha20poly1305;
+pub mod client;
+pub mod der;
+pub mod digest;
+pub mod ecdsa;
+pub mod hkdf;
+pub mod roots;
+pub mod rsa;
+pub mod sha256;
+pub mod sha512;
+pub mod x509;
+pub mod x25519;
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    /// 16 進数の文字列をバイト列にする。
+    pub fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
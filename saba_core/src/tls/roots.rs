@@ -0,0 +1,61 @@
+//! 信頼するルート証明書
+//! 主要な認証局のルート証明書を、ディストリビューションの CA 証明書の集合 (/etc/ssl/certs) から抜き出して埋め込む。
+//! https://wiki.mozilla.org/CA/Included_Certificates
+
+use crate::tls::x509::pem_to_der;
+use alloc::vec::Vec;
+
+const ROOT_CERTIFICATES: [&str; 27] = [
+    include_str!("roots/amazon_root_ca_1.pem"),
+    include_str!("roots/amazon_root_ca_2.pem"),
+    include_str!("roots/amazon_root_ca_3.pem"),
+    include_str!("roots/amazon_root_ca_4.pem"),
+    include_str!("roots/digicert_global_root_ca.pem"),
+    include_str!("roots/digicert_global_root_g2.pem"),
+    include_str!("roots/digicert_global_root_g3.pem"),
+    include_str!("roots/entrust_root_certification_authority_g2.pem"),
+    include_str!("roots/globalsign_ecc_root_ca_r4.pem"),
+    include_str!("roots/globalsign_root_ca.pem"),
+    include_str!("roots/globalsign_root_ca_r3.pem"),
+    include_str!("roots/go_daddy_root_certificate_authority_g2.pem"),
+    include_str!("roots/gts_root_r1.pem"),
+    include_str!("roots/gts_root_r2.pem"),
+    include_str!("roots/gts_root_r3.pem"),
+    include_str!("roots/gts_root_r4.pem"),
+    include_str!("roots/isrg_root_x1.pem"),
+    include_str!("roots/isrg_root_x2.pem"),
+    include_str!("roots/microsoft_ecc_root_certificate_authority_2017.pem"),
+    include_str!("roots/microsoft_rsa_root_certificate_authority_2017.pem"),
+    include_str!("roots/sectigo_public_server_authentication_root_e46.pem"),
+    include_str!("roots/sectigo_public_server_authentication_root_r46.pem"),
+    include_str!("roots/ssl_com_root_certification_authority_ecc.pem"),
+    include_str!("roots/ssl_com_root_certification_authority_rsa.pem"),
+    include_str!("roots/starfield_root_certificate_authority_g2.pem"),
+    include_str!("roots/usertrust_ecc_certification_authority.pem"),
+    include_str!("roots/usertrust_rsa_certification_authority.pem"),
+];
+
+/// 組み込みのルート証明書を DER のバイト列で返す。
+pub fn trust_anchors() -> Vec<Vec<u8>> {
+    ROOT_CERTIFICATES
+        .iter()
+        .filter_map(|pem| pem_to_der(pem))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::x509::Certificate;
+
+    #[test]
+    /// 組み込みのルート証明書をすべて読み取れ、公開鍵に対応していることのテスト。
+    fn test_trust_anchors() {
+        let anchors = trust_anchors();
+        assert_eq!(ROOT_CERTIFICATES.len(), anchors.len());
+        for anchor in &anchors {
+            let certificate = Certificate::parse(anchor).unwrap();
+            assert!(certificate.public_key().is_ok());
+        }
+    }
+}
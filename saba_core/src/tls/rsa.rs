@@ -0,0 +1,231 @@
+//! RSA 署名の検証
+//! 証明書の署名に使われる RSASSA-PKCS1-v1_5 と、CertificateVerify に使われる RSASSA-PSS に対応する。
+//! https://datatracker.ietf.org/doc/html/rfc8017#section-8
+
+use crate::tls::bigint::BigUint;
+use crate::tls::bigint::Modulus;
+use crate::tls::digest::HashAlgorithm;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 受け付ける法の最小のビット数
+const MIN_MODULUS_BITS: usize = 2048;
+
+/// ハッシュ関数ごとの DigestInfo の DER エンコードのうち、ハッシュ値より前の部分
+/// https://datatracker.ietf.org/doc/html/rfc8017#section-9.2
+fn digest_info_prefix(hash: HashAlgorithm) -> &'static [u8] {
+    match hash {
+        HashAlgorithm::Sha256 => &[
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x01, 0x05, 0x00, 0x04, 0x20,
+        ],
+        HashAlgorithm::Sha384 => &[
+            0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x02, 0x05, 0x00, 0x04, 0x30,
+        ],
+        HashAlgorithm::Sha512 => &[
+            0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x03, 0x05, 0x00, 0x04, 0x40,
+        ],
+    }
+}
+
+/// RSA の公開鍵
+#[derive(Debug, Clone)]
+pub struct RsaPublicKey {
+    modulus: Modulus,
+    exponent: BigUint,
+    // 法のバイト数
+    length: usize,
+}
+
+impl RsaPublicKey {
+    /// 法と公開指数のビッグエンディアンのバイト列から公開鍵を作成する。
+    /// 法が 2048 ビット未満の場合や、公開指数が 1 以下または偶数の場合は None を返す。
+    pub fn new(modulus: &[u8], exponent: &[u8]) -> Option<Self> {
+        let n = BigUint::from_be_bytes(modulus);
+        let e = BigUint::from_be_bytes(exponent);
+        if n.bit_len() < MIN_MODULUS_BITS || e.bit_len() < 2 || !e.bit(0) {
+            return None;
+        }
+        Some(Self {
+            length: n.bit_len().div_ceil(8),
+            modulus: Modulus::new(&n)?,
+            exponent: e,
+        })
+    }
+
+    /// 署名 s から s^e mod n を計算し、法と同じ長さのバイト列で返す (RSAVP1)。
+    /// 署名の長さが法と異なる場合や、署名が法以上の場合は None を返す。
+    fn open(&self, signature: &[u8]) -> Option<Vec<u8>> {
+        if signature.len() != self.length {
+            return None;
+        }
+        let s = self
+            .modulus
+            .to_montgomery(&BigUint::from_be_bytes(signature))?;
+        let m = self.modulus.pow(&s, &self.exponent);
+        self.modulus.from_montgomery(&m).to_be_bytes(self.length)
+    }
+
+    /// RSASSA-PKCS1-v1_5 の署名を検証する。
+    /// https://datatracker.ietf.org/doc/html/rfc8017#section-8.2.2
+    pub fn verify_pkcs1_v15(&self, hash: HashAlgorithm, message: &[u8], signature: &[u8]) -> bool {
+        let encoded = match self.open(signature) {
+            Some(encoded) => encoded,
+            None => return false,
+        };
+
+        // EM = 0x00 || 0x01 || PS (0xff の列) || 0x00 || DigestInfo
+        let prefix = digest_info_prefix(hash);
+        let t_len = prefix.len() + hash.output_len();
+        if self.length < t_len + 11 {
+            return false;
+        }
+        let mut expected = vec![0xff; self.length];
+        expected[0] = 0x00;
+        expected[1] = 0x01;
+        expected[self.length - t_len - 1] = 0x00;
+        expected[self.length - t_len..self.length - hash.output_len()].copy_from_slice(prefix);
+        expected[self.length - hash.output_len()..].copy_from_slice(&hash.digest(message));
+        encoded == expected
+    }
+
+    /// MGF1 のソルトの長さをハッシュ値の長さとした RSASSA-PSS の署名を検証する。
+    /// TLS 1.3 はソルトの長さがハッシュ値の長さと等しいことを要求する。
+    /// https://datatracker.ietf.org/doc/html/rfc8017#section-8.1.2
+    /// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.3
+    pub fn verify_pss(&self, hash: HashAlgorithm, message: &[u8], signature: &[u8]) -> bool {
+        let encoded = match self.open(signature) {
+            Some(encoded) => encoded,
+            None => return false,
+        };
+
+        // EM は emBits = modBits - 1 ビットで、emLen = ceil(emBits / 8) バイト。
+        let em_bits = self.modulus.value().bit_len() - 1;
+        let em_len = em_bits.div_ceil(8);
+        let em = &encoded[self.length - em_len..];
+        if self.length != em_len && encoded[0] != 0 {
+            return false;
+        }
+
+        let h_len = hash.output_len();
+        let s_len = h_len;
+        if em_len < h_len + s_len + 2 || em[em_len - 1] != 0xbc {
+            return false;
+        }
+        let (masked_db, rest) = em.split_at(em_len - h_len - 1);
+        let h = &rest[..h_len];
+
+        // 使われない上位のビットは 0 でなければならない。
+        let unused_bits = 8 * em_len - em_bits;
+        if masked_db[0] & !(0xff >> unused_bits) != 0 {
+            return false;
+        }
+
+        let mask = mgf1(hash, h, masked_db.len());
+        let mut db: Vec<u8> = masked_db.iter().zip(&mask).map(|(a, b)| a ^ b).collect();
+        db[0] &= 0xff >> unused_bits;
+
+        // DB = PS (0x00 の列) || 0x01 || salt
+        let ps_len = em_len - h_len - s_len - 2;
+        if db[..ps_len].iter().any(|b| *b != 0) || db[ps_len] != 0x01 {
+            return false;
+        }
+        let salt = &db[ps_len + 1..];
+
+        // M' = 0x00 * 8 || mHash || salt
+        let mut m_prime = vec![0u8; 8];
+        m_prime.extend_from_slice(&hash.digest(message));
+        m_prime.extend_from_slice(salt);
+        hash.digest(&m_prime) == h
+    }
+}
+
+/// MGF1 でマスクを生成する。
+/// https://datatracker.ietf.org/doc/html/rfc8017#appendix-B.2.1
+fn mgf1(hash: HashAlgorithm, seed: &[u8], length: usize) -> Vec<u8> {
+    let mut mask = Vec::new();
+    let mut counter: u32 = 0;
+    while mask.len() < length {
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&counter.to_be_bytes());
+        mask.extend_from_slice(&hash.digest(&input));
+        counter += 1;
+    }
+    mask.truncate(length);
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+
+    // openssl genrsa で生成した 2048 ビットの鍵の法
+    const MODULUS: &str = "b5362f62eadc04ac4d0030b03dbb32c6a9be2418cf6106a61f5f9cba86a8de19\
+        8e35cb7cc503245b7d7c58f89cdd055072f66db79f640abbe5ca7a44fa93aa2c\
+        8a380c4cc65fcae0c46eb4c56703fe3669cb77db756c5e48882efa972abc6d9e\
+        d156e108c72a0e8333c194b83263d2afca3ae42621cfb3906b719b6eafedb460\
+        7d9f2956ad342ba1111e9e37cbcf9c5a39835a1d94b94f8320885ee5623d8bae\
+        9ad172cd948849ec19c5829fad6ba12faae68a9dcd743c93c32178c098ac5c19\
+        42052d27ac8fd154267c687dff220e1ac9c94cd18af3c71b0584bd2670b43881\
+        36c71290fa9f54a837437b73ad55d701799a8eec994638580d10e9e60c4585b1";
+
+    // "saba" の RSASSA-PKCS1-v1_5 (SHA-256) の署名
+    const PKCS1_SIGNATURE: &str =
+        "614c7458e1abfab7c7db914413109a827cbdd398e7bd8a0a8c4e9371aadefb73\
+        f1ee98c89a0dd5a2e16eda82da36e90a9765921cab2a3b7bbd05d93b8be5b350\
+        cc83eef11290c82e1d818365ac7d3360085b7765ea4b9564ba27cbf6c6afff73\
+        fdd666566d69e12cd3c1295d0bf4e0a7f348092ad253e2a7990ecefcee0cc95d\
+        cf0a38d51fdf913408401420a49bf7cc11c2b581127d8e14efec18b598c5deb6\
+        31823bf42084d700398000d739298748bc73e3e5d0dbcd0bb376df3aed68b692\
+        0c2bc8fbfee18a1ba4e478472c448b4f74c3e268cfa6d5e6deedd3dd9a046c7f\
+        00da2be466cfdc78b59b0eae81f7772e1f44d9bd8dd2c12d556090d5d8a0fe89";
+
+    // "saba" の RSASSA-PSS (SHA-256、ソルトの長さ 32) の署名
+    const PSS_SIGNATURE: &str = "0a5f091e40a83fe2eab73f089804ed496c748d900beb561db3c7e655e3ce897e\
+        cca663303dd6bee6042773750149d733f5761d587164948b4da994127c3e33bf\
+        c3d60dc440798d1f5f73bc4beba6a814bf1e6d2d8d088682ff252e4dbfa94def\
+        ea114f794e95adc0e5e8239e5a5f31ff01d525fa380c4e3ef85897d8d13edad1\
+        e83b266892a2fbc4af0f9957c263ae43f026639bb71c628a49b435212712b970\
+        920e7ff7e5eeee35ba92478b0e3af11af9439974d4a9a143d48481d3641a3f74\
+        fb78ae9b7325a0511f96310f715d6c752aa0a54502258c3b06a6f1d89e37aed4\
+        db3cd1670957d35973afd404159189f0074830859f8b7b06e9d12d78c6b16de3";
+
+    fn key() -> RsaPublicKey {
+        RsaPublicKey::new(&hex(MODULUS), &hex("010001")).unwrap()
+    }
+
+    #[test]
+    /// openssl dgst -sha256 -sign で作成した署名の検証。
+    fn test_verify_pkcs1_v15() {
+        let signature = hex(PKCS1_SIGNATURE);
+        assert!(key().verify_pkcs1_v15(HashAlgorithm::Sha256, b"saba", &signature));
+        assert!(!key().verify_pkcs1_v15(HashAlgorithm::Sha256, b"sabb", &signature));
+        assert!(!key().verify_pkcs1_v15(HashAlgorithm::Sha384, b"saba", &signature));
+        assert!(!key().verify_pss(HashAlgorithm::Sha256, b"saba", &signature));
+        assert!(!key().verify_pkcs1_v15(HashAlgorithm::Sha256, b"saba", &signature[1..]));
+    }
+
+    #[test]
+    /// openssl dgst -sha256 -sigopt rsa_padding_mode:pss -sigopt rsa_pss_saltlen:32 で作成した署名の検証。
+    fn test_verify_pss() {
+        let signature = hex(PSS_SIGNATURE);
+        assert!(key().verify_pss(HashAlgorithm::Sha256, b"saba", &signature));
+        assert!(!key().verify_pss(HashAlgorithm::Sha256, b"sabb", &signature));
+        assert!(!key().verify_pkcs1_v15(HashAlgorithm::Sha256, b"saba", &signature));
+
+        let mut tampered = signature.clone();
+        tampered[100] ^= 1;
+        assert!(!key().verify_pss(HashAlgorithm::Sha256, b"saba", &tampered));
+    }
+
+    #[test]
+    /// 短い法と、不正な公開指数の鍵は作成できない。
+    fn test_invalid_key() {
+        assert!(RsaPublicKey::new(&hex(&MODULUS[..256]), &hex("010001")).is_none());
+        assert!(RsaPublicKey::new(&hex(MODULUS), &hex("01")).is_none());
+        assert!(RsaPublicKey::new(&hex(MODULUS), &hex("010000")).is_none());
+    }
+}
@@ -0,0 +1,216 @@
+//! 楕円曲線 Curve25519 上の Diffie-Hellman 鍵交換 X25519
+//! TLS 1.3 の key_share 拡張で、サーバと共有の秘密を作るために使う。
+//! 体の要素は 16 ビットずつ 16 個の limb に分けて表し、秘密の値によって分岐しないように計算する。
+//! https://datatracker.ietf.org/doc/html/rfc7748
+
+/// 鍵と共有の秘密のバイト数
+pub const KEY_LEN: usize = 32;
+
+/// 2^255 - 19 を法とする体の要素
+type Fe = [i64; 16];
+
+/// 121665 = (486662 - 2) / 4
+const A24: Fe = [0xdb41, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// 各 limb を 16 ビットに収め、あふれた分を次の limb に繰り上げる。
+/// 最上位の limb のあふれは、2^256 = 38 (mod 2^255 - 19) を使って最下位に戻す。
+fn carry(o: &mut Fe) {
+    for i in 0..16 {
+        o[i] += 1 << 16;
+        let c = o[i] >> 16;
+        if i < 15 {
+            o[i + 1] += c - 1;
+        } else {
+            o[0] += 38 * (c - 1);
+        }
+        o[i] -= c << 16;
+    }
+}
+
+/// b が 1 の場合に p と q を入れ替える。b によって分岐しない。
+fn swap(p: &mut Fe, q: &mut Fe, b: i64) {
+    let mask = !(b - 1);
+    for i in 0..16 {
+        let t = mask & (p[i] ^ q[i]);
+        p[i] ^= t;
+        q[i] ^= t;
+    }
+}
+
+fn add(a: &Fe, b: &Fe) -> Fe {
+    let mut o = [0; 16];
+    for i in 0..16 {
+        o[i] = a[i] + b[i];
+    }
+    o
+}
+
+fn sub(a: &Fe, b: &Fe) -> Fe {
+    let mut o = [0; 16];
+    for i in 0..16 {
+        o[i] = a[i] - b[i];
+    }
+    o
+}
+
+fn mul(a: &Fe, b: &Fe) -> Fe {
+    let mut t = [0i64; 31];
+    for i in 0..16 {
+        for j in 0..16 {
+            t[i + j] += a[i] * b[j];
+        }
+    }
+    for i in 0..15 {
+        t[i] += 38 * t[i + 16];
+    }
+    let mut o = [0; 16];
+    o.copy_from_slice(&t[..16]);
+    carry(&mut o);
+    carry(&mut o);
+    o
+}
+
+fn square(a: &Fe) -> Fe {
+    mul(a, a)
+}
+
+/// フェルマーの小定理により、a^(p - 2) として逆元を求める。
+fn invert(a: &Fe) -> Fe {
+    let mut c = *a;
+    for i in (0..=253).rev() {
+        c = square(&c);
+        if i != 2 && i != 4 {
+            c = mul(&c, a);
+        }
+    }
+    c
+}
+
+/// リトルエンディアンのバイト列を体の要素にする。最上位のビットは無視する。
+fn unpack(n: &[u8; KEY_LEN]) -> Fe {
+    let mut o = [0; 16];
+    for i in 0..16 {
+        o[i] = n[2 * i] as i64 + ((n[2 * i + 1] as i64) << 8);
+    }
+    o[15] &= 0x7fff;
+    o
+}
+
+/// 体の要素を 2^255 - 19 未満に正規化し、リトルエンディアンのバイト列にする。
+fn pack(n: &Fe) -> [u8; KEY_LEN] {
+    let mut t = *n;
+    carry(&mut t);
+    carry(&mut t);
+    carry(&mut t);
+    for _ in 0..2 {
+        let mut m = [0i64; 16];
+        m[0] = t[0] - 0xffed;
+        for i in 1..15 {
+            m[i] = t[i] - 0xffff - ((m[i - 1] >> 16) & 1);
+            m[i - 1] &= 0xffff;
+        }
+        m[15] = t[15] - 0x7fff - ((m[14] >> 16) & 1);
+        let b = (m[15] >> 16) & 1;
+        m[14] &= 0xffff;
+        swap(&mut t, &mut m, 1 - b);
+    }
+
+    let mut o = [0; KEY_LEN];
+    for i in 0..16 {
+        o[2 * i] = (t[i] & 0xff) as u8;
+        o[2 * i + 1] = (t[i] >> 8) as u8;
+    }
+    o
+}
+
+/// スカラー scalar と点の u 座標 point から、scalar 倍した点の u 座標を返す。
+/// モンゴメリラダーで計算する。
+/// https://datatracker.ietf.org/doc/html/rfc7748#section-5
+pub fn x25519(scalar: &[u8; KEY_LEN], point: &[u8; KEY_LEN]) -> [u8; KEY_LEN] {
+    let mut z = *scalar;
+    z[31] = (z[31] & 127) | 64;
+    z[0] &= 248;
+
+    let x = unpack(point);
+    let mut a: Fe = [0; 16];
+    let mut b: Fe = x;
+    let mut c: Fe = [0; 16];
+    let mut d: Fe = [0; 16];
+    a[0] = 1;
+    d[0] = 1;
+
+    for i in (0..=254).rev() {
+        let r = ((z[i >> 3] >> (i & 7)) & 1) as i64;
+        swap(&mut a, &mut b, r);
+        swap(&mut c, &mut d, r);
+        let e = add(&a, &c);
+        a = sub(&a, &c);
+        c = add(&b, &d);
+        b = sub(&b, &d);
+        d = square(&e);
+        let f = square(&a);
+        a = mul(&c, &a);
+        c = mul(&b, &e);
+        let e = add(&a, &c);
+        a = sub(&a, &c);
+        b = square(&a);
+        c = sub(&d, &f);
+        a = mul(&c, &A24);
+        a = add(&a, &d);
+        c = mul(&c, &a);
+        a = mul(&d, &f);
+        d = mul(&b, &x);
+        b = square(&e);
+        swap(&mut a, &mut b, r);
+        swap(&mut c, &mut d, r);
+    }
+
+    pack(&mul(&a, &invert(&c)))
+}
+
+/// 秘密鍵から、相手に送る公開鍵 (基点 u = 9 を秘密鍵倍した点) を返す。
+pub fn public_key(private_key: &[u8; KEY_LEN]) -> [u8; KEY_LEN] {
+    let mut base = [0; KEY_LEN];
+    base[0] = 9;
+    x25519(private_key, &base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::hex;
+
+    fn key(s: &str) -> [u8; KEY_LEN] {
+        hex(s).try_into().unwrap()
+    }
+
+    #[test]
+    /// RFC 7748 の 5.2 節のテストベクタのテスト。
+    fn test_x25519() {
+        let scalar = key("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+        let point = key("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+        assert_eq!(
+            key("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552"),
+            x25519(&scalar, &point)
+        );
+    }
+
+    #[test]
+    /// RFC 7748 の 6.1 節の鍵交換の例のテスト。
+    fn test_key_exchange() {
+        let alice = key("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = key("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        assert_eq!(
+            key("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"),
+            public_key(&alice)
+        );
+        assert_eq!(
+            key("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"),
+            public_key(&bob)
+        );
+
+        let shared = key("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(shared, x25519(&alice, &public_key(&bob)));
+        assert_eq!(shared, x25519(&bob, &public_key(&alice)));
+    }
+}
@@ -0,0 +1,622 @@
+//! X.509 証明書の読み取りと、サーバ証明書の検証
+//! サーバから受け取った証明書チェーンを信頼できるルート証明書までたどり、各証明書の署名、基本制約、鍵の用途、
+//! 有効期間を確認する。また、サーバ証明書の subjectAltName が接続先のホスト名と一致することを確認する。
+//! https://datatracker.ietf.org/doc/html/rfc5280
+//! https://datatracker.ietf.org/doc/html/rfc6125#section-6.4
+
+use crate::error::Error;
+use crate::navigation::decode_base64;
+use crate::tls::der::context_primitive_tag;
+use crate::tls::der::context_tag;
+use crate::tls::der::der_error;
+use crate::tls::der::Der;
+use crate::tls::der::Tlv;
+use crate::tls::der::TAG_BIT_STRING;
+use crate::tls::der::TAG_BOOLEAN;
+use crate::tls::der::TAG_GENERALIZED_TIME;
+use crate::tls::der::TAG_INTEGER;
+use crate::tls::der::TAG_OCTET_STRING;
+use crate::tls::der::TAG_OID;
+use crate::tls::der::TAG_SEQUENCE;
+use crate::tls::der::TAG_UTC_TIME;
+use crate::tls::digest::HashAlgorithm;
+use crate::tls::ecdsa::Curve;
+use crate::tls::ecdsa::EcPublicKey;
+use crate::tls::rsa::RsaPublicKey;
+use alloc::format;
+use alloc::vec::Vec;
+
+// アルゴリズムと拡張の OID (DER でエンコードした値)
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const OID_SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+const OID_SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_SECP256R1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_SECP384R1: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+const OID_ECDSA_WITH_SHA512: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x04];
+const OID_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+const OID_EXT_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25];
+const OID_ANY_EXTENDED_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25, 0x00];
+const OID_SERVER_AUTH: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01];
+
+// keyUsage のビットの位置
+const KEY_USAGE_DIGITAL_SIGNATURE: usize = 0;
+const KEY_USAGE_KEY_CERT_SIGN: usize = 5;
+
+/// 証明書チェーンでたどる中間 CA 証明書の最大の数
+const MAX_INTERMEDIATES: usize = 8;
+
+/// 証明書の検証の失敗を表すエラーを作成する。
+fn verification_error(message: &str) -> Error {
+    Error::Network(format!("TLS: certificate verification failed: {}", message))
+}
+
+/// 証明書の公開鍵
+#[derive(Debug, Clone)]
+pub enum PublicKey {
+    Rsa(RsaPublicKey),
+    Ec(EcPublicKey),
+}
+
+/// 証明書の署名に使われるアルゴリズム
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignatureAlgorithm {
+    RsaPkcs1(HashAlgorithm),
+    Ecdsa(HashAlgorithm),
+}
+
+/// 読み取った X.509 証明書。検証に必要な項目のみを持つ。
+/// https://datatracker.ietf.org/doc/html/rfc5280#section-4.1
+#[derive(Debug, Clone)]
+pub struct Certificate<'a> {
+    // 署名の対象である tbsCertificate のエンコードされたバイト列
+    tbs: &'a [u8],
+    // 対応していないアルゴリズムの場合は None。この証明書の署名を検証する場合のみエラーとなる。
+    signature_algorithm: Option<SignatureAlgorithm>,
+    signature: &'a [u8],
+    // 名前はエンコードされたバイト列のまま比較する。
+    issuer: &'a [u8],
+    subject: &'a [u8],
+    // 有効期間 (UNIX 時間の秒)
+    not_before: i64,
+    not_after: i64,
+    public_key: Option<PublicKey>,
+    is_ca: bool,
+    path_len_constraint: Option<usize>,
+    // keyUsage 拡張がない場合は None
+    key_usage: Option<&'a [u8]>,
+    // extKeyUsage 拡張がない場合は None
+    server_auth: Option<bool>,
+    dns_names: Vec<&'a [u8]>,
+    ip_addresses: Vec<&'a [u8]>,
+}
+
+impl<'a> Certificate<'a> {
+    /// DER でエンコードされた証明書を読み取る。
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        let mut der = Der::new(data);
+        let mut certificate = der.expect(TAG_SEQUENCE)?.contents();
+        der.finish()?;
+        let tbs = certificate.expect(TAG_SEQUENCE)?;
+        let outer_algorithm = certificate.expect(TAG_SEQUENCE)?;
+        let signature = certificate.read()?.bit_string()?;
+        certificate.finish()?;
+
+        let mut fields = tbs.contents();
+        // version は v3 (2) を想定するが、拡張を持たない v1 の古いルート証明書も読み取る。
+        fields.read_optional(context_tag(0))?;
+        fields.expect(TAG_INTEGER)?;
+        let inner_algorithm = fields.expect(TAG_SEQUENCE)?;
+        if inner_algorithm.raw != outer_algorithm.raw {
+            return Err(der_error("mismatched signature algorithms"));
+        }
+        let issuer = fields.expect(TAG_SEQUENCE)?;
+        let mut validity = fields.expect(TAG_SEQUENCE)?.contents();
+        let not_before = parse_time(validity.read()?)?;
+        let not_after = parse_time(validity.read()?)?;
+        validity.finish()?;
+        let subject = fields.expect(TAG_SEQUENCE)?;
+        let public_key = parse_public_key(fields.expect(TAG_SEQUENCE)?)?;
+        // issuerUniqueID と subjectUniqueID は使わない。
+        fields.read_optional(context_primitive_tag(1))?;
+        fields.read_optional(context_primitive_tag(2))?;
+
+        let mut certificate = Self {
+            tbs: tbs.raw,
+            signature_algorithm: parse_signature_algorithm(outer_algorithm)?,
+            signature,
+            issuer: issuer.raw,
+            subject: subject.raw,
+            not_before,
+            not_after,
+            public_key,
+            is_ca: false,
+            path_len_constraint: None,
+            key_usage: None,
+            server_auth: None,
+            dns_names: Vec::new(),
+            ip_addresses: Vec::new(),
+        };
+        if let Some(extensions) = fields.read_optional(context_tag(3))? {
+            let mut outer = extensions.contents();
+            let mut extensions = outer.expect(TAG_SEQUENCE)?.contents();
+            outer.finish()?;
+            while !extensions.is_empty() {
+                certificate.parse_extension(extensions.expect(TAG_SEQUENCE)?)?;
+            }
+        }
+        fields.finish()?;
+        Ok(certificate)
+    }
+
+    /// 拡張を1つ読み取る。検証に使わない拡張は無視するが、critical な場合はエラーとする。
+    /// https://datatracker.ietf.org/doc/html/rfc5280#section-4.2
+    fn parse_extension(&mut self, extension: Tlv<'a>) -> Result<(), Error> {
+        let mut fields = extension.contents();
+        let id = fields.expect(TAG_OID)?.value;
+        let critical = match fields.read_optional(TAG_BOOLEAN)? {
+            Some(critical) => critical.value == [0xff],
+            None => false,
+        };
+        let value = fields.expect(TAG_OCTET_STRING)?.value;
+        fields.finish()?;
+
+        let mut der = Der::new(value);
+        match id {
+            OID_BASIC_CONSTRAINTS => {
+                let mut constraints = der.expect(TAG_SEQUENCE)?.contents();
+                if let Some(ca) = constraints.read_optional(TAG_BOOLEAN)? {
+                    self.is_ca = ca.value == [0xff];
+                }
+                if let Some(path_len) = constraints.read_optional(TAG_INTEGER)? {
+                    let path_len = path_len.positive_integer()?;
+                    self.path_len_constraint = Some(path_len.iter().fold(0usize, |acc, b| {
+                        acc.saturating_mul(256).saturating_add(*b as usize)
+                    }));
+                }
+                constraints.finish()?;
+            }
+            OID_KEY_USAGE => {
+                // 末尾の未使用ビットは 0 なので、バイト列のまま各ビットを調べられる。
+                let bits = der.expect(TAG_BIT_STRING)?.value;
+                if bits.is_empty() {
+                    return Err(der_error("invalid key usage"));
+                }
+                self.key_usage = Some(&bits[1..]);
+            }
+            OID_EXT_KEY_USAGE => {
+                let mut purposes = der.expect(TAG_SEQUENCE)?.contents();
+                let mut server_auth = false;
+                while !purposes.is_empty() {
+                    let purpose = purposes.expect(TAG_OID)?.value;
+                    server_auth |=
+                        purpose == OID_SERVER_AUTH || purpose == OID_ANY_EXTENDED_KEY_USAGE;
+                }
+                self.server_auth = Some(server_auth);
+            }
+            OID_SUBJECT_ALT_NAME => {
+                let mut names = der.expect(TAG_SEQUENCE)?.contents();
+                while !names.is_empty() {
+                    let name = names.read()?;
+                    // dNSName [2] と iPAddress [7] 以外の名前は使わない。
+                    if name.tag == context_primitive_tag(2) {
+                        self.dns_names.push(name.value);
+                    } else if name.tag == context_primitive_tag(7) {
+                        self.ip_addresses.push(name.value);
+                    }
+                }
+            }
+            _ if critical => return Err(der_error("unsupported critical extension")),
+            _ => return Ok(()),
+        }
+        der.finish()
+    }
+
+    /// keyUsage 拡張がないか、指定したビットが立っているかどうか。
+    fn allows_key_usage(&self, bit: usize) -> bool {
+        match self.key_usage {
+            Some(bits) => bits
+                .get(bit / 8)
+                .is_some_and(|b| b & (0x80 >> (bit % 8)) != 0),
+            None => true,
+        }
+    }
+
+    /// 証明書の公開鍵を返す。対応していない種類の鍵の場合はエラーを返す。
+    pub fn public_key(&self) -> Result<&PublicKey, Error> {
+        self.public_key
+            .as_ref()
+            .ok_or_else(|| verification_error("unsupported public key"))
+    }
+
+    /// この証明書が issuer の鍵で署名されていることを確認する。
+    fn verify_signed_by(&self, issuer: &Certificate) -> Result<(), Error> {
+        let key = issuer.public_key()?;
+        let verified = match (self.signature_algorithm, key) {
+            (Some(SignatureAlgorithm::RsaPkcs1(hash)), PublicKey::Rsa(key)) => {
+                key.verify_pkcs1_v15(hash, self.tbs, self.signature)
+            }
+            (Some(SignatureAlgorithm::Ecdsa(hash)), PublicKey::Ec(key)) => {
+                key.verify(hash, self.tbs, self.signature)
+            }
+            (None, _) => return Err(verification_error("unsupported signature algorithm")),
+            _ => false,
+        };
+        if !verified {
+            return Err(verification_error("invalid signature"));
+        }
+        Ok(())
+    }
+
+    /// now (UNIX 時間の秒) が有効期間内であることを確認する。
+    fn check_validity(&self, now: Option<i64>) -> Result<(), Error> {
+        match now {
+            Some(now) if now < self.not_before || self.not_after < now => Err(verification_error(
+                "the certificate is expired or not yet valid",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// 証明書が host (ドメイン名か IPv4 アドレス) に対して発行されたものかどうか。
+    /// 共通名 (CN) は使わず、subjectAltName のみを調べる。
+    /// https://datatracker.ietf.org/doc/html/rfc6125#section-6.4
+    pub fn matches_host(&self, host: &str) -> bool {
+        let host = host.strip_suffix('.').unwrap_or(host);
+        if let Some(address) = parse_ipv4(host) {
+            return self.ip_addresses.iter().any(|ip| *ip == address);
+        }
+        self.dns_names
+            .iter()
+            .any(|name| matches_dns_name(name, host.as_bytes()))
+    }
+}
+
+/// 証明書の dNSName が host と一致するかどうか。大文字と小文字は区別しない。
+/// ワイルドカードは、最も左のラベル全体が "*" の場合のみ、そのラベル1つと一致する。
+fn matches_dns_name(name: &[u8], host: &[u8]) -> bool {
+    let name = name.strip_suffix(b".").unwrap_or(name);
+    if let Some(suffix) = name.strip_prefix(b"*.") {
+        // "*.com" のように、ワイルドカード以外のラベルが1つしかない名前は受け付けない。
+        if !suffix.contains(&b'.') {
+            return false;
+        }
+        return match host.iter().position(|b| *b == b'.') {
+            Some(dot) if dot > 0 => host[dot + 1..].eq_ignore_ascii_case(suffix),
+            _ => false,
+        };
+    }
+    name.eq_ignore_ascii_case(host)
+}
+
+/// "192.168.0.1" のような IPv4 アドレスを 4 バイトにする。
+fn parse_ipv4(host: &str) -> Option<[u8; 4]> {
+    let mut address = [0u8; 4];
+    let mut parts = host.split('.');
+    for byte in address.iter_mut() {
+        let part = parts.next()?;
+        if part.is_empty() || part.len() > 3 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *byte = part.parse().ok()?;
+    }
+    match parts.next() {
+        Some(_) => None,
+        None => Some(address),
+    }
+}
+
+/// AlgorithmIdentifier から署名アルゴリズムを読み取る。対応していない場合は None を返す。
+fn parse_signature_algorithm(algorithm: Tlv) -> Result<Option<SignatureAlgorithm>, Error> {
+    let id = algorithm.contents().expect(TAG_OID)?.value;
+    Ok(match id {
+        OID_SHA256_WITH_RSA => Some(SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha256)),
+        OID_SHA384_WITH_RSA => Some(SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha384)),
+        OID_SHA512_WITH_RSA => Some(SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha512)),
+        OID_ECDSA_WITH_SHA256 => Some(SignatureAlgorithm::Ecdsa(HashAlgorithm::Sha256)),
+        OID_ECDSA_WITH_SHA384 => Some(SignatureAlgorithm::Ecdsa(HashAlgorithm::Sha384)),
+        OID_ECDSA_WITH_SHA512 => Some(SignatureAlgorithm::Ecdsa(HashAlgorithm::Sha512)),
+        _ => None,
+    })
+}
+
+/// SubjectPublicKeyInfo から公開鍵を読み取る。対応していない種類の鍵の場合は None を返す。
+/// https://datatracker.ietf.org/doc/html/rfc3279#section-2.3
+fn parse_public_key(info: Tlv) -> Result<Option<PublicKey>, Error> {
+    let mut fields = info.contents();
+    let mut algorithm = fields.expect(TAG_SEQUENCE)?.contents();
+    let key = fields.read()?.bit_string()?;
+    fields.finish()?;
+
+    let id = algorithm.expect(TAG_OID)?.value;
+    match id {
+        OID_RSA_ENCRYPTION => {
+            let mut der = Der::new(key);
+            let mut numbers = der.expect(TAG_SEQUENCE)?.contents();
+            der.finish()?;
+            let modulus = numbers.read()?.positive_integer()?;
+            let exponent = numbers.read()?.positive_integer()?;
+            numbers.finish()?;
+            Ok(RsaPublicKey::new(modulus, exponent).map(PublicKey::Rsa))
+        }
+        OID_EC_PUBLIC_KEY => {
+            let curve = match algorithm.expect(TAG_OID)?.value {
+                OID_SECP256R1 => Curve::P256,
+                OID_SECP384R1 => Curve::P384,
+                _ => return Ok(None),
+            };
+            EcPublicKey::from_sec1(curve, key)
+                .map(|key| Some(PublicKey::Ec(key)))
+                .ok_or_else(|| der_error("invalid EC public key"))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// UTCTime (YYMMDDHHMMSSZ) または GeneralizedTime (YYYYMMDDHHMMSSZ) を UNIX 時間の秒にする。
+/// https://datatracker.ietf.org/doc/html/rfc5280#section-4.1.2.5
+fn parse_time(time: Tlv) -> Result<i64, Error> {
+    let (year, rest) = match (time.tag, time.value.len()) {
+        (TAG_UTC_TIME, 13) => {
+            let year = parse_digits(&time.value[..2])?;
+            // 50 以上は 19xx 年、50 未満は 20xx 年を表す。
+            (
+                if year >= 50 { 1900 + year } else { 2000 + year },
+                &time.value[2..],
+            )
+        }
+        (TAG_GENERALIZED_TIME, 15) => (parse_digits(&time.value[..4])?, &time.value[4..]),
+        _ => return Err(der_error("invalid time")),
+    };
+    if rest[10] != b'Z' {
+        return Err(der_error("invalid time"));
+    }
+    let month = parse_digits(&rest[0..2])?;
+    let day = parse_digits(&rest[2..4])?;
+    let hour = parse_digits(&rest[4..6])?;
+    let minute = parse_digits(&rest[6..8])?;
+    let second = parse_digits(&rest[8..10])?;
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(der_error("invalid time"));
+    }
+    Ok(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+fn parse_digits(digits: &[u8]) -> Result<i64, Error> {
+    digits.iter().try_fold(0, |acc, b| {
+        if b.is_ascii_digit() {
+            Ok(acc * 10 + (b - b'0') as i64)
+        } else {
+            Err(der_error("invalid time"))
+        }
+    })
+}
+
+/// グレゴリオ暦の日付から、1970年1月1日からの日数を返す。
+/// https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// PEM の最初の証明書を DER のバイト列にする。
+pub fn pem_to_der(pem: &str) -> Option<Vec<u8>> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let start = pem.find(BEGIN)? + BEGIN.len();
+    let end = start + pem[start..].find(END)?;
+    decode_base64(pem[start..end].as_bytes())
+}
+
+/// サーバから受け取った証明書チェーン (先頭がサーバ証明書) を検証し、サーバ証明書の公開鍵を返す。
+/// 中間 CA 証明書は順序を問わず、発行者の名前が一致し署名を検証できるものをたどる。
+/// trust_anchors のいずれかの鍵で署名された証明書に到達した場合に信頼する。
+/// now が None の場合は有効期間を確認しない。
+/// https://datatracker.ietf.org/doc/html/rfc5280#section-6
+pub fn verify_server_certificate(
+    chain: &[&[u8]],
+    trust_anchors: &[Vec<u8>],
+    host: &str,
+    now: Option<i64>,
+) -> Result<PublicKey, Error> {
+    let (leaf, intermediates) = chain
+        .split_first()
+        .ok_or_else(|| verification_error("the server sent no certificate"))?;
+    let leaf = Certificate::parse(leaf)?;
+    let intermediates = intermediates
+        .iter()
+        .map(|c| Certificate::parse(c))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let anchors = trust_anchors
+        .iter()
+        .filter_map(|c| Certificate::parse(c).ok())
+        .collect::<Vec<_>>();
+
+    leaf.check_validity(now)?;
+    if !leaf.matches_host(host) {
+        return Err(verification_error(
+            "the certificate is not valid for the host",
+        ));
+    }
+    if leaf.server_auth == Some(false) || !leaf.allows_key_usage(KEY_USAGE_DIGITAL_SIGNATURE) {
+        return Err(verification_error("the certificate is not for a server"));
+    }
+
+    let mut current = &leaf;
+    for depth in 0..=MAX_INTERMEDIATES {
+        if anchors.iter().any(|anchor| {
+            anchor.subject == current.issuer && current.verify_signed_by(anchor).is_ok()
+        }) {
+            return Ok(leaf.public_key()?.clone());
+        }
+        if depth == MAX_INTERMEDIATES {
+            break;
+        }
+        // depth 個の中間 CA 証明書を既にたどっているため、発行者の pathLenConstraint は depth 以上でなければならない。
+        current = intermediates
+            .iter()
+            .find(|issuer| {
+                issuer.subject == current.issuer
+                    && issuer.is_ca
+                    && issuer.allows_key_usage(KEY_USAGE_KEY_CERT_SIGN)
+                    && issuer.server_auth != Some(false)
+                    && issuer.path_len_constraint.map_or(true, |len| len >= depth)
+                    && issuer.check_validity(now).is_ok()
+                    && current.verify_signed_by(issuer).is_ok()
+            })
+            .ok_or_else(|| verification_error("unknown issuer"))?;
+    }
+    Err(verification_error("the certificate chain is too long"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    // テスト用の証明書チェーン。ルート CA (P-256) が中間 CA (RSA 2048 ビット) に署名し、
+    // 中間 CA が example.com、*.example.org、127.0.0.1 に対するサーバ証明書 (P-256) に署名している。
+    // サーバ証明書の有効期間は 2024年1月1日から 2034年1月1日まで。
+    const ROOT: &str = include_str!("testdata/root.pem");
+    const INTERMEDIATE: &str = include_str!("testdata/intermediate.pem");
+    const LEAF: &str = include_str!("testdata/leaf.pem");
+
+    // 2025年1月1日 00:00:00 UTC
+    const NOW: i64 = 1735689600;
+
+    fn chain() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        (
+            pem_to_der(LEAF).unwrap(),
+            pem_to_der(INTERMEDIATE).unwrap(),
+            pem_to_der(ROOT).unwrap(),
+        )
+    }
+
+    #[test]
+    /// 証明書の各項目の読み取りのテスト。
+    fn test_parse() {
+        let (leaf, intermediate, root) = chain();
+        let leaf = Certificate::parse(&leaf).unwrap();
+        let intermediate = Certificate::parse(&intermediate).unwrap();
+        let root = Certificate::parse(&root).unwrap();
+
+        assert_eq!(leaf.issuer, intermediate.subject);
+        assert_eq!(intermediate.issuer, root.subject);
+        assert_eq!(root.issuer, root.subject);
+        assert_eq!(1704067200, leaf.not_before);
+        assert_eq!(2019686400, leaf.not_after);
+        // ルート CA の有効期限は 2124年で、GeneralizedTime で表される。
+        assert_eq!(4859740800, root.not_after);
+        assert!(!leaf.is_ca);
+        assert!(intermediate.is_ca);
+        assert_eq!(Some(0), intermediate.path_len_constraint);
+        assert_eq!(Some(true), leaf.server_auth);
+        assert!(leaf.allows_key_usage(KEY_USAGE_DIGITAL_SIGNATURE));
+        assert!(!leaf.allows_key_usage(KEY_USAGE_KEY_CERT_SIGN));
+        assert!(root.allows_key_usage(KEY_USAGE_KEY_CERT_SIGN));
+        assert!(matches!(leaf.public_key(), Ok(PublicKey::Ec(_))));
+        assert!(matches!(intermediate.public_key(), Ok(PublicKey::Rsa(_))));
+
+        assert!(leaf.verify_signed_by(&intermediate).is_ok());
+        assert!(intermediate.verify_signed_by(&root).is_ok());
+        assert!(root.verify_signed_by(&root).is_ok());
+        assert!(leaf.verify_signed_by(&root).is_err());
+    }
+
+    #[test]
+    /// subjectAltName とホスト名の照合のテスト。
+    fn test_matches_host() {
+        let (leaf, _, _) = chain();
+        let leaf = Certificate::parse(&leaf).unwrap();
+        assert!(leaf.matches_host("example.com"));
+        assert!(leaf.matches_host("EXAMPLE.com."));
+        assert!(leaf.matches_host("www.example.org"));
+        assert!(leaf.matches_host("127.0.0.1"));
+        // 共通名やワイルドカードは、サブドメインや別のドメインには一致しない。
+        assert!(!leaf.matches_host("www.example.com"));
+        assert!(!leaf.matches_host("example.org"));
+        assert!(!leaf.matches_host("a.b.example.org"));
+        assert!(!leaf.matches_host("example.com.evil.test"));
+        assert!(!leaf.matches_host("127.0.0.2"));
+
+        assert!(!matches_dns_name(b"*.com", b"example.com"));
+        assert!(!matches_dns_name(b"w*.example.com", b"www.example.com"));
+        assert_eq!(None, parse_ipv4("1.2.3"));
+        assert_eq!(None, parse_ipv4("1.2.3.256"));
+        assert_eq!(Some([10, 0, 0, 1]), parse_ipv4("10.0.0.1"));
+    }
+
+    #[test]
+    /// 証明書チェーンの検証のテスト。
+    fn test_verify_server_certificate() {
+        let (leaf, intermediate, root) = chain();
+        let anchors = vec![root.clone()];
+        let verify = |chain: &[&[u8]], host: &str, now: Option<i64>| {
+            verify_server_certificate(chain, &anchors, host, now)
+        };
+
+        assert!(matches!(
+            verify(&[&leaf, &intermediate], "example.com", Some(NOW)),
+            Ok(PublicKey::Ec(_))
+        ));
+        // 現在時刻が分からない場合は有効期間を確認しない。
+        assert!(verify(&[&leaf, &intermediate], "example.com", None).is_ok());
+        // ルート証明書が含まれていてもよい。
+        assert!(verify(&[&leaf, &intermediate, &root], "example.com", Some(NOW)).is_ok());
+
+        assert_eq!(
+            Err(verification_error(
+                "the certificate is not valid for the host"
+            )),
+            verify(&[&leaf, &intermediate], "example.net", Some(NOW)).map(|_| ())
+        );
+        assert_eq!(
+            Err(verification_error(
+                "the certificate is expired or not yet valid"
+            )),
+            verify(&[&leaf, &intermediate], "example.com", Some(2019686401)).map(|_| ())
+        );
+        // 中間 CA 証明書がなければ、ルート証明書までたどれない。
+        assert_eq!(
+            Err(verification_error("unknown issuer")),
+            verify(&[&leaf], "example.com", Some(NOW)).map(|_| ())
+        );
+        // 信頼するルート証明書がない。
+        assert_eq!(
+            Err(verification_error("unknown issuer")),
+            verify_server_certificate(&[&leaf, &intermediate], &[], "example.com", Some(NOW))
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(verification_error("the server sent no certificate")),
+            verify(&[], "example.com", Some(NOW)).map(|_| ())
+        );
+
+        // 改ざんされた証明書は、署名を検証できない。
+        let mut tampered = leaf.clone();
+        let position = tampered
+            .windows(11)
+            .position(|w| w == b"example.com")
+            .unwrap();
+        tampered[position] = b'E';
+        assert_eq!(
+            Err(verification_error("unknown issuer")),
+            verify(&[&tampered, &intermediate], "example.com", Some(NOW)).map(|_| ())
+        );
+    }
+}
@@ -21,8 +21,10 @@ pub fn convert_dom_to_string_internal(
             result.push_str(&"   ".repeat(depth));
             result.push_str(&format!("{:?}", n.borrow().kind()));
             result.push('\n');
-            convert_dom_to_string_internal(&n.borrow().first_child(), depth + 1, result);
-            convert_dom_to_string_internal(&n.borrow().next_sibling(), depth, result);
+            let children = n.borrow().children();
+            for child in children {
+                convert_dom_to_string_internal(&Some(child), depth + 1, result);
+            }
         }
         None => (),
     }
@@ -14,6 +14,15 @@ use noli::*;
 use saba_core::browser::Browser;
 use saba_core::error::Error;
 use saba_core::http::HttpResponse;
+use saba_core::navigation::load_about_url;
+use saba_core::navigation::authenticate;
+use saba_core::navigation::is_final_response;
+use saba_core::navigation::BodyChunkHandler;
+use saba_core::navigation::follow_redirects;
+use saba_core::navigation::CredentialsPrompt;
+use saba_core::navigation::load_data_url;
+use saba_core::navigation::normalize_input;
+use saba_core::scheduler::FetchScheduler;
 use saba_core::url::Url;
 use ui_wasabi::app::WasabiUI;
 
@@ -44,7 +53,7 @@ fn main() -> u64 {
     let browser = Browser::new();
     let ui = Rc::new(RefCell::new(WasabiUI::new(browser)));
 
-    match ui.borrow_mut().start(handle_url) {
+    match ui.borrow_mut().start(handle_url, fetch_subresources) {
         Ok(_) => {}
         Err(e) => {
             println!("browser fails to start {:?}", e);
@@ -76,10 +85,16 @@ fn main() -> u64 {
     0 */
 }
 
-/// 引数の URL にアクセスし、HttpResponse 構造体を返す。
-fn handle_url(url: String) -> Result<HttpResponse, Error> {
-    // URL を解釈する。
-    let parsed_url = match Url::new(url.to_string()).parse() {
+/// 引数の URL にアクセスし、リダイレクトをたどった最終的な URL と HttpResponse 構造体を返す。
+/// サーバから Basic 認証を求められた場合は、prompt で入力された認証情報を付けてリクエストを送信し直す。
+/// ページとして表示するレスポンスのボディは、受信した分から順に on_chunk に渡す。
+fn handle_url(
+    url: String,
+    prompt: &mut CredentialsPrompt,
+    on_chunk: &mut BodyChunkHandler,
+) -> Result<(Url, HttpResponse), Error> {
+    // アドレスバーに入力された文字列を URL に正規化してから解釈する。
+    let parsed_url = match Url::new(normalize_input(&url)).parse() {
         Ok(url) => url,
         Err(e) => {
             return Err(Error::UnexpectedInput(format!(
@@ -89,51 +104,45 @@ fn handle_url(url: String) -> Result<HttpResponse, Error> {
         }
     };
 
-    // HTTP リクエストを送信する。
+    // リクエストを送信する。レスポンスがリダイレクトの場合は、転送先の URL をたどる。
+    // follow_redirects は http と https 以外への転送をエラーとするため、
+    // data URL と about URL は、アドレスバーに入力されたり、リンクで開かれたりした場合のみ読み込む。
     let client = HttpClient::new();
-    let response = match client.get(
-        parsed_url.host(),
-        parsed_url.port().parse::<u16>().expect(&format!(
-            "port number should be u16 but got {}",
-            parsed_url.port()
-        )),
-        parsed_url.path(),
-    ) {
-        Ok(res) => {
-            // HTTP レスポンスのステータスコードが 302 の場合、転送する（リダイレクト）。
-            // Location が示す転送先 URL を解釈し、HTTP アクセスする。
-            if res.status_code() == 302 {
-                let location = match res.header_value("Location") {
-                    Ok(value) => value,
-                    Err(_) => return Ok(res),
-                };
-                let redirect_parsed_url = Url::new(location);
-
-                let redirect_res = match client.get(
-                    redirect_parsed_url.host(),
-                    redirect_parsed_url.port().parse::<u16>().expect(&format!(
-                        "port number should be u16 but got {}",
-                        parsed_url.port()
-                    )),
-                    redirect_parsed_url.path(),
-                ) {
-                    Ok(res) => res,
-                    Err(e) => return Err(Error::Network(format!("{:?}", e))),
-                };
-                redirect_res
-            } else {
-                // ステータスコードが 302 以外の場合、そのまま返す。
-                res
-            }
+    follow_redirects(parsed_url, "GET", String::new(), |url, method, body| {
+        // data URL の場合は、ネットワークに接続せずにレスポンスを生成する。
+        if url.is_data() {
+            return load_data_url(url);
         }
-        Err(e) => {
-            return Err(Error::Network(format!(
-                "failed to get http response: {:?}",
-                e
-            )))
+
+        // about URL の場合は、ブラウザ組み込みのページを返す。
+        if url.is_about() {
+            return load_about_url(url);
         }
-    };
-    Ok(response)
+
+        // リダイレクトや認証を求めるレスポンスのボディは、ページに渡さない。
+        let mut stream_body = |head: &HttpResponse, chunk: &str| {
+            if is_final_response(head) {
+                on_chunk(chunk);
+            }
+        };
+        let response = client
+            .fetch_streaming(url, method, body.to_string(), &mut stream_body)
+            .map_err(|e| Error::Network(format!("failed to get http response: {:?}", e)))?;
+
+        // 401 で Basic 認証を求められた場合は、Authorization ヘッダを付けて送信し直す。
+        authenticate(url, response, prompt, |authorization| {
+            let mut client = HttpClient::new();
+            client.set_header("Authorization", authorization)?;
+            client
+                .fetch_streaming(url, method, body.to_string(), &mut stream_body)
+                .map_err(|e| Error::Network(format!("failed to get http response: {:?}", e)))
+        })
+    })
+}
+
+/// スケジューラに登録された <img> や <iframe> などのサブリソースをすべて取得する。
+fn fetch_subresources(scheduler: &mut FetchScheduler) {
+    HttpClient::new().fetch_all(scheduler);
 }
 
 entry_point!(main);
@@ -1,5 +1,6 @@
 use crate::alloc::string::ToString;
 use crate::cursor::Cursor;
+use crate::time::NoliTimeSource;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
@@ -21,9 +22,15 @@ use saba_core::constants::WINDOW_WIDTH;
 use saba_core::constants::*;
 use saba_core::display_item::DisplayItem;
 use saba_core::error::Error;
-use saba_core::http::HttpResponse;
+use saba_core::navigation::load_parse_errors_page;
+use saba_core::navigation::normalize_input;
+use saba_core::navigation::Credentials;
+use saba_core::navigation::SubresourceFetcher;
+use saba_core::navigation::UrlHandler;
+use saba_core::navigation::PARSE_ERRORS_URL;
 use saba_core::renderer::layout::computed_style::FontSize;
-use saba_core::renderer::layout::computed_style::TextDecoration;
+use saba_core::scheduler::FetchScheduler;
+use saba_core::url::Url;
 
 /// WasabiUI 構造体
 /// ウィンドウのインスタンスとブラウザの実装を保持する。
@@ -34,6 +41,8 @@ pub struct WasabiUI {
     window: Window,
     input_mode: InputMode,
     input_url: String,
+    // 現在表示しているページの URL。相対 URL のリンクを解決する基準として使う。
+    current_url: String,
     cursor: Cursor,
 }
 
@@ -47,10 +56,18 @@ enum InputMode {
 
 impl WasabiUI {
     /// WasabiUI 構造体のコンストラクタ
+    /// JavaScript の Date.now や setTimeout が OS のクロックを参照するように、ページに時刻の取得元を設定する。
     pub fn new(browser: Rc<RefCell<Browser>>) -> Self {
+        browser
+            .borrow()
+            .current_page()
+            .borrow_mut()
+            .set_time_source(Rc::new(NoliTimeSource));
+
         Self {
             browser,
             input_url: String::new(),
+            current_url: String::new(),
             input_mode: InputMode::Normal,
             window: Window::new(
                 "saba".to_string(),
@@ -124,10 +141,13 @@ impl WasabiUI {
     /// UI を開始するメソッド
     pub fn start(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: UrlHandler,
+        fetch_subresources: SubresourceFetcher,
     ) -> Result<(), Error> {
         self.setup()?;
-        self.run_app(handle_url)?;
+        // 起動時は組み込みの空のページを表示する。
+        self.start_navigation(handle_url, fetch_subresources, "about:blank".to_string())?;
+        self.run_app(handle_url, fetch_subresources)?;
 
         Ok(())
     }
@@ -135,14 +155,29 @@ impl WasabiUI {
     /// アプリケーションを実行するための関数
     fn run_app(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: UrlHandler,
+        fetch_subresources: SubresourceFetcher,
     ) -> Result<(), Error> {
         loop {
             // キー入力を取得する。
-            self.handle_key_input(handle_url)?;
+            self.handle_key_input(handle_url, fetch_subresources)?;
             // マウスの位置を取得する。
-            self.handle_mouse_input(handle_url)?;
+            self.handle_mouse_input(handle_url, fetch_subresources)?;
+            // JavaScript のタイマーを実行する。
+            self.handle_timers()?;
+        }
+    }
+
+    /// 実行時刻に達した JavaScript のタイマーを実行し、ページが変更された場合は再描画する。
+    fn handle_timers(&mut self) -> Result<(), Error> {
+        let page = self.browser.borrow().current_page();
+        let ran = page.borrow_mut().run_timers();
+
+        if ran {
+            self.clear_content_area()?;
+            self.update_ui()?;
         }
+        Ok(())
     }
 
     /// マウスの位置を取得する。
@@ -150,7 +185,8 @@ impl WasabiUI {
     /// これは戻り値で マウスクリックの状態とマウスの位置を保持する MouseEvent 構造体を返す。
     fn handle_mouse_input(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: UrlHandler,
+        fetch_subresources: SubresourceFetcher,
     ) -> Result<(), Error> {
         if let Some(MouseEvent { button, position }) = Api::get_mouse_cursor_info() {
             // マウスの位置にマウスカーソルを表示する。
@@ -189,17 +225,25 @@ impl WasabiUI {
                 self.input_mode = InputMode::Normal;
 
                 // リンクである <a> タグをクリックした場合の挙動
+                // ページがスクロールされている場合、スクロール位置を加えてページ上の座標に変換する。
+                let page = self.browser.borrow().current_page();
+                let scroll_position = page.borrow().scroll_position();
                 let position_in_content_area = (
-                    relative_pos.0,
-                    relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
+                    relative_pos.0 + scroll_position.0,
+                    relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT + scroll_position.1,
                 );
-                let page = self.browser.borrow().current_page();
+                // click イベントのリスナーがページを変更した可能性があるため、遷移しない場合は再描画する。
                 let next_destination = page.borrow_mut().clicked(position_in_content_area);
 
-                if let Some(url) = next_destination {
+                if let Some(href) = next_destination {
+                    // href は相対 URL であることが多いため、ページのベース URL を基準に解決する。
+                    let url = page.borrow().resolve_url(&href);
                     self.input_url = url.clone();
                     self.update_address_bar()?;
-                    self.start_navigation(handle_url, url)?;
+                    self.start_navigation(handle_url, fetch_subresources, url)?;
+                } else {
+                    self.clear_content_area()?;
+                    self.update_ui()?;
                 }
             }
             // println!("mouse position {:?}", position);
@@ -214,20 +258,35 @@ impl WasabiUI {
     /// noli の Api::read_key 関数は文字入力に対して1文字を返す。
     fn handle_key_input(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: UrlHandler,
+        fetch_subresources: SubresourceFetcher,
     ) -> Result<(), Error> {
         match self.input_mode {
             InputMode::Normal => {
-                // InputMode が Normal の時、キー入力を無視する。
-                let _ = Api::read_key();
+                // InputMode が Normal の時、j キーと k キーでページを上下にスクロールし、他のキー入力は無視する。
+                let dy = match Api::read_key() {
+                    Some('j') => CHAR_HEIGHT_WITH_PADDING,
+                    Some('k') => -CHAR_HEIGHT_WITH_PADDING,
+                    _ => return Ok(()),
+                };
+                let page = self.browser.borrow().current_page();
+                let scrolled = page.borrow_mut().scroll_by(dy);
+                if scrolled {
+                    self.clear_content_area()?;
+                    self.update_ui()?;
+                }
             }
             InputMode::Editing => {
                 if let Some(c) = Api::read_key() {
                     if c == 0x0A as char {
                         println!("{:?}", self.input_url);
                         // Enter キーが押された場合、ナビゲーションを開始する。
-                        self.start_navigation(handle_url, self.input_url.clone())?;
-                        self.input_url = String::new();
+                        // アドレスバーには start_navigation が設定した最終的な URL が残る。
+                        self.start_navigation(
+                            handle_url,
+                            fetch_subresources,
+                            self.input_url.clone(),
+                        )?;
                         self.input_mode = InputMode::Normal;
                     } else if c == 0x7F as char || c == 0x08 as char {
                         // デリートキーまたはバックスペースキーが押された場合、最後の文字を削除する。
@@ -247,9 +306,54 @@ impl WasabiUI {
         Ok(())
     }
 
+    /// Basic 認証のユーザ名とパスワードをアドレスバーで入力してもらう。
+    /// Esc キーで入力を取り消した場合は None を返す。
+    fn prompt_credentials(&mut self, realm: &str) -> Option<Credentials> {
+        let username = self.read_line(&format!("[{}] user: ", realm), false)?;
+        let password = self.read_line(&format!("[{}] password: ", realm), true)?;
+        Some(Credentials::new(username, password))
+    }
+
+    /// アドレスバーに label を表示し、Enter キーが押されるまで入力された文字列を返す。
+    /// masked が true の場合、入力した文字を "*" で表示する。
+    /// Esc キーが押された場合や、描画に失敗した場合は None を返す。
+    fn read_line(&mut self, label: &str, masked: bool) -> Option<String> {
+        let mut line = String::new();
+        loop {
+            let shown = if masked {
+                "*".repeat(line.chars().count())
+            } else {
+                line.clone()
+            };
+            self.draw_address_bar(&format!("{}{}", label, shown)).ok()?;
+
+            // キーが入力されるまで待つ。
+            let c = loop {
+                if let Some(c) = Api::read_key() {
+                    break c;
+                }
+            };
+            if c == 0x0A as char {
+                return Some(line);
+            } else if c == 0x1B as char {
+                return None;
+            } else if c == 0x7F as char || c == 0x08 as char {
+                line.pop();
+            } else {
+                line.push(c);
+            }
+        }
+    }
+
     /// URL の情報をツールバーに反映する。
     /// fill_rect や draw_string などの描画 API は呼び出した時点で描画せず、flush_area を呼び出したタイミングで描画される。
     fn update_address_bar(&mut self) -> Result<(), Error> {
+        let input_url = self.input_url.clone();
+        self.draw_address_bar(&input_url)
+    }
+
+    /// アドレスバーに text を描画する。
+    fn draw_address_bar(&mut self, text: &str) -> Result<(), Error> {
         // アドレスバーを白く塗りつぶす
         if self
             .window
@@ -261,14 +365,14 @@ impl WasabiUI {
             ));
         }
 
-        // input_url をアドレスバーに描画する。
+        // text をアドレスバーに描画する。
         if self
             .window
             .draw_string(
                 BLACK,
                 74,
                 6,
-                &self.input_url,
+                text,
                 StringSize::Medium,
                 /*underline=*/ false,
             )
@@ -320,23 +424,70 @@ impl WasabiUI {
     }
 
     /// コンテンツエリアをリセットしてから URL に対してナビゲーションを行う handle_url 関数を呼び出す。
+    /// ページを受け取った後、<img> や <iframe> などのサブリソースを fetch_subresources で取得してページに渡す。
     fn start_navigation(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: UrlHandler,
+        fetch_subresources: SubresourceFetcher,
         destination: String,
     ) -> Result<(), Error> {
         self.clear_content_area()?;
 
-        match handle_url(destination) {
-            Ok(response) => {
-                let page = self.browser.borrow().current_page();
-                page.borrow_mut().receive_response(response);
+        // about:parse-errors の場合、表示中のページのパースエラーの一覧を表示する。
+        // サーバから認証を求められた場合は、アドレスバーでユーザ名とパスワードを入力してもらう。
+        // ページとして表示するレスポンスのボディは、受信した分から順にページに渡して DOM ツリーを構築する。
+        let page = self.browser.borrow().current_page();
+        let mut streamed = false;
+        let result = if normalize_input(&destination) == PARSE_ERRORS_URL {
+            let errors = page.borrow().parse_errors();
+            Url::new(PARSE_ERRORS_URL.to_string())
+                .parse()
+                .map_err(Error::UnexpectedInput)
+                .and_then(|url| Ok((url, load_parse_errors_page(&errors)?)))
+        } else {
+            handle_url(
+                destination.clone(),
+                &mut |_, realm| self.prompt_credentials(realm),
+                &mut |chunk| {
+                    if !streamed {
+                        page.borrow_mut().begin_response();
+                        streamed = true;
+                    }
+                    page.borrow_mut().receive_chunk(chunk);
+                },
+            )
+        };
+        // 認証情報の入力でアドレスバーを使った場合に備えて、元の表示に戻す。
+        // リダイレクトされた場合は、最終的な URL を表示する。
+        if let Ok((url, _)) = &result {
+            self.input_url = url.href();
+        }
+        self.update_address_bar()?;
+
+        match result {
+            Ok((url, response)) => {
+                self.current_url = url.href();
+                page.borrow_mut().set_url(url);
+                // data URL のようにボディを受け取っていない場合は、レスポンス全体からページを作成する。
+                if streamed {
+                    page.borrow_mut().finish_streaming_response(response);
+                } else {
+                    page.borrow_mut().receive_response(response);
+                }
             }
             Err(e) => {
                 return Err(e);
             }
         }
 
+        // サブリソースを受け取るたびに、ページはレイアウトと描画をやり直す。
+        let mut scheduler = FetchScheduler::new();
+        page.borrow().enqueue_subresources(&mut scheduler);
+        if !scheduler.is_idle() {
+            fetch_subresources(&mut scheduler);
+            page.borrow_mut().receive_completed_subresources(&mut scheduler);
+        }
+
         self.update_ui()?;
 
         Ok(())
@@ -367,46 +518,90 @@ impl WasabiUI {
     }
 
     /// Browser 構造体から取得した DisplayItem 列挙型の配列を１つずつ描画する。
+    /// DisplayItem はページのスクロール位置を反映し、コンテンツ領域で切り取られている。
     fn update_ui(&mut self) -> Result<(), Error> {
-        let display_items = self
-            .browser
-            .borrow()
-            .current_page()
-            .borrow()
-            .display_items();
+        let page = self.browser.borrow().current_page();
+        let display_items = page.borrow().display_items();
 
         for item in display_items {
             // println!("{:?}", item);
             match item {
                 DisplayItem::Text {
                     text,
+                    bold,
+                    underline,
+                    line_through,
+                    style,
+                    layout_point,
+                } => {
+                    let y = layout_point.y();
+                    // OS のライブラリには太字のフォントがないため、1ピクセルずらして重ね描きすることで太字を表現する。
+                    // 斜体も描画できないため、FontStyle は描画に反映しない。
+                    let strokes = if bold {
+                        2
+                    } else {
+                        1
+                    };
+                    for dx in 0..strokes {
+                        if self
+                            .window
+                            .draw_string(
+                                style.color().code_u32(),
+                                layout_point.x() + WINDOW_PADDING + dx,
+                                y + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                                &text,
+                                convert_font_size(style.font_size()),
+                                underline,
+                            )
+                            .is_err()
+                        {
+                            return Err(Error::InvalidUI("failed to draw a string".to_string()));
+                        }
+                    }
+                    // OS のライブラリは取り消し線を描画できないため、文字の高さの中央に線を引く。
+                    if line_through {
+                        let scale = font_scale(style.font_size());
+                        let x = layout_point.x() + WINDOW_PADDING;
+                        let line_y = y + WINDOW_PADDING + TOOLBAR_HEIGHT + CHAR_HEIGHT * scale / 2;
+                        let width = CHAR_WIDTH * scale * text.chars().count() as i64;
+                        if self
+                            .window
+                            .draw_line(style.color().code_u32(), x, line_y, x + width - 1, line_y)
+                            .is_err()
+                        {
+                            return Err(Error::InvalidUI("failed to draw a line".to_string()));
+                        }
+                    }
+                }
+                DisplayItem::Rect {
                     style,
                     layout_point,
+                    layout_size,
                 } => {
                     if self
                         .window
-                        .draw_string(
-                            style.color().code_u32(),
+                        .fill_rect(
+                            style.background_color().code_u32(),
                             layout_point.x() + WINDOW_PADDING,
                             layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            &text,
-                            convert_font_size(style.font_size()),
-                            style.text_decoration() == TextDecoration::Underline,
+                            layout_size.width(),
+                            layout_size.height(),
                         )
                         .is_err()
                     {
                         return Err(Error::InvalidUI("failed to draw a string".to_string()));
                     }
                 }
-                DisplayItem::Rect {
-                    style,
+                // 画像のデコードには対応していないため、画像の領域を灰色で塗りつぶして表示する。
+                DisplayItem::Image {
                     layout_point,
                     layout_size,
+                    ..
                 } => {
                     if self
                         .window
                         .fill_rect(
-                            style.background_color().code_u32(),
+                            LIGHTGREY,
                             layout_point.x() + WINDOW_PADDING,
                             layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
                             layout_size.width(),
@@ -414,7 +609,7 @@ impl WasabiUI {
                         )
                         .is_err()
                     {
-                        return Err(Error::InvalidUI("failed to draw a string".to_string()));
+                        return Err(Error::InvalidUI("failed to draw an image".to_string()));
                     }
                 }
                 _ => {}
@@ -425,12 +620,25 @@ impl WasabiUI {
     }
 }
 
+/// 文字を描画する大きさが、Medium (16px) の文字の何倍かを返す。
+/// px や em で指定された大きさは、1倍、2倍、3倍のうち最も近い大きさで描画する。
+fn font_scale(size: FontSize) -> i64 {
+    let ratio = size.px() / FontSize::Medium.px();
+    if ratio < 1.5 {
+        1
+    } else if ratio < 2.5 {
+        2
+    } else {
+        3
+    }
+}
+
 /// OS のライブラリで使用している文字の大きさを表す列挙型 (StringSize) と、ブラウザで使用している文字の大きさを表す列挙型 (FontSize) は違う型である。
 /// 文字を表す型を変化する。
 fn convert_font_size(size: FontSize) -> StringSize {
-    match size {
-        FontSize::Medium => StringSize::Medium,
-        FontSize::XLarge => StringSize::Large,
-        FontSize::XXLarge => StringSize::XLarge,
+    match font_scale(size) {
+        1 => StringSize::Medium,
+        2 => StringSize::Large,
+        _ => StringSize::XLarge,
     }
 }
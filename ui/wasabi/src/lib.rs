@@ -4,3 +4,4 @@ extern crate alloc;
 
 pub mod app;
 mod cursor;
+mod time;
@@ -0,0 +1,15 @@
+use noli::prelude::SystemApi;
+use noli::sys::wasabi::Api;
+use saba_core::renderer::js::time::TimeSource;
+
+/// NoliTimeSource 構造体
+/// noli ライブラリを通して OS のクロックから現在時刻を取得する。
+/// wasabiOS のクロックは OS が起動してからの経過時間を返すため、Date.now も起動からのミリ秒となる。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoliTimeSource;
+
+impl TimeSource for NoliTimeSource {
+    fn now(&self) -> u64 {
+        Api::get_time_ns() / 1_000_000
+    }
+}